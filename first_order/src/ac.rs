@@ -0,0 +1,371 @@
+//! # Equality modulo associativity and commutativity
+//!
+//! This module decides whether two formulae are equal when `∧` and `∨` are treated as associative
+//! and commutative, for [`Formula::equal_modulo_ac`]. Each maximal chain of the same one of these
+//! connectives is flattened into the multiset of its operands, so `∧ ∧ P B Q`, `∧ B ∧ Q P` and
+//! `∧ Q ∧ P B` are all equal, while `∧ P P` and `P` are not. Every other connective keeps the
+//! order of its operands.
+//!
+//! Rather than comparing the flattened operands pairwise, every subformula of both formulae is
+//! given an id, with equal ids for subformulae equal modulo AC, by interning the shape of each
+//! node with the ids of its operands, sorted for `∧` and `∨`. The formulae are then equal exactly
+//! when their ids are.
+//!
+//! The same flattening gives the operands of a chain in order, for [`Formula::conjuncts`] and
+//! [`Formula::disjuncts`], and rebuilds a chain from its sorted operands for
+//! [`Formula::canonicalize`].
+use std::collections::HashMap;
+
+use crate::normal_form::map_operands;
+use crate::{Formula, FormulaKind, kind};
+
+/// The shape of a node with the ids of its operands.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Shape<'a> {
+    /// A term, `⊤` or `⊥`.
+    Atom(&'a Formula),
+    /// A negation or quantifier, with the variable of a quantifier.
+    Unary(FormulaKind, Option<&'a str>, usize),
+    /// A binary connective other than `∧` and `∨`, with the symbol of a `Custom` one.
+    Binary(FormulaKind, Option<&'a str>, usize, usize),
+    /// A flattened chain of `∧` or `∨`, with the ids of its operands in ascending order.
+    Flat(FormulaKind, Vec<usize>),
+}
+
+/// Assigns ids to subformulae so that subformulae equal modulo AC get the same id.
+#[derive(Debug, Default)]
+struct Interner<'a> {
+    ids: HashMap<Shape<'a>, usize>, // The id of every shape seen so far.
+}
+
+impl<'a> Interner<'a> {
+    /// Returns the id of `formula`, walking it without recursion.
+    fn id(&mut self, formula: &'a Formula) -> usize {
+        let mut pending: Vec<(&'a Formula, bool)> = vec![(formula, false)];
+        let mut ids: Vec<usize> = Vec::new();
+        while let Some((current, expanded)) = pending.pop() {
+            let operands: Vec<&'a Formula> = operands(current);
+            if !expanded && !operands.is_empty() {
+                pending.push((current, true));
+                pending.extend(operands.into_iter().rev().map(|operand| (operand, false)));
+                continue;
+            }
+            let mut operand_ids: Vec<usize> = ids.split_off(ids.len() - operands.len());
+            let shape: Shape<'a> = match current {
+                Formula::Term(_)
+                | Formula::Top
+                | Formula::Bottom
+                | Formula::Equality(_, _)
+                | Formula::Less(_, _)
+                | Formula::Predicate(_, _) => Shape::Atom(current),
+                Formula::Conjunction(_, _) | Formula::Disjunction(_, _) => {
+                    operand_ids.sort_unstable();
+                    Shape::Flat(current.kind(), operand_ids)
+                }
+                Formula::Negation(_)
+                | Formula::UniversalQuantifier(_, _)
+                | Formula::ExistentialQuantifier(_, _) => {
+                    Shape::Unary(current.kind(), current.binder(), operand_ids[0])
+                }
+                Formula::Custom(symbol, _, _) => {
+                    Shape::Binary(current.kind(), Some(symbol), operand_ids[0], operand_ids[1])
+                }
+                _ => Shape::Binary(current.kind(), None, operand_ids[0], operand_ids[1]),
+            };
+            let next: usize = self.ids.len();
+            ids.push(*self.ids.entry(shape).or_insert(next));
+        }
+        ids[0]
+    }
+}
+
+/// Returns the operands of `formula`, flattening a chain of `∧` or `∨` into its operands.
+fn operands(formula: &Formula) -> Vec<&Formula> {
+    match formula.kind() {
+        kind @ (FormulaKind::Conjunction | FormulaKind::Disjunction) => spine(formula, kind),
+        _ => formula.children(),
+    }
+}
+
+/// Returns the operands of the maximal chain of `kind` connectives at the root of `formula`,
+/// from left to right. A formula of another kind is its own only operand.
+pub(crate) fn spine(formula: &Formula, kind: FormulaKind) -> Vec<&Formula> {
+    let mut operands: Vec<&Formula> = Vec::new();
+    let mut stack: Vec<&Formula> = vec![formula];
+    while let Some(current) = stack.pop() {
+        if current.kind() == kind {
+            stack.extend(current.children().into_iter().rev());
+        } else {
+            operands.push(current);
+        }
+    }
+    operands
+}
+
+/// Returns whether `lhs` and `rhs` are equal modulo the associativity and commutativity of `∧`
+/// and `∨`. See [`Formula::equal_modulo_ac`].
+pub(crate) fn equal_modulo_ac(lhs: &Formula, rhs: &Formula) -> bool {
+    let mut interner: Interner<'_> = Interner::default();
+    interner.id(lhs) == interner.id(rhs)
+}
+
+/// Rebuilds `formula` with every chain of `∧` or `∨` sorted, without duplicates and grouped to
+/// the left, walking it without recursion. See [`Formula::canonicalize`].
+pub(crate) fn canonicalize(formula: &Formula) -> Formula {
+    let mut pending: Vec<(&Formula, bool)> = vec![(formula, false)];
+    let mut canonical: Vec<Formula> = Vec::new();
+    while let Some((current, expanded)) = pending.pop() {
+        let operands: Vec<&Formula> = operands(current);
+        if !expanded && !operands.is_empty() {
+            pending.push((current, true));
+            pending.extend(operands.into_iter().rev().map(|operand| (operand, false)));
+            continue;
+        }
+        let mut done: std::vec::IntoIter<Formula> = canonical
+            .split_off(canonical.len() - operands.len())
+            .into_iter();
+        let rebuilt: Formula = match current.kind() {
+            chain @ (FormulaKind::Conjunction | FormulaKind::Disjunction) => {
+                // An operand whose duplicates were removed may have become a chain of its own.
+                let mut flat: Vec<Formula> = Vec::new();
+                for operand in done {
+                    if operand.kind() == chain {
+                        flat.extend(spine(&operand, chain).into_iter().cloned());
+                    } else {
+                        flat.push(operand);
+                    }
+                }
+                flat.sort();
+                flat.dedup();
+                let mut flat: std::vec::IntoIter<Formula> = flat.into_iter();
+                let first: Formula = flat.next().expect("a chain has operands");
+                flat.fold(first, |lhs, rhs| {
+                    kind::binary(chain, Box::new(lhs), Box::new(rhs)).expect("∧ and ∨ are binary")
+                })
+            }
+            _ => map_operands(current, |_| done.next().expect("one result per operand")),
+        };
+        canonical.push(rebuilt);
+    }
+    canonical.pop().expect("the root is rebuilt last")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_reordered_conjunctions() {
+        let test_formula: Formula = Formula::new("∧ P B");
+        assert!(test_formula.equal_modulo_ac(&Formula::new("∧ B P")));
+        assert!(Formula::new("∧ ∧ ∧ A B C D").equal_modulo_ac(&Formula::new("∧ D ∧ B ∧ C A")));
+        assert!(Formula::new("∨ < x y ∨ P Q").equal_modulo_ac(&Formula::new("∨ ∨ Q P < x y")));
+    }
+
+    #[test]
+    fn test_reassociated_conjunctions() {
+        assert!(Formula::new("∧ ∧ A B C").equal_modulo_ac(&Formula::new("∧ A ∧ B C")));
+        let fibonacci: Formula = Formula::new(corpus::FIBONACCI[4]);
+        let reassociated: Formula = Formula::new(
+            "∧ = curFib fib(index) ∧ ∧ = preFib fib(index-1) ∨ = index N < index N ∧ = N input < 0 index",
+        );
+        assert!(fibonacci.equal_modulo_ac(&reassociated));
+    }
+
+    #[test]
+    fn test_nested_under_other_connectives() {
+        assert!(
+            Formula::new("∀ x → ∧ P(x) Q(x) ¬ ∨ R S")
+                .equal_modulo_ac(&Formula::new("∀ x → ∧ Q(x) P(x) ¬ ∨ S R"))
+        );
+        assert!(Formula::new("∨ ∧ A B ∧ C D").equal_modulo_ac(&Formula::new("∨ ∧ D C ∧ B A")));
+    }
+
+    #[test]
+    fn test_different_operands() {
+        assert!(!Formula::new("∧ P B").equal_modulo_ac(&Formula::new("∧ P C")));
+        assert!(!Formula::new("∧ P P").equal_modulo_ac(&Formula::new("P")));
+        assert!(!Formula::new("∧ ∧ P P Q").equal_modulo_ac(&Formula::new("∧ ∧ P Q Q")));
+        assert!(!Formula::new("∧ P ∨ Q R").equal_modulo_ac(&Formula::new("∨ P ∧ Q R")));
+        assert!(!Formula::new("∧ A ∨ B C").equal_modulo_ac(&Formula::new("∧ ∧ A B C")));
+        assert!(!Formula::new("→ P Q").equal_modulo_ac(&Formula::new("→ Q P")));
+        assert!(!Formula::new("< x y").equal_modulo_ac(&Formula::new("< y x")));
+        assert!(!Formula::new("∀ x P").equal_modulo_ac(&Formula::new("∀ y P")));
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let mut lhs: Formula = Formula::term("P0");
+        let mut rhs: Formula = Formula::term("P99999");
+        for index in 1..100_000 {
+            lhs = lhs.and(Formula::term(format!("P{index}")));
+            rhs = Formula::term(format!("P{}", 99_999 - index)).and(rhs);
+        }
+        assert!(lhs.equal_modulo_ac(&rhs));
+        assert_eq!(spine(&lhs, FormulaKind::Conjunction).len(), 100_000);
+    }
+
+    #[test]
+    fn test_fibonacci_conjuncts() {
+        let test_formula: Formula = Formula::new(corpus::FIBONACCI[4]);
+        let conjuncts: Vec<&Formula> = test_formula.conjuncts();
+        let expected: [&str; 5] = [
+            "= curFib fib(index)",
+            "= preFib fib(index-1)",
+            "< 0 index",
+            "∨ < index N = index N",
+            "= N input",
+        ];
+        assert_eq!(conjuncts.len(), expected.len());
+        for (conjunct, input) in conjuncts.iter().zip(expected) {
+            assert_eq!(**conjunct, Formula::new(input));
+        }
+        let rebuilt: Formula = Formula::from_conjuncts(conjuncts.into_iter().cloned().collect());
+        assert_eq!(rebuilt, test_formula);
+        assert!(test_formula.contains_conjunct(&Formula::new("< 0 index")));
+        assert!(test_formula.contains_conjunct(&Formula::new("∨ < index N = index N")));
+        assert!(!test_formula.contains_conjunct(&Formula::new("< index N")));
+        assert!(!test_formula.contains_conjunct(&Formula::new("∧ = N input < 0 index")));
+    }
+
+    #[test]
+    fn test_disjuncts_and_single_operands() {
+        let test_formula: Formula = Formula::new("∨ ∨ < y r = y r ∧ P Q");
+        let disjuncts: Vec<&Formula> = test_formula.disjuncts();
+        assert_eq!(
+            disjuncts,
+            [
+                &Formula::new("< y r"),
+                &Formula::new("= y r"),
+                &Formula::new("∧ P Q")
+            ]
+        );
+        assert_eq!(test_formula.conjuncts(), [&test_formula]);
+        assert!(test_formula.contains_conjunct(&test_formula));
+        assert_eq!(
+            Formula::from_conjuncts(vec![Formula::new("P")]),
+            Formula::new("P")
+        );
+        assert_eq!(Formula::from_conjuncts(Vec::new()), Formula::Top);
+        let right_leaning: Formula = Formula::new("∧ A ∧ B C");
+        assert_eq!(
+            Formula::from_conjuncts(right_leaning.conjuncts().into_iter().cloned().collect()),
+            Formula::new("∧ ∧ A B C")
+        );
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let test_formula: Formula = Formula::new("∧ ∧ < 0 i P(i) ∨ Q ∧ R R");
+        let canonical: Formula = test_formula.canonicalize();
+        assert_eq!(canonical, Formula::new("∧ ∧ ∨ Q R < 0 i P(i)"));
+        assert_eq!(canonical.canonicalize(), canonical);
+        assert_eq!(
+            Formula::new("∀ x → ∧ B A ∧ A B").canonicalize(),
+            Formula::new("∀ x → ∧ A B ∧ A B")
+        );
+        assert_eq!(Formula::new("∨ P P").canonicalize(), Formula::new("P"));
+    }
+
+    #[test]
+    fn test_canonicalize_merges_collapsed_chains() {
+        let test_formula: Formula = Formula::new("∨ ∧ ∨ B A ∨ A B C");
+        let canonical: Formula = test_formula.canonicalize();
+        assert_eq!(canonical, Formula::new("∨ ∨ A B C"));
+        assert_eq!(canonical.canonicalize(), canonical);
+    }
+
+    #[test]
+    fn test_canonicalize_deep_chain() {
+        let mut test_formula: Formula = Formula::term("P99999");
+        for index in (0..99_999).rev() {
+            test_formula = test_formula.and(Formula::term(format!("P{index}")));
+        }
+        let canonical: Formula = test_formula.canonicalize();
+        assert_eq!(canonical.conjuncts().len(), 100_000);
+        assert!(canonical.equal_modulo_ac(&test_formula));
+    }
+
+    mod generated {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+        use rand::seq::SliceRandom;
+
+        use crate::r#gen::{GenConfig, random_formula};
+        use crate::{Formula, FormulaKind};
+
+        /// The number of formulae each property is checked against.
+        const SAMPLES: u64 = 1000;
+
+        /// Joins `operands` with `kind` in a random order and grouping.
+        fn regroup(rng: &mut StdRng, mut operands: Vec<Formula>, kind: FormulaKind) -> Formula {
+            operands.shuffle(rng);
+            while operands.len() > 1 {
+                let index: usize = rand::Rng::random_range(rng, 0..operands.len() - 1);
+                let rhs: Formula = operands.remove(index + 1);
+                let lhs: Formula = operands.remove(index);
+                let joined: Formula = crate::kind::binary(kind, Box::new(lhs), Box::new(rhs))
+                    .expect("∧ and ∨ are binary");
+                operands.insert(index, joined);
+            }
+            operands.pop().expect("at least one operand")
+        }
+
+        #[test]
+        fn test_canonicalize_is_idempotent() {
+            for seed in 0..SAMPLES {
+                let mut rng: StdRng = StdRng::seed_from_u64(seed);
+                let canonical: Formula = random_formula(&mut rng, &GenConfig::new()).canonicalize();
+                assert_eq!(canonical.canonicalize(), canonical, "seed {seed}");
+            }
+        }
+
+        #[test]
+        fn test_canonicalize_agrees_with_equal_modulo_ac() {
+            for seed in 0..SAMPLES {
+                let mut rng: StdRng = StdRng::seed_from_u64(seed);
+                let config: GenConfig = GenConfig::new();
+                let count: usize = rand::Rng::random_range(&mut rng, 2..6);
+                let operands: Vec<Formula> = (0..count)
+                    .map(|_| random_formula(&mut rng, &config))
+                    .collect();
+                for kind in [FormulaKind::Conjunction, FormulaKind::Disjunction] {
+                    let lhs: Formula = regroup(&mut rng, operands.clone(), kind);
+                    let rhs: Formula = regroup(&mut rng, operands.clone(), kind);
+                    assert!(lhs.equal_modulo_ac(&rhs), "seed {seed}");
+                    assert_eq!(lhs.canonicalize(), rhs.canonicalize(), "seed {seed}");
+                }
+                let other: Formula = random_formula(&mut rng, &config);
+                let (lhs, rhs): (Formula, Formula) =
+                    (operands[0].canonicalize(), other.canonicalize());
+                assert_eq!(lhs == rhs, lhs.equal_modulo_ac(&rhs), "seed {seed}");
+            }
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    mod properties {
+        use proptest::prelude::*;
+
+        use crate::Formula;
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(1000))]
+
+            #[test]
+            fn canonicalize_is_idempotent(formula: Formula) {
+                let canonical: Formula = formula.canonicalize();
+                prop_assert_eq!(canonical.canonicalize(), canonical);
+            }
+
+            #[test]
+            fn canonicalize_agrees_with_equal_modulo_ac(lhs: Formula, rhs: Formula) {
+                let conjunction: Formula = lhs.clone().and(rhs.clone());
+                let swapped: Formula = rhs.and(lhs);
+                prop_assert!(conjunction.equal_modulo_ac(&swapped));
+                prop_assert_eq!(conjunction.canonicalize(), swapped.canonicalize());
+            }
+        }
+    }
+}
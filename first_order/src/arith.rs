@@ -0,0 +1,291 @@
+//! # Arithmetic evaluation
+//!
+//! This module evaluates terms to integers and quantifier-free formulae to truth values on a
+//! concrete state, for [`Term::eval`] and [`Formula::eval_arith`], so that a triple can be tested
+//! on the states a program actually reaches.
+//!
+//! A variable is looked up in the environment and a numeral stands for its own value. The
+//! operators `+`, `-`, `*`, `/`, `%` and `^` are built in: `/` and `%` truncate towards zero like
+//! Rust's, a division by zero is an error, and so is a result that does not fit in a
+//! [`DomainElem`]. Every other function symbol, such as `fib` or `mod`, is looked up in the
+//! functions supplied by the caller, which return `None` where they are undefined.
+//!
+//! Unlike [`Formula::holds_in`], there is no domain to quantify over, so a quantifier is an error,
+//! and `=`, `≠`, `<`, `≤`, `≥` and `>` always compare the values of their terms as integers.
+use std::collections::HashMap;
+
+use crate::{DomainElem, EvalError, Formula, Term};
+
+/// A function symbol interpreted by [`Term::eval`], returning `None` where it is undefined.
+pub type ArithFunction = fn(&[DomainElem]) -> Option<DomainElem>;
+
+/// Evaluates `term` under `env`. See [`Term::eval`].
+pub(crate) fn eval_term(
+    term: &Term,
+    env: &HashMap<String, DomainElem>,
+    funcs: &HashMap<String, ArithFunction>,
+) -> Result<DomainElem, EvalError> {
+    match term {
+        Term::Var(name) => env
+            .get(name)
+            .copied()
+            .ok_or_else(|| EvalError::UnboundVariable { name: name.clone() }),
+        Term::IntLit(value) => Ok(*value),
+        Term::Const(name) => name
+            .parse()
+            .map_err(|_| EvalError::InvalidConstant { name: name.clone() }),
+        Term::App(name, args) if name.is_empty() => eval_term(&args[0], env, funcs),
+        Term::App(name, args) => {
+            let values: Vec<DomainElem> = args
+                .iter()
+                .map(|arg| eval_term(arg, env, funcs))
+                .collect::<Result<_, _>>()?;
+            let result: Option<DomainElem> = match (name.as_str(), values.as_slice()) {
+                ("-", [value]) => value.checked_neg(),
+                ("+", [lhs, rhs]) => lhs.checked_add(*rhs),
+                ("-", [lhs, rhs]) => lhs.checked_sub(*rhs),
+                ("*", [lhs, rhs]) => lhs.checked_mul(*rhs),
+                ("/" | "%", [_, 0]) => {
+                    return Err(EvalError::DivisionByZero {
+                        term: term.to_string(),
+                    });
+                }
+                ("/", [lhs, rhs]) => lhs.checked_div(*rhs),
+                ("%", [lhs, rhs]) => lhs.checked_rem(*rhs),
+                ("^", [base, exponent]) => match u32::try_from(*exponent) {
+                    Ok(exponent) => base.checked_pow(exponent),
+                    Err(_) if *exponent < 0 => {
+                        return Err(EvalError::UndefinedApplication {
+                            name: name.clone(),
+                            arguments: values,
+                        });
+                    }
+                    Err(_) => None,
+                },
+                _ => {
+                    let function: &ArithFunction =
+                        funcs.get(name).ok_or_else(|| EvalError::UnknownFunction {
+                            name: name.clone(),
+                            arity: values.len(),
+                        })?;
+                    return function(&values).ok_or_else(|| EvalError::UndefinedApplication {
+                        name: name.clone(),
+                        arguments: values,
+                    });
+                }
+            };
+            result.ok_or_else(|| EvalError::Overflow {
+                term: term.to_string(),
+            })
+        }
+    }
+}
+
+/// Evaluates the quantifier-free `formula` under `env`. See [`Formula::eval_arith`].
+pub(crate) fn eval_formula(
+    formula: &Formula,
+    env: &HashMap<String, DomainElem>,
+    funcs: &HashMap<String, ArithFunction>,
+) -> Result<bool, EvalError> {
+    let holds = |formula: &Formula| eval_formula(formula, env, funcs);
+    let compare_terms = |symbol: &str, lhs: &Term, rhs: &Term| {
+        let lhs: DomainElem = eval_term(lhs, env, funcs)?;
+        let rhs: DomainElem = eval_term(rhs, env, funcs)?;
+        Ok::<bool, EvalError>(match symbol {
+            "=" => lhs == rhs,
+            "≠" => lhs != rhs,
+            "<" => lhs < rhs,
+            "≤" => lhs <= rhs,
+            "≥" => lhs >= rhs,
+            _ => lhs > rhs,
+        })
+    };
+    let compare = |symbol: &str, lhs: &Formula, rhs: &Formula| match (lhs, rhs) {
+        (Formula::Term(lhs), Formula::Term(rhs)) => compare_terms(symbol, lhs, rhs),
+        _ => Err(EvalError::UnsupportedConnective {
+            symbol: symbol.to_string(),
+        }),
+    };
+    Ok(match formula {
+        Formula::Term(term) => {
+            // A proposition has no meaning on a state of integer variables.
+            let (name, arity): (String, usize) = match term {
+                Term::App(name, args) => (name.clone(), args.len()),
+                _ => (term.to_string(), 0),
+            };
+            return Err(EvalError::UnknownRelation { name, arity });
+        }
+        Formula::Predicate(name, args) => {
+            return Err(EvalError::UnknownRelation {
+                name: name.clone(),
+                arity: args.len(),
+            });
+        }
+        Formula::Equality(lhs, rhs) => compare_terms("=", lhs, rhs)?,
+        Formula::Less(lhs, rhs) => compare_terms("<", lhs, rhs)?,
+        Formula::Top => true,
+        Formula::Bottom => false,
+        Formula::Negation(inner) => !holds(inner)?,
+        Formula::Conjunction(lhs, rhs) => holds(lhs)? && holds(rhs)?,
+        Formula::Disjunction(lhs, rhs) => holds(lhs)? || holds(rhs)?,
+        Formula::Implication(lhs, rhs) => !holds(lhs)? || holds(rhs)?,
+        Formula::Equivalence(lhs, rhs) => match (&**lhs, &**rhs) {
+            (Formula::Term(_), Formula::Term(_)) => compare("=", lhs, rhs)?,
+            _ => holds(lhs)? == holds(rhs)?,
+        },
+        Formula::Iff(lhs, rhs) => holds(lhs)? == holds(rhs)?,
+        Formula::NotEqual(lhs, rhs) => compare("≠", lhs, rhs)?,
+        Formula::LessThan(lhs, rhs) => compare("<", lhs, rhs)?,
+        Formula::LessEqual(lhs, rhs) => compare("≤", lhs, rhs)?,
+        Formula::GreaterEqual(lhs, rhs) => compare("≥", lhs, rhs)?,
+        Formula::GreaterThan(lhs, rhs) => compare(">", lhs, rhs)?,
+        Formula::UniversalQuantifier(variable, _) | Formula::ExistentialQuantifier(variable, _) => {
+            return Err(EvalError::NotQuantifierFree {
+                variable: variable.clone(),
+            });
+        }
+        Formula::Custom(symbol, _, _) => {
+            return Err(EvalError::UnsupportedConnective {
+                symbol: symbol.clone(),
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    fn gcd(args: &[DomainElem]) -> Option<DomainElem> {
+        let (mut a, mut b): (DomainElem, DomainElem) = (args[0].abs(), args[1].abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        Some(a)
+    }
+
+    fn modulo(args: &[DomainElem]) -> Option<DomainElem> {
+        args[0].checked_rem_euclid(args[1])
+    }
+
+    fn funcs() -> HashMap<String, ArithFunction> {
+        HashMap::from([
+            ("gcd".to_string(), gcd as ArithFunction),
+            ("mod".to_string(), modulo as ArithFunction),
+        ])
+    }
+
+    fn env(values: &[(&str, DomainElem)]) -> HashMap<String, DomainElem> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_gcd_invariant() {
+        let invariant: Formula = Formula::new(corpus::OTHER_EXAMPLES[6]);
+        assert_eq!(
+            invariant.eval_arith(&env(&[("a", 18), ("b", 12)]), &funcs()),
+            Ok(true)
+        );
+        // gcd(12,18) is 6, but gcd(12,mod(12,18)) is 12.
+        assert_eq!(
+            invariant.eval_arith(&env(&[("a", 12), ("b", 18)]), &funcs()),
+            Ok(false)
+        );
+        assert_eq!(
+            invariant.eval_arith(&env(&[("a", 18), ("b", 0)]), &funcs()),
+            Err(EvalError::UndefinedApplication {
+                name: "mod".to_string(),
+                arguments: vec![18, 0],
+            })
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        let state: HashMap<String, DomainElem> = env(&[("r", 7), ("y", 3), ("q", 2), ("p", 2)]);
+        let value = |input: &str| Term::new(input).eval(&state, &HashMap::new());
+        assert_eq!(value("(r-y)+y*(1+q)"), Ok(13));
+        assert_eq!(value("r*10^(p)"), Ok(700));
+        assert_eq!(value("-r/y"), Ok(-2));
+        assert_eq!(value("-r%y"), Ok(-1));
+        assert_eq!(
+            value("mod(r,y)"),
+            Err(EvalError::UnknownFunction {
+                name: "mod".to_string(),
+                arity: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let state: HashMap<String, DomainElem> = env(&[("x", 1), ("zero", 0)]);
+        let value = |input: &str| Term::new(input).eval(&state, &funcs());
+        assert_eq!(
+            value("x+index"),
+            Err(EvalError::UnboundVariable {
+                name: "index".to_string()
+            })
+        );
+        assert_eq!(
+            value("x/(x-1)"),
+            Err(EvalError::DivisionByZero {
+                term: "x/(x-1)".to_string()
+            })
+        );
+        assert_eq!(
+            value("fib(x)"),
+            Err(EvalError::UnknownFunction {
+                name: "fib".to_string(),
+                arity: 1
+            })
+        );
+        assert_eq!(
+            value("2^64"),
+            Err(EvalError::Overflow {
+                term: "2^64".to_string()
+            })
+        );
+        assert_eq!(
+            value("99999999999999999999"),
+            Err(EvalError::InvalidConstant {
+                name: "99999999999999999999".to_string()
+            })
+        );
+        assert_eq!(
+            value("2^-1").unwrap_err().to_string(),
+            "^ is undefined for the arguments [2, -1]"
+        );
+    }
+
+    #[test]
+    fn test_formula_errors() {
+        let state: HashMap<String, DomainElem> = env(&[("x", 1)]);
+        let holds = |input: &str| Formula::new(input).eval_arith(&state, &funcs());
+        assert_eq!(holds("∧ ≤ 0 x ∨ < x 1 = x 1"), Ok(true));
+        assert_eq!(holds("= < 0 x ⊤"), Ok(true));
+        assert_eq!(
+            holds("∀ y < y x"),
+            Err(EvalError::NotQuantifierFree {
+                variable: "y".to_string()
+            })
+        );
+        assert_eq!(
+            holds("∧ P(x) ⊤"),
+            Err(EvalError::UnknownRelation {
+                name: "P".to_string(),
+                arity: 1
+            })
+        );
+        assert_eq!(
+            holds("< ⊤ x"),
+            Err(EvalError::UnsupportedConnective {
+                symbol: "<".to_string()
+            })
+        );
+    }
+}
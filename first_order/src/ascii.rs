@@ -0,0 +1,151 @@
+//! # ASCII
+//!
+//! This module renders a formula in plain ASCII for [`Formula::to_ascii`], for log files and
+//! terminals that cannot display `∧` or `∀`. The mapping is fixed:
+//!
+//! | Formula       | ASCII                                       |
+//! |---------------|---------------------------------------------|
+//! | `⊤`, `⊥`      | `true`, `false`                             |
+//! | `¬`           | `!`                                         |
+//! | `∧`, `∨`      | `&`, `\|`                                   |
+//! | `→`           | `->`                                        |
+//! | `=`, `↔`      | `=`, `<->`                                  |
+//! | `≠`, `≤`, `≥` | `!=`, `<=`, `>=`                            |
+//! | `<`, `>`      | `<`, `>`                                    |
+//! | `∀ x`, `∃ x`  | `forall x.`, `exists x.`                    |
+//!
+//! The symbols are the ASCII aliases [`Formula::new`] accepts in prefix notation.
+//!
+//! The body of a quantifier is always parenthesized, and so is every operand of a connective
+//! unless it is a term, a truth constant, a negation, a quantifier or a relation between two
+//! terms. Terms are written with their `Display`, and a `Custom` connective keeps its registered
+//! symbol, which is only ASCII if the symbol is.
+use crate::{Atom, Formula};
+
+/// Renders `formula` in ASCII. See [`Formula::to_ascii`].
+pub(crate) fn to_ascii(formula: &Formula) -> String {
+    match formula {
+        Formula::Term(term) => term.to_string(),
+        Formula::Top => "true".to_string(),
+        Formula::Bottom => "false".to_string(),
+        Formula::Negation(inner) => format!("!{}", operand(inner)),
+        Formula::Conjunction(lhs, rhs) => binary(lhs, "&", rhs),
+        Formula::Disjunction(lhs, rhs) => binary(lhs, "|", rhs),
+        Formula::Implication(lhs, rhs) => binary(lhs, "->", rhs),
+        Formula::Equivalence(lhs, rhs) => binary(lhs, "=", rhs),
+        Formula::Iff(lhs, rhs) => binary(lhs, "<->", rhs),
+        Formula::NotEqual(lhs, rhs) => binary(lhs, "!=", rhs),
+        Formula::LessThan(lhs, rhs) => binary(lhs, "<", rhs),
+        Formula::LessEqual(lhs, rhs) => binary(lhs, "<=", rhs),
+        Formula::GreaterEqual(lhs, rhs) => binary(lhs, ">=", rhs),
+        Formula::GreaterThan(lhs, rhs) => binary(lhs, ">", rhs),
+        Formula::UniversalQuantifier(variable, inner) => {
+            format!("forall {variable}. ({})", to_ascii(inner))
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            format!("exists {variable}. ({})", to_ascii(inner))
+        }
+        Formula::Custom(symbol, lhs, rhs) => binary(lhs, symbol, rhs),
+        Formula::Equality(lhs, rhs) => format!("{lhs} = {rhs}"),
+        Formula::Less(lhs, rhs) => format!("{lhs} < {rhs}"),
+        Formula::Predicate(_, _) => formula.to_string(),
+    }
+}
+
+/// Returns whether `formula` is a relation between two terms, e.g. `x = r+y`.
+fn is_relation(formula: &Formula) -> bool {
+    formula
+        .as_atom()
+        .is_some_and(|atom| !matches!(atom, Atom::Predicate(_, _)))
+}
+
+/// Renders `formula` as the operand of a connective, parenthesized unless it is atomic, a
+/// negation or a quantifier.
+fn operand(formula: &Formula) -> String {
+    match formula {
+        Formula::Term(_)
+        | Formula::Predicate(_, _)
+        | Formula::Top
+        | Formula::Bottom
+        | Formula::Negation(_)
+        | Formula::UniversalQuantifier(_, _)
+        | Formula::ExistentialQuantifier(_, _) => to_ascii(formula),
+        _ if is_relation(formula) => to_ascii(formula),
+        _ => format!("({})", to_ascii(formula)),
+    }
+}
+
+fn binary(lhs: &Formula, symbol: &str, rhs: &Formula) -> String {
+    format!("{} {symbol} {}", operand(lhs), operand(rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParserConfig, corpus};
+
+    #[test]
+    fn test_every_connective() {
+        let cases: [(&str, &str); 18] = [
+            ("P(x)", "P(x)"),
+            ("⊤", "true"),
+            ("⊥", "false"),
+            ("¬ P", "!P"),
+            ("∧ P Q", "P & Q"),
+            ("∨ P Q", "P | Q"),
+            ("→ P Q", "P -> Q"),
+            ("= x 5", "x = 5"),
+            ("= P ¬ Q", "P = !Q"),
+            ("↔ P ¬ Q", "P <-> !Q"),
+            ("≠ x 5", "x != 5"),
+            ("< x 5", "x < 5"),
+            ("≤ x 5", "x <= 5"),
+            ("≥ x 5", "x >= 5"),
+            ("> x 5", "x > 5"),
+            ("∀ x P(x)", "forall x. (P(x))"),
+            ("∃ y ¬ R(y)", "exists y. (!R(y))"),
+            ("¬ ∧ P Q", "!(P & Q)"),
+        ];
+        for (input, ascii) in cases {
+            assert_eq!(Formula::new(input).to_ascii(), ascii, "{input}");
+        }
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        assert_eq!(
+            config.parse("→ ⊆ A B P").unwrap().to_ascii(),
+            "(A ⊆ B) -> P"
+        );
+    }
+
+    #[test]
+    fn test_nested_operands() {
+        let test_formula: Formula = Formula::new("∀ x → P(x) ∧ Q(x) ↔ ¬ R(x) < y 2*x");
+        assert_eq!(
+            test_formula.to_ascii(),
+            "forall x. (P(x) -> (Q(x) & (!R(x) <-> y < 2*x)))"
+        );
+        assert_eq!(
+            Formula::new("∨ ∧ P Q ¬ ∀ x P(x)").to_ascii(),
+            "(P & Q) | !forall x. (P(x))"
+        );
+    }
+
+    #[test]
+    fn test_paper_example_lemma() {
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[4]).to_ascii(),
+            "x = r+y*0 -> x = r+y*0"
+        );
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[5]).to_ascii(),
+            "(x = r+y*q & (y < r | y = r)) -> x = (r-y)+y*(1+q)"
+        );
+    }
+
+    #[test]
+    fn test_corpus_is_ascii() {
+        for input in corpus::all() {
+            assert!(Formula::new(input).to_ascii().is_ascii(), "{input}");
+        }
+    }
+}
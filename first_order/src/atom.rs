@@ -0,0 +1,353 @@
+//! # Atomic formulae
+//!
+//! This module builds the typed atomic formulae [`Formula::Equality`], [`Formula::Less`] and
+//! [`Formula::Predicate`] for the parsers, and provides [`Atom`], a typed view of every atomic
+//! formula, for [`Formula::as_atom`]. An `Atom` borrows the terms of a typed variant, and equally
+//! those of a comparison built by hand from two `Formula::Term`s, which saves every caller from
+//! matching both forms:
+//!
+//! | Formula              | Atom                          |
+//! |----------------------|-------------------------------|
+//! | `= a b`, `≠ a b`     | `Equality`, `NotEqual`        |
+//! | `< a b`, `≤ a b`     | `LessThan`, `LessEqual`       |
+//! | `≥ a b`, `> a b`     | `GreaterEqual`, `GreaterThan` |
+//! | `P`, `P(x,y)`        | `Predicate`                   |
+//!
+//! A comparison is only an atom if both of its operands are terms, so `= P ∧ Q R` is not one, and
+//! a term is only a predicate if it is a name or an application of a function symbol, so a
+//! numeral or `x+1` on its own is not one. Building the typed variants changes no notation: each
+//! is written exactly as the `Formula::Term`s it replaces.
+use std::fmt;
+use std::mem;
+
+use crate::{Formula, Term, term};
+
+/// A typed view of an atomic formula, borrowing its terms. See [`Formula::as_atom`].
+///
+/// # Example
+/// ```
+/// use first_order::{Atom, Formula, Term};
+///
+/// let test_formula: Formula = Formula::new("= x+1 43");
+/// let Some(Atom::Equality(lhs, rhs)) = test_formula.as_atom() else {
+///     panic!("expected an equality");
+/// };
+/// assert_eq!((lhs, rhs), (&Term::new("x+1"), &Term::new("43")));
+/// assert_eq!(Formula::from(test_formula.as_atom().unwrap()), test_formula);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Atom<'a> {
+    /// The equality `= a b` of two terms.
+    Equality(&'a Term, &'a Term),
+    /// The inequality `≠ a b` of two terms.
+    NotEqual(&'a Term, &'a Term),
+    /// The comparison `< a b` of two terms.
+    LessThan(&'a Term, &'a Term),
+    /// The comparison `≤ a b` of two terms.
+    LessEqual(&'a Term, &'a Term),
+    /// The comparison `≥ a b` of two terms.
+    GreaterEqual(&'a Term, &'a Term),
+    /// The comparison `> a b` of two terms.
+    GreaterThan(&'a Term, &'a Term),
+    /// The predicate `P(a,b,...)` applied to terms, or the proposition `P` without arguments.
+    Predicate(&'a str, &'a [Term]),
+}
+
+impl<'a> Atom<'a> {
+    /// Returns the symbol of the relation, e.g. `=` or `P`.
+    pub fn symbol(&self) -> &'a str {
+        match self {
+            Atom::Equality(_, _) => "=",
+            Atom::NotEqual(_, _) => "≠",
+            Atom::LessThan(_, _) => "<",
+            Atom::LessEqual(_, _) => "≤",
+            Atom::GreaterEqual(_, _) => "≥",
+            Atom::GreaterThan(_, _) => ">",
+            Atom::Predicate(name, _) => name,
+        }
+    }
+
+    /// Returns the terms the relation is applied to, from left to right.
+    pub fn terms(&self) -> Vec<&'a Term> {
+        match self {
+            Atom::Equality(lhs, rhs)
+            | Atom::NotEqual(lhs, rhs)
+            | Atom::LessThan(lhs, rhs)
+            | Atom::LessEqual(lhs, rhs)
+            | Atom::GreaterEqual(lhs, rhs)
+            | Atom::GreaterThan(lhs, rhs) => vec![*lhs, *rhs],
+            Atom::Predicate(_, args) => args.iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for Atom<'_> {
+    /// Formats the atom as the formula it views is displayed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Formula::from(*self))
+    }
+}
+
+impl From<Atom<'_>> for Formula {
+    /// Builds the formula the atom views, using the typed variant where the parser would.
+    fn from(atom: Atom<'_>) -> Formula {
+        let comparison = |lhs: &Term, rhs: &Term| {
+            (
+                Box::new(Formula::Term(lhs.clone())),
+                Box::new(Formula::Term(rhs.clone())),
+            )
+        };
+        match atom {
+            Atom::Equality(lhs, rhs) => Formula::Equality(lhs.clone(), rhs.clone()),
+            Atom::NotEqual(lhs, rhs) => {
+                let (lhs, rhs) = comparison(lhs, rhs);
+                Formula::NotEqual(lhs, rhs)
+            }
+            Atom::LessThan(lhs, rhs) => Formula::Less(lhs.clone(), rhs.clone()),
+            Atom::LessEqual(lhs, rhs) => {
+                let (lhs, rhs) = comparison(lhs, rhs);
+                Formula::LessEqual(lhs, rhs)
+            }
+            Atom::GreaterEqual(lhs, rhs) => {
+                let (lhs, rhs) = comparison(lhs, rhs);
+                Formula::GreaterEqual(lhs, rhs)
+            }
+            Atom::GreaterThan(lhs, rhs) => {
+                let (lhs, rhs) = comparison(lhs, rhs);
+                Formula::GreaterThan(lhs, rhs)
+            }
+            Atom::Predicate(name, args) => proposition(Formula::Term(predicate_term(name, args))),
+        }
+    }
+}
+
+/// Returns the atom `formula` is, if it is one. See [`Formula::as_atom`].
+pub(crate) fn as_atom(formula: &Formula) -> Option<Atom<'_>> {
+    let (lhs, rhs) = match formula {
+        Formula::Equality(lhs, rhs) => return Some(Atom::Equality(lhs, rhs)),
+        Formula::Less(lhs, rhs) => return Some(Atom::LessThan(lhs, rhs)),
+        Formula::Predicate(name, args) => return Some(Atom::Predicate(name, args)),
+        Formula::Term(Term::Var(name)) => return Some(Atom::Predicate(name, &[])),
+        Formula::Term(Term::App(name, args)) if is_predicate(name, args) => {
+            return Some(Atom::Predicate(name, args));
+        }
+        Formula::Equivalence(lhs, rhs)
+        | Formula::NotEqual(lhs, rhs)
+        | Formula::LessThan(lhs, rhs)
+        | Formula::LessEqual(lhs, rhs)
+        | Formula::GreaterEqual(lhs, rhs)
+        | Formula::GreaterThan(lhs, rhs) => match (lhs.as_ref(), rhs.as_ref()) {
+            (Formula::Term(lhs), Formula::Term(rhs)) => (lhs, rhs),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(match formula {
+        Formula::Equivalence(_, _) => Atom::Equality(lhs, rhs),
+        Formula::NotEqual(_, _) => Atom::NotEqual(lhs, rhs),
+        Formula::LessThan(_, _) => Atom::LessThan(lhs, rhs),
+        Formula::LessEqual(_, _) => Atom::LessEqual(lhs, rhs),
+        Formula::GreaterEqual(_, _) => Atom::GreaterEqual(lhs, rhs),
+        _ => Atom::GreaterThan(lhs, rhs),
+    })
+}
+
+/// Returns whether the application of `name` to `args` is a predicate rather than an arithmetic
+/// operator, parentheses, or `P()`, which is written differently from the proposition `P`.
+fn is_predicate(name: &str, args: &[Term]) -> bool {
+    !term::is_built_in(name) && !args.is_empty()
+}
+
+/// Returns the predicate `name(args)` as the term it was parsed from: `name(args)`, or the
+/// variable `name` if it has no arguments.
+pub(crate) fn predicate_term(name: &str, args: &[Term]) -> Term {
+    if args.is_empty() {
+        Term::Var(name.to_string())
+    } else {
+        Term::App(name.to_string(), args.to_vec())
+    }
+}
+
+/// Reads `formula`, found where a formula is expected, as a proposition: an application of a
+/// symbol becomes a [`Formula::Predicate`], and any other formula, including a bare name, is
+/// returned as it is.
+pub(crate) fn proposition(mut formula: Formula) -> Formula {
+    match &mut formula {
+        Formula::Term(Term::App(name, args)) if is_predicate(name, args) => {
+            Formula::Predicate(mem::take(name), mem::take(args))
+        }
+        _ => formula,
+    }
+}
+
+/// Builds `= lhs rhs`: a [`Formula::Equality`] if both operands are terms, and otherwise the
+/// biconditional [`Formula::Equivalence`], whose operands are then read as propositions.
+pub(crate) fn equality(mut lhs: Box<Formula>, mut rhs: Box<Formula>) -> Formula {
+    match take_terms(&mut lhs, &mut rhs) {
+        Some((lhs, rhs)) => Formula::Equality(lhs, rhs),
+        None => Formula::Equivalence(Box::new(proposition(*lhs)), Box::new(proposition(*rhs))),
+    }
+}
+
+/// Builds `< lhs rhs`: a [`Formula::Less`] if both operands are terms, and a
+/// [`Formula::LessThan`] otherwise.
+pub(crate) fn less_than(mut lhs: Box<Formula>, mut rhs: Box<Formula>) -> Formula {
+    match take_terms(&mut lhs, &mut rhs) {
+        Some((lhs, rhs)) => Formula::Less(lhs, rhs),
+        None => Formula::LessThan(lhs, rhs),
+    }
+}
+
+/// Returns the term `formula` stands for if it is one, reading a [`Formula::Predicate`] as the
+/// application it was parsed from, so that `f(y)` is accepted wherever a term is expected.
+pub(crate) fn as_term(formula: &Formula) -> Option<Term> {
+    match formula {
+        Formula::Term(term) => Some(term.clone()),
+        Formula::Predicate(name, args) => Some(predicate_term(name, args)),
+        _ => None,
+    }
+}
+
+/// Pairs up the terms of two atoms of the same relation, or returns `None` if either is not an
+/// atom or they differ in their relation symbol or arity. An atom built from `Formula::Term`s is
+/// paired with its typed variant.
+pub(crate) fn zip_terms<'a>(
+    lhs: &'a Formula,
+    rhs: &'a Formula,
+) -> Option<Vec<(&'a Term, &'a Term)>> {
+    let (lhs, rhs): (Atom<'a>, Atom<'a>) = (as_atom(lhs)?, as_atom(rhs)?);
+    let is_predicate = |atom: &Atom<'_>| matches!(atom, Atom::Predicate(_, _));
+    let (lhs_terms, rhs_terms): (Vec<&Term>, Vec<&Term>) = (lhs.terms(), rhs.terms());
+    (lhs.symbol() == rhs.symbol()
+        && is_predicate(&lhs) == is_predicate(&rhs)
+        && lhs_terms.len() == rhs_terms.len())
+    .then(|| lhs_terms.into_iter().zip(rhs_terms).collect())
+}
+
+/// Takes the terms out of `lhs` and `rhs` if both are a `Formula::Term`, leaving `0` in their
+/// place, and leaves both untouched otherwise.
+fn take_terms(lhs: &mut Formula, rhs: &mut Formula) -> Option<(Term, Term)> {
+    match (lhs, rhs) {
+        (Formula::Term(lhs), Formula::Term(rhs)) => Some((
+            mem::replace(lhs, Term::IntLit(0)),
+            mem::replace(rhs, Term::IntLit(0)),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_comparisons() {
+        let inputs: [(&str, &str); 6] = [
+            ("= x+1 43", "="),
+            ("≠ x+1 43", "≠"),
+            ("< index N", "<"),
+            ("≤ 0 r", "≤"),
+            ("≥ r y", "≥"),
+            ("> fib(n) 0", ">"),
+        ];
+        for (input, symbol) in inputs {
+            let test_formula: Formula = Formula::new(input);
+            let atom: Atom<'_> = test_formula.as_atom().unwrap();
+            assert_eq!(atom.symbol(), symbol);
+            assert_eq!(atom.terms().len(), 2);
+            assert_eq!(atom.to_string(), test_formula.to_string());
+        }
+        assert_eq!(
+            Formula::new("= x+1 43").as_atom(),
+            Some(Atom::Equality(&Term::new("x+1"), &Term::new("43")))
+        );
+    }
+
+    #[test]
+    fn test_predicates() {
+        let args: Vec<Term> = vec![Term::new("x"), Term::new("y+1")];
+        assert_eq!(
+            Formula::new("R(x,y+1)").as_atom(),
+            Some(Atom::Predicate("R", &args))
+        );
+        assert_eq!(Formula::new("P").as_atom(), Some(Atom::Predicate("P", &[])));
+        for input in ["43", "x+1", "(P)", "∧ P Q", "= P ∧ Q R", "∀ x P(x)", "⊤"] {
+            assert_eq!(Formula::new(input).as_atom(), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parser_builds_typed_atoms() {
+        assert_eq!(
+            Formula::new("= x+1 43"),
+            Formula::Equality(Term::new("x+1"), Term::new("43"))
+        );
+        assert_eq!(
+            Formula::new("< index N"),
+            Formula::Less(Term::new("index"), Term::new("N"))
+        );
+        assert_eq!(
+            Formula::new("P(x,1)"),
+            Formula::Predicate("P".to_string(), vec![Term::new("x"), Term::new("1")])
+        );
+        assert_eq!(Formula::new("P"), Formula::Term(Term::new("P")));
+        // With a formula on either side, `=` and `<` keep their old variants.
+        assert_eq!(
+            Formula::new("= P(x) ¬ Q"),
+            Formula::Equivalence(
+                Box::new(Formula::Predicate("P".to_string(), vec![Term::new("x")])),
+                Box::new(Formula::Negation(Box::new(Formula::term("Q")))),
+            )
+        );
+        assert_eq!(
+            Formula::new("< ⊤ x"),
+            Formula::LessThan(Box::new(Formula::Top), Box::new(Formula::term("x")))
+        );
+    }
+
+    #[test]
+    fn test_old_shapes_are_written_alike() {
+        let inputs: [(Formula, &str); 3] = [
+            (
+                Formula::Equivalence(
+                    Box::new(Formula::term("x")),
+                    Box::new(Formula::term("r+y*q")),
+                ),
+                "= x r+y*q",
+            ),
+            (
+                Formula::LessThan(Box::new(Formula::term("y")), Box::new(Formula::term("r"))),
+                "< y r",
+            ),
+            (Formula::term("R(x,y+1)"), "R(x,y+1)"),
+        ];
+        for (old, input) in inputs {
+            let parsed: Formula = Formula::new(input);
+            assert_ne!(old, parsed, "{input}");
+            assert_eq!(old.to_prefix_notation(), parsed.to_prefix_notation());
+            assert_eq!(old.to_infix_notation(), parsed.to_infix_notation());
+            assert_eq!(old.to_infix_pretty(), parsed.to_infix_pretty());
+            assert_eq!(old.to_sexpr(), parsed.to_sexpr());
+            assert_eq!(old.to_latex(), parsed.to_latex());
+            assert_eq!(old.as_atom(), parsed.as_atom());
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            for subformula in test_formula.subformulae() {
+                if let Some(atom) = subformula.as_atom() {
+                    let rebuilt: Formula = Formula::from(atom);
+                    assert_eq!(&rebuilt, subformula);
+                    assert_eq!(
+                        rebuilt.to_prefix_notation(),
+                        subformula.to_prefix_notation()
+                    );
+                }
+            }
+        }
+    }
+}
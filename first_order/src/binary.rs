@@ -0,0 +1,652 @@
+//! # Binary encoding
+//!
+//! This module implements a compact binary encoding of `Formula`, used by [`Formula::to_bytes`]
+//! and [`Formula::from_bytes`].
+//!
+//! An encoded formula starts with the magic byte [`MAGIC`], which also identifies the version of
+//! the format, followed by the formula in prefix order. Every node is a tag byte followed by its
+//! fields. Strings are a length followed by that many bytes of UTF-8, and every length or count
+//! is an unsigned LEB128 integer (7 bits per byte, least significant group first).
+//!
+//! | Tag    | Node                    | Fields                               |
+//! |--------|-------------------------|--------------------------------------|
+//! | `0x00` | `Formula::Term`         | a term                               |
+//! | `0x01` | `Formula::Top`          |                                      |
+//! | `0x02` | `Formula::Bottom`       |                                      |
+//! | `0x03` | `Formula::Negation`     | a formula                            |
+//! | `0x04` | `Formula::Conjunction`  | two formulae                         |
+//! | `0x05` | `Formula::Disjunction`  | two formulae                         |
+//! | `0x06` | `Formula::Implication`  | two formulae                         |
+//! | `0x07` | `Formula::Equivalence`  | two formulae                         |
+//! | `0x08` | `Formula::NotEqual`     | two formulae                         |
+//! | `0x09` | `Formula::LessThan`     | two formulae                         |
+//! | `0x0A` | `Formula::LessEqual`    | two formulae                         |
+//! | `0x0B` | `Formula::GreaterEqual` | two formulae                         |
+//! | `0x0C` | `Formula::GreaterThan`  | two formulae                         |
+//! | `0x0D` | `Formula::UniversalQuantifier`   | a string and a formula      |
+//! | `0x0E` | `Formula::ExistentialQuantifier` | a string and a formula      |
+//! | `0x0F` | `Formula::Custom`       | a string and two formulae            |
+//! | `0x10` | `Term::Var`             | a string                             |
+//! | `0x11` | `Term::IntLit` or `Const` | its spelling, as a string          |
+//! | `0x12` | `Term::App`             | a string, a count `n` and `n` terms  |
+//! | `0x13` | `Formula::Iff`          | two formulae                         |
+//! | `0x14` | `Formula::Equality`     | two terms                            |
+//! | `0x15` | `Formula::Less`         | two terms                            |
+//! | `0x16` | `Formula::Predicate`    | a string, a count `n` and `n` terms  |
+//!
+//! A constant is decoded with [`Term::constant`](crate::Term::constant), so an integer literal and
+//! a constant with any other spelling share the tag `0x11`.
+//!
+//! A variant added to `Formula` later gets a new tag, which an older decoder rejects as an
+//! [`UnknownTag`](DecodeErrorKind::UnknownTag). A future version of the format that changes the
+//! meaning of an existing tag will use a different magic byte instead, so that older decoders
+//! reject it instead of misreading it.
+//!
+//! [`Formula::to_bytes`]: crate::Formula::to_bytes
+//! [`Formula::from_bytes`]: crate::Formula::from_bytes
+use std::fmt;
+
+use crate::{Formula, Term};
+
+/// The first byte of every encoded formula, identifying version 1 of the format.
+pub const MAGIC: u8 = 0xF1;
+
+const TERM: u8 = 0x00;
+const TOP: u8 = 0x01;
+const BOTTOM: u8 = 0x02;
+const NEGATION: u8 = 0x03;
+const CONJUNCTION: u8 = 0x04;
+const DISJUNCTION: u8 = 0x05;
+const IMPLICATION: u8 = 0x06;
+const EQUIVALENCE: u8 = 0x07;
+const NOT_EQUAL: u8 = 0x08;
+const LESS_THAN: u8 = 0x09;
+const LESS_EQUAL: u8 = 0x0A;
+const GREATER_EQUAL: u8 = 0x0B;
+const GREATER_THAN: u8 = 0x0C;
+const UNIVERSAL_QUANTIFIER: u8 = 0x0D;
+const EXISTENTIAL_QUANTIFIER: u8 = 0x0E;
+const CUSTOM: u8 = 0x0F;
+const VARIABLE: u8 = 0x10;
+const CONSTANT: u8 = 0x11;
+const FUNCTION: u8 = 0x12;
+const IFF: u8 = 0x13;
+const EQUALITY: u8 = 0x14;
+const LESS: u8 = 0x15;
+const PREDICATE: u8 = 0x16;
+
+/// Encodes `formula`, including the magic byte. See [`Formula::to_bytes`].
+///
+/// The encoder keeps its own stack of the nodes still to be written instead of recursing, so
+/// that no formula or term is too deep to encode.
+pub(crate) fn encode(formula: &Formula) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![MAGIC];
+    let mut stack: Vec<Node<'_>> = vec![Node::Formula(formula)];
+    while let Some(node) = stack.pop() {
+        match node {
+            Node::Formula(formula) => encode_formula(formula, &mut bytes, &mut stack),
+            Node::Term(term) => encode_term(term, &mut bytes, &mut stack),
+        }
+    }
+    bytes
+}
+
+/// A node that is still to be encoded.
+enum Node<'a> {
+    Formula(&'a Formula),
+    Term(&'a Term),
+}
+
+/// Writes the tag and strings of `formula`, and pushes its subformulae or term onto `stack`.
+fn encode_formula<'a>(formula: &'a Formula, bytes: &mut Vec<u8>, stack: &mut Vec<Node<'a>>) {
+    let (tag, children): (u8, [Option<&Formula>; 2]) = match formula {
+        Formula::Term(term) => {
+            bytes.push(TERM);
+            stack.push(Node::Term(term));
+            return;
+        }
+        Formula::Top => (TOP, [None, None]),
+        Formula::Bottom => (BOTTOM, [None, None]),
+        Formula::Negation(inner) => (NEGATION, [Some(inner), None]),
+        Formula::Conjunction(lhs, rhs) => (CONJUNCTION, [Some(lhs), Some(rhs)]),
+        Formula::Disjunction(lhs, rhs) => (DISJUNCTION, [Some(lhs), Some(rhs)]),
+        Formula::Implication(lhs, rhs) => (IMPLICATION, [Some(lhs), Some(rhs)]),
+        Formula::Equivalence(lhs, rhs) => (EQUIVALENCE, [Some(lhs), Some(rhs)]),
+        Formula::Iff(lhs, rhs) => (IFF, [Some(lhs), Some(rhs)]),
+        Formula::NotEqual(lhs, rhs) => (NOT_EQUAL, [Some(lhs), Some(rhs)]),
+        Formula::LessThan(lhs, rhs) => (LESS_THAN, [Some(lhs), Some(rhs)]),
+        Formula::LessEqual(lhs, rhs) => (LESS_EQUAL, [Some(lhs), Some(rhs)]),
+        Formula::GreaterEqual(lhs, rhs) => (GREATER_EQUAL, [Some(lhs), Some(rhs)]),
+        Formula::GreaterThan(lhs, rhs) => (GREATER_THAN, [Some(lhs), Some(rhs)]),
+        Formula::UniversalQuantifier(variable, inner) => {
+            bytes.push(UNIVERSAL_QUANTIFIER);
+            encode_string(variable, bytes);
+            stack.push(Node::Formula(inner));
+            return;
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            bytes.push(EXISTENTIAL_QUANTIFIER);
+            encode_string(variable, bytes);
+            stack.push(Node::Formula(inner));
+            return;
+        }
+        Formula::Custom(symbol, lhs, rhs) => {
+            bytes.push(CUSTOM);
+            encode_string(symbol, bytes);
+            stack.push(Node::Formula(rhs));
+            stack.push(Node::Formula(lhs));
+            return;
+        }
+        Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+            bytes.push(if matches!(formula, Formula::Equality(_, _)) {
+                EQUALITY
+            } else {
+                LESS
+            });
+            stack.push(Node::Term(rhs));
+            stack.push(Node::Term(lhs));
+            return;
+        }
+        Formula::Predicate(name, args) => {
+            bytes.push(PREDICATE);
+            encode_string(name, bytes);
+            encode_length(args.len(), bytes);
+            stack.extend(args.iter().rev().map(Node::Term));
+            return;
+        }
+    };
+    bytes.push(tag);
+    // The left operand is pushed last, so that it is written first.
+    stack.extend(children.into_iter().rev().flatten().map(Node::Formula));
+}
+
+/// Writes the tag and strings of `term`, and pushes its arguments onto `stack`.
+fn encode_term<'a>(term: &'a Term, bytes: &mut Vec<u8>, stack: &mut Vec<Node<'a>>) {
+    match term {
+        Term::Var(name) => {
+            bytes.push(VARIABLE);
+            encode_string(name, bytes);
+        }
+        Term::IntLit(value) => {
+            bytes.push(CONSTANT);
+            encode_string(&value.to_string(), bytes);
+        }
+        Term::Const(value) => {
+            bytes.push(CONSTANT);
+            encode_string(value, bytes);
+        }
+        Term::App(name, args) => {
+            bytes.push(FUNCTION);
+            encode_string(name, bytes);
+            encode_length(args.len(), bytes);
+            stack.extend(args.iter().rev().map(Node::Term));
+        }
+    }
+}
+
+fn encode_string(string: &str, bytes: &mut Vec<u8>) {
+    encode_length(string.len(), bytes);
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+fn encode_length(mut length: usize, bytes: &mut Vec<u8>) {
+    while length >= 0x80 {
+        bytes.push((length as u8 & 0x7F) | 0x80);
+        length >>= 7;
+    }
+    bytes.push(length as u8);
+}
+
+/// An error returned when bytes cannot be decoded into a `Formula`.
+///
+/// # Example
+/// ```
+/// use first_order::{DecodeError, DecodeErrorKind, Formula};
+///
+/// let error: DecodeError = Formula::from_bytes(&[0xF1, 0x04, 0x01]).unwrap_err();
+/// assert_eq!(error.kind, DecodeErrorKind::UnexpectedEnd);
+/// assert_eq!(error.offset, 3);
+/// assert_eq!(error.to_string(), "unexpected end of input at byte 3");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The category of the failure.
+    pub kind: DecodeErrorKind,
+    /// The offset of the byte at which decoding failed.
+    pub offset: usize,
+}
+
+/// The category of a `DecodeError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The input does not start with the magic byte of a supported version of the format.
+    BadMagic,
+    /// The input ended in the middle of a formula.
+    UnexpectedEnd,
+    /// A byte is not the tag of a formula or term where one was expected.
+    UnknownTag(u8),
+    /// A length does not fit in a `usize`.
+    InvalidLength,
+    /// A string is not valid UTF-8.
+    InvalidUtf8,
+    /// A complete formula was decoded, but bytes remained after it.
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::BadMagic => write!(f, "missing or unsupported magic byte")?,
+            DecodeErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+            DecodeErrorKind::UnknownTag(tag) => write!(f, "unknown tag {tag:#04x}")?,
+            DecodeErrorKind::InvalidLength => write!(f, "invalid length")?,
+            DecodeErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8")?,
+            DecodeErrorKind::TrailingBytes => write!(f, "trailing bytes")?,
+        }
+        write!(f, " at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A node whose fields are still being decoded.
+enum Pending {
+    Term,
+    Negation,
+    Binary(u8),
+    BinaryRight(u8, Formula),
+    Quantifier(u8, String),
+    Custom(String),
+    CustomRight(String, Formula),
+    Function(String, usize, Vec<Term>),
+    /// An `Equality`, `Less` or `Predicate`, with the number of terms it takes.
+    Atom(u8, String, usize, Vec<Term>),
+}
+
+/// A node that has been decoded completely.
+enum Decoded {
+    Formula(Formula),
+    Term(Term),
+}
+
+/// Decodes `bytes`, which must start with the magic byte. See [`Formula::from_bytes`].
+///
+/// The decoder keeps its own stack of pending nodes instead of recursing, so that no input can
+/// overflow the call stack while it is decoded. Both `Formula` and `Term` are dropped with an
+/// explicit stack too, so neither can a deeply nested result.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Formula, DecodeError> {
+    let mut decoder: Decoder<'_> = Decoder { bytes, offset: 0 };
+    if decoder.byte()? != MAGIC {
+        return Err(decoder.error_at(DecodeErrorKind::BadMagic, 0));
+    }
+
+    let mut pending: Vec<Pending> = Vec::new();
+    loop {
+        let offset: usize = decoder.offset;
+        let tag: u8 = decoder.byte()?;
+        let in_term: bool = matches!(
+            pending.last(),
+            Some(Pending::Term | Pending::Function(..) | Pending::Atom(..))
+        );
+        let mut decoded: Decoded = match (in_term, tag) {
+            (false, TERM) => {
+                pending.push(Pending::Term);
+                continue;
+            }
+            (false, TOP) => Decoded::Formula(Formula::Top),
+            (false, BOTTOM) => Decoded::Formula(Formula::Bottom),
+            (false, NEGATION) => {
+                pending.push(Pending::Negation);
+                continue;
+            }
+            (false, CONJUNCTION..=GREATER_THAN | IFF) => {
+                pending.push(Pending::Binary(tag));
+                continue;
+            }
+            (false, UNIVERSAL_QUANTIFIER | EXISTENTIAL_QUANTIFIER) => {
+                pending.push(Pending::Quantifier(tag, decoder.string()?));
+                continue;
+            }
+            (false, CUSTOM) => {
+                pending.push(Pending::Custom(decoder.string()?));
+                continue;
+            }
+            (false, EQUALITY | LESS) => {
+                pending.push(Pending::Atom(tag, String::new(), 2, Vec::new()));
+                continue;
+            }
+            (false, PREDICATE) => {
+                let name: String = decoder.string()?;
+                match decoder.length()? {
+                    0 => Decoded::Formula(Formula::Predicate(name, Vec::new())),
+                    count => {
+                        pending.push(Pending::Atom(tag, name, count, Vec::new()));
+                        continue;
+                    }
+                }
+            }
+            (true, VARIABLE) => Decoded::Term(Term::Var(decoder.string()?)),
+            (true, CONSTANT) => Decoded::Term(Term::constant(decoder.string()?)),
+            (true, FUNCTION) => {
+                let name: String = decoder.string()?;
+                match decoder.length()? {
+                    0 => Decoded::Term(Term::App(name, Vec::new())),
+                    count => {
+                        pending.push(Pending::Function(name, count, Vec::new()));
+                        continue;
+                    }
+                }
+            }
+            _ => return Err(decoder.error_at(DecodeErrorKind::UnknownTag(tag), offset)),
+        };
+
+        // Complete every pending node whose last field has now been decoded.
+        loop {
+            decoded = match (decoded, pending.pop()) {
+                (Decoded::Term(term), Some(Pending::Function(name, count, mut args))) => {
+                    args.push(term);
+                    if args.len() < count {
+                        pending.push(Pending::Function(name, count, args));
+                        break;
+                    }
+                    Decoded::Term(Term::App(name, args))
+                }
+                (Decoded::Term(term), Some(Pending::Atom(tag, name, count, mut args))) => {
+                    args.push(term);
+                    if args.len() < count {
+                        pending.push(Pending::Atom(tag, name, count, args));
+                        break;
+                    }
+                    Decoded::Formula(atom(tag, name, args))
+                }
+                (Decoded::Term(term), Some(Pending::Term)) => Decoded::Formula(Formula::Term(term)),
+                (Decoded::Formula(formula), None) => {
+                    if decoder.offset != bytes.len() {
+                        return Err(decoder.error(DecodeErrorKind::TrailingBytes));
+                    }
+                    return Ok(formula);
+                }
+                (Decoded::Formula(inner), Some(Pending::Negation)) => {
+                    Decoded::Formula(Formula::Negation(Box::new(inner)))
+                }
+                (Decoded::Formula(lhs), Some(Pending::Binary(tag))) => {
+                    pending.push(Pending::BinaryRight(tag, lhs));
+                    break;
+                }
+                (Decoded::Formula(rhs), Some(Pending::BinaryRight(tag, lhs))) => {
+                    Decoded::Formula(binary(tag, lhs, rhs))
+                }
+                (Decoded::Formula(inner), Some(Pending::Quantifier(tag, variable))) => {
+                    Decoded::Formula(quantifier(tag, variable, inner))
+                }
+                (Decoded::Formula(lhs), Some(Pending::Custom(symbol))) => {
+                    pending.push(Pending::CustomRight(symbol, lhs));
+                    break;
+                }
+                (Decoded::Formula(rhs), Some(Pending::CustomRight(symbol, lhs))) => {
+                    Decoded::Formula(Formula::Custom(symbol, Box::new(lhs), Box::new(rhs)))
+                }
+                _ => unreachable!("terms are only decoded inside a term or function"),
+            };
+        }
+    }
+}
+
+/// Builds the binary variant identified by `tag`.
+fn binary(tag: u8, lhs: Formula, rhs: Formula) -> Formula {
+    let (lhs, rhs): (Box<Formula>, Box<Formula>) = (Box::new(lhs), Box::new(rhs));
+    match tag {
+        CONJUNCTION => Formula::Conjunction(lhs, rhs),
+        DISJUNCTION => Formula::Disjunction(lhs, rhs),
+        IMPLICATION => Formula::Implication(lhs, rhs),
+        EQUIVALENCE => Formula::Equivalence(lhs, rhs),
+        IFF => Formula::Iff(lhs, rhs),
+        NOT_EQUAL => Formula::NotEqual(lhs, rhs),
+        LESS_THAN => Formula::LessThan(lhs, rhs),
+        LESS_EQUAL => Formula::LessEqual(lhs, rhs),
+        GREATER_EQUAL => Formula::GreaterEqual(lhs, rhs),
+        _ => Formula::GreaterThan(lhs, rhs),
+    }
+}
+
+/// Builds the atom identified by `tag` from its complete `args`.
+fn atom(tag: u8, name: String, args: Vec<Term>) -> Formula {
+    match tag {
+        PREDICATE => Formula::Predicate(name, args),
+        _ => {
+            let [lhs, rhs]: [Term; 2] = args
+                .try_into()
+                .unwrap_or_else(|_| unreachable!("an equality or less has two terms"));
+            match tag {
+                EQUALITY => Formula::Equality(lhs, rhs),
+                _ => Formula::Less(lhs, rhs),
+            }
+        }
+    }
+}
+
+/// Builds the quantifier identified by `tag`.
+fn quantifier(tag: u8, variable: String, inner: Formula) -> Formula {
+    match tag {
+        UNIVERSAL_QUANTIFIER => Formula::UniversalQuantifier(variable, Box::new(inner)),
+        _ => Formula::ExistentialQuantifier(variable, Box::new(inner)),
+    }
+}
+
+/// A cursor over the bytes being decoded.
+struct Decoder<'a> {
+    bytes: &'a [u8], // The encoded formula.
+    offset: usize,   // The offset of the next byte to read.
+}
+
+impl Decoder<'_> {
+    fn error_at(&self, kind: DecodeErrorKind, offset: usize) -> DecodeError {
+        DecodeError { kind, offset }
+    }
+
+    /// Builds a `DecodeError` located at the next byte.
+    fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        self.error_at(kind, self.offset)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte: u8 = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(self.error(DecodeErrorKind::UnexpectedEnd))?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn length(&mut self) -> Result<usize, DecodeError> {
+        let start: usize = self.offset;
+        let mut length: usize = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte: u8 = self.byte()?;
+            let group: usize = (byte & 0x7F) as usize;
+            if group.checked_shl(shift).map(|value| value >> shift) != Some(group) {
+                break;
+            }
+            length |= group << shift;
+            if byte & 0x80 == 0 {
+                return Ok(length);
+            }
+        }
+        Err(self.error_at(DecodeErrorKind::InvalidLength, start))
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let length: usize = self.length()?;
+        let start: usize = self.offset;
+        let Some(slice) = start
+            .checked_add(length)
+            .and_then(|end| self.bytes.get(start..end))
+        else {
+            return Err(self.error_at(DecodeErrorKind::UnexpectedEnd, self.bytes.len()));
+        };
+        self.offset += length;
+        match std::str::from_utf8(slice) {
+            Ok(string) => Ok(string.to_string()),
+            Err(_) => Err(self.error_at(DecodeErrorKind::InvalidUtf8, start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_encoding() {
+        let test_formula: Formula = Formula::new("∀ x ∧ ⊤ f(x)");
+        assert_eq!(
+            test_formula.to_bytes(),
+            vec![
+                MAGIC,
+                UNIVERSAL_QUANTIFIER,
+                1,
+                b'x',
+                CONJUNCTION,
+                TOP,
+                PREDICATE,
+                1,
+                b'f',
+                1,
+                VARIABLE,
+                1,
+                b'x'
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip_every_variant() {
+        let term = |s: &str| Box::new(Formula::new(s));
+        let formulae: Vec<Formula> = vec![
+            Formula::Top,
+            Formula::Bottom,
+            Formula::Negation(term("P")),
+            Formula::Conjunction(term("P"), term("Q")),
+            Formula::Disjunction(term("P"), term("Q")),
+            Formula::Implication(term("P"), term("Q")),
+            Formula::Equivalence(term("x"), term("(r-y)+y*(1+q)")),
+            Formula::Iff(term("P"), term("Q")),
+            Formula::NotEqual(term("b"), term("0")),
+            Formula::LessThan(term("0"), term("index")),
+            Formula::LessEqual(term("index"), term("N")),
+            Formula::GreaterEqual(term("gcd(a,mod(a,b))"), term("-1")),
+            Formula::GreaterThan(term("r*10^(p)"), term("0")),
+            Formula::UniversalQuantifier("x".to_string(), term("P(x)")),
+            Formula::ExistentialQuantifier("ÿ".to_string(), term("P(ÿ)")),
+            Formula::Custom("⊆".to_string(), term("A"), term("B")),
+            Formula::Term(Term::App("f".to_string(), Vec::new())),
+            Formula::Equality(Term::new("x"), Term::new("(r-y)+y*(1+q)")),
+            Formula::Less(Term::new("0"), Term::new("index")),
+            Formula::Predicate("P".to_string(), vec![Term::new("x"), Term::new("f(y)")]),
+            Formula::Predicate("P".to_string(), Vec::new()),
+        ];
+        for formula in formulae {
+            assert_eq!(Formula::from_bytes(&formula.to_bytes()), Ok(formula));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_examples() {
+        for input in corpus::all() {
+            let formula: Formula = Formula::new(input);
+            assert_eq!(Formula::from_bytes(&formula.to_bytes()), Ok(formula));
+        }
+    }
+
+    #[test]
+    fn test_long_strings() {
+        let name: String = "a".repeat(300);
+        let formula: Formula = Formula::Term(Term::Var(name));
+        let bytes: Vec<u8> = formula.to_bytes();
+        assert_eq!(&bytes[..5], &[MAGIC, TERM, VARIABLE, 0xAC, 0x02]);
+        assert_eq!(Formula::from_bytes(&bytes), Ok(formula));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let kind = |bytes: &[u8]| Formula::from_bytes(bytes).unwrap_err().kind;
+        assert_eq!(kind(&[]), DecodeErrorKind::UnexpectedEnd);
+        assert_eq!(kind(&[0xF2, TOP]), DecodeErrorKind::BadMagic);
+        assert_eq!(kind(&[MAGIC, 0x17]), DecodeErrorKind::UnknownTag(0x17));
+        assert_eq!(kind(&[MAGIC, TOP, TOP]), DecodeErrorKind::TrailingBytes);
+        assert_eq!(kind(&[MAGIC, TERM, TOP]), DecodeErrorKind::UnknownTag(TOP));
+        assert_eq!(
+            kind(&[MAGIC, TERM, VARIABLE, 2, b'x']),
+            DecodeErrorKind::UnexpectedEnd
+        );
+        assert_eq!(
+            kind(&[MAGIC, TERM, VARIABLE, 1, 0xFF]),
+            DecodeErrorKind::InvalidUtf8
+        );
+        assert_eq!(
+            kind(&[
+                MAGIC, TERM, VARIABLE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F
+            ]),
+            DecodeErrorKind::InvalidLength
+        );
+    }
+
+    #[test]
+    fn test_decode_random_bytes_without_panicking() {
+        // A xorshift generator keeps the test deterministic without extra dependencies.
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut decoded: usize = 0;
+        for _ in 0..20_000 {
+            let length: usize = (next() % 64) as usize;
+            let mut bytes: Vec<u8> = vec![MAGIC];
+            // Bias the bytes towards valid tags and short lengths so that decoding gets deep.
+            bytes.extend((0..length).map(|_| (next() % 0x14) as u8));
+            if let Ok(formula) = Formula::from_bytes(&bytes) {
+                assert_eq!(formula.to_bytes(), bytes);
+                decoded += 1;
+            }
+            let noise: Vec<u8> = (0..length).map(|_| next() as u8).collect();
+            let _ = Formula::from_bytes(&noise);
+        }
+        assert!(decoded > 0);
+    }
+
+    #[test]
+    fn test_deep_term() {
+        // `f(f(…f(x)…))`, nested a million times.
+        let mut bytes: Vec<u8> = vec![MAGIC, TERM];
+        for _ in 0..1_000_000 {
+            bytes.extend_from_slice(&[FUNCTION, 1, b'f', 1]);
+        }
+        bytes.extend_from_slice(&[VARIABLE, 1, b'x']);
+        let formula: Formula = Formula::from_bytes(&bytes).unwrap();
+        assert_eq!(formula.to_bytes(), bytes);
+        drop(formula);
+    }
+
+    #[test]
+    fn test_deep_formula_round_trip() {
+        let depth: usize = 100_000;
+        let input: String = format!("{}= x {}1", "¬ ".repeat(depth), "1+".repeat(depth));
+        let formula: Formula = Formula::new(&input);
+        let bytes: Vec<u8> = formula.to_bytes();
+        assert_eq!(Formula::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_decode_deep_input() {
+        let mut bytes: Vec<u8> = vec![MAGIC];
+        bytes.extend(std::iter::repeat_n(NEGATION, 100_000));
+        assert_eq!(
+            Formula::from_bytes(&bytes).unwrap_err().kind,
+            DecodeErrorKind::UnexpectedEnd
+        );
+    }
+}
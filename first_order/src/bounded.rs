@@ -0,0 +1,132 @@
+//! # Bounded quantifiers
+//!
+//! This module desugars the bounded quantifiers `∀ x < b φ` and `∃ x < b φ` into the encodings
+//! loop invariants usually spell out, and recognises those encodings again for
+//! [`Formula::as_bounded_quantifier`]:
+//!
+//! | Bounded form  | Encoding           |
+//! |---------------|--------------------|
+//! | `∀ x < b φ`   | `∀ x → < x b φ`    |
+//! | `∃ x < b φ`   | `∃ x ∧ < x b φ`    |
+//!
+//! The bound `b` is a single term. It lies inside the scope of `x`, exactly as in the encoding.
+//! The prefix parser reads the bounded forms with [`ParserConfig::bounded_quantifiers`], the infix
+//! parser always does, and [`Formula::to_infix_pretty`] writes every recognised encoding in its
+//! bounded form.
+//!
+//! [`ParserConfig::bounded_quantifiers`]: crate::ParserConfig::bounded_quantifiers
+use crate::{Atom, Formula, Quantifier, Term, atom};
+
+/// Returns the encoding of the bounded quantifier `quantifier variable < bound body`.
+pub(crate) fn desugar(
+    quantifier: Quantifier,
+    variable: String,
+    bound: Formula,
+    body: Formula,
+) -> Formula {
+    let guard: Formula = atom::less_than(
+        Box::new(Formula::Term(Term::Var(variable.clone()))),
+        Box::new(bound),
+    );
+    match quantifier {
+        Quantifier::Universal => {
+            Formula::UniversalQuantifier(variable, Box::new(guard.implies(body)))
+        }
+        Quantifier::Existential => {
+            Formula::ExistentialQuantifier(variable, Box::new(guard.and(body)))
+        }
+    }
+}
+
+/// Returns the parts of `formula` if it encodes a bounded quantifier. See
+/// [`Formula::as_bounded_quantifier`].
+pub(crate) fn recognize(formula: &Formula) -> Option<(Quantifier, &str, &Term, &Formula)> {
+    let (quantifier, variable, guard, body) = match formula {
+        Formula::UniversalQuantifier(variable, inner) => match inner.as_ref() {
+            Formula::Implication(guard, body) => (Quantifier::Universal, variable, guard, body),
+            _ => return None,
+        },
+        Formula::ExistentialQuantifier(variable, inner) => match inner.as_ref() {
+            Formula::Conjunction(guard, body) => (Quantifier::Existential, variable, guard, body),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    match guard.as_atom() {
+        Some(Atom::LessThan(Term::Var(name), bound)) if name == variable => {
+            Some((quantifier, variable, bound, body))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desugar() {
+        assert_eq!(
+            desugar(
+                Quantifier::Universal,
+                "i".to_string(),
+                Formula::term("index"),
+                Formula::new("= out(i) fib(i)"),
+            ),
+            Formula::new("∀ i → < i index = out(i) fib(i)")
+        );
+        assert_eq!(
+            desugar(
+                Quantifier::Existential,
+                "j".to_string(),
+                Formula::term("n+1"),
+                Formula::new("P(j)"),
+            ),
+            Formula::new("∃ j ∧ < j n+1 P(j)")
+        );
+    }
+
+    #[test]
+    fn test_recognize() {
+        let test_formula: Formula = Formula::new("∀ i → < i index = out(i) fib(i)");
+        assert_eq!(
+            recognize(&test_formula),
+            Some((
+                Quantifier::Universal,
+                "i",
+                &Term::new("index"),
+                &Formula::new("= out(i) fib(i)")
+            ))
+        );
+        let test_formula: Formula = Formula::new("∃ j ∧ < j n+1 P(j)");
+        assert_eq!(
+            recognize(&test_formula).map(|(quantifier, _, bound, _)| (quantifier, bound.clone())),
+            Some((Quantifier::Existential, Term::new("n+1")))
+        );
+    }
+
+    #[test]
+    fn test_not_bounded() {
+        let inputs: [&str; 7] = [
+            "∀ i P(i)",
+            "∀ i ∧ < i n P(i)",
+            "∃ i → < i n P(i)",
+            "∀ i → < j n P(i)",
+            "∀ i → < n i P(i)",
+            "∀ i → ≤ i n P(i)",
+            "→ < i n P(i)",
+        ];
+        for input in inputs {
+            assert_eq!(recognize(&Formula::new(input)), None, "{input}");
+        }
+        // A bound that is not a term cannot be written in the bounded form.
+        let test_formula: Formula = Formula::UniversalQuantifier(
+            "i".to_string(),
+            Box::new(
+                Formula::LessThan(Box::new(Formula::term("i")), Box::new(Formula::Top))
+                    .implies(Formula::term("P")),
+            ),
+        );
+        assert_eq!(recognize(&test_formula), None);
+    }
+}
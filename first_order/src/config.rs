@@ -0,0 +1,406 @@
+//! # Parser configuration
+//!
+//! This module provides [`ParserConfig`], which extends the prefix notation parser with extra
+//! binary connectives, such as `⊆` or `∈`, that are read into [`Formula::Custom`] rather than
+//! being mistaken for terms.
+//!
+//! Like the built-in relations `=` and `<`, a registered connective takes two operands, and an
+//! atomic operand is in term position when checked against a [`Signature`](crate::Signature).
+//!
+//! The configuration also decides whether lookalike characters, such as `⟶` for `→`, are
+//! replaced before parsing, which is on by default like for [`Formula::new`], and whether a
+//! quantifier may bind several variables at once, as in `∀ x y P(x,y)`, and whether the bounded
+//! quantifiers `∀ x < b φ` and `∃ x < b φ` are read, which are both off by default.
+use std::collections::BTreeSet;
+
+use crate::{Formula, ParseError, Parser, Symbol, lookalike};
+
+/// A set of extra binary connectives recognised when parsing prefix notation.
+///
+/// With no connectives registered and the default lookalike replacement, parsing behaves exactly
+/// like [`Formula::try_new`].
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, ParserConfig};
+///
+/// let mut config = ParserConfig::new();
+/// config.binary_connective("⊆").binary_connective("∈");
+///
+/// let test_formula: Formula = config.parse("∧ ⊆ A B ∈ x A").unwrap();
+/// assert_eq!(test_formula.to_prefix_notation(), "∧ ⊆ A B ∈ x A");
+/// assert_eq!(test_formula.to_infix_notation(), "((A⊆B)∧(x∈A))");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserConfig {
+    connectives: BTreeSet<String>, // The tokens read as binary connectives.
+    normalize_lookalikes: bool,    // Whether lookalike characters are replaced before parsing.
+    multi_variable_quantifiers: bool, // Whether a quantifier binds every identifier after it.
+    bounded_quantifiers: bool,     // Whether `< b` after the variables of a quantifier bounds them.
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        ParserConfig {
+            connectives: BTreeSet::new(),
+            normalize_lookalikes: true,
+            multi_variable_quantifiers: false,
+            bounded_quantifiers: false,
+        }
+    }
+}
+
+impl ParserConfig {
+    /// Creates a `ParserConfig` without extra connectives, replacing lookalike characters.
+    pub fn new() -> Self {
+        ParserConfig::default()
+    }
+
+    /// Sets whether characters that look like a symbol, such as `⟶` (U+27F6) for `→`, are
+    /// replaced with that symbol before parsing. The replaced characters are listed in the
+    /// `lookalike` module.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, ParserConfig};
+    ///
+    /// let mut config = ParserConfig::new();
+    /// assert_eq!(config.parse("⟶ P Q"), Ok(Formula::new("→ P Q")));
+    ///
+    /// config.normalize_lookalikes(false);
+    /// assert_eq!(
+    ///     config.parse("⟶ P Q").unwrap_err().expected,
+    ///     "a formula (did you mean \"→\"?)"
+    /// );
+    /// ```
+    pub fn normalize_lookalikes(&mut self, enabled: bool) -> &mut Self {
+        self.normalize_lookalikes = enabled;
+        self
+    }
+
+    /// Sets whether a quantifier binds every identifier that follows it, so that `∀ x y z φ` is
+    /// read as `∀ x ∀ y ∀ z φ`. [`Formula::to_prefix_notation_grouped`] writes formulae in this
+    /// shorthand.
+    ///
+    /// The variables end at the first token that is not a plain identifier, such as a connective,
+    /// a quantifier, a truth constant or an application like `P(x,y)`. An atomic body that is
+    /// itself an identifier, such as `P` in `∀ x P`, is therefore read as another variable, and
+    /// the quantifier is left without a body. Such formulae are parsed with the shorthand off.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, ParserConfig};
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.multi_variable_quantifiers(true);
+    /// assert_eq!(
+    ///     config.parse("∀ x y ∃ z < x+y z"),
+    ///     Ok(Formula::new("∀ x ∀ y ∃ z < x+y z"))
+    /// );
+    /// assert_eq!(
+    ///     config.parse("∀ x P").unwrap_err().expected,
+    ///     "a formula after ∀"
+    /// );
+    /// ```
+    pub fn multi_variable_quantifiers(&mut self, enabled: bool) -> &mut Self {
+        self.multi_variable_quantifiers = enabled;
+        self
+    }
+
+    /// Returns whether a quantifier binds every identifier that follows it.
+    pub(crate) fn binds_several_variables(&self) -> bool {
+        self.multi_variable_quantifiers
+    }
+
+    /// Sets whether `∀ x < b φ` and `∃ x < b φ` are read as bounded quantifiers, which desugar to
+    /// `∀ x → < x b φ` and `∃ x ∧ < x b φ`. The bound `b` is a single term, and with
+    /// [`ParserConfig::multi_variable_quantifiers`] it bounds every variable of the quantifier.
+    ///
+    /// Without this option, `∀ x < b φ` is the quantifier `∀ x` over the relation `< b φ`, so the
+    /// two readings of a formula such as `∀ x < x y` differ: `∀ x < x y` itself, or
+    /// `∀ x → < x x y` with the option.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, ParserConfig};
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.bounded_quantifiers(true);
+    /// assert_eq!(
+    ///     config.parse("∀ i < index = out(i) fib(i)"),
+    ///     Ok(Formula::new("∀ i → < i index = out(i) fib(i)"))
+    /// );
+    /// assert_eq!(
+    ///     config.parse("∃ j < n P(j)"),
+    ///     Ok(Formula::new("∃ j ∧ < j n P(j)"))
+    /// );
+    /// ```
+    pub fn bounded_quantifiers(&mut self, enabled: bool) -> &mut Self {
+        self.bounded_quantifiers = enabled;
+        self
+    }
+
+    /// Returns whether `< b` after the variables of a quantifier is read as a bound.
+    pub(crate) fn reads_bounds(&self) -> bool {
+        self.bounded_quantifiers
+    }
+
+    /// Registers `token` as a binary connective, so that `token φ ψ` is parsed into
+    /// `Formula::Custom(token, φ, ψ)`.
+    ///
+    /// # Panics
+    /// Panics if `token` is empty, contains whitespace, or is already a built-in symbol such as
+    /// `∧` or `forall`.
+    pub fn binary_connective(&mut self, token: &str) -> &mut Self {
+        if token.is_empty() || token.chars().any(char::is_whitespace) {
+            panic!("The connective {token:?} must be a single non-empty token");
+        }
+        if Symbol::from_token(token).is_some() {
+            panic!("The connective {token:?} is already a built-in symbol");
+        }
+        self.connectives.insert(token.to_string());
+        self
+    }
+
+    /// Returns whether `token` is a registered binary connective.
+    pub fn is_connective(&self, token: &str) -> bool {
+        self.connectives.contains(token)
+    }
+
+    /// Parses a formula in prefix notation, recognising the registered connectives.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the logical formula in prefix notation.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` if the input is malformed.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, ParserConfig};
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.binary_connective("⊆");
+    ///
+    /// assert_eq!(
+    ///     config.parse("⊆ A B"),
+    ///     Ok(Formula::Custom(
+    ///         "⊆".to_string(),
+    ///         Box::new(Formula::new("A")),
+    ///         Box::new(Formula::new("B")),
+    ///     ))
+    /// );
+    /// assert!(config.parse("⊆ A").is_err());
+    /// ```
+    pub fn parse(&self, input: &str) -> Result<Formula, ParseError> {
+        let input: String = if self.normalize_lookalikes {
+            lookalike::normalize(input)
+        } else {
+            input.to_string()
+        };
+        let tokens: Vec<String> = input
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let mut parser: Parser<'_> = Parser::with_config(&tokens, self);
+        parser.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    fn subset() -> ParserConfig {
+        let mut config = ParserConfig::new();
+        config.binary_connective("⊆");
+        config
+    }
+
+    #[test]
+    fn test_parse_and_display_registered_connective() {
+        let test_formula: Formula = subset().parse("⊆ A B").unwrap();
+        assert_eq!(
+            test_formula,
+            Formula::Custom(
+                "⊆".to_string(),
+                Box::new(Formula::new("A")),
+                Box::new(Formula::new("B")),
+            )
+        );
+        assert_eq!(test_formula.to_prefix_notation(), "⊆ A B");
+        assert_eq!(test_formula.to_infix_notation(), "(A⊆B)");
+        assert_eq!(test_formula.to_string(), "(A⊆B)");
+        assert_eq!(test_formula.get_info(), ["⊆", "A", "B"]);
+    }
+
+    #[test]
+    fn test_registered_connective_nests() {
+        let test_formula: Formula = subset().parse("∀ x → ⊆ x A ⊆ ∩(x,B) A").unwrap();
+        assert_eq!(test_formula.to_prefix_notation(), "∀ x → ⊆ x A ⊆ ∩(x,B) A");
+        assert_eq!(test_formula.to_infix_notation(), "∀x(((x⊆A)→(∩(x,B)⊆A)))");
+        assert_eq!(
+            test_formula.get_info(),
+            ["UniversalQuantifier", "x", "→ ⊆ x A ⊆ ∩(x,B) A"]
+        );
+    }
+
+    #[test]
+    fn test_registered_connective_missing_operand() {
+        let error: ParseError = subset().parse("⊆ A").unwrap_err();
+        assert_eq!(error.index, 2);
+        assert_eq!(error.found, None);
+        assert_eq!(error.expected, "a formula after ⊆");
+        assert_eq!(
+            subset().parse("∀ ⊆ P").unwrap_err().expected,
+            "a variable after ∀"
+        );
+    }
+
+    #[test]
+    fn test_default_config_is_unchanged() {
+        let config: ParserConfig = ParserConfig::new();
+        for input in corpus::all() {
+            assert_eq!(config.parse(input), Formula::try_new(input));
+        }
+        assert_eq!(config.parse("⊆ A B"), Formula::try_new("⊆ A B"));
+        assert!(Formula::try_new("⊆ A B").is_err());
+    }
+
+    fn shorthand() -> ParserConfig {
+        let mut config = ParserConfig::new();
+        config.multi_variable_quantifiers(true);
+        config
+    }
+
+    #[test]
+    fn test_multi_variable_quantifiers_desugar() {
+        assert_eq!(
+            shorthand().parse("∀ x y z → ∧ < x y < y z < x z"),
+            Ok(Formula::new("∀ x ∀ y ∀ z → ∧ < x y < y z < x z"))
+        );
+        assert_eq!(
+            shorthand().parse("∧ ∃ i j = fib(i) j ∀ k P(k)"),
+            Ok(Formula::new("∧ ∃ i ∃ j = fib(i) j ∀ k P(k)"))
+        );
+        // Quantifiers of different kinds stay separate, and single variables parse as before.
+        assert_eq!(
+            shorthand().parse("∀ x ∃ y z ⊤"),
+            Ok(Formula::new("∀ x ∃ y ∃ z ⊤"))
+        );
+        for input in corpus::all() {
+            let expected: Formula = Formula::new(input);
+            if let Ok(parsed) = shorthand().parse(input) {
+                assert_eq!(parsed, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_variable_quantifiers_round_trip() {
+        let inputs: [&str; 3] = [
+            "∀ x ∀ y ∃ z ∧ < x z < y z",
+            "¬ ∃ a ∃ b ∀ c ∀ d = f(a,b) g(c,d)",
+            "∨ ∀ x ∃ y P(x,y) ∃ y ∃ x ⊥",
+        ];
+        for input in inputs {
+            let test_formula: Formula = Formula::new(input);
+            let grouped: String = test_formula.to_prefix_notation_grouped();
+            assert_eq!(shorthand().parse(&grouped), Ok(test_formula.clone()));
+            let ungrouped: String = test_formula.to_prefix_notation();
+            assert_eq!(ungrouped, input);
+            assert_eq!(ParserConfig::new().parse(&ungrouped), Ok(test_formula));
+        }
+        assert_eq!(
+            Formula::new("¬ ∃ a ∃ b ∀ c ∀ d = f(a,b) g(c,d)").to_prefix_notation_grouped(),
+            "¬ ∃ a b ∀ c d = f(a,b) g(c,d)"
+        );
+    }
+
+    #[test]
+    fn test_multi_variable_quantifiers_ambiguity() {
+        // With the shorthand, an identifier body is read as another bound variable.
+        let error: ParseError = shorthand().parse("∀ x y P").unwrap_err();
+        assert_eq!(error.index, 4);
+        assert_eq!(error.found, None);
+        assert_eq!(error.expected, "a formula after ∀");
+        assert_eq!(
+            ParserConfig::new().parse("∀ x y P").unwrap_err().kind,
+            crate::ParseErrorKind::TrailingTokens
+        );
+        assert_eq!(
+            ParserConfig::new().parse("∀ x ∀ y P"),
+            Ok(Formula::new("∀ x ∀ y P"))
+        );
+        // An application or a connective ends the variables.
+        assert_eq!(
+            shorthand().parse("∀ x y P(x,y)"),
+            Ok(Formula::new("∀ x ∀ y P(x,y)"))
+        );
+        // Registered connectives are never variables.
+        let mut config: ParserConfig = shorthand();
+        config.binary_connective("in");
+        assert_eq!(
+            config.parse("∀ x in x A").unwrap().to_prefix_notation(),
+            "∀ x in x A"
+        );
+    }
+
+    fn bounded() -> ParserConfig {
+        let mut config = ParserConfig::new();
+        config.bounded_quantifiers(true);
+        config
+    }
+
+    #[test]
+    fn test_bounded_quantifiers_desugar() {
+        assert_eq!(
+            bounded().parse("∧ ∀ i < index = out(i) fib(i) ∃ j < n+1 ¬ P(j)"),
+            Ok(Formula::new(
+                "∧ ∀ i → < i index = out(i) fib(i) ∃ j ∧ < j n+1 ¬ P(j)"
+            ))
+        );
+        // A quantifier not followed by `<` is unchanged.
+        assert_eq!(
+            bounded().parse("∀ i → < i n P(i)"),
+            Ok(Formula::new("∀ i → < i n P(i)"))
+        );
+        let mut config: ParserConfig = bounded();
+        config.multi_variable_quantifiers(true);
+        assert_eq!(
+            config.parse("∀ i j < n < i j"),
+            Ok(Formula::new("∀ i → < i n ∀ j → < j n < i j"))
+        );
+        let test_formula: Formula = bounded().parse("∀ i < n P(i)").unwrap();
+        assert_eq!(
+            test_formula.as_bounded_quantifier().map(|parts| parts.1),
+            Some("i")
+        );
+    }
+
+    #[test]
+    fn test_bounded_quantifiers_malformed() {
+        let error: ParseError = bounded().parse("∀ i < ∧ P Q").unwrap_err();
+        assert_eq!(error.index, 3);
+        assert_eq!(error.expected, "a bound after <");
+        let error: ParseError = bounded().parse("∀ i < n").unwrap_err();
+        assert_eq!(error.index, 4);
+        assert_eq!(error.expected, "a formula after ∀");
+        // Without the option, the same tokens are a relation in the quantifier body.
+        assert_eq!(
+            ParserConfig::new().parse("∀ x < x y"),
+            Ok(Formula::new("∀ x < x y"))
+        );
+        assert_eq!(
+            bounded().parse("∀ x < x y"),
+            Ok(Formula::new("∀ x → < x x y"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "already a built-in symbol")]
+    fn test_register_built_in_symbol() {
+        ParserConfig::new().binary_connective("∧");
+    }
+}
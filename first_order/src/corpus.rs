@@ -0,0 +1,56 @@
+//! Formulae taken from the example proofs in `results/`, shared by the tests of this crate.
+//!
+//! Every entry is written in prefix notation exactly as it appears (or is assembled) in the
+//! corresponding example, so that the tests exercise the inputs real proofs use.
+
+/// The formula used throughout the crate documentation.
+pub(crate) const DOC_EXAMPLE: &str = "∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V";
+
+/// Conditions and lemmas of the quotient and remainder proof from the paper.
+pub(crate) const PAPER_EXAMPLE: [&str; 10] = [
+    "→ ⊤ = x x+y*0",
+    "= x x+y*0",
+    "= x r+y*0",
+    "= x r+y*q",
+    "→ = x r+y*0 = x r+y*0",
+    "→ ∧ = x r+y*q ∨ < y r = y r = x (r-y)+y*(1+q)",
+    "= x (r-y)+y*(1+q)",
+    "= x r+y*(1+q)",
+    "→ = x r+y*q = x r+y*q",
+    "→ ∧ = x r+y*q ¬ ∨ < y r = y r ∧ ¬ ∨ < y r = y r = x r+y*q",
+];
+
+/// Conditions and lemmas of the fibonacci proof.
+pub(crate) const FIBONACCI: [&str; 10] = [
+    "∧ ∧ ∧ ∧ = preFib+curFib fib(index+1) = preFib+curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+    "∧ ∧ ∧ ∧ = curFib fib(index+1) = curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+    "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+    "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index+1-1) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+    "∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input",
+    "→ ∧ ∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input < index N ∧ ∧ ∧ ∧ = preFib+curFib fib(index+1) = preFib+curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+    "→ ∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input ∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index+1-1) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+    "→ ∧ ∧ ∧ ∧ = preFib 0 = curFib 1 = index 1 ∨ < 1 N = 1 N = N input ∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input",
+    "∧ ¬ < index N ∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input",
+    "→ ∧ ¬ < index N ∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input = curFib fib(input)",
+];
+
+/// Conditions and lemmas of the factorial, euclidean and decimal to binary proofs.
+pub(crate) const OTHER_EXAMPLES: [&str; 9] = [
+    "∧ = (result*count)*fact(count-1) fact(x) ∨ < 0 (count-1) = 0 (count-1)",
+    "∧ = result*fact(count-1) fact(x) ∨ < 0 (count-1) = 0 (count-1)",
+    "∧ = result*fact(count) fact(x) ∨ < 0 count = 0 count",
+    "→ ∧ ∧ = result*fact(count) fact(x) ∨ < 0 count = 0 count ¬ = 0 count ∧ = (result*count)*fact(count-1) fact(x) ∨ < 0 (count-1) = 0 (count-1)",
+    "→ ∧ ∧ = count x ∨ < 0 count = 0 count = result 1 ∧ = result*fact(count) fact(x) ∨ < 0 count = 0 count",
+    "→ ∧ ¬ ¬ = 0 count ∧ = result*fact(count) fact(x) ∨ < 0 count = 0 count = result fact(x)",
+    "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
+    "∧ ∨ = mod(i,2) 0 = mod(i,2) 1 ¬ = i 0",
+    "∨ = mod(i,2) 0 = mod(i,2) 1",
+];
+
+/// Every formula in the corpus, including the documentation example.
+pub(crate) fn all() -> impl Iterator<Item = &'static str> {
+    std::iter::once(DOC_EXAMPLE)
+        .chain(PAPER_EXAMPLE)
+        .chain(FIBONACCI)
+        .chain(OTHER_EXAMPLES)
+}
@@ -0,0 +1,115 @@
+//! # Documents
+//!
+//! This module reads documents holding one formula in prefix notation per line, such as proof
+//! scripts. Blank lines and lines starting with `#` are skipped, and every error records the line
+//! it was found on.
+use crate::{Formula, ParseError};
+
+/// Parses every formula of a document, one per line.
+///
+/// Lines that are empty or whose first non-whitespace character is `#` are skipped. Line numbers
+/// start at 1.
+///
+/// # Arguments
+/// * `input` - The document, with one formula in prefix notation per line.
+///
+/// # Returns
+/// An iterator yielding, for each formula line in order, the parsed `Formula` or a `ParseError`
+/// whose `line` is set.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, parse_all};
+///
+/// let document = "# precondition\n= x 5\n\n∧ P\n";
+/// let results: Vec<_> = parse_all(document).collect();
+/// assert_eq!(results[0], Ok(Formula::new("= x 5")));
+/// assert_eq!(results[1].as_ref().unwrap_err().line, Some(4));
+/// ```
+pub fn parse_all(input: &str) -> impl Iterator<Item = Result<Formula, ParseError>> + '_ {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            Formula::try_new(line).map_err(|error| ParseError {
+                line: Some(number),
+                ..error
+            })
+        })
+}
+
+/// Parses every formula of a document, one per line, stopping at the first error.
+///
+/// See [`parse_all`] for the lines that are skipped.
+///
+/// # Arguments
+/// * `input` - The document, with one formula in prefix notation per line.
+///
+/// # Returns
+/// A `Result` containing the formulae in order, or the first `ParseError`, whose `line` is set.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, parse_document};
+///
+/// let formulae = parse_document("= x 5\n# loop invariant\n< x 10").unwrap();
+/// assert_eq!(formulae, vec![Formula::new("= x 5"), Formula::new("< x 10")]);
+///
+/// let error = parse_document("= x 5\n< x").unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "line 2: expected a formula after < at token 2, found end of input"
+/// );
+/// ```
+pub fn parse_document(input: &str) -> Result<Vec<Formula>, ParseError> {
+    parse_all(input).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseErrorKind, corpus};
+
+    const DOCUMENT: &str = "\
+# Quotient and remainder
+= x x+y*0
+
+    # The loop invariant, indented
+= x r+y*q
+∧ = x r+y*q P Q
+→ = x r+y*0 = x r+y*0
+";
+
+    #[test]
+    fn test_parse_all_skips_comments_and_blank_lines() {
+        let results: Vec<Result<Formula, ParseError>> = parse_all(DOCUMENT).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], Ok(Formula::new(corpus::PAPER_EXAMPLE[1])));
+        assert_eq!(results[1], Ok(Formula::new(corpus::PAPER_EXAMPLE[3])));
+        assert_eq!(results[3], Ok(Formula::new(corpus::PAPER_EXAMPLE[4])));
+    }
+
+    #[test]
+    fn test_parse_all_reports_the_line_of_a_malformed_formula() {
+        let error: ParseError = parse_all(DOCUMENT).nth(2).unwrap().unwrap_err();
+        assert_eq!(error.line, Some(6));
+        assert_eq!(error.kind, ParseErrorKind::TrailingTokens);
+        assert_eq!(error.index, 5);
+        assert_eq!(
+            error.to_string(),
+            "line 6: trailing tokens starting at index 5, found \"Q\""
+        );
+    }
+
+    #[test]
+    fn test_parse_document_fails_with_the_first_error() {
+        assert_eq!(parse_document(DOCUMENT).unwrap_err().line, Some(6));
+        assert_eq!(parse_document("# nothing here\n\n"), Ok(Vec::new()));
+        assert_eq!(
+            parse_document(&corpus::FIBONACCI.join("\n")),
+            Ok(corpus::FIBONACCI.map(Formula::new).to_vec())
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! # Fragments
+//!
+//! This module classifies formulae by the smallest fragment of first-order logic they belong to,
+//! used by [`Formula::fragment`], [`Formula::is_quantifier_free`] and
+//! [`Formula::is_propositional`].
+//!
+//! A formula is propositional if it has no quantifier and every comparison relates variables and
+//! constants only, such as `< y r`, so that each comparison can be read as an opaque proposition.
+//! A comparison over a compound term, such as `< r+y*q x` or `= curFib fib(index)`, makes the
+//! formula merely quantifier-free, and a quantifier makes it first-order.
+//!
+//! The evaluation APIs that need quantifier-free input, [`Formula::truth_table`] and
+//! [`Formula::is_satisfiable`], name the fragment of a rejected formula in their error.
+use std::fmt;
+
+use crate::term::Term;
+use crate::{Formula, FormulaKind};
+
+/// A fragment of first-order logic, ordered from the smallest to the largest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fragment {
+    /// No quantifiers, and comparisons over variables and constants only.
+    Propositional,
+    /// No quantifiers, but some comparison over a compound term.
+    QuantifierFree,
+    /// At least one quantifier.
+    FirstOrder,
+}
+
+impl fmt::Display for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Fragment::Propositional => "propositional",
+            Fragment::QuantifierFree => "quantifier-free",
+            Fragment::FirstOrder => "first-order",
+        })
+    }
+}
+
+/// Returns the smallest fragment containing `formula`. See [`Formula::fragment`].
+pub(crate) fn fragment(formula: &Formula) -> Fragment {
+    let mut smallest: Fragment = Fragment::Propositional;
+    for node in formula.subformulae() {
+        match node.kind() {
+            FormulaKind::UniversalQuantifier | FormulaKind::ExistentialQuantifier => {
+                return Fragment::FirstOrder;
+            }
+            FormulaKind::Equivalence
+            | FormulaKind::NotEqual
+            | FormulaKind::LessThan
+            | FormulaKind::LessEqual
+            | FormulaKind::GreaterEqual
+            | FormulaKind::GreaterThan
+                if node.children().into_iter().any(is_compound) =>
+            {
+                smallest = Fragment::QuantifierFree;
+            }
+            FormulaKind::Equality | FormulaKind::Less
+                if node
+                    .as_atom()
+                    .is_some_and(|atom| atom.terms().into_iter().any(is_compound_term)) =>
+            {
+                smallest = Fragment::QuantifierFree;
+            }
+            _ => {}
+        }
+    }
+    smallest
+}
+
+/// Returns whether `operand` is a term other than a variable or a constant, looking through
+/// parentheses.
+fn is_compound(operand: &Formula) -> bool {
+    match operand {
+        Formula::Term(term) => is_compound_term(term),
+        _ => false,
+    }
+}
+
+/// Returns whether `term` is other than a variable or a constant, looking through parentheses.
+fn is_compound_term(mut term: &Term) -> bool {
+    while let Term::App(name, args) = term
+        && name.is_empty()
+        && args.len() == 1
+    {
+        term = &args[0];
+    }
+    matches!(term, Term::App(_, _))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EvalError, corpus};
+
+    #[test]
+    fn test_one_formula_per_fragment() {
+        let propositional: Formula = Formula::new("→ ∧ < y r ¬ = y 0 ∨ P ⊥");
+        assert_eq!(propositional.fragment(), Fragment::Propositional);
+        assert!(propositional.is_propositional());
+        assert!(propositional.is_quantifier_free());
+
+        let quantifier_free: Formula = Formula::new(corpus::FIBONACCI[4]);
+        assert_eq!(quantifier_free.fragment(), Fragment::QuantifierFree);
+        assert!(!quantifier_free.is_propositional());
+        assert!(quantifier_free.is_quantifier_free());
+
+        let first_order: Formula = Formula::new("∧ P ∀ x < x y");
+        assert_eq!(first_order.fragment(), Fragment::FirstOrder);
+        assert!(!first_order.is_propositional());
+        assert!(!first_order.is_quantifier_free());
+    }
+
+    #[test]
+    fn test_compound_operands() {
+        assert_eq!(Formula::new("< (x) 5").fragment(), Fragment::Propositional);
+        assert_eq!(
+            Formula::new("< (x+1) 5").fragment(),
+            Fragment::QuantifierFree
+        );
+        assert_eq!(Formula::new("≥ 0 -x").fragment(), Fragment::QuantifierFree);
+        assert_eq!(
+            Formula::new("∧ P(x) Q(f(x))").fragment(),
+            Fragment::Propositional
+        );
+        assert_eq!(
+            Formula::new("= P ∧ Q R").fragment(),
+            Fragment::Propositional
+        );
+        assert!(Fragment::Propositional < Fragment::QuantifierFree);
+        assert!(Fragment::QuantifierFree < Fragment::FirstOrder);
+    }
+
+    #[test]
+    fn test_clause_error_names_fragment() {
+        let test_formula: Formula = Formula::new("∧ P ∀ x Q(x)");
+        let error: EvalError = test_formula.is_satisfiable().unwrap_err();
+        assert_eq!(
+            error,
+            EvalError::NotQuantifierFree {
+                variable: "x".to_string()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "the formula quantifies over x, so it is {} rather than {}",
+                test_formula.fragment(),
+                Fragment::QuantifierFree
+            )
+        );
+        assert_eq!(
+            test_formula.truth_table().unwrap_err().to_string(),
+            error.to_string()
+        );
+    }
+}
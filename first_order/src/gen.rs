@@ -0,0 +1,509 @@
+//! # Random formulae
+//!
+//! This module provides [`random_formula`], which draws a random [`Formula`] shaped by a
+//! [`GenConfig`], for testing code that handles formulae against many more inputs than can be
+//! written by hand. With the `proptest` feature, `Formula` also implements
+//! `proptest::arbitrary::Arbitrary` with a [`GenConfig`] as its parameters, so that a failing
+//! formula is shrunk to a small counterexample.
+//!
+//! The terms of a generated formula are the configured names, numeric constants, applications
+//! such as `P(x,3)` and flat arithmetic such as `x+3`, which all survive a round trip through
+//! prefix notation.
+//!
+//! The module is named `gen`, which is a reserved keyword, so it is written `r#gen` in paths.
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::{Formula, FormulaKind, Term, atom, is_identifier};
+
+/// The connectives taking two subformulae, which are all those [`GenConfig::connectives`] accepts
+/// apart from `Negation`.
+const BINARY: [FormulaKind; 10] = [
+    FormulaKind::Conjunction,
+    FormulaKind::Disjunction,
+    FormulaKind::Implication,
+    FormulaKind::Equivalence,
+    FormulaKind::Iff,
+    FormulaKind::NotEqual,
+    FormulaKind::LessThan,
+    FormulaKind::LessEqual,
+    FormulaKind::GreaterEqual,
+    FormulaKind::GreaterThan,
+];
+
+/// The arithmetic operators used in generated terms.
+const OPERATORS: [&str; 3] = ["+", "-", "*"];
+
+/// The probability that a subformula above the maximum depth is a leaf anyway, so that generated
+/// formulae vary in shape.
+const LEAF_PROBABILITY: f64 = 0.2;
+
+/// Options for [`random_formula`].
+///
+/// # Example
+/// ```
+/// use first_order::FormulaKind;
+/// use first_order::r#gen::GenConfig;
+///
+/// let mut config: GenConfig = GenConfig::new();
+/// config
+///     .max_depth(6)
+///     .names(&["a", "b"])
+///     .quantifier_probability(0.0)
+///     .connectives(&[FormulaKind::Conjunction, FormulaKind::Negation]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenConfig {
+    max_depth: usize, // The largest number of connectives on a path from the root to a leaf.
+    names: Vec<String>, // The variables, propositions and function names terms are built from.
+    quantifier_probability: f64, // The probability that a subformula is a quantifier.
+    connectives: Vec<FormulaKind>, // The kinds of connective subformulae are built with.
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        let mut connectives: Vec<FormulaKind> = vec![FormulaKind::Negation];
+        connectives.extend(BINARY);
+        GenConfig {
+            max_depth: 4,
+            names: ["x", "y", "z", "P", "Q"].map(String::from).to_vec(),
+            quantifier_probability: 0.2,
+            connectives,
+        }
+    }
+}
+
+impl GenConfig {
+    /// Creates the default options: a depth of at most 4, the names `x`, `y`, `z`, `P` and `Q`,
+    /// a quantifier probability of 0.2, and every connective from `Negation` to `GreaterThan`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets the largest number of connectives and quantifiers on a path from the root of the
+    /// formula to a leaf. A depth of 0 only generates `⊤`, `⊥` and terms.
+    pub fn max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// Sets the names used as variables, propositions, function names and quantified variables.
+    ///
+    /// # Panics
+    /// Panics if a name is not a plain identifier, e.g. `x` or `index`, or is `true` or `false`.
+    pub fn names(&mut self, names: &[&str]) -> &mut Self {
+        for name in names {
+            assert!(
+                is_identifier(name),
+                "{name:?} cannot be used as a name in a generated formula"
+            );
+        }
+        self.names = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+    /// Sets the probability, from 0 to 1, that a subformula above the maximum depth is a
+    /// quantifier.
+    ///
+    /// # Panics
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn quantifier_probability(&mut self, probability: f64) -> &mut Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "the quantifier probability must be between 0 and 1"
+        );
+        self.quantifier_probability = probability;
+        self
+    }
+    /// Sets the connectives subformulae are built with, out of `Negation`, `Conjunction`,
+    /// `Disjunction`, `Implication`, `Equivalence`, `Iff`, `NotEqual`, `LessThan`, `LessEqual`,
+    /// `GreaterEqual` and `GreaterThan`. Quantifiers are controlled by
+    /// [`GenConfig::quantifier_probability`] instead.
+    ///
+    /// # Panics
+    /// Panics if `connectives` holds any other kind.
+    pub fn connectives(&mut self, connectives: &[FormulaKind]) -> &mut Self {
+        for kind in connectives {
+            assert!(
+                *kind == FormulaKind::Negation || BINARY.contains(kind),
+                "{kind} is not a connective random formulae can be built with"
+            );
+        }
+        self.connectives = connectives.to_vec();
+        self
+    }
+}
+
+/// Generates a random formula.
+///
+/// The formula is at most [`GenConfig::max_depth`] connectives and quantifiers deep. Below the
+/// maximum depth, a subformula is a quantifier with the configured probability, and otherwise
+/// usually one of the configured connectives. The leaves are `⊤`, `⊥` and terms built from the
+/// configured names.
+///
+/// # Arguments
+/// * `rng` - The source of randomness, e.g. a seeded `rand::rngs::StdRng` for reproducible
+///   formulae.
+/// * `config` - The shape of the formulae to generate.
+///
+/// # Returns
+/// A random `Formula`, which [`Formula::new`] parses back from its prefix notation.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use first_order::r#gen::{GenConfig, random_formula};
+/// use rand::SeedableRng;
+/// use rand::rngs::StdRng;
+///
+/// let mut rng: StdRng = StdRng::seed_from_u64(7);
+/// let formula: Formula = random_formula(&mut rng, &GenConfig::new());
+/// assert_eq!(Formula::new(&formula.to_prefix_notation()), formula);
+/// ```
+pub fn random_formula(rng: &mut impl Rng, config: &GenConfig) -> Formula {
+    atom::proposition(generate(rng, config, config.max_depth))
+}
+
+/// Generates a formula at most `depth` connectives deep.
+fn generate(rng: &mut impl Rng, config: &GenConfig, depth: usize) -> Formula {
+    if depth == 0 || rng.random_bool(LEAF_PROBABILITY) {
+        return random_leaf(rng, config);
+    }
+    if let Some(variable) = config.names.choose(rng)
+        && rng.random_bool(config.quantifier_probability)
+    {
+        let body: Box<Formula> = Box::new(atom::proposition(generate(rng, config, depth - 1)));
+        return if rng.random_bool(0.5) {
+            Formula::UniversalQuantifier(variable.clone(), body)
+        } else {
+            Formula::ExistentialQuantifier(variable.clone(), body)
+        };
+    }
+    let Some(kind) = config.connectives.choose(rng) else {
+        return random_leaf(rng, config);
+    };
+    if *kind == FormulaKind::Negation {
+        return Formula::Negation(Box::new(atom::proposition(generate(
+            rng,
+            config,
+            depth - 1,
+        ))));
+    }
+    let lhs: Box<Formula> = Box::new(generate(rng, config, depth - 1));
+    let rhs: Box<Formula> = Box::new(generate(rng, config, depth - 1));
+    binary(*kind, lhs, rhs)
+}
+
+/// Builds the binary connective of the given kind. A term operand of a connective other than a
+/// relation is read as a proposition, as the parser does.
+fn binary(kind: FormulaKind, lhs: Box<Formula>, rhs: Box<Formula>) -> Formula {
+    let (lhs, rhs): (Box<Formula>, Box<Formula>) = match kind {
+        FormulaKind::Conjunction
+        | FormulaKind::Disjunction
+        | FormulaKind::Implication
+        | FormulaKind::Iff => (
+            Box::new(atom::proposition(*lhs)),
+            Box::new(atom::proposition(*rhs)),
+        ),
+        _ => (lhs, rhs),
+    };
+    crate::kind::binary(kind, lhs, rhs)
+        .unwrap_or_else(|| unreachable!("{kind} is not a binary connective"))
+}
+
+/// Generates `⊤`, `⊥` or a term.
+fn random_leaf(rng: &mut impl Rng, config: &GenConfig) -> Formula {
+    match rng.random_range(0..8) {
+        0 => Formula::Top,
+        1 => Formula::Bottom,
+        _ => Formula::Term(random_term(rng, config)),
+    }
+}
+
+/// Generates a variable, a constant, an application of a name to one or two simple terms, or a
+/// simple term combined with a constant by an arithmetic operator.
+fn random_term(rng: &mut impl Rng, config: &GenConfig) -> Term {
+    let Some(name) = config.names.choose(rng) else {
+        return random_constant(rng);
+    };
+    match rng.random_range(0..6) {
+        0 => random_constant(rng),
+        1 => {
+            let arity: usize = rng.random_range(1..=2);
+            let arguments: Vec<Term> = (0..arity)
+                .map(|_| random_simple_term(rng, config))
+                .collect();
+            Term::App(name.clone(), arguments)
+        }
+        2 => {
+            let operator: &str = OPERATORS.choose(rng).unwrap();
+            Term::App(
+                operator.to_string(),
+                vec![random_simple_term(rng, config), random_constant(rng)],
+            )
+        }
+        _ => Term::Var(name.clone()),
+    }
+}
+
+/// Generates a variable or a constant.
+fn random_simple_term(rng: &mut impl Rng, config: &GenConfig) -> Term {
+    match config.names.choose(rng) {
+        Some(name) if rng.random_bool(0.7) => Term::Var(name.clone()),
+        _ => random_constant(rng),
+    }
+}
+
+/// Generates a constant from 0 to 9.
+fn random_constant(rng: &mut impl Rng) -> Term {
+    Term::IntLit(rng.random_range(0..10))
+}
+
+#[cfg(feature = "proptest")]
+mod arbitrary {
+    use proptest::prelude::*;
+    use proptest::strategy::Union;
+
+    use super::{BINARY, GenConfig, OPERATORS, binary};
+    use crate::{Formula, FormulaKind, Term, atom};
+
+    impl Arbitrary for Formula {
+        type Parameters = GenConfig;
+        type Strategy = BoxedStrategy<Formula>;
+
+        /// Generates formulae shaped like those of [`super::random_formula`], which shrink
+        /// towards their subformulae and leaves.
+        fn arbitrary_with(config: GenConfig) -> Self::Strategy {
+            let leaf: BoxedStrategy<Formula> = prop_oneof![
+                1 => Just(Formula::Top),
+                1 => Just(Formula::Bottom),
+                6 => term(&config.names).prop_map(Formula::Term),
+            ]
+            .boxed();
+            if config.connectives.is_empty() && config.names.is_empty() {
+                return leaf.prop_map(atom::proposition).boxed();
+            }
+            let depth: u32 = config.max_depth as u32;
+            leaf.prop_recursive(depth, 256, 2, move |inner| {
+                let quantifier_weight: u32 = (config.quantifier_probability * 100.0) as u32;
+                let connective_weight: u32 = 100 - quantifier_weight;
+                let mut options: Vec<(u32, BoxedStrategy<Formula>)> = Vec::new();
+                for kind in config.connectives.iter().copied() {
+                    let strategy: BoxedStrategy<Formula> = if kind == FormulaKind::Negation {
+                        inner
+                            .clone()
+                            .prop_map(|formula| {
+                                Formula::Negation(Box::new(atom::proposition(formula)))
+                            })
+                            .boxed()
+                    } else {
+                        debug_assert!(BINARY.contains(&kind));
+                        (inner.clone(), inner.clone())
+                            .prop_map(move |(lhs, rhs)| binary(kind, Box::new(lhs), Box::new(rhs)))
+                            .boxed()
+                    };
+                    options.push((connective_weight.max(1), strategy));
+                }
+                if !config.names.is_empty() && quantifier_weight > 0 {
+                    let strategy: BoxedStrategy<Formula> = (
+                        any::<bool>(),
+                        proptest::sample::select(config.names.clone()),
+                        inner.clone(),
+                    )
+                        .prop_map(|(universal, variable, body)| {
+                            let body: Formula = atom::proposition(body);
+                            if universal {
+                                Formula::UniversalQuantifier(variable, Box::new(body))
+                            } else {
+                                Formula::ExistentialQuantifier(variable, Box::new(body))
+                            }
+                        })
+                        .boxed();
+                    options.push((
+                        quantifier_weight * config.connectives.len().max(1) as u32,
+                        strategy,
+                    ));
+                }
+                if options.is_empty() {
+                    return inner;
+                }
+                Union::new_weighted(options).boxed()
+            })
+            .prop_map(atom::proposition)
+            .boxed()
+        }
+    }
+
+    /// Generates terms like those of [`super::random_term`].
+    fn term(names: &[String]) -> BoxedStrategy<Term> {
+        let constant: BoxedStrategy<Term> = (0..10u8)
+            .prop_map(|digit| Term::IntLit(i64::from(digit)))
+            .boxed();
+        if names.is_empty() {
+            return constant;
+        }
+        let name: BoxedStrategy<String> = proptest::sample::select(names.to_vec()).boxed();
+        let simple: BoxedStrategy<Term> = prop_oneof![
+            7 => name.clone().prop_map(Term::Var),
+            3 => constant.clone(),
+        ]
+        .boxed();
+        prop_oneof![
+            3 => name.clone().prop_map(Term::Var),
+            1 => constant.clone(),
+            1 => (name, proptest::collection::vec(simple.clone(), 1..=2))
+                .prop_map(|(name, arguments)| Term::App(name, arguments)),
+            1 => (proptest::sample::select(OPERATORS.to_vec()), simple, constant)
+                .prop_map(|(operator, lhs, rhs)| Term::App(operator.to_string(), vec![lhs, rhs])),
+        ]
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    /// The number of formulae each property is checked against.
+    const SAMPLES: u64 = 1000;
+
+    /// Generates the formula for the given seed with the default options.
+    fn sample(seed: u64) -> Formula {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        random_formula(&mut rng, &GenConfig::new())
+    }
+
+    #[test]
+    fn test_prefix_notation_round_trips() {
+        for seed in 0..SAMPLES {
+            let formula: Formula = sample(seed);
+            let prefix: String = formula.to_prefix_notation();
+            assert_eq!(Formula::new(&prefix), formula, "seed {seed}: {prefix}");
+        }
+    }
+
+    #[test]
+    fn test_infix_notation_has_balanced_parentheses() {
+        for seed in 0..SAMPLES {
+            let infix: String = sample(seed).to_infix_notation();
+            let mut open: usize = 0;
+            for c in infix.chars() {
+                match c {
+                    '(' => open += 1,
+                    ')' => {
+                        assert!(open > 0, "seed {seed}: {infix}");
+                        open -= 1;
+                    }
+                    _ => {}
+                }
+            }
+            assert_eq!(open, 0, "seed {seed}: {infix}");
+        }
+    }
+
+    #[test]
+    fn test_get_info_reconstructs_children() {
+        for seed in 0..SAMPLES {
+            let formula: Formula = sample(seed);
+            let info: [String; 3] = formula.get_info();
+            assert_eq!(info[0], formula.kind().to_string());
+            match &formula {
+                Formula::Term(term) => assert_eq!(info[1], term.to_string()),
+                Formula::Predicate(_, _) => assert_eq!(info[1], formula.to_prefix_notation()),
+                Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+                    assert_eq!([&info[1], &info[2]], [&lhs.to_string(), &rhs.to_string()]);
+                }
+                Formula::UniversalQuantifier(variable, body)
+                | Formula::ExistentialQuantifier(variable, body) => {
+                    assert_eq!(&info[1], variable);
+                    assert_eq!(Formula::new(&info[2]), **body, "seed {seed}");
+                }
+                _ => {
+                    let children: Vec<Formula> = [&info[1], &info[2]]
+                        .into_iter()
+                        .filter(|argument| !argument.is_empty())
+                        .map(Formula::new)
+                        .collect();
+                    // A term standing alone is read as a proposition.
+                    let expected: Vec<Formula> = formula
+                        .children()
+                        .into_iter()
+                        .cloned()
+                        .map(atom::proposition)
+                        .collect();
+                    assert_eq!(children, expected, "seed {seed}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_count_matches_subformulae() {
+        for seed in 0..SAMPLES {
+            let formula: Formula = sample(seed);
+            assert_eq!(
+                formula.node_count(),
+                formula.subformulae().count(),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_shapes_formulae() {
+        let mut config: GenConfig = GenConfig::new();
+        config
+            .max_depth(3)
+            .names(&["a"])
+            .quantifier_probability(0.0)
+            .connectives(&[FormulaKind::Conjunction]);
+        for seed in 0..100 {
+            let mut rng: StdRng = StdRng::seed_from_u64(seed);
+            let formula: Formula = random_formula(&mut rng, &config);
+            assert!(formula.depth() <= 4);
+            for subformula in formula.subformulae() {
+                assert!(matches!(
+                    subformula.kind(),
+                    FormulaKind::Conjunction
+                        | FormulaKind::Term
+                        | FormulaKind::Predicate
+                        | FormulaKind::Top
+                        | FormulaKind::Bottom
+                ));
+            }
+            assert!(formula.free_variables().iter().all(|name| name == "a"));
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_formula() {
+        assert_eq!(sample(42), sample(42));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be used as a name")]
+    fn test_invalid_name_panics() {
+        GenConfig::new().names(&["x y"]);
+    }
+
+    #[cfg(feature = "proptest")]
+    mod properties {
+        use proptest::prelude::*;
+
+        use crate::Formula;
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(1000))]
+
+            #[test]
+            fn prefix_notation_round_trips(formula: Formula) {
+                prop_assert_eq!(Formula::new(formula.to_prefix_notation()), formula);
+            }
+
+            #[test]
+            fn node_count_matches_subformulae(formula: Formula) {
+                prop_assert_eq!(formula.node_count(), formula.subformulae().count());
+            }
+        }
+    }
+}
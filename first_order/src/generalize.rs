@@ -0,0 +1,142 @@
+//! # Generalization
+//!
+//! This module abstracts concrete terms of a formula into fresh variables, for
+//! [`Formula::generalize`] and [`Formula::generalize_with`], turning a proved lemma about
+//! particular values into a schema. Generalizing `43` in `→ = x 43 ∨ < x 44 = x 44` gives
+//! `→ = x v1 ∨ < x 44 = x 44`.
+//!
+//! Targets are matched against whole subterms of the term trees, so `43` is replaced in
+//! `fib(43)+1` but not in `143`, and `x+1` is replaced in `fib(x+1)` but not in `x+(1+y)`.
+//! Quantifier variables are kept as they are. The fresh variables are `v1`, `v2`, ..., numbered
+//! in the order of the targets and skipping every name already used in the formula.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Formula, Term, variables};
+
+/// Replaces every occurrence of each of `targets` in `formula` with a fresh variable, and wraps
+/// the result in universal quantifiers over the fresh variables if `quantify` is set. See
+/// [`Formula::generalize_with`].
+pub(crate) fn generalize(
+    formula: &Formula,
+    targets: &[&str],
+    quantify: bool,
+) -> (Formula, BTreeMap<String, String>) {
+    let mut taken: BTreeSet<String> = variables::free(formula);
+    taken.extend(variables::bound(formula));
+    let targets: Vec<(String, Term)> = targets
+        .iter()
+        .map(|target| (target.to_string(), Term::new(target)))
+        .collect();
+    // Only the targets that occur are given a fresh variable.
+    let mut found: BTreeSet<&str> = BTreeSet::new();
+    formula.map_term_trees(|term| {
+        replace(term, &mut |subterm| {
+            let (target, _) = targets.iter().find(|(_, target)| target == subterm)?;
+            found.insert(target);
+            None
+        })
+    });
+    let mut mapping: BTreeMap<String, String> = BTreeMap::new();
+    let mut fresh: Vec<String> = Vec::new();
+    let mut names = (1..).map(|suffix: usize| format!("v{suffix}"));
+    for (target, _) in &targets {
+        if found.contains(target.as_str()) && !mapping.contains_key(target) {
+            let name: String = names
+                .by_ref()
+                .find(|name| !taken.contains(name))
+                .expect("there are infinitely many names");
+            mapping.insert(target.clone(), name.clone());
+            fresh.push(name);
+        }
+    }
+    let generalized: Formula = formula.map_term_trees(|term| {
+        replace(term, &mut |subterm| {
+            let (target, _) = targets.iter().find(|(_, target)| target == subterm)?;
+            Some(Term::Var(mapping[target].clone()))
+        })
+    });
+    if !quantify {
+        return (generalized, mapping);
+    }
+    // The variable of the first target is bound outermost.
+    let quantified: Formula = fresh
+        .into_iter()
+        .rev()
+        .fold(generalized, |body, name| body.forall(name));
+    (quantified, mapping)
+}
+
+/// Rebuilds `term`, replacing every outermost subterm for which `f` returns a replacement.
+fn replace(term: &Term, f: &mut impl FnMut(&Term) -> Option<Term>) -> Term {
+    if let Some(replacement) = f(term) {
+        return replacement;
+    }
+    match term {
+        Term::Var(_) | Term::IntLit(_) | Term::Const(_) => term.clone(),
+        Term::App(name, args) => Term::App(
+            name.clone(),
+            args.iter().map(|arg| replace(arg, f)).collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiple_occurrences() {
+        let test_formula: Formula = Formula::new("→ = x 43 ∨ < x 44 = fib(43) 43+1");
+        let (generalized, mapping) = test_formula.generalize(&["43"]);
+        assert_eq!(
+            generalized,
+            Formula::new("→ = x v1 ∨ < x 44 = fib(v1) v1+1")
+        );
+        assert_eq!(
+            mapping,
+            BTreeMap::from([("43".to_string(), "v1".to_string())])
+        );
+        // Several targets, including a compound term, get distinct fresh variables that avoid
+        // the names already in the formula.
+        let test_formula: Formula = Formula::new("∧ = v1 fib(n+1) ∀ v2 < 43 n+1");
+        let (generalized, mapping) = test_formula.generalize(&["n+1", "43"]);
+        assert_eq!(generalized, Formula::new("∧ = v1 fib(v3) ∀ v2 < v4 v3"));
+        assert_eq!(
+            mapping,
+            BTreeMap::from([
+                ("43".to_string(), "v4".to_string()),
+                ("n+1".to_string(), "v3".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_quantified() {
+        let test_formula: Formula = Formula::new("→ = x 43 ∨ < x 44 = x 44");
+        let (generalized, mapping) = test_formula.generalize_with(&["44", "43"], true);
+        assert_eq!(
+            generalized,
+            Formula::new("∀ v1 ∀ v2 → = x v2 ∨ < x v1 = x v1")
+        );
+        assert_eq!(mapping["44"], "v1");
+        assert_eq!(mapping["43"], "v2");
+        assert!(generalized.free_variables().contains("x"));
+        assert!(!generalized.free_variables().contains("v1"));
+        // A target that does not occur is neither mapped nor quantified.
+        let (generalized, mapping) = test_formula.generalize_with(&["7"], true);
+        assert_eq!(generalized, test_formula);
+        assert!(mapping.is_empty());
+    }
+
+    #[test]
+    fn test_embedded_digits_do_not_match() {
+        let test_formula: Formula = Formula::new("∧ = x 143 < a43 430");
+        let (generalized, mapping) = test_formula.generalize(&["43"]);
+        assert_eq!(generalized, test_formula);
+        assert!(mapping.is_empty());
+        // Only whole subterms match, so `x+1` is not found in `x+(1+y)`.
+        let test_formula: Formula = Formula::new("= x+(1+y) (x+1)+y");
+        let (generalized, _) = test_formula.generalize(&["x+1"]);
+        assert_eq!(generalized, Formula::new("= x+(1+y) (v1)+y"));
+    }
+}
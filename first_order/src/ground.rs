@@ -0,0 +1,181 @@
+//! # Ground instances
+//!
+//! This module expands the quantifiers of a formula over a finite domain, for
+//! [`Formula::ground_instances`] and [`Formula::ground_instances_with`]. Over the domain
+//! `{0, 1}`:
+//!
+//! | Formula       | Ground instances       |
+//! |---------------|------------------------|
+//! | `∀ x P(x)`    | `∧ P(0) P(1)`          |
+//! | `∃ x P(x)`    | `∨ P(0) P(1)`          |
+//!
+//! The body of a quantifier is expanded first, and each domain element is then substituted for
+//! the variable like [`Formula::apply`] does, so the result is quantifier-free and can be passed
+//! to [`Formula::holds_in`] or [`Formula::is_satisfiable`]. Each quantifier multiplies the size of
+//! its body by the size of the domain, so the size of the result is computed before it is built,
+//! and rejected if it exceeds a limit.
+use std::fmt;
+
+use crate::{Formula, Substitution, normal_form, unify};
+
+/// The largest number of nodes [`Formula::ground_instances`] builds.
+pub(crate) const DEFAULT_LIMIT: usize = 100_000;
+
+/// An error returned by [`Formula::ground_instances`] and [`Formula::ground_instances_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroundError {
+    /// The ground instances would have `nodes` nodes, more than `limit`. The count saturates at
+    /// `usize::MAX`.
+    TooLarge { nodes: usize, limit: usize },
+}
+
+impl fmt::Display for GroundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroundError::TooLarge { nodes, limit } => write!(
+                f,
+                "the ground instances would have {nodes} nodes, more than the limit of {limit}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GroundError {}
+
+/// Expands the quantifiers of `formula` over `domain`, building at most `limit` nodes. See
+/// [`Formula::ground_instances_with`].
+pub(crate) fn ground_instances(
+    formula: &Formula,
+    domain: &[&str],
+    limit: usize,
+) -> Result<Formula, GroundError> {
+    let nodes: usize = size(formula, domain.len());
+    if nodes > limit {
+        return Err(GroundError::TooLarge { nodes, limit });
+    }
+    Ok(expand(formula, domain))
+}
+
+/// Returns the number of nodes of the ground instances of `formula` over a domain of `elements`
+/// elements.
+fn size(formula: &Formula, elements: usize) -> usize {
+    match formula {
+        Formula::UniversalQuantifier(_, inner) | Formula::ExistentialQuantifier(_, inner) => {
+            // The instances are joined by one connective fewer than there are instances, and an
+            // empty domain leaves a single truth constant.
+            let instances: usize = size(inner, elements).saturating_mul(elements);
+            instances.saturating_add(elements.saturating_sub(1)).max(1)
+        }
+        _ => formula
+            .children()
+            .into_iter()
+            .fold(1, |nodes: usize, child| {
+                nodes.saturating_add(size(child, elements))
+            }),
+    }
+}
+
+/// Expands the quantifiers of `formula` over `domain`, innermost first.
+fn expand(formula: &Formula, domain: &[&str]) -> Formula {
+    let (variable, inner, universal): (&str, &Formula, bool) = match formula {
+        Formula::UniversalQuantifier(variable, inner) => (variable, inner, true),
+        Formula::ExistentialQuantifier(variable, inner) => (variable, inner, false),
+        _ => return normal_form::map_operands(formula, |operand| expand(operand, domain)),
+    };
+    let body: Formula = expand(inner, domain);
+    let mut instances = domain.iter().map(|element| {
+        let mut substitution: Substitution = Substitution::new();
+        substitution.bind(variable, Formula::term(*element));
+        unify::apply(&body, &substitution)
+    });
+    let Some(first) = instances.next() else {
+        // An empty conjunction holds, and an empty disjunction does not.
+        return if universal {
+            Formula::Top
+        } else {
+            Formula::Bottom
+        };
+    };
+    instances.fold(first, |joined, instance| {
+        if universal {
+            joined.and(instance)
+        } else {
+            joined.or(instance)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::Model;
+
+    #[test]
+    fn test_nested_quantifiers() {
+        let test_formula: Formula = Formula::new("∀ x ∃ y < x y");
+        let ground: Formula = test_formula.ground_instances(&["0", "1"]).unwrap();
+        assert_eq!(ground, Formula::new("∧ ∨ < 0 0 < 0 1 ∨ < 1 0 < 1 1"));
+        assert_eq!(ground.quantifier_rank(), 0);
+        let model: Model = Model::new(vec![0, 1]);
+        assert_eq!(ground.holds_in(&model, &HashMap::new()), Ok(false));
+        assert_eq!(test_formula.holds_in(&model, &HashMap::new()), Ok(false));
+        let ground: Formula = test_formula.ground_instances(&["0", "1", "2"]).unwrap();
+        assert_eq!(ground.node_count(), 3 * (3 + 2) + 2);
+    }
+
+    #[test]
+    fn test_terms_and_shadowing() {
+        let test_formula: Formula = Formula::new("∧ ∀ i = fib(i) fib(i+1) ∃ i ∀ i P(i)");
+        assert_eq!(
+            test_formula.ground_instances(&["a", "b"]),
+            Ok(Formula::new(
+                "∧ ∧ = fib(a) fib(a+1) = fib(b) fib(b+1) ∨ ∧ P(a) P(b) ∧ P(a) P(b)"
+            ))
+        );
+        // Free variables and quantifier-free formulae are left alone.
+        assert_eq!(
+            Formula::new("→ P(x) Q").ground_instances(&["0"]),
+            Ok(Formula::new("→ P(x) Q"))
+        );
+    }
+
+    #[test]
+    fn test_empty_domain() {
+        assert_eq!(
+            Formula::new("∧ ∀ x P(x) ¬ ∃ x P(x)").ground_instances(&[]),
+            Ok(Formula::new("∧ ⊤ ¬ ⊥"))
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let test_formula: Formula = Formula::new("∀ x ∃ y < x y");
+        assert_eq!(
+            test_formula.ground_instances_with(&["0", "1"], 6),
+            Err(GroundError::TooLarge { nodes: 7, limit: 6 })
+        );
+        assert!(test_formula.ground_instances_with(&["0", "1"], 7).is_ok());
+        let mut deep: Formula = Formula::new("P(x)");
+        for _ in 0..40 {
+            deep = Formula::UniversalQuantifier("x".to_string(), Box::new(deep));
+        }
+        let error: GroundError = deep.ground_instances(&["0", "1", "2"]).unwrap_err();
+        assert_eq!(
+            error,
+            GroundError::TooLarge {
+                nodes: usize::MAX,
+                limit: DEFAULT_LIMIT
+            }
+        );
+        assert_eq!(
+            GroundError::TooLarge {
+                nodes: 11,
+                limit: 10
+            }
+            .to_string(),
+            "the ground instances would have 11 nodes, more than the limit of 10"
+        );
+    }
+}
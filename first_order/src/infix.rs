@@ -0,0 +1,631 @@
+//! # Infix notation parser
+//!
+//! This module parses formulae written in infix notation, such as the output of
+//! [`Formula::to_infix_notation`], into a `Formula`.
+//!
+//! Unlike the prefix notation, infix input does not require whitespace between tokens. A pair of
+//! parentheses is treated as grouping when it encloses a connective, relation, or quantifier, and
+//! as part of a term otherwise, so terms such as `fib(index+1)` or `(count-1)` are kept intact.
+//!
+//! A quantifier may be bounded, as in `∀i < n (P(i) → Q(i))`, which is read like the bounded
+//! quantifiers of [`Formula::as_bounded_quantifier`].
+use crate::{Formula, ParseError, ParseErrorKind, Quantifier, atom, bounded};
+
+/// Characters that form a connective, relation, or quantifier token on their own.
+pub(crate) const SYMBOL_CHARS: [char; 13] = [
+    '¬', '∧', '∨', '→', '↔', '=', '≠', '<', '≤', '≥', '>', '∀', '∃',
+];
+
+/// Parses `input` in infix notation. See [`Formula::from_infix`].
+pub(crate) fn parse(input: &str) -> Result<Formula, ParseError> {
+    let tokens: Vec<String> = tokenize(input);
+    let mut parser: InfixParser<'_> = InfixParser {
+        tokens: &tokens,
+        current: 0,
+    };
+    parser.parse()
+}
+
+/// Returns the index of the parenthesis closing the one at `open`, if any.
+fn matching_parenthesis(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth: usize = 0;
+    for (index, c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns `true` if the parenthesis at `open` encloses part of a term rather than a formula.
+fn is_term_group(chars: &[char], open: usize) -> bool {
+    match matching_parenthesis(chars, open) {
+        Some(close) => !chars[open + 1..close]
+            .iter()
+            .any(|c| SYMBOL_CHARS.contains(c)),
+        None => false,
+    }
+}
+
+/// Splits an infix input into tokens.
+///
+/// The variable following a quantifier is read as its own token, and a parenthesis directly after
+/// it always opens the quantifier body, so `∀x(P(x))` splits into `∀`, `x`, `(`, `P(x)`, `)`. The
+/// same holds for a parenthesis after the bound of a bounded quantifier, as in `∀x < n (P(x))`.
+fn tokenize(input: &str) -> Vec<String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<String> = vec![];
+    let mut index: usize = 0;
+    let mut expect_variable: bool = false;
+    let mut force_group: bool = false;
+    // Whether a `<` would bound the quantifier whose variable was just read, and whether it did.
+    let (mut expect_bound, mut in_bound): (bool, bool) = (false, false);
+
+    while index < chars.len() {
+        let c: char = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        if expect_variable {
+            expect_variable = false;
+            let start: usize = index;
+            while index < chars.len()
+                && !chars[index].is_whitespace()
+                && !SYMBOL_CHARS.contains(&chars[index])
+                && chars[index] != '('
+                && chars[index] != ')'
+            {
+                index += 1;
+            }
+            if index > start {
+                tokens.push(chars[start..index].iter().collect());
+                force_group = true;
+                expect_bound = true;
+            }
+            continue;
+        }
+        if SYMBOL_CHARS.contains(&c) {
+            tokens.push(c.to_string());
+            in_bound = expect_bound && c == '<';
+            expect_bound = false;
+            expect_variable = c == '∀' || c == '∃';
+            force_group = false;
+            index += 1;
+            continue;
+        }
+        expect_bound = false;
+        if c == ')' || (c == '(' && (force_group || !is_term_group(&chars, index))) {
+            tokens.push(c.to_string());
+            (force_group, in_bound) = (false, false);
+            index += 1;
+            continue;
+        }
+
+        let start: usize = index;
+        while index < chars.len() {
+            let c: char = chars[index];
+            if c.is_whitespace() || SYMBOL_CHARS.contains(&c) || c == ')' {
+                break;
+            }
+            if c == '(' {
+                if !is_term_group(&chars, index) {
+                    break;
+                }
+                index = matching_parenthesis(&chars, index).unwrap_or(index) + 1;
+                continue;
+            }
+            index += 1;
+        }
+        tokens.push(chars[start..index].iter().collect());
+        force_group = std::mem::take(&mut in_bound);
+    }
+    tokens
+}
+
+/// A recursive descent parser over infix tokens.
+///
+/// From the loosest to the tightest binding, the grammar levels are `=` and `↔`, `→`, `∨`, `∧`, the unary
+/// `¬`/`∀`/`∃`, and the `≠`, `<`, `≤`, `≥`, `>` relations between terms.
+///
+/// Each level returns a term as it is, and a connective reads its term operands as propositions,
+/// so that the operands of `=` and the relations stay terms as in the prefix parser.
+struct InfixParser<'a> {
+    tokens: &'a [String], // A slice of tokens representing the logical formula.
+    current: usize,       // The current index in the token slice.
+}
+
+impl InfixParser<'_> {
+    fn parse(&mut self) -> Result<Formula, ParseError> {
+        let formula = self.parse_equivalence()?;
+        if self.current != self.tokens.len() {
+            return Err(ParseError {
+                kind: ParseErrorKind::TrailingTokens,
+                ..self.error("end of input".to_string())
+            });
+        }
+        Ok(atom::proposition(formula))
+    }
+
+    /// Builds a `ParseError` located at the current token.
+    fn error(&self, expected: String) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::Malformed,
+            index: self.current,
+            found: self.tokens.get(self.current).cloned(),
+            expected,
+            line: None,
+        }
+    }
+
+    /// Consumes the current token if it is `symbol`.
+    fn eat(&mut self, symbol: &str) -> bool {
+        if self.tokens.get(self.current).map(String::as_str) == Some(symbol) {
+            self.current += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_equivalence(&mut self) -> Result<Formula, ParseError> {
+        let mut left = self.parse_implication()?;
+        loop {
+            left = if self.eat("=") {
+                let right = self.parse_implication()?;
+                atom::equality(Box::new(left), Box::new(right))
+            } else if self.eat("↔") {
+                let right = self.parse_implication()?;
+                Formula::Iff(proposition(left), proposition(right))
+            } else {
+                return Ok(left);
+            };
+        }
+    }
+
+    fn parse_implication(&mut self) -> Result<Formula, ParseError> {
+        let left = self.parse_disjunction()?;
+        if self.eat("→") {
+            // Implication is right-associative, so the right operand may itself be an implication.
+            let right = self.parse_implication()?;
+            return Ok(Formula::Implication(proposition(left), proposition(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_disjunction(&mut self) -> Result<Formula, ParseError> {
+        let mut left = self.parse_conjunction()?;
+        while self.eat("∨") {
+            let right = self.parse_conjunction()?;
+            left = Formula::Disjunction(proposition(left), proposition(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_conjunction(&mut self) -> Result<Formula, ParseError> {
+        let mut left = self.parse_unary()?;
+        while self.eat("∧") {
+            let right = self.parse_unary()?;
+            left = Formula::Conjunction(proposition(left), proposition(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Formula, ParseError> {
+        if self.eat("¬") {
+            let inner = self.parse_unary()?;
+            return Ok(Formula::Negation(proposition(inner)));
+        }
+        // A quantifier with a parenthesised body, `∀x(φ)`, is an atom and is parsed below.
+        let grouped: bool = self.tokens.get(self.current + 2).map(String::as_str) == Some("(");
+        for quantifier in ["∀", "∃"] {
+            if !grouped && self.eat(quantifier) {
+                let variable = self.parse_variable(quantifier)?;
+                if self.eat("<") {
+                    let bound = self.parse_bound()?;
+                    let inner = self.parse_unary()?;
+                    let quantifier: Quantifier = if quantifier == "∀" {
+                        Quantifier::Universal
+                    } else {
+                        Quantifier::Existential
+                    };
+                    return Ok(bounded::desugar(quantifier, variable, bound, inner));
+                }
+                let inner = self.parse_unary()?;
+                return Ok(quantified(quantifier, variable, inner));
+            }
+        }
+        self.parse_relation()
+    }
+
+    fn parse_relation(&mut self) -> Result<Formula, ParseError> {
+        let left = self.parse_atom()?;
+        let relation: fn(Box<Formula>, Box<Formula>) -> Formula =
+            match self.tokens.get(self.current).map(String::as_str) {
+                Some("≠") => Formula::NotEqual,
+                Some("<") => atom::less_than,
+                Some("≤") => Formula::LessEqual,
+                Some("≥") => Formula::GreaterEqual,
+                Some(">") => Formula::GreaterThan,
+                _ => return Ok(left),
+            };
+        self.current += 1;
+        let right = self.parse_atom()?;
+        Ok(relation(Box::new(left), Box::new(right)))
+    }
+
+    fn parse_atom(&mut self) -> Result<Formula, ParseError> {
+        if self.eat("(") {
+            let inner = self.parse_equivalence()?;
+            if !self.eat(")") {
+                return Err(self.error("a closing parenthesis".to_string()));
+            }
+            return Ok(inner);
+        }
+        for quantifier in ["∀", "∃"] {
+            if self.eat(quantifier) {
+                let variable = self.parse_variable(quantifier)?;
+                if self.tokens.get(self.current).map(String::as_str) != Some("(") {
+                    return Err(self.error(format!("a parenthesised formula after {quantifier}")));
+                }
+                let inner = self.parse_atom()?;
+                return Ok(quantified(quantifier, variable, inner));
+            }
+        }
+        match self.tokens.get(self.current) {
+            Some(token) if !is_symbol(token) => {
+                let atom = match token.as_str() {
+                    "⊤" => Formula::Top,
+                    "⊥" => Formula::Bottom,
+                    _ => crate::parse_term(token, self.current)?,
+                };
+                self.current += 1;
+                Ok(atom)
+            }
+            _ => Err(self.error("a formula".to_string())),
+        }
+    }
+
+    /// Parses the term bounding a bounded quantifier.
+    fn parse_bound(&mut self) -> Result<Formula, ParseError> {
+        match self.tokens.get(self.current) {
+            Some(token) if !is_symbol(token) && token != "⊤" && token != "⊥" => {
+                let bound = crate::parse_term(token, self.current)?;
+                self.current += 1;
+                Ok(bound)
+            }
+            _ => Err(self.error("a bound after <".to_string())),
+        }
+    }
+
+    /// Parses the variable bound by the quantifier `quantifier`.
+    fn parse_variable(&mut self, quantifier: &str) -> Result<String, ParseError> {
+        match self.tokens.get(self.current) {
+            Some(token) if !is_symbol(token) => {
+                self.current += 1;
+                Ok(token.clone())
+            }
+            _ => Err(self.error(format!("a variable after {quantifier}"))),
+        }
+    }
+}
+
+/// Builds the quantifier `quantifier` binding `variable` in `inner`.
+fn quantified(quantifier: &str, variable: String, inner: Formula) -> Formula {
+    match quantifier {
+        "∀" => Formula::UniversalQuantifier(variable, proposition(inner)),
+        _ => Formula::ExistentialQuantifier(variable, proposition(inner)),
+    }
+}
+
+/// Boxes the operand `formula` of a connective, reading a term as a proposition.
+fn proposition(formula: Formula) -> Box<Formula> {
+    Box::new(atom::proposition(formula))
+}
+
+/// Returns `true` if `token` is a connective, relation, quantifier, or grouping parenthesis.
+fn is_symbol(token: &str) -> bool {
+    token == "(" || token == ")" || token.chars().all(|c| SYMBOL_CHARS.contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Formula, ParseErrorKind, corpus};
+
+    #[test]
+    fn test_round_trip_doc_example() {
+        let formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(
+            Formula::from_infix(&formula.to_infix_notation()),
+            Ok(formula)
+        );
+    }
+
+    #[test]
+    fn test_round_trip_paper_example() {
+        for input in corpus::PAPER_EXAMPLE {
+            let formula = Formula::new(input);
+            assert_eq!(
+                Formula::from_infix(&formula.to_infix_notation()),
+                Ok(formula),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_fibonacci() {
+        for input in corpus::FIBONACCI {
+            let formula = Formula::new(input);
+            assert_eq!(
+                Formula::from_infix(&formula.to_infix_notation()),
+                Ok(formula),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_all_examples() {
+        for input in corpus::all() {
+            let formula = Formula::new(input);
+            assert_eq!(
+                Formula::from_infix(&formula.to_infix_notation()),
+                Ok(formula),
+                "{input}"
+            );
+        }
+    }
+
+    /// Returns `formula` and all of its subformulae.
+    fn subformulae(formula: &Formula) -> Vec<Formula> {
+        let mut result: Vec<Formula> = vec![formula.clone()];
+        match formula {
+            Formula::Term(_)
+            | Formula::Equality(_, _)
+            | Formula::Less(_, _)
+            | Formula::Predicate(_, _)
+            | Formula::Top
+            | Formula::Bottom => {}
+            Formula::Negation(inner)
+            | Formula::UniversalQuantifier(_, inner)
+            | Formula::ExistentialQuantifier(_, inner) => result.extend(subformulae(inner)),
+            Formula::Conjunction(lhs, rhs)
+            | Formula::Disjunction(lhs, rhs)
+            | Formula::Implication(lhs, rhs)
+            | Formula::Equivalence(lhs, rhs)
+            | Formula::Iff(lhs, rhs)
+            | Formula::NotEqual(lhs, rhs)
+            | Formula::LessThan(lhs, rhs)
+            | Formula::LessEqual(lhs, rhs)
+            | Formula::GreaterEqual(lhs, rhs)
+            | Formula::GreaterThan(lhs, rhs)
+            | Formula::Custom(_, lhs, rhs) => {
+                result.extend(subformulae(lhs));
+                result.extend(subformulae(rhs));
+            }
+        }
+        result
+    }
+
+    type Connective = fn(Box<Formula>, Box<Formula>) -> Formula;
+
+    /// Wraps `inner` in every unary and binary variant, using `other` as the second operand. Each
+    /// wrapping is read back from its prefix notation, so that it has the shape the parser builds.
+    fn wrappings(inner: &Formula, other: &Formula) -> Vec<Formula> {
+        let binary: [Connective; 10] = [
+            Formula::Conjunction,
+            Formula::Disjunction,
+            Formula::Implication,
+            Formula::Equivalence,
+            Formula::Iff,
+            Formula::NotEqual,
+            Formula::LessThan,
+            Formula::LessEqual,
+            Formula::GreaterEqual,
+            Formula::GreaterThan,
+        ];
+        let mut result: Vec<Formula> = vec![
+            Formula::Negation(Box::new(inner.clone())),
+            Formula::UniversalQuantifier("x".to_string(), Box::new(inner.clone())),
+            Formula::ExistentialQuantifier("y".to_string(), Box::new(inner.clone())),
+        ];
+        for connective in binary {
+            result.push(connective(Box::new(inner.clone()), Box::new(other.clone())));
+            result.push(connective(Box::new(other.clone()), Box::new(inner.clone())));
+        }
+        result
+            .iter()
+            .map(|formula| Formula::new(formula.to_prefix_notation()))
+            .collect()
+    }
+
+    #[test]
+    fn test_roundtrip_infix_every_subformula_of_the_examples() {
+        for input in corpus::all() {
+            for formula in subformulae(&Formula::new(input)) {
+                assert_eq!(formula.roundtrip_infix(), Ok(formula.clone()), "{formula}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_infix_every_nesting_of_variants() {
+        let leaves: Vec<Formula> = vec![
+            Formula::new("P(x)"),
+            Formula::new("(count-1)"),
+            Formula::Top,
+            Formula::Bottom,
+        ];
+        let mut formulae: Vec<Formula> = leaves.clone();
+        for leaf in &leaves {
+            formulae.extend(wrappings(leaf, &Formula::new("fib(index+1)")));
+        }
+        for formula in formulae.clone() {
+            formulae.extend(wrappings(&formula, &Formula::new("= x x+y*0")));
+        }
+        for formula in formulae {
+            assert_eq!(formula.roundtrip_infix(), Ok(formula.clone()), "{formula}");
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_infix_relational_symbol_in_a_name() {
+        // `x=y` is not a term, so it cannot be mistaken for an equality after the round trip.
+        assert!(Formula::try_new("x=y").is_err());
+        assert_eq!(Formula::from_infix("x=y"), Ok(Formula::new("= x y")));
+        // A quantified variable is not checked, so the guarantee does not cover it.
+        let test_formula: Formula = Formula::new("∀ x=y P");
+        assert_ne!(test_formula.roundtrip_infix(), Ok(test_formula.clone()));
+    }
+
+    #[test]
+    fn test_pretty_round_trip_every_subformula_of_the_examples() {
+        for input in corpus::all() {
+            for formula in subformulae(&Formula::new(input)) {
+                assert_eq!(
+                    Formula::from_infix(&formula.to_infix_pretty()),
+                    Ok(formula.clone()),
+                    "{formula:#}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pretty_round_trip_every_nesting_of_variants() {
+        let leaves: Vec<Formula> = vec![
+            Formula::new("P(x)"),
+            Formula::new("(count-1)"),
+            Formula::Top,
+            Formula::Bottom,
+        ];
+        let mut formulae: Vec<Formula> = leaves.clone();
+        for leaf in &leaves {
+            formulae.extend(wrappings(leaf, &Formula::new("fib(index+1)")));
+        }
+        for formula in formulae.clone() {
+            formulae.extend(wrappings(&formula, &Formula::new("= x x+y*0")));
+        }
+        for formula in formulae.clone() {
+            formulae.extend(wrappings(&formula, &Formula::new("∀ z ¬ < z 0")));
+        }
+        for formula in formulae {
+            assert_eq!(
+                Formula::from_infix(&formula.to_infix_pretty()),
+                Ok(formula.clone()),
+                "{formula:#}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_top_and_bottom() {
+        assert_eq!(
+            Formula::from_infix("⊤ → (x=x+y*0)"),
+            Ok(Formula::new("→ ⊤ = x x+y*0"))
+        );
+        assert_eq!(Formula::from_infix("¬⊥"), Ok(Formula::new("¬ ⊥")));
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            Formula::from_infix("¬ A ∧ B ∨ C → D = E"),
+            Ok(Formula::new("= → ∨ ∧ ¬ A B C D E"))
+        );
+        assert_eq!(
+            Formula::from_infix("A = B → C ∨ D ∧ ¬ E"),
+            Ok(Formula::new("= A → B ∨ C ∧ D ¬ E"))
+        );
+    }
+
+    #[test]
+    fn test_associativity() {
+        assert_eq!(
+            Formula::from_infix("A → B → C"),
+            Ok(Formula::new("→ A → B C"))
+        );
+        assert_eq!(
+            Formula::from_infix("A ∧ B ∧ C"),
+            Ok(Formula::new("∧ ∧ A B C"))
+        );
+    }
+
+    #[test]
+    fn test_quantifier_forms() {
+        let expected = Formula::new("∀ x → P(x) Q(x)");
+        assert_eq!(Formula::from_infix("∀x(P(x)→Q(x))"), Ok(expected.clone()));
+        assert_eq!(Formula::from_infix("∀ x (P(x) → Q(x))"), Ok(expected));
+        assert_eq!(
+            Formula::from_infix("∃ y P(y) ∧ Q"),
+            Ok(Formula::new("∧ ∃ y P(y) Q"))
+        );
+    }
+
+    #[test]
+    fn test_bounded_quantifier_forms() {
+        let expected = Formula::new("∀ i → < i n → P(i) Q(i)");
+        assert_eq!(
+            Formula::from_infix("∀i<n (P(i)→Q(i))"),
+            Ok(expected.clone())
+        );
+        assert_eq!(Formula::from_infix("∀ i < n (P(i) → Q(i))"), Ok(expected));
+        assert_eq!(
+            Formula::from_infix("∃j < len(a)-1 (a(j))"),
+            Ok(Formula::new("∃ j ∧ < j len(a)-1 a(j)"))
+        );
+        assert_eq!(
+            Formula::from_infix("∃j<n ¬P(j) ∧ Q"),
+            Ok(Formula::new("∧ ∃ j ∧ < j n ¬ P(j) Q"))
+        );
+        let error = Formula::from_infix("∀i < ∧").unwrap_err();
+        assert_eq!(error.expected, "a bound after <");
+    }
+
+    #[test]
+    fn test_less_than_between_terms() {
+        assert_eq!(
+            Formula::from_infix("0 < (count-1) ∨ 0 = (count-1)"),
+            Ok(Formula::new("= ∨ < 0 (count-1) 0 (count-1)"))
+        );
+        assert_eq!(
+            Formula::from_infix("(0<(count-1))∨(0=(count-1))"),
+            Ok(Formula::new("∨ < 0 (count-1) = 0 (count-1)"))
+        );
+    }
+
+    #[test]
+    fn test_comparison_relations() {
+        assert_eq!(
+            Formula::from_infix("0 ≤ count ∧ count > 0 ∧ N ≥ index"),
+            Ok(Formula::new("∧ ∧ ≤ 0 count > count 0 ≥ N index"))
+        );
+        assert_eq!(
+            Formula::from_infix("(gcd(a,b)=gcd(a,mod(a,b)))∧(b≠0)"),
+            Ok(Formula::new("∧ = gcd(a,b) gcd(a,mod(a,b)) ≠ b 0"))
+        );
+    }
+
+    #[test]
+    fn test_malformed_infix() {
+        let error = Formula::from_infix("(P ∧ Q").unwrap_err();
+        assert_eq!(error.index, 4);
+        assert_eq!(error.expected, "a closing parenthesis");
+
+        let error = Formula::from_infix("P ∧").unwrap_err();
+        assert_eq!(error.index, 2);
+        assert_eq!(error.found, None);
+
+        let error = Formula::from_infix("P Q").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::TrailingTokens);
+        assert_eq!(error.index, 1);
+    }
+}
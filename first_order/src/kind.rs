@@ -0,0 +1,378 @@
+//! # Formula kinds
+//!
+//! This module provides [`FormulaKind`], which names the kind of the outermost node of a
+//! `Formula` without its subformulae, so that rules can check the shape of a formula by matching
+//! on an enum rather than comparing strings, and [`FormulaInfo`], which pairs the kind with
+//! borrowed parts of the node.
+use std::fmt;
+
+use crate::{Atom, Formula, Term, atom};
+
+/// The kind of the outermost node of a [`Formula`], as returned by [`Formula::kind`].
+///
+/// There is one kind per variant of `Formula`. Its `Display` is the name
+/// [`Formula::get_info`] returns at position 0, e.g. `Conjunction`.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, FormulaKind};
+///
+/// let test_formula: Formula = Formula::new("∧ P Q");
+/// assert_eq!(test_formula.kind(), FormulaKind::Conjunction);
+/// assert_eq!(test_formula.kind().to_string(), test_formula.get_info()[0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormulaKind {
+    /// A [`Formula::Term`].
+    Term,
+    /// A [`Formula::Top`].
+    Top,
+    /// A [`Formula::Bottom`].
+    Bottom,
+    /// A [`Formula::Negation`].
+    Negation,
+    /// A [`Formula::Conjunction`].
+    Conjunction,
+    /// A [`Formula::Disjunction`].
+    Disjunction,
+    /// A [`Formula::Implication`].
+    Implication,
+    /// A [`Formula::Equivalence`].
+    Equivalence,
+    /// A [`Formula::Iff`].
+    Iff,
+    /// A [`Formula::NotEqual`].
+    NotEqual,
+    /// A [`Formula::LessThan`].
+    LessThan,
+    /// A [`Formula::LessEqual`].
+    LessEqual,
+    /// A [`Formula::GreaterEqual`].
+    GreaterEqual,
+    /// A [`Formula::GreaterThan`].
+    GreaterThan,
+    /// A [`Formula::UniversalQuantifier`].
+    UniversalQuantifier,
+    /// A [`Formula::ExistentialQuantifier`].
+    ExistentialQuantifier,
+    /// A [`Formula::Custom`], whatever its registered symbol.
+    Custom,
+    /// A [`Formula::Equality`].
+    Equality,
+    /// A [`Formula::Less`].
+    Less,
+    /// A [`Formula::Predicate`], whatever its relation symbol.
+    Predicate,
+}
+
+impl FormulaKind {
+    /// Returns the name of the kind, e.g. `"Conjunction"`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::FormulaKind;
+    ///
+    /// assert_eq!(FormulaKind::UniversalQuantifier.name(), "UniversalQuantifier");
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            FormulaKind::Term => "Term",
+            FormulaKind::Top => "Top",
+            FormulaKind::Bottom => "Bottom",
+            FormulaKind::Negation => "Negation",
+            FormulaKind::Conjunction => "Conjunction",
+            FormulaKind::Disjunction => "Disjunction",
+            FormulaKind::Implication => "Implication",
+            FormulaKind::Equivalence => "Equivalence",
+            FormulaKind::Iff => "Iff",
+            FormulaKind::NotEqual => "NotEqual",
+            FormulaKind::LessThan => "LessThan",
+            FormulaKind::LessEqual => "LessEqual",
+            FormulaKind::GreaterEqual => "GreaterEqual",
+            FormulaKind::GreaterThan => "GreaterThan",
+            FormulaKind::UniversalQuantifier => "UniversalQuantifier",
+            FormulaKind::ExistentialQuantifier => "ExistentialQuantifier",
+            FormulaKind::Custom => "Custom",
+            FormulaKind::Equality => "Equality",
+            FormulaKind::Less => "Less",
+            FormulaKind::Predicate => "Predicate",
+        }
+    }
+}
+
+impl fmt::Display for FormulaKind {
+    /// Formats the kind as its name, e.g. `Conjunction`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The kind and parts of the outermost node of a [`Formula`], borrowed from it, as returned by
+/// [`Formula::info`].
+///
+/// The parts are laid out like the positions of [`Formula::get_info`]: a term, the variable of a
+/// quantifier or the first subformula comes first, and the second subformula or the body of a
+/// quantifier comes second. The terms of a typed atom such as `= x 5` are returned together, as
+/// an [`Atom`]. Nothing is copied or rendered.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, FormulaInfo, FormulaKind};
+///
+/// let test_formula: Formula = Formula::new("∧ ¬ B P");
+/// let info: FormulaInfo<'_> = test_formula.info();
+/// assert_eq!(info.kind(), FormulaKind::Conjunction);
+/// assert_eq!(info.left(), Some(&Formula::new("¬ B")));
+/// assert_eq!(info.right(), Some(&Formula::new("P")));
+/// assert_eq!(info.binder(), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormulaInfo<'a> {
+    kind: FormulaKind,          // The kind of the node.
+    symbol: Option<&'a str>,    // The registered symbol of a `Custom` connective.
+    term: Option<&'a Term>,     // The term of a `Term` node.
+    binder: Option<&'a str>,    // The variable bound by a quantifier.
+    left: Option<&'a Formula>,  // The operand of a negation or the first operand of a connective.
+    right: Option<&'a Formula>, // The second operand of a connective or the body of a quantifier.
+    atom: Option<Atom<'a>>,     // The terms of an `Equality`, `Less` or `Predicate` node.
+}
+
+impl<'a> FormulaInfo<'a> {
+    /// Returns the kind of the node.
+    pub fn kind(&self) -> FormulaKind {
+        self.kind
+    }
+    /// Returns the registered symbol if the node is a `Custom` connective, e.g. `⊆`.
+    pub fn symbol(&self) -> Option<&'a str> {
+        self.symbol
+    }
+    /// Returns the term if the node is a `Term`.
+    pub fn term(&self) -> Option<&'a Term> {
+        self.term
+    }
+    /// Returns the variable bound by the node if it is a quantifier.
+    pub fn binder(&self) -> Option<&'a str> {
+        self.binder
+    }
+    /// Returns the operand of a negation or the first operand of a binary connective.
+    pub fn left(&self) -> Option<&'a Formula> {
+        self.left
+    }
+    /// Returns the second operand of a binary connective or the body of a quantifier.
+    pub fn right(&self) -> Option<&'a Formula> {
+        self.right
+    }
+    /// Returns the atom if the node is an `Equality`, a `Less` or a `Predicate`.
+    pub fn atom(&self) -> Option<Atom<'a>> {
+        self.atom
+    }
+}
+
+/// Returns the kind and borrowed parts of the outermost node of `formula`. See [`Formula::info`].
+pub(crate) fn info(formula: &Formula) -> FormulaInfo<'_> {
+    let mut info: FormulaInfo<'_> = FormulaInfo {
+        kind: kind(formula),
+        symbol: None,
+        term: None,
+        binder: None,
+        left: None,
+        right: None,
+        atom: None,
+    };
+    match formula {
+        Formula::Term(term) => info.term = Some(term),
+        Formula::Equality(_, _) | Formula::Less(_, _) | Formula::Predicate(_, _) => {
+            info.atom = formula.as_atom();
+        }
+        Formula::Top | Formula::Bottom => {}
+        Formula::Negation(inner) => info.left = Some(inner),
+        Formula::UniversalQuantifier(variable, inner)
+        | Formula::ExistentialQuantifier(variable, inner) => {
+            info.binder = Some(variable);
+            info.right = Some(inner);
+        }
+        Formula::Custom(symbol, lhs, rhs) => {
+            info.symbol = Some(symbol);
+            info.left = Some(lhs);
+            info.right = Some(rhs);
+        }
+        Formula::Conjunction(lhs, rhs)
+        | Formula::Disjunction(lhs, rhs)
+        | Formula::Implication(lhs, rhs)
+        | Formula::Equivalence(lhs, rhs)
+        | Formula::Iff(lhs, rhs)
+        | Formula::NotEqual(lhs, rhs)
+        | Formula::LessThan(lhs, rhs)
+        | Formula::LessEqual(lhs, rhs)
+        | Formula::GreaterEqual(lhs, rhs)
+        | Formula::GreaterThan(lhs, rhs) => {
+            info.left = Some(lhs);
+            info.right = Some(rhs);
+        }
+    }
+    info
+}
+
+/// Returns the kind of the outermost node of `formula`. See [`Formula::kind`].
+pub(crate) fn kind(formula: &Formula) -> FormulaKind {
+    match formula {
+        Formula::Term(_) => FormulaKind::Term,
+        Formula::Top => FormulaKind::Top,
+        Formula::Bottom => FormulaKind::Bottom,
+        Formula::Negation(_) => FormulaKind::Negation,
+        Formula::Conjunction(_, _) => FormulaKind::Conjunction,
+        Formula::Disjunction(_, _) => FormulaKind::Disjunction,
+        Formula::Implication(_, _) => FormulaKind::Implication,
+        Formula::Equivalence(_, _) => FormulaKind::Equivalence,
+        Formula::Iff(_, _) => FormulaKind::Iff,
+        Formula::NotEqual(_, _) => FormulaKind::NotEqual,
+        Formula::LessThan(_, _) => FormulaKind::LessThan,
+        Formula::LessEqual(_, _) => FormulaKind::LessEqual,
+        Formula::GreaterEqual(_, _) => FormulaKind::GreaterEqual,
+        Formula::GreaterThan(_, _) => FormulaKind::GreaterThan,
+        Formula::UniversalQuantifier(_, _) => FormulaKind::UniversalQuantifier,
+        Formula::ExistentialQuantifier(_, _) => FormulaKind::ExistentialQuantifier,
+        Formula::Custom(_, _, _) => FormulaKind::Custom,
+        Formula::Equality(_, _) => FormulaKind::Equality,
+        Formula::Less(_, _) => FormulaKind::Less,
+        Formula::Predicate(_, _) => FormulaKind::Predicate,
+    }
+}
+
+/// Builds the binary connective of the given kind from its operands, or returns `None` if `kind`
+/// is not a binary connective. A `Custom` formula needs its symbol, so it is not built here.
+///
+/// An `=` or `<` between two terms is built as an `Equality` or `Less`, as the parser does.
+pub(crate) fn binary(kind: FormulaKind, lhs: Box<Formula>, rhs: Box<Formula>) -> Option<Formula> {
+    match kind {
+        FormulaKind::Conjunction => Some(Formula::Conjunction(lhs, rhs)),
+        FormulaKind::Disjunction => Some(Formula::Disjunction(lhs, rhs)),
+        FormulaKind::Implication => Some(Formula::Implication(lhs, rhs)),
+        FormulaKind::Equivalence => Some(atom::equality(lhs, rhs)),
+        FormulaKind::Iff => Some(Formula::Iff(lhs, rhs)),
+        FormulaKind::NotEqual => Some(Formula::NotEqual(lhs, rhs)),
+        FormulaKind::LessThan => Some(atom::less_than(lhs, rhs)),
+        FormulaKind::LessEqual => Some(Formula::LessEqual(lhs, rhs)),
+        FormulaKind::GreaterEqual => Some(Formula::GreaterEqual(lhs, rhs)),
+        FormulaKind::GreaterThan => Some(Formula::GreaterThan(lhs, rhs)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParserConfig, corpus};
+
+    #[test]
+    fn test_kind_of_each_variant() {
+        let cases: [(&str, FormulaKind); 19] = [
+            ("x+1", FormulaKind::Term),
+            ("⊤", FormulaKind::Top),
+            ("⊥", FormulaKind::Bottom),
+            ("¬ P", FormulaKind::Negation),
+            ("∧ P Q", FormulaKind::Conjunction),
+            ("∨ P Q", FormulaKind::Disjunction),
+            ("→ P Q", FormulaKind::Implication),
+            ("= x 5", FormulaKind::Equality),
+            ("= P ¬ Q", FormulaKind::Equivalence),
+            ("↔ P Q", FormulaKind::Iff),
+            ("≠ x 5", FormulaKind::NotEqual),
+            ("< x 5", FormulaKind::Less),
+            ("< ¬ P Q", FormulaKind::LessThan),
+            ("≤ x 5", FormulaKind::LessEqual),
+            ("≥ x 5", FormulaKind::GreaterEqual),
+            ("> x 5", FormulaKind::GreaterThan),
+            ("∀ x P(x)", FormulaKind::UniversalQuantifier),
+            ("∃ x P(x)", FormulaKind::ExistentialQuantifier),
+            ("P(x)", FormulaKind::Predicate),
+        ];
+        for (input, kind) in cases {
+            assert_eq!(Formula::new(input).kind(), kind, "{input}");
+        }
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        assert_eq!(config.parse("⊆ A B").unwrap().kind(), FormulaKind::Custom);
+    }
+
+    #[test]
+    fn test_display_matches_get_info() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(
+                test_formula.kind().to_string(),
+                test_formula.get_info()[0],
+                "{input}"
+            );
+        }
+        assert_eq!(Formula::Top.kind().to_string(), "Top");
+        assert_eq!(FormulaKind::Custom.to_string(), "Custom");
+    }
+
+    #[test]
+    fn test_info_borrows_the_parts() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        let info: FormulaInfo<'_> = test_formula.info();
+        let children: Vec<&Formula> = test_formula.children();
+        assert!(std::ptr::eq(info.left().unwrap(), children[0]));
+        assert!(std::ptr::eq(info.right().unwrap(), children[1]));
+
+        let quantifier: &Formula = info.left().unwrap();
+        let info: FormulaInfo<'_> = quantifier.info();
+        assert_eq!(info.kind(), FormulaKind::UniversalQuantifier);
+        assert_eq!(info.binder(), Some("x"));
+        assert_eq!(info.left(), None);
+        assert!(std::ptr::eq(
+            info.right().unwrap(),
+            quantifier.children()[0]
+        ));
+
+        let term: Formula = Formula::new("x+1");
+        assert_eq!(term.info().term(), Some(&Term::new("x+1")));
+        let equality: Formula = Formula::new("= x 5");
+        assert_eq!(
+            equality.info().atom(),
+            Some(Atom::Equality(&Term::new("x"), &Term::new("5")))
+        );
+        assert_eq!(equality.info().left(), None);
+        assert_eq!(Formula::Top.info().right(), None);
+
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        let custom: Formula = config.parse("⊆ A B").unwrap();
+        assert_eq!(custom.info().symbol(), Some("⊆"));
+        assert_eq!(custom.info().kind(), FormulaKind::Custom);
+    }
+
+    #[test]
+    fn test_get_info_agrees_with_info() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            let info: FormulaInfo<'_> = test_formula.info();
+            let [kind, first, second]: [String; 3] = test_formula.get_info();
+            assert_eq!(kind, info.kind().to_string(), "{input}");
+            let expected: String = match (info.left(), info.binder()) {
+                (Some(left), _) => left.to_prefix_notation(),
+                (None, Some(binder)) => binder.to_string(),
+                (None, None) => match info.atom() {
+                    Some(Atom::Predicate(_, _)) => test_formula.to_string(),
+                    Some(atom) => atom.terms()[0].to_string(),
+                    None => info.term().map(Term::to_string).unwrap_or_default(),
+                },
+            };
+            assert_eq!(first, expected, "{input}");
+            let expected: String = match info.atom() {
+                Some(atom) => atom
+                    .terms()
+                    .get(1)
+                    .map(|term| term.to_string())
+                    .unwrap_or_default(),
+                None => info
+                    .right()
+                    .map(Formula::to_prefix_notation)
+                    .unwrap_or_default(),
+            };
+            assert_eq!(second, expected, "{input}");
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! # LaTeX
+//!
+//! This module renders a formula as LaTeX math for [`Formula::to_latex`] and
+//! [`Formula::to_latex_with`], e.g. `\forall x\, (P(x) \rightarrow Q(x))`.
+//!
+//! | Formula     | LaTeX                                                |
+//! |-------------|------------------------------------------------------|
+//! | `⊤`, `⊥`    | `\top`, `\bot`                                       |
+//! | `¬`         | `\neg`                                               |
+//! | `∧`, `∨`    | `\land`, `\lor`                                      |
+//! | `→`         | `\rightarrow`                                        |
+//! | `=`, `↔`    | `=`, `\leftrightarrow`                               |
+//! | `≠`, `≤`, `≥` | `\neq`, `\leq`, `\geq`                             |
+//! | `∀`, `∃`    | `\forall`, `\exists`                                 |
+//!
+//! Only the operands of a connective are parenthesized, and a relation between two terms is not,
+//! since it binds more tightly than every connective. Terms are rendered from their structure:
+//! `*` becomes `\cdot`, `%` becomes `\bmod`, and the exponent of `^` is set as a superscript.
+//! Characters that are special in LaTeX, such as `_`, are escaped in names, and `≔` becomes
+//! `\coloneqq`.
+use crate::term::{self, POWER, Term, UNARY};
+use crate::{Atom, Formula, atom};
+
+/// How [`Formula::to_latex_with`] renders a formula.
+///
+/// By default parentheses are plain and every quantifier body is parenthesized.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, LatexOptions};
+///
+/// let test_formula: Formula = Formula::new("∀ x → P(x) Q(x)");
+/// assert_eq!(test_formula.to_latex(), r"\forall x\, (P(x) \rightarrow Q(x))");
+///
+/// let mut options = LatexOptions::new();
+/// options.left_right(true);
+/// assert_eq!(
+///     test_formula.to_latex_with(&options),
+///     r"\forall x\, \left(P(x) \rightarrow Q(x)\right)"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatexOptions {
+    left_right: bool, // Whether parentheses are written as `\left(` and `\right)`.
+    parenthesize_bodies: bool, // Whether every quantifier body is parenthesized.
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        LatexOptions {
+            left_right: false,
+            parenthesize_bodies: true,
+        }
+    }
+}
+
+impl LatexOptions {
+    /// Creates `LatexOptions` with plain parentheses and parenthesized quantifier bodies.
+    pub fn new() -> Self {
+        LatexOptions::default()
+    }
+
+    /// Sets whether the parentheses around subformulae are written as `\left(` and `\right)`,
+    /// so that they grow with their contents.
+    pub fn left_right(&mut self, enabled: bool) -> &mut Self {
+        self.left_right = enabled;
+        self
+    }
+
+    /// Sets whether every quantifier body is parenthesized, as in `\forall x\, (P(x))`. If not,
+    /// only a body that would need parentheses as an operand gets them, as in `\forall x\, P(x)`.
+    pub fn parenthesize_bodies(&mut self, enabled: bool) -> &mut Self {
+        self.parenthesize_bodies = enabled;
+        self
+    }
+}
+
+/// Renders `formula` as LaTeX. See [`Formula::to_latex_with`].
+pub(crate) fn to_latex(formula: &Formula, options: &LatexOptions) -> String {
+    match formula {
+        Formula::Term(term) => term_latex(term),
+        Formula::Top => r"\top".to_string(),
+        Formula::Bottom => r"\bot".to_string(),
+        Formula::Negation(inner) => format!(r"\neg {}", operand(inner, options)),
+        Formula::Conjunction(lhs, rhs) => binary(lhs, r"\land", rhs, options),
+        Formula::Disjunction(lhs, rhs) => binary(lhs, r"\lor", rhs, options),
+        Formula::Implication(lhs, rhs) => binary(lhs, r"\rightarrow", rhs, options),
+        Formula::Equivalence(lhs, rhs) => binary(lhs, "=", rhs, options),
+        Formula::Iff(lhs, rhs) => binary(lhs, r"\leftrightarrow", rhs, options),
+        Formula::NotEqual(lhs, rhs) => binary(lhs, r"\neq", rhs, options),
+        Formula::LessThan(lhs, rhs) => binary(lhs, "<", rhs, options),
+        Formula::LessEqual(lhs, rhs) => binary(lhs, r"\leq", rhs, options),
+        Formula::GreaterEqual(lhs, rhs) => binary(lhs, r"\geq", rhs, options),
+        Formula::GreaterThan(lhs, rhs) => binary(lhs, ">", rhs, options),
+        Formula::UniversalQuantifier(variable, inner) => {
+            quantifier(r"\forall", variable, inner, options)
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            quantifier(r"\exists", variable, inner, options)
+        }
+        Formula::Custom(symbol, lhs, rhs) => binary(lhs, &escape(symbol), rhs, options),
+        Formula::Equality(lhs, rhs) => format!("{} = {}", term_latex(lhs), term_latex(rhs)),
+        Formula::Less(lhs, rhs) => format!("{} < {}", term_latex(lhs), term_latex(rhs)),
+        Formula::Predicate(name, args) => term_latex(&atom::predicate_term(name, args)),
+    }
+}
+
+/// Returns whether `formula` is a relation between two terms, e.g. `x = r + y`.
+fn is_relation(formula: &Formula) -> bool {
+    formula
+        .as_atom()
+        .is_some_and(|atom| !matches!(atom, Atom::Predicate(_, _)))
+}
+
+/// Returns whether `formula` must be parenthesized as the operand of a connective.
+fn needs_parentheses(formula: &Formula) -> bool {
+    match formula {
+        Formula::Term(_)
+        | Formula::Predicate(_, _)
+        | Formula::Top
+        | Formula::Bottom
+        | Formula::Negation(_)
+        | Formula::UniversalQuantifier(_, _)
+        | Formula::ExistentialQuantifier(_, _) => false,
+        _ => !is_relation(formula),
+    }
+}
+
+/// Renders `formula` as an operand, parenthesized if it needs to be.
+fn operand(formula: &Formula, options: &LatexOptions) -> String {
+    if needs_parentheses(formula) {
+        parenthesize(&to_latex(formula, options), options)
+    } else {
+        to_latex(formula, options)
+    }
+}
+
+fn parenthesize(inner: &str, options: &LatexOptions) -> String {
+    if options.left_right {
+        format!(r"\left({inner}\right)")
+    } else {
+        format!("({inner})")
+    }
+}
+
+fn binary(lhs: &Formula, symbol: &str, rhs: &Formula, options: &LatexOptions) -> String {
+    format!(
+        "{} {symbol} {}",
+        operand(lhs, options),
+        operand(rhs, options)
+    )
+}
+
+fn quantifier(symbol: &str, variable: &str, inner: &Formula, options: &LatexOptions) -> String {
+    let body: String = if options.parenthesize_bodies {
+        parenthesize(&to_latex(inner, options), options)
+    } else {
+        operand(inner, options)
+    };
+    format!(r"{symbol} {}\, {body}", escape(variable))
+}
+
+/// Renders `term` as LaTeX, parenthesizing operands like [`Term`]'s `Display` does.
+fn term_latex(term: &Term) -> String {
+    match term {
+        Term::Var(name) | Term::Const(name) => escape(name),
+        Term::IntLit(value) => value.to_string(),
+        Term::App(name, args) => match (name.as_str(), args.as_slice()) {
+            ("^", [base, exponent]) => {
+                // The braces of the superscript group the exponent, so its parentheses are dropped.
+                let exponent: &Term = match exponent {
+                    Term::App(name, args) if name.is_empty() && args.len() == 1 => &args[0],
+                    _ => exponent,
+                };
+                format!(
+                    "{}^{{{}}}",
+                    term_operand(base, POWER + 1),
+                    term_latex(exponent)
+                )
+            }
+            (operator, [lhs, rhs]) if term::is_operator(operator) => {
+                let precedence: u8 = term.precedence();
+                let symbol: &str = match operator {
+                    "*" => r"\cdot",
+                    "%" => r"\bmod",
+                    _ => operator,
+                };
+                format!(
+                    "{} {symbol} {}",
+                    term_operand(lhs, precedence),
+                    term_operand(rhs, precedence + 1)
+                )
+            }
+            ("-", [inner]) => format!("-{}", term_operand(inner, UNARY)),
+            ("", [inner]) => format!("({})", term_latex(inner)),
+            _ => format!(
+                "{}({})",
+                escape(name),
+                args.iter()
+                    .map(term_latex)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        },
+    }
+}
+
+/// Renders `term`, parenthesized if it binds less tightly than `precedence`.
+fn term_operand(term: &Term, precedence: u8) -> String {
+    if term.precedence() < precedence {
+        format!("({})", term_latex(term))
+    } else {
+        term_latex(term)
+    }
+}
+
+/// Escapes the characters of a name that are special in LaTeX math.
+fn escape(name: &str) -> String {
+    let mut escaped: String = String::new();
+    for c in name.chars() {
+        match c {
+            '_' | '%' | '#' | '&' | '$' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '^' => escaped.push_str(r"\hat{}"),
+            '\\' => escaped.push_str(r"\backslash "),
+            '≔' => escaped.push_str(r"\coloneqq "),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_paper_example_snapshots() {
+        let expected: [&str; 10] = [
+            r"\top \rightarrow x = x + y \cdot 0",
+            r"x = x + y \cdot 0",
+            r"x = r + y \cdot 0",
+            r"x = r + y \cdot q",
+            r"x = r + y \cdot 0 \rightarrow x = r + y \cdot 0",
+            r"(x = r + y \cdot q \land (y < r \lor y = r)) \rightarrow x = (r - y) + y \cdot (1 + q)",
+            r"x = (r - y) + y \cdot (1 + q)",
+            r"x = r + y \cdot (1 + q)",
+            r"x = r + y \cdot q \rightarrow x = r + y \cdot q",
+            r"(x = r + y \cdot q \land \neg (y < r \lor y = r)) \rightarrow (\neg (y < r \lor y = r) \land x = r + y \cdot q)",
+        ];
+        for (input, latex) in corpus::PAPER_EXAMPLE.into_iter().zip(expected) {
+            assert_eq!(Formula::new(input).to_latex(), latex, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_paper_example_left_right() {
+        let mut options: LatexOptions = LatexOptions::new();
+        options.left_right(true);
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[5]).to_latex_with(&options),
+            r"\left(x = r + y \cdot q \land \left(y < r \lor y = r\right)\right) \rightarrow x = (r - y) + y \cdot (1 + q)"
+        );
+    }
+
+    #[test]
+    fn test_quantifier_bodies() {
+        let test_formula: Formula = Formula::new("∀ x ∃ y ∧ < x y ¬ P(y)");
+        assert_eq!(
+            test_formula.to_latex(),
+            r"\forall x\, (\exists y\, (x < y \land \neg P(y)))"
+        );
+        let mut options: LatexOptions = LatexOptions::new();
+        options.parenthesize_bodies(false);
+        assert_eq!(
+            test_formula.to_latex_with(&options),
+            r"\forall x\, \exists y\, (x < y \land \neg P(y))"
+        );
+    }
+
+    #[test]
+    fn test_connectives() {
+        let cases: [(&str, &str); 9] = [
+            ("∨ ⊤ ⊥", r"\top \lor \bot"),
+            ("= P Q", "P = Q"),
+            ("= ¬ P Q", r"\neg P = Q"),
+            ("↔ ¬ P Q", r"\neg P \leftrightarrow Q"),
+            ("↔ → P Q R", r"(P \rightarrow Q) \leftrightarrow R"),
+            ("≠ x 5", r"x \neq 5"),
+            ("∧ ≤ 0 i ≥ n i", r"0 \leq i \land n \geq i"),
+            ("¬ > x 5", r"\neg x > 5"),
+            ("¬ ¬ ∧ P Q", r"\neg \neg (P \land Q)"),
+        ];
+        for (input, latex) in cases {
+            assert_eq!(Formula::new(input).to_latex(), latex, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_terms() {
+        let cases: [(&str, &str); 5] = [
+            ("= y x^(n+1)", "y = x^{n + 1}"),
+            ("= y (a+b)^2", "y = (a + b)^{2}"),
+            ("= r -x%2", r"r = -x \bmod 2"),
+            ("= z (a-b)-(c-d)", "z = (a - b) - (c - d)"),
+            ("= f(x,y) g(x)", "f(x, y) = g(x)"),
+        ];
+        for (input, latex) in cases {
+            assert_eq!(Formula::new(input).to_latex(), latex, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_special_characters_are_escaped() {
+        let test_formula: Formula = Formula::new("∀ x_1 P_q(x_1,a^b)");
+        assert_eq!(
+            test_formula.to_latex(),
+            r"\forall x\_1\, (P\_q(x\_1, a^{b}))"
+        );
+        assert_eq!(escape("x≔e"), r"x\coloneqq e");
+        assert_eq!(escape("a^b&c"), r"a\hat{}b\&c");
+    }
+}
@@ -0,0 +1,151 @@
+//! # Lemma constructors
+//!
+//! This module builds the implications that proofs use as lemmas of the consequence rule again
+//! and again, for [`Formula::identity_implication`], [`Formula::weaken_left`] and
+//! [`Formula::add_conjunct_implication`]:
+//!
+//! | Constructor                | Lemma                 |
+//! |----------------------------|-----------------------|
+//! | `identity_implication`     | `→ P P`               |
+//! | `weaken_left`              | `→ ∧ ∧ A B C B`       |
+//! | `add_conjunct_implication` | `→ ∧ extra base base` |
+//!
+//! Every lemma is an instance of a propositional tautology, whatever its parts are, so it needs no
+//! further proof.
+use std::fmt;
+
+use crate::{Formula, FormulaKind};
+
+/// An error returned by [`Formula::weaken_left`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LemmaError {
+    /// The formula whose conjunct is kept is not a conjunction.
+    NotAConjunction { formula: String },
+    /// The conjunct `index` is kept, but the conjunction only has `conjuncts` conjuncts.
+    NoSuchConjunct { index: usize, conjuncts: usize },
+}
+
+impl fmt::Display for LemmaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LemmaError::NotAConjunction { formula } => {
+                write!(f, "{formula} is not a conjunction")
+            }
+            LemmaError::NoSuchConjunct { index, conjuncts } => write!(
+                f,
+                "the conjunct {index} is kept, but the conjunction only has {conjuncts} conjuncts"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LemmaError {}
+
+/// Returns `→ formula formula`. See [`Formula::identity_implication`].
+pub(crate) fn identity_implication(formula: &Formula) -> Formula {
+    formula.clone().implies(formula.clone())
+}
+
+/// Returns `→ conjunction conjunct`, where `conjunct` is the conjunct `index` of `conjunction`.
+/// See [`Formula::weaken_left`].
+pub(crate) fn weaken_left(conjunction: &Formula, index: usize) -> Result<Formula, LemmaError> {
+    if conjunction.kind() != FormulaKind::Conjunction {
+        return Err(LemmaError::NotAConjunction {
+            formula: conjunction.to_prefix_notation(),
+        });
+    }
+    let conjuncts: Vec<&Formula> = conjunction.conjuncts();
+    let kept: &Formula = conjuncts.get(index).ok_or(LemmaError::NoSuchConjunct {
+        index,
+        conjuncts: conjuncts.len(),
+    })?;
+    Ok(conjunction.clone().implies(kept.clone()))
+}
+
+/// Returns `→ ∧ extra base base`. See [`Formula::add_conjunct_implication`].
+pub(crate) fn add_conjunct_implication(base: &Formula, extra: &Formula) -> Formula {
+    extra.clone().and(base.clone()).implies(base.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_identity_implication() {
+        for input in corpus::FIBONACCI {
+            let condition: Formula = Formula::new(input);
+            let lemma: Formula = Formula::identity_implication(&condition);
+            assert_eq!(lemma, Formula::new(format!("→ {input} {input}")));
+            assert_eq!(lemma.is_tautology(), Ok(true));
+        }
+        assert_eq!(
+            Formula::identity_implication(&Formula::new(corpus::PAPER_EXAMPLE[5])),
+            Formula::new(
+                "→ → ∧ = x r+y*q ∨ < y r = y r = x (r-y)+y*(1+q) → ∧ = x r+y*q ∨ < y r = y r = x (r-y)+y*(1+q)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_weaken_left() {
+        let invariant: Formula = Formula::new(corpus::FIBONACCI[4]);
+        assert_eq!(
+            Formula::weaken_left(&invariant, 4),
+            Ok(Formula::new(
+                "→ ∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input = N input"
+            ))
+        );
+        assert_eq!(
+            Formula::weaken_left(&Formula::new("∧ A B"), 0),
+            Ok(Formula::new("→ ∧ A B A"))
+        );
+        assert_eq!(
+            Formula::weaken_left(&Formula::new("∧ A ∧ B C"), 2),
+            Ok(Formula::new("→ ∧ A ∧ B C C"))
+        );
+        assert_eq!(
+            Formula::weaken_left(&invariant, 2).unwrap().is_tautology(),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_weaken_left_errors() {
+        assert_eq!(
+            Formula::weaken_left(&Formula::new("∨ A B"), 0),
+            Err(LemmaError::NotAConjunction {
+                formula: "∨ A B".to_string()
+            })
+        );
+        let error: LemmaError = Formula::weaken_left(&Formula::new("∧ ∧ A B C"), 3).unwrap_err();
+        assert_eq!(
+            error,
+            LemmaError::NoSuchConjunct {
+                index: 3,
+                conjuncts: 3
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "the conjunct 3 is kept, but the conjunction only has 3 conjuncts"
+        );
+    }
+
+    #[test]
+    fn test_add_conjunct_implication() {
+        let invariant: Formula = Formula::new(corpus::FIBONACCI[4]);
+        let lemma: Formula =
+            Formula::add_conjunct_implication(&invariant, &Formula::new("¬ < index N"));
+        assert_eq!(
+            lemma,
+            Formula::new(format!(
+                "→ {} {}",
+                corpus::FIBONACCI[8],
+                corpus::FIBONACCI[4]
+            ))
+        );
+        assert_eq!(lemma.is_tautology(), Ok(true));
+    }
+}
@@ -0,0 +1,93 @@
+//! # Prefix notation lexer
+//!
+//! This module splits a formula written in prefix notation into tokens without relying on
+//! whitespace, so that inputs such as `∧∀x→P(x)Q R` can be read by [`Formula::new_lexed`].
+//!
+//! Every connective, relation, quantifier, and truth constant code point is a token on its own,
+//! even when glued to a term. All other characters (identifier characters, digits, arithmetic
+//! symbols, and parentheses) are collected into term tokens. Whitespace still separates tokens,
+//! and a term also ends after a closing parenthesis that is not followed by an arithmetic symbol,
+//! so `P(x)Q` yields the two terms `P(x)` and `Q`. Two other adjacent terms, such as the operands
+//! of `= x x+y*0`, must be separated by whitespace.
+//!
+//! [`Formula::new_lexed`]: crate::Formula::new_lexed
+
+/// Characters that form a token on their own, wherever they appear outside parentheses.
+const SYMBOL_CHARS: [char; 15] = [
+    '⊤', '⊥', '¬', '∧', '∨', '→', '↔', '=', '≠', '<', '≤', '≥', '>', '∀', '∃',
+];
+
+/// Arithmetic symbols that continue a term after a closing parenthesis, e.g. `(r-y)+y`.
+const ARITHMETIC_CHARS: [char; 6] = ['+', '-', '*', '/', '%', '^'];
+
+/// Splits `input` into the tokens of a formula in prefix notation.
+pub(crate) fn lex(input: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut term: String = String::new();
+    let mut depth: usize = 0; // Nesting depth of the parentheses inside the current term.
+    let mut closed: bool = false; // Whether the current term ends with a balanced `)`.
+
+    for c in input.chars() {
+        if depth == 0 {
+            if c.is_whitespace() || SYMBOL_CHARS.contains(&c) {
+                flush(&mut tokens, &mut term);
+                if !c.is_whitespace() {
+                    tokens.push(c.to_string());
+                }
+                closed = false;
+                continue;
+            }
+            if closed && !ARITHMETIC_CHARS.contains(&c) {
+                flush(&mut tokens, &mut term);
+            }
+        }
+        closed = false;
+        term.push(c);
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => {
+                depth -= 1;
+                closed = depth == 0;
+            }
+            _ => {}
+        }
+    }
+    flush(&mut tokens, &mut term);
+    tokens
+}
+
+/// Moves the term collected so far, if any, into `tokens`.
+fn flush(tokens: &mut Vec<String>, term: &mut String) {
+    if !term.is_empty() {
+        tokens.push(std::mem::take(term));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_lex_glued_symbols() {
+        assert_eq!(
+            lex("∧∀x→P(x)Q R"),
+            vec!["∧", "∀", "x", "→", "P(x)", "Q", "R"]
+        );
+    }
+
+    #[test]
+    fn test_lex_keeps_arithmetic_in_terms() {
+        assert_eq!(lex("=x (r-y)+y*(1+q)"), vec!["=", "x", "(r-y)+y*(1+q)"]);
+        assert_eq!(lex("<0(count-1)"), vec!["<", "0(count-1)"]);
+        assert_eq!(lex("≠fib(index)fib(n)"), vec!["≠", "fib(index)", "fib(n)"]);
+    }
+
+    #[test]
+    fn test_lex_matches_whitespace_split_on_corpus() {
+        for input in corpus::all() {
+            let expected: Vec<&str> = input.split_whitespace().collect();
+            assert_eq!(lex(input), expected, "lexing {input:?}");
+        }
+    }
+}
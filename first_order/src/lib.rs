@@ -2,21 +2,174 @@
 //!
 //! This module provides an implementation of logical formulae using an enum `Formula`.
 //! It supports the following logical operations:
+//! - Truth ⊤ and Falsity ⊥
 //! - Negation ¬
 //! - Conjunction ∧
 //! - Disjunction ∨
 //! - Implication →
 //! - Equivalence =
+//! - Biconditional ↔
+//! - Not Equal ≠
 //! - Less Than <
+//! - Less Than or Equal ≤
+//! - Greater Than or Equal ≥
+//! - Greater Than >
 //! - Universal Quantifier ∀
 //! - Existential Quantifier ∃
+//!
+//! Formulae can be read from prefix notation with [`Formula::new`] or [`Formula::try_new`], from
+//! prefix notation without separating whitespace with [`Formula::new_lexed`], from infix
+//! notation with [`Formula::from_infix`], and from s-expressions with [`Formula::from_sexpr`].
+//! A document holding one formula in prefix notation per line is read with [`parse_all`] or
+//! [`parse_document`]. Extra binary connectives such as `⊆` are registered with a
+//! [`ParserConfig`]. The terms inside a formula are parsed into [`Term`]
+//! trees, see the [`term`] module.
+//!
+//! Formulae can also be built in code, with [`Formula::term`] and builder methods such as
+//! [`Formula::and`] and [`Formula::forall`], with the operators `&`, `|` and `!`, or with the
+//! [`formula!`] macro. A [`SharedFormula`] shares its subformulae, so that it is cloned in
+//! constant time. The [`nd`] module proves formulae by natural deduction.
+//!
+//! ## Dropping formulae
+//! `Formula` implements `Drop`, tearing a formula down with an explicit stack, so that dropping
+//! one nested hundreds of thousands of levels deep, which the parsers can read, does not overflow
+//! the call stack. This is a breaking change: a `Formula` can no longer be destructured by value,
+//! and moving an operand out of one fails to compile with error E0509.
+//!
+//! ```compile_fail,E0509
+//! use first_order::Formula;
+//!
+//! let Formula::Negation(inner) = Formula::new("¬ ∧ P Q") else { return };
+//! ```
+//!
+//! Match on a reference and clone the operands instead, or move them out with
+//! [`std::mem::replace`], leaving `⊤` in their place, as [`Formula::negated`] does.
+//!
+//! ```
+//! use first_order::Formula;
+//!
+//! let mut test_formula: Formula = Formula::new("¬ ∧ P Q");
+//! if let Formula::Negation(inner) = &mut test_formula {
+//!     let inner: Formula = std::mem::replace(&mut **inner, Formula::Top);
+//!     assert_eq!(inner, Formula::new("∧ P Q"));
+//! }
+//! ```
+//!
+//! ## Features
+//! - `serde`: implements `Serialize` and `Deserialize` for [`Formula`] and [`Term`], using the
+//!   externally tagged representation, e.g. `{"Conjunction":["Top","Bottom"]}`. Serialization
+//!   grows the stack as needed. To deserialize deeply nested formulae, wrap the deserializer in
+//!   `serde_stacker::Deserializer` and lift the format's recursion limit, e.g. with
+//!   `serde_json::Deserializer::disable_recursion_limit`.
+//! - `proptest`: implements `proptest::arbitrary::Arbitrary` for [`Formula`], generating formulae
+//!   like [`gen::random_formula`] does.
+//! - `z3`: adds `Formula::check_validity`, which checks the validity of a formula over the
+//!   integers with the Z3 solver. It binds the C API of `libz3` directly instead of using the
+//!   `z3` crate, so it only needs the shared library of Z3 4.8 or later to be installed.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
+mod ac;
+mod arith;
+mod ascii;
+mod atom;
+pub mod binary;
+mod bounded;
+mod config;
+#[cfg(test)]
+mod corpus;
+mod document;
+mod fragment;
+pub mod r#gen;
+mod generalize;
+mod ground;
+mod infix;
+mod kind;
+mod latex;
+mod lemma;
+mod lexer;
+mod lint;
+mod lookalike;
+mod macros;
+mod map_terms;
+mod metrics;
+mod model;
+pub mod nd;
+mod normal_form;
+mod normalize;
+mod notation;
+mod ops;
+mod path;
+mod pattern;
+mod polarity;
+mod postfix;
+mod pretty;
+mod print;
+mod rename;
+mod rewrite;
+pub mod sat;
+pub mod sequent;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests;
+mod sexpr;
+mod shared;
+pub mod signature;
+mod simplify;
+mod smtlib;
+#[cfg(feature = "z3")]
+mod solver;
+mod span;
+mod store;
+mod symbols;
+pub mod term;
+mod tree;
+mod truth_table;
+mod unify;
+mod variables;
+mod visitor;
+
+pub use arith::ArithFunction;
+pub use atom::Atom;
+pub use binary::{DecodeError, DecodeErrorKind};
+pub use config::ParserConfig;
+pub use document::{parse_all, parse_document};
+pub use fragment::Fragment;
+pub use ground::GroundError;
+pub use kind::{FormulaInfo, FormulaKind};
+pub use latex::LatexOptions;
+pub use lemma::LemmaError;
+pub use lint::FormulaLint;
+pub use metrics::Quantifier;
+pub use model::{DomainElem, EvalError, Model};
+pub use path::{FormulaPath, PathError};
+pub use pattern::{Bindings, Pattern, PatternError};
+pub use polarity::Polarity;
+pub use print::{PrintOptions, TermPrintOptions};
+pub use rename::RenameError;
+pub use rewrite::{RewriteRule, RuleError, parse_rules};
+pub use shared::SharedFormula;
+pub use signature::{Signature, SignatureError, SymbolKind};
+pub use simplify::SimplifyOptions;
+pub use smtlib::ExportError;
+#[cfg(feature = "z3")]
+pub use solver::{CounterModel, SolverConfig, SolverError, Validity};
+pub use span::{Span, SpanMap};
+pub use store::{FormulaId, FormulaStore, StoreStats};
+pub use symbols::{SymbolClass, SymbolTable};
+pub use term::{Term, TermParseError, TermSubst};
+pub use tree::TreeOptions;
+pub use truth_table::TruthTable;
+pub use unify::{Substitution, unify};
+pub use visitor::{FormulaFolder, FormulaVisitor, Subformulae};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 /// An enum representing different types of logical formulae.
 ///
 /// A `Formula` is defined as follows:
-/// - `⊥` is a formula.
+/// - `⊤` and `⊥` are formulae.
 /// - If `R` is an `n`-place relation symbol and `a,b,...,m` are terms, then `R(a,b,...,m)` is a formula.
 /// - If `φ` and `ψ` are formulae and `x` is a variable, then the following are formulae:
 ///     * `¬ φ`
@@ -24,20 +177,45 @@ use std::fmt;
 ///     * `∨ φ ψ`
 ///     * `→ φ ψ`
 ///     * `= φ ψ`
+///     * `≠ φ ψ`
 ///     * `< φ ψ`
+///     * `≤ φ ψ`
+///     * `≥ φ ψ`
+///     * `> φ ψ`
 ///     * `∀ x φ`
 ///     * `∃ x φ`
+///     * `⊆ φ ψ`, where `⊆` is any binary connective registered with a [`ParserConfig`]
+///
+/// An `=` or `<` between two terms and a relation symbol applied to terms are parsed into the
+/// typed variants [`Formula::Equality`], [`Formula::Less`] and [`Formula::Predicate`], which hold
+/// their [`Term`]s directly. The same atoms built from `Formula::Term` operands, such as
+/// `Equivalence(Term(a), Term(b))`, are still accepted everywhere and written the same way, so
+/// formulae built by hand before the typed variants existed keep working.
+///
+/// Formulae are ordered by variant first, in the order the variants are declared below, and then
+/// by comparing their fields from left to right, so `⊤ < ⊥ < ¬ φ < ∧ φ ψ` and `∧ P Q < ∧ Q P`.
+/// Terms are ordered in the same way, see [`Term`]. The ordering only depends on the structure
+/// of the formulae, so it is stable across runs and can be used to sort or deduplicate lemmas.
+///
+/// Parsing and dropping a `Formula` do not recurse, so their depth is limited only by the
+/// available memory. Because `Formula` implements `Drop`, fields cannot be moved out of it by
+/// pattern matching; match on a reference instead.
 pub enum Formula {
     /// A `Term` is define as follows
     /// - Every variable is a term.
     /// - Every constant symbol is a term
     /// - if `f` is an arity `m` function symbol and `a,b,...,m` are terms then `f(a,b,...,m)` is a term.
     /// <div class="warning">
-    /// Do not use whitespace to separate a term. The program will not build a parse tree of a term. Separate a term with whitespace will cause the program to treat the parts as different terms.
+    /// Do not use whitespace to separate a term. Separate a term with whitespace will cause the program to treat the parts as different terms.
     /// </div>
     ///
     /// While a term is distinct from a formula, it is necessary to include term in the `Formula` enum to facilitate the construction of a formula parse tree.
-    Term(String),
+    /// The term itself is parsed into a [`Term`] tree, see the [`term`] module.
+    Term(Term),
+    /// The `Top` `Formula` `⊤` is always true. It is parsed from `⊤` or `true`.
+    Top,
+    /// The `Bottom` `Formula` `⊥` is always false. It is parsed from `⊥` or `false`.
+    Bottom,
     /// A `Negation` `Formula` takes a form `¬ φ` where `φ` is a formula.
     Negation(Box<Formula>),
     /// A `Conjunction` `Formula` takes a form `∧ φ ψ` where `φ` and `ψ` are formulae.
@@ -46,14 +224,40 @@ pub enum Formula {
     Disjunction(Box<Formula>, Box<Formula>),
     /// A `Implication` `Formula` takes a form `→ φ ψ` where `φ` and `ψ` are formulae.  
     Implication(Box<Formula>, Box<Formula>),
-    /// A `Equivalence` `Formula` takes a form `= φ ψ` where `φ` and `ψ` are formulae.
+    /// A `Equivalence` `Formula` takes a form `= φ ψ` where `φ` and `ψ` are formulae. Between two
+    /// terms it is equality, and between two formulae it is read as a biconditional.
     Equivalence(Box<Formula>, Box<Formula>),
+    /// An `Iff` `Formula` takes a form `↔ φ ψ` where `φ` and `ψ` are formulae. It is the logical
+    /// biconditional, kept apart from the equality `=`, and is parsed from `↔` or `<->`.
+    Iff(Box<Formula>, Box<Formula>),
+    /// A `NotEqual` `Formula` takes a form `≠ φ ψ` where `φ` and `ψ` are formulae. It is parsed from `≠` or `!=`.
+    NotEqual(Box<Formula>, Box<Formula>),
     /// A `LessThan` `Formula` takes a form `< φ ψ` where `φ` and `ψ` are formulae.
     LessThan(Box<Formula>, Box<Formula>),
+    /// A `LessEqual` `Formula` takes a form `≤ φ ψ` where `φ` and `ψ` are formulae. It is parsed from `≤` or `<=`.
+    LessEqual(Box<Formula>, Box<Formula>),
+    /// A `GreaterEqual` `Formula` takes a form `≥ φ ψ` where `φ` and `ψ` are formulae. It is parsed from `≥` or `>=`.
+    GreaterEqual(Box<Formula>, Box<Formula>),
+    /// A `GreaterThan` `Formula` takes a form `> φ ψ` where `φ` and `ψ` are formulae.
+    GreaterThan(Box<Formula>, Box<Formula>),
     /// A `UniversalQuantifier` `Formula` takes a form `∀ x φ` where `φ` is a formula and `x` is a variable.
     UniversalQuantifier(String, Box<Formula>),
     /// A `ExistentialQuantifier` `Formula` takes a form `∃ x φ` where `φ` is a formula and `x` is a variable.
     ExistentialQuantifier(String, Box<Formula>),
+    /// A `Custom` `Formula` takes a form `⊆ φ ψ` where `⊆` is a binary connective registered with a
+    /// [`ParserConfig`] and `φ` and `ψ` are formulae. The first field holds the registered token.
+    Custom(String, Box<Formula>, Box<Formula>),
+    /// An `Equality` `Formula` takes a form `= a b` where `a` and `b` are terms. The parser builds
+    /// it for every `=` between two terms, which keeps `Equivalence` for the biconditional.
+    Equality(Term, Term),
+    /// A `Less` `Formula` takes a form `< a b` where `a` and `b` are terms. The parser builds it
+    /// for every `<` between two terms; `LessThan` keeps the comparison of two formulae.
+    Less(Term, Term),
+    /// A `Predicate` `Formula` takes a form `P(a,b,...,m)` where `P` is a relation symbol and
+    /// `a,b,...,m` are terms. The parser builds it for every application of a symbol that stands
+    /// where a formula is expected, while a bare name such as `P` stays a `Term`. Without
+    /// arguments it is written as the name `P`.
+    Predicate(String, Vec<Term>),
 }
 impl fmt::Display for Formula {
     /// Formats the formula in infix notation for display.
@@ -74,8 +278,80 @@ impl fmt::Display for Formula {
     ///     "(∀x((P(x)→(Q(x)∧∃y((R(y)∨S(y))))))∧((¬T(x))=(U<V)))"
     /// );
     /// ```
+    ///
+    /// The alternate flag, `{:#}`, writes the formula in prefix notation instead, which is the form
+    /// the rules of inference compare.
+    ///
+    /// ```
+    /// use first_order::Formula;
+    /// let test_formula: Formula = Formula::new("∀ x → P(x) ∧ Q(x) R(x)");
+    /// assert_eq!(format!("{test_formula:#}"), "∀ x → P(x) ∧ Q(x) R(x)");
+    /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_infix_notation())
+        if f.alternate() {
+            return notation::write_prefix(self, f);
+        }
+        notation::write_infix(self, f)
+    }
+}
+
+impl Drop for Formula {
+    /// Drops the subformulae with an explicit stack, so that dropping a deeply nested formula
+    /// cannot overflow the call stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<Formula> = Vec::new();
+        self.detach_subformulae(&mut stack);
+        while let Some(mut formula) = stack.pop() {
+            formula.detach_subformulae(&mut stack);
+        }
+    }
+}
+
+impl std::str::FromStr for Formula {
+    type Err = ParseError;
+
+    /// Parses a `Formula` from a string in prefix notation without panicking.
+    ///
+    /// This is equivalent to [`Formula::try_new`], and allows formulae to be used wherever a
+    /// `FromStr` bound is expected.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, ParseError};
+    ///
+    /// let test_formula: Formula = "∧ x y".parse()?;
+    /// assert_eq!(test_formula, Formula::new("∧ x y"));
+    /// assert!("∧ x".parse::<Formula>().is_err());
+    /// # Ok::<(), ParseError>(())
+    /// ```
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Formula::try_new(input)
+    }
+}
+
+impl TryFrom<&str> for Formula {
+    type Error = ParseError;
+
+    /// Parses a `Formula` from a string slice in prefix notation. See [`Formula::try_new`].
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Formula::try_new(input)
+    }
+}
+
+impl TryFrom<String> for Formula {
+    type Error = ParseError;
+
+    /// Parses a `Formula` from a `String` in prefix notation. See [`Formula::try_new`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula = Formula::try_from("→ P Q".to_string());
+    /// assert_eq!(test_formula, Ok(Formula::new("→ P Q")));
+    /// ```
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Formula::try_new(input)
     }
 }
 
@@ -84,6 +360,10 @@ impl Formula {
     ///
     /// # Arguments
     /// * `input` - A `String` or `&str` that represents the logical formula in prefix notation. Every terms, logical connectives, and logical quantifiers must be separated using a whitespace.
+    ///   The ASCII aliases `!`, `&`, `|`, `->`, `<->`, `forall`, and `exists` may be used in place of `¬`, `∧`, `∨`, `→`, `↔`, `∀`, and `∃`.
+    ///   Likewise, `true`, `false`, `!=`, `<=`, and `>=` may be used in place of `⊤`, `⊥`, `≠`, `≤`, and `≥`.
+    ///   The LaTeX macros `\top`, `\bot`, `\neg`, `\lnot`, `\wedge`, `\land`, `\vee`, `\lor`, `\rightarrow`, `\leftrightarrow`, `\neq`, `\ne`, `\leq`, `\le`, `\geq`, `\ge`, `\forall`, and `\exists` are accepted as well.
+    ///   Characters that merely look like a symbol, such as `⟶` (U+27F6) for `→`, are replaced with that symbol first, see [`ParserConfig::normalize_lookalikes`].
     ///
     /// # Returns
     /// A `Formula` instance representing the parsed logical formula.
@@ -93,7 +373,7 @@ impl Formula {
     ///
     /// # Example
     /// ```
-    /// use first_order::Formula;
+    /// use first_order::{Formula, Term};
     ///
     /// // Create a formula using the new function
     /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
@@ -103,358 +383,4559 @@ impl Formula {
     ///     Box::new(Formula::UniversalQuantifier(
     ///         "x".to_string(),
     ///         Box::new(Formula::Implication(
-    ///             Box::new(Formula::Term("P(x)".to_string())),
+    ///             Box::new(Formula::Predicate("P".to_string(), vec![Term::new("x")])),
     ///             Box::new(Formula::Conjunction(
-    ///                 Box::new(Formula::Term("Q(x)".to_string())),
+    ///                 Box::new(Formula::Predicate("Q".to_string(), vec![Term::new("x")])),
     ///                 Box::new(Formula::ExistentialQuantifier(
     ///                     "y".to_string(),
     ///                     Box::new(Formula::Disjunction(
-    ///                         Box::new(Formula::Term("R(y)".to_string())),
-    ///                         Box::new(Formula::Term("S(y)".to_string())),
+    ///                         Box::new(Formula::Predicate("R".to_string(), vec![Term::new("y")])),
+    ///                         Box::new(Formula::Predicate("S".to_string(), vec![Term::new("y")])),
     ///                     )),
     ///                 )),
     ///             )),
     ///         )),
     ///     )),
     ///     Box::new(Formula::Equivalence(
-    ///         Box::new(Formula::Negation(Box::new(Formula::Term(
-    ///             "T(x)".to_string(),
+    ///         Box::new(Formula::Negation(Box::new(Formula::Predicate(
+    ///             "T".to_string(),
+    ///             vec![Term::new("x")],
     ///         )))),
-    ///         Box::new(Formula::LessThan(
-    ///             Box::new(Formula::Term("U".to_string())),
-    ///             Box::new(Formula::Term("V".to_string())),
-    ///         )),
+    ///         Box::new(Formula::Less(Term::new("U"), Term::new("V"))),
     ///     )),
     /// );
     /// assert_eq!(test_formula, result);
     /// ```
     pub fn new<T: Into<String>>(input: T) -> Self {
         let input_str: String = input.into();
+        match Formula::try_new(input_str.as_str()) {
+            Ok(formula) => formula,
+            Err(error) => panic!(
+                "The input {:?} is malformed:\n{}",
+                input_str,
+                error.render(&input_str)
+            ),
+        }
+    }
+    /// Creates a new `Formula` from a string input without panicking.
+    ///
+    /// This is the fallible counterpart of [`Formula::new`], intended for inputs that are not known
+    /// to be well-formed in advance (e.g. formulae supplied by a user).
+    ///
+    /// # Arguments
+    /// * `input` - A `String` or `&str` that represents the logical formula in prefix notation. Every terms, logical connectives, and logical quantifiers must be separated using a whitespace.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` if the input is malformed.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula = Formula::try_new("∧ P(x) Q(x)");
+    /// assert_eq!(test_formula, Ok(Formula::new("∧ P(x) Q(x)")));
+    /// assert!(Formula::try_new("∧ P(x)").is_err());
+    /// ```
+    pub fn try_new<T: Into<String>>(input: T) -> Result<Self, ParseError> {
+        let input_str: String = lookalike::normalize(&input.into());
         let tokens: Vec<String> = input_str
             .split_whitespace()
             .map(String::from)
             .collect::<Vec<_>>();
         let mut parser: Parser<'_> = Parser::new(&tokens);
-        match parser.parse() {
+        parser.parse()
+    }
+    /// Parses a formula in prefix notation like [`Formula::try_new`], and records which part of the
+    /// input each subformula was parsed from, e.g. to highlight it in an editor.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the logical formula in prefix notation.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula` and the [`SpanMap`] giving the token range and
+    /// the byte range of the input of each subformula, keyed by its [`FormulaPath`], or a
+    /// `ParseError` if the input is malformed.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaPath};
+    ///
+    /// let input: &str = "∧ ∀ x P(x) ¬ Q";
+    /// let (test_formula, spans) = Formula::parse_spanned(input).unwrap();
+    /// assert_eq!(test_formula, Formula::new(input));
+    /// let body = spans.get(&FormulaPath(vec![0, 0])).unwrap();
+    /// assert_eq!(body.tokens, 3..4);
+    /// assert_eq!(&input[body.bytes.clone()], "P(x)");
+    /// ```
+    pub fn parse_spanned(input: &str) -> Result<(Formula, SpanMap), ParseError> {
+        let formula: Formula = Formula::try_new(input)?;
+        let spans: SpanMap = span::spans(&formula, &span::token_bytes(input));
+        Ok((formula, spans))
+    }
+    /// Parses a formula in prefix notation, reporting every error instead of only the first.
+    ///
+    /// Where the input is malformed, the error is recorded and the parser carries on: a missing
+    /// or unreadable operand is replaced with the placeholder term `<error>`, a token that cannot
+    /// be a quantifier variable is read as the quantifier body instead, and tokens left over after
+    /// the formula are reported once. A well-formed input gives the same formula as
+    /// [`Formula::try_new`] with no errors.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the logical formula in prefix notation.
+    ///
+    /// # Returns
+    /// The parsed `Formula`, with placeholders where the input was malformed, or `None` if nothing
+    /// could be parsed, along with every `ParseError` in the order of the input.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let (formula, errors) = Formula::parse_with_recovery("∧ → P f(x Q R");
+    /// assert_eq!(formula.unwrap().to_prefix_notation(), "∧ → P <error> Q");
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].index, 3);
+    /// assert_eq!(errors[1].index, 5);
+    ///
+    /// assert_eq!(
+    ///     Formula::parse_with_recovery("∧ P Q"),
+    ///     (Some(Formula::new("∧ P Q")), vec![])
+    /// );
+    /// ```
+    pub fn parse_with_recovery(input: &str) -> (Option<Formula>, Vec<ParseError>) {
+        let input_str: String = lookalike::normalize(input);
+        let tokens: Vec<String> = input_str
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let mut parser: Parser<'_> = Parser::recovering(&tokens);
+        let formula: Formula = parser
+            .parse()
+            .expect("a recovering parser records its errors");
+        let errors: Vec<ParseError> = parser.errors.unwrap_or_default();
+        if let Formula::Term(Term::Var(name)) = &formula
+            && name == RECOVERED
+            && !errors.is_empty()
+        {
+            return (None, errors);
+        }
+        (Some(formula), errors)
+    }
+    /// Creates a new `Formula` from a string in prefix notation that may omit whitespace.
+    ///
+    /// Connectives, relations, quantifiers, and truth constants are recognised as tokens even
+    /// when glued to a term, while identifier characters, digits, arithmetic symbols, and
+    /// parentheses stay inside a single term. A term ends at whitespace, at a symbol, or after a
+    /// closing parenthesis that is not followed by an arithmetic symbol. Other adjacent terms
+    /// must still be separated by whitespace.
+    ///
+    /// # Arguments
+    /// * `input` - A `String` or `&str` that represents the logical formula in prefix notation.
+    ///
+    /// # Returns
+    /// A new `Formula` instance.
+    ///
+    /// # Panics
+    /// Panics if the input is malformed. Use [`Formula::try_new_lexed`] to handle the error instead.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula = Formula::new_lexed("∧∀x→P(x)Q R");
+    /// assert_eq!(test_formula, Formula::new("∧ ∀ x → P(x) Q R"));
+    /// ```
+    pub fn new_lexed<T: Into<String>>(input: T) -> Self {
+        let input_str: String = input.into();
+        match Formula::try_new_lexed(input_str.as_str()) {
             Ok(formula) => formula,
-            Err(_) => panic!("The input {:?} is malformed.", input_str),
+            Err(error) => panic!("The input {:?} is malformed: {error}.", input_str),
         }
     }
-    /// Converts the formula itself prefix notation.
-    ///
-    /// This method returns a string representation of the formula in prefix notation,
-    /// which is a way of writing logical expressions where the operator precedes its operands.
-    ///
-    /// # Returns
-    /// A `String` representing the formula in prefix notation
-    ///
-    /// # Example
-    /// ```
-    /// use first_order::Formula;
-    ///     let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
-    /// assert_eq!(
-    ///     test_formula.to_prefix_notation(),
-    ///     "∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V"
-    /// );
-    /// ```
-    pub fn to_prefix_notation(&self) -> String {
-        match self {
-            Formula::Term(s) => format!("{s}"),
-            Formula::Negation(formula) => format!("¬ {}", formula.to_prefix_notation()),
-            Formula::Conjunction(lhs, rhs) => {
-                format!(
-                    "∧ {} {}",
-                    lhs.to_prefix_notation(),
-                    rhs.to_prefix_notation()
-                )
-            }
-            Formula::Disjunction(lhs, rhs) => format!(
-                "∨ {} {}",
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation()
-            ),
-            Formula::Implication(lhs, rhs) => format!(
-                "→ {} {}",
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation()
-            ),
-            Formula::Equivalence(lhs, rhs) => format!(
-                "= {} {}",
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation()
-            ),
-            Formula::LessThan(lhs, rhs) => format!(
-                "< {} {}",
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation()
-            ),
-            Formula::UniversalQuantifier(variable, formula) => {
-                format!("∀ {} {}", variable, formula.to_prefix_notation())
-            }
-            Formula::ExistentialQuantifier(variable, formula) => {
-                format!("∃ {} {}", variable, formula.to_prefix_notation())
-            }
+    /// Creates a new `Formula` from a string in prefix notation that may omit whitespace, without
+    /// panicking.
+    ///
+    /// This is the fallible counterpart of [`Formula::new_lexed`]. The token indices reported in
+    /// a `ParseError` refer to the lexed tokens.
+    ///
+    /// # Arguments
+    /// * `input` - A `String` or `&str` that represents the logical formula in prefix notation.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` if the input is malformed.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::try_new_lexed("¬<x y"), Formula::try_new("¬ < x y"));
+    /// assert!(Formula::try_new_lexed("∧P(x)").is_err());
+    /// ```
+    pub fn try_new_lexed<T: Into<String>>(input: T) -> Result<Self, ParseError> {
+        let input_str: String = lookalike::normalize(&input.into());
+        let tokens: Vec<String> = lexer::lex(&input_str);
+        let mut parser: Parser<'_> = Parser::new(&tokens);
+        parser.parse()
+    }
+    /// Creates a new `Formula` from a string in infix notation.
+    ///
+    /// The parser accepts the output of [`Formula::to_infix_notation`] as well as conventional
+    /// input without redundant parentheses. From the tightest to the loosest binding, the
+    /// precedence is `<`, then `¬` and the quantifiers, then `∧`, `∨`, `→` and `=`. `∧`, `∨` and `=`
+    /// are left-associative while `→` is right-associative. A quantifier is written as `∀x(φ)` or
+    /// `∀ x φ`. With a parenthesised body it is an atom, so it may be an operand of `<`, and
+    /// otherwise it binds as tightly as `¬`.
+    ///
+    /// Whitespace is optional. Parentheses enclosing a connective, relation, or quantifier group a
+    /// subformula, while any other parentheses are kept as part of a term, e.g. `fib(index+1)`.
+    /// Lookalike characters are replaced like in [`Formula::new`].
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the logical formula in infix notation.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` whose index refers to the
+    /// infix tokens of `input`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+    /// assert_eq!(
+    ///     Formula::from_infix("(∀x((P(x)→(Q(x)∧∃y((R(y)∨S(y))))))∧((¬T(x))=(U<V)))"),
+    ///     Ok(test_formula.clone())
+    /// );
+    /// assert_eq!(
+    ///     Formula::from_infix("∀x(P(x) → Q(x) ∧ ∃y(R(y) ∨ S(y))) ∧ (¬T(x) = U < V)"),
+    ///     Ok(test_formula)
+    /// );
+    /// ```
+    pub fn from_infix(input: &str) -> Result<Self, ParseError> {
+        infix::parse(&lookalike::normalize(input))
+    }
+    /// Parses the infix notation of the formula back into a `Formula`.
+    ///
+    /// The output of [`Formula::to_infix_notation`] and `Display` is guaranteed to be read back by
+    /// [`Formula::from_infix`] into a structurally equal formula, provided that its quantified
+    /// variables and its terms contain no whitespace or logical symbols, and that it contains no
+    /// `Custom` connective registered with a [`ParserConfig`]. [`Term::parse`] rejects such
+    /// symbols, so the terms read by the other parsers of this crate always qualify, and a proof
+    /// logged in infix notation can be reloaded as long as its quantified variables are plain
+    /// names. A quantified variable, or an atom quoted in an s-expression, is not checked, so
+    /// `∀ x=y P` does not round-trip.
+    ///
+    /// # Returns
+    /// A `Result` containing the reparsed `Formula`, which equals `self`, or a `ParseError` if the
+    /// guarantee does not hold for this formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x ¬ ∃ y < x y");
+    /// assert_eq!(test_formula.roundtrip_infix(), Ok(test_formula));
+    /// ```
+    pub fn roundtrip_infix(&self) -> Result<Self, ParseError> {
+        Formula::from_infix(&self.to_infix_notation())
+    }
+    /// Creates a new `Formula` from an s-expression such as `(and (forall x (P x)) (not r))`.
+    ///
+    /// Each connective, relation, and quantifier is written as a list headed by its keyword: `not`,
+    /// `and`, `or`, `implies`, `=`, `!=`, `<`, `<=`, `>=`, `>`, `forall`, and `exists`, while `true`
+    /// and `false` stand for `⊤` and `⊥`. A custom connective `⊆` is written `(custom ⊆ φ ψ)`. A
+    /// list headed by any other atom applies a function, and any other atom is parsed as a term.
+    /// Atoms may be quoted with `"` to include whitespace.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the logical formula as an s-expression.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` if the input is malformed. The
+    /// index of the error counts parentheses and atoms.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula = Formula::from_sexpr("(and (forall x (implies (P x) (Q x))) (not r))");
+    /// assert_eq!(test_formula, Ok(Formula::new("∧ ∀ x → P(x) Q(x) ¬ r")));
+    /// ```
+    pub fn from_sexpr(input: &str) -> Result<Self, ParseError> {
+        sexpr::parse(input)
+    }
+    /// Creates a new `Formula` from a string in postfix notation, such as `P(x) Q ∧ ¬`.
+    ///
+    /// The tokens are those of the prefix notation, but every connective comes after its operands,
+    /// and a quantifier comes after its variable and its body, as written by
+    /// [`Formula::to_postfix_notation`]. `Custom` connectives are not recognised.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the logical formula in postfix notation.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` if a connective lacks an
+    /// operand, or if more than one formula is left once the input is read.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula = Formula::from_postfix("x P(x) Q(x) → ∀ R ∧");
+    /// assert_eq!(test_formula, Ok(Formula::new("∧ ∀ x → P(x) Q(x) R")));
+    /// assert!(Formula::from_postfix("P ∧").is_err());
+    /// ```
+    pub fn from_postfix(input: &str) -> Result<Self, ParseError> {
+        postfix::parse(input)
+    }
+    /// Creates a new `Formula` from a string in prefix notation, rejecting any function or
+    /// relation symbol that does not agree with `signature`.
+    ///
+    /// # Arguments
+    /// * `input` - A `String` or `&str` that represents the logical formula in prefix notation.
+    /// * `signature` - The `Signature` declaring the symbols the formula may use.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Formula`, or a `ParseError` located at the first term that
+    /// is malformed or does not agree with the signature.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Signature};
+    ///
+    /// let mut sig = Signature::new();
+    /// sig.function("fib", 1);
+    ///
+    /// assert!(Formula::try_new_with_signature("= x fib(n)", &sig).is_ok());
+    /// assert!(Formula::try_new_with_signature("= x fib(n,1)", &sig).is_err());
+    /// ```
+    pub fn try_new_with_signature<T: Into<String>>(
+        input: T,
+        signature: &Signature,
+    ) -> Result<Self, ParseError> {
+        let input_str: String = lookalike::normalize(&input.into());
+        let tokens: Vec<String> = input_str
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let mut parser: Parser<'_> = Parser::with_signature(&tokens, signature);
+        parser.parse()
+    }
+    /// Converts the formula to an s-expression.
+    ///
+    /// The output is read back into the same formula by [`Formula::from_sexpr`]. Function
+    /// applications are written as lists, e.g. `(P x)`, while other terms are written as atoms in
+    /// their usual spelling, quoted when necessary.
+    ///
+    /// # Returns
+    /// A `String` representing the formula as an s-expression.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) Q(x) ¬ r");
+    /// assert_eq!(
+    ///     test_formula.to_sexpr(),
+    ///     "(and (forall x (implies (P x) (Q x))) (not r))"
+    /// );
+    /// ```
+    pub fn to_sexpr(&self) -> String {
+        sexpr::write(self)
+    }
+    /// Encodes the formula in the compact binary format described in the [`binary`] module.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` holding the magic byte followed by the encoded formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("¬ ⊥");
+    /// assert_eq!(test_formula.to_bytes(), vec![0xF1, 0x03, 0x02]);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(self)
+    }
+    /// Decodes a formula encoded by [`Formula::to_bytes`].
+    ///
+    /// Decoding rebuilds the exact tree without going through any parser, and never panics on
+    /// malformed input.
+    ///
+    /// # Arguments
+    /// * `bytes` - The encoded formula, starting with the magic byte.
+    ///
+    /// # Returns
+    /// A `Result` containing the decoded `Formula`, or a `DecodeError` if the bytes are not a single
+    /// encoded formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new(
+    ///     "∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input",
+    /// );
+    /// let bytes: Vec<u8> = test_formula.to_bytes();
+    /// assert_eq!(Formula::from_bytes(&bytes), Ok(test_formula));
+    /// assert!(Formula::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        binary::decode(bytes)
+    }
+    /// Converts the formula itself prefix notation.
+    ///
+    /// This method returns a string representation of the formula in prefix notation,
+    /// which is a way of writing logical expressions where the operator precedes its operands.
+    /// The formula is written without recursion, so formulae of any depth can be converted.
+    ///
+    /// # Returns
+    /// A `String` representing the formula in prefix notation
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///     let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+    /// assert_eq!(
+    ///     test_formula.to_prefix_notation(),
+    ///     "∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V"
+    /// );
+    /// ```
+    pub fn to_prefix_notation(&self) -> String {
+        notation::to_prefix_notation(self)
+    }
+    /// Converts the formula to prefix notation, writing a run of quantifiers of the same kind as a
+    /// single quantifier followed by all of its variables, e.g. `∀ x y φ` for `∀ x ∀ y φ`.
+    ///
+    /// The output is read back with [`ParserConfig::multi_variable_quantifiers`] enabled, unless a
+    /// quantifier body is an identifier such as `P`, which that shorthand reads as a variable.
+    ///
+    /// # Returns
+    /// A `String` representing the formula in prefix notation with grouped quantifiers
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x ∀ y ∃ z ∧ < x z < y z");
+    /// assert_eq!(
+    ///     test_formula.to_prefix_notation_grouped(),
+    ///     "∀ x y ∃ z ∧ < x z < y z"
+    /// );
+    /// ```
+    pub fn to_prefix_notation_grouped(&self) -> String {
+        notation::to_prefix_notation_grouped(self)
+    }
+    /// Converts the formula to postfix notation, which [`Formula::from_postfix`] reads back.
+    ///
+    /// # Returns
+    /// A `String` representing the formula in postfix notation
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) Q(x) ¬ R");
+    /// assert_eq!(test_formula.to_postfix_notation(), "x P(x) Q(x) → ∀ R ¬ ∧");
+    /// ```
+    pub fn to_postfix_notation(&self) -> String {
+        notation::to_postfix_notation(self)
+    }
+    /// Writes the formula in prefix notation into `w`, token by token.
+    ///
+    /// This writes the same text as [`Formula::to_prefix_notation`] without building any
+    /// intermediate `String`, so a formula can be written straight into a file, a formatter or a
+    /// buffer that is reused between formulae.
+    ///
+    /// # Arguments
+    /// * `w` - The target to write to, e.g. a `String` or a `fmt::Formatter`.
+    ///
+    /// # Returns
+    /// A `fmt::Result`, which is only an error if `w` returns one.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let mut output: String = String::from("lemma: ");
+    /// Formula::new("→ P(x) ∨ Q R").write_prefix(&mut output).unwrap();
+    /// assert_eq!(output, "lemma: → P(x) ∨ Q R");
+    /// ```
+    pub fn write_prefix(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        notation::write_prefix(self, w)
+    }
+    /// Converts the formula itself infix notation.
+    ///
+    /// This method returns a string representation of the formula in infix notation,
+    /// which is a way of writing logical expressions where operators are placed between
+    /// their operands. This format is commonly used in mathematical expressions and is
+    /// more familiar to users.
+    ///
+    /// Every connective and relation is enclosed in parentheses, so the output can be parsed back
+    /// with [`Formula::from_infix`], see [`Formula::roundtrip_infix`]. A `Custom` connective is
+    /// written between its operands like the built-in ones, e.g. `(A⊆B)`. Like
+    /// [`Formula::to_prefix_notation`], this does not recurse, and `Display` writes the same text.
+    ///
+    /// # Returns
+    /// A `String` representing the formula in infix notation
+    ///
+    /// # Examples
+    /// ```
+    /// use first_order::Formula;
+    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+    /// assert_eq!(
+    ///    format!("{test_formula}"),
+    ///    "(∀x((P(x)→(Q(x)∧∃y((R(y)∨S(y))))))∧((¬T(x))=(U<V)))"
+    /// );
+    /// ```
+    pub fn to_infix_notation(&self) -> String {
+        notation::to_infix_notation(self)
+    }
+    /// Writes the formula in infix notation into `w`, token by token.
+    ///
+    /// This writes the same text as [`Formula::to_infix_notation`] and `Display` without building
+    /// any intermediate `String`.
+    ///
+    /// # Arguments
+    /// * `w` - The target to write to, e.g. a `String` or a `fmt::Formatter`.
+    ///
+    /// # Returns
+    /// A `fmt::Result`, which is only an error if `w` returns one.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let mut output: String = String::new();
+    /// Formula::new("→ P(x) ∨ Q R").write_infix(&mut output).unwrap();
+    /// assert_eq!(output, "(P(x)→(Q∨R))");
+    /// ```
+    pub fn write_infix(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        notation::write_infix(self, w)
+    }
+    /// Converts the formula to fully parenthesized infix notation, writing its terms as `options`
+    /// say.
+    ///
+    /// # Arguments
+    /// * `options` - How the terms are written. The default options write the formula as
+    ///   `Display` does.
+    ///
+    /// # Returns
+    /// The formula in infix notation, with its terms written with the given options.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, PrintOptions, TermPrintOptions};
+    ///
+    /// let test_formula: Formula = Formula::new("= x r+y*q");
+    /// let mut terms: TermPrintOptions = TermPrintOptions::new();
+    /// terms.spaced_operators(true).multiplication_dot(true);
+    /// let mut options: PrintOptions = PrintOptions::new();
+    /// options.terms(terms);
+    /// assert_eq!(test_formula.to_string_with(&options), "(x=r + y · q)");
+    /// ```
+    pub fn to_string_with(&self, options: &PrintOptions) -> String {
+        notation::to_string_with(self, options)
+    }
+    /// Converts the formula to infix notation with only the parentheses that are needed.
+    ///
+    /// Unlike [`Formula::to_infix_notation`], a connective is only parenthesized where the
+    /// precedences and associativities of [`Formula::from_infix`] would otherwise read the output
+    /// differently: `¬` binds most tightly, then `∧`, `∨`, `→` and finally `=`. `∧` and `∨` associate
+    /// to the left and `→` to the right. The output is read back by `from_infix` into a
+    /// structurally equal formula under the same conditions as [`Formula::roundtrip_infix`].
+    ///
+    /// # Returns
+    /// A `String` representing the formula in infix notation
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+    /// assert_eq!(
+    ///     test_formula.to_infix_pretty(),
+    ///     "∀x(P(x) → Q(x) ∧ ∃y(R(y) ∨ S(y))) ∧ (¬T(x) = U < V)"
+    /// );
+    /// assert_eq!(Formula::from_infix(&test_formula.to_infix_pretty()), Ok(test_formula));
+    /// ```
+    pub fn to_infix_pretty(&self) -> String {
+        pretty::to_infix_pretty(self)
+    }
+    /// Draws the formula as an indented tree, one node per line, with box-drawing characters.
+    ///
+    /// Unlike the `Debug` output, which puts the whole formula on one line, the tree keeps large
+    /// invariants readable. Each line holds the kind of a node and its variable, symbol or term.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∀ x P(x) ¬ Q");
+    /// assert_eq!(
+    ///     test_formula.to_tree_string(),
+    ///     "Conjunction\n├─ UniversalQuantifier x\n│  └─ Predicate P(x)\n└─ Negation\n   └─ Term Q\n"
+    /// );
+    /// ```
+    pub fn to_tree_string(&self) -> String {
+        tree::to_tree_string(self, &TreeOptions::new())
+    }
+    /// Draws the formula as an indented tree with the connectors chosen by `options`.
+    ///
+    /// # Arguments
+    /// * `options` - The `TreeOptions` choosing the characters of the branches, e.g. ASCII.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, TreeOptions};
+    ///
+    /// let mut options = TreeOptions::new();
+    /// options.ascii(true);
+    /// assert_eq!(
+    ///     Formula::new("¬ < x y").to_tree_string_with(&options),
+    ///     "Negation\n`- Less < x y\n"
+    /// );
+    /// ```
+    pub fn to_tree_string_with(&self, options: &TreeOptions) -> String {
+        tree::to_tree_string(self, options)
+    }
+    /// Renders the formula as LaTeX math with the default [`LatexOptions`].
+    ///
+    /// Connectives become `\land`, `\lor`, `\rightarrow` and so on, `↔` becomes `\leftrightarrow`
+    /// while `=` stays `=`, and characters that are special in LaTeX, such as `_`, are escaped.
+    /// Parentheses are only written where they are needed.
+    ///
+    /// # Returns
+    /// A `String` to be placed in a LaTeX math environment.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x → P(x) ∧ Q(x) ↔ ¬ R(x) < y_1 2*x");
+    /// assert_eq!(
+    ///     test_formula.to_latex(),
+    ///     r"\forall x\, (P(x) \rightarrow (Q(x) \land (\neg R(x) \leftrightarrow y\_1 < 2 \cdot x)))"
+    /// );
+    /// ```
+    pub fn to_latex(&self) -> String {
+        latex::to_latex(self, &LatexOptions::new())
+    }
+    /// Renders the formula as LaTeX math as configured by `options`.
+    ///
+    /// # Arguments
+    /// * `options` - The `LatexOptions` choosing the style of the parentheses.
+    ///
+    /// # Returns
+    /// A `String` to be placed in a LaTeX math environment.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, LatexOptions};
+    ///
+    /// let mut options = LatexOptions::new();
+    /// options.parenthesize_bodies(false);
+    /// let test_formula: Formula = Formula::new("∀ x ∃ y < x y");
+    /// assert_eq!(test_formula.to_latex_with(&options), r"\forall x\, \exists y\, x < y");
+    /// ```
+    pub fn to_latex_with(&self, options: &LatexOptions) -> String {
+        latex::to_latex(self, options)
+    }
+    /// Renders the formula in plain ASCII, e.g. `forall x. (P(x) -> Q(x))`.
+    ///
+    /// `¬`, `∧`, `∨`, `→` and `↔` become `!`, `&`, `|`, `->` and `<->`, `=` stays `=`, and `≠`, `≤`
+    /// and `≥` become `!=`, `<=` and `>=`. The full mapping is documented in the `ascii` module.
+    /// Parentheses are only written where they are needed, apart from quantifier bodies, which
+    /// are always parenthesized.
+    ///
+    /// # Returns
+    /// A `String` holding the formula in ASCII, unless a `Custom` symbol or a name in a term is
+    /// not ASCII itself.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x → P(x) ∧ Q(x) ↔ ¬ R(x) ≤ y 2*x");
+    /// assert_eq!(
+    ///     test_formula.to_ascii(),
+    ///     "forall x. (P(x) -> (Q(x) & (!R(x) <-> y <= 2*x)))"
+    /// );
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        ascii::to_ascii(self)
+    }
+    /// Exports the formula as an SMT-LIB 2 script that checks its validity over the integers.
+    ///
+    /// The script declares every free variable as an `Int` constant and every function or
+    /// relation as an uninterpreted symbol, asserts the negation of the formula and ends with
+    /// `(check-sat)`, so a solver answering `unsat` proves the formula valid. The translation of
+    /// each connective and arithmetic operator is documented in the `smtlib` module.
+    ///
+    /// # Arguments
+    /// * `logic` - The SMT-LIB logic to set, e.g. `QF_NIA` for quantifier-free nonlinear integer
+    ///   arithmetic. It must be a simple SMT-LIB symbol.
+    ///
+    /// # Returns
+    /// A `Result` containing the script, or an `ExportError` naming the term or connective that
+    /// has no SMT-LIB counterpart, such as `x^2`, or the logic if it is not a symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let lemma: Formula = Formula::new("→ = x r+y*0 = x r");
+    /// assert_eq!(
+    ///     lemma.to_smtlib("QF_NIA").unwrap(),
+    ///     "(set-logic QF_NIA)\n\
+    ///      (declare-const r Int)\n\
+    ///      (declare-const x Int)\n\
+    ///      (declare-const y Int)\n\
+    ///      (assert (not (=> (= x (+ r (* y 0))) (= x r))))\n\
+    ///      (check-sat)\n"
+    /// );
+    /// assert!(Formula::new("= y x^2").to_smtlib("QF_NIA").is_err());
+    /// ```
+    pub fn to_smtlib(&self, logic: &str) -> Result<String, ExportError> {
+        smtlib::to_smtlib(self, logic)
+    }
+    /// Checks whether the formula is valid over the integers with the Z3 solver.
+    ///
+    /// The formula is exported as [`Formula::to_smtlib`] does, so every free variable is an
+    /// integer and every function or relation that is not built into SMT-LIB, such as `fib`, is
+    /// uninterpreted: the formula is valid only if it holds whatever they mean.
+    ///
+    /// # Arguments
+    /// * `cfg` - The time limit and the SMT-LIB logic of the check.
+    ///
+    /// # Returns
+    /// A `Result` containing `Validity::Valid`, `Validity::Invalid` with a counter-model, or
+    /// `Validity::Unknown` if Z3 gave up, or a `SolverError` if the formula cannot be exported or
+    /// Z3 rejects it.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, SolverConfig, Validity};
+    ///
+    /// let config: SolverConfig = SolverConfig::new();
+    /// assert_eq!(
+    ///     Formula::new("→ = x 5 < x 6").check_validity(&config).unwrap(),
+    ///     Validity::Valid
+    /// );
+    /// let Validity::Invalid(counter_model) =
+    ///     Formula::new("→ < x y < y x").check_validity(&config).unwrap()
+    /// else {
+    ///     panic!("→ < x y < y x is not valid");
+    /// };
+    /// assert!(counter_model.integer("x") < counter_model.integer("y"));
+    /// ```
+    #[cfg(feature = "z3")]
+    pub fn check_validity(&self, cfg: &SolverConfig) -> Result<Validity, SolverError> {
+        solver::check_validity(self, cfg)
+    }
+    /// Evaluates the formula in the finite `model`, with the free variables bound by `env`.
+    ///
+    /// Quantifiers range over the domain of the model, and the terms, relations and propositions
+    /// are interpreted as described in the `model` module: `<`, `=` between two terms and the
+    /// other relations consult the relation tables of the model under their symbol, and fall
+    /// back to comparing the elements as integers.
+    ///
+    /// # Arguments
+    /// * `model` - The `Model` interpreting the constants, functions and relations.
+    /// * `env` - The values of the free variables.
+    ///
+    /// # Returns
+    /// A `Result` containing whether the formula holds, or an `EvalError` if a variable is
+    /// unbound or a symbol is not interpreted by the model.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use first_order::{EvalError, Formula, Model};
+    ///
+    /// let model = Model::new(vec![0, 1, 2]);
+    /// let test_formula: Formula = Formula::new("∀ x ∃ y < x y");
+    /// assert_eq!(test_formula.holds_in(&model, &HashMap::new()), Ok(false));
+    ///
+    /// let env = HashMap::from([("y".to_string(), 1)]);
+    /// assert_eq!(Formula::new("∃ x < x y").holds_in(&model, &env), Ok(true));
+    /// assert_eq!(
+    ///     Formula::new("< x y").holds_in(&model, &env),
+    ///     Err(EvalError::UnboundVariable { name: "x".to_string() })
+    /// );
+    /// ```
+    pub fn holds_in(
+        &self,
+        model: &Model,
+        env: &HashMap<String, DomainElem>,
+    ) -> Result<bool, EvalError> {
+        model::holds_in(self, model, env)
+    }
+    /// Evaluates the quantifier-free formula on the concrete state `env`, comparing the values of
+    /// its terms as integers.
+    ///
+    /// The terms are evaluated with [`Term::eval`]. See the `arith` module for details.
+    ///
+    /// # Arguments
+    /// * `env` - The values of the variables.
+    /// * `funcs` - The functions other than the arithmetic operators, e.g. `fib` or `gcd`.
+    ///
+    /// # Returns
+    /// A `Result` containing whether the formula holds, or an `EvalError` if a term cannot be
+    /// evaluated, or the formula has a quantifier or a proposition.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use first_order::{ArithFunction, Formula};
+    ///
+    /// let test_formula: Formula = Formula::new("∧ = x r+y*q ≤ 0 r");
+    /// let env = HashMap::from([
+    ///     ("x".to_string(), 17),
+    ///     ("y".to_string(), 5),
+    ///     ("q".to_string(), 3),
+    ///     ("r".to_string(), 2),
+    /// ]);
+    /// let funcs: HashMap<String, ArithFunction> = HashMap::new();
+    /// assert_eq!(test_formula.eval_arith(&env, &funcs), Ok(true));
+    /// ```
+    pub fn eval_arith(
+        &self,
+        env: &HashMap<String, DomainElem>,
+        funcs: &HashMap<String, ArithFunction>,
+    ) -> Result<bool, EvalError> {
+        arith::eval_formula(self, env, funcs)
+    }
+    /// Builds the truth table of the quantifier-free formula, with at most
+    /// [`TruthTable::DEFAULT_LIMIT`] atoms.
+    ///
+    /// The atoms are the atomic propositions and the relations between two terms, which are
+    /// treated as opaque propositions, see [`TruthTable`].
+    ///
+    /// # Returns
+    /// A `Result` containing the `TruthTable`, or an `EvalError` if the formula has a quantifier,
+    /// a `Custom` connective, or too many atoms.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let lemma: Formula = Formula::new("→ ∧ = x r+y*q ¬ < y r = x r+y*q");
+    /// assert!(lemma.truth_table().unwrap().is_tautology());
+    /// assert!(Formula::new("∀ x P(x)").truth_table().is_err());
+    /// ```
+    pub fn truth_table(&self) -> Result<TruthTable, EvalError> {
+        truth_table::truth_table(self, TruthTable::DEFAULT_LIMIT)
+    }
+    /// Builds the truth table of the quantifier-free formula, with at most `limit` atoms.
+    ///
+    /// # Arguments
+    /// * `limit` - The largest number of atoms to accept. The table has 2^n rows for n atoms.
+    ///
+    /// # Returns
+    /// A `Result` containing the `TruthTable`, or an `EvalError` if the formula has a quantifier,
+    /// a `Custom` connective, or more than `limit` atoms.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{EvalError, Formula};
+    ///
+    /// assert_eq!(
+    ///     Formula::new("∨ P ∨ Q R").truth_table_with(2),
+    ///     Err(EvalError::TooManyAtoms { atoms: 3, limit: 2 })
+    /// );
+    /// ```
+    pub fn truth_table_with(&self, limit: usize) -> Result<TruthTable, EvalError> {
+        truth_table::truth_table(self, limit)
+    }
+    /// Returns whether the quantifier-free formula holds under some assignment to its atoms.
+    ///
+    /// The atoms are treated as opaque propositions like in [`Formula::truth_table`], and the
+    /// formula is decided by the DPLL solver of [`sat`] rather than by enumerating the
+    /// assignments, so it may have any number of atoms.
+    ///
+    /// # Returns
+    /// A `Result` containing whether the formula is satisfiable, or an `EvalError` if it has a
+    /// quantifier or a `Custom` connective.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("∧ < y r ¬ = y r").is_satisfiable(), Ok(true));
+    /// assert_eq!(Formula::new("∧ P ¬ P").is_satisfiable(), Ok(false));
+    /// ```
+    pub fn is_satisfiable(&self) -> Result<bool, EvalError> {
+        sat::is_satisfiable(self)
+    }
+    /// Expands the quantifiers of the formula over the finite `domain`, building at most 100,000
+    /// nodes.
+    ///
+    /// Each universal quantifier becomes the conjunction of its body instantiated at every
+    /// element of `domain`, and each existential quantifier the disjunction. The instances are
+    /// built by substituting the element for the variable in the terms, so the result is
+    /// quantifier-free and can be evaluated with [`Formula::holds_in`] or decided with
+    /// [`Formula::is_satisfiable`].
+    ///
+    /// # Arguments
+    /// * `domain` - The elements to instantiate each quantifier at, e.g. `&["0", "1"]`.
+    ///
+    /// # Returns
+    /// A `Result` containing the quantifier-free `Formula`, or a `GroundError` if it would be too
+    /// large.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x ∃ y < x y");
+    /// assert_eq!(
+    ///     test_formula.ground_instances(&["0", "1"]),
+    ///     Ok(Formula::new("∧ ∨ < 0 0 < 0 1 ∨ < 1 0 < 1 1"))
+    /// );
+    /// ```
+    pub fn ground_instances(&self, domain: &[&str]) -> Result<Formula, GroundError> {
+        ground::ground_instances(self, domain, ground::DEFAULT_LIMIT)
+    }
+    /// Expands the quantifiers of the formula over the finite `domain` like
+    /// [`Formula::ground_instances`], building at most `limit` nodes.
+    ///
+    /// # Arguments
+    /// * `domain` - The elements to instantiate each quantifier at.
+    /// * `limit` - The largest number of nodes, as counted by [`Formula::node_count`], to build.
+    ///
+    /// # Returns
+    /// A `Result` containing the quantifier-free `Formula`, or a `GroundError` if it would have
+    /// more than `limit` nodes.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, GroundError};
+    ///
+    /// assert_eq!(
+    ///     Formula::new("∀ x P(x)").ground_instances_with(&["0", "1", "2"], 4),
+    ///     Err(GroundError::TooLarge { nodes: 5, limit: 4 })
+    /// );
+    /// ```
+    pub fn ground_instances_with(
+        &self,
+        domain: &[&str],
+        limit: usize,
+    ) -> Result<Formula, GroundError> {
+        ground::ground_instances(self, domain, limit)
+    }
+    /// Returns whether the quantifier-free formula holds under every assignment to its atoms.
+    ///
+    /// The atoms are treated as opaque propositions like in [`Formula::truth_table`], so an
+    /// arithmetic fact such as `= x x+0` is not recognised as valid. The formula is a tautology
+    /// exactly when its negation is unsatisfiable, which is decided like in
+    /// [`Formula::is_satisfiable`].
+    ///
+    /// # Returns
+    /// A `Result` containing whether the formula is a tautology, or an `EvalError` if it has a
+    /// quantifier or a `Custom` connective.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("→ ∧ P Q P").is_tautology(), Ok(true));
+    /// assert_eq!(Formula::new("→ P Q").is_tautology(), Ok(false));
+    /// assert!(Formula::new("→ ∀ x P(x) P(y)").is_tautology().is_err());
+    /// ```
+    pub fn is_tautology(&self) -> Result<bool, EvalError> {
+        let negation: Formula = Formula::Negation(Box::new(self.clone()));
+        Ok(!sat::is_satisfiable(&negation)?)
+    }
+    /// Returns the smallest fragment of first-order logic containing the formula.
+    ///
+    /// A formula without quantifiers is [`Fragment::Propositional`] if every comparison relates
+    /// variables and constants only, and [`Fragment::QuantifierFree`] if some comparison has a
+    /// compound operand such as `fib(index)` or `r+y*q`. Any quantifier makes it
+    /// [`Fragment::FirstOrder`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Fragment};
+    ///
+    /// assert_eq!(Formula::new("∧ P < y r").fragment(), Fragment::Propositional);
+    /// assert_eq!(Formula::new("= x r+y*q").fragment(), Fragment::QuantifierFree);
+    /// assert_eq!(Formula::new("∀ x P(x)").fragment(), Fragment::FirstOrder);
+    /// ```
+    pub fn fragment(&self) -> Fragment {
+        fragment::fragment(self)
+    }
+    /// Returns whether the formula has no quantifiers, as required by [`Formula::truth_table`]
+    /// and [`Formula::is_satisfiable`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert!(Formula::new("= curFib fib(index)").is_quantifier_free());
+    /// assert!(!Formula::new("∃ x = x 1").is_quantifier_free());
+    /// ```
+    pub fn is_quantifier_free(&self) -> bool {
+        self.fragment() <= Fragment::QuantifierFree
+    }
+    /// Returns whether the formula has no quantifiers and compares variables and constants only.
+    /// See [`Formula::fragment`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert!(Formula::new("→ < y r P").is_propositional());
+    /// assert!(!Formula::new("< y r-1").is_propositional());
+    /// ```
+    pub fn is_propositional(&self) -> bool {
+        self.fragment() == Fragment::Propositional
+    }
+    /// Returns the kind of the outermost node of the formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaKind};
+    ///
+    /// assert_eq!(Formula::new("→ P Q").kind(), FormulaKind::Implication);
+    /// assert_eq!(Formula::new("= x 5").kind(), FormulaKind::Equality);
+    /// assert_eq!(Formula::new("= P ¬ Q").kind(), FormulaKind::Equivalence);
+    /// ```
+    pub fn kind(&self) -> FormulaKind {
+        kind::kind(self)
+    }
+    /// Retrieves information about the formula in an array format
+    ///
+    /// # Returns
+    /// Returns an array of three `String` values containing the following information:
+    /// - Position 0: The type of the formula, the `Display` of its [`FormulaKind`].
+    /// - Position 1: The first argument of the formula.
+    /// - Position 2: the second argument of the formula. If the formula is a term. The function will return an empty  `String`.
+    ///
+    /// `⊤` and `⊥` have no arguments, so positions 1 and 2 are empty for `Top` and `Bottom`. For a
+    /// `Custom` formula, position 0 holds the registered symbol, e.g. `⊆`. The arguments of an
+    /// `Equality` or a `Less` are its two terms, and the argument of a `Predicate` is the whole
+    /// atom, e.g. `P(x)`, as it was for the `Term` it replaces.
+    ///
+    /// # Examples
+    /// ```
+    /// use first_order::Formula;
+    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+    /// assert_eq!(
+    ///    test_formula.get_info(),
+    ///    [
+    ///        "Conjunction",
+    ///        "∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y)",
+    ///        "= ¬ T(x) < U V"
+    ///    ]
+    /// );
+    /// ```
+    pub fn get_info(&self) -> [String; 3] {
+        let info: FormulaInfo<'_> = self.info();
+        let kind: String = match info.symbol() {
+            Some(symbol) => symbol.to_string(),
+            None => info.kind().to_string(),
+        };
+        if let Some(atom) = info.atom() {
+            return match atom {
+                Atom::Predicate(_, _) => [kind, atom.to_string(), "".to_string()],
+                _ => {
+                    let terms: Vec<&Term> = atom.terms();
+                    [kind, terms[0].to_string(), terms[1].to_string()]
+                }
+            };
+        }
+        let first: String = match (info.term(), info.binder(), info.left()) {
+            (Some(term), _, _) => term.to_string(),
+            (_, Some(variable), _) => variable.to_string(),
+            (_, _, Some(lhs)) => lhs.to_prefix_notation(),
+            (None, None, None) => "".to_string(),
+        };
+        let second: String = match info.right() {
+            Some(rhs) => rhs.to_prefix_notation(),
+            None => "".to_string(),
+        };
+        [kind, first, second]
+    }
+    /// Returns the kind and parts of the outermost node, borrowed from the formula.
+    ///
+    /// This is the borrowing counterpart of [`Formula::get_info`], with the same layout: the
+    /// subformulae are returned by reference instead of being rendered in prefix notation, so
+    /// inspecting a formula does not allocate.
+    ///
+    /// # Returns
+    /// A [`FormulaInfo`] borrowing the term, variable and subformulae of the outermost node.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaKind};
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x → P(x) Q(x)");
+    /// let info = test_formula.info();
+    /// assert_eq!(info.kind(), FormulaKind::UniversalQuantifier);
+    /// assert_eq!(info.binder(), Some("x"));
+    /// assert_eq!(info.right(), Some(&Formula::new("→ P(x) Q(x)")));
+    /// ```
+    pub fn info(&self) -> FormulaInfo<'_> {
+        kind::info(self)
+    }
+    /// Moves every subformula that has subformulae of its own onto `stack`, leaving `⊤` in its
+    /// place, so that `Drop` never recurses more than one level.
+    fn detach_subformulae(&mut self, stack: &mut Vec<Formula>) {
+        let mut detach = |formula: &mut Box<Formula>| {
+            if !matches!(
+                **formula,
+                Formula::Term(_)
+                    | Formula::Top
+                    | Formula::Bottom
+                    | Formula::Equality(_, _)
+                    | Formula::Less(_, _)
+                    | Formula::Predicate(_, _)
+            ) {
+                stack.push(std::mem::replace(&mut **formula, Formula::Top));
+            }
+        };
+        match self {
+            Formula::Term(_)
+            | Formula::Top
+            | Formula::Bottom
+            | Formula::Equality(_, _)
+            | Formula::Less(_, _)
+            | Formula::Predicate(_, _) => {}
+            Formula::Negation(inner)
+            | Formula::UniversalQuantifier(_, inner)
+            | Formula::ExistentialQuantifier(_, inner) => detach(inner),
+            Formula::Conjunction(lhs, rhs)
+            | Formula::Disjunction(lhs, rhs)
+            | Formula::Implication(lhs, rhs)
+            | Formula::Equivalence(lhs, rhs)
+            | Formula::Iff(lhs, rhs)
+            | Formula::NotEqual(lhs, rhs)
+            | Formula::LessThan(lhs, rhs)
+            | Formula::LessEqual(lhs, rhs)
+            | Formula::GreaterEqual(lhs, rhs)
+            | Formula::GreaterThan(lhs, rhs)
+            | Formula::Custom(_, lhs, rhs) => {
+                detach(lhs);
+                detach(rhs);
+            }
+        }
+    }
+    /// Swaps an equality for the corresponding inequality and vice versa.
+    ///
+    /// `= a b` becomes `≠ a b` and `≠ a b` becomes `= a b`, which allows a negated loop condition
+    /// produced by the While Rule to be stated either way.
+    ///
+    /// # Returns
+    /// `Some` containing the swapped `Formula`, or `None` if the formula is neither an
+    /// `Equality`, an `Equivalence` nor a `NotEqual`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("≠ b 0");
+    /// assert_eq!(test_formula.negate_comparison(), Some(Formula::new("= b 0")));
+    /// assert_eq!(Formula::new("< b 0").negate_comparison(), None);
+    /// ```
+    pub fn negate_comparison(&self) -> Option<Formula> {
+        match self {
+            Formula::Equality(lhs, rhs) => Some(Formula::NotEqual(
+                Box::new(Formula::Term(lhs.clone())),
+                Box::new(Formula::Term(rhs.clone())),
+            )),
+            Formula::Equivalence(lhs, rhs) => Some(Formula::NotEqual(lhs.clone(), rhs.clone())),
+            Formula::NotEqual(lhs, rhs) => Some(atom::equality(lhs.clone(), rhs.clone())),
+            _ => None,
+        }
+    }
+    /// Returns the operands of the formula if it is an `Implication` `→ φ ψ`.
+    ///
+    /// # Returns
+    /// `Some((φ, ψ))` borrowing the operands, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("→ P Q");
+    /// let (lhs, rhs) = test_formula.as_implication().unwrap();
+    /// assert_eq!((lhs, rhs), (&Formula::new("P"), &Formula::new("Q")));
+    /// assert_eq!(Formula::new("∧ P Q").as_implication(), None);
+    /// ```
+    pub fn as_implication(&self) -> Option<(&Formula, &Formula)> {
+        match self {
+            Formula::Implication(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+    /// Returns the antecedent `φ` of the formula if it is an `Implication` `→ φ ψ`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("→ P Q").antecedent(), Some(&Formula::new("P")));
+    /// assert_eq!(Formula::new("∧ P Q").antecedent(), None);
+    /// ```
+    pub fn antecedent(&self) -> Option<&Formula> {
+        self.as_implication().map(|(antecedent, _)| antecedent)
+    }
+    /// Returns the consequent `ψ` of the formula if it is an `Implication` `→ φ ψ`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("→ P Q").consequent(), Some(&Formula::new("Q")));
+    /// assert_eq!(Formula::new("∧ P Q").consequent(), None);
+    /// ```
+    pub fn consequent(&self) -> Option<&Formula> {
+        self.as_implication().map(|(_, consequent)| consequent)
+    }
+    /// Splits a chain of implications into its premises followed by its conclusion.
+    ///
+    /// `→` associates to the right, so `→ A → B C` reads "A implies that B implies C" and splits
+    /// into `[A, B, C]`. Only the consequents are followed, so `→ → A B C` splits into
+    /// `[→ A B, C]`. A formula that is not an implication is a chain of length 1.
+    ///
+    /// # Returns
+    /// A `Vec` borrowing the premises in order, followed by the conclusion. [`Formula::curry`]
+    /// builds the chain back.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("→ A → ∧ B C D");
+    /// let chain: Vec<&Formula> = test_formula.implication_chain();
+    /// assert_eq!(chain, [&Formula::new("A"), &Formula::new("∧ B C"), &Formula::new("D")]);
+    /// ```
+    pub fn implication_chain(&self) -> Vec<&Formula> {
+        let mut chain: Vec<&Formula> = Vec::new();
+        let mut current: &Formula = self;
+        while let Some((antecedent, consequent)) = current.as_implication() {
+            chain.push(antecedent);
+            current = consequent;
+        }
+        chain.push(current);
+        chain
+    }
+    /// Builds the chain of implications `→ p₁ → p₂ … → pₙ conclusion` from its premises.
+    ///
+    /// This is the inverse of [`Formula::implication_chain`]: with no premises, the conclusion
+    /// is returned as it is.
+    ///
+    /// # Arguments
+    /// * `premises` - The premises, outermost first.
+    /// * `conclusion` - The formula the premises imply.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let premises: [Formula; 2] = [Formula::new("A"), Formula::new("B")];
+    /// let test_formula: Formula = Formula::curry(&premises, Formula::new("C"));
+    /// assert_eq!(test_formula, Formula::new("→ A → B C"));
+    /// ```
+    pub fn curry(premises: &[Formula], conclusion: Formula) -> Formula {
+        premises
+            .iter()
+            .rev()
+            .fold(conclusion, |consequent, premise| {
+                premise.clone().implies(consequent)
+            })
+    }
+    /// Returns whether the formula implies `other` by one of a few syntactic patterns, without
+    /// evaluating anything.
+    ///
+    /// The formula `φ` trivially implies `ψ` if:
+    /// - `φ` and `ψ` are equal (reflexivity),
+    /// - every conjunct of `ψ` is a conjunct of `φ`, as in `∧ A B` implying `A` (conjunct
+    ///   projection),
+    /// - every disjunct of `φ` is a disjunct of `ψ`, as in `A` implying `∨ A B` (disjunct
+    ///   injection),
+    /// - `φ` is `⊥` or has a conjunct `⊥`, or
+    /// - `ψ` is `⊤` or has a disjunct `⊤`.
+    ///
+    /// Conjuncts and disjuncts are those of [`Formula::conjuncts`] and [`Formula::disjuncts`], so
+    /// chains are flattened however they are grouped. A `false` result does not mean that the
+    /// implication is invalid, only that it is not one of these patterns.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let antecedent: Formula = Formula::new("∧ ∧ < 0 i < i n P(i)");
+    /// assert!(antecedent.implies_trivially(&Formula::new("∧ P(i) < 0 i")));
+    /// assert!(Formula::new("< i n").implies_trivially(&Formula::new("∨ = i n < i n")));
+    /// assert!(!antecedent.implies_trivially(&Formula::new("< 0 n")));
+    /// ```
+    pub fn implies_trivially(&self, other: &Formula) -> bool {
+        let conjuncts: Vec<&Formula> = self.conjuncts();
+        let disjuncts: Vec<&Formula> = other.disjuncts();
+        self == other
+            || conjuncts.contains(&&Formula::Bottom)
+            || disjuncts.contains(&&Formula::Top)
+            || other
+                .conjuncts()
+                .iter()
+                .all(|conjunct| conjuncts.contains(conjunct))
+            || self
+                .disjuncts()
+                .iter()
+                .all(|disjunct| disjuncts.contains(disjunct))
+    }
+    /// Returns whether the formula is an `Implication` `→ φ ψ` where `φ` trivially implies `ψ`,
+    /// as decided by [`Formula::implies_trivially`]. Such lemmas are valid whatever their atoms
+    /// mean.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert!(Formula::new("→ ∧ = x r+y*q ≤ 0 r = x r+y*q").is_trivial_implication());
+    /// assert!(Formula::new("→ ⊥ P").is_trivial_implication());
+    /// assert!(!Formula::new("→ P Q").is_trivial_implication());
+    /// assert!(!Formula::new("∧ P P").is_trivial_implication());
+    /// ```
+    pub fn is_trivial_implication(&self) -> bool {
+        match (self.antecedent(), self.consequent()) {
+            (Some(antecedent), Some(consequent)) => antecedent.implies_trivially(consequent),
+            _ => false,
+        }
+    }
+    /// Returns the operands of the formula if it is a `Conjunction` `∧ φ ψ`.
+    ///
+    /// # Returns
+    /// `Some((φ, ψ))` borrowing the operands, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ P Q");
+    /// let (lhs, rhs) = test_formula.as_conjunction().unwrap();
+    /// assert_eq!((lhs, rhs), (&Formula::new("P"), &Formula::new("Q")));
+    /// assert_eq!(Formula::new("∨ P Q").as_conjunction(), None);
+    /// ```
+    pub fn as_conjunction(&self) -> Option<(&Formula, &Formula)> {
+        match self {
+            Formula::Conjunction(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+    /// Returns the operands of the formula if it is a `Disjunction` `∨ φ ψ`.
+    ///
+    /// # Returns
+    /// `Some((φ, ψ))` borrowing the operands, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∨ P Q");
+    /// let (lhs, rhs) = test_formula.as_disjunction().unwrap();
+    /// assert_eq!((lhs, rhs), (&Formula::new("P"), &Formula::new("Q")));
+    /// assert_eq!(Formula::new("∧ P Q").as_disjunction(), None);
+    /// ```
+    pub fn as_disjunction(&self) -> Option<(&Formula, &Formula)> {
+        match self {
+            Formula::Disjunction(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+    /// Returns the operands of the formula if it is an `Equivalence` `= φ ψ`.
+    ///
+    /// An `=` between two terms is parsed into an [`Formula::Equality`] instead, whose terms
+    /// [`Formula::as_atom`] returns.
+    ///
+    /// # Returns
+    /// `Some((φ, ψ))` borrowing the operands, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("= P ¬ Q");
+    /// let (lhs, rhs) = test_formula.as_equivalence().unwrap();
+    /// assert_eq!((lhs, rhs), (&Formula::new("P"), &Formula::new("¬ Q")));
+    /// assert_eq!(Formula::new("= x 5").as_equivalence(), None);
+    /// ```
+    pub fn as_equivalence(&self) -> Option<(&Formula, &Formula)> {
+        match self {
+            Formula::Equivalence(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+    /// Returns the operands of the formula if it is a `LessThan` `< φ ψ`.
+    ///
+    /// A `<` between two terms is parsed into a [`Formula::Less`] instead, whose terms
+    /// [`Formula::as_atom`] returns.
+    ///
+    /// # Returns
+    /// `Some((φ, ψ))` borrowing the operands, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("< ¬ P Q");
+    /// let (lhs, rhs) = test_formula.as_less_than().unwrap();
+    /// assert_eq!((lhs, rhs), (&Formula::new("¬ P"), &Formula::new("Q")));
+    /// assert_eq!(Formula::new("< x 5").as_less_than(), None);
+    /// ```
+    pub fn as_less_than(&self) -> Option<(&Formula, &Formula)> {
+        match self {
+            Formula::LessThan(lhs, rhs) => Some((lhs, rhs)),
+            _ => None,
+        }
+    }
+    /// Returns a typed view of the formula if it is atomic: a comparison of two terms, or a
+    /// predicate applied to terms.
+    ///
+    /// # Returns
+    /// `Some` with the `Atom` borrowing the terms, or `None` for a connective, a quantifier, a
+    /// comparison of formulae, or a term such as `43` or `x+1` that is not a predicate.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Atom, Formula, Term};
+    ///
+    /// let test_formula: Formula = Formula::new("< index N");
+    /// assert_eq!(
+    ///     test_formula.as_atom(),
+    ///     Some(Atom::LessThan(&Term::new("index"), &Term::new("N")))
+    /// );
+    /// assert_eq!(Formula::new("P(x)").as_atom().unwrap().symbol(), "P");
+    /// assert_eq!(Formula::new("∧ P Q").as_atom(), None);
+    /// ```
+    pub fn as_atom(&self) -> Option<Atom<'_>> {
+        atom::as_atom(self)
+    }
+    /// Returns the negated formula if the formula is a `Negation` `¬ φ`.
+    ///
+    /// # Returns
+    /// `Some(φ)` borrowing the negated formula, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("¬ < y r");
+    /// assert_eq!(test_formula.as_negation(), Some(&Formula::new("< y r")));
+    /// assert_eq!(Formula::new("< y r").as_negation(), None);
+    /// ```
+    pub fn as_negation(&self) -> Option<&Formula> {
+        match self {
+            Formula::Negation(inner) => Some(inner),
+            _ => None,
+        }
+    }
+    /// Returns the variable and body of the formula if it is a `UniversalQuantifier` `∀ x φ` or
+    /// an `ExistentialQuantifier` `∃ x φ`.
+    ///
+    /// Use [`Formula::kind`] to tell the two quantifiers apart.
+    ///
+    /// # Returns
+    /// `Some((x, φ))` borrowing the variable and body, or `None` for any other kind of formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∃ y < x y");
+    /// assert_eq!(test_formula.as_quantifier(), Some(("y", &Formula::new("< x y"))));
+    /// assert_eq!(Formula::new("< x y").as_quantifier(), None);
+    /// ```
+    pub fn as_quantifier(&self) -> Option<(&str, &Formula)> {
+        match self {
+            Formula::UniversalQuantifier(variable, inner)
+            | Formula::ExistentialQuantifier(variable, inner) => Some((variable, inner)),
+            _ => None,
+        }
+    }
+    /// Returns the immediate subformulae of the formula, from left to right.
+    ///
+    /// The returned references point into the formula itself, so nothing is cloned or formatted.
+    ///
+    /// # Returns
+    /// A `Vec` that is empty for a `Term`, `⊤`, `⊥` or a typed atom such as `= x 5`, holds one
+    /// formula for a negation or a quantifier, and two for any binary connective or relation.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("→ ∀ x P(x) ¬ Q");
+    /// assert_eq!(
+    ///     test_formula.children(),
+    ///     [&Formula::new("∀ x P(x)"), &Formula::new("¬ Q")]
+    /// );
+    /// assert_eq!(test_formula.children()[0].children(), [&Formula::new("P(x)")]);
+    /// assert!(Formula::new("P(x)").children().is_empty());
+    /// ```
+    pub fn children(&self) -> Vec<&Formula> {
+        match self {
+            Formula::Term(_)
+            | Formula::Top
+            | Formula::Bottom
+            | Formula::Equality(_, _)
+            | Formula::Less(_, _)
+            | Formula::Predicate(_, _) => Vec::new(),
+            Formula::Negation(inner)
+            | Formula::UniversalQuantifier(_, inner)
+            | Formula::ExistentialQuantifier(_, inner) => vec![inner],
+            Formula::Conjunction(lhs, rhs)
+            | Formula::Disjunction(lhs, rhs)
+            | Formula::Implication(lhs, rhs)
+            | Formula::Equivalence(lhs, rhs)
+            | Formula::Iff(lhs, rhs)
+            | Formula::NotEqual(lhs, rhs)
+            | Formula::LessThan(lhs, rhs)
+            | Formula::LessEqual(lhs, rhs)
+            | Formula::GreaterEqual(lhs, rhs)
+            | Formula::GreaterThan(lhs, rhs)
+            | Formula::Custom(_, lhs, rhs) => vec![lhs, rhs],
+        }
+    }
+    /// Returns an iterator over the formula and all of its subformulae in prefix order.
+    ///
+    /// Every node is yielded once, so the iterator yields [`Formula::node_count`] formulae, and a
+    /// subformula occurring twice is yielded twice.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("→ ∀ x P(x) ¬ Q");
+    /// let subformulae: Vec<String> = test_formula
+    ///     .subformulae()
+    ///     .map(Formula::to_prefix_notation)
+    ///     .collect();
+    /// assert_eq!(subformulae, ["→ ∀ x P(x) ¬ Q", "∀ x P(x)", "P(x)", "¬ Q", "Q"]);
+    /// ```
+    pub fn subformulae(&self) -> Subformulae<'_> {
+        Subformulae::new(self)
+    }
+    /// Returns the subformula at `path`, or `None` if the path leaves the formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaPath};
+    ///
+    /// let test_formula: Formula = Formula::new("∧ P ∀ x Q(x)");
+    /// assert_eq!(test_formula.get_at(&FormulaPath(vec![1, 0])), Some(&Formula::new("Q(x)")));
+    /// assert_eq!(test_formula.get_at(&FormulaPath(vec![2])), None);
+    /// ```
+    pub fn get_at(&self, path: &FormulaPath) -> Option<&Formula> {
+        path::get_at(self, path)
+    }
+    /// Rebuilds the formula with the subformula at `path` replaced by `new`.
+    ///
+    /// Only the subformulae on the path are rebuilt, and the rest of the formula is copied.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the subformula to replace.
+    /// * `new` - The formula to put in its place.
+    ///
+    /// # Returns
+    /// A `Result` containing the rebuilt formula, or a [`PathError`] if the path leaves the
+    /// formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaPath};
+    ///
+    /// let test_formula: Formula = Formula::new("∧ P ∀ x Q(x)");
+    /// assert_eq!(
+    ///     test_formula.replace_at(&FormulaPath(vec![1, 0]), Formula::new("¬ R(x)")),
+    ///     Ok(Formula::new("∧ P ∀ x ¬ R(x)"))
+    /// );
+    /// assert!(test_formula.replace_at(&FormulaPath(vec![0, 0]), Formula::Top).is_err());
+    /// ```
+    pub fn replace_at(&self, path: &FormulaPath, new: Formula) -> Result<Formula, PathError> {
+        path::replace_at(self, path, new)
+    }
+    /// Returns the polarity of every subformula with its path, in prefix order.
+    ///
+    /// The root is positive, and the polarity flips under `¬` and on the left of `→`. Both
+    /// operands of `=`, and everything below them, occur with both polarities. Strengthening a
+    /// positive subformula strengthens the whole formula, while strengthening a negative one
+    /// weakens it.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaPath, Polarity};
+    ///
+    /// let polarities: Vec<(FormulaPath, Polarity)> = Formula::new("→ P ¬ Q").polarities();
+    /// assert_eq!(
+    ///     polarities,
+    ///     [
+    ///         (FormulaPath(vec![]), Polarity::Positive),
+    ///         (FormulaPath(vec![0]), Polarity::Negative),
+    ///         (FormulaPath(vec![1]), Polarity::Positive),
+    ///         (FormulaPath(vec![1, 0]), Polarity::Negative),
+    ///     ]
+    /// );
+    /// ```
+    pub fn polarities(&self) -> Vec<(FormulaPath, Polarity)> {
+        polarity::polarities(self)
+    }
+    /// Returns the polarity of the subformula at `path`, or `None` if the path leaves the
+    /// formula. See [`Formula::polarities`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaPath, Polarity};
+    ///
+    /// let lemma: Formula = Formula::new("→ ∧ P ¬ Q R");
+    /// assert_eq!(lemma.polarity_at(&FormulaPath(vec![0, 1, 0])), Some(Polarity::Positive));
+    /// assert_eq!(lemma.polarity_at(&FormulaPath(vec![1, 0])), None);
+    /// ```
+    pub fn polarity_at(&self, path: &FormulaPath) -> Option<Polarity> {
+        polarity::polarity_at(self, path)
+    }
+    /// Returns the operands of the maximal chain of `∧` at the root of the formula, from left to
+    /// right.
+    ///
+    /// The chain is flattened however it is grouped, so `∧ ∧ A B C` and `∧ A ∧ B C` both have the
+    /// conjuncts `[A, B, C]`. A formula that is not a conjunction is its own only conjunct.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∧ ∧ = x r+y*q ≤ 0 r ∨ P Q ¬ R");
+    /// let conjuncts: Vec<&Formula> = test_formula.conjuncts();
+    /// assert_eq!(conjuncts.len(), 4);
+    /// assert_eq!(conjuncts[2], &Formula::new("∨ P Q"));
+    /// assert_eq!(Formula::new("∨ P Q").conjuncts(), [&Formula::new("∨ P Q")]);
+    /// ```
+    pub fn conjuncts(&self) -> Vec<&Formula> {
+        ac::spine(self, FormulaKind::Conjunction)
+    }
+    /// Returns the operands of the maximal chain of `∨` at the root of the formula, from left to
+    /// right, like [`Formula::conjuncts`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∨ < y r ∨ = y r > y r");
+    /// assert_eq!(test_formula.disjuncts().len(), 3);
+    /// ```
+    pub fn disjuncts(&self) -> Vec<&Formula> {
+        ac::spine(self, FormulaKind::Disjunction)
+    }
+    /// Builds the conjunction of `conjuncts`, leaning to the left like invariants are written.
+    ///
+    /// `[A, B, C]` becomes `∧ ∧ A B C`, so a left-leaning chain is rebuilt exactly from its
+    /// [`Formula::conjuncts`]. A single conjunct is returned as it is, and no conjuncts give `⊤`,
+    /// the conjunction of nothing.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let conjuncts: Vec<Formula> = vec![Formula::new("A"), Formula::new("B"), Formula::new("C")];
+    /// assert_eq!(Formula::from_conjuncts(conjuncts), Formula::new("∧ ∧ A B C"));
+    /// assert_eq!(Formula::from_conjuncts(vec![]), Formula::Top);
+    /// ```
+    pub fn from_conjuncts(conjuncts: Vec<Formula>) -> Formula {
+        conjuncts
+            .into_iter()
+            .reduce(Formula::and)
+            .unwrap_or(Formula::Top)
+    }
+    /// Builds the lemma `→ formula formula`, which lets the consequence rule keep a condition as
+    /// it is.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let postcondition: Formula = Formula::new("∧ = x r+y*q ≤ 0 r");
+    /// assert_eq!(
+    ///     Formula::identity_implication(&postcondition),
+    ///     Formula::new("→ ∧ = x r+y*q ≤ 0 r ∧ = x r+y*q ≤ 0 r")
+    /// );
+    /// ```
+    pub fn identity_implication(formula: &Formula) -> Formula {
+        lemma::identity_implication(formula)
+    }
+    /// Builds the lemma `→ conjunction conjunct`, keeping only the conjunct `kept_index` of
+    /// `conjunction`, counted from the left like [`Formula::conjuncts`].
+    ///
+    /// # Arguments
+    /// * `conjunction` - The conjunction to weaken.
+    /// * `kept_index` - The index of the conjunct to keep.
+    ///
+    /// # Returns
+    /// A `Result` containing the lemma, or a `LemmaError` if `conjunction` is not a conjunction or
+    /// has no conjunct `kept_index`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let conjunction: Formula = Formula::new("∧ ∧ A B C");
+    /// assert_eq!(
+    ///     Formula::weaken_left(&conjunction, 1),
+    ///     Ok(Formula::new("→ ∧ ∧ A B C B"))
+    /// );
+    /// assert!(Formula::weaken_left(&conjunction, 3).is_err());
+    /// ```
+    pub fn weaken_left(conjunction: &Formula, kept_index: usize) -> Result<Formula, LemmaError> {
+        lemma::weaken_left(conjunction, kept_index)
+    }
+    /// Builds the lemma `→ ∧ extra base base`, which drops the conjunct `extra` that a rule such
+    /// as the while rule added to `base`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let invariant: Formula = Formula::new("≤ 0 r");
+    /// assert_eq!(
+    ///     Formula::add_conjunct_implication(&invariant, &Formula::new("¬ ≤ y r")),
+    ///     Formula::new("→ ∧ ¬ ≤ y r ≤ 0 r ≤ 0 r")
+    /// );
+    /// ```
+    pub fn add_conjunct_implication(base: &Formula, extra: &Formula) -> Formula {
+        lemma::add_conjunct_implication(base, extra)
+    }
+    /// Returns whether `conjunct` is one of the [`Formula::conjuncts`] of the formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∧ A B C");
+    /// assert!(test_formula.contains_conjunct(&Formula::new("B")));
+    /// assert!(!test_formula.contains_conjunct(&Formula::new("∧ A B")));
+    /// ```
+    pub fn contains_conjunct(&self, conjunct: &Formula) -> bool {
+        self.conjuncts().contains(&conjunct)
+    }
+    /// Returns whether the formula equals `other` when `∧` and `∨` are treated as associative and
+    /// commutative.
+    ///
+    /// Each maximal chain of `∧`, and each maximal chain of `∨`, is compared as the multiset of
+    /// its operands, so the order and grouping of the operands do not matter but how often each
+    /// occurs does. Every other connective keeps the order of its operands.
+    ///
+    /// # Arguments
+    /// * `other` - The formula to compare with.
+    ///
+    /// # Returns
+    /// `true` if the formulae are equal modulo associativity and commutativity.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∧ P B Q");
+    /// assert!(test_formula.equal_modulo_ac(&Formula::new("∧ B ∧ Q P")));
+    /// assert!(!test_formula.equal_modulo_ac(&Formula::new("∧ B ∧ Q Q")));
+    /// assert!(!Formula::new("→ P B").equal_modulo_ac(&Formula::new("→ B P")));
+    /// ```
+    pub fn equal_modulo_ac(&self, other: &Formula) -> bool {
+        ac::equal_modulo_ac(self, other)
+    }
+    /// Returns the canonical form of the formula, in which every chain of `∧` or `∨` is sorted.
+    ///
+    /// Each maximal chain is flattened into its operands, which are canonicalized themselves,
+    /// sorted by the `Ord` of `Formula`, stripped of exact duplicates and regrouped to the left.
+    /// Every other connective keeps its operands in order, and quantifiers keep their variables.
+    /// Two formulae have the same canonical form exactly when they are equal modulo
+    /// associativity, commutativity and idempotence of `∧` and `∨`, so it is a key for
+    /// deduplicating lemmas, and canonicalizing twice changes nothing.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ Q ∧ P ∨ B ∧ A A");
+    /// assert_eq!(test_formula.canonicalize(), Formula::new("∧ ∧ P Q ∨ A B"));
+    /// assert_eq!(
+    ///     test_formula.canonicalize(),
+    ///     Formula::new("∧ ∧ ∨ B A Q P").canonicalize()
+    /// );
+    /// ```
+    pub fn canonicalize(&self) -> Formula {
+        ac::canonicalize(self)
+    }
+    /// Counts the occurrences of the identifier `name` in the terms and binders of the formula.
+    ///
+    /// Identifiers are compared whole, so `index` occurs in `fib(index)` but not in `index2`. A
+    /// name counts wherever it appears as a variable, a function or relation symbol, a bare
+    /// proposition or the variable of a quantifier, whether it is free or bound there.
+    ///
+    /// # Arguments
+    /// * `name` - The identifier to count.
+    ///
+    /// # Returns
+    /// The number of occurrences of `name`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ index ∧ = curFib fib(index) < index index2");
+    /// assert_eq!(test_formula.occurrences("index"), 3);
+    /// assert_eq!(test_formula.occurrences("fib"), 1);
+    /// ```
+    pub fn occurrences(&self, name: &str) -> usize {
+        path::occurrences(self, name)
+    }
+    /// Finds the subformulae in which the identifier `name` occurs, as counted by
+    /// [`Formula::occurrences`].
+    ///
+    /// # Arguments
+    /// * `name` - The identifier to look for.
+    ///
+    /// # Returns
+    /// The paths to every atomic formula and every quantifier over `name` in which `name` occurs,
+    /// in prefix order. An atomic formula in which `name` occurs several times is found once.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaPath};
+    ///
+    /// let test_formula: Formula = Formula::new("∀ index ∧ = curFib fib(index) < index index2");
+    /// assert_eq!(
+    ///     test_formula.find_occurrences("index"),
+    ///     [
+    ///         FormulaPath(vec![]),
+    ///         FormulaPath(vec![0, 0]),
+    ///         FormulaPath(vec![0, 1])
+    ///     ]
+    /// );
+    /// ```
+    pub fn find_occurrences(&self, name: &str) -> Vec<FormulaPath> {
+        path::find_occurrences(self, name)
+    }
+    /// Returns the variable bound by the formula if it is a quantifier.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("∃ y < x y").binder(), Some("y"));
+    /// assert_eq!(Formula::new("¬ ∃ y < x y").binder(), None);
+    /// ```
+    pub fn binder(&self) -> Option<&str> {
+        self.as_quantifier().map(|(variable, _)| variable)
+    }
+    /// Builds an atomic `Term` formula, such as `P(x)` or `x+1`, from its spelling.
+    ///
+    /// Together with [`Formula::and`], [`Formula::or`], [`Formula::implies`], [`Formula::iff`],
+    /// [`Formula::lt`], [`Formula::not`], [`Formula::forall`] and [`Formula::exists`], this builds
+    /// formulae without writing out the boxed variants.
+    ///
+    /// # Arguments
+    /// * `term` - A `String` or `&str` holding a single term, without whitespace.
+    ///
+    /// # Panics
+    /// Panics if `term` is not a well-formed term.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let p = |variable: &str| Formula::term(format!("P({variable})"));
+    /// let test_formula: Formula = p("x")
+    ///     .implies(
+    ///         Formula::term("Q(x)").and(
+    ///             Formula::term("R(y)")
+    ///                 .or(Formula::term("S(y)"))
+    ///                 .exists("y"),
+    ///         ),
+    ///     )
+    ///     .forall("x")
+    ///     .and(Formula::term("T(x)").not().iff(Formula::term("U").lt(Formula::term("V"))));
+    /// assert_eq!(
+    ///     test_formula,
+    ///     Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) ↔ ¬ T(x) < U V")
+    /// );
+    /// ```
+    pub fn term(term: impl Into<String>) -> Formula {
+        Formula::Term(Term::new(&term.into()))
+    }
+    /// Builds the atomic formula a term stands for where a formula is expected, as the parser
+    /// does: an application such as `P(x)` becomes a [`Formula::Predicate`], and any other term,
+    /// such as `P` or `x+1`, a `Term`.
+    ///
+    /// # Arguments
+    /// * `term` - A `String` or `&str` holding a single term, without whitespace.
+    ///
+    /// # Panics
+    /// Panics if `term` is not a well-formed term.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Term};
+    ///
+    /// assert_eq!(
+    ///     Formula::proposition("P(x)"),
+    ///     Formula::Predicate("P".to_string(), vec![Term::new("x")])
+    /// );
+    /// assert_eq!(Formula::proposition("P"), Formula::term("P"));
+    /// assert_eq!(Formula::proposition("P(x)"), Formula::new("P(x)"));
+    /// ```
+    pub fn proposition(term: impl Into<String>) -> Formula {
+        atom::proposition(Formula::term(term))
+    }
+    /// Builds the conjunction `∧ self rhs`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::term("P").and(Formula::term("Q"));
+    /// assert_eq!(test_formula, Formula::new("∧ P Q"));
+    /// ```
+    pub fn and(self, rhs: Formula) -> Formula {
+        Formula::Conjunction(
+            Box::new(atom::proposition(self)),
+            Box::new(atom::proposition(rhs)),
+        )
+    }
+    /// Builds the disjunction `∨ self rhs`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::term("P").or(Formula::term("Q"));
+    /// assert_eq!(test_formula, Formula::new("∨ P Q"));
+    /// ```
+    pub fn or(self, rhs: Formula) -> Formula {
+        Formula::Disjunction(
+            Box::new(atom::proposition(self)),
+            Box::new(atom::proposition(rhs)),
+        )
+    }
+    /// Builds the implication `→ self rhs`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::term("P").implies(Formula::term("Q"));
+    /// assert_eq!(test_formula, Formula::new("→ P Q"));
+    /// ```
+    pub fn implies(self, rhs: Formula) -> Formula {
+        Formula::Implication(
+            Box::new(atom::proposition(self)),
+            Box::new(atom::proposition(rhs)),
+        )
+    }
+    /// Builds the biconditional `↔ self rhs`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::term("P").iff(Formula::term("Q"));
+    /// assert_eq!(test_formula, Formula::new("↔ P Q"));
+    /// ```
+    pub fn iff(self, rhs: Formula) -> Formula {
+        Formula::Iff(
+            Box::new(atom::proposition(self)),
+            Box::new(atom::proposition(rhs)),
+        )
+    }
+    /// Builds the equality `= self rhs`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::term("x").equals(Formula::term("r+y*q"));
+    /// assert_eq!(test_formula, Formula::new("= x r+y*q"));
+    /// ```
+    pub fn equals(self, rhs: Formula) -> Formula {
+        atom::equality(Box::new(self), Box::new(rhs))
+    }
+    /// Builds the comparison `< self rhs`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::term("P").lt(Formula::term("Q"));
+    /// assert_eq!(test_formula, Formula::new("< P Q"));
+    /// ```
+    pub fn lt(self, rhs: Formula) -> Formula {
+        atom::less_than(Box::new(self), Box::new(rhs))
+    }
+    /// Builds the negation `¬ self`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::term("P").not(), Formula::new("¬ P"));
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Formula {
+        Formula::Negation(Box::new(atom::proposition(self)))
+    }
+    /// Negates the formula without stacking negations: the negation of `¬ φ` is `φ`, and any
+    /// other formula is wrapped in a `Negation`.
+    ///
+    /// Comparisons are wrapped like any other formula; see [`Formula::negated_with`] to flip them
+    /// instead.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("¬ < y r").negated(), Formula::new("< y r"));
+    /// assert_eq!(Formula::new("∧ P Q").negated(), Formula::new("¬ ∧ P Q"));
+    /// assert_eq!(Formula::new("= y r").negated(), Formula::new("¬ = y r"));
+    /// ```
+    pub fn negated(self) -> Formula {
+        self.negated_with(false)
+    }
+    /// Negates the formula like [`Formula::negated`], also flipping a comparison between two
+    /// terms into its complement if `flip_comparisons` is `true`.
+    ///
+    /// The complements are `=` and `≠`, `<` and `≥`, and `>` and `≤`. An `=` between formulae is
+    /// an equivalence and is wrapped instead.
+    ///
+    /// # Arguments
+    /// * `flip_comparisons` - Whether comparisons are flipped rather than wrapped.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("< y r").negated_with(true), Formula::new("≥ y r"));
+    /// assert_eq!(Formula::new("≠ b 0").negated_with(true), Formula::new("= b 0"));
+    /// assert_eq!(Formula::new("= P ¬ Q").negated_with(true), Formula::new("¬ = P ¬ Q"));
+    /// ```
+    pub fn negated_with(mut self, flip_comparisons: bool) -> Formula {
+        let take = |operand: &mut Box<Formula>| std::mem::replace(operand, Box::new(Formula::Top));
+        let operand =
+            |term: &mut Term| Box::new(Formula::Term(std::mem::replace(term, Term::IntLit(0))));
+        let comparison: bool = self
+            .children()
+            .iter()
+            .all(|operand| matches!(operand, Formula::Term(_)));
+        match &mut self {
+            Formula::Negation(inner) => *take(inner),
+            Formula::Equality(lhs, rhs) if flip_comparisons => {
+                Formula::NotEqual(operand(lhs), operand(rhs))
+            }
+            Formula::Less(lhs, rhs) if flip_comparisons => {
+                Formula::GreaterEqual(operand(lhs), operand(rhs))
+            }
+            Formula::Equivalence(lhs, rhs) if flip_comparisons && comparison => {
+                Formula::NotEqual(take(lhs), take(rhs))
+            }
+            Formula::NotEqual(lhs, rhs) if flip_comparisons && comparison => {
+                atom::equality(take(lhs), take(rhs))
+            }
+            Formula::LessThan(lhs, rhs) if flip_comparisons && comparison => {
+                Formula::GreaterEqual(take(lhs), take(rhs))
+            }
+            Formula::GreaterEqual(lhs, rhs) if flip_comparisons && comparison => {
+                atom::less_than(take(lhs), take(rhs))
+            }
+            Formula::GreaterThan(lhs, rhs) if flip_comparisons && comparison => {
+                Formula::LessEqual(take(lhs), take(rhs))
+            }
+            Formula::LessEqual(lhs, rhs) if flip_comparisons && comparison => {
+                Formula::GreaterThan(take(lhs), take(rhs))
+            }
+            _ => Formula::Negation(Box::new(atom::proposition(self))),
+        }
+    }
+    /// Returns whether one of the formula and `other` is the `Negation` of the other, in either
+    /// orientation.
+    ///
+    /// Only an explicit `¬` counts, so `≥ y r` is not recognised as the negation of `< y r`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let condition: Formula = Formula::new("< y r");
+    /// assert!(Formula::new("¬ < y r").is_negation_of(&condition));
+    /// assert!(condition.is_negation_of(&Formula::new("¬ < y r")));
+    /// assert!(!Formula::new("¬ ¬ < y r").is_negation_of(&condition));
+    /// ```
+    pub fn is_negation_of(&self, other: &Formula) -> bool {
+        self.as_negation() == Some(other) || other.as_negation() == Some(self)
+    }
+    /// Builds the universal quantification `∀ variable self`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::term("P(x)").forall("x"), Formula::new("∀ x P(x)"));
+    /// ```
+    pub fn forall(self, variable: impl Into<String>) -> Formula {
+        Formula::UniversalQuantifier(variable.into(), Box::new(atom::proposition(self)))
+    }
+    /// Builds the existential quantification `∃ variable self`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::term("P(x)").exists("x"), Formula::new("∃ x P(x)"));
+    /// ```
+    pub fn exists(self, variable: impl Into<String>) -> Formula {
+        Formula::ExistentialQuantifier(variable.into(), Box::new(atom::proposition(self)))
+    }
+    /// Checks every function and relation symbol of the formula against a `Signature`.
+    ///
+    /// See the [`signature`] module for how terms and relation applications are told apart.
+    ///
+    /// # Arguments
+    /// * `signature` - The `Signature` declaring the symbols the formula may use.
+    ///
+    /// # Returns
+    /// `Ok(())` if every symbol agrees with the signature, or the first `SignatureError` otherwise.
+    /// A permissive signature does not report undeclared symbols.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Signature};
+    ///
+    /// let mut sig = Signature::new();
+    /// sig.function("mod", 2);
+    ///
+    /// assert!(Formula::new("= mod(i,2) 0").check_against(&sig).is_ok());
+    /// assert!(Formula::new("= mod(i) 0").check_against(&sig).is_err());
+    /// assert!(Formula::new("= div(i,2) 0").check_against(&sig).is_err());
+    /// ```
+    pub fn check_against(&self, signature: &Signature) -> Result<(), SignatureError> {
+        let mut issues: Vec<SignatureError> = Vec::new();
+        signature.collect(self, &mut issues);
+        match issues.into_iter().find(|issue| signature.rejects(issue)) {
+            Some(issue) => Err(issue),
+            None => Ok(()),
+        }
+    }
+    /// Lists the undeclared symbols of the formula, which a permissive `Signature` accepts.
+    ///
+    /// # Arguments
+    /// * `signature` - The `Signature` declaring the symbols the formula may use.
+    ///
+    /// # Returns
+    /// A `Vec` of `SignatureError::UnknownSymbol`, in the order the symbols appear.
+    pub fn signature_warnings(&self, signature: &Signature) -> Vec<SignatureError> {
+        let mut issues: Vec<SignatureError> = Vec::new();
+        signature.collect(self, &mut issues);
+        issues.retain(|issue| matches!(issue, SignatureError::UnknownSymbol { .. }));
+        issues
+    }
+    /// Collects the relation symbols the formula applies, with their arities.
+    ///
+    /// A term in formula position, such as `P(x)` or `Q`, applies the relation named before its
+    /// parentheses, and a term without parentheses is a 0-ary proposition. A comparison between
+    /// two terms, such as `< y r` or `= x 5`, applies the built-in relation `<` or `=` of arity 2,
+    /// while an `=` between formulae is an equivalence and applies nothing. The result can seed a
+    /// [`Signature`].
+    ///
+    /// # Returns
+    /// A `Result` containing the arity of each relation symbol, or a
+    /// [`SignatureError::WrongArity`] for the first symbol applied with two different arities.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("→ ∧ P(x) Q < x fib(y)");
+    /// assert_eq!(
+    ///     test_formula.relation_symbols(),
+    ///     Ok(BTreeMap::from([
+    ///         ("<".to_string(), 2),
+    ///         ("P".to_string(), 1),
+    ///         ("Q".to_string(), 0),
+    ///     ]))
+    /// );
+    /// assert!(Formula::new("∨ P(x) P(x,y)").relation_symbols().is_err());
+    /// ```
+    pub fn relation_symbols(&self) -> Result<BTreeMap<String, usize>, SignatureError> {
+        signature::relation_symbols(self)
+    }
+    /// Collects the function symbols applied in the terms of the formula, with their arities.
+    ///
+    /// Only terms in term position count, such as the operands of `=` and `<` and the arguments
+    /// of a relation, so `P` in `P(fib(x))` is a relation while `fib` is a function. The arithmetic
+    /// operators are built in and not reported. A symbol applied with several arities is reported
+    /// with the arity of its first application; [`Formula::check_against`] catches the mismatch.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ = gcd(a,b) gcd(b,mod(a,b)) P(fib(a+1))");
+    /// assert_eq!(
+    ///     test_formula.function_symbols(),
+    ///     BTreeMap::from([
+    ///         ("fib".to_string(), 1),
+    ///         ("gcd".to_string(), 2),
+    ///         ("mod".to_string(), 2),
+    ///     ])
+    /// );
+    /// ```
+    pub fn function_symbols(&self) -> BTreeMap<String, usize> {
+        signature::function_symbols(self)
+    }
+    /// Collects the bare identifiers in the terms of the formula, which name constants or
+    /// variables, whether free or bound.
+    ///
+    /// Like in [`Formula::function_symbols`], only terms in term position count, so the 0-ary
+    /// proposition `Q` in `→ Q < x N` is not reported. Numeric literals are reported by
+    /// [`Formula::numeric_literals`] instead.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(
+    ///     Formula::new("→ Q ∀ x < x N+1").constant_and_variable_names(),
+    ///     BTreeSet::from(["N".to_string(), "x".to_string()])
+    /// );
+    /// ```
+    pub fn constant_and_variable_names(&self) -> BTreeSet<String> {
+        signature::constant_and_variable_names(self)
+    }
+    /// Collects the numeric literals in the terms of the formula, as written.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(
+    ///     Formula::new("∧ < 0 index = curFib fib(index+1)").numeric_literals(),
+    ///     BTreeSet::from(["0".to_string(), "1".to_string()])
+    /// );
+    /// ```
+    pub fn numeric_literals(&self) -> BTreeSet<String> {
+        signature::numeric_literals(self)
+    }
+    /// Returns the names of the variables occurring free in the formula.
+    ///
+    /// An occurrence of a variable is bound by the innermost enclosing quantifier over its name
+    /// and free if there is none. Every variable of a term counts as an occurrence, and so does a
+    /// bare identifier used as a proposition, while function symbols, relation symbols, and
+    /// numeric constants do not.
+    ///
+    /// # Returns
+    /// A `BTreeSet` holding the names of the free variables.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x P(x,y)");
+    /// assert_eq!(test_formula.free_variables(), BTreeSet::from(["y".to_string()]));
+    /// assert!(Formula::new("∀ x ∃ x P(x)").free_variables().is_empty());
+    /// ```
+    pub fn free_variables(&self) -> BTreeSet<String> {
+        variables::free(self)
+    }
+    /// Returns the names of the variables occurring free in the formula that `symbols`
+    /// classifies as variables, leaving out the constants such as `N` or `input`.
+    ///
+    /// # Arguments
+    /// * `symbols` - The `SymbolTable` classifying the names, or `None` to classify them by the
+    ///   convention alone, see [`SymbolTable`].
+    ///
+    /// # Returns
+    /// A `BTreeSet` holding the names of the free variables.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use first_order::{Formula, SymbolTable};
+    ///
+    /// let test_formula: Formula = Formula::new("∧ < index N = N input");
+    /// assert_eq!(test_formula.free_variables_with(None), BTreeSet::from(["N".to_string()]));
+    /// let mut symbols: SymbolTable = SymbolTable::new();
+    /// symbols.command("index≔index+1").constant("N");
+    /// assert_eq!(
+    ///     test_formula.free_variables_with(Some(&symbols)),
+    ///     BTreeSet::from(["index".to_string()])
+    /// );
+    /// ```
+    pub fn free_variables_with(&self, symbols: Option<&SymbolTable>) -> BTreeSet<String> {
+        symbols::free_variables(self, symbols.unwrap_or(&SymbolTable::default()))
+    }
+    /// Returns the names of the variables bound by a quantifier of the formula.
+    ///
+    /// # Returns
+    /// A `BTreeSet` holding the variable of every quantifier, whether or not it occurs in the
+    /// quantifier's body.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ P(x) ∀ y ∃ z R(y,z)");
+    /// assert_eq!(
+    ///     test_formula.bound_variables(),
+    ///     BTreeSet::from(["y".to_string(), "z".to_string()])
+    /// );
+    /// ```
+    /// Returns whether the formula is a sentence, i.e. whether no variable occurs free in it.
+    ///
+    /// Every name that is not bound counts as free, see [`Formula::free_variables`]. Use
+    /// [`Formula::assert_closed_in`] to treat some names as constants.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert!(Formula::new("∀ x P(x)").is_closed());
+    /// assert!(!Formula::new("= curFib fib(index)").is_closed());
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        self.free_variables().is_empty()
+    }
+    /// Checks that the formula is a sentence, i.e. that no variable occurs free in it.
+    ///
+    /// # Returns
+    /// `Ok(())` if the formula is closed, or the names of its free variables in sorted order.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("∀ x P(x)").assert_closed(), Ok(()));
+    /// assert_eq!(
+    ///     Formula::new("∀ x < x n").assert_closed(),
+    ///     Err(vec!["n".to_string()])
+    /// );
+    /// ```
+    pub fn assert_closed(&self) -> Result<(), Vec<String>> {
+        self.assert_closed_in(&Signature::new())
+    }
+    /// Checks that the formula is a sentence, treating the symbols that `signature` declares with
+    /// no arguments as constants rather than variables.
+    ///
+    /// A 0-ary function symbol, such as a bound `N` of a loop, and a 0-ary relation symbol, such
+    /// as a proposition `P`, are constants. Every other free name is reported.
+    ///
+    /// # Arguments
+    /// * `signature` - The `Signature` declaring the constant symbols.
+    ///
+    /// # Returns
+    /// `Ok(())` if every free name is a constant, or the names of the free variables in sorted
+    /// order otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Signature};
+    ///
+    /// let mut sig = Signature::new();
+    /// sig.function("fib", 1).function("N", 0);
+    ///
+    /// assert_eq!(Formula::new("∀ i → < i N > fib(i) 0").assert_closed_in(&sig), Ok(()));
+    /// assert_eq!(
+    ///     Formula::new("= curFib fib(index)").assert_closed_in(&sig),
+    ///     Err(vec!["curFib".to_string(), "index".to_string()])
+    /// );
+    /// ```
+    pub fn assert_closed_in(&self, signature: &Signature) -> Result<(), Vec<String>> {
+        let free: Vec<String> = self
+            .free_variables()
+            .into_iter()
+            .filter(|name| {
+                signature.function_arity(name) != Some(0)
+                    && signature.relation_arity(name) != Some(0)
+            })
+            .collect();
+        if free.is_empty() { Ok(()) } else { Err(free) }
+    }
+    pub fn bound_variables(&self) -> BTreeSet<String> {
+        variables::bound(self)
+    }
+    /// Lists the names bound by a quantifier while already bound by an enclosing quantifier,
+    /// which usually means that an invariant was written with a clashing name.
+    ///
+    /// # Returns
+    /// A `Vec` with one name for every quantifier that rebinds its variable, in the order the
+    /// quantifiers appear in prefix notation.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("∀ x ∀ x P(x)").duplicate_bindings(), ["x"]);
+    /// assert!(Formula::new("∀ x ∃ y R(x,y)").duplicate_bindings().is_empty());
+    /// ```
+    pub fn duplicate_bindings(&self) -> Vec<String> {
+        variables::duplicates(self)
+    }
+    /// Lists the suspicious quantifier bindings of the formula, which usually mean a typo.
+    ///
+    /// A quantifier is reported if its variable does not occur free in its body, or if an
+    /// enclosing quantifier already binds it. A free variable is reported if some quantifier
+    /// elsewhere binds the same name. Free and bound occurrences are told apart like in
+    /// [`Formula::free_variables`].
+    ///
+    /// # Returns
+    /// A `Vec` of [`FormulaLint`], with the quantifiers in prefix order followed by the free
+    /// variables in sorted order. It is empty for a clean formula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaLint, FormulaPath};
+    ///
+    /// assert_eq!(
+    ///     Formula::new("∧ P(i) ∀ j Q(i)").lint(),
+    ///     [FormulaLint::UnusedBinder { var: "j".to_string(), path: FormulaPath(vec![1]) }]
+    /// );
+    /// assert!(Formula::new("∀ i ∃ j < i j").lint().is_empty());
+    /// ```
+    pub fn lint(&self) -> Vec<FormulaLint> {
+        lint::lint(self)
+    }
+    /// Renames the variable bound by a quantifier, together with every occurrence it binds.
+    ///
+    /// Only the first quantifier binding `from`, in prefix order, is renamed. Occurrences bound by
+    /// a nested quantifier over the same name are left unchanged.
+    ///
+    /// # Arguments
+    /// * `from` - The variable bound by the quantifier to rename.
+    /// * `to` - The new variable, which must be read back as a variable.
+    ///
+    /// # Returns
+    /// A `Result` containing the renamed formula, or a `RenameError` if no quantifier binds
+    /// `from`, if `to` is not a variable name, or if `to` already occurs free or bound in the
+    /// scope of the quantifier.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, RenameError};
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x → P(x) Q(x,y)");
+    /// assert_eq!(test_formula.rename_bound("x", "z"), Ok(Formula::new("∀ z → P(z) Q(z,y)")));
+    /// assert_eq!(
+    ///     test_formula.rename_bound("x", "y"),
+    ///     Err(RenameError::Capture { name: "y".to_string() })
+    /// );
+    /// ```
+    pub fn rename_bound(&self, from: &str, to: &str) -> Result<Formula, RenameError> {
+        rename::rename_bound(self, from, to)
+    }
+    /// Renames bound variables so that no quantifier binds a name in `taken`, a free variable of
+    /// the formula, or a name bound by an earlier quantifier.
+    ///
+    /// A clashing variable `x` is renamed to the first of `x_1`, `x_2`, ... that occurs nowhere in
+    /// the formula and is not in `taken`. Other quantifiers keep their variables.
+    ///
+    /// # Arguments
+    /// * `taken` - The names that must not be bound, such as the variables of another formula.
+    ///
+    /// # Returns
+    /// A new `Formula` with the same meaning, whose quantifiers bind pairwise distinct names.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ∀ x P(x) ∃ y ∀ x R(x,y)");
+    /// let taken: BTreeSet<String> = BTreeSet::from(["y".to_string()]);
+    /// assert_eq!(
+    ///     test_formula.freshen_bound(&taken),
+    ///     Formula::new("∧ ∀ x P(x) ∃ y_1 ∀ x_1 R(x_1,y_1)")
+    /// );
+    /// ```
+    pub fn freshen_bound(&self, taken: &BTreeSet<String>) -> Formula {
+        rename::freshen_bound(self, taken)
+    }
+    /// Renames the free variables of the formula simultaneously, as `mapping` says.
+    ///
+    /// Every name is looked up in the original formula, so a mapping that swaps `a` and `b`
+    /// exchanges them rather than merging them, as renaming one at a time would. Quantifiers and
+    /// the occurrences they bind are kept; see [`Formula::rename_with`] to rename them too.
+    ///
+    /// # Arguments
+    /// * `mapping` - The new name of each variable to rename, which must be read back as a variable.
+    ///
+    /// # Returns
+    /// A `Result` containing the renamed formula, or a `RenameError` if a name is not a variable
+    /// name, if a quantifier would capture a renamed variable, or if two different variables
+    /// would end up with the same name.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use first_order::{Formula, RenameError};
+    ///
+    /// let test_formula: Formula = Formula::new("∧ < a b ∀ a = a b");
+    /// let swap: BTreeMap<String, String> =
+    ///     BTreeMap::from([("a".to_string(), "b".to_string()), ("b".to_string(), "a".to_string())]);
+    /// assert_eq!(
+    ///     test_formula.rename(&swap),
+    ///     Err(RenameError::Capture { name: "a".to_string() })
+    /// );
+    /// assert_eq!(
+    ///     Formula::new("< a b").rename(&swap),
+    ///     Ok(Formula::new("< b a"))
+    /// );
+    /// ```
+    pub fn rename(&self, mapping: &BTreeMap<String, String>) -> Result<Formula, RenameError> {
+        rename::rename(self, mapping, false)
+    }
+    /// Renames variables simultaneously like [`Formula::rename`], also renaming the variable of
+    /// every quantifier, and the occurrences it binds, if `binders` is `true`.
+    ///
+    /// Renaming binders too cannot capture a variable, but two different variables, free or
+    /// bound, must still not end up with the same name.
+    ///
+    /// # Arguments
+    /// * `mapping` - The new name of each variable to rename.
+    /// * `binders` - Whether quantifier variables and their bound occurrences are renamed.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ < a b ∀ a = a b");
+    /// let swap: BTreeMap<String, String> =
+    ///     BTreeMap::from([("a".to_string(), "b".to_string()), ("b".to_string(), "a".to_string())]);
+    /// assert_eq!(
+    ///     test_formula.rename_with(&swap, true),
+    ///     Ok(Formula::new("∧ < b a ∀ b = b a"))
+    /// );
+    /// ```
+    pub fn rename_with(
+        &self,
+        mapping: &BTreeMap<String, String>,
+        binders: bool,
+    ) -> Result<Formula, RenameError> {
+        rename::rename(self, mapping, binders)
+    }
+    /// Simplifies the formula with every rule of [`SimplifyOptions`], such as removing double
+    /// negations and the operands `⊤` of `∧`.
+    ///
+    /// The rewrites are purely syntactic and never change the meaning of the formula. See
+    /// [`Formula::simplify_with`] to choose the rules.
+    ///
+    /// # Returns
+    /// A new `Formula` to which no rule applies.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("→ = x r+y*0 = x r+y*0").simplify(), Formula::Top);
+    /// assert_eq!(Formula::new("→ ⊤ = x x+y*0").simplify(), Formula::new("= x x+y*0"));
+    /// ```
+    pub fn simplify(&self) -> Formula {
+        simplify::simplify(self, &SimplifyOptions::new())
+    }
+    /// Simplifies the formula with the rules enabled by `options`.
+    ///
+    /// # Arguments
+    /// * `options` - The `SimplifyOptions` choosing the rules and how deep they may fire.
+    ///
+    /// # Returns
+    /// A new `Formula` to which no enabled rule applies within the configured depth.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, SimplifyOptions};
+    ///
+    /// let mut options = SimplifyOptions::new();
+    /// options.reflexive_implication(false);
+    /// let test_formula: Formula = Formula::new("∧ ⊤ → P P");
+    /// assert_eq!(test_formula.simplify_with(&options), Formula::new("→ P P"));
+    /// ```
+    pub fn simplify_with(&self, options: &SimplifyOptions) -> Formula {
+        simplify::simplify(self, options)
+    }
+    /// Pushes every negation of the formula one level inwards, using De Morgan's laws and the
+    /// quantifier dualities, and removes double negations.
+    ///
+    /// `¬ ∧ φ ψ` becomes `∨ ¬ φ ¬ ψ`, `¬ ∨ φ ψ` becomes `∧ ¬ φ ¬ ψ`, `¬ ∀ x φ` becomes `∃ x ¬ φ`,
+    /// `¬ ∃ x φ` becomes `∀ x ¬ φ`, and `¬ ¬ φ` becomes `φ`. The negations this introduces are
+    /// not pushed further, so the steps can be followed one at a time.
+    ///
+    /// # Returns
+    /// A tuple holding the rewritten formula and whether any negation was pushed.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ ¬ ∨ < y r = y r = x r+y*q");
+    /// assert_eq!(
+    ///     test_formula.push_negations_once(),
+    ///     (Formula::new("∧ ∧ ¬ < y r ¬ = y r = x r+y*q"), true)
+    /// );
+    /// assert_eq!(Formula::new("¬ → P Q").push_negations_once().1, false);
+    /// ```
+    pub fn push_negations_once(&self) -> (Formula, bool) {
+        normal_form::push_negations_once(self)
+    }
+    /// Pushes every negation of the formula inwards as far as De Morgan's laws and the quantifier
+    /// dualities allow, removing double negations.
+    ///
+    /// This is the negation normal form, except that `→` and `=` are kept, so a negation may
+    /// remain in front of them as well as in front of atoms and relations.
+    ///
+    /// # Returns
+    /// A new `Formula` to which [`Formula::push_negations_once`] makes no change.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("¬ ∀ x ∨ P(x) ¬ → Q R");
+    /// assert_eq!(test_formula.de_morgan(), Formula::new("∃ x ∧ ¬ P(x) → Q R"));
+    /// ```
+    pub fn de_morgan(&self) -> Formula {
+        normal_form::de_morgan(self)
+    }
+    /// Rewrites every implication and equivalence of the formula using only `¬`, `∧` and `∨`.
+    ///
+    /// `→ φ ψ` becomes `∨ ¬ φ ψ`, and `↔ φ ψ` and `= φ ψ` become `∧ ∨ ¬ φ ψ ∨ ¬ ψ φ`, the
+    /// conjunction of both implications with each eliminated. The rewriting recurses under
+    /// quantifiers. An `=` whose operands are both terms, such as `= x 5`, is an equality between
+    /// values rather than an equivalence, and is left unchanged.
+    ///
+    /// # Returns
+    /// A new `Formula` without `→` or `↔`, in which `=` only relates terms.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x → = x 5 = P(x) ∧ Q R");
+    /// assert_eq!(
+    ///     test_formula.eliminate_implications(),
+    ///     Formula::new("∀ x ∨ ¬ = x 5 ∧ ∨ ¬ P(x) ∧ Q R ∨ ¬ ∧ Q R P(x)")
+    /// );
+    /// ```
+    pub fn eliminate_implications(&self) -> Formula {
+        normal_form::eliminate_implications(self)
+    }
+    /// Returns the depth of the formula, the number of nodes on its longest path from the root
+    /// to an atom.
+    ///
+    /// An atom (a `Term`, `Equality`, `Less`, `Predicate`, `⊤` or `⊥`) has depth 1. The formula is
+    /// walked without recursion, so
+    /// arbitrarily deep formulae can be measured.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("P(x)").depth(), 1);
+    /// assert_eq!(Formula::new("= x 5").depth(), 1);
+    /// assert_eq!(Formula::new("= ⊤ P").depth(), 2);
+    /// assert_eq!(Formula::new("∀ x → P(x) ¬ Q").depth(), 4);
+    /// ```
+    pub fn depth(&self) -> usize {
+        metrics::depth(self)
+    }
+    /// Returns the number of nodes of the formula, counting every connective, relation,
+    /// quantifier and atom once.
+    ///
+    /// A term is a single node however it is built, and so is an equality or comparison of two
+    /// terms, so `= x r+y*q` has one node and `= ⊤ r+y*q` three.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("= x r+y*q").node_count(), 1);
+    /// assert_eq!(Formula::new("= ⊤ r+y*q").node_count(), 3);
+    /// assert_eq!(Formula::new("∀ x → P(x) ¬ Q").node_count(), 5);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        metrics::node_count(self)
+    }
+    /// Counts the nodes of the formula by kind.
+    ///
+    /// The keys are the [`FormulaKind`] names, which [`Formula::get_info`] also uses, such as
+    /// `"Conjunction"` or `"Term"`. Every registered connective is counted under `"Custom"`. The counts add up to
+    /// [`Formula::node_count`].
+    ///
+    /// # Returns
+    /// A `BTreeMap` from each kind of node occurring in the formula to its number of occurrences.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let counts = Formula::new("∧ ¬ P ∨ ¬ Q R").connective_counts();
+    /// assert_eq!(counts["Negation"], 2);
+    /// assert_eq!(counts["Term"], 3);
+    /// assert!(!counts.contains_key("Implication"));
+    /// ```
+    pub fn connective_counts(&self) -> BTreeMap<&'static str, usize> {
+        metrics::connective_counts(self)
+    }
+    /// Returns the largest number of switches between `∀` and `∃` along any path from the root
+    /// to an atom, so `∀ x ∃ y ∀ z P` has 2 alternations.
+    ///
+    /// The count is purely syntactic: a quantifier counts as written, even under a negation or
+    /// on the left of an implication, where it would change kind if the formula were put in
+    /// prenex form.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("∀ x ∃ y ∀ z R(x,y,z)").quantifier_alternations(), 2);
+    /// assert_eq!(Formula::new("∀ x ¬ ∀ y R(x,y)").quantifier_alternations(), 0);
+    /// assert_eq!(Formula::new("P").quantifier_alternations(), 0);
+    /// ```
+    pub fn quantifier_alternations(&self) -> usize {
+        metrics::quantifier_alternations(self)
+    }
+    /// Returns the quantifier rank of the formula, the largest number of quantifiers nested along
+    /// any path from the root to an atom.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// assert_eq!(Formula::new("∧ ∀ x ∀ y R(x,y) ∃ z P(z)").quantifier_rank(), 2);
+    /// assert_eq!(Formula::new("P").quantifier_rank(), 0);
+    /// ```
+    pub fn quantifier_rank(&self) -> usize {
+        metrics::quantifier_rank(self)
+    }
+    /// Returns the quantifier prefix of the formula if it is in prenex form, i.e. if no
+    /// quantifier occurs below the leading run of quantifiers.
+    ///
+    /// # Returns
+    /// An `Option` containing each leading quantifier with its variable, outermost first, which
+    /// is empty for a quantifier-free formula, or `None` if the formula is not in prenex form.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Quantifier};
+    ///
+    /// assert_eq!(
+    ///     Formula::new("∀ x ∃ y → P(x) Q(y)").quantifier_prefix(),
+    ///     Some(vec![
+    ///         (Quantifier::Universal, "x".to_string()),
+    ///         (Quantifier::Existential, "y".to_string())
+    ///     ])
+    /// );
+    /// assert_eq!(Formula::new("→ ∀ x P(x) Q").quantifier_prefix(), None);
+    /// ```
+    pub fn quantifier_prefix(&self) -> Option<Vec<(Quantifier, String)>> {
+        metrics::quantifier_prefix(self)
+    }
+    /// Recognises the encoding of a bounded quantifier, `∀ x → < x b φ` for `∀ x < b φ` or
+    /// `∃ x ∧ < x b φ` for `∃ x < b φ`, so that it can be written in its bounded form.
+    ///
+    /// # Returns
+    /// An `Option` containing the quantifier, its variable, the bound and the body, or `None` if
+    /// the formula does not have the shape of the encoding or the bound is not a term.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Quantifier, Term};
+    ///
+    /// let test_formula: Formula = Formula::new("∀ i → < i index = out(i) fib(i)");
+    /// let (quantifier, variable, bound, body) = test_formula.as_bounded_quantifier().unwrap();
+    /// assert_eq!(quantifier, Quantifier::Universal);
+    /// assert_eq!(variable, "i");
+    /// assert_eq!(*bound, Term::new("index"));
+    /// assert_eq!(*body, Formula::new("= out(i) fib(i)"));
+    /// assert_eq!(test_formula.to_infix_pretty(), "∀i < index (out(i) = fib(i))");
+    ///
+    /// assert_eq!(Formula::new("∀ i → < index i P(i)").as_bounded_quantifier(), None);
+    /// ```
+    pub fn as_bounded_quantifier(&self) -> Option<(Quantifier, &str, &Term, &Formula)> {
+        bounded::recognize(self)
+    }
+    /// Calls the method of `visitor` matching the outermost node of the formula.
+    ///
+    /// The default methods of [`FormulaVisitor`] recurse into the subformulae, so this walks the
+    /// whole formula unless `visitor` stops it.
+    ///
+    /// # Arguments
+    /// * `visitor` - The [`FormulaVisitor`] to call.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaVisitor, Term};
+    ///
+    /// struct Atoms(usize);
+    ///
+    /// impl FormulaVisitor for Atoms {
+    ///     fn visit_term(&mut self, _term: &Term) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// let mut atoms = Atoms(0);
+    /// Formula::new("∀ x → P(x) ∧ Q R").accept(&mut atoms);
+    /// assert_eq!(atoms.0, 3);
+    /// ```
+    pub fn accept(&self, visitor: &mut (impl FormulaVisitor + ?Sized)) {
+        visitor::accept(self, visitor)
+    }
+    /// Rebuilds the formula with `folder`, by calling the method of `folder` matching its
+    /// outermost node.
+    ///
+    /// The default methods of [`FormulaFolder`] fold the subformulae and rebuild the node, so
+    /// only the nodes `folder` overrides are rewritten.
+    ///
+    /// # Arguments
+    /// * `folder` - The [`FormulaFolder`] to call.
+    ///
+    /// # Returns
+    /// The `Formula` returned by `folder`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, FormulaFolder, Term};
+    ///
+    /// struct Weaken;
+    ///
+    /// impl FormulaFolder for Weaken {
+    ///     fn fold_less(&mut self, lhs: &Term, rhs: &Term) -> Formula {
+    ///         Formula::LessEqual(Box::new(self.fold_term(lhs)), Box::new(self.fold_term(rhs)))
+    ///     }
+    /// }
+    ///
+    /// let test_formula: Formula = Formula::new("∀ i → < i n < 0 n");
+    /// assert_eq!(test_formula.fold(&mut Weaken), Formula::new("∀ i → ≤ i n ≤ 0 n"));
+    /// ```
+    pub fn fold(&self, folder: &mut (impl FormulaFolder + ?Sized)) -> Formula {
+        visitor::fold(self, folder)
+    }
+    /// Rebuilds the formula, applying `f` to the name of every variable and the spelling of every
+    /// constant in its terms.
+    ///
+    /// Only whole leaves are passed to `f`, so mapping `index` does not affect `index2`, and
+    /// function and relation symbols such as `fib` are kept. Quantifier variables are kept as
+    /// well; see [`Formula::map_terms_with`] to map them too.
+    ///
+    /// # Arguments
+    /// * `f` - A closure mapping the text of a leaf to its replacement.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ < index index2 = fib(index) 1");
+    /// let renamed: Formula = test_formula.map_terms(|leaf| match leaf {
+    ///     "index" => "i".to_string(),
+    ///     _ => leaf.to_string(),
+    /// });
+    /// assert_eq!(renamed, Formula::new("∧ < i index2 = fib(i) 1"));
+    /// ```
+    pub fn map_terms(&self, f: impl FnMut(&str) -> String) -> Formula {
+        map_terms::map_terms(self, f, false)
+    }
+    /// Rebuilds the formula like [`Formula::map_terms`], also applying `f` to the variable of
+    /// every quantifier if `quantifiers` is `true`.
+    ///
+    /// # Arguments
+    /// * `f` - A closure mapping the text of a leaf or quantifier variable to its replacement.
+    /// * `quantifiers` - Whether the variables of quantifiers are mapped.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∀ x < x n");
+    /// assert_eq!(
+    ///     test_formula.map_terms_with(str::to_uppercase, false),
+    ///     Formula::new("∀ x < X N")
+    /// );
+    /// assert_eq!(
+    ///     test_formula.map_terms_with(str::to_uppercase, true),
+    ///     Formula::new("∀ X < X N")
+    /// );
+    /// ```
+    pub fn map_terms_with(&self, f: impl FnMut(&str) -> String, quantifiers: bool) -> Formula {
+        map_terms::map_terms(self, f, quantifiers)
+    }
+    /// Rebuilds the formula, replacing each of its terms with the result of `f`.
+    ///
+    /// Unlike [`Formula::map_terms`], `f` receives every term as a whole [`Term`] tree, e.g. all
+    /// of `r+y*q` rather than its leaves. Quantifier variables are kept.
+    ///
+    /// # Arguments
+    /// * `f` - A closure mapping a term to its replacement.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Term};
+    ///
+    /// let test_formula: Formula = Formula::new("= x r+y*q");
+    /// let renamed: Formula = test_formula.map_term_trees(|term| match term {
+    ///     Term::Var(name) if name == "x" => Term::new("r+y*q"),
+    ///     _ => term.clone(),
+    /// });
+    /// assert_eq!(renamed, Formula::new("= r+y*q r+y*q"));
+    /// ```
+    pub fn map_term_trees(&self, f: impl FnMut(&Term) -> Term) -> Formula {
+        map_terms::map_term_trees(self, f)
+    }
+    /// Replaces every occurrence of each of `targets`, concrete terms such as `43`, with a fresh
+    /// variable, turning a lemma about particular values into a schema.
+    ///
+    /// Targets are matched against whole subterms, so `43` is not found in `143`. The fresh
+    /// variables are `v1`, `v2`, ... in the order of the targets, skipping every name already used
+    /// in the formula. See [`Formula::generalize_with`] to also quantify over them.
+    ///
+    /// # Arguments
+    /// * `targets` - The terms to abstract, in prefix notation.
+    ///
+    /// # Returns
+    /// The generalized `Formula`, and a map from each target that occurs to its fresh variable.
+    ///
+    /// # Panics
+    /// Panics if a target is not a well-formed term.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let lemma: Formula = Formula::new("→ = x 43 ∨ < x 44 = x 44");
+    /// let (schema, mapping) = lemma.generalize(&["43", "44"]);
+    /// assert_eq!(schema, Formula::new("→ = x v1 ∨ < x v2 = x v2"));
+    /// assert_eq!(mapping["44"], "v2");
+    /// ```
+    pub fn generalize(&self, targets: &[&str]) -> (Formula, BTreeMap<String, String>) {
+        generalize::generalize(self, targets, false)
+    }
+    /// Generalizes the formula like [`Formula::generalize`], also wrapping the result in universal
+    /// quantifiers over the fresh variables if `quantify` is `true`, the first target's outermost.
+    ///
+    /// # Arguments
+    /// * `targets` - The terms to abstract, in prefix notation.
+    /// * `quantify` - Whether the fresh variables are universally quantified.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let lemma: Formula = Formula::new("→ = x 43 ∨ < x 44 = x 44");
+    /// let (schema, _) = lemma.generalize_with(&["43", "44"], true);
+    /// assert_eq!(schema, Formula::new("∀ v1 ∀ v2 → = x v1 ∨ < x v2 = x v2"));
+    /// ```
+    pub fn generalize_with(
+        &self,
+        targets: &[&str],
+        quantify: bool,
+    ) -> (Formula, BTreeMap<String, String>) {
+        generalize::generalize(self, targets, quantify)
+    }
+    /// Replaces the free variables of the formula with their bindings in `substitution`.
+    ///
+    /// A variable used as a proposition is replaced with its binding, whatever formula it is. A
+    /// variable inside a term is only replaced if its binding is a term. Occurrences bound by a
+    /// quantifier of the formula are left alone, and a quantifier whose variable is free in a
+    /// binding inserted into its scope is first renamed to a fresh variable, such as `y_1` for
+    /// `y`, so that the binding is not captured.
+    ///
+    /// # Arguments
+    /// * `substitution` - A reference to the `Substitution` to apply, e.g. one returned by
+    ///   [`unify`].
+    ///
+    /// # Returns
+    /// The `Formula` with the substitution applied.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Substitution};
+    ///
+    /// let mut substitution: Substitution = Substitution::new();
+    /// substitution.bind("y", Formula::new("index+1"));
+    /// assert_eq!(
+    ///     Formula::new("∧ = x fib(y) ∀ y P(y)").apply(&substitution),
+    ///     Formula::new("∧ = x fib(index+1) ∀ y P(y)")
+    /// );
+    /// ```
+    pub fn apply(&self, substitution: &Substitution) -> Formula {
+        unify::apply(self, substitution)
+    }
+    /// Replaces the free occurrences of the variable `variable` with the term `replacement`, in
+    /// every term of the formula, as the assignment axiom does with the postcondition.
+    ///
+    /// Occurrences bound by a quantifier of the formula are left alone, and a quantifier over a
+    /// variable of `replacement` is renamed so as not to capture it, as with [`Formula::apply`].
+    ///
+    /// # Arguments
+    /// * `variable` - The name of the variable to replace.
+    /// * `replacement` - The `Term` to put in its place.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, Term};
+    ///
+    /// let test_formula: Formula = Formula::new("∧ = y 43 ∀ y < y 43");
+    /// assert_eq!(
+    ///     test_formula.substitute_term("y", &Term::new("x+1")),
+    ///     Formula::new("∧ = x+1 43 ∀ y < y 43")
+    /// );
+    /// ```
+    pub fn substitute_term(&self, variable: &str, replacement: &Term) -> Formula {
+        let mut substitution: Substitution = Substitution::new();
+        substitution.bind(variable, Formula::Term(replacement.clone()));
+        unify::apply(self, &substitution)
+    }
+    /// Replaces the free occurrences of `variable` with `replacement` as
+    /// [`Formula::substitute_term`] does, but only if `symbols` classifies `variable` as a
+    /// variable; a constant or function symbol is left alone.
+    ///
+    /// # Arguments
+    /// * `variable` - The name of the variable to replace.
+    /// * `replacement` - The `Term` to put in its place.
+    /// * `symbols` - The `SymbolTable` classifying the names, or `None` to classify them by the
+    ///   convention alone, see [`SymbolTable`].
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, SymbolTable, Term};
+    ///
+    /// let test_formula: Formula = Formula::new("< index N");
+    /// let mut symbols: SymbolTable = SymbolTable::new();
+    /// symbols.constant("N");
+    /// assert_eq!(
+    ///     test_formula.substitute_term_with("N", &Term::new("1"), Some(&symbols)),
+    ///     test_formula
+    /// );
+    /// symbols.variable("index");
+    /// assert_eq!(
+    ///     test_formula.substitute_term_with("index", &Term::new("1"), Some(&symbols)),
+    ///     Formula::new("< 1 N")
+    /// );
+    /// ```
+    pub fn substitute_term_with(
+        &self,
+        variable: &str,
+        replacement: &Term,
+        symbols: Option<&SymbolTable>,
+    ) -> Formula {
+        symbols::substitute_term(
+            self,
+            variable,
+            replacement,
+            symbols.unwrap_or(&SymbolTable::default()),
+        )
+    }
+    /// Normalizes every term of the formula with [`Term::normalize`], so that terms such as
+    /// `index+1-1` and `index` are written the same.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    ///
+    /// let test_formula: Formula = Formula::new("∧ = preFib fib(index+1-1) = x r+y*0");
+    /// assert_eq!(
+    ///     test_formula.normalize_terms(),
+    ///     Formula::new("∧ = preFib fib(index) = x r")
+    /// );
+    /// ```
+    pub fn normalize_terms(&self) -> Formula {
+        self.map_term_trees(normalize::normalize)
+    }
+    /// Rewrites the formula with `rules` until none applies or `max_steps` rules were applied.
+    ///
+    /// The rules are applied bottom-up, to subformulae and to subterms, in passes over the whole
+    /// formula until a pass changes nothing. At each node the first rule that applies wins. A rule
+    /// whose right-hand side is not a term is never applied inside a term. See the `rewrite`
+    /// module for details.
+    ///
+    /// # Arguments
+    /// * `rules` - The `RewriteRule`s to apply, e.g. read with [`parse_rules`].
+    /// * `max_steps` - The largest number of rule applications, which bounds the work done by
+    ///   rules that never stop applying, such as `?x => ?x+0`.
+    ///
+    /// # Returns
+    /// The rewritten `Formula`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, RewriteRule, parse_rules};
+    ///
+    /// let rules: Vec<RewriteRule> = parse_rules("?x+1-1 => ?x").unwrap();
+    /// assert_eq!(
+    ///     Formula::new("= preFib fib(index+1-1)").rewrite(&rules, 10),
+    ///     Formula::new("= preFib fib(index)")
+    /// );
+    /// ```
+    pub fn rewrite(&self, rules: &[RewriteRule], max_steps: usize) -> Formula {
+        rewrite::rewrite(self, rules, max_steps)
+    }
+}
+
+/// The logical connectives, relations, and quantifiers recognised by the prefix parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Symbol {
+    Top,
+    Bottom,
+    Negation,
+    Conjunction,
+    Disjunction,
+    Implication,
+    Equivalence,
+    Iff,
+    NotEqual,
+    LessThan,
+    LessEqual,
+    GreaterEqual,
+    GreaterThan,
+    UniversalQuantifier,
+    ExistentialQuantifier,
+}
+
+impl Symbol {
+    /// Recognises a token as a symbol, accepting the Unicode spelling, its ASCII alias, or its
+    /// LaTeX macro name.
+    ///
+    /// | Symbol | ASCII alias | LaTeX                   |
+    /// |--------|-------------|-------------------------|
+    /// | `⊤`    | `true`      | `\top`                  |
+    /// | `⊥`    | `false`     | `\bot`                  |
+    /// | `¬`    | `!`         | `\neg`, `\lnot`         |
+    /// | `∧`    | `&`         | `\wedge`, `\land`       |
+    /// | `∨`    | `\|`        | `\vee`, `\lor`          |
+    /// | `→`    | `->`        | `\rightarrow`           |
+    /// | `=`    |             |                         |
+    /// | `↔`    | `<->`       | `\leftrightarrow`       |
+    /// | `≠`    | `!=`        | `\neq`, `\ne`           |
+    /// | `<`    |             | `<`                     |
+    /// | `≤`    | `<=`        | `\leq`, `\le`           |
+    /// | `≥`    | `>=`        | `\geq`, `\ge`           |
+    /// | `>`    |             | `>`                     |
+    /// | `∀`    | `forall`    | `\forall`               |
+    /// | `∃`    | `exists`    | `\exists`               |
+    fn from_token(token: &str) -> Option<Symbol> {
+        match token {
+            "⊤" | "true" | "\\top" => Some(Symbol::Top),
+            "⊥" | "false" | "\\bot" => Some(Symbol::Bottom),
+            "¬" | "!" | "\\neg" | "\\lnot" => Some(Symbol::Negation),
+            "∧" | "&" | "\\wedge" | "\\land" => Some(Symbol::Conjunction),
+            "∨" | "|" | "\\vee" | "\\lor" => Some(Symbol::Disjunction),
+            "→" | "->" | "\\rightarrow" => Some(Symbol::Implication),
+            "=" => Some(Symbol::Equivalence),
+            "↔" | "<->" | "\\leftrightarrow" => Some(Symbol::Iff),
+            "≠" | "!=" | "\\neq" | "\\ne" => Some(Symbol::NotEqual),
+            "<" => Some(Symbol::LessThan),
+            "≤" | "<=" | "\\leq" | "\\le" => Some(Symbol::LessEqual),
+            "≥" | ">=" | "\\geq" | "\\ge" => Some(Symbol::GreaterEqual),
+            ">" => Some(Symbol::GreaterThan),
+            "∀" | "forall" | "\\forall" => Some(Symbol::UniversalQuantifier),
+            "∃" | "exists" | "\\exists" => Some(Symbol::ExistentialQuantifier),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the symbol relates two terms, such as `=` or `<`.
+    fn is_relation(self) -> bool {
+        matches!(
+            self,
+            Symbol::Equivalence
+                | Symbol::NotEqual
+                | Symbol::LessThan
+                | Symbol::LessEqual
+                | Symbol::GreaterEqual
+                | Symbol::GreaterThan
+        )
+    }
+}
+
+/// An error returned when an input cannot be parsed into a `Formula`.
+///
+/// The error records where the parser stopped and what it was looking for, so that malformed
+/// inputs can be located without re-reading the whole formula.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, ParseError};
+///
+/// let error: ParseError = Formula::try_new("∧ P(x)").unwrap_err();
+/// assert_eq!(error.index, 2);
+/// assert_eq!(error.found, None);
+/// assert_eq!(
+///     error.to_string(),
+///     "expected a formula after ∧ at token 2, found end of input"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The category of the failure.
+    pub kind: ParseErrorKind,
+    /// The index of the token at which the parser failed.
+    pub index: usize,
+    /// The offending token, or `None` if the input ended before the parser was done.
+    pub found: Option<String>,
+    /// A description of what the parser expected at `index`.
+    pub expected: String,
+    /// The line of the document on which the formula starts, if it was read by [`parse_all`] or
+    /// [`parse_document`].
+    pub line: Option<usize>,
+}
+
+/// The category of a `ParseError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The parser could not complete a formula, either because the input ended early or because
+    /// a token did not fit the grammar.
+    Malformed,
+    /// A complete formula was parsed, but tokens remained after it.
+    TrailingTokens,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        if self.kind == ParseErrorKind::TrailingTokens {
+            write!(f, "trailing tokens starting at index {}", self.index)?;
+            if let Some(token) = &self.found {
+                write!(f, ", found {token:?}")?;
+            }
+            return Ok(());
+        }
+        write!(
+            f,
+            "expected {} at token {}, found ",
+            self.expected, self.index
+        )?;
+        match &self.found {
+            Some(token) => write!(f, "{token:?}"),
+            None => write!(f, "end of input"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Renders the error under the input it was found in, with carets under the offending token.
+    ///
+    /// The first line is the line of `source` holding the offending token, and the second marks
+    /// that token with one `^` per character, followed by the error message. If the input ended
+    /// early, a single `^` is placed just after its last token. Columns are counted in
+    /// characters rather than bytes, so multi-byte symbols such as `∧` take up a single column.
+    ///
+    /// # Arguments
+    /// * `source` - The input the error was found in, split into tokens at whitespace as
+    ///   [`Formula::try_new`] does.
+    ///
+    /// # Returns
+    /// A `String` of two lines rendering the error.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, ParseError};
+    ///
+    /// let source: &str = "∧ ∀ ∧ P Q R";
+    /// let error: ParseError = Formula::try_new(source).unwrap_err();
+    /// assert_eq!(
+    ///     error.render(source),
+    ///     "∧ ∀ ∧ P Q R\n    ^ expected a variable after ∀ at token 2, found \"∧\""
+    /// );
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let tokens: Vec<std::ops::Range<usize>> = span::token_bytes(source);
+        // The offset the carets start at, and how many characters they cover.
+        let (offset, width): (usize, usize) = match tokens.get(self.index) {
+            Some(bytes) => (bytes.start, source[bytes.clone()].chars().count()),
+            None => (tokens.last().map_or(0, |bytes| bytes.end), 1),
+        };
+        let start: usize = source[..offset]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let line: &str = source[start..].lines().next().unwrap_or("");
+        // Tabs are kept, so that the carets line up however wide a tab is shown.
+        let mut indent: String = source[start..offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        if self.index >= tokens.len() && !tokens.is_empty() {
+            indent.push(' ');
+        }
+        format!("{line}\n{indent}{} {self}", "^".repeat(width))
+    }
+}
+
+/// Returns whether `name` is an identifier that parses back as the same variable.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && name != "true"
+        && name != "false"
+}
+
+/// Parses `token`, found at token `index`, into an atomic `Formula::Term`.
+pub(crate) fn parse_term(token: &str, index: usize) -> Result<Formula, ParseError> {
+    match Term::parse(token) {
+        Ok(term) => Ok(Formula::Term(term)),
+        Err(error) => Err(ParseError {
+            kind: ParseErrorKind::Malformed,
+            index,
+            found: Some(token.to_string()),
+            expected: format!("a well-formed term ({error})"),
+            line: None,
+        }),
+    }
+}
+
+/// A binary connective of `Formula`, such as `Formula::Conjunction`.
+type Connective = fn(Box<Formula>, Box<Formula>) -> Formula;
+
+/// A quantifier of `Formula`, such as `Formula::UniversalQuantifier`.
+type Binder = fn(String, Box<Formula>) -> Formula;
+
+/// The name of the placeholder term substituted for what a recovering parser could not parse.
+const RECOVERED: &str = "<error>";
+
+/// A connective or quantifier whose operands are still being parsed.
+enum Pending {
+    Negation,
+    Quantifier(Binder, String),
+    Bounded(Quantifier, String, Formula),
+    Left(Connective),
+    Right(Connective, Formula),
+    CustomLeft,
+    CustomRight(Formula),
+}
+
+/// A struct for parsing logical formulae from a sequence of tokens.
+struct Parser<'a> {
+    tokens: &'a [String], // A slice of tokens representing the logical formula.
+    current: usize,       // The current index in the token slice.
+    signature: Option<&'a Signature>, // The signature every term must agree with, if any.
+    config: Option<&'a ParserConfig>, // The extra connectives to recognise, if any.
+    errors: Option<Vec<ParseError>>, // The errors recovered from so far, if recovering.
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [String]) -> Self {
+        Parser {
+            tokens,
+            current: 0,
+            signature: None,
+            config: None,
+            errors: None,
+        }
+    }
+
+    /// Creates a parser that records every error it finds and continues past it, substituting
+    /// [`RECOVERED`] for what it could not parse.
+    fn recovering(tokens: &'a [String]) -> Self {
+        Parser {
+            errors: Some(Vec::new()),
+            ..Parser::new(tokens)
+        }
+    }
+
+    /// Creates a parser that rejects terms and relation applications disagreeing with `signature`.
+    fn with_signature(tokens: &'a [String], signature: &'a Signature) -> Self {
+        Parser {
+            tokens,
+            current: 0,
+            signature: Some(signature),
+            config: None,
+            errors: None,
+        }
+    }
+
+    /// Creates a parser that also recognises the connectives registered with `config`.
+    fn with_config(tokens: &'a [String], config: &'a ParserConfig) -> Self {
+        Parser {
+            tokens,
+            current: 0,
+            signature: None,
+            config: Some(config),
+            errors: None,
+        }
+    }
+
+    /// Parses the whole token slice as a single formula, rejecting any tokens left over.
+    fn parse(&mut self) -> Result<Formula, ParseError> {
+        let formula = self.parse_formula()?;
+        if self.current != self.tokens.len() {
+            self.recover(ParseError {
+                kind: ParseErrorKind::TrailingTokens,
+                ..self.error("end of input".to_string())
+            })?;
+        }
+        Ok(formula)
+    }
+
+    /// Records `error` and returns `Ok` if the parser is recovering, or returns `error` otherwise.
+    fn recover(&mut self, error: ParseError) -> Result<(), ParseError> {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(error);
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Builds a `ParseError` located at the current token.
+    fn error(&self, expected: String) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::Malformed,
+            index: self.current,
+            found: self.tokens.get(self.current).cloned(),
+            expected,
+            line: None,
+        }
+    }
+
+    /// Parses the formula starting at the current token.
+    ///
+    /// The parser keeps its own stack of connectives and quantifiers awaiting operands instead
+    /// of recursing, so that the depth of a formula is limited only by the available memory.
+    fn parse_formula(&mut self) -> Result<Formula, ParseError> {
+        let tokens: &'a [String] = self.tokens;
+        let mut pending: Vec<(&'a str, Pending)> = Vec::new();
+        loop {
+            // The connective or quantifier whose operand is being parsed, if any.
+            let after: Option<&str> = pending.last().map(|(token, _)| *token);
+            let Some(token) = tokens.get(self.current) else {
+                let error: ParseError = self.error(match after {
+                    Some(symbol) => format!("a formula after {symbol}"),
+                    None => "a formula".to_string(),
+                });
+                self.recover(error)?;
+                let formula: Formula = Formula::Term(Term::Var(RECOVERED.to_string()));
+                match Self::complete(&mut pending, formula) {
+                    Some(formula) => return Ok(formula),
+                    None => continue,
+                }
+            };
+            let formula: Formula = match Symbol::from_token(token) {
+                Some(Symbol::Top) => {
+                    self.current += 1;
+                    Formula::Top
+                }
+                Some(Symbol::Bottom) => {
+                    self.current += 1;
+                    Formula::Bottom
+                }
+                Some(symbol) => {
+                    self.current += 1;
+                    match self.parse_operator(symbol, token)? {
+                        Pending::Quantifier(binder, variable) => {
+                            self.parse_binders(token, symbol, binder, variable, &mut pending)?
+                        }
+                        operator => pending.push((token, operator)),
+                    }
+                    continue;
+                }
+                None if self
+                    .config
+                    .is_some_and(|config| config.is_connective(token)) =>
+                {
+                    self.current += 1;
+                    pending.push((token, Pending::CustomLeft));
+                    continue;
+                }
+                None => match self.parse_atom(token, after) {
+                    Ok(formula) => formula,
+                    Err(error) => {
+                        self.recover(error)?;
+                        self.current += 1;
+                        Formula::Term(Term::Var(RECOVERED.to_string()))
+                    }
+                },
+            };
+            if let Some(formula) = Self::complete(&mut pending, formula) {
+                return Ok(formula);
+            }
+        }
+    }
+
+    /// Completes every pending connective or quantifier whose last operand is `formula`, and
+    /// returns the whole formula once nothing is pending.
+    fn complete(pending: &mut Vec<(&'a str, Pending)>, mut formula: Formula) -> Option<Formula> {
+        loop {
+            let Some((token, operator)) = pending.pop() else {
+                return Some(atom::proposition(formula));
+            };
+            // The operands of relations and registered connectives are terms, and any other
+            // operand is a formula.
+            let term_position: bool =
+                matches!(operator, Pending::CustomLeft | Pending::CustomRight(_))
+                    || Symbol::from_token(token).is_some_and(Symbol::is_relation);
+            if !term_position {
+                formula = atom::proposition(formula);
+            }
+            formula = match operator {
+                Pending::Negation => Formula::Negation(Box::new(formula)),
+                Pending::Quantifier(quantifier, variable) => {
+                    quantifier(variable, Box::new(formula))
+                }
+                Pending::Bounded(quantifier, variable, bound) => {
+                    bounded::desugar(quantifier, variable, bound, formula)
+                }
+                Pending::Left(connective) => {
+                    pending.push((token, Pending::Right(connective, formula)));
+                    return None;
+                }
+                Pending::Right(connective, left) => connective(Box::new(left), Box::new(formula)),
+                Pending::CustomLeft => {
+                    pending.push((token, Pending::CustomRight(formula)));
+                    return None;
+                }
+                Pending::CustomRight(left) => {
+                    Formula::Custom(token.to_string(), Box::new(left), Box::new(formula))
+                }
+            };
+        }
+    }
+
+    /// Starts the connective or quantifier `symbol`, read from `token`, parsing the variable
+    /// bound by a quantifier.
+    fn parse_operator(&mut self, symbol: Symbol, token: &str) -> Result<Pending, ParseError> {
+        Ok(match symbol {
+            Symbol::Top | Symbol::Bottom => unreachable!("truth constants take no operands"),
+            Symbol::Negation => Pending::Negation,
+            Symbol::Conjunction => Pending::Left(Formula::Conjunction),
+            Symbol::Disjunction => Pending::Left(Formula::Disjunction),
+            Symbol::Implication => Pending::Left(Formula::Implication),
+            Symbol::Equivalence => Pending::Left(atom::equality),
+            Symbol::Iff => Pending::Left(Formula::Iff),
+            Symbol::NotEqual => Pending::Left(Formula::NotEqual),
+            Symbol::LessThan => Pending::Left(atom::less_than),
+            Symbol::LessEqual => Pending::Left(Formula::LessEqual),
+            Symbol::GreaterEqual => Pending::Left(Formula::GreaterEqual),
+            Symbol::GreaterThan => Pending::Left(Formula::GreaterThan),
+            Symbol::UniversalQuantifier => {
+                Pending::Quantifier(Formula::UniversalQuantifier, self.parse_variable(token)?)
+            }
+            Symbol::ExistentialQuantifier => {
+                Pending::Quantifier(Formula::ExistentialQuantifier, self.parse_variable(token)?)
+            }
+        })
+    }
+
+    /// Pushes the quantifier `token`, read as `symbol` and binding `variable`, onto `pending`.
+    ///
+    /// If the configuration enables them, every identifier after `variable` is bound by another
+    /// quantifier of the same kind, and a bound `< b` after the variables makes each of them a
+    /// bounded quantifier.
+    fn parse_binders(
+        &mut self,
+        token: &'a str,
+        symbol: Symbol,
+        binder: Binder,
+        variable: String,
+        pending: &mut Vec<(&'a str, Pending)>,
+    ) -> Result<(), ParseError> {
+        let tokens: &'a [String] = self.tokens;
+        let mut variables: Vec<String> = vec![variable];
+        if self
+            .config
+            .is_some_and(ParserConfig::binds_several_variables)
+        {
+            while let Some(variable) = tokens
+                .get(self.current)
+                .filter(|token| self.is_variable(token))
+            {
+                self.current += 1;
+                variables.push(variable.clone());
+            }
+        }
+        let bounded: bool = self.config.is_some_and(ParserConfig::reads_bounds)
+            && tokens
+                .get(self.current)
+                .is_some_and(|next| Symbol::from_token(next) == Some(Symbol::LessThan));
+        let bound: Option<Formula> = if bounded {
+            self.current += 1;
+            Some(self.parse_bound(&tokens[self.current - 1])?)
+        } else {
+            None
+        };
+        let quantifier: Quantifier = if symbol == Symbol::UniversalQuantifier {
+            Quantifier::Universal
+        } else {
+            Quantifier::Existential
+        };
+        for variable in variables {
+            let operator: Pending = match &bound {
+                Some(bound) => Pending::Bounded(quantifier, variable, bound.clone()),
+                None => Pending::Quantifier(binder, variable),
+            };
+            pending.push((token, operator));
+        }
+        Ok(())
+    }
+
+    /// Parses the term bounding a bounded quantifier, after the relation `relation`.
+    fn parse_bound(&mut self, relation: &str) -> Result<Formula, ParseError> {
+        match self.tokens.get(self.current) {
+            Some(token) if self.is_operand(token) => {
+                let bound: Formula = parse_term(token, self.current)?;
+                self.current += 1;
+                Ok(bound)
+            }
+            _ => {
+                self.recover(self.error(format!("a bound after {relation}")))?;
+                Ok(Formula::Term(Term::Var(RECOVERED.to_string())))
+            }
+        }
+    }
+
+    /// Parses the atomic proposition `token` at the current token.
+    ///
+    /// `after` names the connective or quantifier whose operand is being parsed, which decides
+    /// whether the atom is checked against the signature as a term or as a relation.
+    fn parse_atom(&mut self, token: &str, after: Option<&str>) -> Result<Formula, ParseError> {
+        let term_position: bool = after.is_some_and(|parent| {
+            Symbol::from_token(parent).is_some_and(|symbol| symbol.is_relation())
+                || self
+                    .config
+                    .is_some_and(|config| config.is_connective(parent))
+        });
+        // A token that looks like a connective was most likely meant as one.
+        if let (false, Some(symbol)) = (term_position, lookalike::suggestion(token)) {
+            return Err(self.error(format!("a formula (did you mean \"{symbol}\"?)")));
+        }
+        let term = parse_term(token, self.current)?; // Atomic proposition
+        if let (Some(signature), Formula::Term(atom)) = (self.signature, &term)
+            && let Err(error) = signature.check_atom(atom, term_position)
+        {
+            return Err(self.error(format!("a term agreeing with the signature ({error})")));
+        }
+        self.current += 1;
+        Ok(term)
+    }
+
+    /// Returns whether `token` is a plain identifier that is neither a symbol nor a registered
+    /// connective, which is all that a quantifier binds with the multi-variable shorthand.
+    fn is_variable(&self, token: &str) -> bool {
+        is_identifier(token) && self.is_operand(token)
+    }
+
+    /// Returns whether `token` is neither a symbol nor a registered connective.
+    fn is_operand(&self, token: &str) -> bool {
+        Symbol::from_token(token).is_none()
+            && !self
+                .config
+                .is_some_and(|config| config.is_connective(token))
+    }
+
+    /// Parses the variable bound by the quantifier `quantifier`.
+    fn parse_variable(&mut self, quantifier: &str) -> Result<String, ParseError> {
+        match self.tokens.get(self.current) {
+            Some(token) if self.is_operand(token) => {
+                self.current += 1;
+                Ok(token.clone())
+            }
+            _ => {
+                // A recovered quantifier binds a placeholder, and the token is read as its body.
+                self.recover(self.error(format!("a variable after {quantifier}")))?;
+                Ok(RECOVERED.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*; // Import the Formula enum and its methods
+    use crate::corpus;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_valid_formula() {
+        let test_formula: Formula =
+            Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+
+        let expected: Formula = Formula::Conjunction(
+            Box::new(Formula::UniversalQuantifier(
+                "x".to_string(),
+                Box::new(Formula::Implication(
+                    Box::new(Formula::proposition("P(x)")),
+                    Box::new(Formula::Conjunction(
+                        Box::new(Formula::proposition("Q(x)")),
+                        Box::new(Formula::ExistentialQuantifier(
+                            "y".to_string(),
+                            Box::new(Formula::Disjunction(
+                                Box::new(Formula::proposition("R(y)")),
+                                Box::new(Formula::proposition("S(y)")),
+                            )),
+                        )),
+                    )),
+                )),
+            )),
+            Box::new(Formula::Equivalence(
+                Box::new(Formula::Negation(Box::new(Formula::proposition("T(x)")))),
+                Box::new(Formula::Less(Term::new("U"), Term::new("V"))),
+            )),
+        );
+
+        assert_eq!(test_formula, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "The input")]
+    fn test_malformed_formula_missing_token() {
+        // This input contains an missing token
+        Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) = T(x) < U V");
+    }
+
+    #[test]
+    #[should_panic(expected = "The input")]
+    fn test_malformed_formula_empty_input() {
+        // This input is empty
+        Formula::new("");
+    }
+
+    #[test]
+    fn test_try_new_empty_input() {
+        let error = Formula::try_new("").unwrap_err();
+        assert_eq!(error.index, 0);
+        assert_eq!(error.found, None);
+        assert_eq!(error.expected, "a formula");
+    }
+
+    #[test]
+    fn test_try_new_truncated_input() {
+        let error = Formula::try_new("∧ x").unwrap_err();
+        assert_eq!(error.index, 2);
+        assert_eq!(error.expected, "a formula after ∧");
+    }
+
+    #[test]
+    fn test_try_new_surplus_arguments() {
+        let error = Formula::try_new("∧ x y z").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::TrailingTokens);
+        assert_eq!(error.index, 3);
+        assert_eq!(error.found, Some("z".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "trailing tokens starting at index 3, found \"z\""
+        );
+    }
+
+    /// The depth of the generated formulae, far beyond what a recursive parser can handle.
+    const DEEP: usize = 100_000;
+
+    #[test]
+    fn test_deeply_nested_negations() {
+        let input: String = format!("{}P", "¬ ".repeat(DEEP));
+        let mut formula: &Formula = &Formula::try_new(input).unwrap();
+        for depth in 0..DEEP {
+            let Formula::Negation(inner) = formula else {
+                panic!("expected a negation at depth {depth}");
+            };
+            formula = inner;
+        }
+        assert_eq!(formula, &Formula::Term(Term::new("P")));
+
+        let error = Formula::try_new("¬ ".repeat(DEEP)).unwrap_err();
+        assert_eq!(error.index, DEEP);
+        assert_eq!(error.expected, "a formula after ¬");
+    }
+
+    #[test]
+    fn test_deeply_nested_conjunctions() {
+        let mut input: String = String::new();
+        for index in 0..DEEP {
+            input.push_str(&format!("∧ P{index} "));
+        }
+        input.push('Q');
+        let mut formula: &Formula = &Formula::try_new(input).unwrap();
+        for index in 0..DEEP {
+            let Formula::Conjunction(lhs, rhs) = formula else {
+                panic!("expected a conjunction at depth {index}");
+            };
+            assert_eq!(**lhs, Formula::Term(Term::new(&format!("P{index}"))));
+            formula = rhs;
+        }
+        assert_eq!(formula, &Formula::Term(Term::new("Q")));
+    }
+
+    #[test]
+    fn test_exactly_consumed_input() {
+        let test_formula = Formula::try_new("∧ ∀ x P(x) ¬ Q").unwrap();
+        assert_eq!(test_formula.to_prefix_notation(), "∧ ∀ x P(x) ¬ Q");
+    }
+
+    #[test]
+    fn test_trailing_tokens_after_quantifier_body() {
+        let error = Formula::try_new("∀ x P(x) Q(x) R(x)").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::TrailingTokens);
+        assert_eq!(error.index, 3);
+        assert_eq!(error.found, Some("Q(x)".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "trailing tokens starting at index 1")]
+    fn test_new_panics_on_trailing_tokens() {
+        Formula::new("P Q");
+    }
+
+    #[test]
+    fn test_parse_error_dangling_binary_connective() {
+        let error = Formula::try_new("∧ ∀ x → P(x) Q(x) ∨ R(y)").unwrap_err();
+        assert_eq!(error.index, 8);
+        assert_eq!(error.found, None);
+        assert_eq!(
+            error.to_string(),
+            "expected a formula after ∨ at token 8, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_missing_quantifier_variable() {
+        let error = Formula::try_new("∧ P ∃").unwrap_err();
+        assert_eq!(error.index, 3);
+        assert_eq!(error.expected, "a variable after ∃");
+
+        let error = Formula::try_new("→ ∀ ∧ P Q R").unwrap_err();
+        assert_eq!(error.index, 2);
+        assert_eq!(error.found, Some("∧".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "expected a variable after ∀ at token 2, found \"∧\""
+        );
+    }
+
+    #[test]
+    fn test_parse_error_missing_quantifier_body() {
+        let error = Formula::try_new("¬ ∀ x").unwrap_err();
+        assert_eq!(error.index, 3);
+        assert_eq!(error.expected, "a formula after ∀");
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        let error = Formula::try_new("∧ x").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected a formula after ∧ at token 2, found end of input"
+        );
+        let _: &dyn std::error::Error = &error;
+    }
+
+    #[test]
+    fn test_parse_with_recovery_reports_every_error() {
+        // The inner ∧ and the → are each missing their last operand.
+        let (formula, errors) = Formula::parse_with_recovery("→ ∧ P ∧ ∨ Q R");
+        let errors: Vec<(usize, Option<&str>, ParseErrorKind)> = errors
+            .iter()
+            .map(|error| (error.index, error.found.as_deref(), error.kind))
+            .collect();
+        assert_eq!(
+            formula.unwrap().to_prefix_notation(),
+            "→ ∧ P ∧ ∨ Q R <error> <error>"
+        );
+        assert_eq!(
+            errors,
+            [
+                (7, None, ParseErrorKind::Malformed),
+                (7, None, ParseErrorKind::Malformed)
+            ]
+        );
+
+        let (formula, errors) = Formula::parse_with_recovery("∧ ∀ → P(x) Q f(x R S");
+        assert_eq!(
+            formula.unwrap().to_prefix_notation(),
+            "∧ ∀ <error> → P(x) Q <error>"
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].expected, "a variable after ∀");
+        assert_eq!(
+            (errors[1].index, errors[1].found.as_deref()),
+            (5, Some("f(x"))
+        );
+        assert_eq!(errors[2].kind, ParseErrorKind::TrailingTokens);
+        assert_eq!(
+            (errors[2].index, errors[2].found.as_deref()),
+            (6, Some("R"))
+        );
+    }
+
+    #[test]
+    fn test_parse_with_recovery_happy_path() {
+        for input in corpus::all() {
+            assert_eq!(
+                Formula::parse_with_recovery(input),
+                (Some(Formula::new(input)), vec![])
+            );
+        }
+        let (formula, errors) = Formula::parse_with_recovery("");
+        assert_eq!(formula, None);
+        assert_eq!(errors, [Formula::try_new("").unwrap_err()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "found end of input")]
+    fn test_new_panic_message_contains_parse_error() {
+        Formula::new("→ P");
+    }
+
+    #[test]
+    #[should_panic(expected = "malformed:\n∧ ⇒ P\n  ^ expected a formula")]
+    fn test_new_panic_message_renders_parse_error() {
+        Formula::new("∧ ⇒ P");
+    }
+
+    #[test]
+    fn test_render_parse_error() {
+        let render = |source: &str| Formula::try_new(source).unwrap_err().render(source);
+        // At the start of the input.
+        assert_eq!(
+            render("⇒ P Q"),
+            "⇒ P Q\n^ expected a formula (did you mean \"→\"?) at token 0, found \"⇒\""
+        );
+        assert_eq!(
+            render("P(x) Q"),
+            "P(x) Q\n     ^ trailing tokens starting at index 1, found \"Q\""
+        );
+        // In the middle, under a token several characters wide.
+        assert_eq!(
+            render("∨ P ∀ forall Q"),
+            "∨ P ∀ forall Q\n      ^^^^^^ expected a variable after ∀ at token 3, found \"forall\""
+        );
+        // At the end of the input, and with nothing to point at.
+        assert_eq!(
+            render("∧ P  "),
+            "∧ P  \n    ^ expected a formula after ∧ at token 2, found end of input"
+        );
+        assert_eq!(
+            render(""),
+            "\n^ expected a formula at token 0, found end of input"
+        );
+        // A multi-byte symbol immediately before the error takes up one column.
+        assert_eq!(
+            render("→ P ∧ ∀ ¬"),
+            "→ P ∧ ∀ ¬\n        ^ expected a variable after ∀ at token 4, found \"¬\""
+        );
+        // Only the line of the offending token is shown.
+        assert_eq!(
+            render("∧ P\n\t∨ Q\t⇒"),
+            "\t∨ Q\t⇒\n\t   \t^ expected a formula (did you mean \"→\"?) at token 4, found \"⇒\""
+        );
+    }
+
+    #[test]
+    fn test_ascii_aliases() {
+        let test_formula: Formula =
+            Formula::new("& forall x -> P(x) ∧ Q(x) exists y | R(y) S(y) <-> ! T(x) < U V");
+        assert_eq!(
+            test_formula,
+            Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) ↔ ¬ T(x) < U V")
+        );
+        assert_eq!(Formula::new("& x y").to_prefix_notation(), "∧ x y");
+    }
+
+    #[test]
+    fn test_iff_symbols() {
+        let expected: Formula = Formula::Iff(
+            Box::new(Formula::new("¬ T(x)")),
+            Box::new(Formula::new("< U V")),
+        );
+        for input in [
+            "↔ ¬ T(x) < U V",
+            "<-> ! T(x) < U V",
+            "\\leftrightarrow ¬ T(x) < U V",
+        ] {
+            assert_eq!(Formula::new(input), expected, "{input}");
+        }
+        assert_eq!(Formula::new_lexed("↔¬T(x)<U V"), expected);
+        assert_eq!(Formula::from_infix("(¬T(x))↔(U<V)"), Ok(expected.clone()));
+        assert_eq!(expected.to_prefix_notation(), "↔ ¬ T(x) < U V");
+        assert_eq!(expected.to_string(), "((¬T(x))↔(U<V))");
+        assert_eq!(expected.get_info()[0], "Iff");
+        assert_ne!(expected, Formula::new("= ¬ T(x) < U V"));
+    }
+
+    #[test]
+    fn test_equals_sign_parses_as_before() {
+        // Inputs written before `↔` existed keep their meaning: `=` between formulae is an
+        // `Equivalence`, and between terms an `Equality` that is written back as `=`.
+        let doc_example: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        let Formula::Equivalence(lhs, rhs) = doc_example.children()[1] else {
+            panic!("expected an equivalence");
+        };
+        assert_eq!(
+            (&**lhs, &**rhs),
+            (&Formula::new("¬ T(x)"), &Formula::new("< U V"))
+        );
+        assert_eq!(
+            Formula::new("= x r+y*q"),
+            Formula::Equality(Term::new("x"), Term::new("r+y*q"))
+        );
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            assert!(
+                test_formula
+                    .subformulae()
+                    .all(|node| node.kind() != FormulaKind::Iff),
+                "{input}"
+            );
+            assert_eq!(
+                Formula::new(test_formula.to_prefix_notation()),
+                test_formula
+            );
+        }
+    }
+
+    #[test]
+    fn test_ascii_alias_inside_term() {
+        assert_eq!(Formula::new("forall(x)"), Formula::proposition("forall(x)"));
+        assert_eq!(
+            Formula::new("∀ x forall(x)"),
+            Formula::UniversalQuantifier(
+                "x".to_string(),
+                Box::new(Formula::proposition("forall(x)"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_ascii_alias_errors() {
+        let error = Formula::try_new("-> P").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected a formula after -> at token 2, found end of input"
+        );
+        let error = Formula::try_new("forall & P Q").unwrap_err();
+        assert_eq!(error.expected, "a variable after forall");
+    }
+
+    #[test]
+    fn test_latex_tokens() {
+        let test_formula: Formula = Formula::new(
+            "\\wedge \\forall x \\rightarrow P(x) \\land Q(x) \\exists y \\vee R(y) S(y) \\leftrightarrow \\neg T(x) < U V",
+        );
+        assert_eq!(
+            test_formula,
+            Formula::new(corpus::DOC_EXAMPLE.replace('=', "↔"))
+        );
+        assert_eq!(
+            Formula::new("\\lnot \\lor P ¬ Q"),
+            Formula::new("¬ ∨ P ¬ Q")
+        );
+    }
+
+    #[test]
+    fn test_latex_paper_example_lemma() {
+        let latex: Formula =
+            Formula::new("\\rightarrow \\wedge = x r+y*q \\vee < y r = y r = x (r-y)+y*(1+q)");
+        let unicode: Formula = Formula::new("→ ∧ = x r+y*q ∨ < y r = y r = x (r-y)+y*(1+q)");
+        assert_eq!(latex, unicode);
+    }
+
+    #[test]
+    fn test_top_and_bottom() {
+        assert_eq!(Formula::new("⊤"), Formula::Top);
+        assert_eq!(Formula::new("false"), Formula::Bottom);
+        assert_eq!(
+            Formula::new("→ true ∨ ⊥ P"),
+            Formula::Implication(
+                Box::new(Formula::Top),
+                Box::new(Formula::Disjunction(
+                    Box::new(Formula::Bottom),
+                    Box::new(Formula::Term(Term::new("P")))
+                ))
+            )
+        );
+        assert!(Formula::try_new("∀ ⊤ P").is_err());
+    }
+
+    #[test]
+    fn test_top_and_bottom_notation() {
+        let test_formula: Formula = Formula::new("→ ⊤ = x x+y*0");
+        assert_eq!(test_formula.to_prefix_notation(), "→ ⊤ = x x+y*0");
+        assert_eq!(test_formula.to_infix_notation(), "(⊤→(x=x+y*0))");
+        assert_eq!(format!("{}", Formula::Bottom), "⊥");
+        assert_eq!(Formula::new("true").to_prefix_notation(), "⊤");
+        assert_eq!(test_formula.get_info(), ["Implication", "⊤", "= x x+y*0"]);
+        assert_eq!(Formula::Top.get_info(), ["Top", "", ""]);
+        assert_eq!(Formula::Bottom.get_info(), ["Bottom", "", ""]);
+    }
+
+    #[test]
+    fn test_comparison_connectives() {
+        let term = |s: &str| Box::new(Formula::Term(Term::new(s)));
+        assert_eq!(
+            Formula::new("≤ index N"),
+            Formula::LessEqual(term("index"), term("N"))
+        );
+        assert_eq!(
+            Formula::new(">= count 0"),
+            Formula::GreaterEqual(term("count"), term("0"))
+        );
+        assert_eq!(
+            Formula::new("> count 0"),
+            Formula::GreaterThan(term("count"), term("0"))
+        );
+        assert_eq!(Formula::new("<= a b"), Formula::new("≤ a b"));
+        assert_eq!(Formula::new("\\geq a b"), Formula::new("≥ a b"));
+    }
+
+    #[test]
+    fn test_comparison_connectives_notation() {
+        let formulae: [Formula; 3] = [
+            Formula::new("≤ index N"),
+            Formula::new("≥ index N"),
+            Formula::new("> index N"),
+        ];
+        let expected: [[&str; 3]; 3] = [
+            ["≤ index N", "(index≤N)", "LessEqual"],
+            ["≥ index N", "(index≥N)", "GreaterEqual"],
+            ["> index N", "(index>N)", "GreaterThan"],
+        ];
+        for (formula, [prefix, infix, kind]) in formulae.iter().zip(expected) {
+            assert_eq!(formula.to_prefix_notation(), prefix);
+            assert_eq!(formula.to_infix_notation(), infix);
+            assert_eq!(formula.get_info(), [kind, "index", "N"]);
         }
     }
-    /// Converts the formula itself infix notation.
-    ///
-    /// This method returns a string representation of the formula in infix notation,
-    /// which is a way of writing logical expressions where operators are placed between
-    /// their operands. This format is commonly used in mathematical expressions and is
-    /// more familiar to users.
-    ///
-    /// # Returns
-    /// A `String` representing the formula in infix notation
-    ///
-    /// # Examples
-    /// ```
-    /// use first_order::Formula;
-    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
-    /// assert_eq!(
-    ///    format!("{test_formula}"),
-    ///    "(∀x((P(x)→(Q(x)∧∃y((R(y)∨S(y))))))∧((¬T(x))=(U<V)))"
-    /// );
-    /// ```
-    pub fn to_infix_notation(&self) -> String {
-        match self {
-            Formula::Term(s) => format!("{s}"),
-            Formula::Negation(formula) => format!("(¬{})", formula.to_infix_notation()),
-            Formula::Conjunction(lhs, rhs) => {
-                format!("({}∧{})", lhs.to_infix_notation(), rhs.to_infix_notation())
-            }
-            Formula::Disjunction(lhs, rhs) => {
-                format!("({}∨{})", lhs.to_infix_notation(), rhs.to_infix_notation())
-            }
-            Formula::Implication(lhs, rhs) => {
-                format!("({}→{})", lhs.to_infix_notation(), rhs.to_infix_notation())
-            }
-            Formula::Equivalence(lhs, rhs) => {
-                format!("({}={})", lhs.to_infix_notation(), rhs.to_infix_notation())
-            }
-            Formula::LessThan(lhs, rhs) => {
-                format!("({}<{})", lhs.to_infix_notation(), rhs.to_infix_notation())
-            }
-            Formula::UniversalQuantifier(variable, formula) => {
-                format!("∀{}({})", variable, formula.to_infix_notation())
+
+    #[test]
+    fn test_shorter_fibonacci_invariant_round_trips() {
+        // `∨ < index N = index N` from the fibonacci invariant, written as `≤ index N`.
+        let invariant: &str =
+            "∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ≤ index N = N input";
+        let test_formula: Formula = Formula::new(invariant);
+        assert_eq!(test_formula.to_prefix_notation(), invariant);
+        assert_eq!(
+            test_formula.to_infix_notation(),
+            "(((((curFib=fib(index))∧(preFib=fib(index-1)))∧(0<index))∧(index≤N))∧(N=input))"
+        );
+        assert_eq!(
+            Formula::from_infix(&test_formula.to_infix_notation()),
+            Ok(test_formula)
+        );
+    }
+
+    #[test]
+    fn test_not_equal() {
+        let test_formula: Formula = Formula::new("≠ b 0");
+        assert_eq!(
+            test_formula,
+            Formula::NotEqual(
+                Box::new(Formula::Term(Term::new("b"))),
+                Box::new(Formula::Term(Term::new("0")))
+            )
+        );
+        assert_eq!(Formula::new("!= b 0"), test_formula);
+        assert_eq!(test_formula.to_prefix_notation(), "≠ b 0");
+        assert_eq!(test_formula.to_infix_notation(), "(b≠0)");
+        assert_eq!(test_formula.get_info(), ["NotEqual", "b", "0"]);
+    }
+
+    #[test]
+    fn test_negate_comparison() {
+        let invariant: Formula = Formula::new("= gcd(a,b) gcd(a,mod(a,b))");
+        assert_eq!(
+            invariant.negate_comparison(),
+            Some(Formula::new("≠ gcd(a,b) gcd(a,mod(a,b))"))
+        );
+        let condition: Formula = Formula::new("≠ b 0");
+        assert_eq!(
+            condition.negate_comparison().unwrap().negate_comparison(),
+            Some(condition)
+        );
+        assert_eq!(Formula::new("¬ = b 0").negate_comparison(), None);
+    }
+
+    /// An accessor such as `Formula::as_conjunction`.
+    type BinaryAccessor = fn(&Formula) -> Option<(&Formula, &Formula)>;
+
+    #[test]
+    fn test_binary_accessors() {
+        let p: Formula = Formula::new("P");
+        let q: Formula = Formula::new("¬ Q");
+        let accessors: [(&str, BinaryAccessor); 5] = [
+            ("→", Formula::as_implication),
+            ("∧", Formula::as_conjunction),
+            ("∨", Formula::as_disjunction),
+            ("=", Formula::as_equivalence),
+            ("<", Formula::as_less_than),
+        ];
+        for (symbol, accessor) in accessors {
+            for (other, _) in accessors {
+                let test_formula: Formula = Formula::new(format!("{other} P ¬ Q"));
+                let expected: Option<(&Formula, &Formula)> = (other == symbol).then_some((&p, &q));
+                assert_eq!(accessor(&test_formula), expected, "{symbol} on {other}");
             }
-            Formula::ExistentialQuantifier(variable, formula) => {
-                format!("∃{}({})", variable, formula.to_infix_notation())
+            for input in ["P", "⊤", "¬ P", "≠ P Q", "∀ x P(x)"] {
+                assert_eq!(accessor(&Formula::new(input)), None, "{symbol} on {input}");
             }
         }
     }
-    /// Retrieves information about the formula in an array format
-    ///
-    /// # Returns
-    /// Returns an array of three `String` values containing the following information:
-    /// - Position 0: The type of the formula.
-    /// - Position 1: The first argument of the formula.
-    /// - Position 2: the second argument of the formula. If the formula is a term. The function will return an empty  `String`.
-    ///
-    /// # Examples
-    /// ```
-    /// use first_order::Formula;
-    /// let test_formula: Formula = Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
-    /// assert_eq!(
-    ///    test_formula.get_info(),
-    ///    [
-    ///        "Conjunction",
-    ///        "∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y)",
-    ///        "= ¬ T(x) < U V"
-    ///    ]
-    /// );
-    /// ```
-    pub fn get_info(&self) -> [String; 3] {
-        match self {
-            Formula::Term(s) => ["Term".to_string(), s.to_string(), "".to_string()],
-            Formula::Negation(formula) => [
-                "Negation".to_string(),
-                formula.to_prefix_notation(),
-                "".to_string(),
-            ],
-            Formula::Conjunction(lhs, rhs) => [
-                "Conjunction".to_string(),
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation(),
-            ],
-            Formula::Disjunction(lhs, rhs) => [
-                "Disjunction".to_string(),
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation(),
-            ],
-            Formula::Implication(lhs, rhs) => [
-                "Implication".to_string(),
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation(),
-            ],
-            Formula::Equivalence(lhs, rhs) => [
-                "Equivalence".to_string(),
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation(),
-            ],
-            Formula::LessThan(lhs, rhs) => [
-                "LessThan".to_string(),
-                lhs.to_prefix_notation(),
-                rhs.to_prefix_notation(),
-            ],
-            Formula::UniversalQuantifier(variable, formula) => [
-                "UniversalQuantifier".to_string(),
-                variable.to_string(),
-                formula.to_prefix_notation(),
-            ],
-            Formula::ExistentialQuantifier(variable, formula) => [
-                "ExistentialQuantifier".to_string(),
-                variable.to_string(),
-                formula.to_prefix_notation(),
-            ],
+
+    #[test]
+    fn test_trivial_implications() {
+        let trivial: [&str; 8] = [
+            "→ < y r < y r",
+            "→ ∧ A B A",
+            "→ ∧ ∧ A B C ∧ C A",
+            "→ A ∨ A B",
+            "→ ∨ B A ∨ ∨ A C B",
+            "→ ∧ P ⊥ Q",
+            "→ P ∨ Q ⊤",
+            corpus::PAPER_EXAMPLE[9],
+        ];
+        for input in trivial {
+            assert!(Formula::new(input).is_trivial_implication(), "{input}");
         }
+        let non_trivial: [&str; 5] = [
+            "→ A B",
+            "→ A ∧ A B",
+            "→ ∨ A B A",
+            "→ ∧ A B ∨ A C",
+            "→ ¬ ¬ A A",
+        ];
+        for input in non_trivial {
+            assert!(!Formula::new(input).is_trivial_implication(), "{input}");
+        }
+        assert!(Formula::Bottom.implies_trivially(&Formula::new("P")));
+        assert!(Formula::new("P").implies_trivially(&Formula::Top));
     }
-}
 
-/// A struct for parsing logical formulae from a sequence of tokens.
-#[derive(Debug)]
-enum ParseError {
-    MalformedInput,
-}
+    #[test]
+    fn test_negated() {
+        let condition: Formula = Formula::new("< y r");
+        let negation: Formula = condition.clone().negated();
+        assert_eq!(negation, Formula::new("¬ < y r"));
+        assert_eq!(negation.clone().negated(), condition);
+        assert_eq!(Formula::new("¬ ¬ P").negated(), Formula::new("¬ P"));
+        assert!(negation.is_negation_of(&condition));
+        assert!(condition.is_negation_of(&negation));
+        assert!(!condition.is_negation_of(&condition));
+        assert!(!Formula::new("≥ y r").is_negation_of(&condition));
+    }
 
-struct Parser<'a> {
-    tokens: &'a [String], // A slice of tokens representing the logical formula.
-    current: usize,       // The current index in the token slice.
-}
+    #[test]
+    fn test_negated_flipping_comparisons() {
+        let flips: [(&str, &str); 6] = [
+            ("= x 5", "≠ x 5"),
+            ("≠ x 5", "= x 5"),
+            ("< y r", "≥ y r"),
+            ("≥ y r", "< y r"),
+            ("> i n+1", "≤ i n+1"),
+            ("≤ i n+1", "> i n+1"),
+        ];
+        for (input, flipped) in flips {
+            assert_eq!(
+                Formula::new(input).negated_with(true),
+                Formula::new(flipped)
+            );
+            assert_eq!(
+                Formula::new(input).negated_with(false),
+                Formula::new(format!("¬ {input}"))
+            );
+            assert_eq!(
+                Formula::new(input).negated_with(true).negated_with(true),
+                Formula::new(input)
+            );
+        }
+        assert_eq!(
+            Formula::new("¬ < y r").negated_with(true),
+            Formula::new("< y r")
+        );
+        assert_eq!(
+            Formula::new("= P ∧ Q R").negated_with(true),
+            Formula::new("¬ = P ∧ Q R")
+        );
+    }
 
-impl<'a> Parser<'a> {
-    fn new(tokens: &'a [String]) -> Self {
-        Parser { tokens, current: 0 }
+    #[test]
+    fn test_implication_chains() {
+        let cases: [(&str, Vec<&str>); 4] = [
+            ("= x r+y*q", vec!["= x r+y*q"]),
+            (corpus::PAPER_EXAMPLE[0], vec!["⊤", "= x x+y*0"]),
+            ("→ A → B C", vec!["A", "B", "C"]),
+            ("→ → A B → C D", vec!["→ A B", "C", "D"]),
+        ];
+        for (input, expected) in cases {
+            let test_formula: Formula = Formula::new(input);
+            let chain: Vec<&Formula> = test_formula.implication_chain();
+            let expected: Vec<Formula> = expected.into_iter().map(Formula::new).collect();
+            assert_eq!(chain, expected.iter().collect::<Vec<&Formula>>(), "{input}");
+            let (conclusion, premises) = expected.split_last().unwrap();
+            assert_eq!(
+                Formula::curry(premises, conclusion.clone()),
+                test_formula,
+                "{input}"
+            );
+        }
+        let lemma: Formula = Formula::new(corpus::PAPER_EXAMPLE[5]);
+        assert_eq!(
+            lemma.antecedent(),
+            Some(&Formula::new("∧ = x r+y*q ∨ < y r = y r"))
+        );
+        assert_eq!(lemma.consequent(), Some(&Formula::new("= x (r-y)+y*(1+q)")));
+        assert_eq!(Formula::new("¬ → P Q").antecedent(), None);
     }
 
-    fn parse(&mut self) -> Result<Formula, ParseError> {
-        self.parse_formula()
+    #[test]
+    fn test_unary_accessors() {
+        let test_formula: Formula = Formula::new(corpus::PAPER_EXAMPLE[9]);
+        let (_, consequent) = test_formula.as_implication().unwrap();
+        let (exit, invariant) = consequent.as_conjunction().unwrap();
+        let (less_than, equal) = exit.as_negation().unwrap().as_disjunction().unwrap();
+        assert_eq!(
+            less_than.as_atom(),
+            Some(Atom::LessThan(&Term::new("y"), &Term::new("r")))
+        );
+        assert_eq!(less_than.as_less_than(), None);
+        assert_eq!(equal, &Formula::new("= y r"));
+        assert_eq!(invariant.as_negation(), None);
+
+        assert_eq!(
+            Formula::new("∀ x ∃ y P(x,y)").as_quantifier(),
+            Some(("x", &Formula::new("∃ y P(x,y)")))
+        );
+        assert_eq!(
+            Formula::new("∃ y P(x,y)").as_quantifier(),
+            Some(("y", &Formula::new("P(x,y)")))
+        );
+        for input in ["P", "⊥", "¬ ∀ x P(x)", "∧ ∀ x P ∀ y Q"] {
+            assert_eq!(Formula::new(input).as_quantifier(), None, "{input}");
+        }
     }
 
-    fn parse_formula(&mut self) -> Result<Formula, ParseError> {
-        if self.current == self.tokens.len() {
-            return Err(ParseError::MalformedInput);
+    #[test]
+    fn test_children_counts() {
+        let cases: [(&str, usize); 10] = [
+            ("P(x)", 0),
+            ("= x 0", 0),
+            ("< x 0", 0),
+            ("⊤", 0),
+            ("⊥", 0),
+            ("¬ P", 1),
+            ("∀ x P(x)", 1),
+            ("∃ x P(x)", 1),
+            ("∧ P Q", 2),
+            ("≥ x 0", 2),
+        ];
+        for (input, count) in cases {
+            assert_eq!(Formula::new(input).children().len(), count, "{input}");
+        }
+        for symbol in ["∨", "→", "=", "≠", "<", "≤", ">"] {
+            assert_eq!(Formula::new(format!("{symbol} ⊤ Q")).children().len(), 2);
         }
+        for symbol in ["∨", "→", "≠", "≤", ">"] {
+            assert_eq!(Formula::new(format!("{symbol} P Q")).children().len(), 2);
+        }
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        assert_eq!(config.parse("⊆ A B").unwrap().children().len(), 2);
+    }
 
-        let token: &String = &self.tokens[self.current];
-        self.current += 1;
+    #[test]
+    fn test_children_borrow_the_original_tree() {
+        let test_formula: Formula = Formula::new("∧ ¬ P ∀ x Q(x)");
+        let Formula::Conjunction(lhs, rhs) = &test_formula else {
+            panic!("expected a conjunction");
+        };
+        let children: Vec<&Formula> = test_formula.children();
+        assert!(std::ptr::eq(children[0], &**lhs));
+        assert!(std::ptr::eq(children[1], &**rhs));
+        let Formula::UniversalQuantifier(_, inner) = &**rhs else {
+            panic!("expected a quantifier");
+        };
+        assert!(std::ptr::eq(children[1].children()[0], &**inner));
+    }
 
-        match token.as_str() {
-            "¬" => {
-                let inner = self.parse_formula()?;
-                Ok(Formula::Negation(Box::new(inner)))
-            }
-            "∧" => {
-                let left = self.parse_formula()?;
-                let right = self.parse_formula()?;
-                Ok(Formula::Conjunction(Box::new(left), Box::new(right)))
-            }
-            "∨" => {
-                let left = self.parse_formula()?;
-                let right = self.parse_formula()?;
-                Ok(Formula::Disjunction(Box::new(left), Box::new(right)))
-            }
-            "→" => {
-                let left = self.parse_formula()?;
-                let right = self.parse_formula()?;
-                Ok(Formula::Implication(Box::new(left), Box::new(right)))
-            }
-            "∀" => {
-                let var = self
-                    .tokens
-                    .get(self.current)
-                    .ok_or(ParseError::MalformedInput)?
-                    .clone();
-                self.current += 1;
-                let inner = self.parse_formula()?;
-                Ok(Formula::UniversalQuantifier(var, Box::new(inner)))
-            }
-            "∃" => {
-                let var = self
-                    .tokens
-                    .get(self.current)
-                    .ok_or(ParseError::MalformedInput)?
-                    .clone();
-                self.current += 1;
-                let inner = self.parse_formula()?;
-                Ok(Formula::ExistentialQuantifier(var, Box::new(inner)))
-            }
-            "=" => {
-                let left = self.parse_formula()?;
-                let right = self.parse_formula()?;
-                Ok(Formula::Equivalence(Box::new(left), Box::new(right)))
-            }
-            "<" => {
-                let left = self.parse_formula()?;
-                let right = self.parse_formula()?;
-                Ok(Formula::LessThan(Box::new(left), Box::new(right)))
-            }
-            _ => Ok(Formula::Term(token.clone())), // Atomic proposition
+    #[test]
+    fn test_builders() {
+        let invariant: Formula = Formula::term("x").equals(Formula::term("r+y*q"));
+        let condition: Formula = Formula::term("y")
+            .lt(Formula::term("r"))
+            .or(Formula::term("y").equals(Formula::term("r")));
+        assert_eq!(
+            invariant
+                .clone()
+                .and(condition.clone().not())
+                .implies(condition.not().and(invariant)),
+            Formula::new(corpus::PAPER_EXAMPLE[9])
+        );
+        assert_eq!(
+            Formula::Top
+                .implies(Formula::Bottom)
+                .exists("n")
+                .forall("m"),
+            Formula::new("∀ m ∃ n → ⊤ ⊥")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "malformed")]
+    fn test_term_builder_malformed() {
+        Formula::term("f(x");
+    }
+
+    #[test]
+    fn test_hash_set_deduplicates_structurally_equal_formulae() {
+        let lemmas: HashSet<Formula> = corpus::PAPER_EXAMPLE
+            .iter()
+            .chain(&corpus::PAPER_EXAMPLE)
+            .map(|input| Formula::new(*input))
+            .chain([Formula::new("→ = x r+y*q = x r+y*q")])
+            .collect();
+        assert_eq!(lemmas.len(), corpus::PAPER_EXAMPLE.len());
+        assert!(lemmas.contains(&Formula::from_infix("(x=r+y*q)").unwrap()));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut formulae: Vec<Formula> = [
+            "∀ x P(x)",
+            "∧ Q P",
+            "⊥",
+            "∧ P Q",
+            "P",
+            "¬ P",
+            "⊤",
+            "∃ x P(x)",
+            "Q",
+            "∀ a P(a)",
+        ]
+        .map(Formula::new)
+        .to_vec();
+        formulae.sort();
+        let sorted: Vec<String> = formulae.iter().map(Formula::to_prefix_notation).collect();
+        assert_eq!(
+            sorted,
+            [
+                "P",
+                "Q",
+                "⊤",
+                "⊥",
+                "¬ P",
+                "∧ P Q",
+                "∧ Q P",
+                "∀ a P(a)",
+                "∀ x P(x)",
+                "∃ x P(x)"
+            ]
+        );
+        let tree: BTreeSet<Formula> = formulae.into_iter().collect();
+        assert_eq!(tree.first(), Some(&Formula::new("P")));
+    }
+
+    #[test]
+    fn test_binder() {
+        assert_eq!(Formula::new("∀ x P(x)").binder(), Some("x"));
+        assert_eq!(Formula::new("∃ index < index N").binder(), Some("index"));
+        for input in ["x", "¬ ∀ x P(x)", "∧ ∀ x P ∀ y Q", "⊤"] {
+            assert_eq!(Formula::new(input).binder(), None, "{input}");
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*; // Import the Formula enum and its methods
+    #[test]
+    fn test_from_str() {
+        let test_formula: Result<Formula, ParseError> = "∃ x P(x)".parse();
+        assert_eq!(test_formula, Ok(Formula::new("∃ x P(x)")));
+        assert_eq!(
+            Formula::try_from("¬ P"),
+            Ok(Formula::Negation(Box::new(Formula::Term(Term::new("P")))))
+        );
+    }
 
     #[test]
-    fn test_valid_formula() {
-        let test_formula: Formula =
-            Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) = ¬ T(x) < U V");
+    fn test_from_str_malformed() {
+        let error = "∨ P".parse::<Formula>().unwrap_err();
+        assert_eq!(error.index, 2);
+        assert!(Formula::try_from("∨ P Q R".to_string()).is_err());
+    }
 
-        let expected: Formula = Formula::Conjunction(
-            Box::new(Formula::UniversalQuantifier(
-                "x".to_string(),
-                Box::new(Formula::Implication(
-                    Box::new(Formula::Term("P(x)".to_string())),
-                    Box::new(Formula::Conjunction(
-                        Box::new(Formula::Term("Q(x)".to_string())),
-                        Box::new(Formula::ExistentialQuantifier(
-                            "y".to_string(),
-                            Box::new(Formula::Disjunction(
-                                Box::new(Formula::Term("R(y)".to_string())),
-                                Box::new(Formula::Term("S(y)".to_string())),
-                            )),
-                        )),
-                    )),
-                )),
-            )),
-            Box::new(Formula::Equivalence(
-                Box::new(Formula::Negation(Box::new(Formula::Term(
-                    "T(x)".to_string(),
-                )))),
-                Box::new(Formula::LessThan(
-                    Box::new(Formula::Term("U".to_string())),
-                    Box::new(Formula::Term("V".to_string())),
-                )),
-            )),
+    #[test]
+    fn test_structured_terms() {
+        let test_formula: Formula = Formula::new("= gcd(a,b) gcd(a,mod(a,b))");
+        let Formula::Equality(_, right) = &test_formula else {
+            panic!("expected an equality");
+        };
+        assert_eq!(
+            *right,
+            Term::App(
+                "gcd".to_string(),
+                vec![Term::new("a"), Term::new("mod(a,b)")]
+            )
         );
+        assert_eq!(
+            test_formula.to_prefix_notation(),
+            "= gcd(a,b) gcd(a,mod(a,b))"
+        );
+    }
 
-        assert_eq!(test_formula, expected);
+    #[test]
+    fn test_malformed_term() {
+        let error: ParseError = Formula::try_new("∧ P(x Q").unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(error.found, Some("P(x".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "expected a well-formed term (expected ',' or ')' at character 3, found end of term) at token 1, found \"P(x\""
+        );
+        assert!(Formula::from_infix("f(a,,b)").is_err());
     }
 
     #[test]
-    #[should_panic(expected = "The input")]
-    fn test_malformed_formula_missing_token() {
-        // This input contains an missing token
-        Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) = T(x) < U V");
+    fn test_new_lexed() {
+        assert_eq!(
+            Formula::new_lexed("∧∀x→P(x)Q R"),
+            Formula::new("∧ ∀ x → P(x) Q R")
+        );
+        assert_eq!(
+            Formula::new_lexed("→⊤=x x+y*0"),
+            Formula::new(corpus::PAPER_EXAMPLE[0])
+        );
+        assert_eq!(
+            Formula::new_lexed("∧=(result*count)*fact(count-1) fact(x)∨<0 (count-1)=0 (count-1)"),
+            Formula::new(corpus::OTHER_EXAMPLES[0])
+        );
     }
 
     #[test]
-    #[should_panic(expected = "The input")]
-    fn test_malformed_formula_empty_input() {
-        // This input is empty
-        Formula::new("");
+    fn test_new_lexed_corpus() {
+        for input in corpus::all() {
+            assert_eq!(Formula::new_lexed(input), Formula::new(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_try_new_lexed_malformed() {
+        let error: ParseError = Formula::try_new_lexed("=x+y*0").unwrap_err();
+        assert_eq!(error.index, 2);
+        assert_eq!(error.found, None);
     }
 
     #[test]
     fn test_valid_formula_with_terms() {
         let test_formula: Formula = Formula::new("∃ x P(x)");
 
-        let expected: Formula = Formula::ExistentialQuantifier(
-            "x".to_string(),
-            Box::new(Formula::Term("P(x)".to_string())),
-        );
+        let expected: Formula =
+            Formula::ExistentialQuantifier("x".to_string(), Box::new(Formula::proposition("P(x)")));
 
         assert_eq!(test_formula, expected);
     }
@@ -463,28 +4944,28 @@ mod tests {
     fn test_valid_formula_with_negation() {
         let test_formula: Formula = Formula::new("¬ P(x)");
 
-        let expected: Formula = Formula::Negation(Box::new(Formula::Term("P(x)".to_string())));
+        let expected: Formula = Formula::Negation(Box::new(Formula::proposition("P(x)")));
 
         assert_eq!(test_formula, expected);
     }
 
     #[test]
     fn test_term_to_prefix_notation() {
-        let formula = Formula::Term("P(x)".to_string());
+        let formula = Formula::Term(Term::new("P(x)"));
         assert_eq!(formula.to_prefix_notation(), "P(x)");
     }
 
     #[test]
     fn test_negation_to_prefix_notation() {
-        let formula = Formula::Negation(Box::new(Formula::Term("P(x)".to_string())));
+        let formula = Formula::Negation(Box::new(Formula::Term(Term::new("P(x)"))));
         assert_eq!(formula.to_prefix_notation(), "¬ P(x)");
     }
 
     #[test]
     fn test_conjunction_to_prefix_notation() {
         let formula = Formula::Conjunction(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_prefix_notation(), "∧ P(x) Q(x)");
     }
@@ -492,8 +4973,8 @@ mod tests {
     #[test]
     fn test_disjunction_to_prefix_notation() {
         let formula = Formula::Disjunction(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_prefix_notation(), "∨ P(x) Q(x)");
     }
@@ -501,8 +4982,8 @@ mod tests {
     #[test]
     fn test_implication_to_prefix_notation() {
         let formula = Formula::Implication(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_prefix_notation(), "→ P(x) Q(x)");
     }
@@ -510,8 +4991,8 @@ mod tests {
     #[test]
     fn test_equivalence_to_prefix_notation() {
         let formula = Formula::Equivalence(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_prefix_notation(), "= P(x) Q(x)");
     }
@@ -519,8 +5000,8 @@ mod tests {
     #[test]
     fn test_less_than_to_prefix_notation() {
         let formula = Formula::LessThan(
-            Box::new(Formula::Term("x".to_string())),
-            Box::new(Formula::Term("y".to_string())),
+            Box::new(Formula::Term(Term::new("x"))),
+            Box::new(Formula::Term(Term::new("y"))),
         );
         assert_eq!(formula.to_prefix_notation(), "< x y");
     }
@@ -529,7 +5010,7 @@ mod tests {
     fn test_universal_quantifier_to_prefix_notation() {
         let formula = Formula::UniversalQuantifier(
             "x".to_string(),
-            Box::new(Formula::Term("P(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
         );
         assert_eq!(formula.to_prefix_notation(), "∀ x P(x)");
     }
@@ -538,7 +5019,7 @@ mod tests {
     fn test_existential_quantifier_to_prefix_notation() {
         let formula = Formula::ExistentialQuantifier(
             "y".to_string(),
-            Box::new(Formula::Term("Q(y)".to_string())),
+            Box::new(Formula::Term(Term::new("Q(y)"))),
         );
         assert_eq!(formula.to_prefix_notation(), "∃ y Q(y)");
     }
@@ -549,17 +5030,17 @@ mod tests {
             Box::new(Formula::UniversalQuantifier(
                 "x".to_string(),
                 Box::new(Formula::Implication(
-                    Box::new(Formula::Term("P(x)".to_string())),
-                    Box::new(Formula::Negation(Box::new(Formula::Term(
-                        "Q(x)".to_string(),
-                    )))),
+                    Box::new(Formula::Term(Term::new("P(x)"))),
+                    Box::new(Formula::Negation(Box::new(Formula::Term(Term::new(
+                        "Q(x)",
+                    ))))),
                 )),
             )),
             Box::new(Formula::ExistentialQuantifier(
                 "y".to_string(),
                 Box::new(Formula::Disjunction(
-                    Box::new(Formula::Term("R(y)".to_string())),
-                    Box::new(Formula::Term("S(y)".to_string())),
+                    Box::new(Formula::Term(Term::new("R(y)"))),
+                    Box::new(Formula::Term(Term::new("S(y)"))),
                 )),
             )),
         );
@@ -572,21 +5053,21 @@ mod tests {
 
     #[test]
     fn test_term_to_infix_notation() {
-        let formula = Formula::Term("P(x)".to_string());
+        let formula = Formula::Term(Term::new("P(x)"));
         assert_eq!(formula.to_infix_notation(), "P(x)");
     }
 
     #[test]
     fn test_negation_to_infix_notation() {
-        let formula = Formula::Negation(Box::new(Formula::Term("P(x)".to_string())));
+        let formula = Formula::Negation(Box::new(Formula::Term(Term::new("P(x)"))));
         assert_eq!(formula.to_infix_notation(), "(¬P(x))");
     }
 
     #[test]
     fn test_conjunction_to_infix_notation() {
         let formula = Formula::Conjunction(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_infix_notation(), "(P(x)∧Q(x))");
     }
@@ -594,8 +5075,8 @@ mod tests {
     #[test]
     fn test_disjunction_to_infix_notation() {
         let formula = Formula::Disjunction(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_infix_notation(), "(P(x)∨Q(x))");
     }
@@ -603,8 +5084,8 @@ mod tests {
     #[test]
     fn test_implication_to_infix_notation() {
         let formula = Formula::Implication(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_infix_notation(), "(P(x)→Q(x))");
     }
@@ -612,8 +5093,8 @@ mod tests {
     #[test]
     fn test_equivalence_to_infix_notation() {
         let formula = Formula::Equivalence(
-            Box::new(Formula::Term("P(x)".to_string())),
-            Box::new(Formula::Term("Q(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
+            Box::new(Formula::Term(Term::new("Q(x)"))),
         );
         assert_eq!(formula.to_infix_notation(), "(P(x)=Q(x))");
     }
@@ -621,8 +5102,8 @@ mod tests {
     #[test]
     fn test_less_than_to_infix_notation() {
         let formula = Formula::LessThan(
-            Box::new(Formula::Term("x".to_string())),
-            Box::new(Formula::Term("y".to_string())),
+            Box::new(Formula::Term(Term::new("x"))),
+            Box::new(Formula::Term(Term::new("y"))),
         );
         assert_eq!(formula.to_infix_notation(), "(x<y)");
     }
@@ -631,7 +5112,7 @@ mod tests {
     fn test_universal_quantifier_to_infix_notation() {
         let formula = Formula::UniversalQuantifier(
             "x".to_string(),
-            Box::new(Formula::Term("P(x)".to_string())),
+            Box::new(Formula::Term(Term::new("P(x)"))),
         );
         assert_eq!(formula.to_infix_notation(), "∀x(P(x))");
     }
@@ -640,28 +5121,39 @@ mod tests {
     fn test_existential_quantifier_to_infix_notation() {
         let formula = Formula::ExistentialQuantifier(
             "y".to_string(),
-            Box::new(Formula::Term("Q(y)".to_string())),
+            Box::new(Formula::Term(Term::new("Q(y)"))),
         );
         assert_eq!(formula.to_infix_notation(), "∃y(Q(y))");
     }
 
+    #[test]
+    fn test_display_flags() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(
+            format!("{test_formula}"),
+            "(∀x((P(x)→(Q(x)∧∃y((R(y)∨S(y))))))∧((¬T(x))=(U<V)))"
+        );
+        assert_eq!(format!("{test_formula:#}"), corpus::DOC_EXAMPLE);
+        assert_eq!(format!("{:#}", Formula::Top), "⊤");
+    }
+
     #[test]
     fn test_complex_formula_to_infix_notation() {
         let formula = Formula::Conjunction(
             Box::new(Formula::UniversalQuantifier(
                 "x".to_string(),
                 Box::new(Formula::Implication(
-                    Box::new(Formula::Term("P(x)".to_string())),
-                    Box::new(Formula::Negation(Box::new(Formula::Term(
-                        "Q(x)".to_string(),
-                    )))),
+                    Box::new(Formula::Term(Term::new("P(x)"))),
+                    Box::new(Formula::Negation(Box::new(Formula::Term(Term::new(
+                        "Q(x)",
+                    ))))),
                 )),
             )),
             Box::new(Formula::ExistentialQuantifier(
                 "y".to_string(),
                 Box::new(Formula::Disjunction(
-                    Box::new(Formula::Term("R(y)".to_string())),
-                    Box::new(Formula::Term("S(y)".to_string())),
+                    Box::new(Formula::Term(Term::new("R(y)"))),
+                    Box::new(Formula::Term(Term::new("S(y)"))),
                 )),
             )),
         );
@@ -693,7 +5185,7 @@ mod tests {
             ["Conjunction", "x", "y"],
             ["Disjunction", "x", "y"],
             ["Implication", "x", "y"],
-            ["Equivalence", "x", "y"],
+            ["Equality", "x", "y"],
             ["UniversalQuantifier", "x", "x"],
             ["ExistentialQuantifier", "x", "x"],
             ["ExistentialQuantifier", "a", "→ b ∧ c a"],
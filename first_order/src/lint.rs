@@ -0,0 +1,154 @@
+//! # Lints
+//!
+//! This module reports suspicious quantifier bindings for [`Formula::lint`]. None of them makes a
+//! formula malformed, but each usually means that an invariant was written with a typo:
+//!
+//! | Lint                          | Example            |
+//! |-------------------------------|--------------------|
+//! | `UnusedBinder`                | `∀ x P(y)`         |
+//! | `ShadowedBinder`              | `∀ x ∃ x P(x)`     |
+//! | `FreeVariableLooksLikeBinder` | `∧ ∀ i P(i) Q(i)`  |
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::{Formula, FormulaPath, variables};
+
+/// A suspicious binding found by [`Formula::lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormulaLint {
+    /// The quantifier at `path` binds `var`, which does not occur free in its body.
+    UnusedBinder { var: String, path: FormulaPath },
+    /// The quantifier at `path` binds `var`, which an enclosing quantifier already binds.
+    ShadowedBinder { var: String, path: FormulaPath },
+    /// `var` occurs free, although some quantifier elsewhere binds the same name.
+    FreeVariableLooksLikeBinder { var: String },
+}
+
+impl fmt::Display for FormulaLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormulaLint::UnusedBinder { var, path } => {
+                write!(
+                    f,
+                    "the quantifier at {:?} binds {var}, which its body never uses",
+                    path.0
+                )
+            }
+            FormulaLint::ShadowedBinder { var, path } => write!(
+                f,
+                "the quantifier at {:?} binds {var}, which an enclosing quantifier already binds",
+                path.0
+            ),
+            FormulaLint::FreeVariableLooksLikeBinder { var } => {
+                write!(f, "{var} occurs free but is bound elsewhere")
+            }
+        }
+    }
+}
+
+/// Lists the suspicious bindings of `formula`. See [`Formula::lint`].
+pub(crate) fn lint(formula: &Formula) -> Vec<FormulaLint> {
+    let mut lints: Vec<FormulaLint> = Vec::new();
+    let mut stack: Vec<(FormulaPath, &Formula, Vec<&str>)> =
+        vec![(FormulaPath::root(), formula, Vec::new())];
+    while let Some((path, current, mut enclosing)) = stack.pop() {
+        if let Formula::UniversalQuantifier(variable, inner)
+        | Formula::ExistentialQuantifier(variable, inner) = current
+        {
+            let var: String = variable.clone();
+            if enclosing.contains(&variable.as_str()) {
+                lints.push(FormulaLint::ShadowedBinder {
+                    var: var.clone(),
+                    path: path.clone(),
+                });
+            }
+            if !variables::free(inner).contains(variable) {
+                lints.push(FormulaLint::UnusedBinder {
+                    var,
+                    path: path.clone(),
+                });
+            }
+            enclosing.push(variable);
+        }
+        for (index, child) in current.children().into_iter().enumerate().rev() {
+            stack.push((path.child(index), child, enclosing.clone()));
+        }
+    }
+    let bound: BTreeSet<String> = variables::bound(formula);
+    lints.extend(
+        variables::free(formula)
+            .into_iter()
+            .filter(|var| bound.contains(var))
+            .map(|var| FormulaLint::FreeVariableLooksLikeBinder { var }),
+    );
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_each_lint() {
+        assert_eq!(
+            Formula::new("∧ P(y) ∀ x Q(y)").lint(),
+            [FormulaLint::UnusedBinder {
+                var: "x".to_string(),
+                path: FormulaPath(vec![1])
+            }]
+        );
+        assert_eq!(
+            Formula::new("∀ x → P(x) ∃ x Q(x)").lint(),
+            [FormulaLint::ShadowedBinder {
+                var: "x".to_string(),
+                path: FormulaPath(vec![0, 1])
+            }]
+        );
+        assert_eq!(
+            Formula::new("∧ ∀ i < 0 i < i n").lint(),
+            [FormulaLint::FreeVariableLooksLikeBinder {
+                var: "i".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lints_combine() {
+        let lints: Vec<FormulaLint> = Formula::new("→ ∀ x ∀ x P(x) R(x)").lint();
+        assert_eq!(
+            lints,
+            [
+                FormulaLint::UnusedBinder {
+                    var: "x".to_string(),
+                    path: FormulaPath(vec![0])
+                },
+                FormulaLint::ShadowedBinder {
+                    var: "x".to_string(),
+                    path: FormulaPath(vec![0, 0])
+                },
+                FormulaLint::FreeVariableLooksLikeBinder {
+                    var: "x".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            lints[1].to_string(),
+            "the quantifier at [0, 0] binds x, which an enclosing quantifier already binds"
+        );
+    }
+
+    #[test]
+    fn test_corpus() {
+        for input in corpus::all().skip(1) {
+            assert!(Formula::new(input).lint().is_empty(), "{input}");
+        }
+        assert_eq!(
+            Formula::new(corpus::DOC_EXAMPLE).lint(),
+            [FormulaLint::FreeVariableLooksLikeBinder {
+                var: "x".to_string()
+            }]
+        );
+        assert!(Formula::new("∀ x ∃ y → P(x) R(x,y)").lint().is_empty());
+    }
+}
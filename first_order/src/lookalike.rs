@@ -0,0 +1,174 @@
+//! # Lookalike symbols
+//!
+//! Text copied from PDFs and word processors often contains characters that look like the
+//! connectives of this crate but are different code points, which the parsers would otherwise
+//! read as terms. Unless disabled with [`ParserConfig::normalize_lookalikes`], every parser first
+//! replaces each of the following characters with the symbol it looks like:
+//!
+//! | Symbol | Lookalikes                                           |
+//! |--------|------------------------------------------------------|
+//! | `⊤`    | `⟙` (U+27D9)                                         |
+//! | `⊥`    | `⟘` (U+27D8)                                         |
+//! | `¬`    | `￢` (U+FFE2)                                        |
+//! | `∧`    | `⋀` (U+22C0), `˄` (U+02C4)                           |
+//! | `∨`    | `⋁` (U+22C1), `˅` (U+02C5)                           |
+//! | `→`    | `⟶` (U+27F6), `⭢` (U+2B62), `➝` (U+279D)             |
+//! | `=`    | `＝` (U+FF1D), `꞊` (U+A78A)                          |
+//! | `<`    | `＜` (U+FF1C), `˂` (U+02C2)                          |
+//! | `≤`    | `⩽` (U+2A7D), `≦` (U+2266)                           |
+//! | `≥`    | `⩾` (U+2A7E), `≧` (U+2267)                           |
+//! | `>`    | `＞` (U+FF1E), `˃` (U+02C3)                          |
+//! | `∀`    | `Ɐ` (U+2C6F)                                         |
+//! | `∃`    | `Ǝ` (U+018E)                                         |
+//!
+//! The replacement applies to the whole input, so a term spelled with one of these characters is
+//! changed too.
+//!
+//! Other spellings of the connectives, such as `⇒` or `~`, are left alone, but a prefix notation
+//! parse error that finds one where a formula is expected suggests the intended symbol.
+//!
+//! [`ParserConfig::normalize_lookalikes`]: crate::ParserConfig::normalize_lookalikes
+
+/// The lookalike characters and the symbols they are replaced with.
+const LOOKALIKES: [(char, char); 22] = [
+    ('⟙', '⊤'),
+    ('⟘', '⊥'),
+    ('￢', '¬'),
+    ('⋀', '∧'),
+    ('˄', '∧'),
+    ('⋁', '∨'),
+    ('˅', '∨'),
+    ('⟶', '→'),
+    ('⭢', '→'),
+    ('➝', '→'),
+    ('＝', '='),
+    ('꞊', '='),
+    ('＜', '<'),
+    ('˂', '<'),
+    ('⩽', '≤'),
+    ('≦', '≤'),
+    ('⩾', '≥'),
+    ('≧', '≥'),
+    ('＞', '>'),
+    ('˃', '>'),
+    ('Ɐ', '∀'),
+    ('Ǝ', '∃'),
+];
+
+/// Other spellings of the symbols, which are not replaced but suggested in parse errors.
+const ALTERNATIVES: [(&str, char); 11] = [
+    ("⇒", '→'),
+    ("⟹", '→'),
+    ("⊃", '→'),
+    ("=>", '→'),
+    ("⇔", '↔'),
+    ("≡", '↔'),
+    ("~", '¬'),
+    ("&&", '∧'),
+    ("||", '∨'),
+    ("/=", '≠'),
+    ("=<", '≤'),
+];
+
+/// Replaces every lookalike character of `input` with the symbol it looks like.
+pub(crate) fn normalize(input: &str) -> String {
+    input.chars().map(|c| symbol(c).unwrap_or(c)).collect()
+}
+
+/// Returns the symbol that `c` looks like, if it is a lookalike character.
+fn symbol(c: char) -> Option<char> {
+    LOOKALIKES
+        .iter()
+        .find(|(lookalike, _)| *lookalike == c)
+        .map(|(_, symbol)| *symbol)
+}
+
+/// Returns the symbol that `token` was likely meant to be, if it is a lookalike character or
+/// another common spelling of a connective.
+pub(crate) fn suggestion(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    if let (Some(c), None) = (chars.next(), chars.next())
+        && let Some(symbol) = symbol(c)
+    {
+        return Some(symbol);
+    }
+    ALTERNATIVES
+        .iter()
+        .find(|(alternative, _)| *alternative == token)
+        .map(|(_, symbol)| *symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Formula, ParseError, ParserConfig, Term};
+
+    #[test]
+    fn test_every_lookalike_parses_as_its_symbol() {
+        for (lookalike, symbol) in LOOKALIKES {
+            let input: String = match symbol {
+                '⊤' | '⊥' => format!("∧ {lookalike} P"),
+                '¬' => format!("{lookalike} P"),
+                '∀' | '∃' => format!("{lookalike} x P(x)"),
+                _ => format!("{lookalike} x y"),
+            };
+            let expected: String = input.replace(lookalike, &symbol.to_string());
+            assert_eq!(
+                Formula::try_new(&input),
+                Formula::try_new(&expected),
+                "{input}"
+            );
+            assert_eq!(Formula::try_new_lexed(&input), Formula::try_new(&expected));
+        }
+    }
+
+    #[test]
+    fn test_lookalikes_in_every_parser() {
+        let expected: Formula = Formula::new("∀ x → ∧ P(x) ¬ Q(x) ≤ x 1");
+        assert_eq!(Formula::new("Ɐ x ⟶ ⋀ P(x) ￢ Q(x) ⩽ x 1"), expected);
+        assert_eq!(Formula::new_lexed("Ɐx⟶⋀P(x)￢Q(x)⩽x 1"), expected);
+        assert_eq!(
+            Formula::from_infix("Ɐx(P(x) ˄ ￢Q(x) ⟶ x ≦ 1)"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            ParserConfig::new().parse("Ɐ x ⟶ ⋀ P(x) ￢ Q(x) ⩽ x 1"),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn test_normalization_can_be_disabled() {
+        let mut config: ParserConfig = ParserConfig::new();
+        config.normalize_lookalikes(false);
+        assert!(config.parse("⋀ P Q").is_err());
+        // In term position, a lookalike is read as a term like any other name.
+        assert_eq!(
+            config.parse("< x ＜"),
+            Ok(Formula::Less(Term::new("x"), Term::new("＜")))
+        );
+    }
+
+    #[test]
+    fn test_suggestion_message() {
+        let mut config: ParserConfig = ParserConfig::new();
+        config.normalize_lookalikes(false);
+        let error: ParseError = config.parse("⟶ P Q").unwrap_err();
+        assert_eq!(error.index, 0);
+        assert_eq!(error.found.as_deref(), Some("⟶"));
+        assert_eq!(
+            error.to_string(),
+            "expected a formula (did you mean \"→\"?) at token 0, found \"⟶\""
+        );
+        assert_eq!(
+            Formula::try_new("∧ P ⇒ Q R").unwrap_err().expected,
+            "a formula (did you mean \"→\"?)"
+        );
+        assert_eq!(
+            Formula::try_new("~ P").unwrap_err().expected,
+            "a formula (did you mean \"¬\"?)"
+        );
+        assert_eq!(suggestion("P"), None);
+        assert_eq!(suggestion("⋀⋀"), None);
+    }
+}
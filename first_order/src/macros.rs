@@ -0,0 +1,302 @@
+//! # The `formula!` macro
+//!
+//! This module provides [`formula!`](crate::formula!), which builds a [`Formula`](crate::Formula)
+//! from infix syntax written directly in Rust source.
+
+/// Builds a [`Formula`](crate::Formula) from infix syntax.
+///
+/// The connectives, quantifiers and truth constants are written as follows, from the loosest to
+/// the tightest binding:
+///
+/// | Syntax                     | Formula   | Associativity |
+/// |----------------------------|-----------|---------------|
+/// | `φ <-> ψ`                  | `↔ φ ψ`   | left          |
+/// | `φ -> ψ`                   | `→ φ ψ`   | right         |
+/// | `φ \| ψ`                   | `∨ φ ψ`   | left          |
+/// | `φ & ψ`                    | `∧ φ ψ`   | left          |
+/// | `!φ`, `forall x φ`, `exists x φ` | `¬ φ`, `∀ x φ`, `∃ x φ` | |
+/// | `a == b`, `a = b`, `a != b`, `a < b`, `a <= b`, `a >= b`, `a > b` | `= a b`, `= a b`, `≠ a b`, `< a b`, `≤ a b`, `≥ a b`, `> a b` | |
+/// | `true`, `false`, `(φ)`, a term | `⊤`, `⊥`, `φ`, a `Predicate` or `Term` | |
+///
+/// The connectives are expanded into `Formula` constructors at compile time. A term, such as
+/// `P(x)`, `index` or `fib(index + 1)`, is spelled out with its whitespace removed and parsed
+/// with [`Term::new`](crate::Term::new) when the expression is evaluated, which panics if it is
+/// not a well-formed term. As in the parser, `==` and `<` between two terms build an `Equality`
+/// and a `Less`, and a term standing alone is read with
+/// [`Formula::proposition`](crate::Formula::proposition).
+///
+/// Rust reads `<-` as a single token, so write `x < -1` as `x < (-1)`. Each unparenthesised
+/// token is handled by a separate macro expansion, so very long formulae may need parentheses
+/// or a larger `recursion_limit`.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, formula};
+///
+/// assert_eq!(
+///     formula!(forall x (P(x) -> (Q(x) & exists y (R(y) | S(y))))),
+///     Formula::new("∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y)")
+/// );
+/// assert_eq!(
+///     formula!(forall x (P(x) -> Q(x) & exists y (R(y) | S(y))) & (!T(x) <-> U < V)),
+///     Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ∨ R(y) S(y) ↔ ¬ T(x) < U V")
+/// );
+/// assert_eq!(
+///     formula!(x == r + y * q & !(y < r | y == r)),
+///     Formula::new("∧ = x r+y*q ¬ ∨ < y r = y r")
+/// );
+/// ```
+///
+/// A connective with a missing operand is rejected at compile time, with the error `expected a
+/// formula`:
+/// ```compile_fail
+/// use first_order::formula;
+///
+/// let test_formula = formula!(forall x (P(x) -> ));
+/// ```
+#[macro_export]
+macro_rules! formula {
+    // `<->`, folded to the left.
+    (@iff [$($current:tt)*] <-> $($rest:tt)*) => {
+        $crate::formula!(@iff_fold ($crate::formula!(@imp [] $($current)*)) [] $($rest)*)
+    };
+    (@iff [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@iff [$($current)* $next] $($rest)*)
+    };
+    (@iff [$($current:tt)*]) => {
+        $crate::formula!(@imp [] $($current)*)
+    };
+    (@iff_fold ($lhs:expr) [$($current:tt)*] <-> $($rest:tt)*) => {
+        $crate::formula!(
+            @iff_fold
+            ($crate::Formula::Iff(
+                ::std::boxed::Box::new($lhs),
+                ::std::boxed::Box::new($crate::formula!(@imp [] $($current)*)),
+            ))
+            []
+            $($rest)*
+        )
+    };
+    (@iff_fold ($lhs:expr) [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@iff_fold ($lhs) [$($current)* $next] $($rest)*)
+    };
+    (@iff_fold ($lhs:expr) [$($current:tt)*]) => {
+        $crate::Formula::Iff(
+            ::std::boxed::Box::new($lhs),
+            ::std::boxed::Box::new($crate::formula!(@imp [] $($current)*)),
+        )
+    };
+
+    // `->`, split at the first occurrence so that it associates to the right.
+    (@imp [$($current:tt)*] -> $($rest:tt)*) => {
+        $crate::Formula::Implication(
+            ::std::boxed::Box::new($crate::formula!(@or [] $($current)*)),
+            ::std::boxed::Box::new($crate::formula!(@imp [] $($rest)*)),
+        )
+    };
+    (@imp [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@imp [$($current)* $next] $($rest)*)
+    };
+    (@imp [$($current:tt)*]) => {
+        $crate::formula!(@or [] $($current)*)
+    };
+
+    // `|`, folded to the left.
+    (@or [$($current:tt)*] | $($rest:tt)*) => {
+        $crate::formula!(@or_fold ($crate::formula!(@and [] $($current)*)) [] $($rest)*)
+    };
+    (@or [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@or [$($current)* $next] $($rest)*)
+    };
+    (@or [$($current:tt)*]) => {
+        $crate::formula!(@and [] $($current)*)
+    };
+    (@or_fold ($lhs:expr) [$($current:tt)*] | $($rest:tt)*) => {
+        $crate::formula!(
+            @or_fold
+            ($crate::Formula::Disjunction(
+                ::std::boxed::Box::new($lhs),
+                ::std::boxed::Box::new($crate::formula!(@and [] $($current)*)),
+            ))
+            []
+            $($rest)*
+        )
+    };
+    (@or_fold ($lhs:expr) [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@or_fold ($lhs) [$($current)* $next] $($rest)*)
+    };
+    (@or_fold ($lhs:expr) [$($current:tt)*]) => {
+        $crate::Formula::Disjunction(
+            ::std::boxed::Box::new($lhs),
+            ::std::boxed::Box::new($crate::formula!(@and [] $($current)*)),
+        )
+    };
+
+    // `&`, folded to the left.
+    (@and [$($current:tt)*] & $($rest:tt)*) => {
+        $crate::formula!(@and_fold ($crate::formula!(@unary $($current)*)) [] $($rest)*)
+    };
+    (@and [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@and [$($current)* $next] $($rest)*)
+    };
+    (@and [$($current:tt)*]) => {
+        $crate::formula!(@unary $($current)*)
+    };
+    (@and_fold ($lhs:expr) [$($current:tt)*] & $($rest:tt)*) => {
+        $crate::formula!(
+            @and_fold
+            ($crate::Formula::Conjunction(
+                ::std::boxed::Box::new($lhs),
+                ::std::boxed::Box::new($crate::formula!(@unary $($current)*)),
+            ))
+            []
+            $($rest)*
+        )
+    };
+    (@and_fold ($lhs:expr) [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@and_fold ($lhs) [$($current)* $next] $($rest)*)
+    };
+    (@and_fold ($lhs:expr) [$($current:tt)*]) => {
+        $crate::Formula::Conjunction(
+            ::std::boxed::Box::new($lhs),
+            ::std::boxed::Box::new($crate::formula!(@unary $($current)*)),
+        )
+    };
+
+    // Negations, quantifiers, truth constants and parenthesised formulae.
+    (@unary ! $($rest:tt)+) => {
+        $crate::Formula::Negation(::std::boxed::Box::new($crate::formula!(@unary $($rest)+)))
+    };
+    (@unary forall $variable:ident $($rest:tt)+) => {
+        $crate::Formula::UniversalQuantifier(
+            ::std::string::String::from(stringify!($variable)),
+            ::std::boxed::Box::new($crate::formula!(@unary $($rest)+)),
+        )
+    };
+    (@unary exists $variable:ident $($rest:tt)+) => {
+        $crate::Formula::ExistentialQuantifier(
+            ::std::string::String::from(stringify!($variable)),
+            ::std::boxed::Box::new($crate::formula!(@unary $($rest)+)),
+        )
+    };
+    (@unary true) => {
+        $crate::Formula::Top
+    };
+    (@unary false) => {
+        $crate::Formula::Bottom
+    };
+    (@unary ($($inner:tt)+)) => {
+        $crate::formula!(@iff [] $($inner)+)
+    };
+    (@unary $($atom:tt)+) => {
+        $crate::formula!(@relation [] $($atom)+)
+    };
+    (@unary) => {
+        compile_error!("expected a formula")
+    };
+
+    // Relations between terms.
+    (@relation [$($lhs:tt)+] == $($rhs:tt)+) => {
+        $crate::formula!(@typed Equality [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)+] = $($rhs:tt)+) => {
+        $crate::formula!(@typed Equality [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)+] != $($rhs:tt)+) => {
+        $crate::formula!(@binary NotEqual [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)+] <= $($rhs:tt)+) => {
+        $crate::formula!(@binary LessEqual [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)+] >= $($rhs:tt)+) => {
+        $crate::formula!(@binary GreaterEqual [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)+] < $($rhs:tt)+) => {
+        $crate::formula!(@typed Less [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)+] > $($rhs:tt)+) => {
+        $crate::formula!(@binary GreaterThan [$($lhs)+] [$($rhs)+])
+    };
+    (@relation [$($lhs:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::formula!(@relation [$($lhs)* $next] $($rest)*)
+    };
+    (@relation [$($term:tt)+]) => {
+        $crate::Formula::proposition(stringify!($($term)+).replace(char::is_whitespace, ""))
+    };
+    (@typed $variant:ident [$($lhs:tt)+] [$($rhs:tt)+]) => {
+        $crate::Formula::$variant(
+            $crate::Term::new(&stringify!($($lhs)+).replace(char::is_whitespace, "")),
+            $crate::Term::new(&stringify!($($rhs)+).replace(char::is_whitespace, "")),
+        )
+    };
+    (@binary $variant:ident [$($lhs:tt)+] [$($rhs:tt)+]) => {
+        $crate::Formula::$variant(
+            ::std::boxed::Box::new($crate::formula!(@term $($lhs)+)),
+            ::std::boxed::Box::new($crate::formula!(@term $($rhs)+)),
+        )
+    };
+    (@term $($term:tt)+) => {
+        $crate::Formula::term(stringify!($($term)+).replace(char::is_whitespace, ""))
+    };
+
+    ($($tokens:tt)+) => {
+        $crate::formula!(@iff [] $($tokens)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Formula, corpus};
+
+    #[test]
+    fn test_formula_macro_connectives() {
+        assert_eq!(crate::formula!(P & Q), Formula::new("∧ P Q"));
+        assert_eq!(crate::formula!(P | Q), Formula::new("∨ P Q"));
+        assert_eq!(crate::formula!(P -> Q), Formula::new("→ P Q"));
+        assert_eq!(crate::formula!(P <-> Q), Formula::new("↔ P Q"));
+        assert_eq!(crate::formula!(!P), Formula::new("¬ P"));
+        assert_eq!(crate::formula!(true -> false), Formula::new("→ ⊤ ⊥"));
+        assert_eq!(crate::formula!(exists n P(n)), Formula::new("∃ n P(n)"));
+    }
+
+    #[test]
+    fn test_formula_macro_precedence() {
+        assert_eq!(crate::formula!(P | Q & R), Formula::new("∨ P ∧ Q R"));
+        assert_eq!(crate::formula!(!P & Q), Formula::new("∧ ¬ P Q"));
+        assert_eq!(crate::formula!(P & Q & R), Formula::new("∧ ∧ P Q R"));
+        assert_eq!(crate::formula!(P -> Q -> R), Formula::new("→ P → Q R"));
+        assert_eq!(crate::formula!(P <-> Q <-> R), Formula::new("↔ ↔ P Q R"));
+        assert_eq!(
+            crate::formula!(P -> Q <-> R | S),
+            Formula::new("↔ → P Q ∨ R S")
+        );
+        assert_eq!(
+            crate::formula!(forall x P(x) & Q(x)),
+            Formula::new("∧ ∀ x P(x) Q(x)")
+        );
+        assert_eq!(
+            crate::formula!(!(P | Q) -> !P & !Q),
+            Formula::new("→ ¬ ∨ P Q ∧ ¬ P ¬ Q")
+        );
+    }
+
+    #[test]
+    fn test_formula_macro_relations() {
+        assert_eq!(
+            crate::formula!(x == r + y * q),
+            Formula::new(corpus::PAPER_EXAMPLE[3])
+        );
+        assert_eq!(
+            crate::formula!(x = x + y * 0),
+            Formula::new(corpus::PAPER_EXAMPLE[1])
+        );
+        assert_eq!(
+            crate::formula!(fib(index + 1) >= fib(index) & b != 0 & i <= n & i > (-1)),
+            Formula::new("∧ ∧ ∧ ≥ fib(index+1) fib(index) ≠ b 0 ≤ i n > i (-1)")
+        );
+        assert_eq!(
+            crate::formula!(x == r + y * q & !(y < r | y == r) -> !(y < r | y == r) & x == r + y * q),
+            Formula::new(corpus::PAPER_EXAMPLE[9])
+        );
+    }
+}
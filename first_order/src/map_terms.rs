@@ -0,0 +1,143 @@
+//! # Mapping terms
+//!
+//! This module rebuilds a formula with its terms transformed, used by [`Formula::map_terms`],
+//! [`Formula::map_terms_with`] and [`Formula::map_term_trees`].
+//!
+//! Unlike replacing text in the prefix notation, a mapping only ever sees whole leaves of the
+//! term trees, so renaming `index` leaves `index2` and `fib` untouched. The connectives and
+//! quantifiers of the formula are kept as they are.
+use crate::{Formula, FormulaFolder, Term};
+
+/// Applies `f` to every variable and constant of `formula`, and to the variable of every
+/// quantifier if `quantifiers` is set. See [`Formula::map_terms_with`].
+pub(crate) fn map_terms(
+    formula: &Formula,
+    f: impl FnMut(&str) -> String,
+    quantifiers: bool,
+) -> Formula {
+    formula.fold(&mut LeafMap { f, quantifiers })
+}
+
+/// Replaces every term of `formula` with the result of `f`. See [`Formula::map_term_trees`].
+pub(crate) fn map_term_trees(formula: &Formula, f: impl FnMut(&Term) -> Term) -> Formula {
+    formula.fold(&mut TermMap { f })
+}
+
+/// Maps the leaves of every term, and optionally the quantifier variables.
+struct LeafMap<F> {
+    f: F,
+    quantifiers: bool, // Whether quantifier variables are mapped too.
+}
+
+impl<F: FnMut(&str) -> String> LeafMap<F> {
+    fn variable(&mut self, variable: &str) -> String {
+        if self.quantifiers {
+            (self.f)(variable)
+        } else {
+            variable.to_string()
+        }
+    }
+}
+
+impl<F: FnMut(&str) -> String> FormulaFolder for LeafMap<F> {
+    fn fold_term(&mut self, term: &Term) -> Formula {
+        Formula::Term(term.map_leaves(&mut self.f))
+    }
+    fn fold_universal_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        let variable: String = self.variable(variable);
+        Formula::UniversalQuantifier(variable, Box::new(inner.fold(self)))
+    }
+    fn fold_existential_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        let variable: String = self.variable(variable);
+        Formula::ExistentialQuantifier(variable, Box::new(inner.fold(self)))
+    }
+}
+
+/// Maps every term as a whole.
+struct TermMap<F> {
+    f: F,
+}
+
+impl<F: FnMut(&Term) -> Term> FormulaFolder for TermMap<F> {
+    fn fold_term(&mut self, term: &Term) -> Formula {
+        Formula::Term((self.f)(term))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    fn rename_index(name: &str) -> String {
+        match name {
+            "index" => "i".to_string(),
+            _ => name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_map_terms_uppercase() {
+        let test_formula: Formula = Formula::new("∀ x → P(x) ∧ < x n+1 ⊤");
+        assert_eq!(
+            test_formula.map_terms(str::to_uppercase),
+            Formula::new("∀ x → P(X) ∧ < X N+1 ⊤")
+        );
+        assert_eq!(
+            test_formula.map_terms_with(str::to_uppercase, true),
+            Formula::new("∀ X → P(X) ∧ < X N+1 ⊤")
+        );
+    }
+
+    #[test]
+    fn test_map_terms_renames_exact_names() {
+        let test_formula: Formula = Formula::new("∧ < index index2 = fib(index) f(index1,index)");
+        assert_eq!(
+            test_formula.map_terms(rename_index),
+            Formula::new("∧ < i index2 = fib(i) f(index1,i)")
+        );
+        assert_eq!(
+            test_formula.to_prefix_notation().replace("index", "i"),
+            "∧ < i i2 = fib(i) f(i1,i)"
+        );
+    }
+
+    #[test]
+    fn test_map_terms_quantifier_flag() {
+        let test_formula: Formula = Formula::new("∃ index ∧ < index n ∀ index2 P(index,index2)");
+        assert_eq!(
+            test_formula.map_terms_with(rename_index, false),
+            Formula::new("∃ index ∧ < i n ∀ index2 P(i,index2)")
+        );
+        assert_eq!(
+            test_formula.map_terms_with(rename_index, true),
+            Formula::new("∃ i ∧ < i n ∀ index2 P(i,index2)")
+        );
+    }
+
+    #[test]
+    fn test_map_terms_preserves_structure() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(
+                test_formula.map_terms_with(str::to_string, true),
+                test_formula,
+                "{input}"
+            );
+            assert_eq!(test_formula.map_term_trees(Term::clone), test_formula);
+        }
+    }
+
+    #[test]
+    fn test_map_term_trees() {
+        let test_formula: Formula = Formula::new("→ = x r+y*q ∀ y ≥ y 0");
+        let zero: Term = Term::new("0");
+        assert_eq!(
+            test_formula.map_term_trees(|term| match term {
+                Term::App(_, _) => zero.clone(),
+                _ => term.clone(),
+            }),
+            Formula::new("→ = x 0 ∀ y ≥ y 0")
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! # Metrics
+//!
+//! This module measures the size of formulae, used by [`Formula::depth`],
+//! [`Formula::node_count`] and [`Formula::connective_counts`], and their quantifier structure, used
+//! by [`Formula::quantifier_alternations`], [`Formula::quantifier_rank`] and
+//! [`Formula::quantifier_prefix`].
+//!
+//! Every measure walks the formula with an explicit stack rather than by recursion, so that
+//! formulae as deep as the parser accepts can be measured. A node is any subformula, including
+//! the atoms `Term`, `Equality`, `Less`, `Predicate`, `⊤` and `⊥`; a term such as `r+y*q` is a
+//! single node, and so is the equality `= r+y*q x`.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Formula;
+
+/// The kind of a quantifier, as listed by [`Formula::quantifier_prefix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantifier {
+    /// The universal quantifier `∀`.
+    Universal,
+    /// The existential quantifier `∃`.
+    Existential,
+}
+
+impl Quantifier {
+    /// Returns the kind of the outermost node of `formula` if it is a quantifier.
+    fn of(formula: &Formula) -> Option<Quantifier> {
+        match formula {
+            Formula::UniversalQuantifier(_, _) => Some(Quantifier::Universal),
+            Formula::ExistentialQuantifier(_, _) => Some(Quantifier::Existential),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Quantifier {
+    /// Formats the quantifier as its symbol, `∀` or `∃`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Quantifier::Universal => "∀",
+            Quantifier::Existential => "∃",
+        })
+    }
+}
+
+/// Returns the number of nodes on the longest path from `formula` to an atom. See
+/// [`Formula::depth`].
+pub(crate) fn depth(formula: &Formula) -> usize {
+    let mut deepest: usize = 0;
+    for_each_node(formula, |_, depth| deepest = deepest.max(depth));
+    deepest
+}
+
+/// Returns the number of nodes of `formula`. See [`Formula::node_count`].
+pub(crate) fn node_count(formula: &Formula) -> usize {
+    let mut count: usize = 0;
+    for_each_node(formula, |_, _| count += 1);
+    count
+}
+
+/// Counts the nodes of `formula` by name. See [`Formula::connective_counts`].
+pub(crate) fn connective_counts(formula: &Formula) -> BTreeMap<&'static str, usize> {
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for_each_node(formula, |node, _| {
+        *counts.entry(node.kind().name()).or_insert(0) += 1
+    });
+    counts
+}
+
+/// Returns the most switches between `∀` and `∃` along any path of `formula`. See
+/// [`Formula::quantifier_alternations`].
+pub(crate) fn quantifier_alternations(formula: &Formula) -> usize {
+    let mut alternations: usize = 0;
+    let mut stack: Vec<(&Formula, Option<Quantifier>, usize)> = vec![(formula, None, 0)];
+    while let Some((node, last, count)) = stack.pop() {
+        // The count only grows at a quantifier of the other kind than the closest one above.
+        let (last, count): (Option<Quantifier>, usize) = match (Quantifier::of(node), last) {
+            (Some(current), Some(previous)) if current != previous => (Some(current), count + 1),
+            (Some(current), _) => (Some(current), count),
+            (None, _) => (last, count),
+        };
+        alternations = alternations.max(count);
+        stack.extend(
+            node.children()
+                .into_iter()
+                .map(|child| (child, last, count)),
+        );
+    }
+    alternations
+}
+
+/// Returns the most quantifiers nested along any path of `formula`. See
+/// [`Formula::quantifier_rank`].
+pub(crate) fn quantifier_rank(formula: &Formula) -> usize {
+    let mut rank: usize = 0;
+    let mut stack: Vec<(&Formula, usize)> = vec![(formula, 0)];
+    while let Some((node, nested)) = stack.pop() {
+        let nested: usize = nested + usize::from(Quantifier::of(node).is_some());
+        rank = rank.max(nested);
+        stack.extend(node.children().into_iter().map(|child| (child, nested)));
+    }
+    rank
+}
+
+/// Returns the leading quantifiers of `formula` if no quantifier occurs below them. See
+/// [`Formula::quantifier_prefix`].
+pub(crate) fn quantifier_prefix(formula: &Formula) -> Option<Vec<(Quantifier, String)>> {
+    let mut prefix: Vec<(Quantifier, String)> = Vec::new();
+    let mut matrix: &Formula = formula;
+    while let Formula::UniversalQuantifier(variable, inner)
+    | Formula::ExistentialQuantifier(variable, inner) = matrix
+    {
+        prefix.push((Quantifier::of(matrix)?, variable.clone()));
+        matrix = inner;
+    }
+    let mut quantifier_free: bool = true;
+    for_each_node(matrix, |node, _| {
+        quantifier_free &= Quantifier::of(node).is_none()
+    });
+    quantifier_free.then_some(prefix)
+}
+
+/// Calls `visit` on every node of `formula` in prefix order, together with its depth, which is
+/// 1 for `formula` itself.
+fn for_each_node<'a>(formula: &'a Formula, mut visit: impl FnMut(&'a Formula, usize)) {
+    let mut stack: Vec<(&'a Formula, usize)> = vec![(formula, 1)];
+    while let Some((node, depth)) = stack.pop() {
+        visit(node, depth);
+        match node {
+            Formula::Term(_)
+            | Formula::Top
+            | Formula::Bottom
+            | Formula::Equality(_, _)
+            | Formula::Less(_, _)
+            | Formula::Predicate(_, _) => {}
+            Formula::Negation(inner)
+            | Formula::UniversalQuantifier(_, inner)
+            | Formula::ExistentialQuantifier(_, inner) => stack.push((inner, depth + 1)),
+            Formula::Conjunction(lhs, rhs)
+            | Formula::Disjunction(lhs, rhs)
+            | Formula::Implication(lhs, rhs)
+            | Formula::Equivalence(lhs, rhs)
+            | Formula::Iff(lhs, rhs)
+            | Formula::NotEqual(lhs, rhs)
+            | Formula::LessThan(lhs, rhs)
+            | Formula::LessEqual(lhs, rhs)
+            | Formula::GreaterEqual(lhs, rhs)
+            | Formula::GreaterThan(lhs, rhs)
+            | Formula::Custom(_, lhs, rhs) => {
+                stack.push((rhs, depth + 1));
+                stack.push((lhs, depth + 1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParserConfig, Term, corpus};
+
+    fn counts(entries: &[(&'static str, usize)]) -> BTreeMap<&'static str, usize> {
+        entries.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_metrics_of_a_term() {
+        let test_formula: Formula = Formula::new("R(x,r+y*q)");
+        assert_eq!(test_formula.depth(), 1);
+        assert_eq!(test_formula.node_count(), 1);
+        assert_eq!(
+            test_formula.connective_counts(),
+            counts(&[("Predicate", 1)])
+        );
+    }
+
+    #[test]
+    fn test_metrics_of_hand_built_formulae() {
+        let test_formula: Formula = Formula::new(corpus::PAPER_EXAMPLE[9]);
+        assert_eq!(test_formula.depth(), 5);
+        assert_eq!(test_formula.node_count(), 13);
+        assert_eq!(
+            test_formula.connective_counts(),
+            counts(&[
+                ("Conjunction", 2),
+                ("Disjunction", 2),
+                ("Equality", 4),
+                ("Implication", 1),
+                ("Less", 2),
+                ("Negation", 2),
+            ])
+        );
+
+        let test_formula: Formula = Formula::new("∀ x ∃ y ∧ < x y ≠ ⊤ ⊥");
+        assert_eq!(test_formula.depth(), 5);
+        assert_eq!(test_formula.node_count(), 7);
+        assert_eq!(
+            test_formula.connective_counts(),
+            counts(&[
+                ("Bottom", 1),
+                ("Conjunction", 1),
+                ("ExistentialQuantifier", 1),
+                ("Less", 1),
+                ("NotEqual", 1),
+                ("Top", 1),
+                ("UniversalQuantifier", 1),
+            ])
+        );
+
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        let test_formula: Formula = config.parse("⊆ A ¬ B").unwrap();
+        assert_eq!(test_formula.depth(), 3);
+        assert_eq!(
+            test_formula.connective_counts(),
+            counts(&[("Custom", 1), ("Negation", 1), ("Term", 2)])
+        );
+    }
+
+    #[test]
+    fn test_counts_add_up_to_node_count() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            let total: usize = test_formula.connective_counts().values().sum();
+            assert_eq!(total, test_formula.node_count(), "{input}");
+            assert!(test_formula.depth() <= test_formula.node_count(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_metrics_of_a_deep_chain() {
+        const DEEP: usize = 1_000_000;
+        let mut test_formula: Formula = Formula::Term(Term::new("P"));
+        for _ in 0..DEEP {
+            test_formula = Formula::Conjunction(Box::new(Formula::Top), Box::new(test_formula));
+        }
+        assert_eq!(test_formula.depth(), DEEP + 1);
+        assert_eq!(test_formula.node_count(), 2 * DEEP + 1);
+        assert_eq!(
+            test_formula.connective_counts(),
+            counts(&[("Conjunction", DEEP), ("Term", 1), ("Top", DEEP)])
+        );
+    }
+
+    #[test]
+    fn test_quantifier_metrics_of_a_prenex_formula() {
+        let test_formula: Formula = Formula::new("∀ x ∃ y ∃ z ∀ w → R(x,y) ∧ S(z) T(w)");
+        assert_eq!(test_formula.quantifier_alternations(), 2);
+        assert_eq!(test_formula.quantifier_rank(), 4);
+        assert_eq!(
+            test_formula.quantifier_prefix(),
+            Some(vec![
+                (Quantifier::Universal, "x".to_string()),
+                (Quantifier::Existential, "y".to_string()),
+                (Quantifier::Existential, "z".to_string()),
+                (Quantifier::Universal, "w".to_string()),
+            ])
+        );
+        let prefix: String = test_formula
+            .quantifier_prefix()
+            .unwrap()
+            .iter()
+            .map(|(quantifier, variable)| format!("{quantifier}{variable}"))
+            .collect();
+        assert_eq!(prefix, "∀x∃y∃z∀w");
+    }
+
+    #[test]
+    fn test_quantifier_metrics_of_a_non_prenex_formula() {
+        // Each path is measured on its own, so the two conjuncts do not add up.
+        let test_formula: Formula = Formula::new("∧ ∀ x ∃ y R(x,y) ¬ ∃ z ∀ w ∃ v S(z,w,v)");
+        assert_eq!(test_formula.quantifier_alternations(), 2);
+        assert_eq!(test_formula.quantifier_rank(), 3);
+        assert_eq!(test_formula.quantifier_prefix(), None);
+        // Negations do not flip the quantifiers below them.
+        let test_formula: Formula = Formula::new("∀ x ¬ ∀ y → ∀ z P(z) Q(x,y)");
+        assert_eq!(test_formula.quantifier_alternations(), 0);
+        assert_eq!(test_formula.quantifier_rank(), 3);
+        assert_eq!(test_formula.quantifier_prefix(), None);
+    }
+
+    #[test]
+    fn test_quantifier_metrics_without_quantifiers() {
+        for input in ["P", "⊤", "→ ∧ P Q = x 0"] {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(test_formula.quantifier_alternations(), 0, "{input}");
+            assert_eq!(test_formula.quantifier_rank(), 0, "{input}");
+            assert_eq!(
+                test_formula.quantifier_prefix(),
+                Some(Vec::new()),
+                "{input}"
+            );
+        }
+    }
+}
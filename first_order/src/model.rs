@@ -0,0 +1,480 @@
+//! # Finite models
+//!
+//! This module evaluates first-order formulae over a finite interpretation, a [`Model`], for
+//! [`Formula::holds_in`]. A wrong lemma can then be falsified on a small model before it is used
+//! in a proof.
+//!
+//! Quantifiers range over the domain of the model. A variable is looked up in the environment,
+//! then among the constants of the model, and a numeral that the model does not interpret stands
+//! for its own value. Function applications, including the arithmetic operators such as `+`, are
+//! interpreted by the functions of the model, and an atomic proposition `P(x)` by its relations.
+//!
+//! The relations `=`, `≠`, `<`, `≤`, `≥` and `>` between two terms are looked up in the relations
+//! of the model under their symbol. If the model has no table for one of them, `=` is the identity
+//! of domain elements, `≠` its negation, and the others compare the elements as integers. An `=`
+//! with an operand that is not a term is the logical equivalence.
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::{Formula, Fragment, Term};
+
+/// An element of the domain of a [`Model`].
+pub type DomainElem = i64;
+
+/// A function of a [`Model`], mapping its arguments to a domain element.
+type Function = Box<dyn Fn(&[DomainElem]) -> DomainElem>;
+
+/// A finite interpretation of the constants, functions and relations of a formula.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use first_order::{Formula, Model};
+///
+/// let mut model = Model::new(vec![0, 1, 2]);
+/// model
+///     .constant("N", 2)
+///     .function("succ", |args| (args[0] + 1) % 3)
+///     .relation("Even", [vec![0], vec![2]]);
+///
+/// let env = HashMap::new();
+/// assert_eq!(Formula::new("Even(N)").holds_in(&model, &env), Ok(true));
+/// assert_eq!(Formula::new("∀ x → Even(x) ¬ Even(succ(x))").holds_in(&model, &env), Ok(false));
+/// ```
+pub struct Model {
+    domain: Vec<DomainElem>, // The elements quantifiers range over.
+    constants: HashMap<String, DomainElem>, // The values of named constants.
+    functions: HashMap<String, Function>, // The meaning of each functor.
+    relations: HashMap<String, BTreeSet<Vec<DomainElem>>>, // The tuples each relation holds for.
+}
+
+impl Model {
+    /// Creates a `Model` over `domain` without constants, functions or relations.
+    pub fn new(domain: Vec<DomainElem>) -> Self {
+        Model {
+            domain,
+            constants: HashMap::new(),
+            functions: HashMap::new(),
+            relations: HashMap::new(),
+        }
+    }
+
+    /// Returns the elements the quantifiers range over.
+    pub fn domain(&self) -> &[DomainElem] {
+        &self.domain
+    }
+
+    /// Interprets the variable or numeral `name`, when it is not bound by the environment or a
+    /// quantifier, as `value`.
+    pub fn constant(&mut self, name: impl Into<String>, value: DomainElem) -> &mut Self {
+        self.constants.insert(name.into(), value);
+        self
+    }
+
+    /// Interprets the functor `name`, e.g. `fib` or `+`, as `function` applied to the values of
+    /// the arguments.
+    pub fn function(
+        &mut self,
+        name: impl Into<String>,
+        function: impl Fn(&[DomainElem]) -> DomainElem + 'static,
+    ) -> &mut Self {
+        self.functions.insert(name.into(), Box::new(function));
+        self
+    }
+
+    /// Interprets the relation `name`, e.g. `P` or `<`, as holding for exactly the `tuples`. A
+    /// proposition `P` without arguments holds if the empty tuple is among them.
+    pub fn relation(
+        &mut self,
+        name: impl Into<String>,
+        tuples: impl IntoIterator<Item = Vec<DomainElem>>,
+    ) -> &mut Self {
+        self.relations
+            .insert(name.into(), tuples.into_iter().collect());
+        self
+    }
+}
+
+impl fmt::Debug for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut functions: Vec<&String> = self.functions.keys().collect();
+        functions.sort();
+        f.debug_struct("Model")
+            .field("domain", &self.domain)
+            .field("constants", &self.constants)
+            .field("functions", &functions)
+            .field("relations", &self.relations)
+            .finish()
+    }
+}
+
+/// An error returned when a formula cannot be evaluated in a model or as a proposition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// A variable is bound neither by the environment, a quantifier, nor the model.
+    UnboundVariable { name: String },
+    /// A numeral is not interpreted by the model and does not fit in a `DomainElem`.
+    InvalidConstant { name: String },
+    /// The model does not interpret a functor.
+    UnknownFunction { name: String, arity: usize },
+    /// The model does not interpret a relation or proposition.
+    UnknownRelation { name: String, arity: usize },
+    /// The formula contains a `Custom` connective, which has no fixed meaning, or compares
+    /// formulae rather than terms, as in `< ¬ P Q`.
+    UnsupportedConnective { symbol: String },
+    /// A propositional evaluation found a quantifier binding `variable`, so the formula is
+    /// [`Fragment::FirstOrder`].
+    NotQuantifierFree { variable: String },
+    /// A truth table would have more than `limit` atoms.
+    TooManyAtoms { atoms: usize, limit: usize },
+    /// An arithmetic evaluation divided by zero in `term`.
+    DivisionByZero { term: String },
+    /// The value of `term` does not fit in a `DomainElem`.
+    Overflow { term: String },
+    /// The function `name` is undefined for `arguments`, such as `^` for a negative exponent.
+    UndefinedApplication {
+        name: String,
+        arguments: Vec<DomainElem>,
+    },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::UnboundVariable { name } => write!(f, "the variable {name} is not bound"),
+            EvalError::InvalidConstant { name } => {
+                write!(f, "the constant {name} is not a domain element")
+            }
+            EvalError::UnknownFunction { name, arity } => {
+                write!(f, "the function {name} of arity {arity} is not interpreted")
+            }
+            EvalError::UnknownRelation { name, arity } => {
+                write!(f, "the relation {name} of arity {arity} is not interpreted")
+            }
+            EvalError::UnsupportedConnective { symbol } => {
+                write!(f, "the connective {symbol} cannot be evaluated")
+            }
+            EvalError::NotQuantifierFree { variable } => {
+                write!(
+                    f,
+                    "the formula quantifies over {variable}, so it is {} rather than {}",
+                    Fragment::FirstOrder,
+                    Fragment::QuantifierFree
+                )
+            }
+            EvalError::TooManyAtoms { atoms, limit } => {
+                write!(
+                    f,
+                    "the formula has {atoms} atoms, more than the limit of {limit}"
+                )
+            }
+            EvalError::DivisionByZero { term } => write!(f, "{term} divides by zero"),
+            EvalError::Overflow { term } => write!(f, "the value of {term} overflows"),
+            EvalError::UndefinedApplication { name, arguments } => {
+                write!(f, "{name} is undefined for the arguments {arguments:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Evaluates `formula` in `model` under `env`. See [`Formula::holds_in`].
+pub(crate) fn holds_in(
+    formula: &Formula,
+    model: &Model,
+    env: &HashMap<String, DomainElem>,
+) -> Result<bool, EvalError> {
+    Evaluator {
+        model,
+        env: env.clone(),
+    }
+    .holds(formula)
+}
+
+/// Evaluates formulae in a model, binding quantified variables in its own environment.
+struct Evaluator<'a> {
+    model: &'a Model,                 // The model to evaluate in.
+    env: HashMap<String, DomainElem>, // The values of the free and quantified variables.
+}
+
+impl Evaluator<'_> {
+    fn holds(&mut self, formula: &Formula) -> Result<bool, EvalError> {
+        Ok(match formula {
+            Formula::Term(term) => self.atom(term)?,
+            Formula::Predicate(name, args) => self.predicate(name, args)?,
+            Formula::Equality(lhs, rhs) => self.relation("=", lhs, rhs)?,
+            Formula::Less(lhs, rhs) => self.relation("<", lhs, rhs)?,
+            Formula::Top => true,
+            Formula::Bottom => false,
+            Formula::Negation(inner) => !self.holds(inner)?,
+            Formula::Conjunction(lhs, rhs) => self.holds(lhs)? && self.holds(rhs)?,
+            Formula::Disjunction(lhs, rhs) => self.holds(lhs)? || self.holds(rhs)?,
+            Formula::Implication(lhs, rhs) => !self.holds(lhs)? || self.holds(rhs)?,
+            Formula::Equivalence(lhs, rhs) => match (&**lhs, &**rhs) {
+                (Formula::Term(lhs), Formula::Term(rhs)) => self.relation("=", lhs, rhs)?,
+                _ => self.holds(lhs)? == self.holds(rhs)?,
+            },
+            Formula::Iff(lhs, rhs) => self.holds(lhs)? == self.holds(rhs)?,
+            Formula::NotEqual(lhs, rhs) => self.comparison("≠", lhs, rhs)?,
+            Formula::LessThan(lhs, rhs) => self.comparison("<", lhs, rhs)?,
+            Formula::LessEqual(lhs, rhs) => self.comparison("≤", lhs, rhs)?,
+            Formula::GreaterEqual(lhs, rhs) => self.comparison("≥", lhs, rhs)?,
+            Formula::GreaterThan(lhs, rhs) => self.comparison(">", lhs, rhs)?,
+            Formula::UniversalQuantifier(variable, inner) => {
+                self.quantifier(variable, inner, true)?
+            }
+            Formula::ExistentialQuantifier(variable, inner) => {
+                self.quantifier(variable, inner, false)?
+            }
+            Formula::Custom(symbol, _, _) => {
+                return Err(EvalError::UnsupportedConnective {
+                    symbol: symbol.clone(),
+                });
+            }
+        })
+    }
+
+    /// Evaluates `∀ variable inner` if `universal` is set, and `∃ variable inner` otherwise.
+    fn quantifier(
+        &mut self,
+        variable: &str,
+        inner: &Formula,
+        universal: bool,
+    ) -> Result<bool, EvalError> {
+        let shadowed: Option<DomainElem> = self.env.get(variable).copied();
+        let mut result: Result<bool, EvalError> = Ok(universal);
+        for element in self.model.domain() {
+            self.env.insert(variable.to_string(), *element);
+            match self.holds(inner) {
+                Ok(holds) if holds == universal => {}
+                other => {
+                    result = other;
+                    break;
+                }
+            }
+        }
+        match shadowed {
+            Some(value) => self.env.insert(variable.to_string(), value),
+            None => self.env.remove(variable),
+        };
+        result
+    }
+
+    /// Evaluates an atomic proposition such as `P` or `R(x,y)` with the relations of the model.
+    fn atom(&self, term: &Term) -> Result<bool, EvalError> {
+        let spelling: String;
+        let (name, args): (&str, &[Term]) = match term {
+            Term::App(name, args) if !name.is_empty() => (name, args),
+            Term::Var(name) | Term::Const(name) => (name, &[]),
+            Term::IntLit(value) => {
+                spelling = value.to_string();
+                (&spelling, &[])
+            }
+            Term::App(_, args) => return self.atom(&args[0]),
+        };
+        self.predicate(name, args)
+    }
+
+    /// Evaluates the relation `name` applied to `args` with the relations of the model.
+    fn predicate(&self, name: &str, args: &[Term]) -> Result<bool, EvalError> {
+        let values: Vec<DomainElem> = args
+            .iter()
+            .map(|arg| self.term(arg))
+            .collect::<Result<_, _>>()?;
+        match self.model.relations.get(name) {
+            Some(tuples) => Ok(tuples.contains(&values)),
+            None => Err(EvalError::UnknownRelation {
+                name: name.to_string(),
+                arity: values.len(),
+            }),
+        }
+    }
+
+    /// Evaluates the relation `symbol` between two terms with the table of the model, or with its
+    /// built-in meaning if the model has none.
+    fn relation(&self, symbol: &str, lhs: &Term, rhs: &Term) -> Result<bool, EvalError> {
+        let values: Vec<DomainElem> = vec![self.term(lhs)?, self.term(rhs)?];
+        if let Some(tuples) = self.model.relations.get(symbol) {
+            return Ok(tuples.contains(&values));
+        }
+        Ok(match symbol {
+            "=" => values[0] == values[1],
+            "≠" => return Ok(!self.relation("=", lhs, rhs)?),
+            "<" => values[0] < values[1],
+            "≤" => values[0] <= values[1],
+            "≥" => values[0] >= values[1],
+            _ => values[0] > values[1],
+        })
+    }
+
+    /// Evaluates the comparison `symbol`, whose operands must be terms.
+    fn comparison(&self, symbol: &str, lhs: &Formula, rhs: &Formula) -> Result<bool, EvalError> {
+        match (lhs, rhs) {
+            (Formula::Term(lhs), Formula::Term(rhs)) => self.relation(symbol, lhs, rhs),
+            _ => Err(EvalError::UnsupportedConnective {
+                symbol: symbol.to_string(),
+            }),
+        }
+    }
+
+    /// Evaluates `term` to a domain element.
+    fn term(&self, term: &Term) -> Result<DomainElem, EvalError> {
+        match term {
+            Term::Var(name) => self
+                .env
+                .get(name)
+                .or_else(|| self.model.constants.get(name))
+                .copied()
+                .ok_or_else(|| EvalError::UnboundVariable { name: name.clone() }),
+            Term::IntLit(value) => Ok(self
+                .model
+                .constants
+                .get(&value.to_string())
+                .copied()
+                .unwrap_or(*value)),
+            Term::Const(name) => match self.model.constants.get(name) {
+                Some(value) => Ok(*value),
+                None => name
+                    .parse()
+                    .map_err(|_| EvalError::InvalidConstant { name: name.clone() }),
+            },
+            Term::App(name, args) if name.is_empty() => self.term(&args[0]),
+            Term::App(name, args) => {
+                let values: Vec<DomainElem> = args
+                    .iter()
+                    .map(|arg| self.term(arg))
+                    .collect::<Result<_, _>>()?;
+                match self.model.functions.get(name) {
+                    Some(function) => Ok(function(&values)),
+                    None => Err(EvalError::UnknownFunction {
+                        name: name.clone(),
+                        arity: values.len(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty() -> HashMap<String, DomainElem> {
+        HashMap::new()
+    }
+
+    #[test]
+    fn test_every_element_has_a_successor() {
+        let test_formula: Formula = Formula::new("∀ x ∃ y < x y");
+        let ordered: Model = Model::new(vec![0, 1, 2]);
+        assert_eq!(test_formula.holds_in(&ordered, &empty()), Ok(false));
+
+        let mut cyclic: Model = Model::new(vec![0, 1, 2]);
+        cyclic.relation("<", [vec![0, 1], vec![1, 2], vec![2, 0]]);
+        assert_eq!(test_formula.holds_in(&cyclic, &empty()), Ok(true));
+        assert_eq!(
+            Formula::new("∀ x ¬ < x x").holds_in(&cyclic, &empty()),
+            Ok(true)
+        );
+        assert_eq!(
+            Formula::new("∀ x ∀ y ∀ z → ∧ < x y < y z < x z").holds_in(&cyclic, &empty()),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_environment() {
+        let model: Model = Model::new(vec![0, 1, 2]);
+        let env: HashMap<String, DomainElem> = HashMap::from([("x".to_string(), 2)]);
+        assert_eq!(Formula::new("∃ y < y x").holds_in(&model, &env), Ok(true));
+        assert_eq!(Formula::new("∃ x < 2 x").holds_in(&model, &env), Ok(false));
+        assert_eq!(
+            Formula::new("∧ ∀ x ≥ x 0 = x 2").holds_in(&model, &env),
+            Ok(true)
+        );
+        assert_eq!(
+            Formula::new("< x y").holds_in(&model, &env),
+            Err(EvalError::UnboundVariable {
+                name: "y".to_string()
+            })
+        );
+        assert_eq!(
+            Formula::new("∀ y < x z")
+                .holds_in(&model, &env)
+                .unwrap_err()
+                .to_string(),
+            "the variable z is not bound"
+        );
+    }
+
+    #[test]
+    fn test_functions_and_relations() {
+        let mut model: Model = Model::new(vec![0, 1, 2, 3]);
+        model
+            .constant("N", 3)
+            .function("+", |args| (args[0] + args[1]) % 4)
+            .function("*", |args| (args[0] * args[1]) % 4)
+            .relation("R", [vec![0, 0], vec![1, 3]])
+            .relation("P", [vec![]]);
+        let env: HashMap<String, DomainElem> = HashMap::from([("q".to_string(), 1)]);
+        assert_eq!(Formula::new("R(q,N)").holds_in(&model, &env), Ok(true));
+        assert_eq!(
+            Formula::new("∧ P ∃ x R(x,x)").holds_in(&model, &env),
+            Ok(true)
+        );
+        assert_eq!(Formula::new("∀ x = x+0 x").holds_in(&model, &env), Ok(true));
+        assert_eq!(
+            Formula::new("∀ x ∀ y = (x+y)*2 x*2+y*2").holds_in(&model, &env),
+            Ok(true)
+        );
+        assert_eq!(
+            Formula::new("= P ¬ R(q,N)").holds_in(&model, &env),
+            Ok(false)
+        );
+        // A numeral the model interprets stands for its interpretation rather than its value.
+        model.constant("4", 0);
+        assert_eq!(Formula::new("R(4,0)").holds_in(&model, &env), Ok(true));
+        assert_eq!(
+            Formula::new("= x f(x)").holds_in(&model, &env),
+            Err(EvalError::UnboundVariable {
+                name: "x".to_string()
+            })
+        );
+        assert_eq!(
+            Formula::new("∀ x = x f(x)").holds_in(&model, &env),
+            Err(EvalError::UnknownFunction {
+                name: "f".to_string(),
+                arity: 1
+            })
+        );
+        assert_eq!(
+            Formula::new("< ¬ P q").holds_in(&model, &env),
+            Err(EvalError::UnsupportedConnective {
+                symbol: "<".to_string()
+            })
+        );
+        assert_eq!(
+            Formula::new("Q(q)").holds_in(&model, &env),
+            Err(EvalError::UnknownRelation {
+                name: "Q".to_string(),
+                arity: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_paper_example_invariant_on_a_small_model() {
+        let mut model: Model = Model::new((0..8).collect());
+        model
+            .function("+", |args| args[0] + args[1])
+            .function("-", |args| args[0] - args[1])
+            .function("*", |args| args[0] * args[1]);
+        let lemma: Formula =
+            Formula::new("∀ x ∀ y ∀ r ∀ q → ∧ = x r+y*q ∨ < y r = y r = x (r-y)+y*(1+q)");
+        assert_eq!(lemma.holds_in(&model, &empty()), Ok(true));
+        let wrong: Formula = Formula::new("∀ x ∀ y ∀ r ∀ q → = x r+y*q = x (r-y)+y*q");
+        assert_eq!(wrong.holds_in(&model, &empty()), Ok(false));
+    }
+}
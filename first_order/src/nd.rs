@@ -0,0 +1,713 @@
+//! # Natural deduction
+//!
+//! This module checks natural deduction proofs of formulae, so that the pure-logic lemmas of a
+//! proof can be derived rather than asserted. A [`Derivation`] is a tree of [`Rule`]
+//! applications whose leaves are assumptions, and it proves its conclusion from the assumptions
+//! that no rule discharges:
+//!
+//! | Rule        | Premises       | Conclusion | Discharges |
+//! |-------------|----------------|------------|------------|
+//! | `∧I`        | `φ`, `ψ`       | `∧ φ ψ`    |            |
+//! | `∧E₁`       | `∧ φ ψ`        | `φ`        |            |
+//! | `∧E₂`       | `∧ φ ψ`        | `ψ`        |            |
+//! | `→I`        | `ψ`            | `→ φ ψ`    | `φ`        |
+//! | `→E`        | `→ φ ψ`, `φ`   | `ψ`        |            |
+//! | `¬I`        | `⊥`            | `¬ φ`      | `φ`        |
+//! | `¬E`        | `¬ φ`, `φ`     | `⊥`        |            |
+//! | `∀I`        | `φ`            | `∀ x φ`    |            |
+//! | `∀E`        | `∀ x φ`        | `φ[x:=t]`  |            |
+//! | `∃I`        | `φ[x:=t]`      | `∃ x φ`    |            |
+//! | `∃E`        | `∃ x φ`, `ψ`   | `ψ`        | `φ[x:=y]`  |
+//!
+//! A rule discharges the assumptions equal to its hypothesis in its last premise. `∀I` requires
+//! that `x` occurs free in no open assumption of its premise, and `∃E` that the eigenvariable `y`
+//! occurs free neither in `∃ x φ`, in `ψ`, nor in an open assumption other than `φ[x:=y]`. The
+//! term `t` substituted by `∀E` and `∃I` may not mention a variable bound inside `φ`, so that
+//! no variable of `t` is captured.
+//!
+//! Every constructor of a `Derivation` checks the side conditions of its rule, and
+//! [`Derivation::check`] checks them again for the whole tree, e.g. after it was assembled or
+//! edited by hand.
+use std::fmt;
+
+use crate::{Formula, Substitution, unify};
+
+/// A rule of natural deduction, applied at a node of a [`Derivation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// Assumes the formula, which stays open until a rule discharges it.
+    Assumption(Formula),
+    /// `∧I`, concluding `∧ φ ψ` from `φ` and `ψ`.
+    AndIntro,
+    /// `∧E₁`, concluding `φ` from `∧ φ ψ`.
+    AndElimLeft,
+    /// `∧E₂`, concluding `ψ` from `∧ φ ψ`.
+    AndElimRight,
+    /// `→I`, concluding `→ φ ψ` from `ψ`, and discharging the hypothesis `φ`.
+    ImpliesIntro(Formula),
+    /// `→E`, concluding `ψ` from `→ φ ψ` and `φ`.
+    ImpliesElim,
+    /// `¬I`, concluding `¬ φ` from `⊥`, and discharging the hypothesis `φ`.
+    NotIntro(Formula),
+    /// `¬E`, concluding `⊥` from `¬ φ` and `φ`.
+    NotElim,
+    /// `∀I`, concluding `∀ x φ` from `φ` for the eigenvariable `x`.
+    ForallIntro(String),
+    /// `∀E`, concluding `φ[x:=t]` from `∀ x φ` for the term `t`.
+    ForallElim(String),
+    /// `∃I`, concluding `∃ x φ` from `φ[x:=t]` for the variable `x`, the body `φ` and the witness
+    /// `t`.
+    ExistsIntro(String, Formula, String),
+    /// `∃E`, concluding `ψ` from `∃ x φ` and `ψ` for the eigenvariable `y`, and discharging
+    /// `φ[x:=y]`.
+    ExistsElim(String),
+}
+
+impl Rule {
+    /// Returns the short name of the rule, such as `"∧I"` or `"∀E"`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::nd::Rule;
+    ///
+    /// assert_eq!(Rule::AndElimLeft.name(), "∧E₁");
+    /// assert_eq!(Rule::ForallIntro("x".to_string()).name(), "∀I");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Assumption(_) => "assumption",
+            Rule::AndIntro => "∧I",
+            Rule::AndElimLeft => "∧E₁",
+            Rule::AndElimRight => "∧E₂",
+            Rule::ImpliesIntro(_) => "→I",
+            Rule::ImpliesElim => "→E",
+            Rule::NotIntro(_) => "¬I",
+            Rule::NotElim => "¬E",
+            Rule::ForallIntro(_) => "∀I",
+            Rule::ForallElim(_) => "∀E",
+            Rule::ExistsIntro(_, _, _) => "∃I",
+            Rule::ExistsElim(_) => "∃E",
+        }
+    }
+
+    /// Returns the number of premises the rule takes.
+    fn premise_count(&self) -> usize {
+        match self {
+            Rule::Assumption(_) => 0,
+            Rule::AndIntro | Rule::ImpliesElim | Rule::NotElim | Rule::ExistsElim(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    /// Formats the rule as its name, e.g. `→E`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An error returned when a rule of a [`Derivation`] does not apply to its premises.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationError {
+    /// The rule takes `expected` premises, but was given `found`.
+    PremiseCount {
+        rule: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A premise does not have the form the rule needs, described by `expected`.
+    WrongPremise {
+        rule: &'static str,
+        expected: String,
+        found: Box<Formula>,
+    },
+    /// The eigenvariable `variable` of the rule occurs free in `formula`.
+    Eigenvariable {
+        rule: &'static str,
+        variable: String,
+        formula: Box<Formula>,
+    },
+    /// Substituting `term` into `formula` would capture a variable of `term`.
+    Capture {
+        rule: &'static str,
+        term: String,
+        formula: Box<Formula>,
+    },
+    /// A node records the conclusion `recorded`, but its rule concludes `derived`.
+    WrongConclusion {
+        recorded: Box<Formula>,
+        derived: Box<Formula>,
+    },
+}
+
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationError::PremiseCount {
+                rule,
+                expected,
+                found,
+            } => write!(f, "{rule} takes {expected} premises, but was given {found}"),
+            DerivationError::WrongPremise {
+                rule,
+                expected,
+                found,
+            } => write!(f, "{rule} expected {expected} as premise, found {found}"),
+            DerivationError::Eigenvariable {
+                rule,
+                variable,
+                formula,
+            } => write!(
+                f,
+                "{rule} cannot use the eigenvariable {variable}, which occurs free in {formula}"
+            ),
+            DerivationError::Capture {
+                rule,
+                term,
+                formula,
+            } => write!(
+                f,
+                "{rule} cannot substitute {term} into {formula}, which binds one of its variables"
+            ),
+            DerivationError::WrongConclusion { recorded, derived } => {
+                write!(
+                    f,
+                    "the conclusion {recorded} does not follow, the rule concludes {derived}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DerivationError {}
+
+/// A natural deduction proof of a formula: a rule applied to the derivations of its premises.
+///
+/// The constructors check the side conditions of their rule and compute the conclusion. The
+/// fields are public so that a derivation can be inspected or assembled by hand, in which case
+/// [`Derivation::check`] validates it.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use first_order::nd::Derivation;
+///
+/// // From the assumption `∧ A B` conclude `A`, then discharge the assumption.
+/// let hypothesis: Formula = Formula::new("∧ A B");
+/// let left = Derivation::and_elim_left(Derivation::assume(hypothesis.clone())).unwrap();
+/// let lemma: Derivation = Derivation::implies_intro(hypothesis, left);
+/// assert_eq!(*lemma.conclusion(), Formula::new("→ ∧ A B A"));
+/// assert!(lemma.is_closed());
+/// assert_eq!(lemma.check(), Ok(()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Derivation {
+    /// The rule applied at the root of the derivation.
+    pub rule: Rule,
+    /// The derivations of the premises of the rule, in the order of the table of the module.
+    pub premises: Vec<Derivation>,
+    /// The formula the rule concludes.
+    pub conclusion: Formula,
+}
+
+impl Derivation {
+    /// Applies `rule` to the derivations of its `premises`.
+    ///
+    /// # Returns
+    /// A `Result` containing the `Derivation`, or a `DerivationError` if the rule does not apply
+    /// to the premises or violates its eigenvariable condition.
+    pub fn new(rule: Rule, premises: Vec<Derivation>) -> Result<Derivation, DerivationError> {
+        let conclusion: Formula = infer(&rule, &premises)?;
+        Ok(Derivation {
+            rule,
+            premises,
+            conclusion,
+        })
+    }
+
+    /// Assumes `formula`, which is an open assumption of every derivation built on this one
+    /// until a rule discharges it.
+    pub fn assume(formula: Formula) -> Derivation {
+        Derivation {
+            rule: Rule::Assumption(formula.clone()),
+            premises: Vec::new(),
+            conclusion: formula,
+        }
+    }
+
+    /// Concludes `∧ φ ψ` from derivations of `φ` and `ψ`.
+    pub fn and_intro(lhs: Derivation, rhs: Derivation) -> Derivation {
+        Derivation::new(Rule::AndIntro, vec![lhs, rhs]).expect("∧I applies to any premises")
+    }
+
+    /// Concludes `φ` from a derivation of `∧ φ ψ`.
+    pub fn and_elim_left(premise: Derivation) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::AndElimLeft, vec![premise])
+    }
+
+    /// Concludes `ψ` from a derivation of `∧ φ ψ`.
+    pub fn and_elim_right(premise: Derivation) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::AndElimRight, vec![premise])
+    }
+
+    /// Concludes `→ φ ψ` from a derivation of `ψ`, discharging the assumptions of `hypothesis`,
+    /// which is `φ`.
+    pub fn implies_intro(hypothesis: Formula, premise: Derivation) -> Derivation {
+        Derivation::new(Rule::ImpliesIntro(hypothesis), vec![premise])
+            .expect("→I applies to any premise")
+    }
+
+    /// Concludes `ψ` from derivations of `→ φ ψ` and of `φ`.
+    pub fn implies_elim(
+        implication: Derivation,
+        antecedent: Derivation,
+    ) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::ImpliesElim, vec![implication, antecedent])
+    }
+
+    /// Concludes `¬ φ` from a derivation of `⊥`, discharging the assumptions of `hypothesis`,
+    /// which is `φ`.
+    pub fn not_intro(
+        hypothesis: Formula,
+        premise: Derivation,
+    ) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::NotIntro(hypothesis), vec![premise])
+    }
+
+    /// Concludes `⊥` from derivations of `¬ φ` and of `φ`.
+    pub fn not_elim(
+        negation: Derivation,
+        premise: Derivation,
+    ) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::NotElim, vec![negation, premise])
+    }
+
+    /// Concludes `∀ x φ` from a derivation of `φ`, provided that `variable`, which is `x`, occurs
+    /// free in none of its open assumptions.
+    pub fn forall_intro(
+        variable: &str,
+        premise: Derivation,
+    ) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::ForallIntro(variable.to_string()), vec![premise])
+    }
+
+    /// Concludes `φ[x:=t]` from a derivation of `∀ x φ`, where `term` is `t`.
+    pub fn forall_elim(premise: Derivation, term: &str) -> Result<Derivation, DerivationError> {
+        Derivation::new(Rule::ForallElim(term.to_string()), vec![premise])
+    }
+
+    /// Concludes `∃ x φ` from a derivation of `φ[x:=t]`, where `variable` is `x`, `body` is `φ`
+    /// and `witness` is `t`.
+    pub fn exists_intro(
+        premise: Derivation,
+        variable: &str,
+        body: Formula,
+        witness: &str,
+    ) -> Result<Derivation, DerivationError> {
+        let rule: Rule = Rule::ExistsIntro(variable.to_string(), body, witness.to_string());
+        Derivation::new(rule, vec![premise])
+    }
+
+    /// Concludes `ψ` from derivations of `∃ x φ` and of `ψ`, discharging the assumptions
+    /// `φ[x:=y]` of the latter, where `variable` is the eigenvariable `y`.
+    pub fn exists_elim(
+        existential: Derivation,
+        variable: &str,
+        premise: Derivation,
+    ) -> Result<Derivation, DerivationError> {
+        Derivation::new(
+            Rule::ExistsElim(variable.to_string()),
+            vec![existential, premise],
+        )
+    }
+
+    /// Returns the formula the derivation proves.
+    pub fn conclusion(&self) -> &Formula {
+        &self.conclusion
+    }
+
+    /// Returns the assumptions that no rule of the derivation discharges, in the order of its
+    /// leaves, with repetitions.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    /// use first_order::nd::Derivation;
+    ///
+    /// let a = Derivation::assume(Formula::new("A"));
+    /// let b = Derivation::assume(Formula::new("B"));
+    /// let both: Derivation = Derivation::and_intro(a, b);
+    /// assert_eq!(both.open_assumptions(), [&Formula::new("A"), &Formula::new("B")]);
+    /// let lemma: Derivation = Derivation::implies_intro(Formula::new("A"), both);
+    /// assert_eq!(lemma.open_assumptions(), [&Formula::new("B")]);
+    /// ```
+    pub fn open_assumptions(&self) -> Vec<&Formula> {
+        if let Rule::Assumption(_) = self.rule {
+            return vec![&self.conclusion];
+        }
+        let discharged: Option<Formula> = self.discharged();
+        let last: usize = self.premises.len().saturating_sub(1);
+        let mut open: Vec<&Formula> = Vec::new();
+        for (index, premise) in self.premises.iter().enumerate() {
+            open.extend(
+                premise
+                    .open_assumptions()
+                    .into_iter()
+                    .filter(|assumption| index != last || Some(*assumption) != discharged.as_ref()),
+            );
+        }
+        open
+    }
+
+    /// Returns whether the derivation proves its conclusion from no assumptions.
+    pub fn is_closed(&self) -> bool {
+        self.open_assumptions().is_empty()
+    }
+
+    /// Checks every rule of the derivation against its premises, and every recorded conclusion
+    /// against the one its rule concludes.
+    ///
+    /// # Returns
+    /// `Ok(())` if the derivation is valid, or the `DerivationError` of the first invalid node,
+    /// checking the premises of every node before the node itself.
+    pub fn check(&self) -> Result<(), DerivationError> {
+        for premise in &self.premises {
+            premise.check()?;
+        }
+        let derived: Formula = infer(&self.rule, &self.premises)?;
+        if derived != self.conclusion {
+            return Err(DerivationError::WrongConclusion {
+                recorded: Box::new(self.conclusion.clone()),
+                derived: Box::new(derived),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the assumption the rule at the root discharges in its last premise, if any.
+    fn discharged(&self) -> Option<Formula> {
+        match &self.rule {
+            Rule::ImpliesIntro(hypothesis) | Rule::NotIntro(hypothesis) => Some(hypothesis.clone()),
+            Rule::ExistsElim(eigenvariable) => {
+                match self.premises.first().map(Derivation::conclusion) {
+                    Some(Formula::ExistentialQuantifier(variable, inner)) => {
+                        Some(substitute(variable, inner, eigenvariable))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the conclusion of `rule` applied to `premises`, checking its side conditions.
+fn infer(rule: &Rule, premises: &[Derivation]) -> Result<Formula, DerivationError> {
+    let name: &'static str = rule.name();
+    if premises.len() != rule.premise_count() {
+        return Err(DerivationError::PremiseCount {
+            rule: name,
+            expected: rule.premise_count(),
+            found: premises.len(),
+        });
+    }
+    let wrong = |expected: String, found: &Formula| DerivationError::WrongPremise {
+        rule: name,
+        expected,
+        found: Box::new(found.clone()),
+    };
+    let conclusion = |index: usize| premises[index].conclusion();
+    match rule {
+        Rule::Assumption(formula) => Ok(formula.clone()),
+        Rule::AndIntro => Ok(conclusion(0).clone().and(conclusion(1).clone())),
+        Rule::AndElimLeft | Rule::AndElimRight => match conclusion(0) {
+            Formula::Conjunction(lhs, _) if *rule == Rule::AndElimLeft => Ok(lhs.as_ref().clone()),
+            Formula::Conjunction(_, rhs) => Ok(rhs.as_ref().clone()),
+            found => Err(wrong("a conjunction".to_string(), found)),
+        },
+        Rule::ImpliesIntro(hypothesis) => Ok(hypothesis.clone().implies(conclusion(0).clone())),
+        Rule::ImpliesElim => match conclusion(0) {
+            Formula::Implication(lhs, rhs) if **lhs == *conclusion(1) => Ok(rhs.as_ref().clone()),
+            Formula::Implication(lhs, _) => {
+                Err(wrong(format!("the antecedent {lhs}"), conclusion(1)))
+            }
+            found => Err(wrong("an implication".to_string(), found)),
+        },
+        Rule::NotIntro(hypothesis) => match conclusion(0) {
+            Formula::Bottom => Ok(hypothesis.clone().not()),
+            found => Err(wrong("⊥".to_string(), found)),
+        },
+        Rule::NotElim => match conclusion(0) {
+            Formula::Negation(inner) if **inner == *conclusion(1) => Ok(Formula::Bottom),
+            Formula::Negation(inner) => Err(wrong(format!("the negated {inner}"), conclusion(1))),
+            found => Err(wrong("a negation".to_string(), found)),
+        },
+        Rule::ForallIntro(variable) => {
+            fresh(name, variable, premises[0].open_assumptions())?;
+            Ok(conclusion(0).clone().forall(variable.clone()))
+        }
+        Rule::ForallElim(term) => match conclusion(0) {
+            Formula::UniversalQuantifier(variable, inner) => {
+                instantiate(name, variable, inner, term)
+            }
+            found => Err(wrong("a universal quantification".to_string(), found)),
+        },
+        Rule::ExistsIntro(variable, body, witness) => {
+            let instance: Formula = instantiate(name, variable, body, witness)?;
+            if instance != *conclusion(0) {
+                return Err(wrong(format!("the instance {instance}"), conclusion(0)));
+            }
+            Ok(body.clone().exists(variable.clone()))
+        }
+        Rule::ExistsElim(eigenvariable) => {
+            let (variable, inner): (&String, &Formula) = match conclusion(0) {
+                Formula::ExistentialQuantifier(variable, inner) => (variable, inner),
+                found => return Err(wrong("an existential quantification".to_string(), found)),
+            };
+            let discharged: Formula = instantiate(name, variable, inner, eigenvariable)?;
+            let mut formulae: Vec<&Formula> = vec![conclusion(0), conclusion(1)];
+            formulae.extend(premises[0].open_assumptions());
+            formulae.extend(
+                premises[1]
+                    .open_assumptions()
+                    .into_iter()
+                    .filter(|assumption| **assumption != discharged),
+            );
+            fresh(name, eigenvariable, formulae)?;
+            Ok(conclusion(1).clone())
+        }
+    }
+}
+
+/// Checks that the eigenvariable `variable` of the rule `rule` occurs free in none of `formulae`.
+fn fresh<'a>(
+    rule: &'static str,
+    variable: &str,
+    formulae: impl IntoIterator<Item = &'a Formula>,
+) -> Result<(), DerivationError> {
+    match formulae
+        .into_iter()
+        .find(|formula| formula.free_variables().contains(variable))
+    {
+        Some(formula) => Err(DerivationError::Eigenvariable {
+            rule,
+            variable: variable.to_string(),
+            formula: Box::new(formula.clone()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns `inner` with `term` substituted for the free occurrences of `variable`, provided that
+/// `inner` binds no variable of `term`.
+fn instantiate(
+    rule: &'static str,
+    variable: &str,
+    inner: &Formula,
+    term: &str,
+) -> Result<Formula, DerivationError> {
+    let bound = inner.bound_variables();
+    if Formula::term(term)
+        .free_variables()
+        .iter()
+        .any(|name| bound.contains(name))
+    {
+        return Err(DerivationError::Capture {
+            rule,
+            term: term.to_string(),
+            formula: Box::new(inner.clone()),
+        });
+    }
+    Ok(substitute(variable, inner, term))
+}
+
+/// Returns `inner` with `term` substituted for the free occurrences of `variable`.
+fn substitute(variable: &str, inner: &Formula, term: &str) -> Formula {
+    let mut substitution: Substitution = Substitution::new();
+    substitution.bind(variable, Formula::term(term));
+    unify::apply(inner, &substitution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assume(input: &str) -> Derivation {
+        Derivation::assume(Formula::new(input))
+    }
+
+    #[test]
+    fn test_conjunction_elimination_lemma() {
+        let hypothesis: Formula = Formula::new("∧ A B");
+        let left: Derivation = Derivation::and_elim_left(assume("∧ A B")).unwrap();
+        assert_eq!(left.open_assumptions(), [&hypothesis]);
+        let lemma: Derivation = Derivation::implies_intro(hypothesis, left);
+        assert_eq!(*lemma.conclusion(), Formula::new("→ ∧ A B A"));
+        assert!(lemma.is_closed());
+        assert_eq!(lemma.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_modus_tollens() {
+        // From → A B and ¬ B, the assumption A leads to ⊥.
+        let b: Derivation = Derivation::implies_elim(assume("→ A B"), assume("A")).unwrap();
+        let absurd: Derivation = Derivation::not_elim(assume("¬ B"), b).unwrap();
+        let not_a: Derivation = Derivation::not_intro(Formula::new("A"), absurd).unwrap();
+        assert_eq!(
+            not_a.open_assumptions(),
+            [&Formula::new("¬ B"), &Formula::new("→ A B")]
+        );
+        let lemma: Derivation = Derivation::implies_intro(
+            Formula::new("∧ → A B ¬ B"),
+            Derivation::new(
+                Rule::NotIntro(Formula::new("A")),
+                vec![
+                    Derivation::not_elim(
+                        Derivation::and_elim_right(assume("∧ → A B ¬ B")).unwrap(),
+                        Derivation::implies_elim(
+                            Derivation::and_elim_left(assume("∧ → A B ¬ B")).unwrap(),
+                            assume("A"),
+                        )
+                        .unwrap(),
+                    )
+                    .unwrap(),
+                ],
+            )
+            .unwrap(),
+        );
+        assert_eq!(*lemma.conclusion(), Formula::new("→ ∧ → A B ¬ B ¬ A"));
+        assert!(lemma.is_closed());
+        assert_eq!(lemma.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_eigenvariable_condition() {
+        assert_eq!(
+            Derivation::forall_intro("x", assume("P(x)")),
+            Err(DerivationError::Eigenvariable {
+                rule: "∀I",
+                variable: "x".to_string(),
+                formula: Box::new(Formula::new("P(x)"))
+            })
+        );
+        let reflexive: Derivation = Derivation::implies_intro(Formula::new("P(x)"), assume("P(x)"));
+        let general: Derivation = Derivation::forall_intro("x", reflexive).unwrap();
+        assert_eq!(*general.conclusion(), Formula::new("∀ x → P(x) P(x)"));
+        assert_eq!(
+            Derivation::forall_intro("x", assume("P(x)"))
+                .unwrap_err()
+                .to_string(),
+            "∀I cannot use the eigenvariable x, which occurs free in P(x)"
+        );
+    }
+
+    #[test]
+    fn test_quantifier_rules() {
+        // → ∀ x P(x) ∃ x P(x)
+        let instance: Derivation = Derivation::forall_elim(assume("∀ x P(x)"), "0").unwrap();
+        assert_eq!(*instance.conclusion(), Formula::new("P(0)"));
+        let witness: Derivation =
+            Derivation::exists_intro(instance, "x", Formula::new("P(x)"), "0").unwrap();
+        let lemma: Derivation = Derivation::implies_intro(Formula::new("∀ x P(x)"), witness);
+        assert_eq!(*lemma.conclusion(), Formula::new("→ ∀ x P(x) ∃ x P(x)"));
+        assert!(lemma.is_closed());
+
+        // → ∃ x ∧ P(x) Q(x) ∃ x P(x)
+        let left: Derivation = Derivation::and_elim_left(assume("∧ P(y) Q(y)")).unwrap();
+        let witness: Derivation =
+            Derivation::exists_intro(left, "x", Formula::new("P(x)"), "y").unwrap();
+        let elim: Derivation =
+            Derivation::exists_elim(assume("∃ x ∧ P(x) Q(x)"), "y", witness).unwrap();
+        assert_eq!(elim.open_assumptions(), [&Formula::new("∃ x ∧ P(x) Q(x)")]);
+        let lemma: Derivation = Derivation::implies_intro(Formula::new("∃ x ∧ P(x) Q(x)"), elim);
+        assert_eq!(
+            *lemma.conclusion(),
+            Formula::new("→ ∃ x ∧ P(x) Q(x) ∃ x P(x)")
+        );
+        assert!(lemma.is_closed());
+        assert_eq!(lemma.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_exists_elim_eigenvariable() {
+        // The eigenvariable may not escape into the conclusion.
+        let left: Derivation = Derivation::and_elim_left(assume("∧ P(y) Q(y)")).unwrap();
+        assert_eq!(
+            Derivation::exists_elim(assume("∃ x ∧ P(x) Q(x)"), "y", left),
+            Err(DerivationError::Eigenvariable {
+                rule: "∃E",
+                variable: "y".to_string(),
+                formula: Box::new(Formula::new("P(y)"))
+            })
+        );
+        // Nor occur in another open assumption.
+        let both: Derivation = Derivation::and_intro(assume("R(y)"), assume("P(y)"));
+        let right: Derivation = Derivation::and_elim_left(both).unwrap();
+        let exists: Derivation =
+            Derivation::exists_intro(right, "z", Formula::new("R(z)"), "y").unwrap();
+        assert!(matches!(
+            Derivation::exists_elim(assume("∃ x P(x)"), "y", exists),
+            Err(DerivationError::Eigenvariable { formula, .. }) if *formula == Formula::new("R(y)")
+        ));
+    }
+
+    #[test]
+    fn test_wrong_premises() {
+        assert_eq!(
+            Derivation::and_elim_left(assume("∨ A B")),
+            Err(DerivationError::WrongPremise {
+                rule: "∧E₁",
+                expected: "a conjunction".to_string(),
+                found: Box::new(Formula::new("∨ A B"))
+            })
+        );
+        assert_eq!(
+            Derivation::implies_elim(assume("→ A B"), assume("B"))
+                .unwrap_err()
+                .to_string(),
+            "→E expected the antecedent A as premise, found B"
+        );
+        assert!(Derivation::not_intro(Formula::new("A"), assume("B")).is_err());
+        assert!(Derivation::not_elim(assume("¬ A"), assume("B")).is_err());
+        assert!(Derivation::forall_elim(assume("∃ x P(x)"), "0").is_err());
+        assert!(Derivation::exists_intro(assume("P(1)"), "x", Formula::new("P(x)"), "0").is_err());
+        assert!(Derivation::new(Rule::AndIntro, vec![assume("A")]).is_err());
+    }
+
+    #[test]
+    fn test_capture() {
+        assert_eq!(
+            Derivation::forall_elim(assume("∀ x ∃ y < x y"), "y+1"),
+            Err(DerivationError::Capture {
+                rule: "∀E",
+                term: "y+1".to_string(),
+                formula: Box::new(Formula::new("∃ y < x y"))
+            })
+        );
+        assert!(Derivation::forall_elim(assume("∀ x ∃ y < x y"), "z+1").is_ok());
+    }
+
+    #[test]
+    fn test_check_edited_derivation() {
+        let mut lemma: Derivation = Derivation::implies_intro(Formula::new("A"), assume("A"));
+        assert_eq!(lemma.check(), Ok(()));
+        lemma.premises[0].conclusion = Formula::new("B");
+        assert_eq!(
+            lemma.check(),
+            Err(DerivationError::WrongConclusion {
+                recorded: Box::new(Formula::new("B")),
+                derived: Box::new(Formula::new("A"))
+            })
+        );
+        lemma.premises.clear();
+        assert_eq!(
+            lemma.check(),
+            Err(DerivationError::PremiseCount {
+                rule: "→I",
+                expected: 1,
+                found: 0
+            })
+        );
+    }
+}
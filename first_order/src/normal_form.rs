@@ -0,0 +1,279 @@
+//! # Normal forms
+//!
+//! This module rewrites formulae towards normal forms, used by [`Formula::push_negations_once`],
+//! [`Formula::de_morgan`] and [`Formula::eliminate_implications`].
+//!
+//! Negations are pushed inwards with De Morgan's laws and the quantifier dualities, and double
+//! negations are removed:
+//!
+//! | Formula     | Rewritten to    |
+//! |-------------|-----------------|
+//! | `¬ ∧ φ ψ`   | `∨ ¬ φ ¬ ψ`     |
+//! | `¬ ∨ φ ψ`   | `∧ ¬ φ ¬ ψ`     |
+//! | `¬ ∀ x φ`   | `∃ x ¬ φ`       |
+//! | `¬ ∃ x φ`   | `∀ x ¬ φ`       |
+//! | `¬ ¬ φ`     | `φ`             |
+//!
+//! A negation of any other formula, such as `¬ → φ ψ` or `¬ < x y`, is left in place.
+//!
+//! Implications and equivalences are eliminated in favour of `¬`, `∧` and `∨`:
+//!
+//! | Formula     | Rewritten to            |
+//! |-------------|-------------------------|
+//! | `→ φ ψ`     | `∨ ¬ φ ψ`               |
+//! | `= φ ψ`     | `∧ ∨ ¬ φ ψ ∨ ¬ ψ φ`     |
+//!
+//! An `=` whose operands are both terms, such as `= x 5`, is an equality rather than an
+//! equivalence, and is left in place.
+use crate::Formula;
+
+/// Pushes every negation of `formula` one level inwards. See [`Formula::push_negations_once`].
+pub(crate) fn push_negations_once(formula: &Formula) -> (Formula, bool) {
+    let mut changed: bool = false;
+    let pushed: Formula = push_once(formula, &mut changed);
+    (pushed, changed)
+}
+
+fn push_once(formula: &Formula, changed: &mut bool) -> Formula {
+    let Formula::Negation(inner) = formula else {
+        return map_operands(formula, |operand| push_once(operand, changed));
+    };
+    let mut negate = |formula: &Formula| negation(push_once(formula, changed));
+    let pushed: Formula = match &**inner {
+        Formula::Conjunction(lhs, rhs) => Formula::Disjunction(negate(lhs), negate(rhs)),
+        Formula::Disjunction(lhs, rhs) => Formula::Conjunction(negate(lhs), negate(rhs)),
+        Formula::UniversalQuantifier(variable, inner) => {
+            Formula::ExistentialQuantifier(variable.clone(), negate(inner))
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            Formula::UniversalQuantifier(variable.clone(), negate(inner))
+        }
+        Formula::Negation(innermost) => push_once(innermost, changed),
+        _ => return Formula::Negation(Box::new(push_once(inner, changed))),
+    };
+    *changed = true;
+    pushed
+}
+
+/// Pushes every negation of `formula` inwards as far as it goes. See [`Formula::de_morgan`].
+pub(crate) fn de_morgan(formula: &Formula) -> Formula {
+    match formula {
+        Formula::Negation(inner) => negated(inner),
+        _ => map_operands(formula, de_morgan),
+    }
+}
+
+/// Returns the negation of `formula` with every negation pushed inwards as far as it goes.
+fn negated(formula: &Formula) -> Formula {
+    let negate = |formula: &Formula| Box::new(negated(formula));
+    match formula {
+        Formula::Conjunction(lhs, rhs) => Formula::Disjunction(negate(lhs), negate(rhs)),
+        Formula::Disjunction(lhs, rhs) => Formula::Conjunction(negate(lhs), negate(rhs)),
+        Formula::UniversalQuantifier(variable, inner) => {
+            Formula::ExistentialQuantifier(variable.clone(), negate(inner))
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            Formula::UniversalQuantifier(variable.clone(), negate(inner))
+        }
+        Formula::Negation(inner) => de_morgan(inner),
+        _ => Formula::Negation(Box::new(de_morgan(formula))),
+    }
+}
+
+/// Rewrites every implication and equivalence of `formula`. See
+/// [`Formula::eliminate_implications`].
+pub(crate) fn eliminate_implications(formula: &Formula) -> Formula {
+    match formula {
+        Formula::Implication(lhs, rhs) => {
+            implication(eliminate_implications(lhs), eliminate_implications(rhs))
+        }
+        Formula::Iff(lhs, rhs) => biconditional(lhs, rhs),
+        Formula::Equivalence(lhs, rhs)
+            if !matches!((&**lhs, &**rhs), (Formula::Term(_), Formula::Term(_))) =>
+        {
+            biconditional(lhs, rhs)
+        }
+        _ => map_operands(formula, eliminate_implications),
+    }
+}
+
+/// Returns the conjunction of the implications both ways between `lhs` and `rhs`, each with its
+/// implications eliminated.
+fn biconditional(lhs: &Formula, rhs: &Formula) -> Formula {
+    let lhs: Formula = eliminate_implications(lhs);
+    let rhs: Formula = eliminate_implications(rhs);
+    Formula::Conjunction(
+        Box::new(implication(lhs.clone(), rhs.clone())),
+        Box::new(implication(rhs, lhs)),
+    )
+}
+
+/// Returns `∨ ¬ lhs rhs`, the implication `→ lhs rhs` without `→`.
+fn implication(lhs: Formula, rhs: Formula) -> Formula {
+    Formula::Disjunction(negation(lhs), Box::new(rhs))
+}
+
+fn negation(formula: Formula) -> Box<Formula> {
+    Box::new(Formula::Negation(Box::new(formula)))
+}
+
+/// Rebuilds `formula` with `map` applied to each of its immediate subformulae.
+pub(crate) fn map_operands(formula: &Formula, mut map: impl FnMut(&Formula) -> Formula) -> Formula {
+    let mut map = |formula: &Formula| Box::new(map(formula));
+    match formula {
+        Formula::Term(_)
+        | Formula::Top
+        | Formula::Bottom
+        | Formula::Equality(_, _)
+        | Formula::Less(_, _)
+        | Formula::Predicate(_, _) => formula.clone(),
+        Formula::Negation(inner) => Formula::Negation(map(inner)),
+        Formula::Conjunction(lhs, rhs) => Formula::Conjunction(map(lhs), map(rhs)),
+        Formula::Disjunction(lhs, rhs) => Formula::Disjunction(map(lhs), map(rhs)),
+        Formula::Implication(lhs, rhs) => Formula::Implication(map(lhs), map(rhs)),
+        Formula::Equivalence(lhs, rhs) => Formula::Equivalence(map(lhs), map(rhs)),
+        Formula::Iff(lhs, rhs) => Formula::Iff(map(lhs), map(rhs)),
+        Formula::NotEqual(lhs, rhs) => Formula::NotEqual(map(lhs), map(rhs)),
+        Formula::LessThan(lhs, rhs) => Formula::LessThan(map(lhs), map(rhs)),
+        Formula::LessEqual(lhs, rhs) => Formula::LessEqual(map(lhs), map(rhs)),
+        Formula::GreaterEqual(lhs, rhs) => Formula::GreaterEqual(map(lhs), map(rhs)),
+        Formula::GreaterThan(lhs, rhs) => Formula::GreaterThan(map(lhs), map(rhs)),
+        Formula::Custom(symbol, lhs, rhs) => Formula::Custom(symbol.clone(), map(lhs), map(rhs)),
+        Formula::UniversalQuantifier(variable, inner) => {
+            Formula::UniversalQuantifier(variable.clone(), map(inner))
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            Formula::ExistentialQuantifier(variable.clone(), map(inner))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    /// The postcondition `while_rule` produces for the loop of the quotient and remainder proof.
+    const WHILE_POSTCONDITION: &str = "∧ ¬ ∨ < y r = y r = x r+y*q";
+
+    fn once(input: &str) -> (Formula, bool) {
+        Formula::new(input).push_negations_once()
+    }
+
+    #[test]
+    fn test_push_negations_once_while_postcondition() {
+        assert_eq!(
+            once(WHILE_POSTCONDITION),
+            (Formula::new("∧ ∧ ¬ < y r ¬ = y r = x r+y*q"), true)
+        );
+        assert_eq!(
+            once(corpus::PAPER_EXAMPLE[9]),
+            (
+                Formula::new("→ ∧ = x r+y*q ∧ ¬ < y r ¬ = y r ∧ ∧ ¬ < y r ¬ = y r = x r+y*q"),
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn test_push_negations_once_each_law() {
+        assert_eq!(once("¬ ∧ P Q"), (Formula::new("∨ ¬ P ¬ Q"), true));
+        assert_eq!(once("¬ ∨ P Q"), (Formula::new("∧ ¬ P ¬ Q"), true));
+        assert_eq!(once("¬ ∀ x P(x)"), (Formula::new("∃ x ¬ P(x)"), true));
+        assert_eq!(once("¬ ∃ x P(x)"), (Formula::new("∀ x ¬ P(x)"), true));
+        assert_eq!(once("¬ ¬ P"), (Formula::new("P"), true));
+    }
+
+    #[test]
+    fn test_push_negations_once_is_one_level() {
+        assert_eq!(once("¬ ∧ ∨ P Q R"), (Formula::new("∨ ¬ ∨ P Q ¬ R"), true));
+        assert_eq!(
+            once("∧ ¬ ∀ x ¬ ∨ P(x) Q ¬ ∃ y R(y)"),
+            (Formula::new("∧ ∃ x ¬ ∧ ¬ P(x) ¬ Q ∀ y ¬ R(y)"), true)
+        );
+    }
+
+    #[test]
+    fn test_push_negations_once_unchanged() {
+        for input in ["¬ → P Q", "¬ = x 5", "∧ ¬ P Q", "∀ x ¬ < x 0", "⊤"] {
+            assert_eq!(once(input), (Formula::new(input), false), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_de_morgan() {
+        assert_eq!(
+            Formula::new(WHILE_POSTCONDITION).de_morgan(),
+            Formula::new("∧ ∧ ¬ < y r ¬ = y r = x r+y*q")
+        );
+        assert_eq!(
+            Formula::new("¬ ∧ ∨ P Q ¬ ∀ x ∃ y ¬ R(x,y)").de_morgan(),
+            Formula::new("∨ ∧ ¬ P ¬ Q ∀ x ∃ y ¬ R(x,y)")
+        );
+        assert_eq!(
+            Formula::new("¬ ∨ → P Q = P Q").de_morgan(),
+            Formula::new("∧ ¬ → P Q ¬ = P Q")
+        );
+        assert_eq!(
+            Formula::new("→ ¬ ¬ ¬ ∧ P Q ¬ ¬ R").de_morgan(),
+            Formula::new("→ ∨ ¬ P ¬ Q R")
+        );
+    }
+
+    #[test]
+    fn test_eliminate_implications() {
+        assert_eq!(
+            Formula::new("→ P Q").eliminate_implications(),
+            Formula::new("∨ ¬ P Q")
+        );
+        assert_eq!(
+            Formula::new("= ∧ P(x) R Q(x)").eliminate_implications(),
+            Formula::new("∧ ∨ ¬ ∧ P(x) R Q(x) ∨ ¬ Q(x) ∧ P(x) R")
+        );
+        assert_eq!(
+            Formula::new("∀ x → P(x) ∃ y = → Q(y) R ¬ S").eliminate_implications(),
+            Formula::new("∀ x ∨ ¬ P(x) ∃ y ∧ ∨ ¬ ∨ ¬ Q(y) R ¬ S ∨ ¬ ¬ S ∨ ¬ Q(y) R")
+        );
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[4]).eliminate_implications(),
+            Formula::new("∨ ¬ = x r+y*0 = x r+y*0")
+        );
+    }
+
+    #[test]
+    fn test_eliminate_implications_keeps_term_equalities() {
+        for input in ["= x 5", "= P(x) Q(x)", "∀ x ∧ = x r+y*q ¬ = y r", "≠ x y"] {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(
+                test_formula.eliminate_implications(),
+                test_formula,
+                "{input}"
+            );
+        }
+        assert_eq!(
+            Formula::new("= = x 5 P(x)").eliminate_implications(),
+            Formula::new("∧ ∨ ¬ = x 5 P(x) ∨ ¬ P(x) = x 5")
+        );
+    }
+
+    #[test]
+    fn test_eliminate_implications_leaves_no_implication() {
+        for input in corpus::all() {
+            let eliminated: Formula = Formula::new(input).eliminate_implications();
+            assert_eq!(eliminated.eliminate_implications(), eliminated, "{input}");
+            assert!(!eliminated.to_prefix_notation().contains('→'), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_de_morgan_is_a_fixed_point_of_push_negations_once() {
+        for input in corpus::all() {
+            let pushed: Formula = Formula::new(input).de_morgan();
+            assert_eq!(
+                pushed.push_negations_once(),
+                (pushed.clone(), false),
+                "{input}"
+            );
+        }
+    }
+}
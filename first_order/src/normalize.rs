@@ -0,0 +1,160 @@
+//! # Term normalization
+//!
+//! This module rewrites terms that are spelled differently but always have the same value into
+//! one spelling, for [`Term::normalize`] and [`Formula::normalize_terms`], so that the
+//! midconditions `= preFib fib(index)` and `= preFib fib(index+1-1)` of two assignments compare
+//! equal. Parentheses are dropped first, and then the following rewrites are applied bottom-up
+//! until none applies:
+//!
+//! | Rewrite               | Result                                              |
+//! |-----------------------|-----------------------------------------------------|
+//! | `x+0`, `0+x`, `x-0`   | `x`                                                 |
+//! | `x*0`, `0*x`          | `0`                                                 |
+//! | `x*1`, `1*x`          | `x`                                                 |
+//! | `x-x`                 | `0`                                                 |
+//! | `a+b-b`, `a-b+b`      | `a`                                                 |
+//! | `2+3`, `-(4)`, ...    | the value, when every operand is an integer literal |
+//!
+//! Every rewrite leaves the term smaller or as it is, so a fixed point is always reached. The rewrites treat
+//! every function as total: `x*0` becomes `0` even if `x` divides by zero. A literal is only
+//! folded if its value is defined and fits in a [`DomainElem`], and a negative value is written
+//! as the negation of a numeral, which is how it is parsed.
+use std::collections::HashMap;
+
+use crate::{DomainElem, Term, arith, term};
+
+/// Returns `term` with the rewrites applied until none applies. See [`Term::normalize`].
+pub(crate) fn normalize(term: &Term) -> Term {
+    let mut current: Term = term.without_parentheses();
+    loop {
+        let next: Term = rewrite(&current);
+        if next == current {
+            return current;
+        }
+        current = next;
+    }
+}
+
+/// Applies the rewrites once, to the arguments of `term` before `term` itself.
+fn rewrite(term: &Term) -> Term {
+    match term {
+        Term::Var(_) | Term::IntLit(_) | Term::Const(_) => term.clone(),
+        Term::App(name, args) => step(name, args.iter().map(rewrite).collect()),
+    }
+}
+
+/// Rewrites the application of `name` to `args`, whose arguments are already rewritten.
+fn step(name: &str, args: Vec<Term>) -> Term {
+    let zero = || Term::IntLit(0);
+    match (name, args.as_slice()) {
+        ("+", [x, y]) if is_literal(y, 0) => x.clone(),
+        ("+", [x, y]) if is_literal(x, 0) => y.clone(),
+        ("-", [x, y]) if is_literal(y, 0) => x.clone(),
+        ("*", [x, y]) if is_literal(x, 0) || is_literal(y, 0) => zero(),
+        ("*", [x, y]) if is_literal(y, 1) => x.clone(),
+        ("*", [x, y]) if is_literal(x, 1) => y.clone(),
+        ("-", [x, y]) if x == y => zero(),
+        ("-", [Term::App(inner, operands), y]) if inner == "+" && ends_with(operands, y) => {
+            operands[0].clone()
+        }
+        ("+", [Term::App(inner, operands), y]) if inner == "-" && ends_with(operands, y) => {
+            operands[0].clone()
+        }
+        _ => fold(name, &args).unwrap_or_else(|| Term::App(name.to_string(), args)),
+    }
+}
+
+/// Returns whether `operands` are two terms, the second of which is `last`.
+fn ends_with(operands: &[Term], last: &Term) -> bool {
+    matches!(operands, [_, second] if second == last)
+}
+
+/// Returns the value of `term` if it is an integer literal, such as `3` or `-3`.
+fn literal(term: &Term) -> Option<DomainElem> {
+    match term {
+        Term::IntLit(value) => Some(*value),
+        Term::Const(name) => name.parse().ok(),
+        Term::App(name, args) if name == "-" => match args.as_slice() {
+            [literal @ (Term::IntLit(_) | Term::Const(_))] => self::literal(literal)?.checked_neg(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns whether `term` is an integer literal of the value `value`.
+fn is_literal(term: &Term, value: DomainElem) -> bool {
+    literal(term) == Some(value)
+}
+
+/// Returns the literal for the application of the operator `name` to `args`, if every argument
+/// is a literal and the value is defined.
+fn fold(name: &str, args: &[Term]) -> Option<Term> {
+    let negation: bool = name == "-" && args.len() == 1;
+    if !(term::is_operator(name) || negation) || args.iter().any(|arg| literal(arg).is_none()) {
+        return None;
+    }
+    let application: Term = Term::App(name.to_string(), args.to_vec());
+    let value: DomainElem =
+        arith::eval_term(&application, &HashMap::new(), &HashMap::new()).ok()?;
+    let numeral: Term = Term::constant(value.unsigned_abs().to_string());
+    Some(if value < 0 {
+        Term::App("-".to_string(), vec![numeral])
+    } else {
+        numeral
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Formula, corpus};
+
+    fn normalized(input: &str) -> String {
+        Term::new(input).normalize().to_string()
+    }
+
+    #[test]
+    fn test_rewrites() {
+        let cases: [(&str, &str); 12] = [
+            ("index+1-1", "index"),
+            ("x+y*0", "x"),
+            ("0+x*1", "x"),
+            ("1*(x-0)", "x"),
+            ("fib(index+1-1)", "fib(index)"),
+            ("preFib+curFib-preFib", "preFib+curFib-preFib"),
+            ("curFib-preFib+preFib", "curFib"),
+            ("x-x+y", "y"),
+            ("(2+3)*x", "5*x"),
+            ("1-4", "-3"),
+            ("-(2*2)+x", "-4+x"),
+            ("2^64+x", "2^64+x"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalized(input), expected, "{input}");
+        }
+        // Division by zero is left alone.
+        assert_eq!(normalized("1/0"), "1/0");
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input).normalize_terms();
+            assert_eq!(test_formula.normalize_terms(), test_formula, "{input}");
+        }
+        assert_eq!(
+            Formula::new(corpus::FIBONACCI[3]).normalize_terms(),
+            Formula::new(corpus::FIBONACCI[2])
+        );
+    }
+
+    #[test]
+    fn test_paper_example_lemma() {
+        // The trivial lemma the paper example leaves behind.
+        assert_eq!(
+            Formula::new("→ = x r+y*0 = x r").normalize_terms(),
+            Formula::new("→ = x r = x r")
+        );
+    }
+}
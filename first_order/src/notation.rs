@@ -0,0 +1,496 @@
+//! # Prefix, postfix and infix notation
+//!
+//! This module writes a formula in the prefix notation of [`Formula::to_prefix_notation`], the
+//! postfix notation of [`Formula::to_postfix_notation`] and the fully parenthesized infix notation
+//! of [`Formula::to_infix_notation`], which the `Display` of a `Formula` also uses.
+//!
+//! All notations are written in a single pass over an explicit stack of pieces still to be
+//! written, so formulae of any depth can be written, and the output goes straight into any
+//! `fmt::Write` target instead of being assembled from a string per subformula. To collect the
+//! output into a `String`, the formula is measured with a [`LengthCounter`] first, so the `String`
+//! is allocated once at its final size.
+use std::fmt;
+
+use crate::{Formula, PrintOptions, TermPrintOptions, print};
+
+/// A piece of output still to be written.
+enum Piece<'a> {
+    /// A subformula, which is expanded into its own pieces when it is reached.
+    Formula(&'a Formula),
+    /// Literal text, such as a connective or a variable.
+    Text(&'a str),
+}
+
+/// A `fmt::Write` target that only counts the bytes written to it.
+#[derive(Debug, Default)]
+pub(crate) struct LengthCounter {
+    length: usize, // The number of bytes written so far.
+}
+
+impl fmt::Write for LengthCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.length += s.len();
+        Ok(())
+    }
+}
+
+/// Collects the output of `write` for `formula` into a `String` allocated at its final size.
+fn collect<W, S>(formula: &Formula, measure: W, write: S) -> String
+where
+    W: Fn(&Formula, &mut LengthCounter) -> fmt::Result,
+    S: Fn(&Formula, &mut String) -> fmt::Result,
+{
+    let mut counter: LengthCounter = LengthCounter::default();
+    measure(formula, &mut counter).expect("counting bytes cannot fail");
+    let mut output: String = String::with_capacity(counter.length);
+    write(formula, &mut output).expect("writing to a String cannot fail");
+    output
+}
+
+/// Returns `formula` in prefix notation. See [`Formula::to_prefix_notation`].
+pub(crate) fn to_prefix_notation(formula: &Formula) -> String {
+    collect(formula, write_prefix, write_prefix)
+}
+
+/// Returns `formula` in prefix notation, with runs of quantifiers of the same kind written as one
+/// quantifier over several variables. See [`Formula::to_prefix_notation_grouped`].
+pub(crate) fn to_prefix_notation_grouped(formula: &Formula) -> String {
+    collect(
+        formula,
+        |formula, out| write_prefix_with(formula, out, true),
+        |formula, out| write_prefix_with(formula, out, true),
+    )
+}
+
+/// Returns `formula` in postfix notation. See [`Formula::to_postfix_notation`].
+pub(crate) fn to_postfix_notation(formula: &Formula) -> String {
+    collect(formula, write_postfix, write_postfix)
+}
+
+/// Returns `formula` in fully parenthesized infix notation. See [`Formula::to_infix_notation`].
+pub(crate) fn to_infix_notation(formula: &Formula) -> String {
+    collect(formula, write_infix, write_infix)
+}
+
+/// Writes `formula` in prefix notation. See [`Formula::write_prefix`].
+pub(crate) fn write_prefix(formula: &Formula, out: &mut impl fmt::Write) -> fmt::Result {
+    write_prefix_with(formula, out, false)
+}
+
+/// Writes `formula` in prefix notation, writing the variables of a run of quantifiers of the same
+/// kind after a single quantifier if `group` is set.
+fn write_prefix_with(formula: &Formula, out: &mut impl fmt::Write, group: bool) -> fmt::Result {
+    let mut stack: Vec<Piece<'_>> = vec![Piece::Formula(formula)];
+    while let Some(piece) = stack.pop() {
+        let formula: &Formula = match piece {
+            Piece::Text(text) => {
+                out.write_str(text)?;
+                continue;
+            }
+            Piece::Formula(formula) => formula,
+        };
+        // The pieces are pushed in reverse, so that the first one is written first.
+        match formula {
+            Formula::Term(term) => write!(out, "{term}")?,
+            Formula::Top => out.write_str("⊤")?,
+            Formula::Bottom => out.write_str("⊥")?,
+            Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+                write!(out, "{} {lhs} {rhs}", connective(formula))?
+            }
+            Formula::Predicate(name, args) => {
+                print::write_predicate(name, args, &TermPrintOptions::default(), out)?
+            }
+            Formula::Negation(inner) => {
+                stack.push(Piece::Formula(inner));
+                out.write_str("¬ ")?;
+            }
+            Formula::UniversalQuantifier(variable, inner)
+            | Formula::ExistentialQuantifier(variable, inner) => {
+                out.write_str(quantifier(formula))?;
+                out.write_str(" ")?;
+                out.write_str(variable)?;
+                let mut body: &Formula = inner;
+                while let (true, Some((variable, inner))) = (group, same_quantifier(formula, body))
+                {
+                    out.write_str(" ")?;
+                    out.write_str(variable)?;
+                    body = inner;
+                }
+                stack.extend([Piece::Formula(body), Piece::Text(" ")]);
+            }
+            Formula::Custom(symbol, lhs, rhs) => {
+                stack.extend([Piece::Formula(rhs), Piece::Text(" "), Piece::Formula(lhs)]);
+                out.write_str(symbol)?;
+                out.write_str(" ")?;
+            }
+            _ => {
+                let (lhs, rhs): (&Formula, &Formula) = operands(formula);
+                stack.extend([Piece::Formula(rhs), Piece::Text(" "), Piece::Formula(lhs)]);
+                out.write_str(connective(formula))?;
+                out.write_str(" ")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the variable and body of `body` if it is a quantifier of the same kind as `quantifier`.
+fn same_quantifier<'a>(quantifier: &Formula, body: &'a Formula) -> Option<(&'a str, &'a Formula)> {
+    match (quantifier, body) {
+        (Formula::UniversalQuantifier(..), Formula::UniversalQuantifier(variable, inner))
+        | (Formula::ExistentialQuantifier(..), Formula::ExistentialQuantifier(variable, inner)) => {
+            Some((variable, inner))
+        }
+        _ => None,
+    }
+}
+
+/// Writes `formula` in postfix notation, with the operands of every node before its symbol and
+/// the variable of a quantifier before its body.
+fn write_postfix(formula: &Formula, out: &mut impl fmt::Write) -> fmt::Result {
+    let mut stack: Vec<Piece<'_>> = vec![Piece::Formula(formula)];
+    while let Some(piece) = stack.pop() {
+        let formula: &Formula = match piece {
+            Piece::Text(text) => {
+                out.write_str(text)?;
+                continue;
+            }
+            Piece::Formula(formula) => formula,
+        };
+        // The pieces are pushed in reverse, so that the first one is written first.
+        match formula {
+            Formula::Term(term) => write!(out, "{term}")?,
+            Formula::Top => out.write_str("⊤")?,
+            Formula::Bottom => out.write_str("⊥")?,
+            Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+                write!(out, "{lhs} {rhs} {}", connective(formula))?
+            }
+            Formula::Predicate(name, args) => {
+                print::write_predicate(name, args, &TermPrintOptions::default(), out)?
+            }
+            Formula::Negation(inner) => {
+                stack.extend([Piece::Text("¬"), Piece::Text(" "), Piece::Formula(inner)]);
+            }
+            Formula::UniversalQuantifier(variable, inner)
+            | Formula::ExistentialQuantifier(variable, inner) => {
+                stack.extend([
+                    Piece::Text(quantifier(formula)),
+                    Piece::Text(" "),
+                    Piece::Formula(inner),
+                    Piece::Text(" "),
+                    Piece::Text(variable),
+                ]);
+            }
+            Formula::Custom(symbol, lhs, rhs) => {
+                stack.extend([
+                    Piece::Text(symbol),
+                    Piece::Text(" "),
+                    Piece::Formula(rhs),
+                    Piece::Text(" "),
+                    Piece::Formula(lhs),
+                ]);
+            }
+            _ => {
+                let (lhs, rhs): (&Formula, &Formula) = operands(formula);
+                stack.extend([
+                    Piece::Text(connective(formula)),
+                    Piece::Text(" "),
+                    Piece::Formula(rhs),
+                    Piece::Text(" "),
+                    Piece::Formula(lhs),
+                ]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes `formula` in fully parenthesized infix notation. See [`Formula::write_infix`].
+pub(crate) fn write_infix(formula: &Formula, out: &mut impl fmt::Write) -> fmt::Result {
+    write_infix_with(formula, out, &TermPrintOptions::default())
+}
+
+/// Returns `formula` in fully parenthesized infix notation, with its terms written as `options`
+/// say. See [`Formula::to_string_with`].
+pub(crate) fn to_string_with(formula: &Formula, options: &PrintOptions) -> String {
+    collect(
+        formula,
+        |formula, out| write_infix_with(formula, out, options.term_options()),
+        |formula, out| write_infix_with(formula, out, options.term_options()),
+    )
+}
+
+/// Writes `formula` in fully parenthesized infix notation, writing its terms as `options` say.
+fn write_infix_with(
+    formula: &Formula,
+    out: &mut impl fmt::Write,
+    options: &TermPrintOptions,
+) -> fmt::Result {
+    let mut stack: Vec<Piece<'_>> = vec![Piece::Formula(formula)];
+    while let Some(piece) = stack.pop() {
+        let formula: &Formula = match piece {
+            Piece::Text(text) => {
+                out.write_str(text)?;
+                continue;
+            }
+            Piece::Formula(formula) => formula,
+        };
+        // The pieces are pushed in reverse, so that the first one is written first.
+        match formula {
+            Formula::Term(term) => print::write_term(term, options, out)?,
+            Formula::Top => out.write_str("⊤")?,
+            Formula::Bottom => out.write_str("⊥")?,
+            Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+                out.write_str("(")?;
+                print::write_term(lhs, options, out)?;
+                out.write_str(connective(formula))?;
+                print::write_term(rhs, options, out)?;
+                out.write_str(")")?;
+            }
+            Formula::Predicate(name, args) => print::write_predicate(name, args, options, out)?,
+            Formula::Negation(inner) => {
+                stack.extend([Piece::Text(")"), Piece::Formula(inner)]);
+                out.write_str("(¬")?;
+            }
+            Formula::UniversalQuantifier(variable, inner)
+            | Formula::ExistentialQuantifier(variable, inner) => {
+                stack.extend([Piece::Text(")"), Piece::Formula(inner), Piece::Text("(")]);
+                out.write_str(quantifier(formula))?;
+                out.write_str(variable)?;
+            }
+            Formula::Custom(symbol, lhs, rhs) => {
+                stack.extend([
+                    Piece::Text(")"),
+                    Piece::Formula(rhs),
+                    Piece::Text(symbol),
+                    Piece::Formula(lhs),
+                ]);
+                out.write_str("(")?;
+            }
+            _ => {
+                let (lhs, rhs): (&Formula, &Formula) = operands(formula);
+                stack.extend([
+                    Piece::Text(")"),
+                    Piece::Formula(rhs),
+                    Piece::Text(connective(formula)),
+                    Piece::Formula(lhs),
+                ]);
+                out.write_str("(")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the symbol of a quantifier.
+fn quantifier(formula: &Formula) -> &'static str {
+    match formula {
+        Formula::UniversalQuantifier(_, _) => "∀",
+        _ => "∃",
+    }
+}
+
+/// Returns the symbol of a built-in binary connective, or of an `Equality` or a `Less` between
+/// two terms.
+pub(crate) fn connective(formula: &Formula) -> &'static str {
+    match formula {
+        Formula::Conjunction(_, _) => "∧",
+        Formula::Disjunction(_, _) => "∨",
+        Formula::Implication(_, _) => "→",
+        Formula::Equivalence(_, _) | Formula::Equality(_, _) => "=",
+        Formula::Iff(_, _) => "↔",
+        Formula::NotEqual(_, _) => "≠",
+        Formula::LessThan(_, _) | Formula::Less(_, _) => "<",
+        Formula::LessEqual(_, _) => "≤",
+        Formula::GreaterEqual(_, _) => "≥",
+        Formula::GreaterThan(_, _) => ">",
+        _ => unreachable!("{} is not a built-in binary connective", formula.kind()),
+    }
+}
+
+/// Returns the operands of a built-in binary connective.
+fn operands(formula: &Formula) -> (&Formula, &Formula) {
+    match formula {
+        Formula::Conjunction(lhs, rhs)
+        | Formula::Disjunction(lhs, rhs)
+        | Formula::Implication(lhs, rhs)
+        | Formula::Equivalence(lhs, rhs)
+        | Formula::Iff(lhs, rhs)
+        | Formula::NotEqual(lhs, rhs)
+        | Formula::LessThan(lhs, rhs)
+        | Formula::LessEqual(lhs, rhs)
+        | Formula::GreaterEqual(lhs, rhs)
+        | Formula::GreaterThan(lhs, rhs) => (lhs, rhs),
+        _ => unreachable!("{} is not a built-in binary connective", formula.kind()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::fmt::Write;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::{ParserConfig, corpus};
+
+    /// The system allocator, counting the allocations each thread makes so that tests running in
+    /// parallel do not disturb each other's counts.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Returns the number of allocations and reallocations `f` makes on this thread.
+    fn allocations(f: impl FnOnce()) -> usize {
+        let before: usize = ALLOCATIONS.with(Cell::get);
+        f();
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    /// Builds `∧ ∧ … ∧ P P … P P` with `count` conjunctions, leaning to the left.
+    fn left_leaning(count: usize) -> Formula {
+        let mut formula: Formula = Formula::term("P");
+        for _ in 0..count {
+            formula = formula.and(Formula::term("P"));
+        }
+        formula
+    }
+
+    #[test]
+    fn test_corpus_output() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(test_formula.to_prefix_notation(), corpus::DOC_EXAMPLE);
+        assert_eq!(
+            test_formula.to_infix_notation(),
+            "(∀x((P(x)→(Q(x)∧∃y((R(y)∨S(y))))))∧((¬T(x))=(U<V)))"
+        );
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(
+                Formula::new(test_formula.to_prefix_notation()),
+                test_formula
+            );
+            assert_eq!(test_formula.to_string(), test_formula.to_infix_notation());
+        }
+    }
+
+    #[test]
+    fn test_every_connective() {
+        let test_formula: Formula = Formula::new("∨ ≠ a b ∧ ≤ a b → ≥ a b = > a b ∃ y ∀ x ∧ ¬ ⊤ ⊥");
+        assert_eq!(
+            test_formula.to_prefix_notation(),
+            "∨ ≠ a b ∧ ≤ a b → ≥ a b = > a b ∃ y ∀ x ∧ ¬ ⊤ ⊥"
+        );
+        assert_eq!(
+            test_formula.to_infix_notation(),
+            "((a≠b)∨((a≤b)∧((a≥b)→((a>b)=∃y(∀x(((¬⊤)∧⊥)))))))"
+        );
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        let custom: Formula = config.parse("¬ ⊆ A ∧ B C").unwrap();
+        assert_eq!(custom.to_prefix_notation(), "¬ ⊆ A ∧ B C");
+        assert_eq!(custom.to_infix_notation(), "(¬(A⊆(B∧C)))");
+    }
+
+    #[test]
+    fn test_deep_conjunction() {
+        let start: Instant = Instant::now();
+        let test_formula: Formula = left_leaning(100_000);
+        assert_eq!(test_formula.node_count(), 200_001);
+        let prefix: String = test_formula.to_prefix_notation();
+        assert_eq!(
+            prefix.len(),
+            100_000 * "∧ ".len() + 100_001 * "P ".len() - 1
+        );
+        let infix: String = format!("{test_formula}");
+        assert_eq!(infix, test_formula.to_infix_notation());
+        assert!(infix.trim_start_matches('(').starts_with("P∧P)∧P)∧P)"));
+        assert_eq!(infix.len(), 100_000 * "(∧)".len() + 100_001);
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_write_into_string() {
+        let mut output: String = String::new();
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            output.clear();
+            test_formula.write_prefix(&mut output).unwrap();
+            assert_eq!(output, test_formula.to_prefix_notation(), "{input}");
+            output.clear();
+            test_formula.write_infix(&mut output).unwrap();
+            assert_eq!(output, test_formula.to_infix_notation(), "{input}");
+        }
+        let mut output: String = String::new();
+        write!(output, "{{ {} }}", Formula::new("= x 5")).unwrap();
+        assert_eq!(output, "{ (x=5) }");
+    }
+
+    #[test]
+    fn test_write_into_length_counter() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            let mut prefix: LengthCounter = LengthCounter::default();
+            test_formula.write_prefix(&mut prefix).unwrap();
+            assert_eq!(
+                prefix.length,
+                test_formula.to_prefix_notation().len(),
+                "{input}"
+            );
+            let mut infix: LengthCounter = LengthCounter::default();
+            test_formula.write_infix(&mut infix).unwrap();
+            assert_eq!(
+                infix.length,
+                test_formula.to_infix_notation().len(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_collecting_allocates_the_string_once() {
+        for input in corpus::FIBONACCI {
+            let test_formula: Formula = Formula::new(input);
+            // The traversal allocates its stack without touching the output, so the same traversal
+            // into a length counter makes exactly the allocations that are not the output.
+            let traversal: usize = allocations(|| {
+                test_formula
+                    .write_prefix(&mut LengthCounter::default())
+                    .unwrap();
+            });
+            let mut output: String = String::new();
+            let collected: usize = allocations(|| output = test_formula.to_prefix_notation());
+            assert_eq!(collected, 2 * traversal + 1, "{input}");
+            assert_eq!(output.capacity(), output.len(), "{input}");
+
+            let traversal: usize = allocations(|| {
+                test_formula
+                    .write_infix(&mut LengthCounter::default())
+                    .unwrap();
+            });
+            let collected: usize = allocations(|| output = test_formula.to_infix_notation());
+            assert_eq!(collected, 2 * traversal + 1, "{input}");
+            assert_eq!(output.capacity(), output.len(), "{input}");
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! # Operators
+//!
+//! This module implements the operators `&`, `|` and `!` for [`Formula`], building a
+//! `Conjunction`, a `Disjunction` and a `Negation` respectively. They are implemented for owned
+//! formulae and for references, which are cloned, in any combination.
+//!
+//! Rust gives `!` the highest precedence, then `&`, then `|`, which matches the usual reading of
+//! `¬`, `∧` and `∨`, so `p & !q | r` is `∨ ∧ p ¬ q r`. Both binary operators associate to the
+//! left, so `p & q & r` is `∧ ∧ p q r` rather than the `∧ p ∧ q r` that is often written in
+//! prefix notation; the two are different formulae.
+//!
+//! There is no operator for implication, since the only candidate, `>>`, binds more tightly than
+//! `&` and `|` in Rust and would make `p & q >> r` read as `p & (q >> r)`. Use
+//! [`Formula::implies`] instead. A method call binds more tightly than `!`, so `!p.implies(q)`
+//! negates the whole implication; write `(!p).implies(q)` to negate `p` alone.
+//!
+//! # Example
+//! ```
+//! use first_order::Formula;
+//!
+//! let p: Formula = Formula::term("p");
+//! let q: Formula = Formula::term("q");
+//! let r: Formula = Formula::term("r");
+//! assert_eq!(&p & !&q | r, Formula::new("∨ ∧ p ¬ q r"));
+//! assert_eq!((!&p).implies(q), Formula::new("→ ¬ p q"));
+//! ```
+use std::ops::{BitAnd, BitOr, Not};
+
+use crate::Formula;
+
+/// Implements a binary operator for every combination of owned and borrowed operands, cloning the
+/// borrowed ones, by calling the builder method of the same meaning.
+macro_rules! binary_operator {
+    ($trait:ident, $method:ident, $builder:ident, $doc:literal) => {
+        impl $trait for Formula {
+            type Output = Formula;
+
+            #[doc = $doc]
+            fn $method(self, rhs: Formula) -> Formula {
+                self.$builder(rhs)
+            }
+        }
+
+        impl $trait<&Formula> for Formula {
+            type Output = Formula;
+
+            #[doc = $doc]
+            fn $method(self, rhs: &Formula) -> Formula {
+                self.$builder(rhs.clone())
+            }
+        }
+
+        impl $trait<Formula> for &Formula {
+            type Output = Formula;
+
+            #[doc = $doc]
+            fn $method(self, rhs: Formula) -> Formula {
+                self.clone().$builder(rhs)
+            }
+        }
+
+        impl $trait for &Formula {
+            type Output = Formula;
+
+            #[doc = $doc]
+            fn $method(self, rhs: &Formula) -> Formula {
+                self.clone().$builder(rhs.clone())
+            }
+        }
+    };
+}
+
+binary_operator!(BitAnd, bitand, and, "Builds the conjunction `∧ self rhs`.");
+binary_operator!(BitOr, bitor, or, "Builds the disjunction `∨ self rhs`.");
+
+impl Not for Formula {
+    type Output = Formula;
+
+    /// Builds the negation `¬ self`.
+    fn not(self) -> Formula {
+        Formula::Negation(Box::new(self))
+    }
+}
+
+impl Not for &Formula {
+    type Output = Formula;
+
+    /// Builds the negation `¬ self` from a clone of the operand.
+    fn not(self) -> Formula {
+        Formula::Negation(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_operators_build_the_paper_example() {
+        let invariant: Formula = Formula::term("x").equals(Formula::term("r+y*q"));
+        let condition: Formula = Formula::term("y").lt(Formula::term("r"))
+            | Formula::term("y").equals(Formula::term("r"));
+        let test_formula: Formula = (&invariant & !&condition).implies(!condition & invariant);
+        assert_eq!(test_formula, Formula::new(corpus::PAPER_EXAMPLE[9]));
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let p: Formula = Formula::term("p");
+        let q: Formula = Formula::term("q");
+        let r: Formula = Formula::term("r");
+        assert_eq!(&p | &q & !&r, Formula::new("∨ p ∧ q ¬ r"));
+        assert_eq!(!&p & &q | &r, Formula::new("∨ ∧ ¬ p q r"));
+        assert_eq!(!(&p | &q), Formula::new("¬ ∨ p q"));
+        assert_eq!(&p & &q & &r, Formula::new("∧ ∧ p q r"));
+        assert_eq!(p & (q & r), Formula::new("∧ p ∧ q r"));
+    }
+
+    #[test]
+    fn test_reference_operators_clone() {
+        let p: Formula = Formula::new("∀ x P(x)");
+        let q: Formula = Formula::new("Q");
+        assert_eq!(&p & &q, p.clone() & q.clone());
+        assert_eq!(&p & q.clone(), p.clone() & &q);
+        assert_eq!(&p | &q, p.clone() | q.clone());
+        assert_eq!(&p | q.clone(), p.clone() | &q);
+        assert_eq!(!&p, !p.clone());
+        assert_eq!(!!&q, Formula::new("¬ ¬ Q"));
+    }
+}
@@ -0,0 +1,302 @@
+//! # Formula paths
+//!
+//! This module provides [`FormulaPath`], which addresses a subformula by the indices of the
+//! children leading to it from the root, in the order of [`Formula::children`]. The operand of a
+//! negation and the body of a quantifier are child 0, and the operands of a binary connective are
+//! children 0 and 1, so `[1, 0]` in `∧ P ∀ x Q(x)` addresses `Q(x)`.
+//!
+//! Paths are resolved by [`Formula::get_at`] and [`Formula::replace_at`], and returned by
+//! [`Formula::find_occurrences`].
+use std::fmt;
+
+use crate::normal_form::map_operands;
+use crate::{Formula, atom};
+
+/// The path from the root of a formula to one of its subformulae, as the index of the child taken
+/// at each step. The empty path addresses the root.
+///
+/// # Example
+/// ```
+/// use first_order::FormulaPath;
+///
+/// let path: FormulaPath = FormulaPath::root().child(1).child(0);
+/// assert_eq!(path, FormulaPath(vec![1, 0]));
+/// assert!(FormulaPath::root().is_root());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FormulaPath(pub Vec<usize>);
+
+impl FormulaPath {
+    /// Returns the empty path, which addresses the root.
+    pub fn root() -> Self {
+        FormulaPath(Vec::new())
+    }
+    /// Returns the path to the child `index` of the subformula this path addresses.
+    pub fn child(&self, index: usize) -> Self {
+        let mut indices: Vec<usize> = self.0.clone();
+        indices.push(index);
+        FormulaPath(indices)
+    }
+    /// Returns whether the path addresses the root.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// An error returned when a [`FormulaPath`] does not address a subformula.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The path takes the child `index` at position `step`, but the subformula reached there only
+    /// has `children` children.
+    NoSuchChild {
+        path: FormulaPath,
+        step: usize,
+        index: usize,
+        children: usize,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::NoSuchChild {
+                path,
+                step,
+                index,
+                children,
+            } => write!(
+                f,
+                "the path {:?} takes child {index} at step {step}, but the subformula there has {children} children",
+                path.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Returns the subformulae on the way from `formula` to the one `path` addresses, starting with
+/// `formula` and ending with the addressed one.
+fn resolve<'a>(formula: &'a Formula, path: &FormulaPath) -> Result<Vec<&'a Formula>, PathError> {
+    let mut chain: Vec<&Formula> = vec![formula];
+    for (step, index) in path.0.iter().copied().enumerate() {
+        let children: Vec<&Formula> = chain[step].children();
+        let Some(child) = children.get(index) else {
+            return Err(PathError::NoSuchChild {
+                path: path.clone(),
+                step,
+                index,
+                children: children.len(),
+            });
+        };
+        chain.push(child);
+    }
+    Ok(chain)
+}
+
+/// Returns the subformula of `formula` at `path`. See [`Formula::get_at`].
+pub(crate) fn get_at<'a>(formula: &'a Formula, path: &FormulaPath) -> Option<&'a Formula> {
+    resolve(formula, path).ok()?.pop()
+}
+
+/// Rebuilds `formula` with the subformula at `path` replaced by `new`. See
+/// [`Formula::replace_at`].
+pub(crate) fn replace_at(
+    formula: &Formula,
+    path: &FormulaPath,
+    new: Formula,
+) -> Result<Formula, PathError> {
+    let chain: Vec<&Formula> = resolve(formula, path)?;
+    let mut replacement: Formula = new;
+    // Rebuild the ancestors from the innermost outwards, cloning the siblings of the path.
+    for (parent, index) in chain.into_iter().zip(path.0.iter().copied()).rev() {
+        let mut slot: Option<Formula> = Some(replacement);
+        let mut position: usize = 0;
+        replacement = map_operands(parent, |child| {
+            let mapped: Formula = match slot.take_if(|_| position == index) {
+                Some(replacement) => replacement,
+                None => child.clone(),
+            };
+            position += 1;
+            mapped
+        });
+    }
+    Ok(replacement)
+}
+
+/// Returns every subformula of `formula` with its path, in prefix order. The walk does not
+/// recurse.
+pub(crate) fn with_paths(formula: &Formula) -> Vec<(FormulaPath, &Formula)> {
+    let mut found: Vec<(FormulaPath, &Formula)> = Vec::new();
+    let mut stack: Vec<(FormulaPath, &Formula)> = vec![(FormulaPath::root(), formula)];
+    while let Some((path, current)) = stack.pop() {
+        for (index, child) in current.children().into_iter().enumerate().rev() {
+            stack.push((path.child(index), child));
+        }
+        found.push((path, current));
+    }
+    found
+}
+
+/// Counts the occurrences of the identifier `name` in the terms and binders of `formula`. See
+/// [`Formula::occurrences`].
+pub(crate) fn occurrences(formula: &Formula, name: &str) -> usize {
+    formula
+        .subformulae()
+        .map(|subformula| occurrences_at(subformula, name))
+        .sum()
+}
+
+/// Returns the paths to the terms and quantifiers of `formula` in which the identifier `name`
+/// occurs. See [`Formula::find_occurrences`].
+pub(crate) fn find_occurrences(formula: &Formula, name: &str) -> Vec<FormulaPath> {
+    with_paths(formula)
+        .into_iter()
+        .filter(|(_, subformula)| occurrences_at(subformula, name) > 0)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Counts the occurrences of `name` in the outermost node of `formula`, leaving out its
+/// subformulae.
+fn occurrences_at(formula: &Formula, name: &str) -> usize {
+    match formula {
+        Formula::Term(term) => term.occurrences(name),
+        Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+            lhs.occurrences(name) + rhs.occurrences(name)
+        }
+        Formula::Predicate(predicate, args) => {
+            atom::predicate_term(predicate, args).occurrences(name)
+        }
+        _ => usize::from(formula.binder() == Some(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_with_paths_follows_children() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        let paths: Vec<(FormulaPath, &Formula)> = with_paths(&test_formula);
+        assert_eq!(paths.len(), test_formula.node_count());
+        for ((path, subformula), expected) in paths.iter().zip(test_formula.subformulae()) {
+            assert!(std::ptr::eq(*subformula, expected), "{path:?}");
+        }
+        assert_eq!(paths[1].0, FormulaPath(vec![0]));
+        assert_eq!(paths[2].0, FormulaPath(vec![0, 0]));
+        assert_eq!(paths[2].1, &Formula::new("→ P(x) ∧ Q(x) ∃ y ∨ R(y) S(y)"));
+    }
+
+    #[test]
+    fn test_binder_occurrences() {
+        let test_formula: Formula = Formula::new("∀ x ∃ y → P(x) ∀ x Q(y)");
+        assert_eq!(test_formula.occurrences("x"), 3);
+        assert_eq!(
+            test_formula.find_occurrences("x"),
+            [
+                FormulaPath(vec![]),
+                FormulaPath(vec![0, 0, 0]),
+                FormulaPath(vec![0, 0, 1])
+            ]
+        );
+        assert_eq!(test_formula.occurrences("y"), 2);
+    }
+
+    #[test]
+    fn test_term_occurrences() {
+        let test_formula: Formula = Formula::new(corpus::FIBONACCI[4]);
+        assert_eq!(test_formula.occurrences("index"), 5);
+        assert_eq!(test_formula.occurrences("fib"), 2);
+        assert_eq!(test_formula.occurrences("N"), 3);
+        assert_eq!(
+            test_formula.find_occurrences("fib"),
+            [FormulaPath(vec![0, 0, 0, 0]), FormulaPath(vec![0, 0, 0, 1])]
+        );
+        let repeated: Formula = Formula::new("= f(x,x) x");
+        assert_eq!(repeated.occurrences("x"), 3);
+        assert_eq!(repeated.find_occurrences("x"), [FormulaPath::root()]);
+    }
+
+    #[test]
+    fn test_near_miss_names() {
+        let test_formula: Formula = Formula::new("∀ index2 ∧ < index2 N+index_ = fib(indexes) 5");
+        assert_eq!(test_formula.occurrences("index"), 0);
+        assert!(test_formula.find_occurrences("index").is_empty());
+        assert_eq!(test_formula.occurrences("ind"), 0);
+        assert_eq!(test_formula.occurrences("5"), 0);
+        assert_eq!(test_formula.occurrences("index2"), 2);
+    }
+
+    #[test]
+    fn test_get_and_replace_at_root() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(
+            test_formula.get_at(&FormulaPath::root()),
+            Some(&test_formula)
+        );
+        assert_eq!(
+            test_formula.replace_at(&FormulaPath::root(), Formula::Top),
+            Ok(Formula::Top)
+        );
+    }
+
+    #[test]
+    fn test_get_and_replace_at_leaf() {
+        let test_formula: Formula = Formula::new(corpus::PAPER_EXAMPLE[5]);
+        let leaf: FormulaPath = FormulaPath(vec![0, 1, 0]);
+        assert_eq!(test_formula.get_at(&leaf), Some(&Formula::new("< y r")));
+        assert_eq!(
+            test_formula.replace_at(&leaf, Formula::new("< y r+1")),
+            Ok(Formula::new(
+                "→ ∧ = x r+y*q ∨ < y r+1 = y r = x (r-y)+y*(1+q)"
+            ))
+        );
+        assert_eq!(test_formula.get_at(&leaf.child(0)), None);
+    }
+
+    #[test]
+    fn test_get_and_replace_through_quantifier() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        let path: FormulaPath = FormulaPath(vec![0, 0, 1, 1, 0]);
+        assert_eq!(
+            test_formula.get_at(&path),
+            Some(&Formula::new("∨ R(y) S(y)"))
+        );
+        let replaced: Formula = test_formula.replace_at(&path, Formula::new("⊥")).unwrap();
+        assert_eq!(
+            replaced,
+            Formula::new("∧ ∀ x → P(x) ∧ Q(x) ∃ y ⊥ = ¬ T(x) < U V")
+        );
+        assert_eq!(replaced.get_at(&path), Some(&Formula::Bottom));
+    }
+
+    #[test]
+    fn test_invalid_path() {
+        let test_formula: Formula = Formula::new("∀ x ¬ P(x)");
+        let path: FormulaPath = FormulaPath(vec![0, 1]);
+        assert_eq!(test_formula.get_at(&path), None);
+        let error: PathError = test_formula.replace_at(&path, Formula::Top).unwrap_err();
+        assert_eq!(
+            error,
+            PathError::NoSuchChild {
+                path: path.clone(),
+                step: 1,
+                index: 1,
+                children: 1
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "the path [0, 1] takes child 1 at step 1, but the subformula there has 1 children"
+        );
+        assert!(
+            test_formula
+                .replace_at(&FormulaPath(vec![0, 0, 0]), Formula::Top)
+                .is_err()
+        );
+        assert_eq!(test_formula.get_at(&FormulaPath(vec![usize::MAX])), None);
+    }
+}
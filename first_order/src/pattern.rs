@@ -0,0 +1,491 @@
+//! # Patterns
+//!
+//! This module provides [`Pattern`], a formula template such as `→ ?A ?A`, for checking that a
+//! lemma has the shape of a rule schema and for building formulae from a schema.
+//!
+//! A pattern is written in prefix notation, and every name starting with `?` is a metavariable.
+//! A metavariable used as a proposition, like `?A` in `→ ?A ?A`, stands for any subformula, one
+//! inside a term, like `?x` in `= ?x+0 ?x`, stands for any term, and one written as the variable
+//! of a quantifier, like `?v` in `∀ ?v P(?v)`, stands for any variable. Everything else must match
+//! exactly, and all occurrences of a metavariable must stand for the same formula.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::{Formula, FormulaFolder, FormulaVisitor, ParseError, Substitution, Term, atom};
+
+/// The formulae bound to the metavariables of a [`Pattern`], keyed by their name including the
+/// `?`. A metavariable standing for a term or a variable is bound to a [`Formula::Term`].
+pub type Bindings = Substitution;
+
+/// A formula template whose names starting with `?` are metavariables.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, Pattern};
+///
+/// let schema: Pattern = Pattern::new("→ ∧ ?A ?B ?A");
+/// let bindings = schema.matches(&Formula::new("→ ∧ < y r = x 0 < y r")).unwrap();
+/// assert_eq!(bindings.get("?A"), Some(&Formula::new("< y r")));
+/// assert_eq!(schema.matches(&Formula::new("→ ∧ P Q Q")), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    template: Formula, // The pattern, with its metavariables as ordinary names.
+}
+
+/// An error returned by [`Pattern::instantiate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// A metavariable of the pattern has no binding.
+    Unbound { metavariable: String },
+    /// A metavariable is bound to a formula that cannot take its place, e.g. a conjunction where
+    /// a term is expected.
+    InvalidBinding {
+        metavariable: String,
+        expected: String,
+    },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Unbound { metavariable } => {
+                write!(f, "the metavariable {metavariable} is not bound")
+            }
+            PatternError::InvalidBinding {
+                metavariable,
+                expected,
+            } => write!(
+                f,
+                "the metavariable {metavariable} must be bound to {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl Pattern {
+    /// Creates a new `Pattern` from prefix notation.
+    ///
+    /// # Arguments
+    /// * `input` - A `String` or `&str` that represents the pattern in prefix notation, like in
+    ///   [`Formula::new`].
+    ///
+    /// # Returns
+    /// A `Pattern` instance representing the parsed template.
+    ///
+    /// # Panics
+    /// This function will panic if the input is not a well-formed formula.
+    pub fn new<T: Into<String>>(input: T) -> Self {
+        Pattern {
+            template: Formula::new(input),
+        }
+    }
+    /// Creates a new `Pattern` from prefix notation without panicking.
+    ///
+    /// # Returns
+    /// A `Result` containing the `Pattern`, or a `ParseError` if the input is malformed.
+    pub fn try_new<T: Into<String>>(input: T) -> Result<Self, ParseError> {
+        Ok(Pattern {
+            template: Formula::try_new(input)?,
+        })
+    }
+    /// Returns the names of the metavariables of the pattern, including the `?`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use first_order::Pattern;
+    ///
+    /// assert_eq!(
+    ///     Pattern::new("∀ ?v → ?A = ?v+0 y").metavariables(),
+    ///     BTreeSet::from(["?A".to_string(), "?v".to_string()])
+    /// );
+    /// ```
+    pub fn metavariables(&self) -> BTreeSet<String> {
+        let positions: Positions = positions(&self.template);
+        positions
+            .formulae
+            .into_iter()
+            .chain(positions.terms)
+            .chain(positions.variables)
+            .collect()
+    }
+    /// Matches the formula against the pattern.
+    ///
+    /// # Arguments
+    /// * `formula` - A reference to the `Formula` to match.
+    ///
+    /// # Returns
+    /// `Some` with the `Bindings` of every metavariable if the formula is an instance of the
+    /// pattern, or `None` if it differs from the pattern or a repeated metavariable would stand for
+    /// two different formulae.
+    pub fn matches(&self, formula: &Formula) -> Option<Bindings> {
+        let mut bindings: Bindings = Bindings::new();
+        matches(&self.template, formula, &mut bindings).then_some(bindings)
+    }
+    /// Replaces the metavariables of the pattern with their bindings.
+    ///
+    /// # Arguments
+    /// * `bindings` - A reference to the `Bindings`, e.g. returned by [`Pattern::matches`].
+    ///
+    /// # Returns
+    /// A `Result` containing the instantiated `Formula`, or a `PatternError` if a metavariable is
+    /// unbound, or bound to a formula that is not a term where a term is expected, or not a
+    /// variable where a quantifier's variable is expected.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Bindings, Formula, Pattern, PatternError};
+    ///
+    /// let schema: Pattern = Pattern::new("→ ?A ∨ ?A ?B");
+    /// let mut bindings: Bindings = Bindings::new();
+    /// bindings.bind("?A", Formula::new("< y r"));
+    /// assert_eq!(
+    ///     schema.instantiate(&bindings),
+    ///     Err(PatternError::Unbound { metavariable: "?B".to_string() })
+    /// );
+    /// bindings.bind("?B", Formula::new("= y r"));
+    /// assert_eq!(
+    ///     schema.instantiate(&bindings),
+    ///     Ok(Formula::new("→ < y r ∨ < y r = y r"))
+    /// );
+    /// ```
+    pub fn instantiate(&self, bindings: &Bindings) -> Result<Formula, PatternError> {
+        let positions: Positions = positions(&self.template);
+        for metavariable in self.metavariables() {
+            let binding: &Formula =
+                bindings
+                    .get(&metavariable)
+                    .ok_or_else(|| PatternError::Unbound {
+                        metavariable: metavariable.clone(),
+                    })?;
+            let expected: Option<&str> = match binding {
+                Formula::Term(Term::Var(_)) => None,
+                _ if positions.variables.contains(&metavariable) => Some("a variable"),
+                _ if atom::as_term(binding).is_some() => None,
+                _ if positions.terms.contains(&metavariable) => Some("a term"),
+                _ => None,
+            };
+            if let Some(expected) = expected {
+                return Err(PatternError::InvalidBinding {
+                    metavariable,
+                    expected: expected.to_string(),
+                });
+            }
+        }
+        Ok(self.template.fold(&mut Instantiation { bindings }))
+    }
+}
+
+impl fmt::Display for Pattern {
+    /// Formats the pattern in prefix notation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.template.to_prefix_notation())
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = ParseError;
+
+    /// Parses a `Pattern` from prefix notation. This is equivalent to [`Pattern::try_new`].
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Pattern::try_new(input)
+    }
+}
+
+fn is_metavariable(name: &str) -> bool {
+    name.starts_with('?')
+}
+
+/// Matches `formula` against `pattern`, adding to `bindings`.
+fn matches(pattern: &Formula, formula: &Formula, bindings: &mut Bindings) -> bool {
+    match (pattern, formula) {
+        (Formula::Term(Term::Var(name)), _) if is_metavariable(name) => {
+            bind(name, formula, bindings)
+        }
+        (Formula::Term(pattern), Formula::Term(term)) => matches_term(pattern, term, bindings),
+        (Formula::Equality(_, _) | Formula::Less(_, _) | Formula::Predicate(_, _), _)
+        | (_, Formula::Equality(_, _) | Formula::Less(_, _) | Formula::Predicate(_, _)) => {
+            atom::zip_terms(pattern, formula).is_some_and(|pairs| {
+                pairs
+                    .into_iter()
+                    .all(|(pattern, term)| matches_term(pattern, term, bindings))
+            })
+        }
+        (Formula::Custom(lhs, _, _), Formula::Custom(rhs, _, _)) if lhs != rhs => false,
+        _ if pattern.kind() != formula.kind() => false,
+        _ => {
+            if let (Some(variable), Some(other)) = (pattern.binder(), formula.binder()) {
+                let other: Formula = Formula::Term(Term::Var(other.to_string()));
+                let bound: bool = if is_metavariable(variable) {
+                    bind(variable, &other, bindings)
+                } else {
+                    pattern.binder() == formula.binder()
+                };
+                if !bound {
+                    return false;
+                }
+            }
+            pattern
+                .children()
+                .into_iter()
+                .zip(formula.children())
+                .all(|(pattern, formula)| matches(pattern, formula, bindings))
+        }
+    }
+}
+
+fn matches_term(pattern: &Term, term: &Term, bindings: &mut Bindings) -> bool {
+    match (pattern, term) {
+        (Term::Var(name), _) if is_metavariable(name) => {
+            bind(name, &Formula::Term(term.clone()), bindings)
+        }
+        (Term::App(lhs, lhs_arguments), Term::App(rhs, rhs_arguments)) => {
+            lhs == rhs
+                && lhs_arguments.len() == rhs_arguments.len()
+                && lhs_arguments
+                    .iter()
+                    .zip(rhs_arguments)
+                    .all(|(pattern, term)| matches_term(pattern, term, bindings))
+        }
+        _ => pattern == term,
+    }
+}
+
+/// Binds `metavariable` to `value`, or checks that it is already bound to `value`.
+fn bind(metavariable: &str, value: &Formula, bindings: &mut Bindings) -> bool {
+    match bindings.get(metavariable) {
+        Some(bound) => bound == value,
+        None => {
+            bindings.bind(metavariable, value.clone());
+            true
+        }
+    }
+}
+
+/// The metavariables of a pattern, by the position they occur in.
+#[derive(Default)]
+struct Positions {
+    formulae: BTreeSet<String>,  // Those used as propositions.
+    terms: BTreeSet<String>,     // Those occurring inside terms.
+    variables: BTreeSet<String>, // Those written as the variable of a quantifier.
+}
+
+fn positions(pattern: &Formula) -> Positions {
+    let mut positions: Positions = Positions::default();
+    pattern.accept(&mut positions);
+    positions
+}
+
+impl Positions {
+    fn visit_term_leaves(&mut self, term: &Term) {
+        match term {
+            Term::Var(name) if is_metavariable(name) => {
+                self.terms.insert(name.clone());
+            }
+            Term::App(_, arguments) => {
+                for argument in arguments {
+                    self.visit_term_leaves(argument);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl FormulaVisitor for Positions {
+    fn visit_term(&mut self, term: &Term) {
+        match term {
+            Term::Var(name) if is_metavariable(name) => {
+                self.formulae.insert(name.clone());
+            }
+            _ => self.visit_term_leaves(term),
+        }
+    }
+    fn visit_universal_quantifier(&mut self, variable: &str, inner: &Formula) {
+        if is_metavariable(variable) {
+            self.variables.insert(variable.to_string());
+        }
+        inner.accept(self);
+    }
+    fn visit_existential_quantifier(&mut self, variable: &str, inner: &Formula) {
+        if is_metavariable(variable) {
+            self.variables.insert(variable.to_string());
+        }
+        inner.accept(self);
+    }
+}
+
+/// Replaces the metavariables of a pattern, whose bindings have been checked.
+struct Instantiation<'a> {
+    bindings: &'a Bindings,
+}
+
+impl Instantiation<'_> {
+    fn term(&self, term: &Term) -> Term {
+        match term {
+            Term::Var(name) if is_metavariable(name) => match self.bindings.get(name) {
+                Some(value) => atom::as_term(value).unwrap_or_else(|| term.clone()),
+                None => term.clone(),
+            },
+            Term::App(name, arguments) => Term::App(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| self.term(argument))
+                    .collect(),
+            ),
+            _ => term.clone(),
+        }
+    }
+
+    fn variable(&self, variable: &str) -> String {
+        match self.bindings.get(variable) {
+            Some(Formula::Term(Term::Var(name))) if is_metavariable(variable) => name.clone(),
+            _ => variable.to_string(),
+        }
+    }
+}
+
+impl FormulaFolder for Instantiation<'_> {
+    fn fold_term(&mut self, term: &Term) -> Formula {
+        if let Term::Var(name) = term
+            && is_metavariable(name)
+            && let Some(value) = self.bindings.get(name)
+        {
+            return value.clone();
+        }
+        Formula::Term(self.term(term))
+    }
+    fn fold_universal_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        Formula::UniversalQuantifier(self.variable(variable), Box::new(inner.fold(self)))
+    }
+    fn fold_existential_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        Formula::ExistentialQuantifier(self.variable(variable), Box::new(inner.fold(self)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_repeated_metavariables() {
+        let schema: Pattern = Pattern::new("→ ?A ?A");
+        let bindings: Bindings = schema
+            .matches(&Formula::new(corpus::PAPER_EXAMPLE[4]))
+            .unwrap();
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings.get("?A"), Some(&Formula::new("= x r+y*0")));
+        assert_eq!(schema.matches(&Formula::new("→ P Q")), None);
+        assert_eq!(
+            Pattern::new("= ?x+0 ?x").matches(&Formula::new("= y*q+0 y*q")),
+            Some(Bindings::new().bind("?x", Formula::new("y*q")).clone())
+        );
+        assert_eq!(
+            Pattern::new("= ?x+0 ?x").matches(&Formula::new("= y*q+0 q*y")),
+            None
+        );
+        // An equality built by hand over two `Term` formulae matches like the parsed one.
+        let legacy: Formula = Formula::Equivalence(
+            Box::new(Formula::term("y*q+0")),
+            Box::new(Formula::term("y*q")),
+        );
+        assert_eq!(
+            Pattern::new("= ?x+0 ?x").matches(&legacy),
+            Some(Bindings::new().bind("?x", Formula::new("y*q")).clone())
+        );
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let schema: Pattern = Pattern::new("→ ∧ ?A ?B ?A");
+        for input in ["∨ ∧ P Q P", "→ ∨ P Q P", "→ P P", "∀ x → ∧ P Q P"] {
+            assert_eq!(schema.matches(&Formula::new(input)), None, "{input}");
+        }
+        assert_eq!(
+            Pattern::new("< f(?x) 1").matches(&Formula::new("< g(y) 1")),
+            None
+        );
+        assert_eq!(
+            Pattern::new("∀ x P(x)").matches(&Formula::new("∀ y P(y)")),
+            None
+        );
+        // A name without `?` is matched literally, even if it looks like a variable.
+        assert_eq!(Pattern::new("→ A A").matches(&Formula::new("→ P P")), None);
+        assert!(
+            Pattern::new("→ A A")
+                .matches(&Formula::new("→ A A"))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_quantifier_metavariables() {
+        let schema: Pattern = Pattern::new("→ ∀ ?v ?A ?A");
+        let bindings: Bindings = schema.matches(&Formula::new("→ ∀ x P(y) P(y)")).unwrap();
+        assert_eq!(bindings.get("?v"), Some(&Formula::new("x")));
+        let schema: Pattern = Pattern::new("∀ ?v P(?v)");
+        assert!(schema.matches(&Formula::new("∀ n P(n)")).is_some());
+        assert_eq!(schema.matches(&Formula::new("∀ n P(m)")), None);
+    }
+
+    #[test]
+    fn test_instantiate_is_parseable() {
+        let schema: Pattern = Pattern::new("→ ∧ ?A ∀ ?v ≤ ?v ?n ?A");
+        let mut bindings: Bindings = Bindings::new();
+        bindings
+            .bind("?A", Formula::new("= x r+y*q"))
+            .bind("?v", Formula::new("i"))
+            .bind("?n", Formula::new("(n-1)*2"));
+        let instance: Formula = schema.instantiate(&bindings).unwrap();
+        assert_eq!(
+            instance,
+            Formula::new("→ ∧ = x r+y*q ∀ i ≤ i (n-1)*2 = x r+y*q")
+        );
+        assert_eq!(
+            Formula::try_new(instance.to_prefix_notation()),
+            Ok(instance.clone())
+        );
+        assert_eq!(schema.matches(&instance), Some(bindings));
+    }
+
+    #[test]
+    fn test_instantiate_errors() {
+        let schema: Pattern = Pattern::new("∀ ?v < ?v ?n+1");
+        let mut bindings: Bindings = Bindings::new();
+        bindings.bind("?v", Formula::new("i"));
+        assert_eq!(
+            schema.instantiate(&bindings),
+            Err(PatternError::Unbound {
+                metavariable: "?n".to_string()
+            })
+        );
+        bindings.bind("?n", Formula::new("∧ P Q"));
+        let error: PatternError = schema.instantiate(&bindings).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the metavariable ?n must be bound to a term"
+        );
+        bindings
+            .bind("?n", Formula::new("10"))
+            .bind("?v", Formula::new("i+1"));
+        assert_eq!(
+            schema.instantiate(&bindings),
+            Err(PatternError::InvalidBinding {
+                metavariable: "?v".to_string(),
+                expected: "a variable".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_display_and_from_str() {
+        let schema: Pattern = "→ ?A ∨ ?A ?B".parse().unwrap();
+        assert_eq!(schema.to_string(), "→ ?A ∨ ?A ?B");
+        assert!("→ ?A".parse::<Pattern>().is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! # Polarity
+//!
+//! This module computes the polarity of subformulae, used by [`Formula::polarities`] and
+//! [`Formula::polarity_at`]. A subformula occurs positively if strengthening it strengthens the
+//! whole formula, and negatively if strengthening it weakens the whole formula, so strengthening a
+//! positive premise of a consequence-rule lemma is always safe.
+//!
+//! The root is positive, and polarity is inherited by the children of every node except:
+//!
+//! | Formula     | Polarity of `φ` | Polarity of `ψ` |
+//! |-------------|-----------------|-----------------|
+//! | `¬ φ`       | flipped         |                 |
+//! | `→ φ ψ`     | flipped         | inherited       |
+//! | `= φ ψ`     | both            | both            |
+//! | `↔ φ ψ`     | both            | both            |
+//!
+//! The operands of `≠`, the comparisons and `Custom` connectives are also both, since they are
+//! not monotone in either operand. Once a subformula is both, so is everything below it.
+use std::fmt;
+
+use crate::{Formula, FormulaPath};
+
+/// The polarity of an occurrence of a subformula.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Polarity {
+    /// Strengthening the subformula strengthens the whole formula.
+    Positive,
+    /// Strengthening the subformula weakens the whole formula.
+    Negative,
+    /// The subformula occurs under an equivalence or another non-monotone connective.
+    Both,
+}
+
+impl Polarity {
+    /// Returns the opposite polarity, where `Both` stays `Both`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Polarity;
+    ///
+    /// assert_eq!(Polarity::Positive.flip(), Polarity::Negative);
+    /// assert_eq!(Polarity::Both.flip(), Polarity::Both);
+    /// ```
+    pub fn flip(self) -> Self {
+        match self {
+            Polarity::Positive => Polarity::Negative,
+            Polarity::Negative => Polarity::Positive,
+            Polarity::Both => Polarity::Both,
+        }
+    }
+}
+
+impl fmt::Display for Polarity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Polarity::Positive => "positive",
+            Polarity::Negative => "negative",
+            Polarity::Both => "both",
+        })
+    }
+}
+
+/// Returns the polarity of the child `index` of `parent`, which has polarity `polarity`.
+fn child_polarity(parent: &Formula, index: usize, polarity: Polarity) -> Polarity {
+    match parent {
+        Formula::Negation(_) => polarity.flip(),
+        Formula::Implication(_, _) if index == 0 => polarity.flip(),
+        Formula::Conjunction(_, _)
+        | Formula::Disjunction(_, _)
+        | Formula::Implication(_, _)
+        | Formula::UniversalQuantifier(_, _)
+        | Formula::ExistentialQuantifier(_, _) => polarity,
+        _ => Polarity::Both,
+    }
+}
+
+/// Returns the polarity of every subformula of `formula` with its path, in prefix order. See
+/// [`Formula::polarities`].
+pub(crate) fn polarities(formula: &Formula) -> Vec<(FormulaPath, Polarity)> {
+    let mut found: Vec<(FormulaPath, Polarity)> = Vec::new();
+    let mut stack: Vec<(FormulaPath, &Formula, Polarity)> =
+        vec![(FormulaPath::root(), formula, Polarity::Positive)];
+    while let Some((path, current, polarity)) = stack.pop() {
+        for (index, child) in current.children().into_iter().enumerate().rev() {
+            let child_polarity: Polarity = child_polarity(current, index, polarity);
+            stack.push((path.child(index), child, child_polarity));
+        }
+        found.push((path, polarity));
+    }
+    found
+}
+
+/// Returns the polarity of the subformula of `formula` at `path`. See [`Formula::polarity_at`].
+pub(crate) fn polarity_at(formula: &Formula, path: &FormulaPath) -> Option<Polarity> {
+    let mut current: &Formula = formula;
+    let mut polarity: Polarity = Polarity::Positive;
+    for index in path.0.iter().copied() {
+        let child: &Formula = current.children().get(index).copied()?;
+        polarity = child_polarity(current, index, polarity);
+        current = child;
+    }
+    Some(polarity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_implications_and_negations() {
+        let test_formula: Formula = Formula::new("→ → ¬ P ∧ Q R ¬ → S ∀ x T(x)");
+        let expected: [(&[usize], &str, Polarity); 12] = [
+            (&[], "→ → ¬ P ∧ Q R ¬ → S ∀ x T(x)", Polarity::Positive),
+            (&[0], "→ ¬ P ∧ Q R", Polarity::Negative),
+            (&[0, 0], "¬ P", Polarity::Positive),
+            (&[0, 0, 0], "P", Polarity::Negative),
+            (&[0, 1], "∧ Q R", Polarity::Negative),
+            (&[0, 1, 0], "Q", Polarity::Negative),
+            (&[0, 1, 1], "R", Polarity::Negative),
+            (&[1], "¬ → S ∀ x T(x)", Polarity::Positive),
+            (&[1, 0], "→ S ∀ x T(x)", Polarity::Negative),
+            (&[1, 0, 0], "S", Polarity::Positive),
+            (&[1, 0, 1], "∀ x T(x)", Polarity::Negative),
+            (&[1, 0, 1, 0], "T(x)", Polarity::Negative),
+        ];
+        let polarities: Vec<(FormulaPath, Polarity)> = test_formula.polarities();
+        assert_eq!(polarities.len(), expected.len());
+        for ((path, polarity), (indices, input, expected)) in polarities.iter().zip(expected) {
+            assert_eq!(path.0, indices);
+            assert_eq!(test_formula.get_at(path), Some(&Formula::new(input)));
+            assert_eq!(*polarity, expected, "at {input}");
+            assert_eq!(test_formula.polarity_at(path), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_equivalences_are_both() {
+        let test_formula: Formula = Formula::new("∧ = P → Q R ¬ < x y");
+        let polarities: Vec<Polarity> = test_formula
+            .polarities()
+            .into_iter()
+            .map(|(_, polarity)| polarity)
+            .collect();
+        assert_eq!(
+            polarities,
+            [
+                Polarity::Positive,
+                Polarity::Positive,
+                Polarity::Both,
+                Polarity::Both,
+                Polarity::Both,
+                Polarity::Both,
+                Polarity::Positive,
+                Polarity::Negative
+            ]
+        );
+        assert_eq!(
+            test_formula.polarity_at(&FormulaPath(vec![0, 1, 0])),
+            Some(Polarity::Both)
+        );
+        assert_eq!(test_formula.polarity_at(&FormulaPath(vec![0, 2])), None);
+        assert_eq!(
+            test_formula.polarity_at(&FormulaPath::root()),
+            Some(Polarity::Positive)
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! # Postfix notation parser
+//!
+//! This module reads formulae written in postfix (reverse Polish) notation, the output of
+//! [`Formula::to_postfix_notation`], into a `Formula` for [`Formula::from_postfix`].
+//!
+//! The tokens are those of the prefix notation, separated by whitespace, but every node comes
+//! after its operands, and a quantifier comes after its variable and its body:
+//!
+//! | Prefix                  | Postfix                 |
+//! |-------------------------|-------------------------|
+//! | `¬ P`                   | `P ¬`                   |
+//! | `∧ P Q`                 | `P Q ∧`                 |
+//! | `→ ∧ P Q R`             | `P Q ∧ R →`             |
+//! | `∀ x < 0 x`             | `x 0 x < ∀`             |
+//!
+//! The input is read from left to right onto a stack of operands. An atom is pushed as it is, and
+//! each connective or quantifier pops its operands and pushes the formula it builds, so that a
+//! well-formed input leaves exactly one formula on the stack.
+use crate::{Formula, ParseError, ParseErrorKind, Symbol, lookalike, parse_term};
+
+/// An operand on the stack, with the index of its first token.
+struct Operand<'a> {
+    start: usize,     // The index of the first token of the operand.
+    value: Value<'a>, // The operand itself.
+}
+
+/// An operand, which is kept as its token while it may still be the variable of a quantifier.
+enum Value<'a> {
+    Atom(&'a str),
+    Formula(Formula),
+}
+
+/// Parses `input` in postfix notation. See [`Formula::from_postfix`].
+pub(crate) fn parse(input: &str) -> Result<Formula, ParseError> {
+    let input: String = lookalike::normalize(input);
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+    let mut stack: Vec<Operand<'_>> = Vec::new();
+    for (index, token) in tokens.iter().copied().enumerate() {
+        let Some(symbol) = Symbol::from_token(token) else {
+            stack.push(Operand {
+                start: index,
+                value: Value::Atom(token),
+            });
+            continue;
+        };
+        let error = |expected: String| ParseError {
+            kind: ParseErrorKind::Malformed,
+            index,
+            found: Some(token.to_string()),
+            expected,
+            line: None,
+        };
+        let formula: Formula = match symbol {
+            Symbol::Top => Formula::Top,
+            Symbol::Bottom => Formula::Bottom,
+            Symbol::Negation => {
+                let inner: Operand<'_> = stack
+                    .pop()
+                    .ok_or_else(|| error(format!("a formula before {token}")))?;
+                stack.push(Operand {
+                    start: inner.start,
+                    value: Value::Formula(Formula::Negation(Box::new(formula(inner, symbol)?))),
+                });
+                continue;
+            }
+            Symbol::UniversalQuantifier | Symbol::ExistentialQuantifier => {
+                let missing = || error(format!("a variable and a formula before {token}"));
+                let inner: Operand<'_> = stack.pop().ok_or_else(missing)?;
+                let Some(Operand {
+                    start,
+                    value: Value::Atom(variable),
+                }) = stack.pop()
+                else {
+                    return Err(missing());
+                };
+                let inner: Box<Formula> = Box::new(formula(inner, symbol)?);
+                let quantified: Formula = if symbol == Symbol::UniversalQuantifier {
+                    Formula::UniversalQuantifier(variable.to_string(), inner)
+                } else {
+                    Formula::ExistentialQuantifier(variable.to_string(), inner)
+                };
+                stack.push(Operand {
+                    start,
+                    value: Value::Formula(quantified),
+                });
+                continue;
+            }
+            _ => {
+                if stack.len() < 2 {
+                    return Err(error(format!("two formulae before {token}")));
+                }
+                let rhs: Operand<'_> = stack.pop().expect("two operands are on the stack");
+                let lhs: Operand<'_> = stack.pop().expect("two operands are on the stack");
+                let start: usize = lhs.start;
+                let (lhs, rhs): (Box<Formula>, Box<Formula>) = (
+                    Box::new(formula(lhs, symbol)?),
+                    Box::new(formula(rhs, symbol)?),
+                );
+                stack.push(Operand {
+                    start,
+                    value: Value::Formula(connective(symbol)(lhs, rhs)),
+                });
+                continue;
+            }
+        };
+        stack.push(Operand {
+            start: index,
+            value: Value::Formula(formula),
+        });
+    }
+    let mut operands = stack.into_iter();
+    let Some(first) = operands.next() else {
+        return Err(ParseError {
+            kind: ParseErrorKind::Malformed,
+            index: 0,
+            found: None,
+            expected: "a formula".to_string(),
+            line: None,
+        });
+    };
+    if let Some(leftover) = operands.next() {
+        // The first operand is a complete formula, and everything after it is left over.
+        return Err(ParseError {
+            kind: ParseErrorKind::TrailingTokens,
+            index: leftover.start,
+            found: Some(tokens[leftover.start].to_string()),
+            expected: "end of input".to_string(),
+            line: None,
+        });
+    }
+    match first.value {
+        Value::Atom(token) => atom(token, first.start, None),
+        Value::Formula(formula) => Ok(formula),
+    }
+}
+
+/// Returns the formula of `operand`, parsing it as an atom of the connective `parent` if it is
+/// still a token.
+fn formula(operand: Operand<'_>, parent: Symbol) -> Result<Formula, ParseError> {
+    match operand.value {
+        Value::Atom(token) => atom(token, operand.start, Some(parent)),
+        Value::Formula(formula) => Ok(formula),
+    }
+}
+
+/// Parses `token`, found at token `index`, as an atom, which is an operand of `parent` if any.
+fn atom(token: &str, index: usize, parent: Option<Symbol>) -> Result<Formula, ParseError> {
+    let term_position: bool = parent.is_some_and(|symbol| symbol.is_relation());
+    // A token that looks like a connective was most likely meant as one.
+    if let (false, Some(symbol)) = (term_position, lookalike::suggestion(token)) {
+        return Err(ParseError {
+            kind: ParseErrorKind::Malformed,
+            index,
+            found: Some(token.to_string()),
+            expected: format!("a formula (did you mean \"{symbol}\"?)"),
+            line: None,
+        });
+    }
+    let term: Formula = parse_term(token, index)?;
+    Ok(if term_position {
+        term
+    } else {
+        crate::atom::proposition(term)
+    })
+}
+
+/// Returns the constructor of the binary connective `symbol`.
+fn connective(symbol: Symbol) -> fn(Box<Formula>, Box<Formula>) -> Formula {
+    match symbol {
+        Symbol::Conjunction => Formula::Conjunction,
+        Symbol::Disjunction => Formula::Disjunction,
+        Symbol::Implication => Formula::Implication,
+        Symbol::Equivalence => crate::atom::equality,
+        Symbol::Iff => Formula::Iff,
+        Symbol::NotEqual => Formula::NotEqual,
+        Symbol::LessThan => crate::atom::less_than,
+        Symbol::LessEqual => Formula::LessEqual,
+        Symbol::GreaterEqual => Formula::GreaterEqual,
+        Symbol::GreaterThan => Formula::GreaterThan,
+        _ => unreachable!("{symbol:?} is not a binary connective"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_doc_example() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        let postfix: String = test_formula.to_postfix_notation();
+        assert_eq!(
+            postfix,
+            "x P(x) Q(x) y R(y) S(y) ∨ ∃ ∧ → ∀ T(x) ¬ U V < = ∧"
+        );
+        assert_eq!(Formula::from_postfix(&postfix), Ok(test_formula));
+    }
+
+    #[test]
+    fn test_round_trip_corpus() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            let reparsed: Formula = Formula::from_postfix(&test_formula.to_postfix_notation())
+                .unwrap_or_else(|error| panic!("{input}: {error}"));
+            assert_eq!(reparsed.to_prefix_notation(), input);
+        }
+    }
+
+    #[test]
+    fn test_every_symbol() {
+        let cases: [(&str, &str); 8] = [
+            ("P", "P"),
+            ("⊤", "⊤"),
+            ("¬ ⊥", "⊥ ¬"),
+            ("↔ → P Q ≠ x 0", "P Q → x 0 ≠ ↔"),
+            ("∨ ≤ 0 i ≥ n i", "0 i ≤ n i ≥ ∨"),
+            ("∃ y > fib(y) y", "y fib(y) y > ∃"),
+            ("∀ x ∀ y ¬ = x y", "x y x y = ¬ ∀ ∀"),
+            ("∧ ∧ P Q R", "P Q ∧ R ∧"),
+        ];
+        for (prefix, postfix) in cases {
+            let test_formula: Formula = Formula::new(prefix);
+            assert_eq!(test_formula.to_postfix_notation(), postfix, "{prefix}");
+            assert_eq!(Formula::from_postfix(postfix), Ok(test_formula), "{prefix}");
+        }
+        assert_eq!(
+            Formula::from_postfix("x P(x) Q -> ! forall"),
+            Ok(Formula::new("∀ x ¬ → P(x) Q"))
+        );
+    }
+
+    #[test]
+    fn test_deep_formula() {
+        let mut test_formula: Formula = Formula::term("P");
+        for _ in 0..100_000 {
+            test_formula = Formula::Negation(Box::new(test_formula)).and(Formula::term("Q"));
+        }
+        let postfix: String = test_formula.to_postfix_notation();
+        assert!(postfix.starts_with("P ¬ Q ∧ ¬ Q ∧"));
+        // Deep formulae are compared through their notation, which is written without recursion.
+        let reparsed: Formula = Formula::from_postfix(&postfix).unwrap();
+        assert_eq!(
+            reparsed.to_prefix_notation(),
+            test_formula.to_prefix_notation()
+        );
+    }
+
+    #[test]
+    fn test_stack_underflow() {
+        let error: ParseError = Formula::from_postfix("P ∧").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::Malformed);
+        assert_eq!(
+            error.to_string(),
+            "expected two formulae before ∧ at token 1, found \"∧\""
+        );
+        let error: ParseError = Formula::from_postfix("¬").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected a formula before ¬ at token 0, found \"¬\""
+        );
+        let error: ParseError = Formula::from_postfix("P(x) ∀").unwrap_err();
+        assert_eq!(error.expected, "a variable and a formula before ∀");
+        let error: ParseError = Formula::from_postfix("P Q ∧ R ∃").unwrap_err();
+        assert_eq!((error.index, error.found), (4, Some("∃".to_string())));
+        assert_eq!(
+            Formula::from_postfix(" ").unwrap_err().to_string(),
+            "expected a formula at token 0, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_leftover_operands() {
+        let error: ParseError = Formula::from_postfix("P Q ∧ R S ∨").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::TrailingTokens);
+        assert_eq!(
+            error.to_string(),
+            "trailing tokens starting at index 3, found \"R\""
+        );
+        let error: ParseError = Formula::from_postfix("x y").unwrap_err();
+        assert_eq!((error.index, error.found), (1, Some("y".to_string())));
+    }
+
+    #[test]
+    fn test_lookalike_atoms() {
+        let error: ParseError = Formula::from_postfix("P => ∧").unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(error.expected, "a formula (did you mean \"→\"?)");
+        // Lookalike characters are replaced before the input is split, as in the prefix parser.
+        assert_eq!(Formula::from_postfix("P Q ⟶"), Ok(Formula::new("→ P Q")));
+        assert!(Formula::from_postfix("x f(x ≤").is_err());
+    }
+}
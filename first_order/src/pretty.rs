@@ -0,0 +1,220 @@
+//! # Pretty infix notation
+//!
+//! This module writes a formula in infix notation with as few parentheses as the infix parser
+//! needs to read it back, for [`Formula::to_infix_pretty`].
+//!
+//! The precedences are those of [`Formula::from_infix`], from the loosest to the tightest binding:
+//!
+//! | Level | Connectives             | Associativity |
+//! |-------|-------------------------|---------------|
+//! | 0     | `=`, `↔`                | left          |
+//! | 1     | `→`                     | right         |
+//! | 2     | `∨`                     | left          |
+//! | 3     | `∧`                     | left          |
+//! | 4     | `¬`, `∀x`, `∃x`         |               |
+//! | 5     | `≠`, `<`, `≤`, `≥`, `>` | none          |
+//!
+//! Since the parser reads every `=` at the loosest level, an equality between two terms is
+//! parenthesized as the operand of any other connective, e.g. `y < r ∨ (y = r)`. A quantifier body
+//! that binds at least as tightly as `¬` is written without parentheses, as in `∀x ¬P(x)`, and any
+//! other body is parenthesized, as in `∀x(P(x) → Q(x))`. A `Custom` connective is always
+//! parenthesized, like in [`Formula::to_infix_notation`].
+//!
+//! The encoding of a bounded quantifier recognised by [`Formula::as_bounded_quantifier`] is
+//! written in its bounded form, as in `∀i < n (P(i) → Q(i))` for `∀ i → < i n → P(i) Q(i)`.
+use crate::{Formula, Quantifier};
+
+const EQUIVALENCE: u8 = 0;
+const IMPLICATION: u8 = 1;
+const DISJUNCTION: u8 = 2;
+const CONJUNCTION: u8 = 3;
+const UNARY: u8 = 4;
+const RELATION: u8 = 5;
+const ATOM: u8 = 6;
+
+/// Writes `formula` in infix notation with minimal parentheses. See [`Formula::to_infix_pretty`].
+pub(crate) fn to_infix_pretty(formula: &Formula) -> String {
+    pretty(formula).0
+}
+
+/// Writes `formula` and returns it along with the level of its outermost connective.
+fn pretty(formula: &Formula) -> (String, u8) {
+    if let Some((quantifier, variable, bound, inner)) = formula.as_bounded_quantifier() {
+        return bounded(quantifier, variable, &bound.to_string(), inner);
+    }
+    match formula {
+        Formula::Term(term) => (term.to_string(), ATOM),
+        Formula::Top => ("⊤".to_string(), ATOM),
+        Formula::Bottom => ("⊥".to_string(), ATOM),
+        Formula::Negation(inner) => (format!("¬{}", operand(inner, UNARY)), UNARY),
+        Formula::Conjunction(lhs, rhs) => binary(lhs, "∧", rhs, CONJUNCTION, UNARY),
+        Formula::Disjunction(lhs, rhs) => binary(lhs, "∨", rhs, DISJUNCTION, CONJUNCTION),
+        Formula::Implication(lhs, rhs) => binary(lhs, "→", rhs, DISJUNCTION, IMPLICATION),
+        Formula::Equivalence(lhs, rhs) => binary(lhs, "=", rhs, EQUIVALENCE, IMPLICATION),
+        Formula::Iff(lhs, rhs) => binary(lhs, "↔", rhs, EQUIVALENCE, IMPLICATION),
+        Formula::NotEqual(lhs, rhs) => relation(lhs, "≠", rhs),
+        Formula::LessThan(lhs, rhs) => relation(lhs, "<", rhs),
+        Formula::LessEqual(lhs, rhs) => relation(lhs, "≤", rhs),
+        Formula::GreaterEqual(lhs, rhs) => relation(lhs, "≥", rhs),
+        Formula::GreaterThan(lhs, rhs) => relation(lhs, ">", rhs),
+        Formula::UniversalQuantifier(variable, inner) => quantifier("∀", variable, inner),
+        Formula::ExistentialQuantifier(variable, inner) => quantifier("∃", variable, inner),
+        Formula::Custom(symbol, lhs, rhs) => (
+            format!("({} {symbol} {})", operand(lhs, UNARY), operand(rhs, UNARY)),
+            ATOM,
+        ),
+        Formula::Equality(lhs, rhs) => (format!("{lhs} = {rhs}"), EQUIVALENCE),
+        Formula::Less(lhs, rhs) => (format!("{lhs} < {rhs}"), RELATION),
+        Formula::Predicate(_, _) => (formula.to_string(), ATOM),
+    }
+}
+
+/// Writes `formula`, parenthesized if its outermost connective binds more loosely than `level`.
+fn operand(formula: &Formula, level: u8) -> String {
+    let (text, own): (String, u8) = pretty(formula);
+    if own < level {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+/// Writes the binary connective `symbol`, whose left operand binds at least as tightly
+/// as `left` and whose right operand binds at least as tightly as `right`. The connective itself
+/// binds at the looser of the two levels.
+fn binary(lhs: &Formula, symbol: &str, rhs: &Formula, left: u8, right: u8) -> (String, u8) {
+    let level: u8 = left.min(right);
+    (
+        format!("{} {symbol} {}", operand(lhs, left), operand(rhs, right)),
+        level,
+    )
+}
+
+/// Writes a relation, whose operands must be atoms.
+fn relation(lhs: &Formula, symbol: &str, rhs: &Formula) -> (String, u8) {
+    (
+        format!("{} {symbol} {}", operand(lhs, ATOM), operand(rhs, ATOM)),
+        RELATION,
+    )
+}
+
+/// Writes a quantifier, parenthesizing its body unless it binds at least as tightly as `¬`.
+///
+/// A body starting with a parenthesis is parenthesized too, since the parser would read that
+/// parenthesis as enclosing the whole body.
+fn quantifier(symbol: &str, variable: &str, inner: &Formula) -> (String, u8) {
+    let (body, level): (String, u8) = pretty(inner);
+    if level >= UNARY && !body.starts_with('(') {
+        (format!("{symbol}{variable} {body}"), UNARY)
+    } else {
+        (format!("{symbol}{variable}({body})"), ATOM)
+    }
+}
+
+/// Writes a bounded quantifier, parenthesizing its body like [`quantifier`] does.
+///
+/// The body follows the bound after a space, and is never read as the argument list of the bound.
+/// Since the parser reads the body as the operand of a unary connective, the bounded quantifier
+/// binds like `¬` even if its body is parenthesized.
+fn bounded(quantifier: Quantifier, variable: &str, bound: &str, inner: &Formula) -> (String, u8) {
+    let (body, level): (String, u8) = pretty(inner);
+    if level >= UNARY && !body.starts_with('(') {
+        (format!("{quantifier}{variable} < {bound} {body}"), UNARY)
+    } else {
+        (format!("{quantifier}{variable} < {bound} ({body})"), UNARY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_doc_example() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(
+            test_formula.to_infix_pretty(),
+            "∀x(P(x) → Q(x) ∧ ∃y(R(y) ∨ S(y))) ∧ (¬T(x) = U < V)"
+        );
+    }
+
+    #[test]
+    fn test_paper_example() {
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[5]).to_infix_pretty(),
+            "(x = r+y*q) ∧ (y < r ∨ (y = r)) → (x = (r-y)+y*(1+q))"
+        );
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[1]).to_infix_pretty(),
+            "x = x+y*0"
+        );
+    }
+
+    #[test]
+    fn test_associativity() {
+        let cases: [(&str, &str); 8] = [
+            ("∧ ∧ A B C", "A ∧ B ∧ C"),
+            ("∧ A ∧ B C", "A ∧ (B ∧ C)"),
+            ("∨ ∨ A B C", "A ∨ B ∨ C"),
+            ("∨ A ∨ B C", "A ∨ (B ∨ C)"),
+            ("→ A → B C", "A → B → C"),
+            ("→ → A B C", "(A → B) → C"),
+            ("= = A B C", "A = B = C"),
+            ("= A = B C", "A = (B = C)"),
+        ];
+        for (input, pretty) in cases {
+            assert_eq!(Formula::new(input).to_infix_pretty(), pretty, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        let cases: [(&str, &str); 6] = [
+            ("= → ∨ ∧ ¬ A B C D E", "¬A ∧ B ∨ C → D = E"),
+            ("¬ = → ∨ ∧ A B C D E", "¬(A ∧ B ∨ C → D = E)"),
+            ("∧ ∨ A B ¬ ∨ C D", "(A ∨ B) ∧ ¬(C ∨ D)"),
+            ("¬ < x y", "¬x < y"),
+            ("< ¬ P Q", "(¬P) < Q"),
+            ("∧ ≤ 0 i ¬ = i n", "0 ≤ i ∧ ¬(i = n)"),
+        ];
+        for (input, pretty) in cases {
+            assert_eq!(Formula::new(input).to_infix_pretty(), pretty, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_quantifier_bodies() {
+        let cases: [(&str, &str); 6] = [
+            ("∀ x ∃ y P(x,y)", "∀x ∃y P(x,y)"),
+            ("∀ x ¬ < x 0", "∀x ¬x < 0"),
+            ("∧ ∀ x P(x) Q", "∀x P(x) ∧ Q"),
+            ("∀ x ∧ P(x) Q", "∀x(P(x) ∧ Q)"),
+            ("∀ x (count-1)", "∀x((count-1))"),
+            ("< ∃ y P(y) z", "(∃y P(y)) < z"),
+        ];
+        for (input, pretty) in cases {
+            assert_eq!(Formula::new(input).to_infix_pretty(), pretty, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_bounded_quantifiers() {
+        let cases: [(&str, &str); 6] = [
+            (
+                "∀ i → < i index = out(i) fib(i)",
+                "∀i < index (out(i) = fib(i))",
+            ),
+            ("∃ j ∧ < j n+1 P(j)", "∃j < n+1 P(j)"),
+            ("∀ i → < i n ∃ j ∧ < j i ¬ P(i,j)", "∀i < n ∃j < i ¬P(i,j)"),
+            ("∧ ∀ i → < i n P(i) Q", "∀i < n P(i) ∧ Q"),
+            ("< ∀ i → < i n P(i) z", "(∀i < n P(i)) < z"),
+            ("∀ i → < n i P(i)", "∀i(n < i → P(i))"),
+        ];
+        for (input, pretty) in cases {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(test_formula.to_infix_pretty(), pretty, "{input}");
+            assert_eq!(Formula::from_infix(pretty), Ok(test_formula), "{input}");
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! # Print options
+//!
+//! This module writes terms in the spelling an audience expects, for [`Term::to_string_with`]
+//! and [`Formula::to_string_with`], and provides the options the `Triple` rendering of the
+//! `hoare_triple` crate threads through to them:
+//!
+//! | Option                  | Default  | Changed     |
+//! |-------------------------|----------|-------------|
+//! | `spaced_operators`      | `x+1`    | `x + 1`     |
+//! | `multiplication_dot`    | `y*q`    | `y·q`       |
+//! | `superscript_exponents` | `10^2`   | `10²`       |
+//! | `assignment_symbol`     | `x≔x+1`  | `x:=x+1`    |
+//!
+//! Only a numeral exponent is written in superscript; any other exponent keeps the `^`. The
+//! default options write every term exactly as its `Display` does, which is how it was parsed.
+use std::fmt;
+
+use crate::Term;
+use crate::term::{UNARY, is_operator};
+
+/// How [`Term::to_string_with`] writes a term.
+///
+/// By default a term is written as its `Display` writes it.
+///
+/// # Example
+/// ```
+/// use first_order::{Term, TermPrintOptions};
+///
+/// let test_term: Term = Term::new("r*10^2+y*q");
+/// let mut options = TermPrintOptions::new();
+/// options.spaced_operators(true).multiplication_dot(true);
+/// assert_eq!(test_term.to_string_with(&options), "r · 10 ^ 2 + y · q");
+/// options.superscript_exponents(true);
+/// assert_eq!(test_term.to_string_with(&options), "r · 10² + y · q");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TermPrintOptions {
+    spaced_operators: bool,   // Whether binary operators are surrounded by spaces.
+    multiplication_dot: bool, // Whether `*` is written as `·`.
+    superscript_exponents: bool, // Whether numeral exponents are written in superscript.
+}
+
+impl TermPrintOptions {
+    /// Creates `TermPrintOptions` writing terms as their `Display` does.
+    pub fn new() -> Self {
+        TermPrintOptions::default()
+    }
+
+    /// Sets whether the binary operators are surrounded by spaces, as in `x + 1`. A unary `-`
+    /// never is.
+    pub fn spaced_operators(&mut self, enabled: bool) -> &mut Self {
+        self.spaced_operators = enabled;
+        self
+    }
+
+    /// Sets whether multiplication is written with the middle dot `·` rather than `*`.
+    pub fn multiplication_dot(&mut self, enabled: bool) -> &mut Self {
+        self.multiplication_dot = enabled;
+        self
+    }
+
+    /// Sets whether a numeral exponent is written in superscript digits, as in `10²`, rather than
+    /// after a `^`.
+    pub fn superscript_exponents(&mut self, enabled: bool) -> &mut Self {
+        self.superscript_exponents = enabled;
+        self
+    }
+}
+
+/// How formulae and triples are written, for
+/// [`Formula::to_string_with`](crate::Formula::to_string_with) and the `Triple` rendering of the
+/// `hoare_triple` crate.
+///
+/// By default everything is written as its `Display` writes it.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, PrintOptions, TermPrintOptions};
+///
+/// let mut terms = TermPrintOptions::new();
+/// terms.spaced_operators(true);
+/// let mut options = PrintOptions::new();
+/// options.terms(terms).assignment_symbol(":=");
+/// assert_eq!(
+///     Formula::new("= x r+y*q").to_string_with(&options),
+///     "(x=r + y * q)"
+/// );
+/// assert_eq!(options.assignment(), " := ");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintOptions {
+    terms: TermPrintOptions,   // How the terms are written.
+    assignment_symbol: String, // The symbol between the variable and the value of an assignment.
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            terms: TermPrintOptions::default(),
+            assignment_symbol: "≔".to_string(),
+        }
+    }
+}
+
+impl PrintOptions {
+    /// Creates `PrintOptions` writing everything as its `Display` does.
+    pub fn new() -> Self {
+        PrintOptions::default()
+    }
+
+    /// Sets how the terms are written.
+    pub fn terms(&mut self, options: TermPrintOptions) -> &mut Self {
+        self.terms = options;
+        self
+    }
+
+    /// Sets the symbol written between the variable and the value of an assignment, e.g. `:=` or
+    /// `\gets`.
+    pub fn assignment_symbol(&mut self, symbol: impl Into<String>) -> &mut Self {
+        self.assignment_symbol = symbol.into();
+        self
+    }
+
+    /// Returns how the terms are written.
+    pub fn term_options(&self) -> &TermPrintOptions {
+        &self.terms
+    }
+
+    /// Returns the text written between the variable and the value of an assignment: the
+    /// assignment symbol, surrounded by spaces if the term operators are.
+    pub fn assignment(&self) -> String {
+        if self.terms.spaced_operators {
+            format!(" {} ", self.assignment_symbol)
+        } else {
+            self.assignment_symbol.clone()
+        }
+    }
+}
+
+/// Writes `term` as `options` say. See [`Term::to_string_with`].
+pub(crate) fn write_term(
+    term: &Term,
+    options: &TermPrintOptions,
+    out: &mut impl fmt::Write,
+) -> fmt::Result {
+    match term {
+        Term::Var(name) | Term::Const(name) => out.write_str(name),
+        Term::IntLit(value) => write!(out, "{value}"),
+        Term::App(name, args) => match (name.as_str(), args.as_slice()) {
+            ("^", [base, exponent @ (Term::IntLit(_) | Term::Const(_))])
+                if options.superscript_exponents
+                    && exponent.to_string().chars().all(|c| c.is_ascii_digit()) =>
+            {
+                write_operand(base, term.precedence() + 1, options, out)?;
+                exponent
+                    .to_string()
+                    .chars()
+                    .try_for_each(|c| out.write_char(superscript(c)))
+            }
+            (operator, [left, right]) if is_operator(operator) => {
+                let precedence: u8 = term.precedence();
+                let (left_min, right_min): (u8, u8) = match operator {
+                    "^" => (precedence + 1, UNARY),
+                    _ => (precedence, precedence + 1),
+                };
+                let symbol: &str = match operator {
+                    "*" if options.multiplication_dot => "·",
+                    _ => operator,
+                };
+                write_operand(left, left_min, options, out)?;
+                if options.spaced_operators {
+                    write!(out, " {symbol} ")?;
+                } else {
+                    out.write_str(symbol)?;
+                }
+                write_operand(right, right_min, options, out)
+            }
+            ("-", [inner]) => {
+                out.write_str("-")?;
+                write_operand(inner, UNARY, options, out)
+            }
+            ("", [inner]) => {
+                out.write_str("(")?;
+                write_term(inner, options, out)?;
+                out.write_str(")")
+            }
+            _ => {
+                out.write_str(name)?;
+                write_arguments(args, options, out)
+            }
+        },
+    }
+}
+
+/// Writes the predicate `name` applied to `args` as `options` say: like the term `name(args)`, or
+/// as `name` alone if it has no arguments.
+pub(crate) fn write_predicate(
+    name: &str,
+    args: &[Term],
+    options: &TermPrintOptions,
+    out: &mut impl fmt::Write,
+) -> fmt::Result {
+    out.write_str(name)?;
+    if args.is_empty() {
+        return Ok(());
+    }
+    write_arguments(args, options, out)
+}
+
+/// Writes the arguments of an application, separated by commas and enclosed in parentheses.
+fn write_arguments(
+    args: &[Term],
+    options: &TermPrintOptions,
+    out: &mut impl fmt::Write,
+) -> fmt::Result {
+    out.write_str("(")?;
+    for (index, arg) in args.iter().enumerate() {
+        if index > 0 {
+            out.write_str(",")?;
+        }
+        write_term(arg, options, out)?;
+    }
+    out.write_str(")")
+}
+
+/// Writes `operand`, parenthesized if it binds less tightly than `min_precedence`.
+fn write_operand(
+    operand: &Term,
+    min_precedence: u8,
+    options: &TermPrintOptions,
+    out: &mut impl fmt::Write,
+) -> fmt::Result {
+    if operand.precedence() < min_precedence {
+        out.write_str("(")?;
+        write_term(operand, options, out)?;
+        out.write_str(")")
+    } else {
+        write_term(operand, options, out)
+    }
+}
+
+/// Returns the superscript form of the digit `c`.
+fn superscript(c: char) -> char {
+    match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        _ => '⁹',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Formula, corpus};
+
+    #[test]
+    fn test_defaults_match_display() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(
+                test_formula.to_string_with(&PrintOptions::new()),
+                test_formula.to_string()
+            );
+            for token in input.split_whitespace() {
+                if let Ok(Formula::Term(term)) = &Formula::try_new(token) {
+                    assert_eq!(term.to_string_with(&TermPrintOptions::new()), token);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_each_option() {
+        let test_term: Term = Term::new("(r-y)+y*(1+q)^2-x^(n)");
+        let with = |configure: fn(&mut TermPrintOptions) -> &mut TermPrintOptions| {
+            let mut options: TermPrintOptions = TermPrintOptions::new();
+            configure(&mut options);
+            test_term.to_string_with(&options)
+        };
+        assert_eq!(with(|options| options), "(r-y)+y*(1+q)^2-x^(n)");
+        assert_eq!(
+            with(|options| options.spaced_operators(true)),
+            "(r - y) + y * (1 + q) ^ 2 - x ^ (n)"
+        );
+        assert_eq!(
+            with(|options| options.multiplication_dot(true)),
+            "(r-y)+y·(1+q)^2-x^(n)"
+        );
+        assert_eq!(
+            with(|options| options.superscript_exponents(true)),
+            "(r-y)+y*(1+q)²-x^(n)"
+        );
+        assert_eq!(
+            Term::new("-x^10").to_string_with(TermPrintOptions::new().superscript_exponents(true)),
+            "-x¹⁰"
+        );
+    }
+
+    #[test]
+    fn test_formula_options() {
+        let test_formula: Formula = Formula::new("∀ i → < i n = a(i) i*i");
+        let mut terms: TermPrintOptions = TermPrintOptions::new();
+        terms.multiplication_dot(true).spaced_operators(true);
+        let mut options: PrintOptions = PrintOptions::new();
+        options.terms(terms);
+        assert_eq!(
+            test_formula.to_string_with(&options),
+            "∀i(((i<n)→(a(i)=i · i)))"
+        );
+        assert_eq!(options.assignment(), " ≔ ");
+        options.assignment_symbol(r"\gets");
+        assert_eq!(options.assignment(), r" \gets ");
+    }
+}
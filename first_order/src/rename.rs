@@ -0,0 +1,498 @@
+//! # Renaming bound variables
+//!
+//! This module implements alpha-conversion, the renaming of the variable bound by a quantifier
+//! together with every occurrence it binds, used by [`Formula::rename_bound`] and
+//! [`Formula::freshen_bound`]. Renaming never changes which quantifier binds an occurrence, so
+//! the renamed formula has the same meaning as the original.
+//!
+//! It also renames several variables at once for [`Formula::rename`] and
+//! [`Formula::rename_with`]. Every name is looked up in the original formula, so swapping `a` and
+//! `b` works, where renaming `a` to `b` and then `b` to `a` would merge them.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::normal_form::map_operands;
+use crate::{Formula, Symbol, Term, atom, variables};
+
+/// An error returned when a bound variable cannot be renamed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    /// No quantifier binds the variable to rename.
+    NotBound { variable: String },
+    /// The new name would not be read back as a variable, e.g. because it contains whitespace.
+    InvalidName { name: String },
+    /// The new name already occurs free or bound in the scope of the quantifier, so renaming
+    /// would change which quantifier binds some occurrence.
+    Capture { name: String },
+    /// Two different variables would both be renamed to `name`.
+    Collision { name: String },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NotBound { variable } => write!(f, "no quantifier binds {variable}"),
+            RenameError::InvalidName { name } => write!(f, "{name:?} is not a variable name"),
+            RenameError::Capture { name } => {
+                write!(f, "{name} already occurs in the scope of the quantifier")
+            }
+            RenameError::Collision { name } => {
+                write!(f, "two different variables would both be renamed to {name}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenameError {}
+
+/// Renames the first quantifier binding `from` to bind `to` instead. See
+/// [`Formula::rename_bound`].
+pub(crate) fn rename_bound(
+    formula: &Formula,
+    from: &str,
+    to: &str,
+) -> Result<Formula, RenameError> {
+    if !is_variable_name(to) {
+        return Err(RenameError::InvalidName {
+            name: to.to_string(),
+        });
+    }
+    let Some(scope) = first_binding(formula, from) else {
+        return Err(RenameError::NotBound {
+            variable: from.to_string(),
+        });
+    };
+    if from != to && (variables::free(scope).contains(to) || variables::bound(scope).contains(to)) {
+        return Err(RenameError::Capture {
+            name: to.to_string(),
+        });
+    }
+    let mut renamed: bool = false;
+    Ok(rename_binders(formula, &mut Vec::new(), &mut |variable| {
+        if !renamed && variable == from {
+            renamed = true;
+            to.to_string()
+        } else {
+            variable.to_string()
+        }
+    }))
+}
+
+/// Renames the variables of `formula` simultaneously as `mapping` says, including the variables
+/// of quantifiers if `binders` is set. See [`Formula::rename_with`].
+pub(crate) fn rename(
+    formula: &Formula,
+    mapping: &BTreeMap<String, String>,
+    binders: bool,
+) -> Result<Formula, RenameError> {
+    if let Some(name) = mapping
+        .iter()
+        .flat_map(|(from, to)| [from, to])
+        .find(|name| !is_variable_name(name))
+    {
+        return Err(RenameError::InvalidName { name: name.clone() });
+    }
+    let mut names: BTreeSet<String> = variables::free(formula);
+    if binders {
+        names.extend(variables::bound(formula));
+    } else {
+        check_capture(formula, mapping, &mut Vec::new())?;
+    }
+    let mut renamed: BTreeSet<&str> = BTreeSet::new();
+    for name in &names {
+        let new: &str = mapping.get(name).unwrap_or(name);
+        if !renamed.insert(new) {
+            return Err(RenameError::Collision {
+                name: new.to_string(),
+            });
+        }
+    }
+    Ok(rename_variables(formula, mapping, binders, &mut Vec::new()))
+}
+
+/// Returns whether `name` is read back as a variable rather than a constant, a compound term or
+/// a connective.
+fn is_variable_name(name: &str) -> bool {
+    Term::parse(name) == Ok(Term::Var(name.to_string())) && Symbol::from_token(name).is_none()
+}
+
+/// Fails if a free variable of `formula` outside `scope` would be renamed to the variable of a
+/// quantifier whose scope it occurs in.
+fn check_capture<'a>(
+    formula: &'a Formula,
+    mapping: &BTreeMap<String, String>,
+    scope: &mut Vec<&'a str>,
+) -> Result<(), RenameError> {
+    if let Formula::UniversalQuantifier(variable, inner)
+    | Formula::ExistentialQuantifier(variable, inner) = formula
+    {
+        let captured: bool = variables::free(formula)
+            .iter()
+            .any(|name| !scope.contains(&name.as_str()) && mapping.get(name) == Some(variable));
+        if captured {
+            return Err(RenameError::Capture {
+                name: variable.clone(),
+            });
+        }
+        scope.push(variable);
+        let checked: Result<(), RenameError> = check_capture(inner, mapping, scope);
+        scope.pop();
+        return checked;
+    }
+    for child in formula.children() {
+        check_capture(child, mapping, scope)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds `formula` with the variables in `mapping` renamed. Occurrences bound by a quantifier
+/// in `scope` are renamed only if `binders` is set, together with the quantifier.
+fn rename_variables(
+    formula: &Formula,
+    mapping: &BTreeMap<String, String>,
+    binders: bool,
+    scope: &mut Vec<String>,
+) -> Formula {
+    let rename = |name: &str, scope: &[String]| -> String {
+        match mapping.get(name) {
+            Some(new) if binders || !scope.iter().any(|bound| bound == name) => new.clone(),
+            _ => name.to_string(),
+        }
+    };
+    match formula {
+        Formula::Term(term) => Formula::Term(term.map_leaves(|leaf| rename(leaf, scope))),
+        Formula::Equality(lhs, rhs) => Formula::Equality(
+            lhs.map_leaves(|leaf| rename(leaf, scope)),
+            rhs.map_leaves(|leaf| rename(leaf, scope)),
+        ),
+        Formula::Less(lhs, rhs) => Formula::Less(
+            lhs.map_leaves(|leaf| rename(leaf, scope)),
+            rhs.map_leaves(|leaf| rename(leaf, scope)),
+        ),
+        Formula::Predicate(name, args) => atom::proposition(Formula::Term(
+            atom::predicate_term(name, args).map_leaves(|leaf| rename(leaf, scope)),
+        )),
+        Formula::UniversalQuantifier(variable, inner)
+        | Formula::ExistentialQuantifier(variable, inner) => {
+            scope.push(variable.clone());
+            let new: String = rename(variable, scope);
+            let inner: Box<Formula> = Box::new(rename_variables(inner, mapping, binders, scope));
+            scope.pop();
+            match formula {
+                Formula::UniversalQuantifier(_, _) => Formula::UniversalQuantifier(new, inner),
+                _ => Formula::ExistentialQuantifier(new, inner),
+            }
+        }
+        _ => map_operands(formula, |operand| {
+            rename_variables(operand, mapping, binders, scope)
+        }),
+    }
+}
+
+/// Renames every quantifier whose variable is in `taken`, is free in `formula`, or is bound by an
+/// earlier quantifier. See [`Formula::freshen_bound`].
+pub(crate) fn freshen_bound(formula: &Formula, taken: &BTreeSet<String>) -> Formula {
+    let mut used: BTreeSet<String> = taken.clone();
+    used.extend(variables::free(formula));
+    let mut avoided: BTreeSet<String> = used.clone();
+    avoided.extend(variables::bound(formula));
+    rename_binders(formula, &mut Vec::new(), &mut |variable| {
+        let mut name: String = variable.to_string();
+        let mut suffix: usize = 0;
+        while used.contains(&name) || (suffix > 0 && avoided.contains(&name)) {
+            suffix += 1;
+            name = format!("{variable}_{suffix}");
+        }
+        used.insert(name.clone());
+        name
+    })
+}
+
+/// Returns the body of the first quantifier of `formula` binding `variable`, in prefix order.
+fn first_binding<'a>(formula: &'a Formula, variable: &str) -> Option<&'a Formula> {
+    match formula {
+        Formula::Term(_)
+        | Formula::Top
+        | Formula::Bottom
+        | Formula::Equality(_, _)
+        | Formula::Less(_, _)
+        | Formula::Predicate(_, _) => None,
+        Formula::Negation(inner) => first_binding(inner, variable),
+        Formula::Conjunction(lhs, rhs)
+        | Formula::Disjunction(lhs, rhs)
+        | Formula::Implication(lhs, rhs)
+        | Formula::Equivalence(lhs, rhs)
+        | Formula::Iff(lhs, rhs)
+        | Formula::NotEqual(lhs, rhs)
+        | Formula::LessThan(lhs, rhs)
+        | Formula::LessEqual(lhs, rhs)
+        | Formula::GreaterEqual(lhs, rhs)
+        | Formula::GreaterThan(lhs, rhs)
+        | Formula::Custom(_, lhs, rhs) => {
+            first_binding(lhs, variable).or_else(|| first_binding(rhs, variable))
+        }
+        Formula::UniversalQuantifier(bound, inner)
+        | Formula::ExistentialQuantifier(bound, inner) => {
+            if bound == variable {
+                Some(inner)
+            } else {
+                first_binding(inner, variable)
+            }
+        }
+    }
+}
+
+/// Rebuilds `formula`, letting `choose` pick the new variable of every quantifier in prefix
+/// order and renaming the occurrences each quantifier binds to match.
+///
+/// `scope` pairs the original and new variables of the quantifiers enclosing `formula`, innermost
+/// last. Free occurrences are left unchanged.
+fn rename_binders<'a>(
+    formula: &'a Formula,
+    scope: &mut Vec<(&'a str, String)>,
+    choose: &mut impl FnMut(&str) -> String,
+) -> Formula {
+    let mut rename = |formula: &'a Formula| Box::new(rename_binders(formula, scope, choose));
+    match formula {
+        Formula::Term(term) => Formula::Term(rename_term(term, scope)),
+        Formula::Equality(lhs, rhs) => {
+            Formula::Equality(rename_term(lhs, scope), rename_term(rhs, scope))
+        }
+        Formula::Less(lhs, rhs) => Formula::Less(rename_term(lhs, scope), rename_term(rhs, scope)),
+        Formula::Predicate(name, args) => atom::proposition(Formula::Term(rename_term(
+            &atom::predicate_term(name, args),
+            scope,
+        ))),
+        Formula::Top => Formula::Top,
+        Formula::Bottom => Formula::Bottom,
+        Formula::Negation(inner) => Formula::Negation(rename(inner)),
+        Formula::Conjunction(lhs, rhs) => Formula::Conjunction(rename(lhs), rename(rhs)),
+        Formula::Disjunction(lhs, rhs) => Formula::Disjunction(rename(lhs), rename(rhs)),
+        Formula::Implication(lhs, rhs) => Formula::Implication(rename(lhs), rename(rhs)),
+        Formula::Equivalence(lhs, rhs) => Formula::Equivalence(rename(lhs), rename(rhs)),
+        Formula::Iff(lhs, rhs) => Formula::Iff(rename(lhs), rename(rhs)),
+        Formula::NotEqual(lhs, rhs) => Formula::NotEqual(rename(lhs), rename(rhs)),
+        Formula::LessThan(lhs, rhs) => Formula::LessThan(rename(lhs), rename(rhs)),
+        Formula::LessEqual(lhs, rhs) => Formula::LessEqual(rename(lhs), rename(rhs)),
+        Formula::GreaterEqual(lhs, rhs) => Formula::GreaterEqual(rename(lhs), rename(rhs)),
+        Formula::GreaterThan(lhs, rhs) => Formula::GreaterThan(rename(lhs), rename(rhs)),
+        Formula::Custom(symbol, lhs, rhs) => {
+            Formula::Custom(symbol.clone(), rename(lhs), rename(rhs))
+        }
+        Formula::UniversalQuantifier(variable, inner) => {
+            let (variable, inner) = rename_quantifier(variable, inner, scope, choose);
+            Formula::UniversalQuantifier(variable, inner)
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            let (variable, inner) = rename_quantifier(variable, inner, scope, choose);
+            Formula::ExistentialQuantifier(variable, inner)
+        }
+    }
+}
+
+fn rename_quantifier<'a>(
+    variable: &'a str,
+    inner: &'a Formula,
+    scope: &mut Vec<(&'a str, String)>,
+    choose: &mut impl FnMut(&str) -> String,
+) -> (String, Box<Formula>) {
+    let renamed: String = choose(variable);
+    scope.push((variable, renamed.clone()));
+    let inner: Box<Formula> = Box::new(rename_binders(inner, scope, choose));
+    scope.pop();
+    (renamed, inner)
+}
+
+/// Renames the variables of `term` bound in `scope`.
+fn rename_term(term: &Term, scope: &[(&str, String)]) -> Term {
+    match term {
+        Term::Var(name) => match scope.iter().rev().find(|(from, _)| from == name) {
+            Some((_, to)) => Term::Var(to.clone()),
+            None => term.clone(),
+        },
+        Term::IntLit(_) | Term::Const(_) => term.clone(),
+        Term::App(name, args) => Term::App(
+            name.clone(),
+            args.iter().map(|arg| rename_term(arg, scope)).collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taken(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rename_bound() {
+        let test_formula: Formula = Formula::new("∧ P(x) ∀ x → Q(x) R(x,y)");
+        assert_eq!(
+            test_formula.rename_bound("x", "z"),
+            Ok(Formula::new("∧ P(x) ∀ z → Q(z) R(z,y)"))
+        );
+        assert_eq!(
+            test_formula.rename_bound("x", "x"),
+            Ok(test_formula.clone())
+        );
+    }
+
+    #[test]
+    fn test_rename_bound_nested_same_name_binders() {
+        let test_formula: Formula = Formula::new("∀ x ∧ P(x) ∃ x Q(x)");
+        assert_eq!(
+            test_formula.rename_bound("x", "y"),
+            Ok(Formula::new("∀ y ∧ P(y) ∃ x Q(x)"))
+        );
+        assert_eq!(
+            Formula::new("∧ ∀ x P(x) ∀ x Q(x)").rename_bound("x", "y"),
+            Ok(Formula::new("∧ ∀ y P(y) ∀ x Q(x)"))
+        );
+    }
+
+    #[test]
+    fn test_rename_bound_collision() {
+        let capture = |name: &str| {
+            Err(RenameError::Capture {
+                name: name.to_string(),
+            })
+        };
+        assert_eq!(
+            Formula::new("∀ x < x y").rename_bound("x", "y"),
+            capture("y")
+        );
+        assert_eq!(
+            Formula::new("∀ x ∃ y < x y").rename_bound("x", "y"),
+            capture("y")
+        );
+        assert_eq!(
+            Formula::new("∧ P(y) ∀ x Q(x)").rename_bound("x", "y"),
+            Ok(Formula::new("∧ P(y) ∀ y Q(y)"))
+        );
+    }
+
+    #[test]
+    fn test_rename_bound_errors() {
+        let test_formula: Formula = Formula::new("∀ x P(x)");
+        assert_eq!(
+            test_formula.rename_bound("y", "z"),
+            Err(RenameError::NotBound {
+                variable: "y".to_string()
+            })
+        );
+        for name in ["", "a b", "f(x)", "1", "∧", "forall"] {
+            assert_eq!(
+                test_formula.rename_bound("x", name),
+                Err(RenameError::InvalidName {
+                    name: name.to_string()
+                })
+            );
+        }
+        assert_eq!(
+            test_formula
+                .rename_bound("x", "a b")
+                .unwrap_err()
+                .to_string(),
+            "\"a b\" is not a variable name"
+        );
+    }
+
+    #[test]
+    fn test_freshen_bound() {
+        let test_formula: Formula = Formula::new("∧ ∀ x ∃ y R(x,y) ∀ x ∀ x P(x,z)");
+        let fresh: Formula = test_formula.freshen_bound(&taken(&["y"]));
+        assert_eq!(
+            fresh,
+            Formula::new("∧ ∀ x ∃ y_1 R(x,y_1) ∀ x_1 ∀ x_2 P(x_2,z)")
+        );
+        assert!(fresh.duplicate_bindings().is_empty());
+        assert_eq!(fresh.free_variables(), test_formula.free_variables());
+        assert_eq!(Formula::new(fresh.to_prefix_notation()), fresh);
+    }
+
+    #[test]
+    fn test_freshen_bound_avoids_free_and_existing_names() {
+        let test_formula: Formula = Formula::new("∧ < x x_1 ∀ x P(x)");
+        assert_eq!(
+            test_formula.freshen_bound(&BTreeSet::new()),
+            Formula::new("∧ < x x_1 ∀ x_2 P(x_2)")
+        );
+        let closed: Formula = Formula::new("∀ n ∃ m = n m+m");
+        assert_eq!(closed.freshen_bound(&BTreeSet::new()), closed);
+        assert_eq!(
+            closed.freshen_bound(&taken(&["n", "m", "n_1"])),
+            Formula::new("∀ n_2 ∃ m_1 = n_2 m_1+m_1")
+        );
+    }
+
+    fn mapping(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rename_swap() {
+        let test_formula: Formula = Formula::new("∧ < a b → P(a) = b a+1");
+        let swap: BTreeMap<String, String> = mapping(&[("a", "b"), ("b", "a")]);
+        assert_eq!(
+            test_formula.rename(&swap),
+            Ok(Formula::new("∧ < b a → P(b) = a b+1"))
+        );
+        let sequential: Formula = test_formula
+            .rename(&mapping(&[("a", "c")]))
+            .and_then(|renamed| renamed.rename(&mapping(&[("b", "a")])))
+            .and_then(|renamed| renamed.rename(&mapping(&[("c", "b")])))
+            .unwrap();
+        assert_eq!(test_formula.rename(&swap), Ok(sequential));
+        assert_eq!(
+            test_formula.rename(&mapping(&[("a", "b")])),
+            Err(RenameError::Collision {
+                name: "b".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_rename_keeps_bound_occurrences() {
+        let test_formula: Formula = Formula::new("∧ P(i,r) ∀ i < i r");
+        let renamed: Result<Formula, RenameError> =
+            test_formula.rename(&mapping(&[("i", "j"), ("r", "s")]));
+        assert_eq!(renamed, Ok(Formula::new("∧ P(j,s) ∀ i < i s")));
+        assert_eq!(
+            test_formula.rename(&mapping(&[("r", "i")])),
+            Err(RenameError::Capture {
+                name: "i".to_string()
+            })
+        );
+        assert_eq!(
+            test_formula.rename(&mapping(&[("r", "1")])),
+            Err(RenameError::InvalidName {
+                name: "1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_rename_binders() {
+        let test_formula: Formula = Formula::new("∧ P(i,r) ∀ i ∃ k < i+k r");
+        let swap: BTreeMap<String, String> = mapping(&[("i", "r"), ("r", "i")]);
+        assert_eq!(
+            test_formula.rename_with(&swap, true),
+            Ok(Formula::new("∧ P(r,i) ∀ r ∃ k < r+k i"))
+        );
+        assert_eq!(
+            test_formula.rename_with(&mapping(&[("i", "k")]), true),
+            Err(RenameError::Collision {
+                name: "k".to_string()
+            })
+        );
+        assert_eq!(
+            test_formula.rename_with(&mapping(&[("k", "n")]), false),
+            Ok(test_formula.clone())
+        );
+    }
+}
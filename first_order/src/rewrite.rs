@@ -0,0 +1,376 @@
+//! # Rewriting
+//!
+//! This module provides [`RewriteRule`], a pair of [`Pattern`]s such as `?x+0 => ?x`, and the
+//! rewriting behind [`Formula::rewrite`], which uses such rules to simplify terms like `x+y*0` or
+//! `index+1-1` that the proofs otherwise have to carry around.
+//!
+//! A rule applies to a subformula, or to a subterm if both of its sides are terms, that matches
+//! its left-hand side, and replaces it with the instantiated right-hand side. Rules are applied
+//! bottom-up, the first applicable rule winning at each node, in passes over the whole formula
+//! until a pass changes nothing or the step budget is spent.
+//!
+//! Rules are read from text with [`parse_rules`], one `lhs => rhs` per line, where blank lines and
+//! lines starting with `#` are skipped.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::normal_form::map_operands;
+use crate::{Bindings, Formula, ParseError, Pattern, Term};
+
+/// A rule replacing instances of `lhs` with the corresponding instances of `rhs`.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, RewriteRule};
+///
+/// let rule: RewriteRule = "?x*0 => 0".parse().unwrap();
+/// assert_eq!(
+///     Formula::new("= x x+y*0").rewrite(&[rule], 10),
+///     Formula::new("= x x+0")
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewriteRule {
+    lhs: Pattern, // The pattern to replace.
+    rhs: Pattern, // The replacement, using only metavariables of `lhs`.
+}
+
+/// An error returned when creating a [`RewriteRule`] or reading rules with [`parse_rules`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleError {
+    /// A line of the rules has no `=>`.
+    MissingArrow { line: Option<usize> },
+    /// A side of a rule is not a well-formed pattern. The `line` of the error is set when reading
+    /// rules with [`parse_rules`].
+    Malformed { error: ParseError },
+    /// The right-hand side uses a metavariable that the left-hand side does not bind.
+    UnboundMetavariable {
+        line: Option<usize>,
+        metavariable: String,
+    },
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::MissingArrow { line } => {
+                write_line(f, *line)?;
+                write!(f, "expected a rule of the form lhs => rhs")
+            }
+            RuleError::Malformed { error } => write!(f, "{error}"),
+            RuleError::UnboundMetavariable { line, metavariable } => {
+                write_line(f, *line)?;
+                write!(
+                    f,
+                    "the metavariable {metavariable} is not bound by the left-hand side"
+                )
+            }
+        }
+    }
+}
+
+fn write_line(f: &mut fmt::Formatter<'_>, line: Option<usize>) -> fmt::Result {
+    match line {
+        Some(line) => write!(f, "line {line}: "),
+        None => Ok(()),
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl RewriteRule {
+    /// Creates a new `RewriteRule`.
+    ///
+    /// # Arguments
+    /// * `lhs` - The `Pattern` to replace.
+    /// * `rhs` - The `Pattern` replacing it.
+    ///
+    /// # Returns
+    /// A `Result` containing the `RewriteRule`, or a `RuleError` if `rhs` has a metavariable that
+    /// `lhs` does not.
+    pub fn new(lhs: Pattern, rhs: Pattern) -> Result<Self, RuleError> {
+        let bound: BTreeSet<String> = lhs.metavariables();
+        if let Some(metavariable) = rhs.metavariables().difference(&bound).next() {
+            return Err(RuleError::UnboundMetavariable {
+                line: None,
+                metavariable: metavariable.clone(),
+            });
+        }
+        Ok(RewriteRule { lhs, rhs })
+    }
+    /// Returns the pattern the rule replaces.
+    pub fn lhs(&self) -> &Pattern {
+        &self.lhs
+    }
+    /// Returns the pattern the rule replaces its left-hand side with.
+    pub fn rhs(&self) -> &Pattern {
+        &self.rhs
+    }
+    /// Applies the rule to `formula` itself, not to its subformulae.
+    fn apply(&self, formula: &Formula) -> Option<Formula> {
+        let bindings: Bindings = self.lhs.matches(formula)?;
+        self.rhs.instantiate(&bindings).ok()
+    }
+}
+
+impl fmt::Display for RewriteRule {
+    /// Formats the rule as `lhs => rhs`, in prefix notation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.lhs, self.rhs)
+    }
+}
+
+impl std::str::FromStr for RewriteRule {
+    type Err = RuleError;
+
+    /// Parses a rule of the form `lhs => rhs`, each side being a pattern in prefix notation.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (lhs, rhs): (&str, &str) = input
+            .split_once("=>")
+            .ok_or(RuleError::MissingArrow { line: None })?;
+        let pattern = |side: &str| {
+            Pattern::try_new(side.trim()).map_err(|error| RuleError::Malformed { error })
+        };
+        RewriteRule::new(pattern(lhs)?, pattern(rhs)?)
+    }
+}
+
+/// Parses rewrite rules, one `lhs => rhs` per line.
+///
+/// Lines that are empty or whose first non-whitespace character is `#` are skipped, like in
+/// [`parse_document`](crate::parse_document). Line numbers start at 1.
+///
+/// # Arguments
+/// * `input` - The rules, e.g. the contents of a file read with `std::fs::read_to_string`.
+///
+/// # Returns
+/// A `Result` containing the rules in order, or the first `RuleError`, whose line is set.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, RewriteRule, parse_rules};
+///
+/// let rules: Vec<RewriteRule> = parse_rules("# identities\n?x*0 => 0\n?x+0 => ?x\n").unwrap();
+/// assert_eq!(
+///     Formula::new("= x x+y*0").rewrite(&rules, 10),
+///     Formula::new("= x x")
+/// );
+/// assert_eq!(
+///     parse_rules("?x+0 => ?x\n?x*0 =>").unwrap_err().to_string(),
+///     "line 2: expected a formula at token 0, found end of input"
+/// );
+/// ```
+pub fn parse_rules(input: &str) -> Result<Vec<RewriteRule>, RuleError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.parse().map_err(|error| match error {
+                RuleError::MissingArrow { .. } => RuleError::MissingArrow { line: Some(number) },
+                RuleError::Malformed { error } => RuleError::Malformed {
+                    error: ParseError {
+                        line: Some(number),
+                        ..error
+                    },
+                },
+                RuleError::UnboundMetavariable { metavariable, .. } => {
+                    RuleError::UnboundMetavariable {
+                        line: Some(number),
+                        metavariable,
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// Rewrites `formula` with `rules`, applying at most `max_steps` rules. See
+/// [`Formula::rewrite`].
+pub(crate) fn rewrite(formula: &Formula, rules: &[RewriteRule], max_steps: usize) -> Formula {
+    let mut rewriter: Rewriter<'_> = Rewriter {
+        rules,
+        steps: max_steps,
+    };
+    let mut current: Formula = formula.clone();
+    while rewriter.steps > 0 {
+        let next: Formula = rewriter.formula(&current);
+        if next == current {
+            break;
+        }
+        current = next;
+    }
+    current
+}
+
+/// Applies rules in one bottom-up pass.
+struct Rewriter<'a> {
+    rules: &'a [RewriteRule],
+    steps: usize, // The number of rule applications left.
+}
+
+impl Rewriter<'_> {
+    fn formula(&mut self, formula: &Formula) -> Formula {
+        let rebuilt: Formula = match formula {
+            Formula::Term(term) => Formula::Term(self.arguments(term)),
+            Formula::Equality(lhs, rhs) => Formula::Equality(self.term(lhs), self.term(rhs)),
+            Formula::Less(lhs, rhs) => Formula::Less(self.term(lhs), self.term(rhs)),
+            Formula::Predicate(name, args) => Formula::Predicate(
+                name.clone(),
+                args.iter().map(|arg| self.term(arg)).collect(),
+            ),
+            _ => map_operands(formula, |operand| self.formula(operand)),
+        };
+        self.apply(rebuilt, |_| true)
+    }
+
+    fn term(&mut self, term: &Term) -> Term {
+        let rebuilt: Formula = Formula::Term(self.arguments(term));
+        let result: Formula = self.apply(rebuilt, |result| matches!(result, Formula::Term(_)));
+        match &result {
+            Formula::Term(term) => term.clone(),
+            _ => unreachable!("only terms replace a term"),
+        }
+    }
+
+    /// Rewrites the arguments of `term`, but not `term` itself.
+    fn arguments(&mut self, term: &Term) -> Term {
+        match term {
+            Term::App(name, arguments) => Term::App(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| self.term(argument))
+                    .collect(),
+            ),
+            _ => term.clone(),
+        }
+    }
+
+    /// Replaces `formula` with the result of the first rule that applies to it and whose result
+    /// is accepted, if any steps are left.
+    fn apply(&mut self, formula: Formula, accept: impl Fn(&Formula) -> bool) -> Formula {
+        if self.steps == 0 {
+            return formula;
+        }
+        let replacement: Option<Formula> = self
+            .rules
+            .iter()
+            .filter_map(|rule| rule.apply(&formula))
+            .find(|result| accept(result));
+        match replacement {
+            Some(result) => {
+                self.steps -= 1;
+                result
+            }
+            None => formula,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    const IDENTITIES: &str = "\
+# Arithmetic identities
+?x*0 => 0
+0*?x => 0
+?x+0 => ?x
+?x+1-1 => ?x
+?a+?b-?a => ?b
+(?x) => ?x
+";
+
+    #[test]
+    fn test_fibonacci_postcondition_becomes_the_next_precondition() {
+        let rules: Vec<RewriteRule> = parse_rules(IDENTITIES).unwrap();
+        // `= preFib fib(index+1-1)` is the postcondition of `preFib ≔ curFib-preFib`, and the
+        // assignment before it needs `= preFib fib(index)`.
+        assert_eq!(
+            Formula::new(corpus::FIBONACCI[3]).rewrite(&rules, 100),
+            Formula::new(corpus::FIBONACCI[2])
+        );
+        assert_eq!(
+            Formula::new(corpus::FIBONACCI[0]).rewrite(&rules, 100),
+            Formula::new(
+                "∧ ∧ ∧ ∧ = preFib+curFib fib(index+1) = curFib fib(index) < 0 index+1 \
+                 ∨ < index+1 N = index+1 N = N input"
+            )
+        );
+        let lemma: Formula = Formula::new(corpus::FIBONACCI[6]).rewrite(&rules, 100);
+        let (lhs, rhs): (&Formula, &Formula) = lemma.as_implication().unwrap();
+        assert_eq!(lhs, rhs);
+    }
+
+    #[test]
+    fn test_paper_example() {
+        let rules: Vec<RewriteRule> = parse_rules(IDENTITIES).unwrap();
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[1]).rewrite(&rules, 100),
+            Formula::new("= x x")
+        );
+        assert_eq!(
+            Formula::new("= x (r+y*0)").rewrite(&rules, 100),
+            Formula::new("= x r")
+        );
+    }
+
+    #[test]
+    fn test_step_budget() {
+        let rules: Vec<RewriteRule> = parse_rules(IDENTITIES).unwrap();
+        let test_formula: Formula = Formula::new("= x x+y*0");
+        assert_eq!(test_formula.rewrite(&rules, 0), test_formula);
+        assert_eq!(test_formula.rewrite(&rules, 1), Formula::new("= x x+0"));
+        // A rule that never stops applying is cut off by the budget.
+        let rules: Vec<RewriteRule> = vec!["?x => ?x+0".parse().unwrap()];
+        assert_eq!(
+            Formula::new("= a 1").rewrite(&rules, 2),
+            Formula::new("= a+0 1+0")
+        );
+    }
+
+    #[test]
+    fn test_formula_rules() {
+        let rules: Vec<RewriteRule> = parse_rules("¬ ¬ ?A => ?A\n∧ ?A ?A => ?A").unwrap();
+        assert_eq!(
+            Formula::new("→ ¬ ¬ ∧ P P ¬ ¬ ¬ Q").rewrite(&rules, 10),
+            Formula::new("→ P ¬ Q")
+        );
+        // A rule producing a formula is not applied inside a term.
+        let rules: Vec<RewriteRule> = vec!["?x+1 => ∧ P ?x".parse().unwrap()];
+        assert_eq!(
+            Formula::new("= fib(n+1) 1").rewrite(&rules, 10),
+            Formula::new("= fib(n+1) 1")
+        );
+        assert_eq!(
+            Formula::new("∨ n+1 Q").rewrite(&rules, 10),
+            Formula::new("∨ ∧ P n Q")
+        );
+    }
+
+    #[test]
+    fn test_parse_rules_errors() {
+        assert_eq!(
+            parse_rules("?x+0 => ?x\n\n?x+0 ?x"),
+            Err(RuleError::MissingArrow { line: Some(3) })
+        );
+        let error: RuleError = parse_rules("?x*0 => ?y").unwrap_err();
+        assert_eq!(
+            error,
+            RuleError::UnboundMetavariable {
+                line: Some(1),
+                metavariable: "?y".to_string()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 1: the metavariable ?y is not bound by the left-hand side"
+        );
+        let rule: RewriteRule = "?a+?b-?a  =>  ?b".parse().unwrap();
+        assert_eq!(rule.to_string(), "?a+?b-?a => ?b");
+        assert_eq!(rule.lhs(), &Pattern::new("?a+?b-?a"));
+    }
+}
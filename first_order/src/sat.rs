@@ -0,0 +1,433 @@
+//! # Satisfiability
+//!
+//! This module provides [`solve`], a DPLL solver for sets of clauses, which also backs
+//! [`Formula::is_satisfiable`] and [`Formula::is_tautology`]. Unlike a truth table, whose size
+//! doubles with every atom, the solver handles side conditions with dozens of atoms.
+//!
+//! A clause is a disjunction of literals, and a literal `(true, name)` stands for the variable
+//! `name` while `(false, name)` stands for its negation. The solver repeatedly assigns the literal
+//! of a unit clause, assigns every pure literal, whose variable occurs with only one polarity, and
+//! otherwise splits on a variable of a shortest clause, trying `true` first.
+//!
+//! A formula is converted to clauses with the Tseitin encoding, which introduces a fresh variable
+//! for each connective so that the clauses grow linearly with the formula. The atoms are the same
+//! opaque propositions as in [`Formula::truth_table`], so a formula with a quantifier or a
+//! `Custom` connective is rejected.
+use std::collections::HashMap;
+
+use crate::truth_table::{self, Proposition};
+use crate::{EvalError, Formula};
+
+/// The result of [`solve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Satisfiability {
+    /// The clauses are satisfiable, holding an assignment to every variable that satisfies them.
+    Sat(HashMap<String, bool>),
+    /// The clauses are unsatisfiable.
+    Unsat,
+}
+
+/// A literal, made of its polarity and the index of its variable.
+type Literal = (bool, usize);
+
+type Clause = Vec<Literal>;
+
+/// Decides whether the conjunction of `clauses` is satisfiable.
+///
+/// # Arguments
+/// * `clauses` - The clauses, each being the disjunction of its literals. A literal is `true`
+///   paired with a variable, or `false` paired with a negated variable.
+///
+/// # Returns
+/// `Satisfiability::Sat` with a satisfying assignment to every variable of `clauses`, or
+/// `Satisfiability::Unsat` if there is none. An empty clause is unsatisfiable, and an empty set of
+/// clauses is satisfied by the empty assignment.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use first_order::sat::{Satisfiability, solve};
+///
+/// let clauses: Vec<Vec<(bool, String)>> = vec![
+///     vec![(true, "P".to_string()), (true, "Q".to_string())],
+///     vec![(false, "P".to_string())],
+/// ];
+/// assert_eq!(
+///     solve(&clauses),
+///     Satisfiability::Sat(HashMap::from([("P".to_string(), false), ("Q".to_string(), true)]))
+/// );
+///
+/// let clauses: Vec<Vec<(bool, String)>> = vec![
+///     vec![(true, "P".to_string())],
+///     vec![(false, "P".to_string())],
+/// ];
+/// assert_eq!(solve(&clauses), Satisfiability::Unsat);
+/// ```
+pub fn solve(clauses: &[Vec<(bool, String)>]) -> Satisfiability {
+    let mut names: Vec<&str> = Vec::new();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    let indexed: Vec<Clause> = clauses
+        .iter()
+        .map(|clause| {
+            clause
+                .iter()
+                .map(|(polarity, name)| {
+                    let index: usize = *indices.entry(name).or_insert_with(|| {
+                        names.push(name);
+                        names.len() - 1
+                    });
+                    (*polarity, index)
+                })
+                .collect()
+        })
+        .collect();
+    match dpll(indexed, names.len()) {
+        Some(assignment) => Satisfiability::Sat(
+            names
+                .into_iter()
+                .map(str::to_string)
+                .zip(assignment)
+                .collect(),
+        ),
+        None => Satisfiability::Unsat,
+    }
+}
+
+/// Decides whether the quantifier-free `formula` holds under some assignment to its atoms. See
+/// [`Formula::is_satisfiable`].
+pub(crate) fn is_satisfiable(formula: &Formula) -> Result<bool, EvalError> {
+    let mut atoms: Vec<String> = Vec::new();
+    let proposition: Proposition = truth_table::compile(formula, &mut atoms)?;
+    let mut encoder: Encoder = Encoder {
+        clauses: Vec::new(),
+        variables: atoms.len(),
+    };
+    let root: Literal = encoder.encode(&proposition);
+    encoder.clauses.push(vec![root]);
+    Ok(dpll(encoder.clauses, encoder.variables).is_some())
+}
+
+/// Converts a proposition into clauses with the Tseitin encoding.
+struct Encoder {
+    clauses: Vec<Clause>, // The clauses defining the fresh variables.
+    variables: usize,     // The number of variables, the atoms coming first.
+}
+
+impl Encoder {
+    /// Returns a fresh variable.
+    fn fresh(&mut self) -> usize {
+        self.variables += 1;
+        self.variables - 1
+    }
+
+    /// Returns a literal that is equivalent to `proposition` under the clauses added.
+    fn encode(&mut self, proposition: &Proposition) -> Literal {
+        match proposition {
+            Proposition::Atom(index) => (true, *index),
+            Proposition::Constant(value) => {
+                let variable: usize = self.fresh();
+                self.clauses.push(vec![(*value, variable)]);
+                (true, variable)
+            }
+            Proposition::Not(inner) => {
+                let (polarity, variable): Literal = self.encode(inner);
+                (!polarity, variable)
+            }
+            Proposition::And(lhs, rhs) => {
+                let (lhs, rhs): (Literal, Literal) = (self.encode(lhs), self.encode(rhs));
+                self.conjunction(lhs, rhs)
+            }
+            Proposition::Or(lhs, rhs) => {
+                let (lhs, rhs): (Literal, Literal) = (self.encode(lhs), self.encode(rhs));
+                negate(self.conjunction(negate(lhs), negate(rhs)))
+            }
+            Proposition::Implies(lhs, rhs) => {
+                let (lhs, rhs): (Literal, Literal) = (self.encode(lhs), self.encode(rhs));
+                negate(self.conjunction(lhs, negate(rhs)))
+            }
+            Proposition::Iff(lhs, rhs) => {
+                let (lhs, rhs): (Literal, Literal) = (self.encode(lhs), self.encode(rhs));
+                let variable: usize = self.fresh();
+                self.clauses.extend([
+                    vec![(false, variable), negate(lhs), rhs],
+                    vec![(false, variable), lhs, negate(rhs)],
+                    vec![(true, variable), lhs, rhs],
+                    vec![(true, variable), negate(lhs), negate(rhs)],
+                ]);
+                (true, variable)
+            }
+        }
+    }
+
+    /// Returns a fresh variable that is equivalent to the conjunction of `lhs` and `rhs`.
+    fn conjunction(&mut self, lhs: Literal, rhs: Literal) -> Literal {
+        let variable: usize = self.fresh();
+        self.clauses.extend([
+            vec![(false, variable), lhs],
+            vec![(false, variable), rhs],
+            vec![(true, variable), negate(lhs), negate(rhs)],
+        ]);
+        (true, variable)
+    }
+}
+
+fn negate((polarity, variable): Literal) -> Literal {
+    (!polarity, variable)
+}
+
+/// Searches for an assignment to the `variables` variables that satisfies `clauses`.
+///
+/// The search is depth-first with an explicit stack of partial assignments, each paired with the
+/// clauses it leaves undecided.
+fn dpll(clauses: Vec<Clause>, variables: usize) -> Option<Vec<bool>> {
+    let mut stack: Vec<(Vec<Clause>, Vec<Option<bool>>)> = vec![(clauses, vec![None; variables])];
+    while let Some((mut clauses, mut assignment)) = stack.pop() {
+        if !propagate(&mut clauses, &mut assignment) {
+            continue;
+        }
+        let Some(clause) = clauses.iter().min_by_key(|clause| clause.len()) else {
+            // Unconstrained variables may take any value.
+            return Some(
+                assignment
+                    .into_iter()
+                    .map(|value| value.unwrap_or(false))
+                    .collect(),
+            );
+        };
+        let variable: usize = clause[0].1;
+        for value in [false, true] {
+            let mut branch: Vec<Clause> = clauses.clone();
+            let mut branch_assignment: Vec<Option<bool>> = assignment.clone();
+            assign(&mut branch, &mut branch_assignment, (value, variable));
+            stack.push((branch, branch_assignment));
+        }
+    }
+    None
+}
+
+/// Assigns the literals of unit clauses and the pure literals until neither is left.
+///
+/// # Returns
+/// `false` if a clause became empty, so that the assignment cannot be extended to a model.
+fn propagate(clauses: &mut Vec<Clause>, assignment: &mut [Option<bool>]) -> bool {
+    loop {
+        if clauses.iter().any(Vec::is_empty) {
+            return false;
+        }
+        if let Some(clause) = clauses.iter().find(|clause| clause.len() == 1) {
+            let literal: Literal = clause[0];
+            assign(clauses, assignment, literal);
+            continue;
+        }
+        // The polarities each variable occurs with, as (positive, negative).
+        let mut polarities: HashMap<usize, (bool, bool)> = HashMap::new();
+        for (polarity, variable) in clauses.iter().flatten() {
+            let entry: &mut (bool, bool) = polarities.entry(*variable).or_default();
+            if *polarity {
+                entry.0 = true;
+            } else {
+                entry.1 = true;
+            }
+        }
+        let pure: Vec<Literal> = polarities
+            .into_iter()
+            .filter(|(_, (positive, negative))| positive != negative)
+            .map(|(variable, (positive, _))| (positive, variable))
+            .collect();
+        if pure.is_empty() {
+            return true;
+        }
+        for literal in pure {
+            assign(clauses, assignment, literal);
+        }
+    }
+}
+
+/// Makes `literal` true, removing the clauses it satisfies and its negation from the others.
+fn assign(clauses: &mut Vec<Clause>, assignment: &mut [Option<bool>], literal: Literal) {
+    let (polarity, variable): Literal = literal;
+    assignment[variable] = Some(polarity);
+    clauses.retain(|clause| !clause.contains(&literal));
+    for clause in clauses.iter_mut() {
+        clause.retain(|(_, other)| *other != variable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    fn clause(literals: &[(bool, &str)]) -> Vec<(bool, String)> {
+        literals
+            .iter()
+            .map(|(polarity, name)| (*polarity, name.to_string()))
+            .collect()
+    }
+
+    /// Returns whether `model` makes some literal of every clause true.
+    fn satisfies(model: &HashMap<String, bool>, clauses: &[Vec<(bool, String)>]) -> bool {
+        clauses.iter().all(|clause| {
+            clause
+                .iter()
+                .any(|(polarity, name)| model[name] == *polarity)
+        })
+    }
+
+    /// The clauses stating that `pigeons` pigeons sit in `holes` holes, at most one per hole.
+    fn pigeonhole(pigeons: usize, holes: usize) -> Vec<Vec<(bool, String)>> {
+        let name = |pigeon: usize, hole: usize| format!("p{pigeon}h{hole}");
+        let mut clauses: Vec<Vec<(bool, String)>> = (0..pigeons)
+            .map(|pigeon| (0..holes).map(|hole| (true, name(pigeon, hole))).collect())
+            .collect();
+        for hole in 0..holes {
+            for first in 0..pigeons {
+                for second in first + 1..pigeons {
+                    clauses.push(vec![
+                        (false, name(first, hole)),
+                        (false, name(second, hole)),
+                    ]);
+                }
+            }
+        }
+        clauses
+    }
+
+    #[test]
+    fn test_trivial_instances() {
+        assert_eq!(solve(&[]), Satisfiability::Sat(HashMap::new()));
+        assert_eq!(solve(&[vec![]]), Satisfiability::Unsat);
+        assert_eq!(
+            solve(&[clause(&[(true, "P"), (false, "P")])]),
+            Satisfiability::Sat(HashMap::from([("P".to_string(), true)]))
+        );
+    }
+
+    #[test]
+    fn test_pigeonhole() {
+        for holes in 1..=4 {
+            assert_eq!(
+                solve(&pigeonhole(holes + 1, holes)),
+                Satisfiability::Unsat,
+                "{holes}"
+            );
+            let clauses: Vec<Vec<(bool, String)>> = pigeonhole(holes, holes);
+            match solve(&clauses) {
+                Satisfiability::Sat(model) => assert!(satisfies(&model, &clauses)),
+                Satisfiability::Unsat => panic!("{holes} pigeons fit in {holes} holes"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_model_is_verified() {
+        let clauses: Vec<Vec<(bool, String)>> = vec![
+            clause(&[(true, "A"), (true, "B"), (false, "C")]),
+            clause(&[(false, "A"), (true, "C")]),
+            clause(&[(false, "B"), (false, "C")]),
+            clause(&[(true, "B"), (true, "C"), (true, "D")]),
+            clause(&[(false, "D"), (false, "A")]),
+        ];
+        let Satisfiability::Sat(model) = solve(&clauses) else {
+            panic!("the clauses are satisfiable");
+        };
+        assert_eq!(model.len(), 4);
+        assert!(satisfies(&model, &clauses));
+    }
+
+    #[test]
+    fn test_fifty_variables() {
+        // A planted 3-SAT instance: every clause agrees with `planted` on some literal.
+        let planted: Vec<bool> = (0..50).map(|index| index % 3 != 0).collect();
+        let mut seed: u64 = 2024;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % bound) as usize
+        };
+        let mut clauses: Vec<Vec<(bool, String)>> = Vec::new();
+        while clauses.len() < 215 {
+            let literals: Vec<(bool, usize)> = (0..3).map(|_| (next(2) == 0, next(50))).collect();
+            if literals
+                .iter()
+                .any(|(polarity, index)| planted[*index] == *polarity)
+            {
+                clauses.push(
+                    literals
+                        .into_iter()
+                        .map(|(polarity, index)| (polarity, format!("x{index}")))
+                        .collect(),
+                );
+            }
+        }
+        let Satisfiability::Sat(model) = solve(&clauses) else {
+            panic!("the planted assignment satisfies the clauses");
+        };
+        assert!(satisfies(&model, &clauses));
+        // A chain of implications x0 → x1 → … → x49 contradicting ¬x49 needs propagation only.
+        let mut chain: Vec<Vec<(bool, String)>> = (0..49)
+            .map(|index| {
+                vec![
+                    (false, format!("x{index}")),
+                    (true, format!("x{}", index + 1)),
+                ]
+            })
+            .collect();
+        chain.extend([clause(&[(true, "x0")]), clause(&[(false, "x49")])]);
+        assert_eq!(solve(&chain), Satisfiability::Unsat);
+    }
+
+    #[test]
+    fn test_formulae() {
+        let cases: [(&str, bool, bool); 8] = [
+            ("→ ∧ P Q P", true, true),
+            ("→ P Q", true, false),
+            ("∧ P ¬ P", false, false),
+            ("= P ¬ ¬ P", true, true),
+            ("∨ ⊤ P", true, true),
+            ("∧ ⊥ P", false, false),
+            ("= ∧ P Q ∧ Q P", true, true),
+            ("→ < y r ∨ < y r = y r", true, true),
+        ];
+        for (input, satisfiable, tautology) in cases {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(test_formula.is_satisfiable(), Ok(satisfiable), "{input}");
+            assert_eq!(test_formula.is_tautology(), Ok(tautology), "{input}");
+        }
+        assert!(Formula::new("∃ x P(x)").is_satisfiable().is_err());
+    }
+
+    #[test]
+    fn test_agrees_with_truth_tables() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            if let Ok(table) = test_formula.truth_table() {
+                assert_eq!(
+                    test_formula.is_tautology(),
+                    Ok(table.is_tautology()),
+                    "{input}"
+                );
+                assert_eq!(
+                    test_formula.is_satisfiable(),
+                    Ok(!table.is_contradiction()),
+                    "{input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_many_atoms() {
+        // A side condition with 40 atoms, far beyond a truth table.
+        let atoms: Vec<String> = (0..40).map(|index| format!("< x{index} y")).collect();
+        let conjunction: String = atoms[1..]
+            .iter()
+            .fold(atoms[0].clone(), |acc, atom| format!("∧ {acc} {atom}"));
+        let lemma: Formula = Formula::new(format!("→ {conjunction} {}", atoms[39]));
+        assert!(lemma.truth_table().is_err());
+        assert_eq!(lemma.is_tautology(), Ok(true));
+        let lemma: Formula = Formula::new(format!("→ {conjunction} < y x0"));
+        assert_eq!(lemma.is_tautology(), Ok(false));
+    }
+}
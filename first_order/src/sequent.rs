@@ -0,0 +1,413 @@
+//! # Sequent calculus
+//!
+//! This module provides [`prove`], which decides whether a quantifier-free formula is a classical
+//! tautology and, if it is, returns a readable [`SequentProof`]. Unlike [`Formula::is_tautology`],
+//! it also explains a failure, by returning a sequent of atoms that no rule can close.
+//!
+//! A sequent `Γ ⊢ Δ` states that the conjunction of the antecedent `Γ` implies the disjunction of
+//! the succedent `Δ`. The search starts from `⊢ φ` and works backwards with the rules of the
+//! classical calculus G3c, each of which replaces a sequent by one or two simpler sequents that
+//! are valid exactly when it is:
+//!
+//! | Formula   | On the left (`φ ⊢`)              | On the right (`⊢ φ`)             |
+//! |-----------|----------------------------------|----------------------------------|
+//! | `¬ φ`     | `⊢ φ`                            | `φ ⊢`                            |
+//! | `∧ φ ψ`   | `φ, ψ ⊢`                         | `⊢ φ` and `⊢ ψ`                  |
+//! | `∨ φ ψ`   | `φ ⊢` and `ψ ⊢`                  | `⊢ φ, ψ`                         |
+//! | `→ φ ψ`   | `⊢ φ` and `ψ ⊢`                  | `φ ⊢ ψ`                          |
+//! | `↔ φ ψ`   | `φ, ψ ⊢` and `⊢ φ, ψ`            | `φ ⊢ ψ` and `ψ ⊢ φ`              |
+//! | `⊤`, `⊥`  | `⊤` is dropped                   | `⊥` is dropped                   |
+//!
+//! A sequent is an axiom if the same formula occurs on both sides, if `⊥` is on the left or if
+//! `⊤` is on the right. Since every rule is invertible, the order in which the rules are applied
+//! does not matter, and a sequent of atoms that is not an axiom shows that the goal is not
+//! provable: making its antecedent true and its succedent false falsifies the goal.
+//!
+//! The atoms are those of [`Formula::truth_table`], and `=` between two formulae is read as `↔`.
+//! The calculus is classical, so Peirce's law `→ → → P Q P P` and the excluded middle are
+//! provable. A formula with a quantifier or a `Custom` connective is not supported.
+use std::fmt;
+
+use crate::{Formula, notation};
+
+/// A sequent `Γ ⊢ Δ`, whose antecedent and succedent are lists of formulae.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use first_order::sequent::Sequent;
+///
+/// let sequent = Sequent {
+///     antecedent: vec![Formula::new("P"), Formula::new("→ P Q")],
+///     succedent: vec![Formula::new("Q")],
+/// };
+/// assert_eq!(sequent.to_string(), "P, → P Q ⊢ Q");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequent {
+    /// The formulae assumed, on the left of `⊢`.
+    pub antecedent: Vec<Formula>,
+    /// The formulae of which one is concluded, on the right of `⊢`.
+    pub succedent: Vec<Formula>,
+}
+
+impl fmt::Display for Sequent {
+    /// Formats the sequent with each side in prefix notation, e.g. `P, → P Q ⊢ Q`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, formula) in self.antecedent.iter().enumerate() {
+            let separator: &str = if index == 0 { "" } else { ", " };
+            write!(f, "{separator}{formula:#}")?;
+        }
+        f.write_str(if self.antecedent.is_empty() {
+            "⊢"
+        } else {
+            " ⊢"
+        })?;
+        for (index, formula) in self.succedent.iter().enumerate() {
+            let separator: &str = if index == 0 { " " } else { ", " };
+            write!(f, "{separator}{formula:#}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A proof of a sequent: the rule applied to it and the proofs of the sequents it reduces to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequentProof {
+    /// The sequent proved.
+    pub sequent: Sequent,
+    /// The name of the rule applied, such as `"→R"`, or `"axiom"` for a leaf.
+    pub rule: String,
+    /// The proofs of the sequents the rule reduces `sequent` to.
+    pub premises: Vec<SequentProof>,
+}
+
+impl fmt::Display for SequentProof {
+    /// Formats the proof as an indented tree, one sequent per line followed by its rule, with
+    /// the premises of each sequent indented below it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stack: Vec<(&SequentProof, usize)> = vec![(self, 0)];
+        while let Some((proof, depth)) = stack.pop() {
+            writeln!(
+                f,
+                "{:indent$}{}  ({})",
+                "",
+                proof.sequent,
+                proof.rule,
+                indent = 2 * depth
+            )?;
+            stack.extend(
+                proof
+                    .premises
+                    .iter()
+                    .rev()
+                    .map(|premise| (premise, depth + 1)),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The reason [`prove`] returns no proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotProvable {
+    /// The search reached `leaf`, a sequent of atoms that is not an axiom. Making its antecedent
+    /// true and its succedent false falsifies the goal.
+    OpenLeaf { leaf: Sequent },
+    /// `formula` is a quantifier, a `Custom` connective, or a comparison between formulae, which
+    /// the propositional calculus does not handle.
+    Unsupported { formula: Formula },
+}
+
+impl fmt::Display for NotProvable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotProvable::OpenLeaf { leaf } => write!(f, "the sequent {leaf} is not provable"),
+            NotProvable::Unsupported { formula } => {
+                write!(f, "{formula:#} is not a propositional formula")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotProvable {}
+
+/// Proves `goal` in the classical sequent calculus.
+///
+/// # Arguments
+/// * `goal` - The quantifier-free formula to prove.
+///
+/// # Returns
+/// A `Result` containing the `SequentProof` of `⊢ goal`, or `NotProvable` with an open leaf if
+/// `goal` is not a tautology, or with the first unsupported subformula.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use first_order::sequent::prove;
+///
+/// let proof = prove(&Formula::new("∨ P ¬ P")).unwrap();
+/// assert_eq!(proof.to_string(), "⊢ ∨ P ¬ P  (∨R)\n  ⊢ P, ¬ P  (¬R)\n    P ⊢ P  (axiom)\n");
+///
+/// let error = prove(&Formula::new("→ P Q")).unwrap_err();
+/// assert_eq!(error.to_string(), "the sequent P ⊢ Q is not provable");
+/// ```
+pub fn prove(goal: &Formula) -> Result<SequentProof, NotProvable> {
+    if let Some(formula) = goal.subformulae().find(|formula| !supported(formula)) {
+        return Err(NotProvable::Unsupported {
+            formula: formula.clone(),
+        });
+    }
+    search(Sequent {
+        antecedent: Vec::new(),
+        succedent: vec![goal.clone()],
+    })
+}
+
+/// Returns whether the outermost node of `formula` is handled by the calculus.
+fn supported(formula: &Formula) -> bool {
+    match formula {
+        Formula::UniversalQuantifier(_, _)
+        | Formula::ExistentialQuantifier(_, _)
+        | Formula::Custom(_, _, _) => false,
+        Formula::NotEqual(lhs, rhs)
+        | Formula::LessThan(lhs, rhs)
+        | Formula::LessEqual(lhs, rhs)
+        | Formula::GreaterEqual(lhs, rhs)
+        | Formula::GreaterThan(lhs, rhs) => {
+            matches!((&**lhs, &**rhs), (Formula::Term(_), Formula::Term(_)))
+        }
+        _ => true,
+    }
+}
+
+/// Returns whether `formula` is an atom, which no rule decomposes.
+fn is_atom(formula: &Formula) -> bool {
+    matches!(formula, Formula::Term(_)) || formula.as_atom().is_some()
+}
+
+/// Searches for a proof of `sequent`, reducing its first compound formula, left side first.
+fn search(sequent: Sequent) -> Result<SequentProof, NotProvable> {
+    if let Some(rule) = axiom(&sequent) {
+        return Ok(SequentProof {
+            sequent,
+            rule: rule.to_string(),
+            premises: Vec::new(),
+        });
+    }
+    let left: Option<usize> = sequent
+        .antecedent
+        .iter()
+        .position(|formula| !is_atom(formula));
+    let (rule, reduced): (String, Vec<Sequent>) = match left {
+        Some(index) => reduce_left(&sequent, index),
+        None => match sequent
+            .succedent
+            .iter()
+            .position(|formula| !is_atom(formula))
+        {
+            Some(index) => reduce_right(&sequent, index),
+            None => return Err(NotProvable::OpenLeaf { leaf: sequent }),
+        },
+    };
+    let premises: Vec<SequentProof> = reduced.into_iter().map(search).collect::<Result<_, _>>()?;
+    Ok(SequentProof {
+        sequent,
+        rule,
+        premises,
+    })
+}
+
+/// Returns the name of the axiom `sequent` is an instance of, if any.
+fn axiom(sequent: &Sequent) -> Option<&'static str> {
+    if sequent.antecedent.contains(&Formula::Bottom) {
+        Some("⊥L")
+    } else if sequent.succedent.contains(&Formula::Top) {
+        Some("⊤R")
+    } else if sequent
+        .antecedent
+        .iter()
+        .any(|formula| sequent.succedent.contains(formula))
+    {
+        Some("axiom")
+    } else {
+        None
+    }
+}
+
+/// Returns the name of the rule for `formula` on `side`, e.g. `→L`.
+fn rule(formula: &Formula, side: char) -> String {
+    let symbol: &str = match formula {
+        Formula::Top => "⊤",
+        Formula::Bottom => "⊥",
+        Formula::Negation(_) => "¬",
+        _ => notation::connective(formula),
+    };
+    format!("{symbol}{side}")
+}
+
+/// Returns `sequent` with its antecedent formula at `index` replaced by `left` and `right` added
+/// to its succedent.
+fn replace_left(sequent: &Sequent, index: usize, left: &[&Formula], right: &[&Formula]) -> Sequent {
+    let mut antecedent: Vec<Formula> = sequent.antecedent.clone();
+    antecedent.splice(index..=index, left.iter().map(|formula| (*formula).clone()));
+    let mut succedent: Vec<Formula> = sequent.succedent.clone();
+    succedent.extend(right.iter().map(|formula| (*formula).clone()));
+    Sequent {
+        antecedent,
+        succedent,
+    }
+}
+
+/// Returns `sequent` with its succedent formula at `index` replaced by `right` and `left` added
+/// to its antecedent.
+fn replace_right(
+    sequent: &Sequent,
+    index: usize,
+    left: &[&Formula],
+    right: &[&Formula],
+) -> Sequent {
+    let mut antecedent: Vec<Formula> = sequent.antecedent.clone();
+    antecedent.extend(left.iter().map(|formula| (*formula).clone()));
+    let mut succedent: Vec<Formula> = sequent.succedent.clone();
+    succedent.splice(
+        index..=index,
+        right.iter().map(|formula| (*formula).clone()),
+    );
+    Sequent {
+        antecedent,
+        succedent,
+    }
+}
+
+/// Applies the left rule of the antecedent formula at `index` of `sequent`.
+fn reduce_left(sequent: &Sequent, index: usize) -> (String, Vec<Sequent>) {
+    let formula: &Formula = &sequent.antecedent[index];
+    let reduced: Vec<Sequent> = match formula {
+        Formula::Top => vec![replace_left(sequent, index, &[], &[])],
+        Formula::Negation(inner) => vec![replace_left(sequent, index, &[], &[inner])],
+        Formula::Conjunction(lhs, rhs) => vec![replace_left(sequent, index, &[lhs, rhs], &[])],
+        Formula::Disjunction(lhs, rhs) => vec![
+            replace_left(sequent, index, &[lhs], &[]),
+            replace_left(sequent, index, &[rhs], &[]),
+        ],
+        Formula::Implication(lhs, rhs) => vec![
+            replace_left(sequent, index, &[], &[lhs]),
+            replace_left(sequent, index, &[rhs], &[]),
+        ],
+        Formula::Equivalence(lhs, rhs) | Formula::Iff(lhs, rhs) => vec![
+            replace_left(sequent, index, &[lhs, rhs], &[]),
+            replace_left(sequent, index, &[], &[lhs, rhs]),
+        ],
+        _ => unreachable!("{formula:#} is an atom"),
+    };
+    (rule(formula, 'L'), reduced)
+}
+
+/// Applies the right rule of the succedent formula at `index` of `sequent`.
+fn reduce_right(sequent: &Sequent, index: usize) -> (String, Vec<Sequent>) {
+    let formula: &Formula = &sequent.succedent[index];
+    let reduced: Vec<Sequent> = match formula {
+        Formula::Bottom => vec![replace_right(sequent, index, &[], &[])],
+        Formula::Negation(inner) => vec![replace_right(sequent, index, &[inner], &[])],
+        Formula::Conjunction(lhs, rhs) => vec![
+            replace_right(sequent, index, &[], &[lhs]),
+            replace_right(sequent, index, &[], &[rhs]),
+        ],
+        Formula::Disjunction(lhs, rhs) => vec![replace_right(sequent, index, &[], &[lhs, rhs])],
+        Formula::Implication(lhs, rhs) => vec![replace_right(sequent, index, &[lhs], &[rhs])],
+        Formula::Equivalence(lhs, rhs) | Formula::Iff(lhs, rhs) => vec![
+            replace_right(sequent, index, &[lhs], &[rhs]),
+            replace_right(sequent, index, &[rhs], &[lhs]),
+        ],
+        _ => unreachable!("{formula:#} is an atom"),
+    };
+    (rule(formula, 'R'), reduced)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::r#gen::{GenConfig, random_formula};
+
+    #[test]
+    fn test_excluded_middle() {
+        let proof: SequentProof = prove(&Formula::new("∨ P ¬ P")).unwrap();
+        assert_eq!(proof.rule, "∨R");
+        assert_eq!(
+            proof.to_string(),
+            "⊢ ∨ P ¬ P  (∨R)\n  ⊢ P, ¬ P  (¬R)\n    P ⊢ P  (axiom)\n"
+        );
+    }
+
+    #[test]
+    fn test_peirce_law_is_classical() {
+        let proof: SequentProof = prove(&Formula::new("→ → → P Q P P")).unwrap();
+        assert_eq!(
+            proof.to_string(),
+            "\
+⊢ → → → P Q P P  (→R)
+  → → P Q P ⊢ P  (→L)
+    ⊢ P, → P Q  (→R)
+      P ⊢ P, Q  (axiom)
+    P ⊢ P  (axiom)
+"
+        );
+    }
+
+    #[test]
+    fn test_open_leaf() {
+        assert_eq!(
+            prove(&Formula::new("→ P Q")),
+            Err(NotProvable::OpenLeaf {
+                leaf: Sequent {
+                    antecedent: vec![Formula::new("P")],
+                    succedent: vec![Formula::new("Q")]
+                }
+            })
+        );
+        let error: NotProvable = prove(&Formula::new("→ ∨ P Q ∧ P Q")).unwrap_err();
+        assert_eq!(error.to_string(), "the sequent P ⊢ Q is not provable");
+    }
+
+    #[test]
+    fn test_relations_and_biconditionals() {
+        let lemma: Formula = Formula::new("→ ∧ = x r+y*q ¬ < y r = x r+y*q");
+        let proof: SequentProof = prove(&lemma).unwrap();
+        assert_eq!(proof.premises[0].rule, "∧L");
+        assert!(prove(&Formula::new("↔ ¬ ¬ P P")).is_ok());
+        assert!(prove(&Formula::new("= ∧ P Q ∧ Q P")).is_ok());
+        assert!(prove(&Formula::new("∨ ⊤ ⊥")).is_ok());
+        assert!(prove(&Formula::new("→ ⊥ P")).is_ok());
+        assert!(prove(&Formula::new("= x y")).is_err());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            prove(&Formula::new("→ P ∀ x Q(x)")),
+            Err(NotProvable::Unsupported {
+                formula: Formula::new("∀ x Q(x)")
+            })
+        );
+        assert!(matches!(
+            prove(&Formula::new("< ¬ P Q")),
+            Err(NotProvable::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_agrees_with_truth_tables() {
+        for seed in 0..500 {
+            let mut rng: StdRng = StdRng::seed_from_u64(seed);
+            let formula: Formula = random_formula(&mut rng, &GenConfig::new());
+            let Ok(table) = formula.truth_table() else {
+                continue;
+            };
+            assert_eq!(prove(&formula).is_ok(), table.is_tautology(), "{formula:#}");
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! # Serialization
+//!
+//! This module implements `Serialize` for `Formula` when the `serde` feature is enabled.
+//!
+//! The implementation produces the same externally tagged representation as
+//! `#[derive(Serialize)]`, which is what `Deserialize` is derived to read, but grows the stack
+//! as it descends so that deeply nested formulae can be written.
+use serde::ser::{Serialize, SerializeTupleVariant, Serializer};
+
+use crate::Formula;
+
+/// The stack space left at which a new stack segment is allocated.
+const RED_ZONE: usize = 64 * 1024;
+/// The size of each newly allocated stack segment.
+const STACK_SIZE: usize = 2 * 1024 * 1024;
+
+impl Serialize for Formula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        stacker::maybe_grow(RED_ZONE, STACK_SIZE, || match self {
+            Formula::Term(term) => serializer.serialize_newtype_variant("Formula", 0, "Term", term),
+            Formula::Top => serializer.serialize_unit_variant("Formula", 1, "Top"),
+            Formula::Bottom => serializer.serialize_unit_variant("Formula", 2, "Bottom"),
+            Formula::Negation(inner) => {
+                serializer.serialize_newtype_variant("Formula", 3, "Negation", inner)
+            }
+            Formula::Conjunction(lhs, rhs) => binary(serializer, 4, "Conjunction", lhs, rhs),
+            Formula::Disjunction(lhs, rhs) => binary(serializer, 5, "Disjunction", lhs, rhs),
+            Formula::Implication(lhs, rhs) => binary(serializer, 6, "Implication", lhs, rhs),
+            Formula::Equivalence(lhs, rhs) => binary(serializer, 7, "Equivalence", lhs, rhs),
+            Formula::Iff(lhs, rhs) => binary(serializer, 8, "Iff", lhs, rhs),
+            Formula::NotEqual(lhs, rhs) => binary(serializer, 9, "NotEqual", lhs, rhs),
+            Formula::LessThan(lhs, rhs) => binary(serializer, 10, "LessThan", lhs, rhs),
+            Formula::LessEqual(lhs, rhs) => binary(serializer, 11, "LessEqual", lhs, rhs),
+            Formula::GreaterEqual(lhs, rhs) => binary(serializer, 12, "GreaterEqual", lhs, rhs),
+            Formula::GreaterThan(lhs, rhs) => binary(serializer, 13, "GreaterThan", lhs, rhs),
+            Formula::UniversalQuantifier(variable, inner) => {
+                binary(serializer, 14, "UniversalQuantifier", variable, inner)
+            }
+            Formula::ExistentialQuantifier(variable, inner) => {
+                binary(serializer, 15, "ExistentialQuantifier", variable, inner)
+            }
+            Formula::Custom(symbol, lhs, rhs) => {
+                let mut state = serializer.serialize_tuple_variant("Formula", 16, "Custom", 3)?;
+                state.serialize_field(symbol)?;
+                state.serialize_field(lhs)?;
+                state.serialize_field(rhs)?;
+                state.end()
+            }
+            Formula::Equality(lhs, rhs) => binary(serializer, 17, "Equality", lhs, rhs),
+            Formula::Less(lhs, rhs) => binary(serializer, 18, "Less", lhs, rhs),
+            Formula::Predicate(name, args) => binary(serializer, 19, "Predicate", name, args),
+        })
+    }
+}
+
+/// Serializes a variant with two fields as a tuple variant.
+fn binary<S: Serializer, L: Serialize, R: Serialize>(
+    serializer: S,
+    index: u32,
+    variant: &'static str,
+    lhs: &L,
+    rhs: &R,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_tuple_variant("Formula", index, variant, 2)?;
+    state.serialize_field(lhs)?;
+    state.serialize_field(rhs)?;
+    state.end()
+}
@@ -0,0 +1,101 @@
+//! Round-trip tests of the `serde` feature through JSON.
+use crate::{Formula, Term, corpus};
+use serde::Deserialize;
+
+/// Serializes `formula` to JSON and deserializes it back.
+fn round_trip(formula: &Formula) -> Formula {
+    let json: String = serde_json::to_string(formula).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn test_externally_tagged_representation() {
+    let test_formula: Formula = Formula::new("∧ ⊤ ¬ P");
+    assert_eq!(
+        serde_json::to_string(&test_formula).unwrap(),
+        r#"{"Conjunction":["Top",{"Negation":{"Term":{"Var":"P"}}}]}"#
+    );
+    assert_eq!(
+        serde_json::to_string(&Formula::new("∀ x = f(x) 1")).unwrap(),
+        r#"{"UniversalQuantifier":["x",{"Equality":[{"App":["f",[{"Var":"x"}]]},{"IntLit":1}]}]}"#
+    );
+    assert_eq!(
+        serde_json::to_string(&Formula::new("P(x,0)")).unwrap(),
+        r#"{"Predicate":["P",[{"Var":"x"},{"IntLit":0}]]}"#
+    );
+}
+
+#[test]
+fn test_deserialize_builds_the_tree() {
+    let json: &str = r#"{"ExistentialQuantifier":["y",{"UniversalQuantifier":["x",{"LessThan":[{"Term":{"Var":"x"}},{"Term":{"Var":"y"}}]}]}]}"#;
+    let test_formula: Formula = serde_json::from_str(json).unwrap();
+    assert_eq!(test_formula.to_prefix_notation(), "∃ y ∀ x < x y");
+    // The comparison is read in the shape it was written in, not as the `Less` the parser builds.
+    assert!(matches!(
+        test_formula
+            .as_quantifier()
+            .and_then(|(_, body)| body.as_quantifier()),
+        Some(("x", Formula::LessThan(_, _)))
+    ));
+    let json: &str = r#"{"ExistentialQuantifier":["y",{"UniversalQuantifier":["x",{"Less":[{"Var":"x"},{"Var":"y"}]}]}]}"#;
+    let test_formula: Formula = serde_json::from_str(json).unwrap();
+    assert_eq!(test_formula, Formula::new("∃ y ∀ x < x y"));
+}
+
+#[test]
+fn test_round_trip_examples() {
+    for input in corpus::all() {
+        let formula: Formula = Formula::new(input);
+        assert_eq!(round_trip(&formula), formula, "{input}");
+    }
+}
+
+#[test]
+fn test_round_trip_every_variant() {
+    let term = |s: &str| Box::new(Formula::Term(Term::new(s)));
+    let formulae: Vec<Formula> = vec![
+        Formula::Top,
+        Formula::Bottom,
+        Formula::Negation(term("P")),
+        Formula::Conjunction(term("P"), term("Q")),
+        Formula::Disjunction(term("P"), term("Q")),
+        Formula::Implication(term("P"), term("Q")),
+        Formula::Equivalence(term("x"), term("(r-y)+y*(1+q)")),
+        Formula::Iff(term("P"), term("Q")),
+        Formula::NotEqual(term("b"), term("0")),
+        Formula::LessThan(term("0"), term("index")),
+        Formula::LessEqual(term("index"), term("N")),
+        Formula::GreaterEqual(term("gcd(a,mod(a,b))"), term("-1")),
+        Formula::GreaterThan(term("r*10^(p)"), term("0")),
+        Formula::UniversalQuantifier("x".to_string(), term("P(x)")),
+        Formula::ExistentialQuantifier("y".to_string(), term("P(y)")),
+        Formula::Custom("⊆".to_string(), term("A"), term("B")),
+        Formula::Equality(Term::new("x"), Term::new("(r-y)+y*(1+q)")),
+        Formula::Less(Term::new("0"), Term::new("index")),
+        Formula::Predicate("P".to_string(), vec![Term::new("x"), Term::new("f(y)")]),
+        Formula::Predicate("P".to_string(), Vec::new()),
+    ];
+    for formula in formulae {
+        assert_eq!(round_trip(&formula), formula);
+    }
+}
+
+#[test]
+fn test_deeply_nested_formula() {
+    let mut formula: Formula = Formula::new("P(x)");
+    for level in 0..1000 {
+        formula = match level % 3 {
+            0 => Formula::Negation(Box::new(formula)),
+            1 => Formula::UniversalQuantifier("x".to_string(), Box::new(formula)),
+            _ => Formula::Conjunction(Box::new(formula), Box::new(Formula::Top)),
+        };
+    }
+
+    let json: String = serde_json::to_string(&formula).unwrap();
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    deserializer.disable_recursion_limit();
+    let result: Formula =
+        Formula::deserialize(serde_stacker::Deserializer::new(&mut deserializer)).unwrap();
+    assert_eq!(result, formula);
+}
@@ -0,0 +1,483 @@
+//! # S-expressions
+//!
+//! This module reads and writes formulae as s-expressions, e.g.
+//! `(and (forall x (implies (P x) (Q x))) (not r))`, for interoperability with Lisp-based tools.
+//!
+//! Every variant of `Formula` is written as a list headed by a fixed keyword:
+//!
+//! | Formula | S-expression      |
+//! |---------|-------------------|
+//! | `⊤`     | `true`            |
+//! | `⊥`     | `false`           |
+//! | `¬ φ`   | `(not φ)`         |
+//! | `∧ φ ψ` | `(and φ ψ)`       |
+//! | `∨ φ ψ` | `(or φ ψ)`        |
+//! | `→ φ ψ` | `(implies φ ψ)`   |
+//! | `= φ ψ` | `(= φ ψ)`         |
+//! | `↔ φ ψ` | `(iff φ ψ)`       |
+//! | `≠ φ ψ` | `(!= φ ψ)`        |
+//! | `< φ ψ` | `(< φ ψ)`         |
+//! | `≤ φ ψ` | `(<= φ ψ)`        |
+//! | `≥ φ ψ` | `(>= φ ψ)`        |
+//! | `> φ ψ` | `(> φ ψ)`         |
+//! | `∀ x φ` | `(forall x φ)`    |
+//! | `∃ x φ` | `(exists x φ)`    |
+//! | `⊆ φ ψ` | `(custom ⊆ φ ψ)`  |
+//!
+//! A function application such as `P(x)` is written as the list `(P x)`. Any other term is
+//! written as an atom holding its usual spelling, e.g. `x+y*0`. An atom is quoted with `"` when it
+//! contains whitespace, parentheses or quotes, or when it is spelled like a keyword. A quoted atom
+//! that is not a well-formed term is read as a variable named by its text.
+use crate::term::is_built_in;
+use crate::{Formula, ParseError, ParseErrorKind, Term, atom};
+
+/// Atoms with a meaning of their own, which must be quoted to be read as terms.
+const KEYWORDS: [&str; 16] = [
+    "true", "false", "not", "and", "or", "implies", "=", "iff", "!=", "<", "<=", ">=", ">",
+    "forall", "exists", "custom",
+];
+
+/// Writes `formula` as an s-expression. See [`Formula::to_sexpr`].
+pub(crate) fn write(formula: &Formula) -> String {
+    match formula {
+        Formula::Term(term) => write_term(term),
+        Formula::Top => "true".to_string(),
+        Formula::Bottom => "false".to_string(),
+        Formula::Negation(inner) => format!("(not {})", write(inner)),
+        Formula::Conjunction(lhs, rhs) => write_list("and", lhs, rhs),
+        Formula::Disjunction(lhs, rhs) => write_list("or", lhs, rhs),
+        Formula::Implication(lhs, rhs) => write_list("implies", lhs, rhs),
+        Formula::Equivalence(lhs, rhs) => write_list("=", lhs, rhs),
+        Formula::Iff(lhs, rhs) => write_list("iff", lhs, rhs),
+        Formula::NotEqual(lhs, rhs) => write_list("!=", lhs, rhs),
+        Formula::LessThan(lhs, rhs) => write_list("<", lhs, rhs),
+        Formula::LessEqual(lhs, rhs) => write_list("<=", lhs, rhs),
+        Formula::GreaterEqual(lhs, rhs) => write_list(">=", lhs, rhs),
+        Formula::GreaterThan(lhs, rhs) => write_list(">", lhs, rhs),
+        Formula::UniversalQuantifier(var, inner) => {
+            format!("(forall {} {})", write_atom(var), write(inner))
+        }
+        Formula::ExistentialQuantifier(var, inner) => {
+            format!("(exists {} {})", write_atom(var), write(inner))
+        }
+        Formula::Custom(symbol, lhs, rhs) => {
+            format!(
+                "(custom {} {} {})",
+                write_atom(symbol),
+                write(lhs),
+                write(rhs)
+            )
+        }
+        Formula::Equality(lhs, rhs) => format!("(= {} {})", write_term(lhs), write_term(rhs)),
+        Formula::Less(lhs, rhs) => format!("(< {} {})", write_term(lhs), write_term(rhs)),
+        Formula::Predicate(name, args) => write_term(&atom::predicate_term(name, args)),
+    }
+}
+
+fn write_list(keyword: &str, lhs: &Formula, rhs: &Formula) -> String {
+    format!("({keyword} {} {})", write(lhs), write(rhs))
+}
+
+fn write_term(term: &Term) -> String {
+    match term {
+        Term::App(name, args) if !is_built_in(name) => {
+            let mut result: String = format!("({}", write_atom(name));
+            for arg in args {
+                result.push(' ');
+                result.push_str(&write_term(arg));
+            }
+            result.push(')');
+            result
+        }
+        _ => write_atom(&term.to_string()),
+    }
+}
+
+/// Writes `atom`, quoting it if it would not be read back as the same atom.
+fn write_atom(atom: &str) -> String {
+    let needs_quotes: bool = atom.is_empty()
+        || KEYWORDS.contains(&atom)
+        || atom
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '(' | ')' | '"' | '\\'));
+    if !needs_quotes {
+        return atom.to_string();
+    }
+    let mut result: String = String::from('"');
+    for c in atom.chars() {
+        if matches!(c, '"' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result.push('"');
+    result
+}
+
+/// A token of an s-expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Atom { text: String, quoted: bool },
+}
+
+impl Token {
+    /// Returns the token as written, for error reporting.
+    fn spelling(&self) -> String {
+        match self {
+            Token::Open => "(".to_string(),
+            Token::Close => ")".to_string(),
+            Token::Atom {
+                text,
+                quoted: false,
+            } => text.clone(),
+            Token::Atom { text, quoted: true } => write_atom(text),
+        }
+    }
+}
+
+/// Parses `input` as an s-expression. See [`Formula::from_sexpr`].
+pub(crate) fn parse(input: &str) -> Result<Formula, ParseError> {
+    let tokens: Vec<Token> = tokenize(input)?;
+    let mut parser: SexprParser<'_> = SexprParser {
+        tokens: &tokens,
+        current: 0,
+    };
+    let formula: Formula = parser.parse_proposition()?;
+    if parser.current != tokens.len() {
+        return Err(ParseError {
+            kind: ParseErrorKind::TrailingTokens,
+            ..parser.error("end of input")
+        });
+    }
+    Ok(formula)
+}
+
+/// Splits `input` into parentheses and atoms.
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut text: String = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek().is_some() => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => {
+                            return Err(ParseError {
+                                kind: ParseErrorKind::Malformed,
+                                index: tokens.len(),
+                                found: None,
+                                expected: "a closing quote".to_string(),
+                                line: None,
+                            });
+                        }
+                    }
+                }
+                tokens.push(Token::Atom { text, quoted: true });
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut text: String = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || matches!(next, '(' | ')' | '"') {
+                        break;
+                    }
+                    text.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Atom {
+                    text,
+                    quoted: false,
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of an s-expression.
+struct SexprParser<'a> {
+    tokens: &'a [Token], // A slice of tokens representing the s-expression.
+    current: usize,      // The current index in the token slice.
+}
+
+impl SexprParser<'_> {
+    /// Builds a `ParseError` located at the current token.
+    fn error(&self, expected: &str) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::Malformed,
+            index: self.current,
+            found: self.tokens.get(self.current).map(Token::spelling),
+            expected: expected.to_string(),
+            line: None,
+        }
+    }
+
+    /// Consumes the closing parenthesis of the current list.
+    fn close(&mut self) -> Result<(), ParseError> {
+        if self.tokens.get(self.current) != Some(&Token::Close) {
+            return Err(self.error("')'"));
+        }
+        self.current += 1;
+        Ok(())
+    }
+
+    /// Consumes an atom, returning its text and whether it was quoted.
+    fn atom(&mut self, expected: &str) -> Result<(String, bool), ParseError> {
+        match self.tokens.get(self.current) {
+            Some(Token::Atom { text, quoted }) => {
+                self.current += 1;
+                Ok((text.clone(), *quoted))
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    /// Parses a formula where a formula is expected, reading a name or an application as a
+    /// predicate.
+    fn parse_proposition(&mut self) -> Result<Formula, ParseError> {
+        self.parse_formula().map(atom::proposition)
+    }
+
+    fn parse_formula(&mut self) -> Result<Formula, ParseError> {
+        match self.tokens.get(self.current) {
+            Some(Token::Open) => {}
+            Some(Token::Atom { text, quoted }) => {
+                let index: usize = self.current;
+                self.current += 1;
+                return match (text.as_str(), quoted) {
+                    ("true", false) => Ok(Formula::Top),
+                    ("false", false) => Ok(Formula::Bottom),
+                    (keyword, false) if KEYWORDS.contains(&keyword) => Err(ParseError {
+                        index,
+                        ..self.error("a formula")
+                    }),
+                    _ => Ok(Formula::Term(term_from_atom(text, *quoted, index)?)),
+                };
+            }
+            _ => return Err(self.error("a formula")),
+        }
+        let open: usize = self.current;
+        self.current += 1;
+        let (head, quoted): (String, bool) = self.atom("a keyword or a function name")?;
+        if quoted || !KEYWORDS.contains(&head.as_str()) {
+            self.current = open;
+            return Ok(Formula::Term(self.parse_term()?));
+        }
+        let formula: Formula = match head.as_str() {
+            "true" | "false" => {
+                self.current -= 1;
+                return Err(self.error("a connective, relation or quantifier"));
+            }
+            "not" => Formula::Negation(Box::new(self.parse_proposition()?)),
+            "forall" | "exists" => {
+                let (var, _) = self.atom(&format!("a variable after {head}"))?;
+                let inner: Box<Formula> = Box::new(self.parse_proposition()?);
+                if head == "forall" {
+                    Formula::UniversalQuantifier(var, inner)
+                } else {
+                    Formula::ExistentialQuantifier(var, inner)
+                }
+            }
+            "custom" => {
+                let (symbol, _) = self.atom("a connective after custom")?;
+                let lhs: Formula = self.parse_formula()?;
+                let rhs: Formula = self.parse_formula()?;
+                Formula::Custom(symbol, Box::new(lhs), Box::new(rhs))
+            }
+            keyword => {
+                let connective: fn(Box<Formula>, Box<Formula>) -> Formula = match keyword {
+                    "and" => Formula::Conjunction,
+                    "or" => Formula::Disjunction,
+                    "implies" => Formula::Implication,
+                    "=" => atom::equality,
+                    "iff" => Formula::Iff,
+                    "!=" => Formula::NotEqual,
+                    "<" => atom::less_than,
+                    "<=" => Formula::LessEqual,
+                    ">=" => Formula::GreaterEqual,
+                    _ => Formula::GreaterThan,
+                };
+                // The operands of a relation are terms, and those of a connective are formulae.
+                let (lhs, rhs): (Formula, Formula) =
+                    if matches!(keyword, "and" | "or" | "implies" | "iff") {
+                        (self.parse_proposition()?, self.parse_proposition()?)
+                    } else {
+                        (self.parse_formula()?, self.parse_formula()?)
+                    };
+                connective(Box::new(lhs), Box::new(rhs))
+            }
+        };
+        self.close()?;
+        Ok(formula)
+    }
+
+    /// Parses a term, which is an atom or a list `(f a b ...)` applying a function to terms.
+    fn parse_term(&mut self) -> Result<Term, ParseError> {
+        match self.tokens.get(self.current) {
+            Some(Token::Atom { text, quoted }) => {
+                self.current += 1;
+                term_from_atom(text, *quoted, self.current - 1)
+            }
+            Some(Token::Open) => {
+                self.current += 1;
+                let (name, _) = self.atom("a function name")?;
+                let mut args: Vec<Term> = Vec::new();
+                while !matches!(self.tokens.get(self.current), Some(Token::Close) | None) {
+                    args.push(self.parse_term()?);
+                }
+                if args.is_empty() {
+                    return Err(self.error("an argument"));
+                }
+                self.close()?;
+                Ok(Term::App(name, args))
+            }
+            _ => Err(self.error("a term")),
+        }
+    }
+}
+
+/// Reads the atom `text`, found at token `index`, as a term.
+fn term_from_atom(text: &str, quoted: bool, index: usize) -> Result<Term, ParseError> {
+    match Term::parse(text) {
+        Ok(term) => Ok(term),
+        Err(_) if quoted => Ok(Term::Var(text.to_string())),
+        Err(error) => Err(ParseError {
+            kind: ParseErrorKind::Malformed,
+            index,
+            found: Some(text.to_string()),
+            expected: format!("a well-formed term ({error})"),
+            line: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Formula, ParseErrorKind, Term, corpus};
+
+    fn term(s: &str) -> Box<Formula> {
+        Box::new(Formula::new(s))
+    }
+
+    /// Builds `s` as the operand of a relation, which stays a term.
+    fn operand(s: &str) -> Box<Formula> {
+        Box::new(Formula::term(s))
+    }
+
+    /// Hand-built formulae covering every variant of `Formula`.
+    fn every_variant() -> Vec<Formula> {
+        vec![
+            Formula::Top,
+            Formula::Bottom,
+            Formula::Negation(term("P")),
+            Formula::Conjunction(term("P(x)"), term("Q(x,y)")),
+            Formula::Disjunction(Box::new(Formula::Top), Box::new(Formula::Bottom)),
+            Formula::Implication(term("P"), Box::new(Formula::Negation(term("Q")))),
+            Formula::Equivalence(term("P(x)"), Box::new(Formula::Top)),
+            Formula::Iff(term("P"), Box::new(Formula::Negation(term("Q")))),
+            Formula::NotEqual(operand("b"), operand("0")),
+            Formula::LessThan(Box::new(Formula::Top), operand("(count-1)")),
+            Formula::LessEqual(operand("index"), operand("N")),
+            Formula::GreaterEqual(operand("fib(index+1)"), operand("-1")),
+            Formula::GreaterThan(operand("gcd(a,mod(a,b))"), operand("r*10^(p)")),
+            Formula::UniversalQuantifier("x".to_string(), term("P(x)")),
+            Formula::ExistentialQuantifier(
+                "y".to_string(),
+                Box::new(Formula::Conjunction(term("R(y)"), term("S(y)"))),
+            ),
+            Formula::Custom("⊆".to_string(), term("A"), term("B")),
+            Formula::Equality(Term::new("x"), Term::new("x+y*0")),
+            Formula::Less(Term::new("0"), Term::new("(count-1)")),
+            Formula::Predicate("Q".to_string(), vec![Term::new("x"), Term::new("f(y)")]),
+        ]
+    }
+
+    #[test]
+    fn test_to_sexpr() {
+        let test_formula: Formula = Formula::new("∧ ∀ x → P(x) Q(x) ¬ r");
+        assert_eq!(
+            test_formula.to_sexpr(),
+            "(and (forall x (implies (P x) (Q x))) (not r))"
+        );
+        assert_eq!(
+            Formula::new("= x (r-y)+y*(1+q)").to_sexpr(),
+            "(= x \"(r-y)+y*(1+q)\")"
+        );
+        assert_eq!(
+            Formula::new("= gcd(a,b) fib(index+1)").to_sexpr(),
+            "(= (gcd a b) (fib index+1))"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_every_variant() {
+        for formula in every_variant() {
+            let sexpr: String = formula.to_sexpr();
+            assert_eq!(Formula::from_sexpr(&sexpr), Ok(formula), "{sexpr}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip_corpus() {
+        for input in corpus::all() {
+            let formula: Formula = Formula::new(input);
+            assert_eq!(Formula::from_sexpr(&formula.to_sexpr()), Ok(formula));
+        }
+    }
+
+    #[test]
+    fn test_from_sexpr_whitespace_and_nesting() {
+        let test_formula =
+            Formula::from_sexpr("  ( and\n\t(forall x\n (implies ( P  x ) (Q x)))   (not r) )  ");
+        assert_eq!(test_formula, Ok(Formula::new("∧ ∀ x → P(x) Q(x) ¬ r")));
+        assert_eq!(
+            Formula::from_sexpr("(= (f (g a) b) c)"),
+            Ok(Formula::new("= f(g(a),b) c"))
+        );
+    }
+
+    #[test]
+    fn test_quoted_atoms() {
+        let test_formula: Formula = Formula::from_sexpr("(not \"the sky is blue\")").unwrap();
+        assert_eq!(
+            test_formula,
+            Formula::Negation(Box::new(Formula::Term(crate::Term::Var(
+                "the sky is blue".to_string()
+            ))))
+        );
+        assert_eq!(test_formula.to_sexpr(), "(not \"the sky is blue\")");
+        assert_eq!(
+            Formula::from_sexpr("(and \"and\" \"say \\\"hi\\\"\")")
+                .unwrap()
+                .to_sexpr(),
+            "(and \"and\" \"say \\\"hi\\\"\")"
+        );
+        assert_eq!(
+            Formula::from_sexpr("(= x \"x + 1\")"),
+            Ok(Formula::new("= x x+1"))
+        );
+    }
+
+    #[test]
+    fn test_from_sexpr_malformed() {
+        let error = Formula::from_sexpr("(and P)").unwrap_err();
+        assert_eq!(error.index, 3);
+        assert_eq!(error.found, Some(")".to_string()));
+        assert!(Formula::from_sexpr("(not P Q)").is_err());
+        assert!(Formula::from_sexpr("(forall (x) P)").is_err());
+        assert!(Formula::from_sexpr("(not \"P)").is_err());
+        assert!(Formula::from_sexpr("and").is_err());
+        assert_eq!(
+            Formula::from_sexpr("P Q").unwrap_err().kind,
+            ParseErrorKind::TrailingTokens
+        );
+    }
+}
@@ -0,0 +1,542 @@
+//! # Shared formulae
+//!
+//! This module provides [`SharedFormula`], a formula whose subformulae are reference counted, so
+//! that cloning one takes constant time and a formula rebuilt from the children of another shares
+//! them instead of copying them. A proof that repeats a large invariant in every triple then holds
+//! a single copy of it.
+//!
+//! `SharedFormula` sits beside [`Formula`], whose variants own their subformulae in `Box`es and are
+//! matched on throughout the workspace. A formula is converted with `SharedFormula::from` and back
+//! with [`SharedFormula::to_formula`], and both conversions, comparing, hashing and dropping do not
+//! recurse.
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::{Formula, FormulaKind, ParseError, Term, kind};
+
+/// A formula whose subformulae are shared through reference counting.
+///
+/// Shared formulae compare, hash and display like the [`Formula`] they stand for. Two formulae
+/// that share a subformula compare it in constant time.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, SharedFormula};
+///
+/// let invariant: SharedFormula = SharedFormula::new("∧ = x r+y*q ≤ 0 r");
+/// let precondition: SharedFormula = invariant.clone().and(SharedFormula::new("≤ y r"));
+/// assert!(precondition.children()[0].ptr_eq(&invariant));
+/// assert_eq!(precondition.to_formula(), Formula::new("∧ ∧ = x r+y*q ≤ 0 r ≤ y r"));
+/// ```
+#[derive(Clone)]
+pub struct SharedFormula {
+    node: Rc<Node>, // The outermost node, which may be shared with other formulae.
+}
+
+/// The outermost node of a [`SharedFormula`].
+#[derive(Debug)]
+enum Node {
+    /// A term, `⊤` or `⊥`, which has no subformulae.
+    Atom(Formula),
+    Negation(SharedFormula),
+    /// Any binary connective apart from a `Custom` one.
+    Binary(FormulaKind, SharedFormula, SharedFormula),
+    Custom(String, SharedFormula, SharedFormula),
+    /// A universal or existential quantifier and its variable.
+    Quantifier(FormulaKind, String, SharedFormula),
+}
+
+impl SharedFormula {
+    fn from_node(node: Node) -> Self {
+        SharedFormula {
+            node: Rc::new(node),
+        }
+    }
+    /// Creates a new `SharedFormula` from a string in prefix notation.
+    ///
+    /// # Arguments
+    /// * `input` - A `String` or `&str` that represents the logical formula in prefix notation,
+    ///   as accepted by [`Formula::new`].
+    ///
+    /// # Panics
+    /// Panics if the input is malformed. Use `str::parse` to handle the error instead.
+    pub fn new<T: Into<String>>(input: T) -> Self {
+        SharedFormula::from(&Formula::new(input))
+    }
+    /// Builds the term `term`.
+    pub fn term(term: impl Into<String>) -> Self {
+        SharedFormula::from_node(Node::Atom(Formula::term(term)))
+    }
+    /// Builds the atomic formula `term` stands for where a formula is expected, as
+    /// [`Formula::proposition`] does.
+    pub fn proposition(term: impl Into<String>) -> Self {
+        SharedFormula::from_node(Node::Atom(Formula::proposition(term)))
+    }
+    /// Builds the conjunction `∧ self rhs`, sharing both operands.
+    pub fn and(self, rhs: SharedFormula) -> Self {
+        SharedFormula::from_node(Node::Binary(FormulaKind::Conjunction, self, rhs))
+    }
+    /// Builds the disjunction `∨ self rhs`, sharing both operands.
+    pub fn or(self, rhs: SharedFormula) -> Self {
+        SharedFormula::from_node(Node::Binary(FormulaKind::Disjunction, self, rhs))
+    }
+    /// Builds the implication `→ self rhs`, sharing both operands.
+    pub fn implies(self, rhs: SharedFormula) -> Self {
+        SharedFormula::from_node(Node::Binary(FormulaKind::Implication, self, rhs))
+    }
+    /// Builds the biconditional `↔ self rhs`, sharing both operands.
+    pub fn iff(self, rhs: SharedFormula) -> Self {
+        SharedFormula::from_node(Node::Binary(FormulaKind::Iff, self, rhs))
+    }
+    /// Builds the equality `= self rhs`, sharing both operands.
+    pub fn equals(self, rhs: SharedFormula) -> Self {
+        SharedFormula::from_node(Node::Binary(FormulaKind::Equivalence, self, rhs))
+    }
+    /// Builds the comparison `< self rhs`, sharing both operands.
+    pub fn lt(self, rhs: SharedFormula) -> Self {
+        SharedFormula::from_node(Node::Binary(FormulaKind::LessThan, self, rhs))
+    }
+    /// Builds the negation `¬ self`, sharing its operand.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        SharedFormula::from_node(Node::Negation(self))
+    }
+    /// Builds the universal quantification `∀ variable self`, sharing its body.
+    pub fn forall(self, variable: impl Into<String>) -> Self {
+        SharedFormula::from_node(Node::Quantifier(
+            FormulaKind::UniversalQuantifier,
+            variable.into(),
+            self,
+        ))
+    }
+    /// Builds the existential quantification `∃ variable self`, sharing its body.
+    pub fn exists(self, variable: impl Into<String>) -> Self {
+        SharedFormula::from_node(Node::Quantifier(
+            FormulaKind::ExistentialQuantifier,
+            variable.into(),
+            self,
+        ))
+    }
+    /// Returns the kind of the outermost node, like [`Formula::kind`].
+    pub fn kind(&self) -> FormulaKind {
+        match &*self.node {
+            Node::Atom(formula) => formula.kind(),
+            Node::Negation(_) => FormulaKind::Negation,
+            Node::Binary(kind, _, _) | Node::Quantifier(kind, _, _) => *kind,
+            Node::Custom(_, _, _) => FormulaKind::Custom,
+        }
+    }
+    /// Returns the direct subformulae from left to right, like [`Formula::children`].
+    pub fn children(&self) -> Vec<&SharedFormula> {
+        match &*self.node {
+            Node::Atom(_) => vec![],
+            Node::Negation(inner) | Node::Quantifier(_, _, inner) => vec![inner],
+            Node::Binary(_, lhs, rhs) | Node::Custom(_, lhs, rhs) => vec![lhs, rhs],
+        }
+    }
+    /// Returns the variable bound by the formula if it is a quantifier, like [`Formula::binder`].
+    pub fn binder(&self) -> Option<&str> {
+        match &*self.node {
+            Node::Quantifier(_, variable, _) => Some(variable),
+            _ => None,
+        }
+    }
+    /// Returns whether both formulae are the same shared node, rather than merely equal.
+    pub fn ptr_eq(&self, other: &SharedFormula) -> bool {
+        Rc::ptr_eq(&self.node, &other.node)
+    }
+    /// Rebuilds the outermost node with every direct subformula replaced by `f` applied to it.
+    ///
+    /// The formula itself is returned, still shared, if `f` returns every subformula unchanged,
+    /// so a rewrite that leaves most of a formula alone keeps most of it shared.
+    ///
+    /// # Arguments
+    /// * `f` - A closure mapping each direct subformula, from left to right, to its replacement.
+    ///
+    /// # Returns
+    /// A `SharedFormula` of the same kind whose subformulae are the results of `f`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::SharedFormula;
+    ///
+    /// let test_formula: SharedFormula = SharedFormula::new("∧ P ∨ Q R");
+    /// let unchanged: SharedFormula = test_formula.map_children(|child| child.clone());
+    /// assert!(unchanged.ptr_eq(&test_formula));
+    ///
+    /// let negated: SharedFormula = test_formula.map_children(|child| child.clone().not());
+    /// assert_eq!(negated, SharedFormula::new("∧ ¬ P ¬ ∨ Q R"));
+    /// ```
+    pub fn map_children(&self, mut f: impl FnMut(&SharedFormula) -> SharedFormula) -> Self {
+        let mut map = |child: &SharedFormula, changed: &mut bool| -> SharedFormula {
+            let mapped: SharedFormula = f(child);
+            *changed |= !mapped.ptr_eq(child);
+            mapped
+        };
+        let mut changed: bool = false;
+        let node: Node = match &*self.node {
+            Node::Atom(_) => return self.clone(),
+            Node::Negation(inner) => Node::Negation(map(inner, &mut changed)),
+            Node::Binary(kind, lhs, rhs) => {
+                Node::Binary(*kind, map(lhs, &mut changed), map(rhs, &mut changed))
+            }
+            Node::Custom(symbol, lhs, rhs) => Node::Custom(
+                symbol.clone(),
+                map(lhs, &mut changed),
+                map(rhs, &mut changed),
+            ),
+            Node::Quantifier(kind, variable, body) => {
+                Node::Quantifier(*kind, variable.clone(), map(body, &mut changed))
+            }
+        };
+        if changed {
+            SharedFormula::from_node(node)
+        } else {
+            self.clone()
+        }
+    }
+    /// Converts the formula into a [`Formula`], copying every shared subformula.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Formula, SharedFormula};
+    ///
+    /// let test_formula: Formula = Formula::new("→ ∀ x P(x) ¬ Q");
+    /// assert_eq!(SharedFormula::from(&test_formula).to_formula(), test_formula);
+    /// ```
+    pub fn to_formula(&self) -> Formula {
+        let mut pending: Vec<(&SharedFormula, bool)> = vec![(self, false)];
+        let mut built: Vec<Formula> = Vec::new();
+        while let Some((current, expanded)) = pending.pop() {
+            if !expanded && !matches!(*current.node, Node::Atom(_)) {
+                pending.push((current, true));
+                pending.extend(
+                    current
+                        .children()
+                        .into_iter()
+                        .rev()
+                        .map(|child| (child, false)),
+                );
+                continue;
+            }
+            let formula: Formula = match &*current.node {
+                Node::Atom(formula) => formula.clone(),
+                Node::Negation(_) => Formula::Negation(Box::new(built.pop().unwrap())),
+                Node::Quantifier(kind, variable, _) => {
+                    let body: Box<Formula> = Box::new(built.pop().unwrap());
+                    if *kind == FormulaKind::UniversalQuantifier {
+                        Formula::UniversalQuantifier(variable.clone(), body)
+                    } else {
+                        Formula::ExistentialQuantifier(variable.clone(), body)
+                    }
+                }
+                Node::Binary(kind, _, _) => {
+                    let rhs: Box<Formula> = Box::new(built.pop().unwrap());
+                    let lhs: Box<Formula> = Box::new(built.pop().unwrap());
+                    kind::binary(*kind, lhs, rhs).unwrap()
+                }
+                Node::Custom(symbol, _, _) => {
+                    let rhs: Box<Formula> = Box::new(built.pop().unwrap());
+                    let lhs: Box<Formula> = Box::new(built.pop().unwrap());
+                    Formula::Custom(symbol.clone(), lhs, rhs)
+                }
+            };
+            built.push(formula);
+        }
+        built.pop().unwrap()
+    }
+    /// Converts the formula into prefix notation, like [`Formula::to_prefix_notation`].
+    pub fn to_prefix_notation(&self) -> String {
+        self.to_formula().to_prefix_notation()
+    }
+}
+
+impl From<&Formula> for SharedFormula {
+    /// Converts a [`Formula`] into a `SharedFormula` with a node for each of its nodes.
+    fn from(formula: &Formula) -> Self {
+        let mut pending: Vec<(&Formula, bool)> = vec![(formula, false)];
+        let mut built: Vec<SharedFormula> = Vec::new();
+        while let Some((current, expanded)) = pending.pop() {
+            if !expanded && !current.children().is_empty() {
+                pending.push((current, true));
+                pending.extend(
+                    current
+                        .children()
+                        .into_iter()
+                        .rev()
+                        .map(|child| (child, false)),
+                );
+                continue;
+            }
+            let node: Node = match current {
+                Formula::Term(_)
+                | Formula::Top
+                | Formula::Bottom
+                | Formula::Equality(_, _)
+                | Formula::Less(_, _)
+                | Formula::Predicate(_, _) => Node::Atom(current.clone()),
+                Formula::Negation(_) => Node::Negation(built.pop().unwrap()),
+                Formula::UniversalQuantifier(variable, _)
+                | Formula::ExistentialQuantifier(variable, _) => {
+                    Node::Quantifier(current.kind(), variable.clone(), built.pop().unwrap())
+                }
+                Formula::Custom(symbol, _, _) => {
+                    let rhs: SharedFormula = built.pop().unwrap();
+                    let lhs: SharedFormula = built.pop().unwrap();
+                    Node::Custom(symbol.clone(), lhs, rhs)
+                }
+                _ => {
+                    let rhs: SharedFormula = built.pop().unwrap();
+                    let lhs: SharedFormula = built.pop().unwrap();
+                    Node::Binary(current.kind(), lhs, rhs)
+                }
+            };
+            built.push(SharedFormula::from_node(node));
+        }
+        built.pop().unwrap()
+    }
+}
+
+impl From<Formula> for SharedFormula {
+    fn from(formula: Formula) -> Self {
+        SharedFormula::from(&formula)
+    }
+}
+
+impl From<&SharedFormula> for Formula {
+    fn from(formula: &SharedFormula) -> Self {
+        formula.to_formula()
+    }
+}
+
+impl From<Term> for SharedFormula {
+    fn from(term: Term) -> Self {
+        SharedFormula::from_node(Node::Atom(Formula::Term(term)))
+    }
+}
+
+impl std::str::FromStr for SharedFormula {
+    type Err = ParseError;
+
+    /// Parses a `SharedFormula` from a string in prefix notation without panicking, like
+    /// [`Formula::try_new`].
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Formula::try_new(input).map(SharedFormula::from)
+    }
+}
+
+impl PartialEq for SharedFormula {
+    /// Compares the formulae structurally, skipping every subformula the two share.
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending: Vec<(&SharedFormula, &SharedFormula)> = vec![(self, other)];
+        while let Some((lhs, rhs)) = pending.pop() {
+            if lhs.ptr_eq(rhs) {
+                continue;
+            }
+            match (&*lhs.node, &*rhs.node) {
+                (Node::Atom(a), Node::Atom(b)) if a == b => {}
+                (Node::Negation(a), Node::Negation(b)) => pending.push((a, b)),
+                (Node::Binary(k1, a1, b1), Node::Binary(k2, a2, b2)) if k1 == k2 => {
+                    pending.extend([(a1, a2), (b1, b2)]);
+                }
+                (Node::Custom(s1, a1, b1), Node::Custom(s2, a2, b2)) if s1 == s2 => {
+                    pending.extend([(a1, a2), (b1, b2)]);
+                }
+                (Node::Quantifier(k1, v1, a), Node::Quantifier(k2, v2, b))
+                    if k1 == k2 && v1 == v2 =>
+                {
+                    pending.push((a, b));
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Eq for SharedFormula {}
+
+impl Hash for SharedFormula {
+    /// Hashes the kind and contents of every node in prefix order.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut pending: Vec<&SharedFormula> = vec![self];
+        while let Some(current) = pending.pop() {
+            current.kind().hash(state);
+            match &*current.node {
+                Node::Atom(formula) => formula.hash(state),
+                Node::Custom(symbol, _, _) => symbol.hash(state),
+                Node::Quantifier(_, variable, _) => variable.hash(state),
+                Node::Negation(_) | Node::Binary(_, _, _) => {}
+            }
+            pending.extend(current.children().into_iter().rev());
+        }
+    }
+}
+
+impl fmt::Display for SharedFormula {
+    /// Formats the formula exactly like the `Display` of the [`Formula`] it stands for, including
+    /// the alternate flag.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_formula(), f)
+    }
+}
+
+impl fmt::Debug for SharedFormula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedFormula")
+            .field(&self.to_prefix_notation())
+            .finish()
+    }
+}
+
+impl Drop for SharedFormula {
+    /// Drops the formula without recursing, like `Formula`.
+    fn drop(&mut self) {
+        let mut stack: Vec<SharedFormula> = Vec::new();
+        detach(self, &mut stack);
+        while let Some(mut formula) = stack.pop() {
+            detach(&mut formula, &mut stack);
+        }
+    }
+}
+
+/// Moves the subformulae of `formula` onto `stack` if no other formula shares its node, leaving
+/// `⊤` in its place, so that dropping it never recurses more than one level.
+fn detach(formula: &mut SharedFormula, stack: &mut Vec<SharedFormula>) {
+    let Some(node) = Rc::get_mut(&mut formula.node) else {
+        return;
+    };
+    if matches!(node, Node::Atom(_)) {
+        return;
+    }
+    match std::mem::replace(node, Node::Atom(Formula::Top)) {
+        Node::Atom(_) => {}
+        Node::Negation(inner) | Node::Quantifier(_, _, inner) => stack.push(inner),
+        Node::Binary(_, lhs, rhs) | Node::Custom(_, lhs, rhs) => stack.extend([lhs, rhs]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{ParserConfig, corpus};
+
+    #[test]
+    fn test_round_trip() {
+        for input in corpus::all() {
+            let formula: Formula = Formula::new(input);
+            let shared: SharedFormula = SharedFormula::from(&formula);
+            assert_eq!(shared.to_formula(), formula, "{input}");
+            assert_eq!(shared.to_string(), formula.to_string(), "{input}");
+            assert_eq!(format!("{shared:#}"), format!("{formula:#}"), "{input}");
+            assert_eq!(shared.kind(), formula.kind(), "{input}");
+        }
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        let custom: Formula = config.parse("∀ x ⊆ A(x) B").unwrap();
+        assert_eq!(SharedFormula::from(&custom).to_formula(), custom);
+    }
+
+    #[test]
+    fn test_builders_match_formula() {
+        let shared: SharedFormula = SharedFormula::proposition("P(x)")
+            .implies(SharedFormula::proposition("Q(x)").or(SharedFormula::term("R").not()))
+            .forall("x")
+            .and(
+                SharedFormula::term("U")
+                    .lt(SharedFormula::term("V"))
+                    .iff(SharedFormula::term("W")),
+            )
+            .exists("y");
+        assert_eq!(
+            shared.to_formula(),
+            Formula::new("∃ y ∧ ∀ x → P(x) ∨ Q(x) ¬ R ↔ < U V W")
+        );
+        assert_eq!(shared.binder(), Some("y"));
+        assert_eq!(
+            "∧ P Q".parse::<SharedFormula>(),
+            Ok(SharedFormula::new("∧ P Q"))
+        );
+        assert!("∧ P".parse::<SharedFormula>().is_err());
+    }
+
+    #[test]
+    fn test_equality_and_hash() {
+        let mut seen: HashSet<SharedFormula> = HashSet::new();
+        for input in corpus::all() {
+            seen.insert(SharedFormula::new(input));
+        }
+        for input in corpus::all() {
+            assert!(seen.contains(&SharedFormula::new(input)), "{input}");
+        }
+        assert_ne!(SharedFormula::new("∧ P Q"), SharedFormula::new("∨ P Q"));
+        assert_ne!(SharedFormula::new("∀ x P"), SharedFormula::new("∀ y P"));
+        assert_ne!(SharedFormula::new("< x y"), SharedFormula::new("< y x"));
+        let shared: SharedFormula = SharedFormula::new(corpus::FIBONACCI[5]);
+        assert!(shared.clone().ptr_eq(&shared));
+    }
+
+    #[test]
+    fn test_composing_triples_shares_the_invariant() {
+        // Each triple {∧ I B} S {I} and its while rule conclusion {I} while B do S {∧ ¬ B I} are
+        // rebuilt from the shared invariant I.
+        let invariant: SharedFormula = SharedFormula::new(corpus::FIBONACCI[4]);
+        let size: usize = invariant.to_formula().node_count();
+        let mut triples: Vec<(SharedFormula, String, SharedFormula)> = Vec::new();
+        for index in 0..1000 {
+            let condition: SharedFormula = SharedFormula::new(format!("< index N{index}"));
+            let body: (SharedFormula, String, SharedFormula) = (
+                invariant.clone().and(condition.clone()),
+                format!("S{index}"),
+                invariant.clone(),
+            );
+            let conclusion: (SharedFormula, String, SharedFormula) = (
+                body.2.clone(),
+                format!("while < index N{index} do S{index} done"),
+                condition.not().and(body.2.clone()),
+            );
+            triples.push(body);
+            triples.push(conclusion);
+        }
+        assert_eq!(Rc::strong_count(&invariant.node), 1 + 4 * 1000);
+        for child in invariant.children() {
+            assert_eq!(Rc::strong_count(&child.node), 1);
+        }
+        assert!(triples[1999].2.children()[1].ptr_eq(&invariant));
+        assert_eq!(triples[1999].2.to_formula().node_count(), size + 3);
+        drop(triples);
+        assert_eq!(Rc::strong_count(&invariant.node), 1);
+    }
+
+    #[test]
+    fn test_map_children_shares_unchanged_children() {
+        let test_formula: SharedFormula = SharedFormula::new(corpus::PAPER_EXAMPLE[5]);
+        let lhs: SharedFormula = test_formula.children()[0].clone();
+        let mapped: SharedFormula = test_formula.map_children(|child| {
+            if child.ptr_eq(&lhs) {
+                child.clone()
+            } else {
+                SharedFormula::new("⊤")
+            }
+        });
+        assert!(mapped.children()[0].ptr_eq(&lhs));
+        assert_eq!(mapped.kind(), FormulaKind::Implication);
+        assert_eq!(*mapped.children()[1], SharedFormula::new("⊤"));
+    }
+
+    #[test]
+    fn test_deep_formula() {
+        let mut deep: SharedFormula = SharedFormula::term("P");
+        for _ in 0..200_000 {
+            deep = deep.not();
+        }
+        let copy: SharedFormula = SharedFormula::from(&deep.to_formula());
+        assert_eq!(copy, deep);
+        let mut seen: HashSet<SharedFormula> = HashSet::new();
+        seen.insert(copy);
+        assert!(seen.contains(&deep));
+    }
+}
@@ -0,0 +1,591 @@
+//! # Signatures
+//!
+//! This module provides [`Signature`], a registry of the function and relation symbols that a
+//! formula may use together with their arities, so that typos such as `fib(index,1)` instead of
+//! `fib(index+1)` are caught.
+//!
+//! A term written directly as an operand of `=`, `≠`, `<`, `≤`, `≥`, `>` or a custom connective
+//! registered with a [`ParserConfig`](crate::ParserConfig) is in term position, and the function
+//! applied at its root is checked against the function symbols. Any other term is in formula
+//! position and is checked against the relation symbols, e.g. `P(x)` is the relation `P` applied
+//! to one argument and `P` on its own is a 0-ary relation. The arguments of a relation or
+//! function are always checked as terms. Arithmetic operators and parentheses are
+//! built in, and variables and numeric constants are never checked.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::notation;
+use crate::term::is_built_in;
+use crate::{Formula, Term, atom};
+
+/// A set of function and relation symbols together with their arities.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, Signature, SignatureError, SymbolKind};
+///
+/// let mut sig = Signature::new();
+/// sig.function("fib", 1).relation("P", 1);
+///
+/// assert_eq!(Formula::new("∧ P(x) = y fib(x+1)").check_against(&sig), Ok(()));
+/// assert_eq!(
+///     Formula::new("= y fib(x,1)").check_against(&sig),
+///     Err(SignatureError::WrongArity {
+///         symbol: "fib".to_string(),
+///         kind: SymbolKind::Function,
+///         expected: 1,
+///         found: 2,
+///     })
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signature {
+    functions: BTreeMap<String, usize>, // The arity of each function symbol.
+    relations: BTreeMap<String, usize>, // The arity of each relation symbol.
+    permissive: bool,                   // Whether undeclared symbols are accepted.
+}
+
+impl Signature {
+    /// Creates an empty, strict `Signature`.
+    pub fn new() -> Self {
+        Signature::default()
+    }
+
+    /// Declares the function symbol `name` taking `arity` arguments.
+    pub fn function(&mut self, name: &str, arity: usize) -> &mut Self {
+        self.functions.insert(name.to_string(), arity);
+        self
+    }
+
+    /// Declares the relation symbol `name` taking `arity` arguments.
+    pub fn relation(&mut self, name: &str, arity: usize) -> &mut Self {
+        self.relations.insert(name.to_string(), arity);
+        self
+    }
+
+    /// Sets whether undeclared symbols are accepted.
+    ///
+    /// A permissive signature only rejects declared symbols used with the wrong arity. The
+    /// undeclared symbols can still be listed with [`Formula::signature_warnings`].
+    pub fn permissive(&mut self, permissive: bool) -> &mut Self {
+        self.permissive = permissive;
+        self
+    }
+
+    /// Returns whether undeclared symbols are accepted.
+    pub fn is_permissive(&self) -> bool {
+        self.permissive
+    }
+
+    /// Returns the arity of the function symbol `name`, if it is declared.
+    pub fn function_arity(&self, name: &str) -> Option<usize> {
+        self.functions.get(name).copied()
+    }
+
+    /// Returns the arity of the relation symbol `name`, if it is declared.
+    pub fn relation_arity(&self, name: &str) -> Option<usize> {
+        self.relations.get(name).copied()
+    }
+
+    /// Checks a single atom, returning the first problem that the signature does not tolerate.
+    ///
+    /// `term_position` tells whether the atom is an operand of a relation such as `=`.
+    pub(crate) fn check_atom(
+        &self,
+        term: &Term,
+        term_position: bool,
+    ) -> Result<(), SignatureError> {
+        let mut issues: Vec<SignatureError> = Vec::new();
+        self.collect_atom(term, term_position, &mut issues);
+        match issues.into_iter().find(|issue| self.rejects(issue)) {
+            Some(issue) => Err(issue),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether `issue` is an error rather than a warning under this signature.
+    pub(crate) fn rejects(&self, issue: &SignatureError) -> bool {
+        !(self.permissive && matches!(issue, SignatureError::UnknownSymbol { .. }))
+    }
+
+    /// Collects every problem of `formula` into `issues`, in the order the symbols appear.
+    pub(crate) fn collect(&self, formula: &Formula, issues: &mut Vec<SignatureError>) {
+        match formula {
+            Formula::Term(term) => self.collect_atom(term, false, issues),
+            Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+                self.collect_term(lhs, issues);
+                self.collect_term(rhs, issues);
+            }
+            Formula::Predicate(name, args) => {
+                self.collect_atom(&atom::predicate_term(name, args), false, issues)
+            }
+            Formula::Top | Formula::Bottom => {}
+            Formula::Negation(inner) => self.collect(inner, issues),
+            Formula::Conjunction(lhs, rhs)
+            | Formula::Disjunction(lhs, rhs)
+            | Formula::Implication(lhs, rhs)
+            | Formula::Iff(lhs, rhs) => {
+                self.collect(lhs, issues);
+                self.collect(rhs, issues);
+            }
+            Formula::Equivalence(lhs, rhs)
+            | Formula::NotEqual(lhs, rhs)
+            | Formula::LessThan(lhs, rhs)
+            | Formula::LessEqual(lhs, rhs)
+            | Formula::GreaterEqual(lhs, rhs)
+            | Formula::GreaterThan(lhs, rhs)
+            | Formula::Custom(_, lhs, rhs) => {
+                self.collect_operand(lhs, issues);
+                self.collect_operand(rhs, issues);
+            }
+            Formula::UniversalQuantifier(_, inner) | Formula::ExistentialQuantifier(_, inner) => {
+                self.collect(inner, issues)
+            }
+        }
+    }
+
+    /// Collects the problems of an operand of a relation, which is a term if it is atomic.
+    fn collect_operand(&self, operand: &Formula, issues: &mut Vec<SignatureError>) {
+        match operand {
+            Formula::Term(term) => self.collect_atom(term, true, issues),
+            _ => self.collect(operand, issues),
+        }
+    }
+
+    fn collect_atom(&self, term: &Term, term_position: bool, issues: &mut Vec<SignatureError>) {
+        if term_position {
+            self.collect_term(term, issues);
+            return;
+        }
+        match term {
+            Term::Var(name) => self.collect_symbol(name, SymbolKind::Relation, 0, issues),
+            Term::App(name, args) if !is_built_in(name) => {
+                self.collect_symbol(name, SymbolKind::Relation, args.len(), issues);
+                for arg in args {
+                    self.collect_term(arg, issues);
+                }
+            }
+            _ => self.collect_term(term, issues),
+        }
+    }
+
+    fn collect_term(&self, term: &Term, issues: &mut Vec<SignatureError>) {
+        if let Term::App(name, args) = term {
+            if !is_built_in(name) {
+                self.collect_symbol(name, SymbolKind::Function, args.len(), issues);
+            }
+            for arg in args {
+                self.collect_term(arg, issues);
+            }
+        }
+    }
+
+    fn collect_symbol(
+        &self,
+        name: &str,
+        kind: SymbolKind,
+        found: usize,
+        issues: &mut Vec<SignatureError>,
+    ) {
+        let declared: Option<usize> = match kind {
+            SymbolKind::Function => self.function_arity(name),
+            SymbolKind::Relation => self.relation_arity(name),
+        };
+        let symbol: String = name.to_string();
+        match declared {
+            None => issues.push(SignatureError::UnknownSymbol { symbol, kind }),
+            Some(expected) if expected != found => issues.push(SignatureError::WrongArity {
+                symbol,
+                kind,
+                expected,
+                found,
+            }),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Returns the atoms of `formula` in prefix order, each as the relation symbol it applies, if
+/// any, and its arguments, which are in term position. A term in formula position that applies no
+/// relation, such as `(P)`, is returned whole as the only argument.
+fn atoms(formula: &Formula) -> Vec<(Option<&str>, Vec<&Term>)> {
+    let mut atoms: Vec<(Option<&str>, Vec<&Term>)> = Vec::new();
+    if let Formula::Term(term) = formula {
+        atoms.push(application(term));
+    }
+    for node in formula.subformulae() {
+        match node {
+            Formula::Equality(lhs, rhs) | Formula::Less(lhs, rhs) => {
+                atoms.push((Some(notation::connective(node)), vec![lhs, rhs]));
+                continue;
+            }
+            Formula::Predicate(name, args) => {
+                atoms.push((Some(name), args.iter().collect()));
+                continue;
+            }
+            _ => {}
+        }
+        let children: Vec<&Formula> = node.children();
+        let relation: Option<&str> = match node {
+            Formula::Custom(symbol, _, _) => Some(symbol),
+            Formula::Equivalence(_, _)
+            | Formula::NotEqual(_, _)
+            | Formula::LessThan(_, _)
+            | Formula::LessEqual(_, _)
+            | Formula::GreaterEqual(_, _)
+            | Formula::GreaterThan(_, _) => Some(notation::connective(node)),
+            _ => None,
+        };
+        let terms: Vec<&Term> = children
+            .iter()
+            .filter_map(|child| match child {
+                Formula::Term(term) => Some(term),
+                _ => None,
+            })
+            .collect();
+        match relation {
+            // A relation between two terms is an atom, whose operands are in term position.
+            Some(symbol) if terms.len() == children.len() => atoms.push((Some(symbol), terms)),
+            _ => atoms.extend(terms.into_iter().map(application)),
+        }
+    }
+    atoms
+}
+
+/// Splits a term in formula position into the relation it applies and its arguments.
+fn application(term: &Term) -> (Option<&str>, Vec<&Term>) {
+    match term {
+        Term::Var(name) => (Some(name), Vec::new()),
+        Term::App(name, args) if !is_built_in(name) => (Some(name), args.iter().collect()),
+        _ => (None, vec![term]),
+    }
+}
+
+/// Calls `visit` on every subterm of the terms in term position of `formula`, in prefix order.
+fn for_each_subterm<'a>(formula: &'a Formula, mut visit: impl FnMut(&'a Term)) {
+    for (_, args) in atoms(formula) {
+        let mut stack: Vec<&Term> = args.into_iter().rev().collect();
+        while let Some(term) = stack.pop() {
+            visit(term);
+            if let Term::App(_, args) = term {
+                stack.extend(args.iter().rev());
+            }
+        }
+    }
+}
+
+/// Collects the relation symbols of `formula` with their arities. See
+/// [`Formula::relation_symbols`].
+pub(crate) fn relation_symbols(
+    formula: &Formula,
+) -> Result<BTreeMap<String, usize>, SignatureError> {
+    let mut symbols: BTreeMap<String, usize> = BTreeMap::new();
+    for (relation, args) in atoms(formula) {
+        if let Some(symbol) = relation {
+            record(symbol, args.len(), &mut symbols)?;
+        }
+    }
+    Ok(symbols)
+}
+
+/// Collects the function symbols of `formula` with the arity of their first application. See
+/// [`Formula::function_symbols`].
+pub(crate) fn function_symbols(formula: &Formula) -> BTreeMap<String, usize> {
+    let mut symbols: BTreeMap<String, usize> = BTreeMap::new();
+    for_each_subterm(formula, |term| {
+        if let Term::App(name, args) = term
+            && !is_built_in(name)
+        {
+            symbols.entry(name.clone()).or_insert(args.len());
+        }
+    });
+    symbols
+}
+
+/// Collects the bare identifiers in term position of `formula`. See
+/// [`Formula::constant_and_variable_names`].
+pub(crate) fn constant_and_variable_names(formula: &Formula) -> BTreeSet<String> {
+    let mut names: BTreeSet<String> = BTreeSet::new();
+    for_each_subterm(formula, |term| {
+        if let Term::Var(name) = term {
+            names.insert(name.clone());
+        }
+    });
+    names
+}
+
+/// Collects the numeric literals of `formula`. See [`Formula::numeric_literals`].
+pub(crate) fn numeric_literals(formula: &Formula) -> BTreeSet<String> {
+    let mut literals: BTreeSet<String> = BTreeSet::new();
+    for_each_subterm(formula, |term| match term {
+        Term::IntLit(value) => {
+            literals.insert(value.to_string());
+        }
+        Term::Const(value) => {
+            literals.insert(value.clone());
+        }
+        _ => {}
+    });
+    literals
+}
+
+/// Records `symbol` with `arity`, failing if it was seen before with another arity.
+fn record(
+    symbol: &str,
+    arity: usize,
+    symbols: &mut BTreeMap<String, usize>,
+) -> Result<(), SignatureError> {
+    match symbols.get(symbol) {
+        Some(&expected) if expected != arity => Err(SignatureError::WrongArity {
+            symbol: symbol.to_string(),
+            kind: SymbolKind::Relation,
+            expected,
+            found: arity,
+        }),
+        Some(_) => Ok(()),
+        None => {
+            symbols.insert(symbol.to_string(), arity);
+            Ok(())
+        }
+    }
+}
+
+/// Whether a symbol is used as a function or as a relation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Relation,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolKind::Function => write!(f, "function"),
+            SymbolKind::Relation => write!(f, "relation"),
+        }
+    }
+}
+
+/// An error returned when a formula does not agree with a [`Signature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The symbol is not declared in the signature.
+    UnknownSymbol { symbol: String, kind: SymbolKind },
+    /// The symbol is declared with a different number of arguments than it is applied to.
+    WrongArity {
+        symbol: String,
+        kind: SymbolKind,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::UnknownSymbol { symbol, kind } => {
+                write!(f, "unknown {kind} symbol {symbol}")
+            }
+            SignatureError::WrongArity {
+                symbol,
+                kind,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{kind} symbol {symbol} takes {expected} arguments, found {found}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseError, corpus};
+
+    fn gcd_signature() -> Signature {
+        let mut sig = Signature::new();
+        sig.function("gcd", 2).function("mod", 2);
+        sig
+    }
+
+    #[test]
+    fn test_correct_usage() {
+        let sig: Signature = gcd_signature();
+        assert_eq!(
+            Formula::new(corpus::OTHER_EXAMPLES[6]).check_against(&sig),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        let sig: Signature = gcd_signature();
+        assert_eq!(
+            Formula::new("= gcd(a,b) gcd(a,mod(a))").check_against(&sig),
+            Err(SignatureError::WrongArity {
+                symbol: "mod".to_string(),
+                kind: SymbolKind::Function,
+                expected: 2,
+                found: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_symbol() {
+        let sig: Signature = gcd_signature();
+        let error: SignatureError = Formula::new("∧ P(a) = gcd(a,b) b")
+            .check_against(&sig)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            SignatureError::UnknownSymbol {
+                symbol: "P".to_string(),
+                kind: SymbolKind::Relation,
+            }
+        );
+        assert_eq!(error.to_string(), "unknown relation symbol P");
+    }
+
+    #[test]
+    fn test_function_used_as_relation() {
+        let sig: Signature = gcd_signature();
+        assert!(Formula::new("¬ gcd(a,b)").check_against(&sig).is_err());
+        assert_eq!(Formula::new("¬ = b 0").check_against(&sig), Ok(()));
+    }
+
+    #[test]
+    fn test_permissive_mode_only_warns() {
+        let mut sig: Signature = gcd_signature();
+        sig.permissive(true);
+        let test_formula: Formula = Formula::new("∧ P(a) = fib(a) gcd(a,b)");
+        assert_eq!(test_formula.check_against(&sig), Ok(()));
+        assert_eq!(
+            test_formula.signature_warnings(&sig),
+            vec![
+                SignatureError::UnknownSymbol {
+                    symbol: "P".to_string(),
+                    kind: SymbolKind::Relation,
+                },
+                SignatureError::UnknownSymbol {
+                    symbol: "fib".to_string(),
+                    kind: SymbolKind::Function,
+                },
+            ]
+        );
+        assert!(Formula::new("= gcd(a) b").check_against(&sig).is_err());
+    }
+
+    #[test]
+    fn test_relation_symbols() {
+        assert_eq!(
+            Formula::new(corpus::DOC_EXAMPLE).relation_symbols(),
+            Ok(BTreeMap::from([
+                ("<".to_string(), 2),
+                ("P".to_string(), 1),
+                ("Q".to_string(), 1),
+                ("R".to_string(), 1),
+                ("S".to_string(), 1),
+                ("T".to_string(), 1),
+            ]))
+        );
+        let gcd: Formula = Formula::new(corpus::OTHER_EXAMPLES[6]);
+        assert_eq!(
+            gcd.relation_symbols(),
+            Ok(BTreeMap::from([("=".to_string(), 2)]))
+        );
+        assert_eq!(
+            Formula::new("→ P ∀ x ≤ x f(x)").relation_symbols(),
+            Ok(BTreeMap::from([("P".to_string(), 0), ("≤".to_string(), 2)]))
+        );
+        assert_eq!(
+            Formula::new("P(x,y)").relation_symbols(),
+            Ok(BTreeMap::from([("P".to_string(), 2)]))
+        );
+    }
+
+    #[test]
+    fn test_relation_symbols_conflict() {
+        assert_eq!(
+            Formula::new("∧ P(x) ∨ Q ¬ P(x,y)").relation_symbols(),
+            Err(SignatureError::WrongArity {
+                symbol: "P".to_string(),
+                kind: SymbolKind::Relation,
+                expected: 1,
+                found: 2,
+            })
+        );
+        assert!(Formula::new("→ Q Q(a)").relation_symbols().is_err());
+    }
+
+    #[test]
+    fn test_term_symbols() {
+        let test_formula: Formula = Formula::new("= curFib+preFib fib(index+1)");
+        assert_eq!(
+            test_formula.function_symbols(),
+            BTreeMap::from([("fib".to_string(), 1)])
+        );
+        assert_eq!(
+            test_formula.constant_and_variable_names(),
+            BTreeSet::from([
+                "curFib".to_string(),
+                "index".to_string(),
+                "preFib".to_string()
+            ])
+        );
+        assert_eq!(
+            test_formula.numeric_literals(),
+            BTreeSet::from(["1".to_string()])
+        );
+
+        let gcd: Formula = Formula::new(corpus::OTHER_EXAMPLES[6]);
+        assert_eq!(
+            gcd.function_symbols(),
+            BTreeMap::from([("gcd".to_string(), 2), ("mod".to_string(), 2)])
+        );
+        assert_eq!(
+            gcd.constant_and_variable_names(),
+            BTreeSet::from(["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(gcd.numeric_literals(), BTreeSet::from(["0".to_string()]));
+    }
+
+    #[test]
+    fn test_term_symbols_positions() {
+        let test_formula: Formula = Formula::new("→ ∧ P(x*(y-2)) Q ≥ 10 (z)/2");
+        assert!(test_formula.function_symbols().is_empty());
+        assert_eq!(
+            test_formula.constant_and_variable_names(),
+            BTreeSet::from(["x".to_string(), "y".to_string(), "z".to_string()])
+        );
+        assert_eq!(
+            test_formula.numeric_literals(),
+            BTreeSet::from(["10".to_string(), "2".to_string()])
+        );
+        assert_eq!(
+            Formula::new("∨ = f(x) 1 = f(x,y) 2").function_symbols(),
+            BTreeMap::from([("f".to_string(), 1)])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_signature() {
+        let mut sig = Signature::new();
+        sig.function("fib", 1).permissive(true);
+        assert_eq!(
+            Formula::try_new_with_signature(corpus::FIBONACCI[0], &sig),
+            Ok(Formula::new(corpus::FIBONACCI[0]))
+        );
+        let error: ParseError =
+            Formula::try_new_with_signature("= curFib fib(index,1)", &sig).unwrap_err();
+        assert_eq!(error.index, 2);
+        assert_eq!(
+            error.to_string(),
+            "expected a term agreeing with the signature (function symbol fib takes 1 arguments, found 2) at token 2, found \"fib(index,1)\""
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! # Simplification
+//!
+//! This module implements [`Formula::simplify`], which removes the noise left by machine
+//! generated formulae, such as the trivial lemma `→ = x r+y*0 = x r+y*0`, using purely syntactic
+//! rewrites that never change the meaning of a formula.
+//!
+//! The rewrites are grouped into rules that can be switched off with [`SimplifyOptions`]:
+//!
+//! | Rule                    | Rewrites                                                        |
+//! |-------------------------|-----------------------------------------------------------------|
+//! | `double_negation`       | `¬ ¬ φ` to `φ`                                                  |
+//! | `units`                 | `∧ φ ⊤`, `∧ ⊤ φ`, `∨ φ ⊥`, `∨ ⊥ φ` and `→ ⊤ φ` to `φ`           |
+//! | `zeros`                 | `∧ φ ⊥` and `∧ ⊥ φ` to `⊥`, `∨ φ ⊤`, `∨ ⊤ φ`, `→ ⊥ φ` and `→ φ ⊤` to `⊤` |
+//! | `idempotence`           | `∧ φ φ` and `∨ φ φ` to `φ`                                      |
+//! | `reflexive_implication` | `→ φ φ` to `⊤`                                                  |
+//! | `truth_constants`       | `¬ ⊤` to `⊥` and `¬ ⊥` to `⊤`                                   |
+//!
+//! The formula is rewritten bottom-up: the operands of a connective are simplified before the
+//! connective itself, and every rule yields a simplified operand or a truth constant, so a single
+//! pass reaches a fixed point. Rewriting can be limited to the subformulae near the root, e.g. to
+//! tidy the outermost connectives of a large invariant.
+use crate::Formula;
+
+/// The rules applied by [`Formula::simplify_with`] and how deep in the formula they may fire.
+///
+/// Every rule is enabled by default and may fire at any depth.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, SimplifyOptions};
+///
+/// let test_formula: Formula = Formula::new("∧ ¬ ¬ P ⊤");
+///
+/// let mut options = SimplifyOptions::new();
+/// options.double_negation(false);
+/// assert_eq!(test_formula.simplify_with(&options), Formula::new("¬ ¬ P"));
+/// assert_eq!(test_formula.simplify(), Formula::new("P"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimplifyOptions {
+    double_negation: bool,       // Whether `¬ ¬ φ` becomes `φ`.
+    units: bool,                 // Whether `⊤` and `⊥` are dropped as operands of `∧`, `∨` and `→`.
+    zeros: bool,                 // Whether `⊤` and `⊥` absorb the connective they are operands of.
+    idempotence: bool,           // Whether `∧ φ φ` and `∨ φ φ` become `φ`.
+    reflexive_implication: bool, // Whether `→ φ φ` becomes `⊤`.
+    truth_constants: bool,       // Whether `¬ ⊤` and `¬ ⊥` are evaluated.
+    max_depth: Option<usize>,    // The deepest level at which rules fire, the root being 0.
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        SimplifyOptions {
+            double_negation: true,
+            units: true,
+            zeros: true,
+            idempotence: true,
+            reflexive_implication: true,
+            truth_constants: true,
+            max_depth: None,
+        }
+    }
+}
+
+impl SimplifyOptions {
+    /// Creates `SimplifyOptions` enabling every rule at every depth.
+    pub fn new() -> Self {
+        SimplifyOptions::default()
+    }
+
+    /// Sets whether `¬ ¬ φ` is rewritten to `φ`.
+    pub fn double_negation(&mut self, enabled: bool) -> &mut Self {
+        self.double_negation = enabled;
+        self
+    }
+
+    /// Sets whether `∧ φ ⊤`, `∨ φ ⊥` and `→ ⊤ φ`, in either operand order for `∧` and `∨`, are
+    /// rewritten to `φ`.
+    pub fn units(&mut self, enabled: bool) -> &mut Self {
+        self.units = enabled;
+        self
+    }
+
+    /// Sets whether `∧ φ ⊥` is rewritten to `⊥`, and `∨ φ ⊤`, `→ ⊥ φ` and `→ φ ⊤` to `⊤`, in
+    /// either operand order for `∧` and `∨`.
+    pub fn zeros(&mut self, enabled: bool) -> &mut Self {
+        self.zeros = enabled;
+        self
+    }
+
+    /// Sets whether `∧ φ φ` and `∨ φ φ` are rewritten to `φ`.
+    pub fn idempotence(&mut self, enabled: bool) -> &mut Self {
+        self.idempotence = enabled;
+        self
+    }
+
+    /// Sets whether `→ φ φ` is rewritten to `⊤`.
+    pub fn reflexive_implication(&mut self, enabled: bool) -> &mut Self {
+        self.reflexive_implication = enabled;
+        self
+    }
+
+    /// Sets whether `¬ ⊤` is rewritten to `⊥` and `¬ ⊥` to `⊤`.
+    pub fn truth_constants(&mut self, enabled: bool) -> &mut Self {
+        self.truth_constants = enabled;
+        self
+    }
+
+    /// Only lets rules fire at the subformulae of the input that are at most `max_depth`
+    /// connectives or quantifiers below the root, or at any depth with `None`. With `Some(0)`
+    /// only the root is rewritten.
+    pub fn max_depth(&mut self, max_depth: Option<usize>) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+/// Simplifies `formula` according to `options`. See [`Formula::simplify_with`].
+pub(crate) fn simplify(formula: &Formula, options: &SimplifyOptions) -> Formula {
+    simplify_at(formula, options, 0)
+}
+
+/// Simplifies `formula`, found `depth` levels below the root of the input.
+fn simplify_at(formula: &Formula, options: &SimplifyOptions, depth: usize) -> Formula {
+    if options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+        return formula.clone();
+    }
+    let simplify = |formula: &Formula| Box::new(simplify_at(formula, options, depth + 1));
+    let rebuilt: Formula = match formula {
+        Formula::Term(_)
+        | Formula::Top
+        | Formula::Bottom
+        | Formula::Equality(_, _)
+        | Formula::Less(_, _)
+        | Formula::Predicate(_, _) => return formula.clone(),
+        Formula::Negation(inner) => Formula::Negation(simplify(inner)),
+        Formula::Conjunction(lhs, rhs) => Formula::Conjunction(simplify(lhs), simplify(rhs)),
+        Formula::Disjunction(lhs, rhs) => Formula::Disjunction(simplify(lhs), simplify(rhs)),
+        Formula::Implication(lhs, rhs) => Formula::Implication(simplify(lhs), simplify(rhs)),
+        Formula::Equivalence(lhs, rhs) => Formula::Equivalence(simplify(lhs), simplify(rhs)),
+        Formula::Iff(lhs, rhs) => Formula::Iff(simplify(lhs), simplify(rhs)),
+        Formula::NotEqual(lhs, rhs) => Formula::NotEqual(simplify(lhs), simplify(rhs)),
+        Formula::LessThan(lhs, rhs) => Formula::LessThan(simplify(lhs), simplify(rhs)),
+        Formula::LessEqual(lhs, rhs) => Formula::LessEqual(simplify(lhs), simplify(rhs)),
+        Formula::GreaterEqual(lhs, rhs) => Formula::GreaterEqual(simplify(lhs), simplify(rhs)),
+        Formula::GreaterThan(lhs, rhs) => Formula::GreaterThan(simplify(lhs), simplify(rhs)),
+        Formula::Custom(symbol, lhs, rhs) => {
+            Formula::Custom(symbol.clone(), simplify(lhs), simplify(rhs))
+        }
+        Formula::UniversalQuantifier(variable, inner) => {
+            Formula::UniversalQuantifier(variable.clone(), simplify(inner))
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            Formula::ExistentialQuantifier(variable.clone(), simplify(inner))
+        }
+    };
+    rewrite(&rebuilt, options).unwrap_or(rebuilt)
+}
+
+/// Applies the first enabled rule matching the root of `formula`, if any.
+fn rewrite(formula: &Formula, options: &SimplifyOptions) -> Option<Formula> {
+    match formula {
+        Formula::Negation(inner) => match &**inner {
+            Formula::Negation(innermost) if options.double_negation => Some((**innermost).clone()),
+            Formula::Top if options.truth_constants => Some(Formula::Bottom),
+            Formula::Bottom if options.truth_constants => Some(Formula::Top),
+            _ => None,
+        },
+        Formula::Conjunction(lhs, rhs) => match (&**lhs, &**rhs) {
+            (Formula::Bottom, _) | (_, Formula::Bottom) if options.zeros => Some(Formula::Bottom),
+            (Formula::Top, other) | (other, Formula::Top) if options.units => Some(other.clone()),
+            (lhs, rhs) if options.idempotence && lhs == rhs => Some(lhs.clone()),
+            _ => None,
+        },
+        Formula::Disjunction(lhs, rhs) => match (&**lhs, &**rhs) {
+            (Formula::Top, _) | (_, Formula::Top) if options.zeros => Some(Formula::Top),
+            (Formula::Bottom, other) | (other, Formula::Bottom) if options.units => {
+                Some(other.clone())
+            }
+            (lhs, rhs) if options.idempotence && lhs == rhs => Some(lhs.clone()),
+            _ => None,
+        },
+        Formula::Implication(lhs, rhs) => match (&**lhs, &**rhs) {
+            (Formula::Bottom, _) | (_, Formula::Top) if options.zeros => Some(Formula::Top),
+            (Formula::Top, other) if options.units => Some(other.clone()),
+            (lhs, rhs) if options.reflexive_implication && lhs == rhs => Some(Formula::Top),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    /// Simplifies `input` with only the rule set by `rule` enabled.
+    fn only(input: &str, rule: fn(&mut SimplifyOptions, bool) -> &mut SimplifyOptions) -> Formula {
+        let mut options = SimplifyOptions::new();
+        options
+            .double_negation(false)
+            .units(false)
+            .zeros(false)
+            .idempotence(false)
+            .reflexive_implication(false)
+            .truth_constants(false);
+        rule(&mut options, true);
+        Formula::new(input).simplify_with(&options)
+    }
+
+    #[test]
+    fn test_double_negation() {
+        let rule = SimplifyOptions::double_negation;
+        assert_eq!(only("¬ ¬ P", rule), Formula::new("P"));
+        assert_eq!(only("¬ ¬ ¬ P", rule), Formula::new("¬ P"));
+        assert_eq!(only("¬ ¬ ¬ ¬ P", rule), Formula::new("P"));
+        assert_eq!(only("∧ ⊤ ¬ ¬ P", rule), Formula::new("∧ ⊤ P"));
+    }
+
+    #[test]
+    fn test_units() {
+        let rule = SimplifyOptions::units;
+        assert_eq!(only("∧ P ⊤", rule), Formula::new("P"));
+        assert_eq!(only("∧ ⊤ P", rule), Formula::new("P"));
+        assert_eq!(only("∨ P ⊥", rule), Formula::new("P"));
+        assert_eq!(only("∨ ⊥ P", rule), Formula::new("P"));
+        assert_eq!(only("→ ⊤ P", rule), Formula::new("P"));
+        assert_eq!(only("→ P ⊥", rule), Formula::new("→ P ⊥"));
+    }
+
+    #[test]
+    fn test_zeros() {
+        let rule = SimplifyOptions::zeros;
+        assert_eq!(only("∧ P ⊥", rule), Formula::Bottom);
+        assert_eq!(only("∧ ⊥ P", rule), Formula::Bottom);
+        assert_eq!(only("∨ P ⊤", rule), Formula::Top);
+        assert_eq!(only("∨ ⊤ P", rule), Formula::Top);
+        assert_eq!(only("→ ⊥ P", rule), Formula::Top);
+        assert_eq!(only("→ P ⊤", rule), Formula::Top);
+    }
+
+    #[test]
+    fn test_idempotence() {
+        let rule = SimplifyOptions::idempotence;
+        assert_eq!(only("∧ < x y < x y", rule), Formula::new("< x y"));
+        assert_eq!(only("∨ P(x) P(x)", rule), Formula::new("P(x)"));
+        assert_eq!(only("∨ P(x) P(y)", rule), Formula::new("∨ P(x) P(y)"));
+    }
+
+    #[test]
+    fn test_reflexive_implication() {
+        let rule = SimplifyOptions::reflexive_implication;
+        assert_eq!(only(corpus::PAPER_EXAMPLE[4], rule), Formula::Top);
+        assert_eq!(only("→ P Q", rule), Formula::new("→ P Q"));
+    }
+
+    #[test]
+    fn test_truth_constants() {
+        let rule = SimplifyOptions::truth_constants;
+        assert_eq!(only("¬ ⊤", rule), Formula::Bottom);
+        assert_eq!(only("¬ ⊥", rule), Formula::Top);
+    }
+
+    #[test]
+    fn test_simplify_paper_example_lemmas() {
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[0]).simplify(),
+            Formula::new(corpus::PAPER_EXAMPLE[1])
+        );
+        let lemmas: Formula = Formula::new(format!(
+            "∧ {} ∧ {} {}",
+            corpus::PAPER_EXAMPLE[0],
+            corpus::PAPER_EXAMPLE[4],
+            corpus::PAPER_EXAMPLE[8]
+        ));
+        assert_eq!(lemmas.simplify(), Formula::new(corpus::PAPER_EXAMPLE[1]));
+        assert_eq!(
+            Formula::new(corpus::OTHER_EXAMPLES[5]).simplify(),
+            Formula::new(
+                "→ ∧ = 0 count ∧ = result*fact(count) fact(x) ∨ < 0 count = 0 count = result fact(x)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let test_formula: Formula = Formula::new("∧ ⊤ ∨ ¬ ¬ P ⊥");
+        let mut options = SimplifyOptions::new();
+        options.max_depth(Some(0));
+        assert_eq!(
+            test_formula.simplify_with(&options),
+            Formula::new("∨ ¬ ¬ P ⊥")
+        );
+        options.max_depth(Some(1));
+        assert_eq!(test_formula.simplify_with(&options), Formula::new("¬ ¬ P"));
+        options.max_depth(Some(2));
+        assert_eq!(test_formula.simplify_with(&options), Formula::new("P"));
+        assert_eq!(
+            Formula::new("∀ x ∃ y ∧ P(x,y) ⊤").simplify(),
+            Formula::new("∀ x ∃ y P(x,y)")
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//! # SMT-LIB
+//!
+//! This module exports a formula as an SMT-LIB 2 script for [`Formula::to_smtlib`], so that a
+//! lemma can be discharged by an external solver such as Z3 or CVC5.
+//!
+//! The script asserts the negation of the formula and ends with `(check-sat)`, so the formula is
+//! valid exactly when the solver answers `unsat`. Its symbols are interpreted over the integers:
+//!
+//! | Formula                 | SMT-LIB                                        |
+//! |-------------------------|------------------------------------------------|
+//! | `⊤`, `⊥`                | `true`, `false`                                |
+//! | `¬`, `∧`, `∨`, `→`      | `not`, `and`, `or`, `=>`                       |
+//! | `=`, `≠`                | `=`, `distinct`                                |
+//! | `<`, `≤`, `≥`, `>`      | `<`, `<=`, `>=`, `>`                           |
+//! | `∀ x`, `∃ x`            | `(forall ((x Int)) …)`, `(exists ((x Int)) …)` |
+//! | `+`, `-`, `*`           | `+`, `-`, `*`                                  |
+//! | `/`, `%`                | `truncated-div`, `truncated-mod`               |
+//!
+//! The division `/` and the remainder `%` truncate towards zero, as [`Term::eval`] does, while the
+//! SMT-LIB `div` and `mod` are Euclidean, so `-7%2` is `-1` rather than `1`. A script using them
+//! therefore defines `truncated-div` and `truncated-mod` in terms of `div` and `mod` with
+//! `define-fun`. Both are undefined for a zero divisor, which the solver may interpret freely.
+//!
+//! A free variable in a term is declared as an `Int` constant, and a function such as `fib` as a
+//! function from `Int` arguments to `Int`. A term in formula position, such as `P(x)` or `Flag`,
+//! is a relation, declared with the result sort `Bool`. The functions `div(a,b)`, `mod(a,b)` and
+//! `abs(a)` are the built-in integer operations rather than uninterpreted symbols. Parentheses
+//! around a term are dropped, since the prefix syntax makes them redundant.
+//!
+//! Exponentiation, non-integer constants, `Custom` connectives and relations other than `=` and
+//! `≠` between formulae have no counterpart and are reported with an [`ExportError`].
+//!
+//! To discharge a lemma, write the script to a file and run the solver on it, e.g.
+//! `z3 lemma.smt2`. The answer `unsat` means the lemma is valid, while `sat` means it has a
+//! counter-model, which Z3 prints when `(get-model)` is appended to the script. With the `z3`
+//! feature, `Formula::check_validity` hands the script to a linked Z3 instead.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::term::is_operator;
+use crate::{Formula, Term, atom};
+
+/// An error returned by [`Formula::to_smtlib`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    /// A term has no SMT-LIB counterpart, e.g. `x^2` or `1.5`.
+    UnsupportedTerm { term: String, reason: String },
+    /// A connective has no SMT-LIB counterpart, e.g. a `Custom` connective or `<` between
+    /// formulae.
+    UnsupportedConnective { symbol: String },
+    /// A symbol is used with different arities, or both as a function and as a relation.
+    InconsistentSymbol { name: String },
+    /// A name cannot be written as an SMT-LIB symbol, e.g. because it is reserved, or the logic
+    /// is not a simple symbol such as `QF_NIA`.
+    InvalidSymbol { name: String },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::UnsupportedTerm { term, reason } => {
+                write!(f, "the term {term} cannot be exported: {reason}")
+            }
+            ExportError::UnsupportedConnective { symbol } => {
+                write!(f, "the connective {symbol} cannot be exported")
+            }
+            ExportError::InconsistentSymbol { name } => {
+                write!(
+                    f,
+                    "the symbol {name} is used with different arities or sorts"
+                )
+            }
+            ExportError::InvalidSymbol { name } => {
+                write!(f, "the name {name} is not a valid SMT-LIB symbol")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// The words of SMT-LIB that cannot be declared as symbols.
+const RESERVED: [&str; 20] = [
+    "_", "!", "as", "let", "par", "forall", "exists", "match", "true", "false", "not", "and", "or",
+    "xor", "ite", "distinct", "div", "mod", "abs", "=>",
+];
+
+/// The functions that are built into SMT-LIB integer arithmetic, with their arities.
+const BUILT_IN: [(&str, usize); 3] = [("div", 2), ("mod", 2), ("abs", 1)];
+
+/// The definitions of the truncating division and remainder, by the operator they translate.
+const TRUNCATING: [(&str, &str, &str); 2] = [
+    (
+        "/",
+        "truncated-div",
+        "(define-fun truncated-div ((a Int) (b Int)) Int \
+         (ite (>= a 0) (div a b) (- (div (- a) b))))\n",
+    ),
+    (
+        "%",
+        "truncated-mod",
+        "(define-fun truncated-mod ((a Int) (b Int)) Int \
+         (ite (>= a 0) (mod a b) (- (mod (- a) b))))\n",
+    ),
+];
+
+/// The sort of the value of a declared symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Int,
+    Bool,
+}
+
+/// Exports `formula` as an SMT-LIB script in `logic`. See [`Formula::to_smtlib`].
+pub(crate) fn to_smtlib(formula: &Formula, logic: &str) -> Result<String, ExportError> {
+    let mut script: String = negated_assertion(formula, logic)?;
+    script.push_str("(check-sat)\n");
+    Ok(script)
+}
+
+/// Returns the script of [`to_smtlib`] without its final `(check-sat)`: the logic, the
+/// declarations, and the assertion of the negation of `formula`.
+pub(crate) fn negated_assertion(formula: &Formula, logic: &str) -> Result<String, ExportError> {
+    if !is_simple_symbol(logic) {
+        return Err(ExportError::InvalidSymbol {
+            name: logic.to_string(),
+        });
+    }
+    let mut exporter: Exporter = Exporter {
+        bound: Vec::new(),
+        declarations: BTreeMap::new(),
+        truncating: BTreeSet::new(),
+    };
+    let body: String = exporter.formula(formula)?;
+    let mut script: String = format!("(set-logic {logic})\n");
+    for (name, (arity, sort)) in &exporter.declarations {
+        let sort: &str = match sort {
+            Sort::Int => "Int",
+            Sort::Bool => "Bool",
+        };
+        if *arity == 0 {
+            script.push_str(&format!("(declare-const {name} {sort})\n"));
+        } else {
+            let arguments: Vec<&str> = vec!["Int"; *arity];
+            script.push_str(&format!(
+                "(declare-fun {name} ({}) {sort})\n",
+                arguments.join(" ")
+            ));
+        }
+    }
+    for (operator, _, definition) in TRUNCATING {
+        if exporter.truncating.contains(operator) {
+            script.push_str(definition);
+        }
+    }
+    script.push_str(&format!("(assert (not {body}))\n"));
+    Ok(script)
+}
+
+/// Translates a formula, collecting the symbols to declare.
+struct Exporter {
+    bound: Vec<String>, // The variables bound by the enclosing quantifiers.
+    declarations: BTreeMap<String, (usize, Sort)>, // The arity and sort of each free symbol.
+    truncating: BTreeSet<&'static str>, // The truncating operators used, `/` or `%`.
+}
+
+impl Exporter {
+    fn formula(&mut self, formula: &Formula) -> Result<String, ExportError> {
+        Ok(match formula {
+            Formula::Term(term) => self.relation(term)?,
+            Formula::Top => "true".to_string(),
+            Formula::Bottom => "false".to_string(),
+            Formula::Negation(inner) => format!("(not {})", self.formula(inner)?),
+            Formula::Conjunction(lhs, rhs) => self.connective("and", lhs, rhs)?,
+            Formula::Disjunction(lhs, rhs) => self.connective("or", lhs, rhs)?,
+            Formula::Implication(lhs, rhs) => self.connective("=>", lhs, rhs)?,
+            Formula::Equivalence(lhs, rhs) => match (&**lhs, &**rhs) {
+                (Formula::Term(lhs), Formula::Term(rhs)) => self.comparison("=", lhs, rhs)?,
+                _ => self.connective("=", lhs, rhs)?,
+            },
+            Formula::Iff(lhs, rhs) => self.connective("=", lhs, rhs)?,
+            Formula::NotEqual(lhs, rhs) => match (&**lhs, &**rhs) {
+                (Formula::Term(lhs), Formula::Term(rhs)) => {
+                    self.comparison("distinct", lhs, rhs)?
+                }
+                _ => self.connective("distinct", lhs, rhs)?,
+            },
+            Formula::LessThan(lhs, rhs) => self.ordering("<", "<", lhs, rhs)?,
+            Formula::LessEqual(lhs, rhs) => self.ordering("≤", "<=", lhs, rhs)?,
+            Formula::GreaterEqual(lhs, rhs) => self.ordering("≥", ">=", lhs, rhs)?,
+            Formula::GreaterThan(lhs, rhs) => self.ordering(">", ">", lhs, rhs)?,
+            Formula::UniversalQuantifier(variable, inner) => {
+                self.quantifier("forall", variable, inner)?
+            }
+            Formula::ExistentialQuantifier(variable, inner) => {
+                self.quantifier("exists", variable, inner)?
+            }
+            Formula::Custom(symbol, _, _) => {
+                return Err(ExportError::UnsupportedConnective {
+                    symbol: symbol.clone(),
+                });
+            }
+            Formula::Equality(lhs, rhs) => self.comparison("=", lhs, rhs)?,
+            Formula::Less(lhs, rhs) => self.comparison("<", lhs, rhs)?,
+            Formula::Predicate(name, arguments) => {
+                self.relation(&atom::predicate_term(name, arguments))?
+            }
+        })
+    }
+
+    fn connective(
+        &mut self,
+        operator: &str,
+        lhs: &Formula,
+        rhs: &Formula,
+    ) -> Result<String, ExportError> {
+        let lhs: String = self.formula(lhs)?;
+        let rhs: String = self.formula(rhs)?;
+        Ok(format!("({operator} {lhs} {rhs})"))
+    }
+
+    fn comparison(
+        &mut self,
+        operator: &str,
+        lhs: &Term,
+        rhs: &Term,
+    ) -> Result<String, ExportError> {
+        let lhs: String = self.term(lhs)?;
+        let rhs: String = self.term(rhs)?;
+        Ok(format!("({operator} {lhs} {rhs})"))
+    }
+
+    /// Translates an ordering, whose operands must be terms.
+    fn ordering(
+        &mut self,
+        symbol: &str,
+        operator: &str,
+        lhs: &Formula,
+        rhs: &Formula,
+    ) -> Result<String, ExportError> {
+        match (lhs, rhs) {
+            (Formula::Term(lhs), Formula::Term(rhs)) => self.comparison(operator, lhs, rhs),
+            _ => Err(ExportError::UnsupportedConnective {
+                symbol: symbol.to_string(),
+            }),
+        }
+    }
+
+    fn quantifier(
+        &mut self,
+        quantifier: &str,
+        variable: &str,
+        inner: &Formula,
+    ) -> Result<String, ExportError> {
+        let name: String = symbol(variable)?;
+        self.bound.push(variable.to_string());
+        let body: Result<String, ExportError> = self.formula(inner);
+        self.bound.pop();
+        Ok(format!("({quantifier} (({name} Int)) {})", body?))
+    }
+
+    /// Translates a term in formula position, which is a relation.
+    fn relation(&mut self, term: &Term) -> Result<String, ExportError> {
+        match term {
+            Term::Var(name) => {
+                if self.bound.contains(name) {
+                    return Err(ExportError::InconsistentSymbol { name: name.clone() });
+                }
+                self.declare(name, 0, Sort::Bool)
+            }
+            Term::App(name, arguments) if !is_operator(name) && !name.is_empty() => {
+                self.application(name, arguments, Sort::Bool)
+            }
+            _ => Err(ExportError::UnsupportedTerm {
+                term: term.to_string(),
+                reason: "a number or arithmetic term is not a formula".to_string(),
+            }),
+        }
+    }
+
+    fn term(&mut self, term: &Term) -> Result<String, ExportError> {
+        match term {
+            Term::Var(name) if self.bound.contains(name) => symbol(name),
+            Term::Var(name) => self.declare(name, 0, Sort::Int),
+            Term::IntLit(value) if *value < 0 => Ok(format!("(- {})", value.unsigned_abs())),
+            Term::IntLit(value) => Ok(value.to_string()),
+            Term::Const(value) => {
+                if !value.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(ExportError::UnsupportedTerm {
+                        term: value.clone(),
+                        reason: "it is not an integer".to_string(),
+                    });
+                }
+                // SMT-LIB numerals have no leading zeros.
+                let digits: &str = value.trim_start_matches('0');
+                Ok(if digits.is_empty() { "0" } else { digits }.to_string())
+            }
+            Term::App(name, arguments) if name.is_empty() => self.term(&arguments[0]),
+            Term::App(name, arguments) if is_operator(name) => {
+                let operator: &str = match name.as_str() {
+                    "^" => {
+                        return Err(ExportError::UnsupportedTerm {
+                            term: term.to_string(),
+                            reason: "SMT-LIB has no integer exponentiation".to_string(),
+                        });
+                    }
+                    operator => {
+                        match TRUNCATING.iter().find(|(symbol, _, _)| *symbol == operator) {
+                            Some((symbol, function, _)) => {
+                                self.truncating.insert(symbol);
+                                function
+                            }
+                            None => operator,
+                        }
+                    }
+                };
+                self.operation(operator, arguments)
+            }
+            Term::App(name, arguments) if BUILT_IN.contains(&(name, arguments.len())) => {
+                self.operation(name, arguments)
+            }
+            Term::App(name, arguments) => self.application(name, arguments, Sort::Int),
+        }
+    }
+
+    /// Translates the application of the built-in `operator`.
+    fn operation(&mut self, operator: &str, arguments: &[Term]) -> Result<String, ExportError> {
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| self.term(argument))
+            .collect::<Result<_, _>>()?;
+        Ok(format!("({operator} {})", arguments.join(" ")))
+    }
+
+    /// Translates the application of the uninterpreted symbol `name` with result `sort`.
+    fn application(
+        &mut self,
+        name: &str,
+        arguments: &[Term],
+        sort: Sort,
+    ) -> Result<String, ExportError> {
+        let name: String = self.declare(name, arguments.len(), sort)?;
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| self.term(argument))
+            .collect::<Result<_, _>>()?;
+        Ok(format!("({name} {})", arguments.join(" ")))
+    }
+
+    /// Declares the free symbol `name`, failing if it was declared differently.
+    ///
+    /// # Returns
+    /// A `Result` containing the SMT-LIB spelling of `name`.
+    fn declare(&mut self, name: &str, arity: usize, sort: Sort) -> Result<String, ExportError> {
+        let spelling: String = symbol(name)?;
+        let declaration: &(usize, Sort) = self
+            .declarations
+            .entry(spelling.clone())
+            .or_insert((arity, sort));
+        if *declaration != (arity, sort) {
+            return Err(ExportError::InconsistentSymbol {
+                name: name.to_string(),
+            });
+        }
+        Ok(spelling)
+    }
+}
+
+/// Returns whether `name` is a simple SMT-LIB symbol, which needs no quoting.
+fn is_simple_symbol(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c))
+}
+
+/// Returns the SMT-LIB spelling of `name`, quoted with `|` unless it is a simple symbol.
+fn symbol(name: &str) -> Result<String, ExportError> {
+    if RESERVED.contains(&name) || name.contains(['|', '\\']) {
+        return Err(ExportError::InvalidSymbol {
+            name: name.to_string(),
+        });
+    }
+    Ok(if is_simple_symbol(name) {
+        name.to_string()
+    } else {
+        format!("|{name}|")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_paper_example_lemma() {
+        assert_eq!(
+            Formula::new(corpus::PAPER_EXAMPLE[5]).to_smtlib("QF_NIA"),
+            Ok("(set-logic QF_NIA)\n\
+                (declare-const q Int)\n\
+                (declare-const r Int)\n\
+                (declare-const x Int)\n\
+                (declare-const y Int)\n\
+                (assert (not (=> (and (= x (+ r (* y q))) (or (< y r) (= y r))) \
+                (= x (+ (- r y) (* y (+ 1 q)))))))\n\
+                (check-sat)\n"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn test_quantifiers_and_functions() {
+        let test_formula: Formula =
+            Formula::new("∀ n → ∧ ≥ n 2 P(n) ∃ m ∧ ≤ m n = fib(n) fib(n-1)+fib(n-2)");
+        assert_eq!(
+            test_formula.to_smtlib("UFNIA"),
+            Ok("(set-logic UFNIA)\n\
+                (declare-fun P (Int) Bool)\n\
+                (declare-fun fib (Int) Int)\n\
+                (assert (not (forall ((n Int)) (=> (and (>= n 2) (P n)) \
+                (exists ((m Int)) (and (<= m n) (= (fib n) (+ (fib (- n 1)) (fib (- n 2))))))))))\n\
+                (check-sat)\n"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn test_connectives() {
+        let cases: [(&str, &str); 9] = [
+            ("⊤", "true"),
+            ("¬ ⊥", "(not false)"),
+            ("= P ¬ Q", "(= P (not Q))"),
+            ("≠ x 007", "(distinct x 7)"),
+            ("> -x 0", "(> (- x) 0)"),
+            ("= mod(a,b) a%b", "(= (mod a b) (truncated-mod a b))"),
+            ("= q a/b", "(= q (truncated-div a b))"),
+            ("= f(x,y) (0)", "(= (f x y) 0)"),
+            ("= x≔y y", "(= |x≔y| y)"),
+        ];
+        for (input, body) in cases {
+            let script: String = Formula::new(input).to_smtlib("ALL").unwrap();
+            assert!(
+                script.contains(&format!("(assert (not {body}))")),
+                "{input}: {script}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncating_division() {
+        assert_eq!(
+            Formula::new("= r a-b*(a/b)").to_smtlib("QF_NIA"),
+            Ok("(set-logic QF_NIA)\n\
+                (declare-const a Int)\n\
+                (declare-const b Int)\n\
+                (declare-const r Int)\n\
+                (define-fun truncated-div ((a Int) (b Int)) Int \
+                (ite (>= a 0) (div a b) (- (div (- a) b))))\n\
+                (assert (not (= r (- a (* b (truncated-div a b))))))\n\
+                (check-sat)\n"
+                .to_string())
+        );
+        let script: String = Formula::new("= -7%2 -1").to_smtlib("ALL").unwrap();
+        assert!(script.contains("(define-fun truncated-mod"), "{script}");
+        assert!(!script.contains("truncated-div"), "{script}");
+        assert!(
+            script.contains("(assert (not (= (truncated-mod (- 7) 2) (- 1))))"),
+            "{script}"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases: [(&str, ExportError); 6] = [
+            (
+                "= y x^2",
+                ExportError::UnsupportedTerm {
+                    term: "x^2".to_string(),
+                    reason: "SMT-LIB has no integer exponentiation".to_string(),
+                },
+            ),
+            (
+                "< x 1.5",
+                ExportError::UnsupportedTerm {
+                    term: "1.5".to_string(),
+                    reason: "it is not an integer".to_string(),
+                },
+            ),
+            (
+                "∧ x+1 P",
+                ExportError::UnsupportedTerm {
+                    term: "x+1".to_string(),
+                    reason: "a number or arithmetic term is not a formula".to_string(),
+                },
+            ),
+            (
+                "< ¬ P Q",
+                ExportError::UnsupportedConnective {
+                    symbol: "<".to_string(),
+                },
+            ),
+            (
+                "∧ P(x) = P 1",
+                ExportError::InconsistentSymbol {
+                    name: "P".to_string(),
+                },
+            ),
+            (
+                "= and 1",
+                ExportError::InvalidSymbol {
+                    name: "and".to_string(),
+                },
+            ),
+        ];
+        for (input, error) in cases {
+            assert_eq!(Formula::new(input).to_smtlib("ALL"), Err(error), "{input}");
+        }
+        for logic in ["", "QF_LIA)(assert false", "QF LIA", "|ALL|"] {
+            assert_eq!(
+                Formula::new("= x x").to_smtlib(logic),
+                Err(ExportError::InvalidSymbol {
+                    name: logic.to_string()
+                }),
+                "{logic}"
+            );
+        }
+        assert_eq!(
+            Formula::new("= y x^2")
+                .to_smtlib("ALL")
+                .unwrap_err()
+                .to_string(),
+            "the term x^2 cannot be exported: SMT-LIB has no integer exponentiation"
+        );
+    }
+}
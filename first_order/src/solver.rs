@@ -0,0 +1,478 @@
+//! # Checking validity with Z3
+//!
+//! This module checks the validity of a formula with the Z3 solver for
+//! [`Formula::check_validity`], when the `z3` feature is enabled. The formula is exported as
+//! [`Formula::to_smtlib`] does, so its terms are interpreted over the integers and every function
+//! or relation that is not built into SMT-LIB, such as `fib`, is uninterpreted. The negation of
+//! the formula is handed to Z3: the formula is valid if the negation is unsatisfiable, and a model
+//! of the negation is a counter-model of the formula.
+//!
+//! Z3 is called through its C API, so the feature links against `libz3`, which must be installed.
+//! The bindings are written out here rather than taken from the `z3` crate, which builds or
+//! locates Z3 through `z3-sys` and `bindgen` at compile time: the few functions needed are
+//! declared by hand, so the feature only needs the shared library. Since nothing checks them
+//! against the headers, the version of the library is checked when it is first used, and a Z3
+//! older than [`MIN_VERSION`] is reported as a [`SolverError`].
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString, c_char, c_int, c_uint, c_void};
+use std::fmt;
+
+use crate::{DomainElem, ExportError, Formula, smtlib};
+
+/// The oldest version of Z3 whose C API matches the declarations here. Z3 4.8 is the first
+/// version whose `Z3_parse_smtlib2_string` returns a vector of assertions.
+const MIN_VERSION: (u32, u32) = (4, 8);
+
+/// How [`Formula::check_validity`] runs Z3.
+///
+/// # Example
+/// ```
+/// use first_order::SolverConfig;
+///
+/// let mut config: SolverConfig = SolverConfig::new();
+/// config.timeout(1000).logic("UFNIA");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverConfig {
+    timeout: Option<u32>, // The time Z3 may spend, in milliseconds, if limited.
+    logic: String,        // The SMT-LIB logic the formula is checked in.
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            timeout: None,
+            logic: "ALL".to_string(),
+        }
+    }
+}
+
+impl SolverConfig {
+    /// Creates a `SolverConfig` without a time limit, in the logic `ALL`.
+    pub fn new() -> Self {
+        SolverConfig::default()
+    }
+
+    /// Sets the time Z3 may spend on a formula, in milliseconds, after which the answer is
+    /// [`Validity::Unknown`].
+    pub fn timeout(&mut self, milliseconds: u32) -> &mut Self {
+        self.timeout = Some(milliseconds);
+        self
+    }
+
+    /// Sets the SMT-LIB logic the formula is checked in, e.g. `QF_NIA`. A logic that is not an
+    /// SMT-LIB symbol is reported by [`Formula::check_validity`] as an
+    /// [`ExportError::InvalidSymbol`].
+    pub fn logic(&mut self, logic: impl Into<String>) -> &mut Self {
+        self.logic = logic.into();
+        self
+    }
+}
+
+/// The answer of [`Formula::check_validity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Validity {
+    /// The formula holds for every value of its variables and every interpretation of its
+    /// functions and relations.
+    Valid,
+    /// The formula is false in the given counter-model.
+    Invalid(CounterModel),
+    /// Z3 gave up, e.g. because of the time limit or nonlinear arithmetic.
+    Unknown,
+}
+
+/// The values of the free symbols of a formula in which it is false, as found by Z3.
+///
+/// Only the symbols Z3 assigns are recorded; any other symbol may take any value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CounterModel {
+    integers: BTreeMap<String, DomainElem>, // The value of each integer constant.
+    truth_values: BTreeMap<String, bool>,   // The truth value of each proposition.
+    text: String,                           // The whole model, as Z3 writes it.
+}
+
+impl CounterModel {
+    /// Returns the value of the integer variable or constant `name`, if Z3 assigned one that fits
+    /// a [`DomainElem`].
+    pub fn integer(&self, name: &str) -> Option<DomainElem> {
+        self.integers.get(name).copied()
+    }
+
+    /// Returns the truth value of the proposition `name`, if Z3 assigned one.
+    pub fn truth_value(&self, name: &str) -> Option<bool> {
+        self.truth_values.get(name).copied()
+    }
+}
+
+impl fmt::Display for CounterModel {
+    /// Formats the counter-model as Z3 writes it, including the interpretations of functions.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text.trim_end())
+    }
+}
+
+/// An error returned by [`Formula::check_validity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolverError {
+    /// The formula has no SMT-LIB counterpart, see [`Formula::to_smtlib`].
+    Export(ExportError),
+    /// Z3 rejected the exported script, or the linked Z3 is too old.
+    Solver { message: String },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::Export(error) => write!(f, "{error}"),
+            SolverError::Solver { message } => write!(f, "Z3 failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl From<ExportError> for SolverError {
+    fn from(error: ExportError) -> Self {
+        SolverError::Export(error)
+    }
+}
+
+/// Checks the validity of `formula` with Z3. See [`Formula::check_validity`].
+pub(crate) fn check_validity(
+    formula: &Formula,
+    config: &SolverConfig,
+) -> Result<Validity, SolverError> {
+    let script: CString = CString::new(smtlib::negated_assertion(formula, &config.logic)?)
+        .map_err(|error| SolverError::Solver {
+            message: error.to_string(),
+        })?;
+    check_version()?;
+    let context: Context = Context::new(config);
+    // SAFETY: every pointer passed to Z3 was created by Z3 in `context`, which outlives them, and
+    // the solver, the assertions and the model are referenced while they are used.
+    unsafe {
+        let assertions: *mut c_void = ffi::Z3_parse_smtlib2_string(
+            context.raw,
+            script.as_ptr(),
+            0,
+            [].as_ptr(),
+            [].as_ptr(),
+            0,
+            [].as_ptr(),
+            [].as_ptr(),
+        );
+        context.check()?;
+        ffi::Z3_ast_vector_inc_ref(context.raw, assertions);
+        let solver: *mut c_void = ffi::Z3_mk_solver(context.raw);
+        ffi::Z3_solver_inc_ref(context.raw, solver);
+        for index in 0..ffi::Z3_ast_vector_size(context.raw, assertions) {
+            let assertion: *mut c_void = ffi::Z3_ast_vector_get(context.raw, assertions, index);
+            ffi::Z3_solver_assert(context.raw, solver, assertion);
+        }
+        let validity: Result<Validity, SolverError> =
+            match ffi::Z3_solver_check(context.raw, solver) {
+                ffi::Z3_L_FALSE => Ok(Validity::Valid),
+                ffi::Z3_L_TRUE => Ok(Validity::Invalid(counter_model(&context, solver))),
+                _ => context.check().map(|()| Validity::Unknown),
+            };
+        ffi::Z3_solver_dec_ref(context.raw, solver);
+        ffi::Z3_ast_vector_dec_ref(context.raw, assertions);
+        validity
+    }
+}
+
+/// Checks that the linked Z3 is at least [`MIN_VERSION`].
+fn check_version() -> Result<(), SolverError> {
+    let (mut major, mut minor, mut build, mut revision): (c_uint, c_uint, c_uint, c_uint) =
+        (0, 0, 0, 0);
+    // SAFETY: `Z3_get_version` only writes the four numbers.
+    unsafe { ffi::Z3_get_version(&mut major, &mut minor, &mut build, &mut revision) };
+    if (major, minor) < MIN_VERSION {
+        return Err(SolverError::Solver {
+            message: format!(
+                "Z3 {}.{} or later is required, but {major}.{minor}.{build} is linked",
+                MIN_VERSION.0, MIN_VERSION.1
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Reads the model of the last satisfiable check of `solver`.
+///
+/// # Safety
+/// `solver` must belong to `context`, and its last check must have been satisfiable.
+unsafe fn counter_model(context: &Context, solver: *mut c_void) -> CounterModel {
+    let mut counter_model: CounterModel = CounterModel::default();
+    // SAFETY: the model belongs to `context` and is referenced while it is read.
+    unsafe {
+        let model: *mut c_void = ffi::Z3_solver_get_model(context.raw, solver);
+        ffi::Z3_model_inc_ref(context.raw, model);
+        for index in 0..ffi::Z3_model_get_num_consts(context.raw, model) {
+            let declaration: *mut c_void = ffi::Z3_model_get_const_decl(context.raw, model, index);
+            let symbol: *mut c_void = ffi::Z3_get_decl_name(context.raw, declaration);
+            let name: String = text(ffi::Z3_get_symbol_string(context.raw, symbol));
+            let value: *mut c_void =
+                ffi::Z3_model_get_const_interp(context.raw, model, declaration);
+            if value.is_null() {
+                continue;
+            }
+            let sort: *mut c_void = ffi::Z3_get_sort(context.raw, value);
+            match ffi::Z3_get_sort_kind(context.raw, sort) {
+                ffi::Z3_BOOL_SORT => {
+                    let truth_value: c_int = ffi::Z3_get_bool_value(context.raw, value);
+                    if truth_value != ffi::Z3_L_UNDEF {
+                        counter_model
+                            .truth_values
+                            .insert(name, truth_value == ffi::Z3_L_TRUE);
+                    }
+                }
+                ffi::Z3_INT_SORT => {
+                    let mut integer: i64 = 0;
+                    if ffi::Z3_get_numeral_int64(context.raw, value, &mut integer) {
+                        counter_model.integers.insert(name, integer);
+                    }
+                }
+                _ => {}
+            }
+        }
+        counter_model.text = text(ffi::Z3_model_to_string(context.raw, model));
+        ffi::Z3_model_dec_ref(context.raw, model);
+    }
+    counter_model
+}
+
+/// Copies a string owned by Z3.
+///
+/// # Safety
+/// `string` must be a valid null-terminated string.
+unsafe fn text(string: *const c_char) -> String {
+    // SAFETY: guaranteed by the caller.
+    unsafe { CStr::from_ptr(string) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A Z3 context, deleted when dropped.
+struct Context {
+    raw: *mut c_void, // The context, which owns every object created in it.
+}
+
+impl Context {
+    /// Creates a context configured as `config` says, which reports errors through
+    /// [`Context::check`] rather than aborting.
+    fn new(config: &SolverConfig) -> Self {
+        // SAFETY: the configuration is only used to create the context, and deleted after.
+        unsafe {
+            let configuration: *mut c_void = ffi::Z3_mk_config();
+            ffi::Z3_set_param_value(configuration, c"model".as_ptr(), c"true".as_ptr());
+            if let Some(timeout) = config.timeout {
+                let timeout: CString =
+                    CString::new(timeout.to_string()).expect("a number has no null byte");
+                ffi::Z3_set_param_value(configuration, c"timeout".as_ptr(), timeout.as_ptr());
+            }
+            let raw: *mut c_void = ffi::Z3_mk_context(configuration);
+            ffi::Z3_del_config(configuration);
+            ffi::Z3_set_error_handler(raw, None);
+            Context { raw }
+        }
+    }
+
+    /// Returns the error of the last call into Z3, if it failed.
+    fn check(&self) -> Result<(), SolverError> {
+        // SAFETY: the context is valid until it is dropped.
+        unsafe {
+            let code: c_int = ffi::Z3_get_error_code(self.raw);
+            if code == ffi::Z3_OK {
+                return Ok(());
+            }
+            Err(SolverError::Solver {
+                message: text(ffi::Z3_get_error_msg(self.raw, code)),
+            })
+        }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        // SAFETY: the context is not used after it is deleted.
+        unsafe { ffi::Z3_del_context(self.raw) }
+    }
+}
+
+/// The parts of the Z3 C API used to check validity, see `z3_api.h`.
+#[allow(non_snake_case)]
+mod ffi {
+    use std::ffi::{c_char, c_int, c_uint, c_void};
+
+    pub(super) const Z3_L_FALSE: c_int = -1;
+    pub(super) const Z3_L_UNDEF: c_int = 0;
+    pub(super) const Z3_L_TRUE: c_int = 1;
+    pub(super) const Z3_OK: c_int = 0;
+    pub(super) const Z3_BOOL_SORT: c_int = 1;
+    pub(super) const Z3_INT_SORT: c_int = 2;
+
+    #[link(name = "z3")]
+    unsafe extern "C" {
+        pub(super) fn Z3_get_version(
+            major: *mut c_uint,
+            minor: *mut c_uint,
+            build_number: *mut c_uint,
+            revision_number: *mut c_uint,
+        );
+        pub(super) fn Z3_mk_config() -> *mut c_void;
+        pub(super) fn Z3_del_config(config: *mut c_void);
+        pub(super) fn Z3_set_param_value(
+            config: *mut c_void,
+            param_id: *const c_char,
+            param_value: *const c_char,
+        );
+        pub(super) fn Z3_mk_context(config: *mut c_void) -> *mut c_void;
+        pub(super) fn Z3_del_context(context: *mut c_void);
+        pub(super) fn Z3_set_error_handler(
+            context: *mut c_void,
+            handler: Option<unsafe extern "C" fn(*mut c_void, c_int)>,
+        );
+        pub(super) fn Z3_get_error_code(context: *mut c_void) -> c_int;
+        pub(super) fn Z3_get_error_msg(context: *mut c_void, code: c_int) -> *const c_char;
+        pub(super) fn Z3_parse_smtlib2_string(
+            context: *mut c_void,
+            string: *const c_char,
+            num_sorts: c_uint,
+            sort_names: *const *mut c_void,
+            sorts: *const *mut c_void,
+            num_decls: c_uint,
+            decl_names: *const *mut c_void,
+            decls: *const *mut c_void,
+        ) -> *mut c_void;
+        pub(super) fn Z3_ast_vector_inc_ref(context: *mut c_void, vector: *mut c_void);
+        pub(super) fn Z3_ast_vector_dec_ref(context: *mut c_void, vector: *mut c_void);
+        pub(super) fn Z3_ast_vector_size(context: *mut c_void, vector: *mut c_void) -> c_uint;
+        pub(super) fn Z3_ast_vector_get(
+            context: *mut c_void,
+            vector: *mut c_void,
+            index: c_uint,
+        ) -> *mut c_void;
+        pub(super) fn Z3_mk_solver(context: *mut c_void) -> *mut c_void;
+        pub(super) fn Z3_solver_inc_ref(context: *mut c_void, solver: *mut c_void);
+        pub(super) fn Z3_solver_dec_ref(context: *mut c_void, solver: *mut c_void);
+        pub(super) fn Z3_solver_assert(context: *mut c_void, solver: *mut c_void, ast: *mut c_void);
+        pub(super) fn Z3_solver_check(context: *mut c_void, solver: *mut c_void) -> c_int;
+        pub(super) fn Z3_solver_get_model(context: *mut c_void, solver: *mut c_void)
+        -> *mut c_void;
+        pub(super) fn Z3_model_inc_ref(context: *mut c_void, model: *mut c_void);
+        pub(super) fn Z3_model_dec_ref(context: *mut c_void, model: *mut c_void);
+        pub(super) fn Z3_model_get_num_consts(context: *mut c_void, model: *mut c_void) -> c_uint;
+        pub(super) fn Z3_model_get_const_decl(
+            context: *mut c_void,
+            model: *mut c_void,
+            index: c_uint,
+        ) -> *mut c_void;
+        pub(super) fn Z3_model_get_const_interp(
+            context: *mut c_void,
+            model: *mut c_void,
+            declaration: *mut c_void,
+        ) -> *mut c_void;
+        pub(super) fn Z3_model_to_string(context: *mut c_void, model: *mut c_void)
+        -> *const c_char;
+        pub(super) fn Z3_get_decl_name(
+            context: *mut c_void,
+            declaration: *mut c_void,
+        ) -> *mut c_void;
+        pub(super) fn Z3_get_symbol_string(
+            context: *mut c_void,
+            symbol: *mut c_void,
+        ) -> *const c_char;
+        pub(super) fn Z3_get_sort(context: *mut c_void, ast: *mut c_void) -> *mut c_void;
+        pub(super) fn Z3_get_sort_kind(context: *mut c_void, sort: *mut c_void) -> c_int;
+        pub(super) fn Z3_get_bool_value(context: *mut c_void, ast: *mut c_void) -> c_int;
+        pub(super) fn Z3_get_numeral_int64(
+            context: *mut c_void,
+            ast: *mut c_void,
+            value: *mut i64,
+        ) -> bool;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn check(input: &str) -> Validity {
+        Formula::new(input)
+            .check_validity(&SolverConfig::new())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_valid_lemma() {
+        assert_eq!(check("→ = x 5 < x 6"), Validity::Valid);
+        assert_eq!(check("→ = x r+y*0 = x r"), Validity::Valid);
+        assert_eq!(check("∀ n → = fib(n) 1 = fib(n)+1 2"), Validity::Valid);
+    }
+
+    #[test]
+    fn test_counter_model() {
+        let Validity::Invalid(counter_model) = check("→ < x y < y x") else {
+            panic!("→ < x y < y x is not valid");
+        };
+        let (x, y): (DomainElem, DomainElem) = (
+            counter_model.integer("x").unwrap(),
+            counter_model.integer("y").unwrap(),
+        );
+        assert!(x < y, "{counter_model}");
+        let Validity::Invalid(counter_model) = check("→ P Q") else {
+            panic!("→ P Q is not valid");
+        };
+        assert_eq!(counter_model.truth_value("P"), Some(true));
+        assert_eq!(counter_model.truth_value("Q"), Some(false));
+        assert!(counter_model.to_string().contains("Q"), "{counter_model}");
+    }
+
+    #[test]
+    fn test_truncating_division() {
+        let env: HashMap<String, DomainElem> = HashMap::new();
+        for input in ["= -7%2 -1", "= -7/2 -3", "= 7%-2 1", "= -7/-2 3"] {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(
+                test_formula.eval_arith(&env, &HashMap::new()),
+                Ok(true),
+                "{input}"
+            );
+            assert_eq!(check(input), Validity::Valid, "{input}");
+        }
+        assert!(matches!(check("= -7%2 1"), Validity::Invalid(_)));
+        assert_eq!(check("→ ≠ b 0 = a (a/b)*b+a%b"), Validity::Valid);
+    }
+
+    #[test]
+    fn test_uninterpreted_functions() {
+        let Validity::Invalid(counter_model) = check("= fib(1) 1") else {
+            panic!("fib is uninterpreted");
+        };
+        assert!(counter_model.to_string().contains("fib"), "{counter_model}");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            Formula::new("= y x^2").check_validity(&SolverConfig::new()),
+            Err(SolverError::Export(_))
+        ));
+        // A logic that is not a symbol could smuggle in assertions, such as `(assert false)`,
+        // which would make every formula valid.
+        assert_eq!(
+            Formula::new("= x y").check_validity(SolverConfig::new().logic("QF_LIA)(assert false")),
+            Err(SolverError::Export(ExportError::InvalidSymbol {
+                name: "QF_LIA)(assert false".to_string()
+            }))
+        );
+        let null: Formula = Formula::Term(crate::Term::Var("P\0".to_string()));
+        assert!(matches!(
+            null.check_validity(&SolverConfig::new()),
+            Err(SolverError::Solver { .. })
+        ));
+        assert_eq!(check_version(), Ok(()));
+    }
+}
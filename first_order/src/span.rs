@@ -0,0 +1,199 @@
+//! # Source spans
+//!
+//! This module records which part of the input each subformula was parsed from, for
+//! [`Formula::parse_spanned`]. A [`SpanMap`] maps the [`FormulaPath`] of every subformula to its
+//! [`Span`], which gives both the half-open range of whitespace-separated tokens and the half-open
+//! range of byte offsets into the original input that the subformula covers.
+//!
+//! In prefix notation every subformula is written as one contiguous run of tokens: its own symbol,
+//! and for a quantifier its variable, followed by the tokens of its children in order. An equality
+//! or comparison of two terms covers its symbol and both terms. The spans are therefore found by
+//! walking the parsed formula in that order, counting tokens. Lookalike
+//! characters are replaced one for one before parsing, so the tokens of the normalised input are
+//! at the same positions as those of the original.
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::{Formula, FormulaPath};
+
+/// The part of the input a subformula was parsed from.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, FormulaPath, Span};
+///
+/// let (_, spans) = Formula::parse_spanned("∧ P(x) Q").unwrap();
+/// let span: &Span = spans.get(&FormulaPath(vec![0])).unwrap();
+/// assert_eq!(span.tokens, 1..2);
+/// assert_eq!(span.bytes, 4..8);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The indices of the tokens the subformula covers.
+    pub tokens: Range<usize>,
+    /// The byte offsets into the input of the text the subformula covers, from the start of its
+    /// first token to the end of its last token.
+    pub bytes: Range<usize>,
+}
+
+/// The [`Span`] of every subformula of a parsed formula, keyed by its path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanMap {
+    spans: BTreeMap<FormulaPath, Span>, // The span of each subformula, keyed by its path.
+}
+
+impl SpanMap {
+    /// Returns the span of the subformula `path` addresses, if it exists.
+    pub fn get(&self, path: &FormulaPath) -> Option<&Span> {
+        self.spans.get(path)
+    }
+
+    /// Returns the path and span of every subformula, in the order of their paths.
+    pub fn iter(&self) -> impl Iterator<Item = (&FormulaPath, &Span)> {
+        self.spans.iter()
+    }
+
+    /// Returns the number of subformulae with a span.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns whether no subformula has a span.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+/// A step of the walk over a parsed formula.
+enum Visit<'a> {
+    /// A subformula whose tokens start at the next token.
+    Enter(&'a Formula, FormulaPath),
+    /// The end of the subformula at the path, whose tokens started at the given token.
+    Exit(FormulaPath, usize),
+}
+
+/// Returns the byte offsets of the whitespace-separated tokens of `input`.
+pub(crate) fn token_bytes(input: &str) -> Vec<Range<usize>> {
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut start: Option<usize> = None;
+    for (offset, c) in input.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(first)) => {
+                tokens.push(first..offset);
+                start = None;
+            }
+            (false, None) => start = Some(offset),
+            _ => {}
+        }
+    }
+    if let Some(first) = start {
+        tokens.push(first..input.len());
+    }
+    tokens
+}
+
+/// Returns the spans of the subformulae of `formula`, parsed from the tokens at `tokens`.
+pub(crate) fn spans(formula: &Formula, tokens: &[Range<usize>]) -> SpanMap {
+    let mut spans: BTreeMap<FormulaPath, Span> = BTreeMap::new();
+    let mut next: usize = 0;
+    let mut stack: Vec<Visit<'_>> = vec![Visit::Enter(formula, FormulaPath::root())];
+    while let Some(visit) = stack.pop() {
+        match visit {
+            Visit::Enter(formula, path) => {
+                stack.push(Visit::Exit(path.clone(), next));
+                next += match formula {
+                    Formula::UniversalQuantifier(_, _) | Formula::ExistentialQuantifier(_, _) => 2,
+                    Formula::Equality(_, _) | Formula::Less(_, _) => 3,
+                    _ => 1,
+                };
+                let children: Vec<&Formula> = formula.children();
+                for (index, child) in children.into_iter().enumerate().rev() {
+                    stack.push(Visit::Enter(child, path.child(index)));
+                }
+            }
+            Visit::Exit(path, start) => {
+                let bytes: Range<usize> = tokens[start].start..tokens[next - 1].end;
+                spans.insert(
+                    path,
+                    Span {
+                        tokens: start..next,
+                        bytes,
+                    },
+                );
+            }
+        }
+    }
+    SpanMap { spans }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_root_covers_everything() {
+        let input: &str = "  ∧ ∀ x P(x)\n  Q ";
+        let (formula, spans) = Formula::parse_spanned(input).unwrap();
+        assert_eq!(formula, Formula::new(input));
+        assert_eq!(
+            spans.get(&FormulaPath::root()),
+            Some(&Span {
+                tokens: 0..5,
+                bytes: 2..input.len() - 1,
+            })
+        );
+        assert_eq!(spans.len(), formula.node_count());
+        for input in corpus::all() {
+            let (formula, spans) = Formula::parse_spanned(input).unwrap();
+            let root: &Span = spans.get(&FormulaPath::root()).unwrap();
+            assert_eq!(root.tokens, 0..input.split_whitespace().count());
+            assert_eq!(&input[root.bytes.clone()], input.trim());
+            for (path, span) in spans.iter() {
+                let subformula: &Formula = formula.get_at(path).unwrap();
+                assert_eq!(
+                    Formula::new(&input[span.bytes.clone()]),
+                    *subformula,
+                    "{path:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_second_conjunct_of_doc_example() {
+        let input: &str = corpus::DOC_EXAMPLE;
+        let (_, spans) = Formula::parse_spanned(input).unwrap();
+        let span: &Span = spans.get(&FormulaPath(vec![1])).unwrap();
+        assert_eq!(span.tokens, 12..18);
+        assert_eq!(&input[span.bytes.clone()], "= ¬ T(x) < U V");
+        let span: &Span = spans.get(&FormulaPath(vec![0, 0, 1, 1])).unwrap();
+        assert_eq!(&input[span.bytes.clone()], "∃ y ∨ R(y) S(y)");
+    }
+
+    #[test]
+    fn test_byte_offsets_around_multi_byte_symbols() {
+        // `∧` and `¬` are three and two bytes long.
+        let (_, spans) = Formula::parse_spanned("∧ ¬ P Q").unwrap();
+        let bytes: Vec<Range<usize>> = [vec![], vec![0], vec![0, 0], vec![1]]
+            .into_iter()
+            .map(|path| spans.get(&FormulaPath(path)).unwrap().bytes.clone())
+            .collect();
+        assert_eq!(bytes, [0..10, 4..8, 7..8, 9..10]);
+        // A lookalike is replaced by a symbol of another length, but the offsets are into the
+        // original input.
+        let input: &str = "⟶ P ＝ Q R";
+        let (formula, spans) = Formula::parse_spanned(input).unwrap();
+        assert_eq!(formula, Formula::new("→ P = Q R"));
+        let span: &Span = spans.get(&FormulaPath(vec![1])).unwrap();
+        assert_eq!(&input[span.bytes.clone()], "＝ Q R");
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert_eq!(
+            Formula::parse_spanned("∧ P").unwrap_err(),
+            Formula::try_new("∧ P").unwrap_err()
+        );
+    }
+}
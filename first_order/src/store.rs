@@ -0,0 +1,235 @@
+//! # Formula store
+//!
+//! This module provides [`FormulaStore`], which interns formulae: every structurally equal formula
+//! interned into the same store gets the same [`FormulaId`], so that two interned formulae are
+//! compared by comparing their ids, in constant time.
+//!
+//! The store hash-conses the formulae node by node. Each subformula is identified by its kind, its
+//! variable or symbol, and the identifiers of its children, so interning a formula hashes every
+//! node once, however deep the formula is, and a subformula shared by many formulae, such as an
+//! invariant repeated in every line of a proof, is only recorded once.
+use std::collections::HashMap;
+
+use crate::{Formula, FormulaKind};
+
+/// The identifier of a formula interned into a [`FormulaStore`].
+///
+/// Identifiers are only meaningful for the store that returned them: two identifiers from the
+/// same store are equal exactly when the formulae they stand for are structurally equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FormulaId(usize);
+
+/// The number of formulae and nodes a [`FormulaStore`] has interned, as returned by
+/// [`FormulaStore::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    /// The number of calls to [`FormulaStore::intern`].
+    pub insertions: usize,
+    /// The number of structurally different formulae interned.
+    pub unique: usize,
+    /// The number of nodes of all interned formulae, counting each insertion.
+    pub nodes: usize,
+    /// The number of structurally different subformulae of all interned formulae.
+    pub unique_nodes: usize,
+}
+
+/// A subformula, identified by its outermost node and the identifiers of its children.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Node {
+    /// A term, `⊤` or `⊥`.
+    Atom(Formula),
+    /// The kind of any other node, its variable or symbol if it has one, and its children.
+    Inner(FormulaKind, Option<String>, Vec<usize>),
+}
+
+/// A store interning structurally equal formulae under the same [`FormulaId`].
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, FormulaId, FormulaStore};
+///
+/// let mut store: FormulaStore = FormulaStore::new();
+/// let invariant: FormulaId = store.intern(Formula::new("∧ = x r+y*q ≤ 0 r"));
+/// let again: FormulaId = store.intern(Formula::new("∧ = x r+y*q ≤ 0 r"));
+/// let other: FormulaId = store.intern(Formula::new("∧ = x r+y*q ≤ y r"));
+/// assert_eq!(invariant, again);
+/// assert_ne!(invariant, other);
+/// assert_eq!(*store.get(invariant), Formula::new("∧ = x r+y*q ≤ 0 r"));
+///
+/// let stats = store.stats();
+/// assert_eq!((stats.insertions, stats.unique), (3, 2));
+/// ```
+#[derive(Debug, Default)]
+pub struct FormulaStore {
+    nodes: HashMap<Node, usize>, // The identifier of every subformula interned so far.
+    formulae: HashMap<usize, Formula>, // The interned formulae, keyed by their identifiers.
+    stats: StoreStats,           // The counts reported by `stats`.
+}
+
+/// A step of the walk interning a formula.
+enum Visit<'a> {
+    /// A subformula whose children have not been interned yet.
+    Enter(&'a Formula),
+    /// A subformula whose children have just been interned.
+    Exit(&'a Formula),
+}
+
+impl FormulaStore {
+    /// Creates an empty `FormulaStore`.
+    pub fn new() -> Self {
+        FormulaStore::default()
+    }
+
+    /// Interns `formula`, returning the identifier shared by every structurally equal formula
+    /// interned into this store.
+    ///
+    /// # Arguments
+    /// * `formula` - The `Formula` to intern, which the store keeps if it has not seen it before.
+    ///
+    /// # Returns
+    /// The `FormulaId` of `formula`.
+    pub fn intern(&mut self, formula: Formula) -> FormulaId {
+        let id: usize = self.intern_nodes(&formula);
+        self.stats.insertions += 1;
+        self.formulae.entry(id).or_insert(formula);
+        self.stats.unique = self.formulae.len();
+        FormulaId(id)
+    }
+
+    /// Returns the formula interned under `id`.
+    ///
+    /// # Panics
+    /// Panics if `id` was not returned by this store.
+    pub fn get(&self, id: FormulaId) -> &Formula {
+        match self.formulae.get(&id.0) {
+            Some(formula) => formula,
+            None => panic!("{id:?} was not returned by this store"),
+        }
+    }
+
+    /// Returns whether the formulae interned under `lhs` and `rhs` are structurally equal, in
+    /// constant time. This is the same as comparing the identifiers.
+    pub fn equal(&self, lhs: FormulaId, rhs: FormulaId) -> bool {
+        lhs == rhs
+    }
+
+    /// Returns how many formulae and nodes have been interned, counting both every insertion and
+    /// only the structurally different ones.
+    pub fn stats(&self) -> StoreStats {
+        self.stats
+    }
+
+    /// Interns every subformula of `formula`, children first, and returns the identifier of
+    /// `formula` itself.
+    fn intern_nodes(&mut self, formula: &Formula) -> usize {
+        let mut ids: Vec<usize> = Vec::new();
+        let mut stack: Vec<Visit<'_>> = vec![Visit::Enter(formula)];
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Enter(formula) => {
+                    stack.push(Visit::Exit(formula));
+                    // The children are pushed in reverse, so that their identifiers end up in order.
+                    stack.extend(formula.children().into_iter().rev().map(Visit::Enter));
+                }
+                Visit::Exit(formula) => {
+                    let node: Node = match formula {
+                        Formula::Term(_)
+                        | Formula::Top
+                        | Formula::Bottom
+                        | Formula::Equality(_, _)
+                        | Formula::Less(_, _)
+                        | Formula::Predicate(_, _) => Node::Atom(formula.clone()),
+                        _ => {
+                            let children: Vec<usize> =
+                                ids.split_off(ids.len() - formula.children().len());
+                            let label: Option<String> = match formula {
+                                Formula::UniversalQuantifier(variable, _)
+                                | Formula::ExistentialQuantifier(variable, _) => {
+                                    Some(variable.clone())
+                                }
+                                Formula::Custom(symbol, _, _) => Some(symbol.clone()),
+                                _ => None,
+                            };
+                            Node::Inner(formula.kind(), label, children)
+                        }
+                    };
+                    let next: usize = self.nodes.len();
+                    ids.push(*self.nodes.entry(node).or_insert(next));
+                    self.stats.nodes += 1;
+                }
+            }
+        }
+        self.stats.unique_nodes = self.nodes.len();
+        ids.pop().expect("the formula itself is interned last")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_fibonacci_formulae_share_nodes() {
+        let mut store: FormulaStore = FormulaStore::new();
+        let ids: Vec<FormulaId> = corpus::FIBONACCI
+            .iter()
+            .chain(corpus::FIBONACCI.iter())
+            .map(|input| store.intern(Formula::new(*input)))
+            .collect();
+        assert_eq!(ids[..10], ids[10..]);
+        let stats: StoreStats = store.stats();
+        assert_eq!((stats.insertions, stats.unique), (20, 10));
+        assert!(stats.unique_nodes * 5 < stats.nodes, "{stats:?}");
+        for (id, input) in ids.iter().zip(corpus::FIBONACCI) {
+            assert_eq!(*store.get(*id), Formula::new(input));
+        }
+    }
+
+    #[test]
+    fn test_id_equality_agrees_with_partial_eq() {
+        let mut store: FormulaStore = FormulaStore::new();
+        let formulae: Vec<Formula> = corpus::all()
+            .map(Formula::new)
+            .chain([
+                Formula::new("∀ x P(x)"),
+                Formula::new("∀ y P(x)"),
+                Formula::new("∃ x P(x)"),
+                Formula::new("< x y"),
+                Formula::new("< y x"),
+                Formula::new("⊤"),
+                Formula::new("⊥"),
+            ])
+            .collect();
+        let ids: Vec<FormulaId> = formulae
+            .iter()
+            .map(|formula| store.intern(formula.clone()))
+            .collect();
+        for (lhs, lhs_id) in formulae.iter().zip(&ids) {
+            for (rhs, rhs_id) in formulae.iter().zip(&ids) {
+                assert_eq!(store.equal(*lhs_id, *rhs_id), lhs == rhs, "{lhs} {rhs}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_deep_formula() {
+        let mut deep: Formula = Formula::new("P");
+        for _ in 0..100_000 {
+            deep = Formula::Negation(Box::new(deep));
+        }
+        let mut store: FormulaStore = FormulaStore::new();
+        let id: FormulaId = store.intern(deep);
+        assert_eq!(store.stats().unique_nodes, 100_001);
+        assert_eq!(store.get(id).depth(), 100_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "was not returned by this store")]
+    fn test_get_unknown_id() {
+        let mut store: FormulaStore = FormulaStore::new();
+        store.intern(Formula::new("∧ P Q"));
+        // The identifier of a subformula is never returned.
+        store.get(FormulaId(0));
+    }
+}
@@ -0,0 +1,211 @@
+//! # Symbol tables
+//!
+//! This module provides [`SymbolTable`], which tells whether a name such as `N`, `input` or
+//! `fib` stands for a variable, a constant or a function, for [`Formula::free_variables_with`]
+//! and [`Formula::substitute_term_with`]. A name can be declared explicitly, and any name that is
+//! not declared is classified by the following convention:
+//!
+//! | Name                                        | Class      |
+//! |---------------------------------------------|------------|
+//! | a single letter, such as `x` or `N`         | `Variable` |
+//! | assigned to by a command, such as `index`   | `Variable` |
+//! | applied to arguments, such as `fib`         | `Function` |
+//! | any other name, such as `input`             | `Constant` |
+//!
+//! The names assigned to are only known once the commands are given to
+//! [`SymbolTable::command`], so the empty table, which is used where no table is supplied,
+//! classifies every name of more than one letter as a constant.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Formula, Term, variables};
+
+/// The class of a name, see [`SymbolTable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolClass {
+    /// A name whose value a program may change, which can be substituted.
+    Variable,
+    /// A name whose value is fixed, such as the input of a program.
+    Constant,
+    /// A function symbol taking the given number of arguments.
+    Function(usize),
+}
+
+/// The classes of the names of formulae and commands, declared or by convention.
+///
+/// # Example
+/// ```
+/// use first_order::{SymbolClass, SymbolTable};
+///
+/// let mut symbols: SymbolTable = SymbolTable::new();
+/// symbols.command("index≔index+1").constant("N").function("fib", 1);
+/// assert_eq!(symbols.classify("index"), SymbolClass::Variable);
+/// assert_eq!(symbols.classify("N"), SymbolClass::Constant);
+/// assert_eq!(symbols.classify("fib"), SymbolClass::Function(1));
+/// assert_eq!(symbols.classify("x"), SymbolClass::Variable);
+/// assert_eq!(symbols.classify("input"), SymbolClass::Constant);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    declared: BTreeMap<String, SymbolClass>, // The class of each declared name.
+}
+
+impl SymbolTable {
+    /// Creates an empty `SymbolTable`, which classifies every name by the convention.
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    /// Declares `name` as a variable.
+    pub fn variable(&mut self, name: &str) -> &mut Self {
+        self.declare(name, SymbolClass::Variable)
+    }
+
+    /// Declares `name` as a constant.
+    pub fn constant(&mut self, name: &str) -> &mut Self {
+        self.declare(name, SymbolClass::Constant)
+    }
+
+    /// Declares `name` as a function symbol taking `arity` arguments.
+    pub fn function(&mut self, name: &str, arity: usize) -> &mut Self {
+        self.declare(name, SymbolClass::Function(arity))
+    }
+
+    /// Declares every name on the left of an assignment of `command` as a variable, whether the
+    /// assignment is written with `≔` or `:=`.
+    pub fn command(&mut self, command: &str) -> &mut Self {
+        for assignment in command.replace(":=", "≔").split('≔').rev().skip(1) {
+            let name: &str = assignment
+                .trim_end()
+                .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                .unwrap_or_default();
+            if !name.is_empty() {
+                self.variable(name);
+            }
+        }
+        self
+    }
+
+    /// Returns the class of `name`, as declared or by the convention.
+    ///
+    /// A name that is applied to arguments is a function symbol whatever its class, so this is
+    /// only consulted for names that are not.
+    pub fn classify(&self, name: &str) -> SymbolClass {
+        match self.declared.get(name) {
+            Some(class) => *class,
+            None if name.chars().count() == 1 => SymbolClass::Variable,
+            None => SymbolClass::Constant,
+        }
+    }
+
+    /// Returns the class `name` was declared with, if it was declared.
+    pub fn declared(&self, name: &str) -> Option<SymbolClass> {
+        self.declared.get(name).copied()
+    }
+
+    /// Returns whether `name` is classified as a variable.
+    pub fn is_variable(&self, name: &str) -> bool {
+        self.classify(name) == SymbolClass::Variable
+    }
+
+    fn declare(&mut self, name: &str, class: SymbolClass) -> &mut Self {
+        self.declared.insert(name.to_string(), class);
+        self
+    }
+}
+
+/// Returns the free variables of `formula` that `symbols` classifies as variables. See
+/// [`Formula::free_variables_with`].
+pub(crate) fn free_variables(formula: &Formula, symbols: &SymbolTable) -> BTreeSet<String> {
+    variables::free(formula)
+        .into_iter()
+        .filter(|name| symbols.is_variable(name))
+        .collect()
+}
+
+/// Replaces the free occurrences of `variable` in `formula` if `symbols` classifies it as a
+/// variable. See [`Formula::substitute_term_with`].
+pub(crate) fn substitute_term(
+    formula: &Formula,
+    variable: &str,
+    replacement: &Term,
+    symbols: &SymbolTable,
+) -> Formula {
+    if symbols.is_variable(variable) {
+        formula.substitute_term(variable, replacement)
+    } else {
+        formula.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fibonacci_invariant() {
+        let invariant: Formula = Formula::new(corpus::FIBONACCI[4]);
+        assert_eq!(
+            invariant.free_variables(),
+            names(&["N", "curFib", "index", "input", "preFib"])
+        );
+        // Without a table, only the single letter `N` is a variable.
+        assert_eq!(invariant.free_variables_with(None), names(&["N"]));
+        let mut symbols: SymbolTable = SymbolTable::new();
+        symbols.command("preFib≔curFib;curFib≔preFib+curFib;index:=index+1");
+        assert_eq!(
+            invariant.free_variables_with(Some(&symbols)),
+            names(&["N", "curFib", "index", "preFib"])
+        );
+        symbols.constant("N");
+        assert_eq!(
+            invariant.free_variables_with(Some(&symbols)),
+            names(&["curFib", "index", "preFib"])
+        );
+        symbols.variable("input").function("fib", 1);
+        assert_eq!(
+            invariant.free_variables_with(Some(&symbols)),
+            names(&["curFib", "index", "input", "preFib"])
+        );
+    }
+
+    #[test]
+    fn test_substitution() {
+        let test_formula: Formula = Formula::new("∧ < index N = N input");
+        let replacement: Term = Term::new("index+1");
+        let mut symbols: SymbolTable = SymbolTable::new();
+        assert_eq!(
+            test_formula.substitute_term_with("index", &replacement, Some(&symbols)),
+            test_formula
+        );
+        symbols.command("while index<N do index≔index+1 od");
+        assert_eq!(
+            test_formula.substitute_term_with("index", &replacement, Some(&symbols)),
+            Formula::new("∧ < index+1 N = N input")
+        );
+        symbols.constant("N");
+        assert_eq!(
+            test_formula.substitute_term_with("N", &replacement, Some(&symbols)),
+            test_formula
+        );
+        assert_eq!(
+            test_formula.substitute_term_with("N", &replacement, None),
+            Formula::new("∧ < index index+1 = index+1 input")
+        );
+    }
+
+    #[test]
+    fn test_command() {
+        let mut symbols: SymbolTable = SymbolTable::new();
+        symbols.command("temp≔b;b ≔ mod(a,b);a:=temp");
+        for name in ["temp", "b", "a"] {
+            assert_eq!(symbols.classify(name), SymbolClass::Variable, "{name}");
+        }
+        assert_eq!(symbols.classify("mod"), SymbolClass::Constant);
+    }
+}
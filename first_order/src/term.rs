@@ -0,0 +1,1077 @@
+//! # Terms
+//!
+//! This module provides a structured representation of the terms appearing inside a `Formula`,
+//! such as `x`, `43`, `fib(index+1)` or `gcd(a,mod(a,b))`.
+//!
+//! A term is parsed into a tree of variables, constants and function applications. The arithmetic
+//! operators `+`, `-`, `*`, `/`, `%` and `^` are read with their usual precedence and are
+//! represented as applications of the function named by the operator, so `index+1` is the
+//! application of `+` to `index` and `1`. Parentheses written around a term are kept as an
+//! application of the empty function name, which lets [`Term`]'s `Display` reproduce the original
+//! spelling of every parsed term. The remainder is written `%`, or with the keyword `mod` between
+//! its operands, as in `a mod b`, which is read as `a%b` and written back with `%`, since a term
+//! in prefix notation cannot contain whitespace. `mod(a,b)`, on the other hand, is an ordinary
+//! application of the function symbol `mod`.
+//!
+//! A name starting with a digit is a number, made of digits and decimal points, so `1x` is an
+//! error. A number is an integer literal, a [`Term::IntLit`], if it is the canonical spelling of
+//! an `i64`, and a [`Term::Const`] keeping its spelling otherwise, so `007` and `7` stay distinct
+//! and `1.5` is kept as written. Any other name that is not applied to arguments is
+//! a [`Term::Var`], whether or not it is ever assigned; the parser cannot tell a named constant
+//! such as `input` from a variable, so a `Const` with such a name is only built in code, see
+//! [`SymbolTable`](crate::SymbolTable) for classifying names. A name cannot contain
+//! whitespace or a logical or relational symbol such as `=` or `∧`, so that the infix notation of
+//! a formula reads each of its terms back whole.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::infix::SYMBOL_CHARS;
+use crate::{ArithFunction, DomainElem, EvalError, TermPrintOptions, arith, normalize, print};
+
+/// An enum representing the terms of first order logic.
+///
+/// A `Term` is defined as follows:
+/// - Every variable is a term.
+/// - Every constant symbol is a term.
+/// - If `f` is an arity `m` function symbol and `a,b,...,m` are terms, then `f(a,b,...,m)` is a term.
+///
+/// Terms are ordered by variant first, variables before integer literals before other constants
+/// before function applications, and then by their fields, so literals are compared as numbers,
+/// names as strings and arguments from left to right.
+///
+/// Like `Formula`, a `Term` implements `Drop`, so that a deeply nested term is dropped without
+/// overflowing the call stack. Its fields therefore cannot be moved out of it by a pattern: match
+/// on a reference and clone them, or take them with [`std::mem::take`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Term {
+    /// A `Var` is a name that is not applied to any argument, e.g. `index`.
+    Var(String),
+    /// An `IntLit` is an integer literal, e.g. `43`. A negative number such as `-3` is the
+    /// negation of a literal.
+    IntLit(i64),
+    /// A `Const` is a constant symbol other than an integer literal, kept as spelled, e.g. `007`
+    /// or `1.5`.
+    Const(String),
+    /// An `App` is a function application of the form `f(a,b,...,m)` where `a,b,...,m` are terms.
+    ///
+    /// Arithmetic is represented with the operator as the function name, e.g. `a+b` is
+    /// `App("+", [a, b])` and `-a` is `App("-", [a])`. A parenthesised term `(a)` is
+    /// `App("", [a])`.
+    App(String, Vec<Term>),
+}
+
+impl Drop for Term {
+    /// Drops the arguments with an explicit stack, so that dropping a deeply nested term, such as
+    /// the sum `1+1+…+1` of a hundred thousand numerals, cannot overflow the call stack.
+    fn drop(&mut self) {
+        let Term::App(_, args) = self else {
+            return;
+        };
+        let mut stack: Vec<Term> = std::mem::take(args);
+        while let Some(mut term) = stack.pop() {
+            if let Term::App(_, args) = &mut term {
+                stack.append(args);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    /// Formats the term in its conventional infix spelling.
+    ///
+    /// A parsed term is reproduced exactly, apart from any whitespace. Parentheses are only added
+    /// around an operand that would otherwise be read differently.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// let test_term: Term = Term::new("(r-y)+y*(1+q)");
+    /// assert_eq!(test_term.to_string(), "(r-y)+y*(1+q)");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        print::write_term(self, &TermPrintOptions::default(), f)
+    }
+}
+
+impl std::str::FromStr for Term {
+    type Err = TermParseError;
+
+    /// Parses a `Term` from a string. This is equivalent to [`Term::parse`].
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Term::parse(input)
+    }
+}
+
+impl Term {
+    /// Creates a new `Term` from a string input.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the term, e.g. `fib(index+1)`.
+    ///
+    /// # Returns
+    /// A `Term` instance representing the parsed term.
+    ///
+    /// # Panics
+    /// This function will panic if the input string is not a well-formed term.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// let test_term: Term = Term::new("mod(a,b)");
+    /// let result: Term = Term::App(
+    ///     "mod".to_string(),
+    ///     vec![
+    ///         Term::Var("a".to_string()),
+    ///         Term::Var("b".to_string()),
+    ///     ],
+    /// );
+    /// assert_eq!(test_term, result);
+    /// ```
+    pub fn new(input: &str) -> Self {
+        match Term::parse(input) {
+            Ok(term) => term,
+            Err(error) => panic!("The term {input:?} is malformed: {error}."),
+        }
+    }
+    /// Builds the constant spelled `spelling`: an [`Term::IntLit`] if `spelling` is the canonical
+    /// spelling of an `i64`, and a [`Term::Const`] otherwise, as [`Term::parse`] reads it.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// assert_eq!(Term::constant("43"), Term::IntLit(43));
+    /// assert_eq!(Term::constant("043"), Term::Const("043".to_string()));
+    /// assert_eq!(Term::constant("input"), Term::Const("input".to_string()));
+    /// ```
+    pub fn constant(spelling: impl Into<String>) -> Term {
+        let spelling: String = spelling.into();
+        match spelling.parse::<i64>() {
+            Ok(value) if value.to_string() == spelling => Term::IntLit(value),
+            _ => Term::Const(spelling),
+        }
+    }
+    /// Creates a new `Term` from a string input without panicking.
+    ///
+    /// # Arguments
+    /// * `input` - A `&str` that represents the term, e.g. `fib(index+1)`.
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed `Term`, or a `TermParseError` if the input is malformed.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// assert_eq!(Term::parse("x"), Ok(Term::Var("x".to_string())));
+    /// assert!(Term::parse("f(a,").is_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, TermParseError> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut parser: TermParser<'_> = TermParser {
+            chars: &chars,
+            current: 0,
+        };
+        let term: Term = parser.parse_additive()?;
+        parser.skip_whitespace();
+        if parser.current != chars.len() {
+            return Err(parser.error("an operator or the end of the term"));
+        }
+        Ok(term)
+    }
+
+    /// Returns the names of the variables occurring in the term.
+    ///
+    /// Function symbols and numeric constants are not variables, so `fib(index+1)` only contains
+    /// `index`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use first_order::Term;
+    ///
+    /// let variables: BTreeSet<String> = Term::new("gcd(a,mod(a,b))+1").variables();
+    /// assert_eq!(variables, BTreeSet::from(["a".to_string(), "b".to_string()]));
+    /// ```
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut variables: BTreeSet<String> = BTreeSet::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut BTreeSet<String>) {
+        match self {
+            Term::Var(name) => {
+                variables.insert(name.clone());
+            }
+            Term::IntLit(_) | Term::Const(_) => {}
+            Term::App(_, args) => {
+                for arg in args {
+                    arg.collect_variables(variables);
+                }
+            }
+        }
+    }
+
+    /// Counts the occurrences of the identifier `name` in the term, as a variable or as a function
+    /// symbol.
+    ///
+    /// Identifiers are compared whole, so `index` occurs once in `fib(index)+1` but not in
+    /// `index2`. Constants never match.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// assert_eq!(Term::new("fib(index)+fib(index-1)").occurrences("index"), 2);
+    /// assert_eq!(Term::new("fib(index)+fib(index-1)").occurrences("fib"), 2);
+    /// assert_eq!(Term::new("index2+1").occurrences("index"), 0);
+    /// ```
+    pub fn occurrences(&self, name: &str) -> usize {
+        match self {
+            Term::Var(variable) => usize::from(variable == name),
+            Term::IntLit(_) | Term::Const(_) => 0,
+            Term::App(function, args) => {
+                usize::from(!function.is_empty() && function == name)
+                    + args.iter().map(|arg| arg.occurrences(name)).sum::<usize>()
+            }
+        }
+    }
+
+    /// Returns whether the term applies one of the arithmetic operators `+`, `-`, `*`, `/`, `%` or
+    /// `^` anywhere.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// assert!(Term::new("fib(index+1)").has_arithmetic());
+    /// assert!(!Term::new("gcd(a,(b))").has_arithmetic());
+    /// ```
+    pub fn has_arithmetic(&self) -> bool {
+        match self {
+            Term::Var(_) | Term::IntLit(_) | Term::Const(_) => false,
+            Term::App(name, args) => is_operator(name) || args.iter().any(Term::has_arithmetic),
+        }
+    }
+
+    /// Rebuilds the term, replacing the name of every variable and the spelling of every constant
+    /// with the result of `f`.
+    ///
+    /// Function symbols and the shape of the term are kept, so a variable stays a variable and a
+    /// constant stays a constant.
+    ///
+    /// # Arguments
+    /// * `f` - A closure mapping the text of a leaf to its replacement.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// let test_term: Term = Term::new("fib(index+1)-fib(index2)");
+    /// let renamed: Term = test_term.map_leaves(|leaf| match leaf {
+    ///     "index" => "i".to_string(),
+    ///     _ => leaf.to_string(),
+    /// });
+    /// assert_eq!(renamed, Term::new("fib(i+1)-fib(index2)"));
+    /// ```
+    pub fn map_leaves(&self, mut f: impl FnMut(&str) -> String) -> Term {
+        self.map_leaves_with(&mut f)
+    }
+
+    fn map_leaves_with(&self, f: &mut impl FnMut(&str) -> String) -> Term {
+        match self {
+            Term::Var(name) => Term::Var(f(name)),
+            Term::IntLit(value) => Term::constant(f(&value.to_string())),
+            Term::Const(value) => Term::constant(f(value)),
+            Term::App(name, args) => Term::App(
+                name.clone(),
+                args.iter().map(|arg| arg.map_leaves_with(f)).collect(),
+            ),
+        }
+    }
+
+    /// Replaces every occurrence of the variable `variable` with `replacement`.
+    ///
+    /// Function symbols are never replaced, so substituting for `fib` in `fib(fib)` only replaces
+    /// the argument. An operator whose operand is replaced is written with the parentheses its
+    /// precedence needs.
+    ///
+    /// # Arguments
+    /// * `variable` - The name of the variable to replace.
+    /// * `replacement` - The `Term` to put in its place.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// let test_term: Term = Term::new("2*y+fib(y)");
+    /// let substituted: Term = test_term.substitute("y", &Term::new("x+1"));
+    /// assert_eq!(substituted.to_string(), "2*(x+1)+fib(x+1)");
+    /// ```
+    pub fn substitute(&self, variable: &str, replacement: &Term) -> Term {
+        match self {
+            Term::Var(name) if name == variable => replacement.clone(),
+            Term::Var(_) | Term::IntLit(_) | Term::Const(_) => self.clone(),
+            Term::App(name, args) => Term::App(
+                name.clone(),
+                args.iter()
+                    .map(|arg| arg.substitute(variable, replacement))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Evaluates the term to an integer on the concrete state `env`.
+    ///
+    /// The arithmetic operators are built in, and every other function symbol is looked up in
+    /// `funcs`. See the `arith` module for details.
+    ///
+    /// # Arguments
+    /// * `env` - The values of the variables.
+    /// * `funcs` - The functions other than the arithmetic operators, e.g. `fib` or `gcd`.
+    ///
+    /// # Returns
+    /// A `Result` containing the value of the term, or an `EvalError` if a variable is missing
+    /// from `env`, a function is missing from `funcs` or undefined for its arguments, or the
+    /// term divides by zero or overflows.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use first_order::{ArithFunction, EvalError, Term};
+    ///
+    /// fn fib(args: &[i64]) -> Option<i64> {
+    ///     let (mut previous, mut current): (i64, i64) = (0, 1);
+    ///     for _ in 1..args[0] {
+    ///         (previous, current) = (current, previous.checked_add(current)?);
+    ///     }
+    ///     Some(current)
+    /// }
+    ///
+    /// let env = HashMap::from([("index".to_string(), 5)]);
+    /// let funcs: HashMap<String, ArithFunction> = HashMap::from([("fib".to_string(), fib as ArithFunction)]);
+    /// assert_eq!(Term::new("fib(index+1)").eval(&env, &funcs), Ok(8));
+    /// assert_eq!(
+    ///     Term::new("index/(index-5)").eval(&env, &funcs),
+    ///     Err(EvalError::DivisionByZero { term: "index/(index-5)".to_string() })
+    /// );
+    /// ```
+    pub fn eval(
+        &self,
+        env: &HashMap<String, DomainElem>,
+        funcs: &HashMap<String, ArithFunction>,
+    ) -> Result<DomainElem, EvalError> {
+        arith::eval_term(self, env, funcs)
+    }
+
+    /// Rebuilds the term without the parentheses written around its subterms.
+    ///
+    /// Parsed parentheses are kept as applications of the empty function name, so that `Display`
+    /// reproduces the original spelling. Once they are removed, `Display` only writes the
+    /// parentheses the precedence and associativity of the operators require, and parsing the
+    /// result gives the same tree again.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// let test_term: Term = Term::new("(r-y)+(y*(1+q))");
+    /// assert_eq!(test_term.without_parentheses().to_string(), "r-y+y*(1+q)");
+    /// assert_eq!(
+    ///     Term::new("r-y+y*(1+q)").without_parentheses(),
+    ///     test_term.without_parentheses()
+    /// );
+    /// ```
+    pub fn without_parentheses(&self) -> Term {
+        match self {
+            Term::Var(_) | Term::IntLit(_) | Term::Const(_) => self.clone(),
+            Term::App(name, args) if name.is_empty() && args.len() == 1 => {
+                args[0].without_parentheses()
+            }
+            Term::App(name, args) => Term::App(
+                name.clone(),
+                args.iter().map(Term::without_parentheses).collect(),
+            ),
+        }
+    }
+
+    /// Rewrites the term into a normal spelling, so that terms such as `index+1-1` and `index`,
+    /// which always have the same value, become equal.
+    ///
+    /// Parentheses are dropped, and `x+0`, `x*0`, `x*1`, `x-x`, `a+b-b` and operators applied to
+    /// integer literals are simplified until none is left. See the `normalize` module for details.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Term;
+    ///
+    /// assert_eq!(Term::new("index+1-1").normalize(), Term::new("index"));
+    /// assert_eq!(Term::new("x+y*0").normalize(), Term::new("x"));
+    /// assert_eq!(Term::new("(2+3)*x").normalize().to_string(), "5*x");
+    /// ```
+    pub fn normalize(&self) -> Term {
+        normalize::normalize(self)
+    }
+
+    /// Formats the term as `options` say, e.g. with spaces around the operators or `·` for
+    /// multiplication.
+    ///
+    /// # Arguments
+    /// * `options` - How the term is written. The default options write it as `Display` does.
+    ///
+    /// # Returns
+    /// The term written with the given options.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{Term, TermPrintOptions};
+    ///
+    /// let test_term: Term = Term::new("(r-y)+y*(1+q)");
+    /// let mut options: TermPrintOptions = TermPrintOptions::new();
+    /// assert_eq!(test_term.to_string_with(&options), test_term.to_string());
+    /// options.spaced_operators(true).multiplication_dot(true);
+    /// assert_eq!(test_term.to_string_with(&options), "(r - y) + y · (1 + q)");
+    /// ```
+    pub fn to_string_with(&self, options: &TermPrintOptions) -> String {
+        let mut out: String = String::new();
+        print::write_term(self, options, &mut out).expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Returns the binding strength of the outermost operator of the term.
+    pub(crate) fn precedence(&self) -> u8 {
+        match self {
+            Term::App(name, args) => match (name.as_str(), args.len()) {
+                ("+" | "-", 2) => ADDITIVE,
+                ("*" | "/" | "%", 2) => MULTIPLICATIVE,
+                ("-", 1) => UNARY,
+                ("^", 2) => POWER,
+                _ => PRIMARY,
+            },
+            _ => PRIMARY,
+        }
+    }
+}
+
+/// A mapping from variable names to the terms that replace them, as returned by [`unify`].
+///
+/// The substitutions returned by [`unify`] are idempotent: no binding mentions a variable that is
+/// itself bound.
+///
+/// # Example
+/// ```
+/// use first_order::term::{Term, TermSubst};
+///
+/// let mut substitution: TermSubst = TermSubst::new();
+/// substitution.bind("X", Term::new("index+1"));
+/// let test_term: Term = substitution.apply(&Term::new("fib(X)-X"));
+/// assert_eq!(test_term.to_string(), "fib(index+1)-(index+1)");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TermSubst {
+    bindings: BTreeMap<String, Term>, // The term replacing each variable.
+}
+
+impl TermSubst {
+    /// Creates an empty `TermSubst`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `variable` to `value`, replacing any previous binding.
+    pub fn bind(&mut self, variable: impl Into<String>, value: Term) -> &mut Self {
+        self.bindings.insert(variable.into(), value);
+        self
+    }
+
+    /// Returns the term bound to `variable`, if any.
+    pub fn get(&self, variable: &str) -> Option<&Term> {
+        self.bindings.get(variable)
+    }
+
+    /// Returns the number of bound variables.
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    /// Returns whether no variable is bound.
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Returns the bindings, ordered by variable name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Term)> {
+        self.bindings
+            .iter()
+            .map(|(variable, value)| (variable.as_str(), value))
+    }
+
+    /// Replaces every bound variable of `term` with its binding, all at once.
+    pub fn apply(&self, term: &Term) -> Term {
+        match term {
+            Term::Var(name) => self.get(name).unwrap_or(term).clone(),
+            Term::IntLit(_) | Term::Const(_) => term.clone(),
+            Term::App(name, args) => Term::App(
+                name.clone(),
+                args.iter().map(|arg| self.apply(arg)).collect(),
+            ),
+        }
+    }
+
+    /// Returns the substitution applying `self` and then `then`.
+    ///
+    /// A variable bound by `self` is bound to its binding with `then` applied, and a variable
+    /// only bound by `then` keeps its binding from `then`.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::term::{Term, TermSubst};
+    ///
+    /// let mut first: TermSubst = TermSubst::new();
+    /// first.bind("X", Term::new("f(Y)"));
+    /// let mut then: TermSubst = TermSubst::new();
+    /// then.bind("Y", Term::new("a")).bind("X", Term::new("b"));
+    /// let composed: TermSubst = first.compose(&then);
+    /// assert_eq!(composed.get("X"), Some(&Term::new("f(a)")));
+    /// assert_eq!(composed.get("Y"), Some(&Term::new("a")));
+    /// let test_term: Term = Term::new("g(X,Y)");
+    /// assert_eq!(composed.apply(&test_term), then.apply(&first.apply(&test_term)));
+    /// ```
+    pub fn compose(&self, then: &TermSubst) -> TermSubst {
+        let mut bindings: BTreeMap<String, Term> = then.bindings.clone();
+        for (variable, value) in &self.bindings {
+            bindings.insert(variable.clone(), then.apply(value));
+        }
+        TermSubst { bindings }
+    }
+}
+
+/// Finds the most general substitution of `variables` that makes the terms `a` and `b` identical.
+///
+/// Function symbols must be equal and have as many arguments, and a variable is never bound to a
+/// term containing itself. The formula-level [`unify`](crate::unify()) unifies the terms of two
+/// formulae in the same way.
+///
+/// # Arguments
+/// * `a` - A reference to the first `Term`.
+/// * `b` - A reference to the second `Term`.
+/// * `variables` - The names that are unification variables, in either term.
+///
+/// # Returns
+/// `Some` with the `TermSubst` if there is one, or `None` if the terms differ in a function symbol
+/// or constant, or a variable would be bound to a term containing itself.
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeSet;
+/// use first_order::term::{self, Term, TermSubst};
+///
+/// let variables: BTreeSet<String> = BTreeSet::from(["X".to_string()]);
+/// let substitution: TermSubst =
+///     term::unify(&Term::new("fib(X)"), &Term::new("fib(index+1)"), &variables).unwrap();
+/// assert_eq!(substitution.get("X"), Some(&Term::new("index+1")));
+/// assert_eq!(term::unify(&Term::new("X"), &Term::new("f(X)"), &variables), None);
+/// ```
+pub fn unify(a: &Term, b: &Term, variables: &BTreeSet<String>) -> Option<TermSubst> {
+    crate::unify::unify_terms(a, b, variables)
+}
+
+pub(crate) const ADDITIVE: u8 = 1;
+pub(crate) const MULTIPLICATIVE: u8 = 2;
+pub(crate) const UNARY: u8 = 3;
+pub(crate) const POWER: u8 = 4;
+pub(crate) const PRIMARY: u8 = 5;
+
+/// Returns whether `name` is one of the binary arithmetic operators.
+pub(crate) fn is_operator(name: &str) -> bool {
+    matches!(name, "+" | "-" | "*" | "/" | "%" | "^")
+}
+
+/// Returns whether `name` is an arithmetic operator or the parentheses of a `Term`, rather than a
+/// function symbol.
+pub(crate) fn is_built_in(name: &str) -> bool {
+    name.is_empty() || is_operator(name)
+}
+
+/// An error returned when a string cannot be parsed into a `Term`.
+///
+/// # Example
+/// ```
+/// use first_order::{Term, TermParseError};
+///
+/// let error: TermParseError = Term::parse("fib(index").unwrap_err();
+/// assert_eq!(error.index, 9);
+/// assert_eq!(
+///     error.to_string(),
+///     "expected ',' or ')' at character 9, found end of term"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermParseError {
+    /// The index of the character at which the parser failed.
+    pub index: usize,
+    /// The offending character, or `None` if the term ended before the parser was done.
+    pub found: Option<char>,
+    /// A description of what the parser expected at `index`.
+    pub expected: String,
+}
+
+impl fmt::Display for TermParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} at character {}, found ",
+            self.expected, self.index
+        )?;
+        match self.found {
+            Some(c) => write!(f, "{c:?}"),
+            None => write!(f, "end of term"),
+        }
+    }
+}
+
+impl std::error::Error for TermParseError {}
+
+/// A recursive descent parser over the characters of a term.
+struct TermParser<'a> {
+    chars: &'a [char], // The characters of the term.
+    current: usize,    // The current index in the character slice.
+}
+
+impl TermParser<'_> {
+    /// Builds a `TermParseError` located at the current character.
+    fn error(&self, expected: &str) -> TermParseError {
+        TermParseError {
+            index: self.current,
+            found: self.chars.get(self.current).copied(),
+            expected: expected.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.current)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.current += 1;
+        }
+    }
+
+    /// Returns the next non-whitespace character without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.current).copied()
+    }
+
+    /// Consumes the next character if it is one of `operators`.
+    fn eat_any(&mut self, operators: &[char]) -> Option<char> {
+        let c: char = self.peek().filter(|c| operators.contains(c))?;
+        self.current += 1;
+        Some(c)
+    }
+
+    fn parse_additive(&mut self) -> Result<Term, TermParseError> {
+        let mut left: Term = self.parse_multiplicative()?;
+        while let Some(operator) = self.eat_any(&['+', '-']) {
+            let right: Term = self.parse_multiplicative()?;
+            left = Term::App(operator.to_string(), vec![left, right]);
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Term, TermParseError> {
+        let mut left: Term = self.parse_unary()?;
+        while let Some(operator) = self
+            .eat_any(&['*', '/', '%'])
+            .or_else(|| self.eat_mod().then_some('%'))
+        {
+            let right: Term = self.parse_unary()?;
+            left = Term::App(operator.to_string(), vec![left, right]);
+        }
+        Ok(left)
+    }
+
+    /// Consumes the keyword `mod` if it comes next as a whole word, which after an operand can only
+    /// be the remainder operator.
+    fn eat_mod(&mut self) -> bool {
+        self.skip_whitespace();
+        let end: usize = self.current + 3;
+        if self.chars.get(self.current..end) != Some(&['m', 'o', 'd'])
+            || self.chars.get(end).is_some_and(|c| is_name_char(*c))
+        {
+            return false;
+        }
+        self.current = end;
+        true
+    }
+
+    fn parse_unary(&mut self) -> Result<Term, TermParseError> {
+        if self.eat_any(&['-']).is_some() {
+            let inner: Term = self.parse_unary()?;
+            return Ok(Term::App("-".to_string(), vec![inner]));
+        }
+        self.parse_power()
+    }
+
+    /// Parses a power, which is right-associative and may have a negated exponent.
+    fn parse_power(&mut self) -> Result<Term, TermParseError> {
+        let base: Term = self.parse_primary()?;
+        if self.eat_any(&['^']).is_some() {
+            let exponent: Term = self.parse_unary()?;
+            return Ok(Term::App("^".to_string(), vec![base, exponent]));
+        }
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> Result<Term, TermParseError> {
+        if self.eat_any(&['(']).is_some() {
+            let inner: Term = self.parse_additive()?;
+            if self.eat_any(&[')']).is_none() {
+                return Err(self.error("')'"));
+            }
+            return Ok(Term::App(String::new(), vec![inner]));
+        }
+
+        let start: usize = self.current;
+        if self.chars.get(start).is_some_and(|c| c.is_ascii_digit()) {
+            while self
+                .chars
+                .get(self.current)
+                .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+            {
+                self.current += 1;
+            }
+            if self
+                .chars
+                .get(self.current)
+                .is_some_and(|c| is_name_char(*c))
+            {
+                return Err(self.error("a digit"));
+            }
+            return Ok(Term::constant(
+                self.chars[start..self.current].iter().collect::<String>(),
+            ));
+        }
+        while self
+            .chars
+            .get(self.current)
+            .is_some_and(|c| is_name_char(*c))
+        {
+            self.current += 1;
+        }
+        if self.current == start {
+            return Err(self.error("a variable, constant or function"));
+        }
+        let name: String = self.chars[start..self.current].iter().collect();
+
+        if self.chars.get(self.current) == Some(&'(') {
+            self.current += 1;
+            let mut args: Vec<Term> = vec![self.parse_additive()?];
+            while self.eat_any(&[',']).is_some() {
+                args.push(self.parse_additive()?);
+            }
+            if self.eat_any(&[')']).is_none() {
+                return Err(self.error("',' or ')'"));
+            }
+            return Ok(Term::App(name, args));
+        }
+        Ok(Term::Var(name))
+    }
+}
+
+/// Returns whether `c` may be part of a name.
+fn is_name_char(c: char) -> bool {
+    !c.is_whitespace() && !"+-*/%^(),".contains(c) && !SYMBOL_CHARS.contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Formula, corpus};
+
+    fn var(name: &str) -> Term {
+        Term::Var(name.to_string())
+    }
+
+    fn constant(value: &str) -> Term {
+        Term::constant(value)
+    }
+
+    fn app(name: &str, args: Vec<Term>) -> Term {
+        Term::App(name.to_string(), args)
+    }
+
+    #[test]
+    fn test_parse_function_with_arithmetic_argument() {
+        assert_eq!(
+            Term::new("fib(index+1)"),
+            app("fib", vec![app("+", vec![var("index"), Term::IntLit(1)])])
+        );
+    }
+
+    #[test]
+    fn test_parse_function_with_several_arguments() {
+        assert_eq!(Term::new("mod(a,b)"), app("mod", vec![var("a"), var("b")]));
+    }
+
+    #[test]
+    fn test_parse_nested_functions() {
+        assert_eq!(
+            Term::new("gcd(a,mod(a,b))"),
+            app("gcd", vec![var("a"), app("mod", vec![var("a"), var("b")])])
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence_and_associativity() {
+        assert_eq!(
+            Term::new("x+y*0"),
+            app("+", vec![var("x"), app("*", vec![var("y"), constant("0")])])
+        );
+        assert_eq!(
+            Term::new("index+1-1"),
+            app(
+                "-",
+                vec![app("+", vec![var("index"), constant("1")]), constant("1")]
+            )
+        );
+        assert_eq!(
+            Term::new("r*10^(p)"),
+            app(
+                "*",
+                vec![
+                    var("r"),
+                    app("^", vec![constant("10"), app("", vec![var("p")])])
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_substitute() {
+        let replacement: Term = Term::new("x+1");
+        assert_eq!(
+            Term::new("y").substitute("y", &replacement),
+            app("+", vec![var("x"), constant("1")])
+        );
+        assert_eq!(
+            Term::new("fib(y)-y*y")
+                .substitute("y", &replacement)
+                .to_string(),
+            "fib(x+1)-(x+1)*(x+1)"
+        );
+        assert_eq!(
+            Term::new("fib(fib)")
+                .substitute("fib", &replacement)
+                .to_string(),
+            "fib(x+1)"
+        );
+        let test_term: Term = Term::new("yy+43");
+        assert_eq!(test_term.substitute("y", &replacement), test_term);
+    }
+
+    #[test]
+    fn test_parse_mod_keyword() {
+        assert_eq!(Term::new("a mod b"), Term::new("a%b"));
+        assert_eq!(
+            Term::new("a+b mod c*d"),
+            app(
+                "+",
+                vec![
+                    var("a"),
+                    app("*", vec![app("%", vec![var("b"), var("c")]), var("d")])
+                ]
+            )
+        );
+        assert_eq!(
+            Term::new("a mod b mod c"),
+            app("%", vec![app("%", vec![var("a"), var("b")]), var("c")])
+        );
+        assert_eq!(Term::new("-a mod (b)^2"), Term::new("-a%(b)^2"));
+        // `mod` is only the operator after an operand, and only as a whole word.
+        assert_eq!(
+            Term::new("mod(a,b) mod mod(b,a)"),
+            app(
+                "%",
+                vec![
+                    app("mod", vec![var("a"), var("b")]),
+                    app("mod", vec![var("b"), var("a")])
+                ]
+            )
+        );
+        assert_eq!(Term::new("modulus"), var("modulus"));
+        assert!(Term::parse("a modb").is_err());
+        assert!(Term::parse("a mod").is_err());
+        for input in ["a mod b", "(a+b) mod c", "a mod (b mod c)", "fib(n mod 2)"] {
+            let test_term: Term = Term::new(input);
+            assert!(!test_term.to_string().contains("mod"), "{input}");
+            assert_eq!(Term::new(&test_term.to_string()), test_term, "{input}");
+        }
+        assert_eq!(Term::new("(a+b) mod c").to_string(), "(a+b)%c");
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        let cases: [(&str, &str); 8] = [
+            ("a+(b*c)", "a+b*c"),
+            ("(a+b)*c", "(a+b)*c"),
+            ("(a-b)-c", "a-b-c"),
+            ("a-(b-c)", "a-(b-c)"),
+            ("(r-y)+y*(1+q)", "r-y+y*(1+q)"),
+            ("r*10^(p)", "r*10^p"),
+            ("(a^b)^c", "(a^b)^c"),
+            ("-(a^b)", "-a^b"),
+        ];
+        for (input, minimal) in cases {
+            let test_term: Term = Term::new(input).without_parentheses();
+            assert_eq!(test_term.to_string(), minimal, "{input}");
+            assert_eq!(
+                Term::new(minimal).without_parentheses(),
+                test_term,
+                "{input}"
+            );
+        }
+        assert_eq!(
+            Term::new("a-b-c"),
+            app("-", vec![app("-", vec![var("a"), var("b")]), var("c")])
+        );
+        assert_eq!(
+            Term::new("a^b^c"),
+            app("^", vec![var("a"), app("^", vec![var("b"), var("c")])])
+        );
+    }
+
+    #[test]
+    fn test_corpus_terms_round_trip() {
+        for input in corpus::all() {
+            for token in input.split_whitespace() {
+                if let Ok(Formula::Term(term)) = &Formula::try_new(token) {
+                    assert_eq!(Term::new(&term.to_string()), *term, "{token}");
+                    let minimal: Term = term.without_parentheses();
+                    assert_eq!(
+                        Term::new(&minimal.to_string()).without_parentheses(),
+                        minimal,
+                        "{token}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_subterm() {
+        let left: Term = Term::new("preFib+curFib-preFib");
+        let right: Term = Term::new("curFib-preFib");
+        let Term::App(_, args) = &left else {
+            panic!("expected a function application");
+        };
+        assert_eq!(args[0], app("+", vec![var("preFib"), var("curFib")]));
+        assert_eq!(right, app("-", vec![var("curFib"), var("preFib")]));
+    }
+
+    #[test]
+    fn test_display_reproduces_spelling() {
+        for input in corpus::all() {
+            for token in input.split_whitespace() {
+                if let Ok(Formula::Term(term)) = &Formula::try_new(token) {
+                    assert_eq!(term.to_string(), token);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_adds_required_parentheses() {
+        let sum: Term = app("+", vec![var("a"), var("b")]);
+        assert_eq!(app("*", vec![sum.clone(), var("c")]).to_string(), "(a+b)*c");
+        assert_eq!(app("-", vec![var("c"), sum.clone()]).to_string(), "c-(a+b)");
+        assert_eq!(app("-", vec![sum.clone()]).to_string(), "-(a+b)");
+        assert_eq!(app("^", vec![sum, var("c")]).to_string(), "(a+b)^c");
+    }
+
+    #[test]
+    fn test_variables() {
+        let names =
+            |term: &str| -> Vec<String> { Term::new(term).variables().into_iter().collect() };
+        assert_eq!(names("r+y*q"), ["q", "r", "y"]);
+        assert_eq!(names("fib(index+1)-fib(index)"), ["index"]);
+        assert_eq!(names("r*10^(p)"), ["p", "r"]);
+        assert!(names("43").is_empty());
+    }
+
+    #[test]
+    fn test_map_leaves() {
+        let test_term: Term = Term::new("gcd(a,mod(a,b))+10");
+        assert_eq!(
+            test_term.map_leaves(str::to_uppercase),
+            Term::new("gcd(A,mod(A,B))+10")
+        );
+        assert_eq!(
+            test_term.map_leaves(|leaf| leaf.replace('0', "1")),
+            Term::new("gcd(a,mod(a,b))+11")
+        );
+        assert_eq!(test_term.map_leaves(str::to_string), test_term);
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let error: TermParseError = Term::parse("f(a,,b)").unwrap_err();
+        assert_eq!(error.index, 4);
+        assert_eq!(error.found, Some(','));
+        assert!(Term::parse("").is_err());
+        assert!(Term::parse("x+").is_err());
+        assert!(Term::parse("(x").is_err());
+        assert_eq!(Term::parse("x)").unwrap_err().index, 1);
+        // A name cannot contain a logical or relational symbol.
+        for input in ["x=y", "a<b", "f(x≠y)", "¬p"] {
+            assert!(Term::parse(input).is_err(), "{input}");
+        }
+        // A name cannot start with a digit.
+        let error: TermParseError = Term::parse("1x").unwrap_err();
+        assert_eq!((error.index, error.found), (1, Some('x')));
+        for input in ["x+2y", "f(007a)", "1.5e3", "3fib(x)"] {
+            assert!(Term::parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_literals_and_names() {
+        assert_eq!(Term::new("43"), Term::IntLit(43));
+        assert_eq!(Term::IntLit(43).to_string(), "43");
+        assert_eq!(Term::new("-3"), app("-", vec![Term::IntLit(3)]));
+        // A spelling that is not canonical, or does not fit an `i64`, is kept as a `Const`.
+        for spelling in ["007", "1.5", "99999999999999999999"] {
+            assert_eq!(Term::new(spelling), Term::Const(spelling.to_string()));
+            assert_eq!(Term::new(spelling).to_string(), spelling);
+        }
+        assert_eq!(Term::Const("input".to_string()).to_string(), "input");
+        assert_eq!(Term::new("N"), var("N"));
+        assert_eq!(
+            Term::new("f(1,N)"),
+            app("f", vec![Term::IntLit(1), var("N")])
+        );
+        let mut terms: Vec<Term> = vec![
+            Term::new("f(x)"),
+            Term::new("007"),
+            Term::new("10"),
+            Term::new("9"),
+            Term::new("x"),
+        ];
+        terms.sort();
+        assert_eq!(
+            terms,
+            vec![
+                var("x"),
+                Term::IntLit(9),
+                Term::IntLit(10),
+                constant("007"),
+                Term::new("f(x)")
+            ]
+        );
+    }
+}
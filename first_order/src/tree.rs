@@ -0,0 +1,205 @@
+//! # Tree rendering
+//!
+//! This module draws a formula as an indented tree, one node per line, for
+//! [`Formula::to_tree_string`] and [`Formula::to_tree_string_with`]. Each line holds the kind of
+//! the node, followed by the variable of a quantifier, the symbol of a `Custom` connective, the
+//! text of a term, or an `Equality`, `Less` or `Predicate` in prefix notation:
+//!
+//! ```text
+//! Conjunction
+//! ├─ UniversalQuantifier x
+//! │  └─ Predicate P(x)
+//! └─ Negation
+//!    └─ Term Q
+//! ```
+use std::fmt::Write;
+
+use crate::Formula;
+
+/// The characters [`Formula::to_tree_string_with`] draws the branches of a tree with.
+///
+/// By default the branches are drawn with box-drawing characters. Each connector is followed by a
+/// space, and the indentation below a connector is as wide as the connector.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, TreeOptions};
+///
+/// let test_formula: Formula = Formula::new("∧ P Q");
+/// assert_eq!(test_formula.to_tree_string(), "Conjunction\n├─ Term P\n└─ Term Q\n");
+///
+/// let mut options = TreeOptions::new();
+/// options.ascii(true);
+/// assert_eq!(
+///     test_formula.to_tree_string_with(&options),
+///     "Conjunction\n|- Term P\n`- Term Q\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeOptions {
+    branch: String,   // The connector of a child with later siblings.
+    last: String,     // The connector of the last child.
+    vertical: String, // The indentation below a child with later siblings.
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        TreeOptions {
+            branch: "├─".to_string(),
+            last: "└─".to_string(),
+            vertical: "│ ".to_string(),
+        }
+    }
+}
+
+impl TreeOptions {
+    /// Creates `TreeOptions` drawing with box-drawing characters.
+    pub fn new() -> Self {
+        TreeOptions::default()
+    }
+
+    /// Sets whether the branches are drawn with the ASCII characters `|-`, `` `- `` and `|`
+    /// rather than with box-drawing characters.
+    pub fn ascii(&mut self, enabled: bool) -> &mut Self {
+        let (branch, last, vertical): (&str, &str, &str) = if enabled {
+            ("|-", "`-", "| ")
+        } else {
+            ("├─", "└─", "│ ")
+        };
+        self.connectors(branch, last, vertical)
+    }
+
+    /// Sets the connector of a child with later siblings, the connector of the last child, and
+    /// the indentation below a child with later siblings. The indentation below the last child is
+    /// blank.
+    pub fn connectors(&mut self, branch: &str, last: &str, vertical: &str) -> &mut Self {
+        self.branch = branch.to_string();
+        self.last = last.to_string();
+        self.vertical = vertical.to_string();
+        self
+    }
+}
+
+/// Draws `formula` as an indented tree. See [`Formula::to_tree_string_with`].
+pub(crate) fn to_tree_string(formula: &Formula, options: &TreeOptions) -> String {
+    let blank: String = " ".repeat(options.last.chars().count());
+    let mut tree: String = String::new();
+    // Each pending node carries the indentation of its line and of the lines of its children.
+    let mut stack: Vec<(&Formula, String, String)> = vec![(formula, String::new(), String::new())];
+    while let Some((current, line, indent)) = stack.pop() {
+        tree.push_str(&line);
+        tree.push_str(current.kind().name());
+        match current {
+            Formula::Term(term) => {
+                let _ = write!(tree, " {term}");
+            }
+            Formula::Equality(_, _) | Formula::Less(_, _) | Formula::Predicate(_, _) => {
+                let _ = write!(tree, " {}", current.to_prefix_notation());
+            }
+            Formula::UniversalQuantifier(detail, _)
+            | Formula::ExistentialQuantifier(detail, _)
+            | Formula::Custom(detail, _, _) => {
+                let _ = write!(tree, " {detail}");
+            }
+            _ => {}
+        }
+        tree.push('\n');
+        let children: Vec<&Formula> = current.children();
+        for (index, child) in children.iter().enumerate().rev() {
+            let (connector, below): (&str, &str) = if index + 1 == children.len() {
+                (&options.last, &blank)
+            } else {
+                (&options.branch, &options.vertical)
+            };
+            stack.push((
+                child,
+                format!("{indent}{connector} "),
+                format!("{indent}{below} "),
+            ));
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_doc_example() {
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(
+            test_formula.to_tree_string(),
+            "\
+Conjunction
+├─ UniversalQuantifier x
+│  └─ Implication
+│     ├─ Predicate P(x)
+│     └─ Conjunction
+│        ├─ Predicate Q(x)
+│        └─ ExistentialQuantifier y
+│           └─ Disjunction
+│              ├─ Predicate R(y)
+│              └─ Predicate S(y)
+└─ Equivalence
+   ├─ Negation
+   │  └─ Predicate T(x)
+   └─ Less < U V
+"
+        );
+        // A comparison built by hand over two `Term` formulae is drawn with its operands.
+        let legacy: Formula =
+            Formula::LessThan(Box::new(Formula::term("U")), Box::new(Formula::term("V")));
+        assert_eq!(legacy.to_tree_string(), "LessThan\n├─ Term U\n└─ Term V\n");
+    }
+
+    #[test]
+    fn test_doc_example_ascii() {
+        let mut options: TreeOptions = TreeOptions::new();
+        options.ascii(true);
+        let test_formula: Formula = Formula::new(corpus::DOC_EXAMPLE);
+        assert_eq!(
+            test_formula.to_tree_string_with(&options),
+            "\
+Conjunction
+|- UniversalQuantifier x
+|  `- Implication
+|     |- Predicate P(x)
+|     `- Conjunction
+|        |- Predicate Q(x)
+|        `- ExistentialQuantifier y
+|           `- Disjunction
+|              |- Predicate R(y)
+|              `- Predicate S(y)
+`- Equivalence
+   |- Negation
+   |  `- Predicate T(x)
+   `- Less < U V
+"
+        );
+    }
+
+    #[test]
+    fn test_single_term() {
+        assert_eq!(
+            Formula::new("fib(index-1)").to_tree_string(),
+            "Predicate fib(index-1)\n"
+        );
+        assert_eq!(
+            Formula::term("fib(index-1)").to_tree_string(),
+            "Term fib(index-1)\n"
+        );
+        assert_eq!(Formula::Top.to_tree_string(), "Top\n");
+    }
+
+    #[test]
+    fn test_custom_connectors() {
+        let mut options: TreeOptions = TreeOptions::new();
+        options.connectors("+--", "\\--", ":  ");
+        assert_eq!(
+            Formula::new("→ ¬ P Q").to_tree_string_with(&options),
+            "Implication\n+-- Negation\n:   \\-- Term P\n\\-- Term Q\n"
+        );
+    }
+}
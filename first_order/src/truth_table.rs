@@ -0,0 +1,366 @@
+//! # Truth tables
+//!
+//! This module evaluates a quantifier-free formula under every assignment of truth values to its
+//! atoms, for [`Formula::truth_table`] and [`Formula::truth_table_with`].
+//!
+//! The atoms are the atomic propositions, such as `P(x)`, and the relations between two terms,
+//! such as `< y r` or `= x r+y*q`, each identified by its prefix notation. The meaning of the
+//! terms is not taken into account, so `= x y` and `≠ x y` are unrelated atoms, and `= x x` is
+//! not a tautology.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{EvalError, Formula};
+
+/// The truth value of a formula under every assignment to its atoms, as returned by
+/// [`Formula::truth_table`].
+///
+/// The rows are ordered like in a textbook table: the first row assigns `true` to every atom and
+/// the last row assigns `false` to every atom.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, TruthTable};
+///
+/// let table: TruthTable = Formula::new("→ P ∧ P Q").truth_table().unwrap();
+/// assert_eq!(table.atoms(), ["P", "Q"]);
+/// assert!(!table.is_tautology());
+/// assert_eq!(
+///     table.to_string(),
+///     "P | Q | → P ∧ P Q\n\
+///      --+---+----------\n\
+///      T | T | T\n\
+///      T | F | F\n\
+///      F | T | T\n\
+///      F | F | T\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    formula: String,    // The prefix notation of the formula.
+    atoms: Vec<String>, // The atoms, in the order of their first occurrence.
+    results: Vec<bool>, // The value of the formula in each row.
+}
+
+impl TruthTable {
+    /// The largest number of atoms [`Formula::truth_table`] accepts, giving 2^20 rows.
+    pub const DEFAULT_LIMIT: usize = 20;
+
+    /// Returns the atoms of the formula, in the order of their first occurrence.
+    pub fn atoms(&self) -> &[String] {
+        &self.atoms
+    }
+
+    /// Returns the assignment of row `row`, in the order of [`TruthTable::atoms`].
+    fn assignment(&self, row: usize) -> Vec<bool> {
+        let count: usize = self.atoms.len();
+        (0..count)
+            .map(|index| (row >> (count - 1 - index)) & 1 == 0)
+            .collect()
+    }
+
+    /// Returns whether the formula holds in every row.
+    pub fn is_tautology(&self) -> bool {
+        self.results.iter().all(|result| *result)
+    }
+
+    /// Returns whether the formula holds in no row.
+    pub fn is_contradiction(&self) -> bool {
+        !self.results.iter().any(|result| *result)
+    }
+
+    /// Returns the assignments under which the formula holds, in the order of the rows.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use first_order::Formula;
+    ///
+    /// let table = Formula::new("∧ P ¬ Q").truth_table().unwrap();
+    /// assert_eq!(
+    ///     table.satisfying_assignments(),
+    ///     [BTreeMap::from([("P".to_string(), true), ("Q".to_string(), false)])]
+    /// );
+    /// ```
+    pub fn satisfying_assignments(&self) -> Vec<BTreeMap<String, bool>> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| **result)
+            .map(|(row, _)| {
+                self.atoms
+                    .iter()
+                    .cloned()
+                    .zip(self.assignment(row))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for TruthTable {
+    /// Formats the table with a column per atom and a last column for the formula, writing `T`
+    /// and `F` for the truth values.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers: Vec<&str> = self
+            .atoms
+            .iter()
+            .map(String::as_str)
+            .chain([self.formula.as_str()])
+            .collect();
+        let widths: Vec<usize> = headers
+            .iter()
+            .map(|header| header.chars().count())
+            .collect();
+        let cells: Vec<String> = headers
+            .iter()
+            .zip(&widths)
+            .map(|(header, width)| format!("{header:<width$}"))
+            .collect();
+        writeln!(f, "{}", cells.join(" | ").trim_end())?;
+        let rules: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        writeln!(f, "{}", rules.join("-+-"))?;
+        for (row, result) in self.results.iter().enumerate() {
+            let values: Vec<bool> = self.assignment(row);
+            let cells: Vec<String> = values
+                .iter()
+                .chain([result])
+                .zip(&widths)
+                .map(|(value, width)| format!("{:<width$}", if *value { "T" } else { "F" }))
+                .collect();
+            writeln!(f, "{}", cells.join(" | ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A quantifier-free formula whose atoms are replaced by their index in the table.
+pub(crate) enum Proposition {
+    Atom(usize),
+    Constant(bool),
+    Not(Box<Proposition>),
+    And(Box<Proposition>, Box<Proposition>),
+    Or(Box<Proposition>, Box<Proposition>),
+    Implies(Box<Proposition>, Box<Proposition>),
+    Iff(Box<Proposition>, Box<Proposition>),
+}
+
+impl Proposition {
+    /// Evaluates the proposition, `row` holding the value of each atom.
+    fn holds(&self, row: &[bool]) -> bool {
+        match self {
+            Proposition::Atom(index) => row[*index],
+            Proposition::Constant(value) => *value,
+            Proposition::Not(inner) => !inner.holds(row),
+            Proposition::And(lhs, rhs) => lhs.holds(row) && rhs.holds(row),
+            Proposition::Or(lhs, rhs) => lhs.holds(row) || rhs.holds(row),
+            Proposition::Implies(lhs, rhs) => !lhs.holds(row) || rhs.holds(row),
+            Proposition::Iff(lhs, rhs) => lhs.holds(row) == rhs.holds(row),
+        }
+    }
+}
+
+/// Builds the truth table of `formula`, failing if it has more than `limit` atoms. See
+/// [`Formula::truth_table_with`].
+pub(crate) fn truth_table(formula: &Formula, limit: usize) -> Result<TruthTable, EvalError> {
+    let mut atoms: Vec<String> = Vec::new();
+    let proposition: Proposition = compile(formula, &mut atoms)?;
+    if atoms.len() > limit {
+        return Err(EvalError::TooManyAtoms {
+            atoms: atoms.len(),
+            limit,
+        });
+    }
+    let mut table: TruthTable = TruthTable {
+        formula: formula.to_prefix_notation(),
+        atoms,
+        results: Vec::new(),
+    };
+    table.results = (0..1_usize << table.atoms.len())
+        .map(|row| proposition.holds(&table.assignment(row)))
+        .collect();
+    Ok(table)
+}
+
+/// Replaces the atoms of `formula` by their index in `atoms`, adding the new ones.
+pub(crate) fn compile(
+    formula: &Formula,
+    atoms: &mut Vec<String>,
+) -> Result<Proposition, EvalError> {
+    Ok(match formula {
+        Formula::Term(_)
+        | Formula::Equality(_, _)
+        | Formula::Less(_, _)
+        | Formula::Predicate(_, _) => atom(formula, atoms),
+        Formula::Top => Proposition::Constant(true),
+        Formula::Bottom => Proposition::Constant(false),
+        Formula::Negation(inner) => Proposition::Not(Box::new(compile(inner, atoms)?)),
+        Formula::Conjunction(lhs, rhs) => binary(Proposition::And, lhs, rhs, atoms)?,
+        Formula::Disjunction(lhs, rhs) => binary(Proposition::Or, lhs, rhs, atoms)?,
+        Formula::Implication(lhs, rhs) => binary(Proposition::Implies, lhs, rhs, atoms)?,
+        Formula::Equivalence(lhs, rhs) => match (&**lhs, &**rhs) {
+            (Formula::Term(_), Formula::Term(_)) => atom(formula, atoms),
+            _ => binary(Proposition::Iff, lhs, rhs, atoms)?,
+        },
+        Formula::Iff(lhs, rhs) => binary(Proposition::Iff, lhs, rhs, atoms)?,
+        Formula::NotEqual(lhs, rhs) => relation(formula, "≠", lhs, rhs, atoms)?,
+        Formula::LessThan(lhs, rhs) => relation(formula, "<", lhs, rhs, atoms)?,
+        Formula::LessEqual(lhs, rhs) => relation(formula, "≤", lhs, rhs, atoms)?,
+        Formula::GreaterEqual(lhs, rhs) => relation(formula, "≥", lhs, rhs, atoms)?,
+        Formula::GreaterThan(lhs, rhs) => relation(formula, ">", lhs, rhs, atoms)?,
+        Formula::UniversalQuantifier(variable, _) | Formula::ExistentialQuantifier(variable, _) => {
+            return Err(EvalError::NotQuantifierFree {
+                variable: variable.clone(),
+            });
+        }
+        Formula::Custom(symbol, _, _) => {
+            return Err(EvalError::UnsupportedConnective {
+                symbol: symbol.clone(),
+            });
+        }
+    })
+}
+
+type Connective = fn(Box<Proposition>, Box<Proposition>) -> Proposition;
+
+/// Compiles the operands of a binary connective and joins them with `connective`.
+fn binary(
+    connective: Connective,
+    lhs: &Formula,
+    rhs: &Formula,
+    atoms: &mut Vec<String>,
+) -> Result<Proposition, EvalError> {
+    let lhs: Proposition = compile(lhs, atoms)?;
+    let rhs: Proposition = compile(rhs, atoms)?;
+    Ok(connective(Box::new(lhs), Box::new(rhs)))
+}
+
+/// Returns the relation `formula` as an atom, provided that its operands are terms.
+fn relation(
+    formula: &Formula,
+    symbol: &str,
+    lhs: &Formula,
+    rhs: &Formula,
+    atoms: &mut Vec<String>,
+) -> Result<Proposition, EvalError> {
+    match (lhs, rhs) {
+        (Formula::Term(_), Formula::Term(_)) => Ok(atom(formula, atoms)),
+        _ => Err(EvalError::UnsupportedConnective {
+            symbol: symbol.to_string(),
+        }),
+    }
+}
+
+/// Returns the atom `formula`, adding it to `atoms` if it is new.
+fn atom(formula: &Formula, atoms: &mut Vec<String>) -> Proposition {
+    let name: String = formula.to_prefix_notation();
+    match atoms.iter().position(|atom| *atom == name) {
+        Some(index) => Proposition::Atom(index),
+        None => {
+            atoms.push(name);
+            Proposition::Atom(atoms.len() - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    #[test]
+    fn test_tautology_and_contradiction() {
+        let table: TruthTable = Formula::new("→ P P").truth_table().unwrap();
+        assert!(table.is_tautology());
+        assert!(!table.is_contradiction());
+        let table: TruthTable = Formula::new("∧ P ¬ P").truth_table().unwrap();
+        assert!(table.is_contradiction());
+        assert!(!table.is_tautology());
+        assert!(table.satisfying_assignments().is_empty());
+        assert!(Formula::Top.truth_table().unwrap().is_tautology());
+        assert!(Formula::Bottom.truth_table().unwrap().is_contradiction());
+    }
+
+    #[test]
+    fn test_trivial_implications_of_the_paper_example() {
+        for input in [corpus::PAPER_EXAMPLE[4], corpus::PAPER_EXAMPLE[8]] {
+            let table: TruthTable = Formula::new(input).truth_table().unwrap();
+            assert!(table.is_tautology(), "{input}");
+            assert_eq!(table.atoms().len(), 1);
+        }
+        let table: TruthTable = Formula::new(corpus::PAPER_EXAMPLE[9])
+            .truth_table()
+            .unwrap();
+        assert_eq!(table.atoms(), ["= x r+y*q", "< y r", "= y r"]);
+        assert!(table.is_tautology());
+        let table: TruthTable = Formula::new(corpus::PAPER_EXAMPLE[5])
+            .truth_table()
+            .unwrap();
+        assert!(!table.is_tautology());
+    }
+
+    #[test]
+    fn test_satisfying_assignments() {
+        let table: TruthTable = Formula::new("= P ¬ Q").truth_table().unwrap();
+        assert_eq!(
+            table.satisfying_assignments(),
+            [
+                BTreeMap::from([("P".to_string(), true), ("Q".to_string(), false)]),
+                BTreeMap::from([("P".to_string(), false), ("Q".to_string(), true)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let table: TruthTable = Formula::new("∨ < x 1 ¬ Flag").truth_table().unwrap();
+        assert_eq!(
+            table.to_string(),
+            "< x 1 | Flag | ∨ < x 1 ¬ Flag\n\
+             ------+------+---------------\n\
+             T     | T    | T\n\
+             T     | F    | T\n\
+             F     | T    | F\n\
+             F     | F    | T\n"
+        );
+    }
+
+    #[test]
+    fn test_variable_limit() {
+        let input: String = (0..21).fold("P0".to_string(), |acc, index| {
+            format!("∧ {acc} P{}", index + 1)
+        });
+        let test_formula: Formula = Formula::new(input);
+        assert_eq!(
+            test_formula.truth_table(),
+            Err(EvalError::TooManyAtoms {
+                atoms: 22,
+                limit: TruthTable::DEFAULT_LIMIT
+            })
+        );
+        assert_eq!(
+            Formula::new("∧ P ∨ Q R")
+                .truth_table_with(2)
+                .unwrap_err()
+                .to_string(),
+            "the formula has 3 atoms, more than the limit of 2"
+        );
+        assert!(Formula::new("∧ P ∨ Q R").truth_table_with(3).is_ok());
+    }
+
+    #[test]
+    fn test_not_propositional() {
+        assert_eq!(
+            Formula::new("∧ P ∀ x Q(x)").truth_table(),
+            Err(EvalError::NotQuantifierFree {
+                variable: "x".to_string()
+            })
+        );
+        assert_eq!(
+            Formula::new("< ¬ P Q").truth_table(),
+            Err(EvalError::UnsupportedConnective {
+                symbol: "<".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,594 @@
+//! # Unification
+//!
+//! This module provides [`unify`], which finds a [`Substitution`] making two formulae identical,
+//! and [`Formula::apply`], which applies one.
+//!
+//! Only the names passed to [`unify`] are unification variables. Such a name used as a
+//! proposition, like `P` in `→ P Q`, stands for a whole subformula, while one occurring inside a
+//! term, like `x` in `f(x)`, stands for a term. Every other name, every function and relation
+//! symbol, and every connective must match exactly, and quantifiers only match quantifiers of the
+//! same kind over the same variable. Inside a quantifier, its variable is not a unification
+//! variable, and no variable may be bound to a formula mentioning it.
+//!
+//! The terms of two formulae are unified structurally, by the same algorithm as
+//! [`term::unify`](crate::term::unify), which unifies two terms on their own: function symbols
+//! must be equal and have as many arguments, and the arguments are unified pairwise.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Formula, FormulaFolder, FormulaKind, Term, TermSubst, atom, rename, variables};
+
+/// A mapping from variable names to the formulae that replace them, as returned by [`unify`].
+///
+/// A variable replacing a term is mapped to a [`Formula::Term`]. The substitutions returned by
+/// [`unify`] are idempotent: no binding mentions a variable that is itself bound.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, Substitution};
+///
+/// let mut substitution: Substitution = Substitution::new();
+/// substitution
+///     .bind("P", Formula::new("< y r"))
+///     .bind("x", Formula::new("r+y*q"));
+/// assert_eq!(
+///     Formula::new("→ P = x 0").apply(&substitution),
+///     Formula::new("→ < y r = r+y*q 0")
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Substitution {
+    bindings: BTreeMap<String, Formula>, // The formula replacing each variable.
+}
+
+impl Substitution {
+    /// Creates an empty `Substitution`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `variable` to `value`, replacing any previous binding.
+    pub fn bind(&mut self, variable: impl Into<String>, value: Formula) -> &mut Self {
+        self.bindings.insert(variable.into(), value);
+        self
+    }
+
+    /// Returns the formula bound to `variable`, if any.
+    pub fn get(&self, variable: &str) -> Option<&Formula> {
+        self.bindings.get(variable)
+    }
+
+    /// Returns the number of bound variables.
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    /// Returns whether no variable is bound.
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Returns the bindings, ordered by variable name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Formula)> {
+        self.bindings
+            .iter()
+            .map(|(variable, value)| (variable.as_str(), value))
+    }
+}
+
+/// Finds the most general substitution of `variables` that makes `a` and `b` identical.
+///
+/// # Arguments
+/// * `a` - A reference to the first `Formula`.
+/// * `b` - A reference to the second `Formula`.
+/// * `variables` - The names that are unification variables, in either formula.
+///
+/// # Returns
+/// `Some` with the `Substitution` if there is one, or `None` if the formulae differ in a
+/// connective or symbol, a variable would be bound to two different formulae, or a variable would
+/// be bound to a formula containing itself.
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeSet;
+/// use first_order::{Formula, Substitution, unify};
+///
+/// let rule: Formula = Formula::new("→ ∧ P Q P");
+/// let lemma: Formula = Formula::new("→ ∧ < y r = x 0 < y r");
+/// let variables: BTreeSet<String> = BTreeSet::from(["P".to_string(), "Q".to_string()]);
+/// let substitution: Substitution = unify(&rule, &lemma, &variables).unwrap();
+/// assert_eq!(substitution.get("P"), Some(&Formula::new("< y r")));
+/// assert_eq!(rule.apply(&substitution), lemma);
+///
+/// assert_eq!(unify(&rule, &Formula::new("→ ∧ A B B"), &variables), None);
+/// ```
+pub fn unify(a: &Formula, b: &Formula, variables: &BTreeSet<String>) -> Option<Substitution> {
+    let mut unifier: Unifier<'_> = Unifier {
+        variables,
+        bound: Vec::new(),
+        substitution: Substitution::new(),
+    };
+    unifier.formulae(a, b).then_some(unifier.substitution)
+}
+
+/// Finds the most general substitution of `variables` that makes the terms `a` and `b`
+/// identical. See [`term::unify`](crate::term::unify).
+pub(crate) fn unify_terms(a: &Term, b: &Term, variables: &BTreeSet<String>) -> Option<TermSubst> {
+    let mut unifier: Unifier<'_> = Unifier {
+        variables,
+        bound: Vec::new(),
+        substitution: Substitution::new(),
+    };
+    if !unifier.terms(a, b) {
+        return None;
+    }
+    // Only terms were unified, so every variable is bound to a term.
+    let mut substitution: TermSubst = TermSubst::new();
+    for (variable, value) in unifier.substitution.iter() {
+        if let Some(value) = atom::as_term(value) {
+            substitution.bind(variable, value);
+        }
+    }
+    Some(substitution)
+}
+
+/// Applies `substitution` to the free variables of `formula`. See [`Formula::apply`].
+pub(crate) fn apply(formula: &Formula, substitution: &Substitution) -> Formula {
+    formula.fold(&mut Substituter {
+        substitution,
+        bound: Vec::new(),
+    })
+}
+
+/// Replaces the free variables of a formula with their bindings.
+struct Substituter<'a> {
+    substitution: &'a Substitution,
+    bound: Vec<String>, // The variables bound by the quantifiers enclosing the current node.
+}
+
+impl Substituter<'_> {
+    /// Returns the binding of the free variable `name`, if any.
+    fn binding(&self, name: &str) -> Option<&Formula> {
+        if self.bound.iter().any(|bound| bound == name) {
+            return None;
+        }
+        self.substitution.get(name)
+    }
+
+    fn term(&self, term: &Term) -> Term {
+        match term {
+            Term::Var(name) => match self.binding(name).and_then(atom::as_term) {
+                Some(value) => value,
+                None => term.clone(),
+            },
+            Term::IntLit(_) | Term::Const(_) => term.clone(),
+            Term::App(name, arguments) => Term::App(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| self.term(argument))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Substitutes into the scope `inner` of a quantifier over `variable`, first renaming the
+    /// quantifier to a fresh variable if a binding inserted into `inner` mentions `variable`, so
+    /// that the binding is not captured.
+    fn quantified(&mut self, variable: &str, inner: &Formula) -> (String, Box<Formula>) {
+        let free: BTreeSet<String> = variables::free(inner);
+        let captured: bool = free.iter().any(|name| {
+            name != variable
+                && self
+                    .binding(name)
+                    .is_some_and(|value| variables::free(value).contains(variable))
+        });
+        let (variable, inner): (String, Formula) = if captured {
+            let mut avoided: BTreeSet<String> = free;
+            avoided.extend(variables::bound(inner));
+            for value in self.substitution.bindings.values() {
+                avoided.extend(variables::free(value));
+            }
+            let mut suffix: usize = 1;
+            let mut fresh: String = format!("{variable}_{suffix}");
+            while avoided.contains(&fresh) {
+                suffix += 1;
+                fresh = format!("{variable}_{suffix}");
+            }
+            let quantifier: Formula =
+                Formula::UniversalQuantifier(variable.to_string(), Box::new(inner.clone()));
+            match &rename::rename_bound(&quantifier, variable, &fresh) {
+                Ok(Formula::UniversalQuantifier(_, inner)) => (fresh, (**inner).clone()),
+                _ => unreachable!("{fresh} is a fresh variable name"),
+            }
+        } else {
+            (variable.to_string(), inner.clone())
+        };
+        self.bound.push(variable.clone());
+        let inner: Formula = inner.fold(self);
+        self.bound.pop();
+        (variable, Box::new(inner))
+    }
+}
+
+impl FormulaFolder for Substituter<'_> {
+    fn fold_term(&mut self, term: &Term) -> Formula {
+        if let Term::Var(name) = term
+            && let Some(value) = self.binding(name)
+        {
+            return value.clone();
+        }
+        Formula::Term(self.term(term))
+    }
+    fn fold_universal_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        let (variable, inner) = self.quantified(variable, inner);
+        Formula::UniversalQuantifier(variable, inner)
+    }
+    fn fold_existential_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        let (variable, inner) = self.quantified(variable, inner);
+        Formula::ExistentialQuantifier(variable, inner)
+    }
+}
+
+/// Unifies formulae, accumulating the bindings found so far.
+struct Unifier<'a> {
+    variables: &'a BTreeSet<String>, // The unification variables.
+    bound: Vec<String>, // The variables of the quantifiers enclosing the current nodes.
+    substitution: Substitution, // The bindings found so far, kept idempotent.
+}
+
+impl Unifier<'_> {
+    /// Returns whether `name` is a unification variable where it occurs.
+    fn is_variable(&self, name: &str) -> bool {
+        self.variables.contains(name) && !self.bound.iter().any(|bound| bound == name)
+    }
+
+    /// Returns the binding of `formula` if it is a bound unification variable.
+    fn resolve(&self, formula: &Formula) -> Option<Formula> {
+        match formula {
+            Formula::Term(Term::Var(name)) if self.is_variable(name) => {
+                self.substitution.get(name).cloned()
+            }
+            _ => None,
+        }
+    }
+
+    fn formulae(&mut self, a: &Formula, b: &Formula) -> bool {
+        let (resolved_a, resolved_b): (Option<Formula>, Option<Formula>) =
+            (self.resolve(a), self.resolve(b));
+        let a: &Formula = resolved_a.as_ref().unwrap_or(a);
+        let b: &Formula = resolved_b.as_ref().unwrap_or(b);
+        match (a, b) {
+            (Formula::Term(a), Formula::Term(b)) => self.terms(a, b),
+            (Formula::Term(Term::Var(name)), _) if self.is_variable(name) => self.bind(name, b),
+            (_, Formula::Term(Term::Var(name))) if self.is_variable(name) => self.bind(name, a),
+            (Formula::Equality(_, _) | Formula::Less(_, _) | Formula::Predicate(_, _), _)
+            | (_, Formula::Equality(_, _) | Formula::Less(_, _) | Formula::Predicate(_, _)) => {
+                atom::zip_terms(a, b)
+                    .is_some_and(|pairs| pairs.into_iter().all(|(a, b)| self.terms(a, b)))
+            }
+            (Formula::Custom(lhs, _, _), Formula::Custom(rhs, _, _)) if lhs != rhs => false,
+            _ if a.kind() != b.kind() || a.binder() != b.binder() => false,
+            _ if a.kind() == FormulaKind::UniversalQuantifier
+                || a.kind() == FormulaKind::ExistentialQuantifier =>
+            {
+                self.bound.push(a.binder().unwrap_or_default().to_string());
+                let unified: bool = self.children(a, b);
+                self.bound.pop();
+                unified
+            }
+            _ => self.children(a, b),
+        }
+    }
+
+    fn children(&mut self, a: &Formula, b: &Formula) -> bool {
+        a.children()
+            .into_iter()
+            .zip(b.children())
+            .all(|(a, b)| self.formulae(a, b))
+    }
+
+    /// Returns the term that `term` stands for, or `None` if it is a variable bound to a formula
+    /// that is not a term.
+    fn resolve_term(&self, term: &Term) -> Option<Term> {
+        match term {
+            Term::Var(name) if self.is_variable(name) => match self.substitution.get(name) {
+                Some(value) => atom::as_term(value),
+                None => Some(term.clone()),
+            },
+            _ => Some(term.clone()),
+        }
+    }
+
+    fn terms(&mut self, a: &Term, b: &Term) -> bool {
+        let (Some(a), Some(b)) = (self.resolve_term(a), self.resolve_term(b)) else {
+            return false;
+        };
+        match (&a, &b) {
+            (Term::Var(lhs), Term::Var(rhs)) if lhs == rhs => true,
+            (Term::Var(name), _) if self.is_variable(name) => {
+                self.bind(name, &Formula::Term(b.clone()))
+            }
+            (_, Term::Var(name)) if self.is_variable(name) => {
+                self.bind(name, &Formula::Term(a.clone()))
+            }
+            (Term::App(lhs, lhs_arguments), Term::App(rhs, rhs_arguments)) => {
+                lhs == rhs
+                    && lhs_arguments.len() == rhs_arguments.len()
+                    && lhs_arguments
+                        .iter()
+                        .zip(rhs_arguments)
+                        .all(|(lhs, rhs)| self.terms(lhs, rhs))
+            }
+            _ => a == b,
+        }
+    }
+
+    /// Binds the unbound `variable` to `value`, failing the occurs check if `value` mentions
+    /// `variable`, or if it mentions the variable of an enclosing quantifier.
+    fn bind(&mut self, variable: &str, value: &Formula) -> bool {
+        let value: Formula = value.apply(&self.substitution);
+        let free: BTreeSet<String> = value.free_variables();
+        if free.contains(variable) || self.bound.iter().any(|bound| free.contains(bound)) {
+            return false;
+        }
+        let mut single: Substitution = Substitution::new();
+        single.bind(variable, value);
+        for binding in self.substitution.bindings.values_mut() {
+            *binding = binding.apply(&single);
+        }
+        self.substitution.bindings.extend(single.bindings);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus;
+
+    fn variables(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unify_propositional_variables() {
+        let pattern: Formula = Formula::new("→ P Q");
+        let instance: Formula = Formula::new("→ = x 5 = y 6");
+        let substitution: Substitution =
+            unify(&pattern, &instance, &variables(&["P", "Q"])).unwrap();
+        assert_eq!(
+            substitution.iter().collect::<Vec<_>>(),
+            [("P", &Formula::new("= x 5")), ("Q", &Formula::new("= y 6"))]
+        );
+        assert_eq!(pattern.apply(&substitution), instance);
+        assert_eq!(
+            unify(&instance, &pattern, &variables(&["P", "Q"])),
+            Some(substitution)
+        );
+        assert_eq!(unify(&pattern, &instance, &variables(&["P"])), None);
+    }
+
+    #[test]
+    fn test_conflicting_bindings() {
+        let pattern: Formula = Formula::new("→ P P");
+        assert_eq!(
+            unify(&pattern, &Formula::new("→ = x 5 = y 6"), &variables(&["P"])),
+            None
+        );
+        assert!(unify(&pattern, &Formula::new("→ = x 5 = x 5"), &variables(&["P"])).is_some());
+        assert_eq!(
+            unify(
+                &Formula::new("∧ = x 1 P(x)"),
+                &Formula::new("∧ = y+1 1 P(y-1)"),
+                &variables(&["x"])
+            ),
+            None
+        );
+        // A variable bound to a formula cannot stand for a term.
+        assert_eq!(
+            unify(
+                &Formula::new("∧ P < P 1"),
+                &Formula::new("∧ Q(z) < z 1"),
+                &variables(&["P"])
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_occurs_check() {
+        assert_eq!(
+            unify(
+                &Formula::new("P(x)"),
+                &Formula::new("P(f(x))"),
+                &variables(&["x"])
+            ),
+            None
+        );
+        assert_eq!(
+            unify(
+                &Formula::new("P"),
+                &Formula::new("∧ P Q"),
+                &variables(&["P"])
+            ),
+            None
+        );
+        assert_eq!(
+            unify(
+                &Formula::new("= x y"),
+                &Formula::new("= y+1 x"),
+                &variables(&["x", "y"])
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_terms_on_both_sides() {
+        let substitution: Substitution = unify(
+            &Formula::new("P(x,g(y))"),
+            &Formula::new("P(f(z),z)"),
+            &variables(&["x", "y", "z"]),
+        )
+        .unwrap();
+        assert_eq!(substitution.get("x"), Some(&Formula::term("f(g(y))")));
+        assert_eq!(substitution.get("z"), Some(&Formula::term("g(y)")));
+        assert_eq!(substitution.get("y"), None);
+        assert_eq!(
+            Formula::new("P(x,g(y))").apply(&substitution),
+            Formula::new("P(f(z),z)").apply(&substitution)
+        );
+        let substitution: Substitution = unify(
+            &Formula::new("= x*2 x+1"),
+            &Formula::new("= (a+b)*2 (a+b)+1"),
+            &variables(&["x"]),
+        )
+        .unwrap();
+        assert_eq!(substitution.get("x"), Some(&Formula::new("(a+b)")));
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        let substitution: Substitution = unify(
+            &Formula::new("∀ x P(x,y)"),
+            &Formula::new("∀ x P(x,3)"),
+            &variables(&["x", "y"]),
+        )
+        .unwrap();
+        assert_eq!(substitution.len(), 1);
+        assert_eq!(substitution.get("y"), Some(&Formula::new("3")));
+        // The variable of a quantifier must not escape its scope.
+        assert_eq!(
+            unify(
+                &Formula::new("∀ x P(y)"),
+                &Formula::new("∀ x P(x)"),
+                &variables(&["y"])
+            ),
+            None
+        );
+        assert_eq!(
+            unify(
+                &Formula::new("∀ x P(x)"),
+                &Formula::new("∃ x P(x)"),
+                &variables(&[])
+            ),
+            None
+        );
+        assert_eq!(
+            Formula::new("∧ P(x) ∀ x P(x)").apply(Substitution::new().bind("x", Formula::new("5"))),
+            Formula::new("∧ P(5) ∀ x P(x)")
+        );
+    }
+
+    #[test]
+    fn test_substitute_term() {
+        let replacement: Term = Term::new("x+1");
+        assert_eq!(
+            Formula::new("= y 43").substitute_term("y", &replacement),
+            Formula::new("= x+1 43")
+        );
+        assert_eq!(
+            Formula::new("∧ < 0 y*2 ∃ y = y fib(y)").substitute_term("y", &replacement),
+            Formula::new("∧ < 0 (x+1)*2 ∃ y = y fib(y)").map_term_trees(Term::without_parentheses)
+        );
+        // The assignment axiom for `index≔index+1`.
+        let postcondition: Formula = Formula::new(corpus::FIBONACCI[4]);
+        assert_eq!(
+            postcondition.substitute_term("index", &Term::new("index+1")),
+            Formula::new(corpus::FIBONACCI[3])
+        );
+    }
+
+    #[test]
+    fn test_substitute_term_avoids_capture() {
+        // `y` is free in the replacement, so the quantifier over `y` is renamed first.
+        assert_eq!(
+            Formula::new("∀ y < x y").substitute_term("x", &Term::new("y+1")),
+            Formula::new("∀ y_1 < y+1 y_1")
+        );
+        assert_eq!(
+            Formula::new("∃ y_1 ∀ y = x+y y_1").substitute_term("x", &Term::new("y")),
+            Formula::new("∃ y_1 ∀ y_2 = y+y_2 y_1")
+        );
+        // A quantifier whose scope receives no binding mentioning its variable is kept.
+        assert_eq!(
+            Formula::new("∧ = x 0 ∀ y P(y)").substitute_term("x", &Term::new("y+1")),
+            Formula::new("∧ = y+1 0 ∀ y P(y)")
+        );
+        assert_eq!(
+            Formula::new("∀ index < x index")
+                .apply(Substitution::new().bind("x", Formula::new("index*2"))),
+            Formula::new("∀ index_1 < index*2 index_1")
+        );
+    }
+
+    #[test]
+    fn test_unify_nested_terms() {
+        let substitution: TermSubst = unify_terms(
+            &Term::new("gcd(X,mod(X,Y))"),
+            &Term::new("gcd(a+1,mod(Z,fib(b)))"),
+            &variables(&["X", "Y", "Z"]),
+        )
+        .unwrap();
+        assert_eq!(substitution.get("X"), Some(&Term::new("a+1")));
+        assert_eq!(substitution.get("Y"), Some(&Term::new("fib(b)")));
+        assert_eq!(substitution.get("Z"), Some(&Term::new("a+1")));
+        assert_eq!(
+            substitution.apply(&Term::new("gcd(X,mod(X,Y))")),
+            substitution.apply(&Term::new("gcd(a+1,mod(Z,fib(b)))"))
+        );
+    }
+
+    #[test]
+    fn test_unify_terms_occurs_check() {
+        assert_eq!(
+            unify_terms(
+                &Term::new("fib(X)"),
+                &Term::new("fib(X+1)"),
+                &variables(&["X"])
+            ),
+            None
+        );
+        assert_eq!(
+            unify_terms(
+                &Term::new("f(X,Y)"),
+                &Term::new("f(Y,g(X))"),
+                &variables(&["X", "Y"])
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unify_terms_clash() {
+        let names: BTreeSet<String> = variables(&["X"]);
+        assert_eq!(
+            unify_terms(&Term::new("fib(X)"), &Term::new("fact(3)"), &names),
+            None
+        );
+        assert_eq!(
+            unify_terms(&Term::new("X+1"), &Term::new("n*1"), &names),
+            None
+        );
+        assert_eq!(
+            unify_terms(&Term::new("f(X)"), &Term::new("f(a,b)"), &names),
+            None
+        );
+        // A name that is not a unification variable only matches itself.
+        assert_eq!(
+            unify_terms(&Term::new("f(a)"), &Term::new("f(b)"), &names),
+            None
+        );
+    }
+
+    #[test]
+    fn test_atoms_unify_structurally() {
+        let substitution: Substitution = unify(
+            &Formula::new("∧ = preFib fib(X-1) < 0 X"),
+            &Formula::new("∧ = preFib fib(index+1-1) < 0 index+1"),
+            &variables(&["X"]),
+        )
+        .unwrap();
+        assert_eq!(substitution.get("X"), Some(&Formula::new("index+1")));
+    }
+}
@@ -0,0 +1,213 @@
+//! # Variables
+//!
+//! This module analyses the variables of a `Formula`, used by [`Formula::free_variables`],
+//! [`Formula::bound_variables`] and [`Formula::duplicate_bindings`].
+//!
+//! Every variable of a term is a variable occurrence, e.g. `index` in `< index N`, and so is a
+//! bare identifier used as a proposition, such as `P` in `∧ P Q`. Function and relation symbols,
+//! such as `fib` in `fib(index)` or `P` in `P(x)`, and numeric constants are not variables. An
+//! occurrence is bound by the innermost enclosing quantifier over its name, so in `∀ x ∃ x P(x)`
+//! the occurrence of `x` is bound by `∃ x`.
+use std::collections::BTreeSet;
+
+use crate::{Formula, FormulaVisitor, Term};
+
+/// Returns the names of the variables occurring free in `formula`. See
+/// [`Formula::free_variables`].
+pub(crate) fn free(formula: &Formula) -> BTreeSet<String> {
+    let mut visitor: FreeVariables = FreeVariables::default();
+    formula.accept(&mut visitor);
+    visitor.free
+}
+
+/// Collects the free variables of a formula.
+#[derive(Default)]
+struct FreeVariables {
+    bound: Vec<String>, // The variables bound by the quantifiers enclosing the current node.
+    free: BTreeSet<String>, // The free variables found so far.
+}
+
+impl FreeVariables {
+    fn visit_quantifier(&mut self, variable: &str, inner: &Formula) {
+        self.bound.push(variable.to_string());
+        inner.accept(self);
+        self.bound.pop();
+    }
+}
+
+impl FormulaVisitor for FreeVariables {
+    fn visit_term(&mut self, term: &Term) {
+        let bound: &[String] = &self.bound;
+        self.free.extend(
+            term.variables()
+                .into_iter()
+                .filter(|name| !bound.contains(name)),
+        );
+    }
+    fn visit_universal_quantifier(&mut self, variable: &str, inner: &Formula) {
+        self.visit_quantifier(variable, inner);
+    }
+    fn visit_existential_quantifier(&mut self, variable: &str, inner: &Formula) {
+        self.visit_quantifier(variable, inner);
+    }
+}
+
+/// Returns the names of the variables bound by a quantifier of `formula`. See
+/// [`Formula::bound_variables`].
+pub(crate) fn bound(formula: &Formula) -> BTreeSet<String> {
+    let mut bound: BTreeSet<String> = BTreeSet::new();
+    for_each_binding(formula, &mut Vec::new(), &mut |variable, _| {
+        bound.insert(variable.to_string());
+    });
+    bound
+}
+
+/// Returns the names bound by a quantifier of `formula` while already bound by an enclosing
+/// quantifier. See [`Formula::duplicate_bindings`].
+pub(crate) fn duplicates(formula: &Formula) -> Vec<String> {
+    let mut duplicates: Vec<String> = Vec::new();
+    for_each_binding(formula, &mut Vec::new(), &mut |variable, enclosing| {
+        if enclosing.contains(&variable) {
+            duplicates.push(variable.to_string());
+        }
+    });
+    duplicates
+}
+
+/// Calls `visit` with the variable of every quantifier of `formula` in prefix order, together
+/// with the variables bound by the quantifiers enclosing it.
+fn for_each_binding<'a>(
+    formula: &'a Formula,
+    enclosing: &mut Vec<&'a str>,
+    visit: &mut impl FnMut(&'a str, &[&'a str]),
+) {
+    match formula {
+        Formula::Term(_)
+        | Formula::Top
+        | Formula::Bottom
+        | Formula::Equality(_, _)
+        | Formula::Less(_, _)
+        | Formula::Predicate(_, _) => {}
+        Formula::Negation(inner) => for_each_binding(inner, enclosing, visit),
+        Formula::Conjunction(lhs, rhs)
+        | Formula::Disjunction(lhs, rhs)
+        | Formula::Implication(lhs, rhs)
+        | Formula::Equivalence(lhs, rhs)
+        | Formula::Iff(lhs, rhs)
+        | Formula::NotEqual(lhs, rhs)
+        | Formula::LessThan(lhs, rhs)
+        | Formula::LessEqual(lhs, rhs)
+        | Formula::GreaterEqual(lhs, rhs)
+        | Formula::GreaterThan(lhs, rhs)
+        | Formula::Custom(_, lhs, rhs) => {
+            for_each_binding(lhs, enclosing, visit);
+            for_each_binding(rhs, enclosing, visit);
+        }
+        Formula::UniversalQuantifier(variable, inner)
+        | Formula::ExistentialQuantifier(variable, inner) => {
+            visit(variable, enclosing);
+            enclosing.push(variable);
+            for_each_binding(inner, enclosing, visit);
+            enclosing.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Signature;
+
+    fn free_names(input: &str) -> Vec<String> {
+        Formula::new(input).free_variables().into_iter().collect()
+    }
+
+    #[test]
+    fn test_quantifier_binds_its_variable() {
+        assert_eq!(free_names("∀ x P(x,y)"), ["y"]);
+        assert_eq!(free_names("∃ y ∀ x P(x,y)"), Vec::<String>::new());
+        assert_eq!(free_names("= curFib fib(index)"), ["curFib", "index"]);
+    }
+
+    #[test]
+    fn test_shadowing_does_not_leak() {
+        assert!(free_names("∀ x ∃ x P(x)").is_empty());
+        assert!(free_names("∃ x ∧ ∀ x P(x) Q(x)").is_empty());
+    }
+
+    #[test]
+    fn test_free_and_bound_occurrences_of_the_same_name() {
+        assert_eq!(free_names("∧ P(x) ∀ x Q(x)"), ["x"]);
+        assert_eq!(free_names("→ ∀ x < x y > x 0"), ["x", "y"]);
+        assert_eq!(free_names("∀ n → ∃ m = n m+m ∃ k = n+m k+k"), ["m"]);
+    }
+
+    #[test]
+    fn test_propositions_and_constants() {
+        assert_eq!(free_names("∧ P ∀ x Q(x,1)"), ["P"]);
+        assert!(free_names("→ ⊤ ⊥").is_empty());
+        assert!(free_names("< 0 10").is_empty());
+    }
+
+    fn bound_names(input: &str) -> Vec<String> {
+        Formula::new(input).bound_variables().into_iter().collect()
+    }
+
+    #[test]
+    fn test_bound_variables() {
+        assert_eq!(bound_names("∀ x ∃ y R(x,y)"), ["x", "y"]);
+        assert_eq!(bound_names("∧ P(x) ∀ x Q(x)"), ["x"]);
+        assert_eq!(bound_names("∀ z P(x)"), ["z"]);
+        assert!(bound_names("→ < x y ∨ P Q").is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_bindings() {
+        assert_eq!(Formula::new("∀ x ∀ x P(x)").duplicate_bindings(), ["x"]);
+        assert_eq!(
+            Formula::new("∀ x ∃ y ∧ ∃ x P(x) ∀ y ∀ x Q(x,y)").duplicate_bindings(),
+            ["x", "y", "x"]
+        );
+        assert!(
+            Formula::new("∀ x ∃ y R(x,y)")
+                .duplicate_bindings()
+                .is_empty()
+        );
+        assert!(
+            Formula::new("∧ ∀ x P(x) ∀ x Q(x)")
+                .duplicate_bindings()
+                .is_empty()
+        );
+        assert!(
+            Formula::new("→ < x y ∨ P Q")
+                .duplicate_bindings()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_is_closed() {
+        assert!(Formula::new("∀ x P(x)").is_closed());
+        assert!(Formula::new("∀ x ∃ y ∧ R(x,y) ⊤").is_closed());
+        assert!(!Formula::new("= curFib fib(index)").is_closed());
+        assert!(!Formula::new("∧ ∀ x P(x) Q(x)").is_closed());
+    }
+
+    #[test]
+    fn test_assert_closed_in() {
+        let mut sig = Signature::new();
+        sig.function("fib", 1).function("N", 0).relation("P", 0);
+        assert_eq!(
+            Formula::new("= curFib fib(index)").assert_closed_in(&sig),
+            Err(vec!["curFib".to_string(), "index".to_string()])
+        );
+        assert_eq!(
+            Formula::new("∧ P ∀ index → ≤ index N ≥ fib(index) 0").assert_closed_in(&sig),
+            Ok(())
+        );
+        assert_eq!(
+            Formula::new("∧ P ∀ i < i N").assert_closed(),
+            Err(vec!["N".to_string(), "P".to_string()])
+        );
+    }
+}
@@ -0,0 +1,460 @@
+//! # Visitors and folders
+//!
+//! This module provides [`FormulaVisitor`], for analyses that walk a formula, and
+//! [`FormulaFolder`], for transforms that rebuild one. Both have a method per kind of node whose
+//! default implementation recurses into the subformulae, so an implementation only overrides the
+//! nodes it cares about. [`Subformulae`] iterates over the nodes instead.
+//!
+//! The typed atoms `Equality`, `Less` and `Predicate` are visited and folded through their terms
+//! by default, exactly like the `Formula::Term`s they replace, so an implementation that only
+//! overrides the method for terms still sees every term of the formula.
+use crate::{Formula, Term, atom};
+
+/// A walk over a formula, started with [`Formula::accept`].
+///
+/// Each method is called for a node of the corresponding kind, with the fields of that node. The
+/// default implementations visit the subformulae from left to right and do nothing at the atoms.
+/// An overriding method that should still visit the subformulae must call
+/// [`Formula::accept`] on them itself.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, FormulaVisitor};
+///
+/// #[derive(Default)]
+/// struct Quantifiers(usize);
+///
+/// impl FormulaVisitor for Quantifiers {
+///     fn visit_universal_quantifier(&mut self, _variable: &str, inner: &Formula) {
+///         self.0 += 1;
+///         inner.accept(self);
+///     }
+///     fn visit_existential_quantifier(&mut self, _variable: &str, inner: &Formula) {
+///         self.0 += 1;
+///         inner.accept(self);
+///     }
+/// }
+///
+/// let mut quantifiers = Quantifiers::default();
+/// Formula::new("∀ x → P(x) ∃ y Q(x,y)").accept(&mut quantifiers);
+/// assert_eq!(quantifiers.0, 2);
+/// ```
+pub trait FormulaVisitor {
+    /// Visits an atomic term such as `P(x)` or `x+1`.
+    fn visit_term(&mut self, _term: &Term) {}
+    /// Visits `⊤`.
+    fn visit_top(&mut self) {}
+    /// Visits `⊥`.
+    fn visit_bottom(&mut self) {}
+    /// Visits `¬ inner`.
+    fn visit_negation(&mut self, inner: &Formula) {
+        inner.accept(self);
+    }
+    /// Visits `∧ lhs rhs`.
+    fn visit_conjunction(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `∨ lhs rhs`.
+    fn visit_disjunction(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `→ lhs rhs`.
+    fn visit_implication(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `= lhs rhs`.
+    fn visit_equivalence(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `↔ lhs rhs`.
+    fn visit_iff(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `≠ lhs rhs`.
+    fn visit_not_equal(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `< lhs rhs`.
+    fn visit_less_than(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `≤ lhs rhs`.
+    fn visit_less_equal(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `≥ lhs rhs`.
+    fn visit_greater_equal(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `> lhs rhs`.
+    fn visit_greater_than(&mut self, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits `∀ variable inner`.
+    fn visit_universal_quantifier(&mut self, _variable: &str, inner: &Formula) {
+        inner.accept(self);
+    }
+    /// Visits `∃ variable inner`.
+    fn visit_existential_quantifier(&mut self, _variable: &str, inner: &Formula) {
+        inner.accept(self);
+    }
+    /// Visits `symbol lhs rhs`, where `symbol` is a connective registered with a
+    /// [`ParserConfig`](crate::ParserConfig).
+    fn visit_custom(&mut self, _symbol: &str, lhs: &Formula, rhs: &Formula) {
+        lhs.accept(self);
+        rhs.accept(self);
+    }
+    /// Visits the equality `= lhs rhs` of two terms, visiting each term.
+    fn visit_equality(&mut self, lhs: &Term, rhs: &Term) {
+        self.visit_term(lhs);
+        self.visit_term(rhs);
+    }
+    /// Visits the comparison `< lhs rhs` of two terms, visiting each term.
+    fn visit_less(&mut self, lhs: &Term, rhs: &Term) {
+        self.visit_term(lhs);
+        self.visit_term(rhs);
+    }
+    /// Visits the predicate `name(args)`, visiting it as the term `name(args)`, or `name` if it
+    /// has no arguments.
+    fn visit_predicate(&mut self, name: &str, args: &[Term]) {
+        self.visit_term(&atom::predicate_term(name, args));
+    }
+}
+
+/// A rewriting transform over a formula, applied with [`Formula::fold`].
+///
+/// Each method is called for a node of the corresponding kind, with the fields of that node, and
+/// returns the formula replacing it. The default implementations rebuild the node from its folded
+/// subformulae and copy the atoms, so a folder overriding no method returns an equal formula. An
+/// overriding method that should still rewrite the subformulae must call [`Formula::fold`] on
+/// them itself.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, FormulaFolder, Term};
+///
+/// struct SwapImplication;
+///
+/// impl FormulaFolder for SwapImplication {
+///     fn fold_implication(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+///         Formula::Implication(Box::new(rhs.fold(self)), Box::new(lhs.fold(self)))
+///     }
+/// }
+///
+/// let test_formula: Formula = Formula::new("∀ x → P(x) → Q R");
+/// assert_eq!(
+///     test_formula.fold(&mut SwapImplication),
+///     Formula::new("∀ x → → R Q P(x)")
+/// );
+/// ```
+pub trait FormulaFolder {
+    /// Folds an atomic term such as `P(x)` or `x+1`.
+    fn fold_term(&mut self, term: &Term) -> Formula {
+        Formula::Term(term.clone())
+    }
+    /// Folds `⊤`.
+    fn fold_top(&mut self) -> Formula {
+        Formula::Top
+    }
+    /// Folds `⊥`.
+    fn fold_bottom(&mut self) -> Formula {
+        Formula::Bottom
+    }
+    /// Folds `¬ inner`.
+    fn fold_negation(&mut self, inner: &Formula) -> Formula {
+        Formula::Negation(Box::new(inner.fold(self)))
+    }
+    /// Folds `∧ lhs rhs`.
+    fn fold_conjunction(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::Conjunction(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `∨ lhs rhs`.
+    fn fold_disjunction(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::Disjunction(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `→ lhs rhs`.
+    fn fold_implication(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::Implication(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `= lhs rhs`.
+    fn fold_equivalence(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::Equivalence(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `↔ lhs rhs`.
+    fn fold_iff(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::Iff(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `≠ lhs rhs`.
+    fn fold_not_equal(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::NotEqual(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `< lhs rhs`.
+    fn fold_less_than(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::LessThan(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `≤ lhs rhs`.
+    fn fold_less_equal(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::LessEqual(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `≥ lhs rhs`.
+    fn fold_greater_equal(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::GreaterEqual(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `> lhs rhs`.
+    fn fold_greater_than(&mut self, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::GreaterThan(Box::new(lhs.fold(self)), Box::new(rhs.fold(self)))
+    }
+    /// Folds `∀ variable inner`.
+    fn fold_universal_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        Formula::UniversalQuantifier(variable.to_string(), Box::new(inner.fold(self)))
+    }
+    /// Folds `∃ variable inner`.
+    fn fold_existential_quantifier(&mut self, variable: &str, inner: &Formula) -> Formula {
+        Formula::ExistentialQuantifier(variable.to_string(), Box::new(inner.fold(self)))
+    }
+    /// Folds `symbol lhs rhs`, where `symbol` is a connective registered with a
+    /// [`ParserConfig`](crate::ParserConfig).
+    fn fold_custom(&mut self, symbol: &str, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::Custom(
+            symbol.to_string(),
+            Box::new(lhs.fold(self)),
+            Box::new(rhs.fold(self)),
+        )
+    }
+    /// Folds the equality `= lhs rhs` of two terms from its folded terms, which is again an
+    /// `Equality` if both fold to terms.
+    fn fold_equality(&mut self, lhs: &Term, rhs: &Term) -> Formula {
+        atom::equality(Box::new(self.fold_term(lhs)), Box::new(self.fold_term(rhs)))
+    }
+    /// Folds the comparison `< lhs rhs` of two terms from its folded terms, which is again a
+    /// `Less` if both fold to terms.
+    fn fold_less(&mut self, lhs: &Term, rhs: &Term) -> Formula {
+        atom::less_than(Box::new(self.fold_term(lhs)), Box::new(self.fold_term(rhs)))
+    }
+    /// Folds the predicate `name(args)` by folding it as the term `name(args)`, or `name` if it
+    /// has no arguments, which is again a `Predicate` if it folds to a name or an application.
+    fn fold_predicate(&mut self, name: &str, args: &[Term]) -> Formula {
+        atom::proposition(self.fold_term(&atom::predicate_term(name, args)))
+    }
+}
+
+/// An iterator over a formula and all of its subformulae in prefix order, as returned by
+/// [`Formula::subformulae`].
+///
+/// The iterator keeps its own stack, so it handles formulae of any depth.
+#[derive(Debug, Clone)]
+pub struct Subformulae<'a> {
+    stack: Vec<&'a Formula>, // The subformulae left to yield, the next one on top.
+}
+
+impl<'a> Subformulae<'a> {
+    pub(crate) fn new(formula: &'a Formula) -> Self {
+        Subformulae {
+            stack: vec![formula],
+        }
+    }
+}
+
+impl<'a> Iterator for Subformulae<'a> {
+    type Item = &'a Formula;
+
+    fn next(&mut self) -> Option<&'a Formula> {
+        let formula: &'a Formula = self.stack.pop()?;
+        self.stack.extend(formula.children().into_iter().rev());
+        Some(formula)
+    }
+}
+
+/// Calls the method of `visitor` matching the outermost node of `formula`. See
+/// [`Formula::accept`].
+pub(crate) fn accept<V: FormulaVisitor + ?Sized>(formula: &Formula, visitor: &mut V) {
+    match formula {
+        Formula::Term(term) => visitor.visit_term(term),
+        Formula::Top => visitor.visit_top(),
+        Formula::Bottom => visitor.visit_bottom(),
+        Formula::Negation(inner) => visitor.visit_negation(inner),
+        Formula::Conjunction(lhs, rhs) => visitor.visit_conjunction(lhs, rhs),
+        Formula::Disjunction(lhs, rhs) => visitor.visit_disjunction(lhs, rhs),
+        Formula::Implication(lhs, rhs) => visitor.visit_implication(lhs, rhs),
+        Formula::Equivalence(lhs, rhs) => visitor.visit_equivalence(lhs, rhs),
+        Formula::Iff(lhs, rhs) => visitor.visit_iff(lhs, rhs),
+        Formula::NotEqual(lhs, rhs) => visitor.visit_not_equal(lhs, rhs),
+        Formula::LessThan(lhs, rhs) => visitor.visit_less_than(lhs, rhs),
+        Formula::LessEqual(lhs, rhs) => visitor.visit_less_equal(lhs, rhs),
+        Formula::GreaterEqual(lhs, rhs) => visitor.visit_greater_equal(lhs, rhs),
+        Formula::GreaterThan(lhs, rhs) => visitor.visit_greater_than(lhs, rhs),
+        Formula::UniversalQuantifier(variable, inner) => {
+            visitor.visit_universal_quantifier(variable, inner)
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            visitor.visit_existential_quantifier(variable, inner)
+        }
+        Formula::Custom(symbol, lhs, rhs) => visitor.visit_custom(symbol, lhs, rhs),
+        Formula::Equality(lhs, rhs) => visitor.visit_equality(lhs, rhs),
+        Formula::Less(lhs, rhs) => visitor.visit_less(lhs, rhs),
+        Formula::Predicate(name, args) => visitor.visit_predicate(name, args),
+    }
+}
+
+/// Calls the method of `folder` matching the outermost node of `formula`. See
+/// [`Formula::fold`].
+pub(crate) fn fold<F: FormulaFolder + ?Sized>(formula: &Formula, folder: &mut F) -> Formula {
+    match formula {
+        Formula::Term(term) => folder.fold_term(term),
+        Formula::Top => folder.fold_top(),
+        Formula::Bottom => folder.fold_bottom(),
+        Formula::Negation(inner) => folder.fold_negation(inner),
+        Formula::Conjunction(lhs, rhs) => folder.fold_conjunction(lhs, rhs),
+        Formula::Disjunction(lhs, rhs) => folder.fold_disjunction(lhs, rhs),
+        Formula::Implication(lhs, rhs) => folder.fold_implication(lhs, rhs),
+        Formula::Equivalence(lhs, rhs) => folder.fold_equivalence(lhs, rhs),
+        Formula::Iff(lhs, rhs) => folder.fold_iff(lhs, rhs),
+        Formula::NotEqual(lhs, rhs) => folder.fold_not_equal(lhs, rhs),
+        Formula::LessThan(lhs, rhs) => folder.fold_less_than(lhs, rhs),
+        Formula::LessEqual(lhs, rhs) => folder.fold_less_equal(lhs, rhs),
+        Formula::GreaterEqual(lhs, rhs) => folder.fold_greater_equal(lhs, rhs),
+        Formula::GreaterThan(lhs, rhs) => folder.fold_greater_than(lhs, rhs),
+        Formula::UniversalQuantifier(variable, inner) => {
+            folder.fold_universal_quantifier(variable, inner)
+        }
+        Formula::ExistentialQuantifier(variable, inner) => {
+            folder.fold_existential_quantifier(variable, inner)
+        }
+        Formula::Custom(symbol, lhs, rhs) => folder.fold_custom(symbol, lhs, rhs),
+        Formula::Equality(lhs, rhs) => folder.fold_equality(lhs, rhs),
+        Formula::Less(lhs, rhs) => folder.fold_less(lhs, rhs),
+        Formula::Predicate(name, args) => folder.fold_predicate(name, args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParserConfig, corpus};
+
+    /// Counts the quantifiers of a formula.
+    #[derive(Default)]
+    struct QuantifierCount {
+        universal: usize,
+        existential: usize,
+    }
+
+    impl FormulaVisitor for QuantifierCount {
+        fn visit_universal_quantifier(&mut self, _variable: &str, inner: &Formula) {
+            self.universal += 1;
+            inner.accept(self);
+        }
+        fn visit_existential_quantifier(&mut self, _variable: &str, inner: &Formula) {
+            self.existential += 1;
+            inner.accept(self);
+        }
+    }
+
+    /// Replaces every occurrence of one term with another.
+    struct ReplaceTerm {
+        from: Term,
+        to: Term,
+    }
+
+    impl FormulaFolder for ReplaceTerm {
+        fn fold_term(&mut self, term: &Term) -> Formula {
+            if *term == self.from {
+                Formula::Term(self.to.clone())
+            } else {
+                Formula::Term(term.clone())
+            }
+        }
+    }
+
+    /// Records the terms of a formula in the order they are visited.
+    #[derive(Default)]
+    struct Terms(Vec<String>);
+
+    impl FormulaVisitor for Terms {
+        fn visit_term(&mut self, term: &Term) {
+            self.0.push(term.to_string());
+        }
+    }
+
+    /// Records the equalities of a formula between two terms.
+    #[derive(Default)]
+    struct Equalities(Vec<String>);
+
+    impl FormulaVisitor for Equalities {
+        fn visit_equality(&mut self, lhs: &Term, rhs: &Term) {
+            self.0.push(format!("{lhs}={rhs}"));
+        }
+    }
+
+    /// A folder that overrides nothing.
+    struct Identity;
+
+    impl FormulaFolder for Identity {}
+
+    #[test]
+    fn test_visitor_counts_quantifiers() {
+        let mut count: QuantifierCount = QuantifierCount::default();
+        Formula::new("∧ ∀ x ∃ y < x y ¬ ∀ z ∨ P(z) ∃ w = z w").accept(&mut count);
+        assert_eq!((count.universal, count.existential), (2, 2));
+
+        let mut count: QuantifierCount = QuantifierCount::default();
+        Formula::new(corpus::PAPER_EXAMPLE[9]).accept(&mut count);
+        assert_eq!((count.universal, count.existential), (0, 0));
+    }
+
+    #[test]
+    fn test_visitor_visits_in_prefix_order() {
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("⊆");
+        let mut terms: Terms = Terms::default();
+        config
+            .parse("→ ⊆ A B ∀ x ∨ ¬ < x 0 ≥ x+1 ⊤")
+            .unwrap()
+            .accept(&mut terms);
+        assert_eq!(terms.0, ["A", "B", "x", "0", "x+1"]);
+    }
+
+    #[test]
+    fn test_visitor_visits_typed_atoms() {
+        let mut equalities: Equalities = Equalities::default();
+        Formula::new("∧ = x r+y*q ∨ < y r = ¬ P(y) ⊤").accept(&mut equalities);
+        assert_eq!(equalities.0, ["x=r+y*q"]);
+        let mut terms: Terms = Terms::default();
+        Formula::new("∧ P(x,y) Q").accept(&mut terms);
+        assert_eq!(terms.0, ["P(x,y)", "Q"]);
+    }
+
+    #[test]
+    fn test_folder_renames_one_term() {
+        let mut rename: ReplaceTerm = ReplaceTerm {
+            from: Term::new("r"),
+            to: Term::new("rem"),
+        };
+        assert_eq!(
+            Formula::new("∧ = x r+y*q ∀ r ∨ < y r = y r").fold(&mut rename),
+            Formula::new("∧ = x r+y*q ∀ r ∨ < y rem = y rem")
+        );
+    }
+
+    #[test]
+    fn test_default_folder_is_identity() {
+        for input in corpus::all() {
+            let test_formula: Formula = Formula::new(input);
+            assert_eq!(test_formula.fold(&mut Identity), test_formula, "{input}");
+        }
+        let mut config: ParserConfig = ParserConfig::new();
+        config.binary_connective("∈");
+        let test_formula: Formula = config.parse("∃ x ∧ ∈ x A ⊥").unwrap();
+        assert_eq!(test_formula.fold(&mut Identity), test_formula);
+    }
+}
@@ -3,12 +3,27 @@
 //! This module provides an implementation of Hoare Logic Triple using an struct `Triple`.
 //! It supports the following Hoare Logic axioms and rules:
 //! - Empty Statement Axiom
-//! - Assignment Axiom
-//! - Rule of Composition
+//! - Assignment Axiom, backwards or forwards after Floyd
+//! - Rule of Composition, optionally up to the order and grouping of `∧` and `∨` and the
+//!   normalization of terms, see [`MatchMode`]
 //! - Condition Rule
-//! - Consequence Rule
+//! - Consequence Rule, optionally checking that its lemmas are tautologies, or with the `z3`
+//!   feature that they are valid over the integers
 //! - While Rule
-use first_order::Formula;
+//!
+//! The Rule of Composition and the While Rule also apply to a [`SharedTriple`], whose conditions
+//! share their subformulae, so that a proof repeating a large invariant holds a single copy of it.
+//!
+//! ## Features
+//! - `z3`: enables the `z3` feature of `first_order` and adds `consequence_rule_checked_with`,
+//!   which checks arithmetic lemmas with the Z3 solver.
+use first_order::{
+    Formula, FormulaId, FormulaInfo, FormulaKind, FormulaStore, FormulaVisitor, PrintOptions,
+    RenameError, SharedFormula, SymbolClass, SymbolTable, Term,
+};
+#[cfg(feature = "z3")]
+use first_order::{SolverConfig, Validity};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 //use crate::first_order::Formula;
@@ -43,7 +58,9 @@ use std::fmt;
 ///     postcondition,
 /// };
 /// ```
-#[derive(Debug, PartialEq)]
+///
+/// Triples are compared, hashed and ordered by precondition, then command, then postcondition.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Triple {
     /// A `Formula` representing the precondition before executing the command.
     pub precondition: Formula,
@@ -53,6 +70,48 @@ pub struct Triple {
     pub postcondition: Formula,
 }
 
+/// A `Triple` whose conditions are interned into a [`FormulaStore`], as returned by
+/// [`Triple::intern_into`].
+///
+/// Two triples interned into the same store are equal exactly when the triples are, and their
+/// conditions are compared by identifier, in constant time, so only the commands are compared
+/// character by character.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InternedTriple {
+    /// The identifier of the precondition in the store.
+    pub precondition: FormulaId,
+    /// A `String` representing the command or program statement to be executed.
+    pub command: String,
+    /// The identifier of the postcondition in the store.
+    pub postcondition: FormulaId,
+}
+
+/// A `Triple` whose conditions are [`SharedFormula`]s, as returned by [`Triple::share`].
+///
+/// Cloning a shared triple takes constant time, and the rules for shared triples,
+/// [`composition_rule_shared`] and [`while_rule_shared`], build their conclusions from the
+/// conditions of their premises without copying them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SharedTriple {
+    /// The precondition, which may share subformulae with other triples.
+    pub precondition: SharedFormula,
+    /// A `String` representing the command or program statement to be executed.
+    pub command: String,
+    /// The postcondition, which may share subformulae with other triples.
+    pub postcondition: SharedFormula,
+}
+
+impl SharedTriple {
+    /// Converts the shared triple back into a `Triple`, copying its conditions.
+    pub fn to_triple(&self) -> Triple {
+        Triple {
+            precondition: self.precondition.to_formula(),
+            command: self.command.clone(),
+            postcondition: self.postcondition.to_formula(),
+        }
+    }
+}
+
 impl Triple {
     /// Creates a new `Triple` from three string input.
     ///
@@ -69,6 +128,9 @@ impl Triple {
     /// # Returns
     /// A `Triple` instance representing the parsed input.
     ///
+    /// # Panics
+    /// This function will panic if the precondition or the postcondition cannot be parsed into a valid `Formula`.
+    ///
     /// # Example
     /// ```
     /// use first_order::Formula;
@@ -88,29 +150,456 @@ impl Triple {
     /// ```
     /// # Note
     /// Ensure that the input strings are formatted correctly to avoid potential parsing errors.
+    /// The command is normalized with [`Triple::normalize_command`], so `x:=x+1` and `x≔x+1`
+    /// give the same triple.
     pub fn new<T: Into<String>>(precondition: T, command: T, postcondition: T) -> Triple {
+        let parse = |input: String, condition: &str| -> Formula {
+            match input.parse::<Formula>() {
+                Ok(formula) => formula,
+                Err(error) => panic!(
+                    "The {condition} {input:?} is malformed:\n{}",
+                    error.render(&input)
+                ),
+            }
+        };
         Triple {
-            precondition: Formula::new(precondition),
-            command: command.into(),
-            postcondition: Formula::new(postcondition),
+            precondition: parse(precondition.into(), "precondition"),
+            command: Triple::normalize_command(&command.into()),
+            postcondition: parse(postcondition.into(), "postcondition"),
+        }
+    }
+
+    /// Writes every assignment of `command` with the canonical symbol `≔`, in place of the ASCII
+    /// spelling `:=`.
+    ///
+    /// # Example
+    /// ```
+    /// use hoare_triple::Triple;
+    ///
+    /// assert_eq!(Triple::normalize_command("r:=r-y;q:=1+q"), "r≔r-y;q≔1+q");
+    /// assert_eq!(
+    ///     Triple::new("⊤", "x:=1", "= x 1"),
+    ///     Triple::new("⊤", "x≔1", "= x 1")
+    /// );
+    /// ```
+    pub fn normalize_command(command: &str) -> String {
+        Triple::normalize_command_with(command, false)
+    }
+
+    /// Writes every assignment of `command` with the same symbol, `:=` if `ascii` is set and `≔`
+    /// otherwise.
+    ///
+    /// # Arguments
+    /// * `command` - The command, in which both spellings may occur.
+    /// * `ascii` - Whether to write `:=` rather than `≔`.
+    ///
+    /// # Example
+    /// ```
+    /// use hoare_triple::Triple;
+    ///
+    /// assert_eq!(Triple::normalize_command_with("r≔r-y;q:=1+q", true), "r:=r-y;q:=1+q");
+    /// assert_eq!(Triple::normalize_command_with("r≔r-y;q:=1+q", false), "r≔r-y;q≔1+q");
+    /// ```
+    pub fn normalize_command_with(command: &str, ascii: bool) -> String {
+        if ascii {
+            command.replace('≔', ":=")
+        } else {
+            command.replace(":=", "≔")
+        }
+    }
+
+    /// Renders the triple in plain ASCII as `{P} C {Q}`.
+    ///
+    /// The conditions are rendered with [`Formula::to_ascii`], and every `≔` in the command is
+    /// written as `:=`.
+    ///
+    /// # Returns
+    /// A `String` holding the triple in ASCII.
+    ///
+    /// # Example
+    /// ```
+    /// use hoare_triple::Triple;
+    ///
+    /// let test_triple: Triple = Triple::new("∧ = x+1 43 ⊤", "y≔x+1", "¬ ≠ y 43");
+    /// assert_eq!(test_triple.to_ascii(), "{x+1 = 43 & true} y:=x+1 {!y != 43}");
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        format!(
+            "{{{}}} {} {{{}}}",
+            self.precondition.to_ascii(),
+            Triple::normalize_command_with(&self.command, true),
+            self.postcondition.to_ascii()
+        )
+    }
+
+    /// Renders the triple as `{P} C {Q}` with the conditions and the assignments of the command
+    /// written as `options` say.
+    ///
+    /// The conditions are rendered with [`Formula::to_string_with`]. Every assignment of the
+    /// command is written with the assignment symbol of `options`, and its value, which runs up to
+    /// the next `;` or whitespace, is reprinted with the term options if it parses as a [`Term`]
+    /// and kept as it is otherwise. The rest of the command is kept as it is.
+    ///
+    /// # Arguments
+    /// * `options` - How the triple is written. The default options write it as `Display` does.
+    ///
+    /// # Returns
+    /// A `String` holding the triple written with the given options.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::{PrintOptions, TermPrintOptions};
+    /// use hoare_triple::Triple;
+    ///
+    /// let test_triple: Triple = Triple::new("= x+1 43", "y≔x+1", "= y 43");
+    /// let mut terms: TermPrintOptions = TermPrintOptions::new();
+    /// terms.spaced_operators(true);
+    /// let mut options: PrintOptions = PrintOptions::new();
+    /// options.terms(terms).assignment_symbol(":=");
+    /// assert_eq!(test_triple.to_string_with(&options), "{(x + 1=43)} y := x + 1 {(y=43)}");
+    /// assert_eq!(test_triple.to_string_with(&PrintOptions::new()), test_triple.to_string());
+    /// ```
+    pub fn to_string_with(&self, options: &PrintOptions) -> String {
+        format!(
+            "{{{}}} {} {{{}}}",
+            self.precondition.to_string_with(options),
+            render_command(&Triple::normalize_command(&self.command), options),
+            self.postcondition.to_string_with(options)
+        )
+    }
+
+    /// Renames variables throughout the triple simultaneously, as `mapping` says.
+    ///
+    /// The conditions are renamed with [`Formula::rename`], so swapping two variables works and
+    /// bound variables are kept. The command is renamed textually: every whole identifier in
+    /// `mapping` is replaced, so renaming `i` leaves `if` and `i2` untouched.
+    ///
+    /// # Arguments
+    /// * `mapping` - The new name of each variable to rename.
+    ///
+    /// # Returns
+    /// A `Result` containing the renamed triple, or the `RenameError` of the first condition that
+    /// cannot be renamed.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use hoare_triple::Triple;
+    ///
+    /// let test_triple: Triple = Triple::new("< i r", "i≔i+r", "< r i");
+    /// let swap: BTreeMap<String, String> =
+    ///     BTreeMap::from([("i".to_string(), "r".to_string()), ("r".to_string(), "i".to_string())]);
+    /// assert_eq!(test_triple.rename(&swap), Ok(Triple::new("< r i", "r≔r+i", "< i r")));
+    /// ```
+    pub fn rename(&self, mapping: &BTreeMap<String, String>) -> Result<Triple, RenameError> {
+        Ok(Triple {
+            precondition: self.precondition.rename(mapping)?,
+            command: rename_identifiers(&self.command, mapping),
+            postcondition: self.postcondition.rename(mapping)?,
+        })
+    }
+
+    /// Interns the conditions of the triple into `store`, so that the triple can be compared with
+    /// other triples interned into the same store without walking its conditions.
+    ///
+    /// # Arguments
+    /// * `store` - The `FormulaStore` to intern the precondition and postcondition into.
+    ///
+    /// # Returns
+    /// An `InternedTriple` holding the identifiers of the conditions and a copy of the command.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::FormulaStore;
+    /// use hoare_triple::Triple;
+    ///
+    /// let mut store: FormulaStore = FormulaStore::new();
+    /// let first = Triple::new("= x+1 43", "y≔x+1", "= y 43").intern_into(&mut store);
+    /// let second = Triple::new("= y 43", "z≔y", "= z 43").intern_into(&mut store);
+    /// assert_eq!(first.postcondition, second.precondition);
+    /// assert_ne!(first, second);
+    /// assert_eq!(store.stats().unique, 3);
+    /// ```
+    pub fn intern_into(&self, store: &mut FormulaStore) -> InternedTriple {
+        InternedTriple {
+            precondition: store.intern(self.precondition.clone()),
+            command: self.command.clone(),
+            postcondition: store.intern(self.postcondition.clone()),
+        }
+    }
+    /// Converts the conditions of the triple into [`SharedFormula`]s.
+    ///
+    /// # Returns
+    /// A `SharedTriple` equal to the triple, see [`SharedTriple::to_triple`].
+    ///
+    /// # Example
+    /// ```
+    /// use hoare_triple::{SharedTriple, Triple};
+    ///
+    /// let test_triple: Triple = Triple::new("= x+1 43", "y≔x+1", "= y 43");
+    /// let shared: SharedTriple = test_triple.share();
+    /// assert_eq!(shared.to_triple(), test_triple);
+    /// ```
+    pub fn share(&self) -> SharedTriple {
+        SharedTriple {
+            precondition: SharedFormula::from(&self.precondition),
+            command: self.command.clone(),
+            postcondition: SharedFormula::from(&self.postcondition),
         }
     }
 }
 
+/// Replaces every whole identifier of `command` that `mapping` renames, in a single pass.
+fn rename_identifiers(command: &str, mapping: &BTreeMap<String, String>) -> String {
+    let mut renamed: String = String::with_capacity(command.len());
+    let mut identifier: String = String::new();
+    for c in command.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            identifier.push(c);
+            continue;
+        }
+        renamed.push_str(mapping.get(&identifier).unwrap_or(&identifier));
+        identifier.clear();
+        renamed.push(c);
+    }
+    renamed.push_str(mapping.get(&identifier).unwrap_or(&identifier));
+    renamed
+}
+
+/// Writes every assignment of `command` as `options` say, see [`Triple::to_string_with`].
+fn render_command(command: &str, options: &PrintOptions) -> String {
+    let mut rendered: String = String::with_capacity(command.len());
+    let mut rest: &str = command;
+    while let Some(index) = rest.find('≔') {
+        rendered.push_str(&rest[..index]);
+        rendered.push_str(&options.assignment());
+        rest = &rest[index + '≔'.len_utf8()..];
+        let end: usize = rest
+            .find(|c: char| c == ';' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        match Term::parse(&rest[..end]) {
+            Ok(value) => rendered.push_str(&value.to_string_with(options.term_options())),
+            Err(_) => rendered.push_str(&rest[..end]),
+        }
+        rest = &rest[end..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
 impl fmt::Display for Triple {
+    /// Formats the triple as `{P} C {Q}`, with its conditions in infix notation, or in prefix
+    /// notation with the alternate flag, `{:#}`. Assignments are written with `≔`, however the
+    /// command was spelled; [`Triple::to_ascii`] writes them with `:=`.
+    ///
+    /// # Example
+    /// ```
+    /// use hoare_triple::Triple;
+    ///
+    /// let triple: Triple = Triple::new("∧ P Q", "x≔1", "→ P Q");
+    /// assert_eq!(format!("{triple}"), "{(P∧Q)} x≔1 {(P→Q)}");
+    /// assert_eq!(format!("{triple:#}"), "{∧ P Q} x≔1 {→ P Q}");
+    /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return write!(
+                f,
+                "{{{:#}}} {} {{{:#}}}",
+                self.precondition,
+                Triple::normalize_command(&self.command),
+                self.postcondition
+            );
+        }
         write!(
             f,
             "{{{}}} {} {{{}}}",
-            self.precondition, self.command, self.postcondition
+            self.precondition,
+            Triple::normalize_command(&self.command),
+            self.postcondition
         )
     }
 }
+/// Creates a new `Triple` using the Assignment Axiom [5], computing the precondition backwards
+/// from the postcondition.
+///
+/// The precondition is `postcondition` with every free occurrence of `variable` replaced by
+/// `expression`, see [`Formula::substitute_term`], and the command is `variable≔expression`,
+/// written from the parsed terms, so without any whitespace around them. A quantifier of
+/// `postcondition` over a variable of `expression` is renamed so as not to capture it.
+///
+/// # Arguments
+/// * `postcondition` - The condition that holds after the assignment.
+/// * `variable` - The name of the variable assigned to.
+/// * `expression` - The term assigned, e.g. `preFib+curFib`.
+///
+/// # Returns
+/// A `Result` containing the `Triple` `{postcondition[variable:=expression]} variable≔expression
+/// {postcondition}`, or an error message if `variable` is not a variable name or `expression` is
+/// not a term, including if either contains an assignment symbol `≔` or `:=`.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use hoare_triple::{Triple, assignment_axiom};
+///
+/// let test_triple: Triple = assignment_axiom(&Formula::new("= y 43"), "y", "x+1").unwrap();
+/// assert_eq!(test_triple, Triple::new("= x+1 43", "y≔x+1", "= y 43"));
+/// assert!(assignment_axiom(&Formula::new("= y 43"), "y", "x+").is_err());
+/// ```
+/// [5]: https://en.wikipedia.org/wiki/Hoare_logic#Assignment_axiom_schema
+pub fn assignment_axiom(
+    postcondition: &Formula,
+    variable: &str,
+    expression: &str,
+) -> Result<Triple, String> {
+    assignment_axiom_with(postcondition, variable, expression, None)
+}
+
+/// Creates a new `Triple` using the Assignment Axiom, refusing to assign to a name that `symbols`
+/// declares as a constant or a function.
+///
+/// This is [`assignment_axiom`], except that `variable` is checked against the declarations of
+/// `symbols`. A name that is not declared may always be assigned to, as assigning to it makes it
+/// a variable by the convention of [`SymbolTable`].
+///
+/// # Arguments
+/// * `postcondition` - The condition that holds after the assignment.
+/// * `variable` - The name of the variable assigned to.
+/// * `expression` - The term assigned, e.g. `preFib+curFib`.
+/// * `symbols` - The `SymbolTable` declaring the names, or `None` if none are declared.
+///
+/// # Returns
+/// A `Result` containing the `Triple`, or an error message if `variable` is not a variable or
+/// `expression` is not a term.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, SymbolTable};
+/// use hoare_triple::{Triple, assignment_axiom_with};
+///
+/// let mut symbols: SymbolTable = SymbolTable::new();
+/// symbols.constant("N");
+/// let postcondition: Formula = Formula::new("< index N");
+/// assert_eq!(
+///     assignment_axiom_with(&postcondition, "index", "index+1", Some(&symbols)),
+///     Ok(Triple::new("< index+1 N", "index≔index+1", "< index N"))
+/// );
+/// assert!(assignment_axiom_with(&postcondition, "N", "N+1", Some(&symbols)).is_err());
+/// ```
+pub fn assignment_axiom_with(
+    postcondition: &Formula,
+    variable: &str,
+    expression: &str,
+    symbols: Option<&SymbolTable>,
+) -> Result<Triple, String> {
+    let (variable, value): (String, Term) = parse_assignment(variable, expression)?;
+    if let Some(class) = symbols.and_then(|symbols| symbols.declared(&variable))
+        && class != SymbolClass::Variable
+    {
+        return Err(format!(
+            "The assigned {variable:?} is declared as {class:?}, not as a variable"
+        ));
+    }
+    Ok(Triple {
+        precondition: postcondition.substitute_term(&variable, &value),
+        command: format!("{variable}≔{value}"),
+        postcondition: postcondition.clone(),
+    })
+}
+
+/// Creates a new `Triple` using Floyd's forward Assignment Axiom, computing the postcondition
+/// from the precondition.
+///
+/// The postcondition is `∃ x0 ∧ P[x:=x0] = x E[x:=x0]`, where `P` is `precondition`, `x` is
+/// `variable`, `E` is `expression` and `x0` names the value of `x` before the assignment. The
+/// name `x0` is the first of `x0`, `x1`, ... that occurs nowhere in `precondition` or
+/// `expression`.
+///
+/// # Arguments
+/// * `precondition` - The condition that holds before the assignment.
+/// * `variable` - The name of the variable assigned to.
+/// * `expression` - The term assigned, e.g. `x+1`.
+///
+/// # Returns
+/// A `Result` containing the `Triple` `{precondition} variable≔expression {∃ x0 ...}`, or an
+/// error message if `variable` is not a variable name or `expression` is not a term.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use hoare_triple::{Triple, assignment_axiom_forward};
+///
+/// let test_triple: Triple = assignment_axiom_forward(&Formula::new("= x 1"), "x", "x+1").unwrap();
+/// assert_eq!(test_triple, Triple::new("= x 1", "x≔x+1", "∃ x0 ∧ = x0 1 = x x0+1"));
+/// ```
+pub fn assignment_axiom_forward(
+    precondition: &Formula,
+    variable: &str,
+    expression: &str,
+) -> Result<Triple, String> {
+    let (variable, value): (String, Term) = parse_assignment(variable, expression)?;
+    let mut taken: BTreeSet<String> = precondition.constant_and_variable_names();
+    taken.extend(precondition.bound_variables());
+    taken.extend(value.variables());
+    let old: String = (0..)
+        .map(|suffix: usize| format!("{variable}{suffix}"))
+        .find(|name| !taken.contains(name))
+        .expect("only finitely many names are taken");
+    let old_value: Term = Term::Var(old.clone());
+    Ok(Triple {
+        precondition: precondition.clone(),
+        command: format!("{variable}≔{value}"),
+        postcondition: Formula::ExistentialQuantifier(
+            old,
+            Box::new(Formula::Conjunction(
+                Box::new(precondition.substitute_term(&variable, &old_value)),
+                Box::new(Formula::Equality(
+                    Term::Var(variable.clone()),
+                    value.substitute(&variable, &old_value),
+                )),
+            )),
+        ),
+    })
+}
+
+/// Parses the name of the variable assigned to and the term `expression` assigned to it.
+///
+/// Neither may contain an assignment symbol, so that the command built from them is a single
+/// assignment.
+fn parse_assignment(variable: &str, expression: &str) -> Result<(String, Term), String> {
+    let name: String = match &Term::parse(variable) {
+        Ok(Term::Var(name)) if !is_assignment(name) => name.clone(),
+        _ => {
+            return Err(format!(
+                "The assigned {variable:?} is not the name of a variable"
+            ));
+        }
+    };
+    if is_assignment(expression) {
+        return Err(format!(
+            "The assigned expression {expression:?} contains an assignment"
+        ));
+    }
+    let value: Term = Term::parse(expression)
+        .map_err(|error| format!("The assigned expression {expression:?} is malformed: {error}"))?;
+    Ok((name, value))
+}
+
+/// Returns whether `text` contains an assignment symbol, `≔` or `:=`.
+fn is_assignment(text: &str) -> bool {
+    text.contains('≔') || text.contains(":=")
+}
+
 /// Creates a new `Triple` using the Rule of Composition [1].
 ///
 /// This function applies the Rule of Composition to two `Triple` instances, `left` and `right`,
 /// by combining them according to the Rule of Composition. The precondition of the `right` `Triple`
 /// must match the postcondition of the `left` `Triple`, which is referred to as the midcondition.
+/// The midconditions are compared as formulae, not as rendered text, so a term written `a-(b-c)`
+/// does not match the same term built without its parentheses; [`composition_rule_normalized`]
+/// accepts both. The conditions of the new triple are the existing formulae, which are not parsed
+/// again. The commands are joined with `;`, and their assignments are written with `≔`, see
+/// [`Triple::normalize_command`].
 ///
 /// # Arguments
 /// * `left` - A reference to the `Triple` executed first.
@@ -132,33 +621,242 @@ impl fmt::Display for Triple {
 /// ```
 /// [1]: https://en.wikipedia.org/wiki/Hoare_logic#Rule_of_composition
 pub fn composition_rule(left: &Triple, right: &Triple) -> Result<Triple, String> {
-    if left.postcondition.to_string() != right.precondition.to_string() {
-        return Err(format!(
-            "The input triples do not have matching midcondition\nleft postcondition: {:?}\n right precondition: {:?}",
-            left.postcondition.to_prefix_notation(),
-            right.precondition.to_prefix_notation()
+    composition_rule_with(left, right, MatchMode::Exact)
+}
+
+/// Creates a new `Triple` using the Rule of Composition, accepting a midcondition whose `∧` and `∨`
+/// operands are ordered or grouped differently.
+///
+/// This is [`composition_rule`], except that the postcondition of `left` and the precondition of
+/// `right` only need to be equal modulo the associativity and commutativity of `∧` and `∨`, see
+/// [`Formula::equal_modulo_ac`]. The `∧ ¬ B P` a while rule produces then composes with a triple
+/// written with `∧ P ¬ B`.
+///
+/// # Arguments
+/// * `left` - A reference to the `Triple` executed first.
+/// * `right` - A reference to the `Triple` executed after `left`.
+///
+/// # Returns
+/// A `Result` containing a `Triple` with the precondition of `left` and the postcondition of
+/// `right`, or an error message if the midconditions differ even modulo AC.
+///
+/// # Example
+/// ```
+/// use hoare_triple::{Triple, composition_rule, composition_rule_ac};
+///
+/// let triple1: Triple = Triple::new("P", "S", "∧ ¬ B I");
+/// let triple2: Triple = Triple::new("∧ I ¬ B", "T", "Q");
+/// assert!(composition_rule(&triple1, &triple2).is_err());
+/// let test_triple: Triple = composition_rule_ac(&triple1, &triple2).unwrap();
+/// assert_eq!(test_triple, Triple::new("P", "S;T", "Q"));
+/// ```
+pub fn composition_rule_ac(left: &Triple, right: &Triple) -> Result<Triple, String> {
+    composition_rule_with(left, right, MatchMode::ModuloAC)
+}
+
+/// Creates a new `Triple` using the Rule of Composition, accepting a midcondition whose terms are
+/// spelled differently but normalize to the same terms.
+///
+/// This is [`composition_rule_ac`], except that the postcondition of `left` and the precondition
+/// of `right` are compared after [`Formula::normalize_terms`], so that `fib(index+1-1)` matches
+/// `fib(index)` without a consequence lemma in between. The conditions of the new triple are kept
+/// as they are written.
+///
+/// # Arguments
+/// * `left` - A reference to the `Triple` executed first.
+/// * `right` - A reference to the `Triple` executed after `left`.
+///
+/// # Returns
+/// A `Result` containing a `Triple` with the precondition of `left` and the postcondition of
+/// `right`, or an error message if the midconditions differ even after normalization.
+///
+/// # Example
+/// ```
+/// use hoare_triple::{Triple, composition_rule, composition_rule_normalized};
+///
+/// let triple1: Triple = Triple::new("= x+1 43", "y≔x+1", "= y+0 43");
+/// let triple2: Triple = Triple::new("= y 43", "z≔y", "= z 43");
+/// assert!(composition_rule(&triple1, &triple2).is_err());
+/// let test_triple: Triple = composition_rule_normalized(&triple1, &triple2).unwrap();
+/// assert_eq!(test_triple, Triple::new("= x+1 43", "y≔x+1;z≔y", "= z 43"));
+/// ```
+pub fn composition_rule_normalized(left: &Triple, right: &Triple) -> Result<Triple, String> {
+    composition_rule_with(left, right, MatchMode::ModuloNormalization)
+}
+
+/// How [`composition_rule_with`] matches the postcondition of the first triple against the
+/// precondition of the second.
+///
+/// Each mode accepts every midcondition the previous one does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MatchMode {
+    /// The midconditions must be equal formulae, as in [`composition_rule`].
+    #[default]
+    Exact,
+    /// The midconditions may differ in the order and grouping of `∧` and `∨`, as in
+    /// [`composition_rule_ac`].
+    ModuloAC,
+    /// The midconditions may additionally differ in terms that normalize to the same term, as in
+    /// [`composition_rule_normalized`].
+    ModuloNormalization,
+}
+
+impl MatchMode {
+    /// Returns whether the midconditions `left` and `right` match in this mode.
+    fn matches(self, left: &Formula, right: &Formula) -> bool {
+        match self {
+            MatchMode::Exact => left == right,
+            MatchMode::ModuloAC => left.equal_modulo_ac(right),
+            MatchMode::ModuloNormalization => left
+                .normalize_terms()
+                .equal_modulo_ac(&right.normalize_terms()),
+        }
+    }
+}
+
+impl fmt::Display for MatchMode {
+    /// Formats the mode as the equivalence it compares the midconditions up to, e.g. `modulo
+    /// the order and grouping of ∧ and ∨`, or as `exactly`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchMode::Exact => write!(f, "exactly"),
+            MatchMode::ModuloAC => write!(f, "modulo the order and grouping of ∧ and ∨"),
+            MatchMode::ModuloNormalization => write!(
+                f,
+                "modulo the order and grouping of ∧ and ∨ and the normalization of terms"
+            ),
+        }
+    }
+}
+
+/// Creates a new `Triple` using the Rule of Composition, matching the midconditions as `mode`
+/// says.
+///
+/// The conditions of the new triple are kept as they are written, and the commands are joined as
+/// in [`composition_rule`].
+///
+/// # Arguments
+/// * `left` - A reference to the `Triple` executed first.
+/// * `right` - A reference to the `Triple` executed after `left`.
+/// * `mode` - How the postcondition of `left` is matched against the precondition of `right`.
+///
+/// # Returns
+/// A `Result` containing a `Triple` with the precondition of `left` and the postcondition of
+/// `right`, or an error message, naming `mode` unless it is `Exact`, if the midconditions do not
+/// match.
+///
+/// # Example
+/// ```
+/// use hoare_triple::{MatchMode, Triple, composition_rule_with};
+///
+/// let triple1: Triple = Triple::new("P", "S", "∧ B = x y+0");
+/// let triple2: Triple = Triple::new("∧ = x y B", "T", "Q");
+/// assert!(composition_rule_with(&triple1, &triple2, MatchMode::Exact).is_err());
+/// let error: String = composition_rule_with(&triple1, &triple2, MatchMode::ModuloAC).unwrap_err();
+/// assert!(error.contains("modulo the order and grouping of ∧ and ∨"));
+/// assert_eq!(
+///     composition_rule_with(&triple1, &triple2, MatchMode::ModuloNormalization),
+///     Ok(Triple::new("P", "S;T", "Q"))
+/// );
+/// ```
+pub fn composition_rule_with(
+    left: &Triple,
+    right: &Triple,
+    mode: MatchMode,
+) -> Result<Triple, String> {
+    if !mode.matches(&left.postcondition, &right.precondition) {
+        return Err(midcondition_mismatch(
+            &left.postcondition.to_prefix_notation(),
+            &right.precondition.to_prefix_notation(),
+            mode,
         ));
     }
-    Ok(Triple::new(
-        left.precondition.to_prefix_notation(),
-        format!("{}{}{}", left.command, ";", right.command),
-        right.postcondition.to_prefix_notation(),
-    ))
+    Ok(Triple {
+        precondition: left.precondition.clone(),
+        command: Triple::normalize_command(&format!("{};{}", left.command, right.command)),
+        postcondition: right.postcondition.clone(),
+    })
+}
+
+/// Creates a new `SharedTriple` using the Rule of Composition, sharing the conditions of `left` and
+/// `right`.
+///
+/// This is [`composition_rule`] for shared triples: the midconditions must be equal, which takes
+/// constant time when they share the same allocation, and the conditions of the new triple are
+/// those of `left` and `right`, not copies of them.
+///
+/// # Arguments
+/// * `left` - A reference to the `SharedTriple` executed first.
+/// * `right` - A reference to the `SharedTriple` executed after `left`.
+///
+/// # Returns
+/// A `Result` containing a `SharedTriple` with the precondition of `left` and the postcondition
+/// of `right`, or an error message if the midcondition does not match.
+///
+/// # Example
+/// ```
+/// use first_order::SharedFormula;
+/// use hoare_triple::{SharedTriple, Triple, composition_rule_shared};
+///
+/// let triple1: SharedTriple = Triple::new("= x+1 43", "y≔x+1", "= y 43").share();
+/// let triple2: SharedTriple = Triple::new("= y 43", "z≔y", "= z 43").share();
+/// let test_triple: SharedTriple = composition_rule_shared(&triple1, &triple2).unwrap();
+/// assert!(test_triple.postcondition.ptr_eq(&triple2.postcondition));
+/// assert_eq!(
+///     test_triple.to_triple(),
+///     Triple::new("= x+1 43", "y≔x+1;z≔y", "= z 43")
+/// );
+/// ```
+pub fn composition_rule_shared(
+    left: &SharedTriple,
+    right: &SharedTriple,
+) -> Result<SharedTriple, String> {
+    if left.postcondition != right.precondition {
+        return Err(midcondition_mismatch(
+            &left.postcondition.to_prefix_notation(),
+            &right.precondition.to_prefix_notation(),
+            MatchMode::Exact,
+        ));
+    }
+    Ok(SharedTriple {
+        precondition: left.precondition.clone(),
+        command: Triple::normalize_command(&format!("{};{}", left.command, right.command)),
+        postcondition: right.postcondition.clone(),
+    })
+}
+
+/// Returns the error message for a left postcondition and a right precondition, both in prefix
+/// notation, that do not match in `mode`.
+fn midcondition_mismatch(postcondition: &str, precondition: &str, mode: MatchMode) -> String {
+    let tried: String = match mode {
+        MatchMode::Exact => String::new(),
+        _ => format!(" {mode}"),
+    };
+    format!(
+        "The input triples do not have matching midcondition{tried}\nleft postcondition: {postcondition:?}\n right precondition: {precondition:?}"
+    )
 }
 
 /// Creates a new `Triple` using the Condition Rule [2].
 ///
-/// This function applies the Condition Rule to two `Triple` instances, `left` and `right`.
-/// The `left` `Triple` must have an unnegated condition as the first value of its conjunction formula,
-/// while the `right` `Triple` must have the corresponding negated condition as the first value of its conjunction formula.
+/// This function applies the Condition Rule to two `Triple` instances, `left` and `right`, whose
+/// preconditions must both be conjunctions. The conjuncts of each precondition are flattened, see
+/// [`Formula::conjuncts`], and the condition `B` is the conjunct of `left` whose negation is a
+/// conjunct of `right`, in any position. The conditions are matched with
+/// [`Formula::is_negation_of`], so a negated condition on the left is also accepted when the right
+/// has the condition it negates, e.g. `¬ < y r` and `< y r`. Every copy of the condition is removed
+/// from the conjuncts of `left`, and every copy of its negation from those of `right`. The
+/// remaining conjuncts of both preconditions must be the same, in any order, and form the
+/// precondition `P` of the new triple, which is `⊤` if none remain.
 ///
 /// # Arguments
-/// * `left` - A reference to the `Triple` with the unnegated condition. The unnegated condition must be the first value of the conjunction formula.
-/// * `right` - A reference to the `Triple` with the negated condition. The negated condition must be the first value of the conjunction formula.
+/// * `left` - A reference to the `Triple` with the unnegated condition among the conjuncts of its precondition.
+/// * `right` - A reference to the `Triple` with the negated condition among the conjuncts of its precondition.
 ///
 /// # Returns
 /// A `Result` containing a `Triple` instance with the Condition Rule applied on `left` and `right`,
-/// or an error message if the input is malformed (e.g., if the preconditions are not of the expected type).
+/// or an error message if the input is malformed (e.g., if the preconditions are not of the expected type),
+/// if no conjunct or more than one conjunct can be the condition, or if the remaining conjuncts differ.
 ///
 /// # Example
 /// ```
@@ -169,40 +867,117 @@ pub fn composition_rule(left: &Triple, right: &Triple) -> Result<Triple, String>
 /// let test_triple: Triple = condition_rule(&triple1, &triple2).unwrap();
 /// let result = Triple::new("P", "if B then S else T endif", "Q");
 /// assert_eq!(test_triple, result);
+///
+/// let triple1: Triple = Triple::new("∧ ∧ P B R", "S", "Q");
+/// let triple2: Triple = Triple::new("∧ R ∧ ¬ B P", "T", "Q");
+/// let test_triple: Triple = condition_rule(&triple1, &triple2).unwrap();
+/// assert_eq!(test_triple, Triple::new("∧ P R", "if B then S else T endif", "Q"));
 /// ```
 /// [2]: https://en.wikipedia.org/wiki/Hoare_logic#Conditional_rule
 pub fn condition_rule(left: &Triple, right: &Triple) -> Result<Triple, String> {
-    let negated_condition = &mut right.precondition.get_info()[1];
-    negated_condition.replace_range(..3, "");
-    if left.precondition.get_info()[0] != "Conjunction"
-        || right.precondition.get_info()[0] != "Conjunction"
+    if left.precondition.kind() != FormulaKind::Conjunction
+        || right.precondition.kind() != FormulaKind::Conjunction
     {
         return Err(
             "The input triples do not have `Conjunction` formulae as precondition".to_string(),
         );
-    } else if left.precondition.get_info()[1] != *negated_condition {
+    }
+    let left_conjuncts: Vec<&Formula> = left.precondition.conjuncts();
+    let right_conjuncts: Vec<&Formula> = right.precondition.conjuncts();
+    let mut candidates: Vec<&Formula> = Vec::new();
+    for conjunct in &left_conjuncts {
+        if right_conjuncts
+            .iter()
+            .any(|other| other.is_negation_of(conjunct))
+            && !candidates.contains(conjunct)
+        {
+            candidates.push(conjunct);
+        }
+    }
+    let prefix_notation = |conjuncts: &[&Formula]| -> Vec<String> {
+        conjuncts
+            .iter()
+            .map(|conjunct| conjunct.to_prefix_notation())
+            .collect()
+    };
+    let condition: &Formula = match candidates.as_slice() {
+        [] => {
+            return Err(format!(
+                "The input triples have no condition among the conjuncts {:?} whose negation is among the conjuncts {:?}",
+                prefix_notation(&left_conjuncts),
+                prefix_notation(&right_conjuncts)
+            ));
+        }
+        [condition] => condition,
+        _ => {
+            let names: Vec<String> = candidates
+                .iter()
+                .map(|candidate| candidate.to_prefix_notation())
+                .collect();
+            return Err(format!(
+                "The input triples have more than one candidate condition: {names:?}"
+            ));
+        }
+    };
+    let left_rest: Vec<&Formula> = left_conjuncts
+        .iter()
+        .filter(|conjunct| **conjunct != condition)
+        .copied()
+        .collect();
+    let right_rest: Vec<&Formula> = right_conjuncts
+        .iter()
+        .filter(|conjunct| !conjunct.is_negation_of(condition))
+        .copied()
+        .collect();
+    let (mut left_sorted, mut right_sorted): (Vec<&Formula>, Vec<&Formula>) =
+        (left_rest.clone(), right_rest.clone());
+    left_sorted.sort();
+    right_sorted.sort();
+    if left_sorted != right_sorted {
         return Err(format!(
-            "The input triples do not match\nnegated {:?}\nunnegated {:?} conditions",
-            left.precondition.get_info()[1],
-            negated_condition
+            "The input triples do not have the same conjuncts besides the condition {:?}\nleft: {:?}, right: {:?}",
+            condition.to_prefix_notation(),
+            prefix_notation(&left_rest),
+            prefix_notation(&right_rest)
         ));
-    } else if left.postcondition != right.postcondition {
+    }
+    if left.postcondition != right.postcondition {
         return Err(format!(
-            "The input triples do not have identical postconditions\nleft: {:?}\nright: {:?}",
+            "The input triples do not have identical postconditions\nleft: {}, right: {}",
             left.postcondition.to_prefix_notation(),
             right.postcondition.to_prefix_notation()
         ));
     }
-    Ok(Triple::new(
-        format!("{}", left.precondition.get_info()[2]),
-        format!(
+    Ok(Triple {
+        precondition: remaining_precondition(&left.precondition, condition, left_rest),
+        command: format!(
             "if {} then {} else {} endif",
-            left.precondition.get_info()[1],
+            condition.to_prefix_notation(),
             left.command,
             right.command,
         ),
-        left.postcondition.to_string(),
-    ))
+        postcondition: left.postcondition.clone(),
+    })
+}
+
+/// Returns the conjunction `precondition` without the conjuncts equal to `condition`, keeping the
+/// grouping of the other conjuncts where `condition` is an operand of the outermost `∧` that does
+/// not occur in the other operand.
+fn remaining_precondition(
+    precondition: &Formula,
+    condition: &Formula,
+    rest: Vec<&Formula>,
+) -> Formula {
+    let info: FormulaInfo<'_> = precondition.info();
+    match (info.left(), info.right()) {
+        (Some(lhs), Some(rhs)) if lhs == condition && !rhs.conjuncts().contains(&condition) => {
+            rhs.clone()
+        }
+        (Some(lhs), Some(rhs)) if rhs == condition && !lhs.conjuncts().contains(&condition) => {
+            lhs.clone()
+        }
+        _ => Formula::from_conjuncts(rest.into_iter().cloned().collect()),
+    }
 }
 
 /// Creates a new `Triple` using the Consequence Rule [3].
@@ -237,36 +1012,238 @@ pub fn consequence_rule(
     middle: &Triple,
     right: &Formula,
 ) -> Result<Triple, String> {
-    if left.get_info()[0] != "Implication" {
+    let (Some(stronger), Some(precondition)) = (left.antecedent(), left.consequent()) else {
         return Err(format!(
             "The left `Formula` {:?} is not an Implication type Formula. Left type: {:?}",
             left.to_prefix_notation(),
-            left.get_info()[0]
+            left.kind().name()
         ));
-    } else if right.get_info()[0] != "Implication" {
+    };
+    let (Some(postcondition), Some(weaker)) = (right.antecedent(), right.consequent()) else {
         return Err(format!(
             "The right `Formula` {:?} is not an Implication type Formula. Right type: {:?}",
             right.to_prefix_notation(),
-            right.get_info()[0]
+            right.kind().name()
         ));
-    } else if left.get_info()[2] != middle.precondition.to_prefix_notation() {
+    };
+    if *precondition != middle.precondition {
         return Err(format!(
             "The left `Formula` {:?} does not match the precondition of the middle `Triple` {:?}",
             left.to_prefix_notation(),
             middle.precondition.to_prefix_notation()
         ));
-    } else if right.get_info()[1] != middle.postcondition.to_prefix_notation() {
+    } else if *postcondition != middle.postcondition {
         return Err(format!(
             "The right `Formula` {:?} does not match the postcondition of the middle `Triple` {:?}",
             right.to_prefix_notation(),
             middle.postcondition.to_prefix_notation()
         ));
     }
-    Ok(Triple::new(
-        format!("{}", left.get_info()[1]),
-        format!("{}", middle.command),
-        format!("{}", right.get_info()[2]),
-    ))
+    Ok(Triple {
+        precondition: stronger.clone(),
+        command: middle.command.to_string(),
+        postcondition: weaker.clone(),
+    })
+}
+
+/// An error returned by [`consequence_rule_checked`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsequenceError {
+    /// The lemmas do not fit the triple, as reported by [`consequence_rule`].
+    Mismatch { message: String },
+    /// A lemma is false under some assignment to its atoms, or, for
+    /// `consequence_rule_checked_with`, under some assignment of integers to its variables.
+    NotTautology { lemma: Formula },
+    /// A lemma cannot be checked propositionally, because it has a quantifier, an arithmetic
+    /// term, or a custom connective, or Z3 cannot decide it.
+    Unchecked { lemma: Box<Formula>, reason: String },
+}
+
+impl fmt::Display for ConsequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsequenceError::Mismatch { message } => write!(f, "{message}"),
+            ConsequenceError::NotTautology { lemma } => {
+                write!(f, "the lemma {lemma} is not a tautology")
+            }
+            ConsequenceError::Unchecked { lemma, reason } => {
+                write!(f, "the lemma {lemma} cannot be checked: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConsequenceError {}
+
+/// Creates a new `Triple` using the Consequence Rule [3], like [`consequence_rule`], after
+/// checking that both lemmas are propositional tautologies.
+///
+/// A lemma that is a trivial implication, such as `→ ∧ A B A`, is accepted at once, whatever its
+/// atoms; see [`Formula::is_trivial_implication`]. Otherwise the check treats every atom, such as
+/// `P(x)` or `< y r`, as an opaque proposition, so it only applies to lemmas without quantifiers
+/// and arithmetic terms. Any other lemma is rejected as unchecked rather than accepted on trust.
+///
+/// # Arguments
+/// * `left` - A reference to the `Formula` that strengthens the precondition.
+/// * `middle` - A reference to the `Triple` which the Consequence Rule is applied on.
+/// * `right` - A reference to the `Formula` that weakens the postcondition.
+///
+/// # Returns
+/// A `Result` containing the `Triple` of [`consequence_rule`], or a `ConsequenceError` if the
+/// lemmas do not fit `middle`, a lemma is not a tautology, or a lemma cannot be checked.
+///
+/// # Example
+/// ```
+/// use first_order::Formula;
+/// use hoare_triple::{ConsequenceError, Triple, consequence_rule_checked};
+///
+/// let triple: Triple = Triple::new("P", "S", "Q");
+/// let result: Triple = consequence_rule_checked(
+///     &Formula::new("→ ∧ P R P"),
+///     &triple,
+///     &Formula::new("→ Q ∨ Q R"),
+/// )
+/// .unwrap();
+/// assert_eq!(result, Triple::new("∧ P R", "S", "∨ Q R"));
+///
+/// let error = consequence_rule_checked(&Formula::new("→ R P"), &triple, &Formula::new("→ Q Q"));
+/// assert_eq!(
+///     error,
+///     Err(ConsequenceError::NotTautology { lemma: Formula::new("→ R P") })
+/// );
+/// ```
+/// [3]: https://en.wikipedia.org/wiki/Hoare_logic#Consequence_rule
+pub fn consequence_rule_checked(
+    left: &Formula,
+    middle: &Triple,
+    right: &Formula,
+) -> Result<Triple, ConsequenceError> {
+    let triple: Triple = consequence_rule(left, middle, right)
+        .map_err(|message| ConsequenceError::Mismatch { message })?;
+    for lemma in [left, right] {
+        check_tautology(lemma)?;
+    }
+    Ok(triple)
+}
+
+/// Creates a new `Triple` using the Consequence Rule [3], like [`consequence_rule_checked`], but
+/// checking with the Z3 solver that both lemmas are valid over the integers.
+///
+/// A lemma that is a trivial implication is accepted at once, as by [`consequence_rule_checked`].
+/// Any other lemma is checked with [`Formula::check_validity`], so it may have quantifiers and
+/// arithmetic terms, and the functions it applies, such as `fib`, are uninterpreted.
+///
+/// # Arguments
+/// * `left` - A reference to the `Formula` that strengthens the precondition.
+/// * `middle` - A reference to the `Triple` which the Consequence Rule is applied on.
+/// * `right` - A reference to the `Formula` that weakens the postcondition.
+/// * `cfg` - How Z3 is run.
+///
+/// # Returns
+/// A `Result` containing the `Triple` of [`consequence_rule`], or a `ConsequenceError` if the
+/// lemmas do not fit `middle`, Z3 finds a counter-model of a lemma, or Z3 cannot decide a lemma.
+///
+/// # Example
+/// ```
+/// use first_order::{Formula, SolverConfig};
+/// use hoare_triple::{ConsequenceError, Triple, consequence_rule_checked_with};
+///
+/// let config: SolverConfig = SolverConfig::new();
+/// let triple: Triple = Triple::new("< x 6", "S", "Q");
+/// let result: Triple = consequence_rule_checked_with(
+///     &Formula::new("→ = x 5 < x 6"),
+///     &triple,
+///     &Formula::new("→ Q Q"),
+///     &config,
+/// )
+/// .unwrap();
+/// assert_eq!(result, Triple::new("= x 5", "S", "Q"));
+///
+/// let triple: Triple = Triple::new("< y x", "S", "Q");
+/// let error = consequence_rule_checked_with(
+///     &Formula::new("→ < x y < y x"),
+///     &triple,
+///     &Formula::new("→ Q Q"),
+///     &config,
+/// );
+/// assert_eq!(
+///     error,
+///     Err(ConsequenceError::NotTautology { lemma: Formula::new("→ < x y < y x") })
+/// );
+/// ```
+/// [3]: https://en.wikipedia.org/wiki/Hoare_logic#Consequence_rule
+#[cfg(feature = "z3")]
+pub fn consequence_rule_checked_with(
+    left: &Formula,
+    middle: &Triple,
+    right: &Formula,
+    cfg: &SolverConfig,
+) -> Result<Triple, ConsequenceError> {
+    let triple: Triple = consequence_rule(left, middle, right)
+        .map_err(|message| ConsequenceError::Mismatch { message })?;
+    for lemma in [left, right] {
+        if lemma.is_trivial_implication() {
+            continue;
+        }
+        match lemma.check_validity(cfg) {
+            Ok(Validity::Valid) => {}
+            Ok(Validity::Invalid(_)) => {
+                return Err(ConsequenceError::NotTautology {
+                    lemma: lemma.clone(),
+                });
+            }
+            Ok(Validity::Unknown) => {
+                return Err(ConsequenceError::Unchecked {
+                    lemma: Box::new(lemma.clone()),
+                    reason: "Z3 could not decide it".to_string(),
+                });
+            }
+            Err(error) => {
+                return Err(ConsequenceError::Unchecked {
+                    lemma: Box::new(lemma.clone()),
+                    reason: error.to_string(),
+                });
+            }
+        }
+    }
+    Ok(triple)
+}
+
+/// Checks that `lemma` is a trivial implication, or a propositional tautology without arithmetic
+/// terms.
+fn check_tautology(lemma: &Formula) -> Result<(), ConsequenceError> {
+    if lemma.is_trivial_implication() {
+        return Ok(());
+    }
+    let mut arithmetic: ArithmeticTerms = ArithmeticTerms { found: false };
+    lemma.accept(&mut arithmetic);
+    if arithmetic.found {
+        return Err(ConsequenceError::Unchecked {
+            lemma: Box::new(lemma.clone()),
+            reason: "the lemma has arithmetic terms".to_string(),
+        });
+    }
+    match lemma.is_tautology() {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ConsequenceError::NotTautology {
+            lemma: lemma.clone(),
+        }),
+        Err(error) => Err(ConsequenceError::Unchecked {
+            lemma: Box::new(lemma.clone()),
+            reason: error.to_string(),
+        }),
+    }
+}
+
+/// Finds whether a formula has a term applying an arithmetic operator.
+struct ArithmeticTerms {
+    found: bool,
+}
+
+impl FormulaVisitor for ArithmeticTerms {
+    fn visit_term(&mut self, term: &Term) {
+        self.found |= term.has_arithmetic();
+    }
 }
 
 /// Creates a new `Triple` using the While Rule [4].
@@ -289,32 +1266,88 @@ pub fn consequence_rule(
 /// ```
 /// [4]: https://en.wikipedia.org/wiki/Hoare_logic#While_rule
 pub fn while_rule(input: &Triple) -> Result<Triple, String> {
-    if input.precondition.get_info()[1] != input.postcondition.to_prefix_notation() {
+    let Some((invariant, condition)) = input.precondition.as_conjunction() else {
+        return Err(format!(
+            "The precondition {:?} of the input triple is not a `Conjunction` formula",
+            input.precondition.to_prefix_notation()
+        ));
+    };
+    if *invariant != input.postcondition {
         return Err(format!(
             "The loop invariant is not preserved\nprecondition (P∧B): {:?}, postcondition (P): {:?}",
-            Formula::new(&input.precondition.get_info()[1]).to_prefix_notation(),
+            invariant.to_prefix_notation(),
             input.postcondition.to_prefix_notation()
         ));
     }
-    Ok(Triple::new(
-        input.postcondition.to_prefix_notation(),
-        format!(
-            "while {} do {} done",
-            Formula::new(&input.precondition.get_info()[2]),
-            input.command
+    Ok(Triple {
+        precondition: input.postcondition.clone(),
+        command: format!("while {} do {} done", condition, input.command),
+        postcondition: Formula::Conjunction(
+            Box::new(Formula::Negation(Box::new(condition.clone()))),
+            Box::new(input.postcondition.clone()),
         ),
-        format!(
-            "∧ ¬ {} {}",
-            input.precondition.get_info()[2],
+    })
+}
+
+/// Creates a new `SharedTriple` using the While Rule, sharing the invariant and the condition of
+/// `input`.
+///
+/// This is [`while_rule`] for shared triples: the new precondition is the postcondition of
+/// `input`, and the new postcondition `∧ ¬ B P` is built around the condition and the invariant
+/// of `input` without copying them.
+///
+/// # Arguments
+/// * `input` - A reference to the `SharedTriple` that contains the loop invariant and loop
+///   condition.
+///
+/// # Returns
+/// A `Result` containing a `SharedTriple` with the While Rule applied on `input`, or an error
+/// message if the precondition is not a conjunction or the loop invariant is not preserved.
+///
+/// # Example
+/// ```
+/// use hoare_triple::{SharedTriple, Triple, while_rule_shared};
+///
+/// let triple1: SharedTriple = Triple::new("∧ P B", "S", "P").share();
+/// let test_triple: SharedTriple = while_rule_shared(&triple1).unwrap();
+/// assert!(test_triple.precondition.ptr_eq(&triple1.postcondition));
+/// assert_eq!(
+///     test_triple.to_triple(),
+///     Triple::new("P", "while B do S done", "∧ ¬ B P")
+/// );
+/// ```
+pub fn while_rule_shared(input: &SharedTriple) -> Result<SharedTriple, String> {
+    let (invariant, condition): (&SharedFormula, &SharedFormula) =
+        match input.precondition.children()[..] {
+            [invariant, condition] if input.precondition.kind() == FormulaKind::Conjunction => {
+                (invariant, condition)
+            }
+            _ => {
+                return Err(format!(
+                    "The precondition {:?} of the input triple is not a `Conjunction` formula",
+                    input.precondition.to_prefix_notation()
+                ));
+            }
+        };
+    if *invariant != input.postcondition {
+        return Err(format!(
+            "The loop invariant is not preserved\nprecondition (P∧B): {:?}, postcondition (P): {:?}",
+            invariant.to_prefix_notation(),
             input.postcondition.to_prefix_notation()
-        ),
-    ))
+        ));
+    }
+    Ok(SharedTriple {
+        precondition: input.postcondition.clone(),
+        command: format!("while {} do {} done", condition, input.command),
+        postcondition: condition.clone().not().and(input.postcondition.clone()),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use first_order::Formula;
+    use first_order::{Formula, TermPrintOptions};
+    use std::collections::{BTreeSet, HashSet};
     use std::panic;
 
     #[test]
@@ -329,45 +1362,331 @@ mod tests {
     }
 
     #[test]
-    fn test_empty_strings() {
-        let result = panic::catch_unwind(|| {
-            Triple::new("", "", "");
-        });
+    fn test_empty_strings() {
+        let result = panic::catch_unwind(|| {
+            Triple::new("", "", "");
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "The postcondition \"∧ Q\" is malformed")]
+    fn test_malformed_postcondition() {
+        Triple::new("P", "S", "∧ Q");
+    }
+
+    #[test]
+    fn test_whitespace_handling() {
+        let result = panic::catch_unwind(|| {
+            Triple::new("   ", "   ", "   ");
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_special_characters() {
+        let test_triple = Triple::new("∧ ∀ x x y", "x≔z", "∧ ∀ z z y");
+        let expected_triple = Triple {
+            precondition: Formula::new("∧ ∀ x x y"),
+            command: "x≔z".to_string(),
+            postcondition: Formula::new("∧ ∀ z z y"),
+        };
+        assert_eq!(test_triple, expected_triple);
+    }
+
+    #[test]
+    fn test_long_strings() {
+        let long_precondition = "A".repeat(1000);
+        let long_command = "B".repeat(1000);
+        let long_postcondition = "C".repeat(1000);
+        let test_triple = Triple::new(&long_precondition, &long_command, &long_postcondition);
+        let expected_triple = Triple {
+            precondition: Formula::new(&long_precondition),
+            command: long_command.clone(),
+            postcondition: Formula::new(&long_postcondition),
+        };
+        assert_eq!(test_triple, expected_triple);
+    }
+
+    #[test]
+    fn test_triples_as_keys() {
+        let triples: Vec<Triple> = vec![
+            Triple::new("= x 5", "y≔x", "= y 5"),
+            Triple::new("P", "S", "Q"),
+            Triple::new("P", "R", "Q"),
+            Triple::new("= x  5", "y≔x", "= y 5"),
+        ];
+        let unique: HashSet<&Triple> = triples.iter().collect();
+        assert_eq!(unique.len(), 3);
+        let sorted: BTreeSet<&Triple> = triples.iter().collect();
+        let commands: Vec<&str> = sorted
+            .iter()
+            .map(|triple| triple.command.as_str())
+            .collect();
+        assert_eq!(commands, ["R", "S", "y≔x"]);
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let test_triple: Triple = Triple::new(
+            "∧ = x r+y*q ¬ ∨ < y r = y r",
+            "r≔r-y;q≔1+q",
+            "∀ z → ≥ z 0 = x r+y*q",
+        );
+        assert_eq!(
+            test_triple.to_ascii(),
+            "{x = r+y*q & !(y < r | y = r)} r:=r-y;q:=1+q {forall z. (z >= 0 -> x = r+y*q)}"
+        );
+        assert!(test_triple.to_ascii().is_ascii());
+    }
+
+    #[test]
+    fn test_assignment_spellings() {
+        let unicode: Triple = Triple::new("= x+1 43", "y≔x+1", "= y 43");
+        let ascii: Triple = Triple::new("= x+1 43", "y:=x+1", "= y 43");
+        assert_eq!(ascii, unicode);
+        assert_eq!(ascii.to_string(), "{(x+1=43)} y≔x+1 {(y=43)}");
+        assert_eq!(ascii.to_ascii(), unicode.to_ascii());
+        // A triple built field by field keeps its spelling, but displays the canonical one.
+        let literal: Triple = Triple {
+            precondition: Formula::new("= y 43"),
+            command: "z:=y".to_string(),
+            postcondition: Formula::new("= z 43"),
+        };
+        assert_eq!(format!("{literal:#}"), "{= y 43} z≔y {= z 43}");
+        for rule in [
+            composition_rule,
+            composition_rule_ac,
+            composition_rule_normalized,
+        ] {
+            let composed: Triple = rule(&ascii, &literal).unwrap();
+            assert_eq!(composed.command, "y≔x+1;z≔y");
+            assert_eq!(
+                Triple::normalize_command_with(&composed.command, true),
+                "y:=x+1;z:=y"
+            );
+        }
+    }
+
+    #[test]
+    fn test_print_options() {
+        let test_triple: Triple = Triple::new(
+            "= o r*10^2+y*q",
+            "r≔r-y;q:=1+q;o≔o+r*10^(p)",
+            "∧ ≥ r 0 = x y*(1+q)",
+        );
+        let render = |spaced: bool, dot: bool, superscript: bool, symbol: &str| {
+            let mut terms: TermPrintOptions = TermPrintOptions::new();
+            terms
+                .spaced_operators(spaced)
+                .multiplication_dot(dot)
+                .superscript_exponents(superscript);
+            let mut options: PrintOptions = PrintOptions::new();
+            options.terms(terms).assignment_symbol(symbol);
+            test_triple.to_string_with(&options)
+        };
+        assert_eq!(render(false, false, false, "≔"), test_triple.to_string());
+        assert_eq!(
+            render(true, false, false, "≔"),
+            "{(o=r * 10 ^ 2 + y * q)} r ≔ r - y;q ≔ 1 + q;o ≔ o + r * 10 ^ (p) {((r≥0)∧(x=y * (1 + q)))}"
+        );
+        assert_eq!(
+            render(false, true, false, "≔"),
+            "{(o=r·10^2+y·q)} r≔r-y;q≔1+q;o≔o+r·10^(p) {((r≥0)∧(x=y·(1+q)))}"
+        );
+        assert_eq!(
+            render(false, false, true, "≔"),
+            "{(o=r*10²+y*q)} r≔r-y;q≔1+q;o≔o+r*10^(p) {((r≥0)∧(x=y*(1+q)))}"
+        );
+        assert_eq!(
+            render(false, false, false, ":="),
+            "{(o=r*10^2+y*q)} r:=r-y;q:=1+q;o:=o+r*10^(p) {((r≥0)∧(x=y*(1+q)))}"
+        );
+        assert_eq!(
+            render(true, true, false, r"\gets"),
+            r"{(o=r · 10 ^ 2 + y · q)} r \gets r - y;q \gets 1 + q;o \gets o + r · 10 ^ (p) {((r≥0)∧(x=y · (1 + q)))}"
+        );
+        // Only the values of assignments are reprinted, so the loop keeps its spelling.
+        let test_loop: Triple = Triple::new("< i a", "while i<a do b≔b+a; i≔i+1 od", "= i a");
+        let mut options: PrintOptions = PrintOptions::new();
+        options.assignment_symbol(":=");
+        assert_eq!(
+            test_loop.to_string_with(&options),
+            "{(i<a)} while i<a do b:=b+a; i:=i+1 od {(i=a)}"
+        );
+    }
+
+    #[test]
+    fn test_assignment_axiom_fibonacci() {
+        // proof[0] and proof[4] of the fibonacci proof, from their postconditions alone.
+        let assignments: [(&str, &str, &str, &str); 2] = [
+            (
+                "∧ ∧ ∧ ∧ = preFib+curFib fib(index+1) = preFib+curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+                "curFib",
+                "preFib+curFib",
+                "∧ ∧ ∧ ∧ = curFib fib(index+1) = curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            ),
+            (
+                "∧ ∧ ∧ ∧ = curFib fib(index+1) = curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+                "preFib",
+                "curFib-preFib",
+                "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            ),
+        ];
+        for (precondition, variable, expression, postcondition) in assignments {
+            let test_triple: Triple =
+                assignment_axiom(&Formula::new(postcondition), variable, expression).unwrap();
+            assert_eq!(
+                test_triple,
+                Triple::new(
+                    precondition,
+                    &format!("{variable}≔{expression}"),
+                    postcondition
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_assignment_axiom_errors() {
+        let postcondition: Formula = Formula::new("∧ < index N ∀ index ≥ index 0");
+        assert_eq!(
+            assignment_axiom(&postcondition, "index", "index+1"),
+            Ok(Triple::new(
+                "∧ < index+1 N ∀ index ≥ index 0",
+                "index≔index+1",
+                "∧ < index N ∀ index ≥ index 0"
+            ))
+        );
+        for variable in ["fib(x)", "x+1", "", "1", "x≔1", "x:=1"] {
+            assert!(
+                assignment_axiom(&postcondition, variable, "0").is_err(),
+                "{variable}"
+            );
+        }
+        for expression in ["x≔1", "x:=1"] {
+            assert_eq!(
+                assignment_axiom(&postcondition, "y", expression),
+                Err(format!(
+                    "The assigned expression {expression:?} contains an assignment"
+                ))
+            );
+        }
+        // The command is written from the parsed terms, without the stray whitespace.
+        assert_eq!(
+            assignment_axiom(&postcondition, " index ", " index+1 ").map(|triple| triple.command),
+            Ok("index≔index+1".to_string())
+        );
+        assert_eq!(
+            assignment_axiom_forward(&Formula::new("= x 1"), "x", " x+1 ")
+                .map(|triple| triple.command),
+            Ok("x≔x+1".to_string())
+        );
+        assert!(
+            assignment_axiom(&postcondition, "index", "fib(index")
+                .unwrap_err()
+                .contains("malformed")
+        );
+        let mut symbols: SymbolTable = SymbolTable::new();
+        symbols.function("fib", 1).constant("N").variable("index");
+        for (variable, allowed) in [("index", true), ("x", true), ("N", false), ("fib", false)] {
+            assert_eq!(
+                assignment_axiom_with(&postcondition, variable, "1", Some(&symbols)).is_ok(),
+                allowed,
+                "{variable}"
+            );
+        }
+    }
 
-        assert!(result.is_err());
+    #[test]
+    fn test_assignment_axiom_avoids_capture() {
+        let postcondition: Formula = Formula::new("∀ y < x y");
+        let test_triple: Triple = assignment_axiom(&postcondition, "x", "y+1").unwrap();
+        assert_eq!(
+            test_triple,
+            Triple::new("∀ y_1 < y+1 y_1", "x≔y+1", "∀ y < x y")
+        );
+        let mut symbols: SymbolTable = SymbolTable::new();
+        symbols.variable("x");
+        assert_eq!(
+            assignment_axiom_with(&postcondition, "x", "y+1", Some(&symbols)),
+            Ok(test_triple)
+        );
     }
 
     #[test]
-    fn test_whitespace_handling() {
-        let result = panic::catch_unwind(|| {
-            Triple::new("   ", "   ", "   ");
-        });
-        assert!(result.is_err());
+    fn test_assignment_axiom_forward() {
+        let test_triple: Triple =
+            assignment_axiom_forward(&Formula::new("= x 1"), "x", "x+1").unwrap();
+        assert_eq!(
+            test_triple,
+            Triple::new("= x 1", "x≔x+1", "∃ x0 ∧ = x0 1 = x x0+1")
+        );
+        assert_eq!(
+            test_triple.to_string(),
+            "{(x=1)} x≔x+1 {∃x0(((x0=1)∧(x=x0+1)))}"
+        );
+        // x0 is taken by the precondition and x1 by the expression, so x2 names the old value.
+        assert_eq!(
+            assignment_axiom_forward(&Formula::new("∧ = x x0 ∀ x3 P(x3)"), "x", "x*x1"),
+            Ok(Triple::new(
+                "∧ = x x0 ∀ x3 P(x3)",
+                "x≔x*x1",
+                "∃ x2 ∧ ∧ = x2 x0 ∀ x3 P(x3) = x x2*x1"
+            ))
+        );
+        // The assignment does not mention the old value when the variable is not read.
+        assert_eq!(
+            assignment_axiom_forward(&Formula::new("< y N"), "x", "y")
+                .unwrap()
+                .postcondition,
+            Formula::new("∃ x0 ∧ < y N = x y")
+        );
+        assert!(assignment_axiom_forward(&Formula::new("⊤"), "x+1", "0").is_err());
+        assert!(assignment_axiom_forward(&Formula::new("⊤"), "x", "x+").is_err());
     }
 
     #[test]
-    fn test_special_characters() {
-        let test_triple = Triple::new("∧ ∀ x x y", "x≔z", "∧ ∀ z z y");
-        let expected_triple = Triple {
-            precondition: Formula::new("∧ ∀ x x y"),
-            command: "x≔z".to_string(),
-            postcondition: Formula::new("∧ ∀ z z y"),
-        };
-        assert_eq!(test_triple, expected_triple);
+    fn test_display_flags() {
+        let test_triple: Triple = Triple::new("∀ i → ≤ 0 i P(i)", "i≔i+1", "¬ = r ⊥");
+        assert_eq!(
+            format!("{test_triple}"),
+            "{∀i(((0≤i)→P(i)))} i≔i+1 {(¬(r=⊥))}"
+        );
+        assert_eq!(
+            format!("{test_triple:#}"),
+            "{∀ i → ≤ 0 i P(i)} i≔i+1 {¬ = r ⊥}"
+        );
     }
 
     #[test]
-    fn test_long_strings() {
-        let long_precondition = "A".repeat(1000);
-        let long_command = "B".repeat(1000);
-        let long_postcondition = "C".repeat(1000);
-        let test_triple = Triple::new(&long_precondition, &long_command, &long_postcondition);
-        let expected_triple = Triple {
-            precondition: Formula::new(&long_precondition),
-            command: long_command.clone(),
-            postcondition: Formula::new(&long_postcondition),
-        };
-        assert_eq!(test_triple, expected_triple);
+    fn test_rename_swap() {
+        let test_triple: Triple = Triple::new(
+            "∧ = a x ∀ i < i b",
+            "while i<b do a≔a+b; i≔i+1 od",
+            "= b a+i",
+        );
+        let swap: BTreeMap<String, String> = BTreeMap::from([
+            ("a".to_string(), "b".to_string()),
+            ("b".to_string(), "a".to_string()),
+        ]);
+        assert_eq!(
+            test_triple.rename(&swap),
+            Ok(Triple::new(
+                "∧ = b x ∀ i < i a",
+                "while i<a do b≔b+a; i≔i+1 od",
+                "= a b+i"
+            ))
+        );
+        let capture: BTreeMap<String, String> =
+            BTreeMap::from([("b".to_string(), "i".to_string())]);
+        assert_eq!(
+            test_triple.rename(&capture),
+            Err(RenameError::Capture {
+                name: "i".to_string()
+            })
+        );
     }
 
     #[test]
@@ -439,12 +1758,167 @@ mod tests {
 
     #[test]
     fn consequence_complex_formulas() {
-        let triple1: Triple = Triple::new("= 2*x + 1 43", "y≔2*x+1", "= y 43");
+        let triple1: Triple = Triple::new("= 2*x+1 43", "y≔2*x+1", "= y 43");
         let triple2: Triple = Triple::new("= y 43", "z≔y", "= z 43");
 
         let test_triple: Triple = composition_rule(&triple1, &triple2).unwrap();
-        let expected: Triple = Triple::new("= 2*x + 1 43", "y≔2*x+1;z≔y", "= z 43");
+        let expected: Triple = Triple::new("= 2*x+1 43", "y≔2*x+1;z≔y", "= z 43");
+        assert_eq!(test_triple, expected);
+    }
+
+    #[test]
+    fn test_composition_rule_structural() {
+        // The postcondition built by substitution is equal to the parsed precondition.
+        let left: Triple = assignment_axiom(&Formula::new("= y 2*x+1"), "y", "y+1").unwrap();
+        let right: Triple = Triple::new("= y 2*x+1", "x≔x", "= y 2*x+1");
+        assert_eq!(left.postcondition, right.precondition);
+        assert_eq!(
+            composition_rule(&left, &right),
+            Ok(Triple::new("= y+1 2*x+1", "y≔y+1;x≔x", "= y 2*x+1"))
+        );
+        // Terms that render alike but differ in their parentheses are different midconditions.
+        let built: Triple = Triple {
+            precondition: Formula::new("⊤"),
+            command: "x≔x".to_string(),
+            postcondition: Formula::new("= y a-d").substitute_term("d", &Term::new("b-c")),
+        };
+        let parsed: Triple = Triple::new("= y a-(b-c)", "x≔x", "⊤");
+        assert_eq!(
+            built.postcondition.to_string(),
+            parsed.precondition.to_string()
+        );
+        assert!(composition_rule(&built, &parsed).is_err());
+        assert!(composition_rule_normalized(&built, &parsed).is_ok());
+    }
+
+    #[test]
+    fn test_composition_rule_does_not_reparse() {
+        // A name containing a space renders as two tokens, so re-parsing the prefix notation of
+        // these conditions would panic or split the name.
+        let opaque = |name: &str| Formula::Term(Term::Var(name.to_string()));
+        let left: Triple = Triple {
+            precondition: opaque("pre condition"),
+            command: "x:=1".to_string(),
+            postcondition: opaque("mid condition"),
+        };
+        let right: Triple = Triple {
+            precondition: opaque("mid condition"),
+            command: "y≔x".to_string(),
+            postcondition: opaque("post condition"),
+        };
+        let mut composed: Triple = composition_rule(&left, &right).unwrap();
+        assert_eq!(composed.precondition, left.precondition);
+        assert_eq!(composed.command, "x≔1;y≔x");
+        assert_eq!(composed.postcondition, right.postcondition);
+        // A long chain composes without rendering or parsing any condition.
+        for _ in 0..1000 {
+            composed = composition_rule(
+                &composed,
+                &Triple {
+                    precondition: opaque("post condition"),
+                    command: "skip".to_string(),
+                    postcondition: opaque("post condition"),
+                },
+            )
+            .unwrap();
+        }
+        assert_eq!(composed.command.matches("skip").count(), 1000);
+    }
+
+    #[test]
+    fn test_composition_rule_ac() {
+        // The postcondition of a while rule against a hand-written precondition.
+        let looped: Triple = while_rule(&Triple::new("∧ ∧ P Q B", "S", "∧ P Q")).unwrap();
+        let next: Triple = Triple::new("∧ Q ∧ ¬ B P", "T", "R");
+        assert!(composition_rule(&looped, &next).is_err());
+        let test_triple: Triple = composition_rule_ac(&looped, &next).unwrap();
+        let expected: Triple = Triple::new("∧ P Q", "while B do S done;T", "R");
         assert_eq!(test_triple, expected);
+
+        let triple1: Triple = Triple::new("= x 1", "S", "∧ ∧ A B C");
+        let triple2: Triple = Triple::new("∧ A ∧ C B", "T", "= x 2");
+        assert!(composition_rule_ac(&triple1, &triple2).is_ok());
+    }
+
+    #[test]
+    fn test_composition_rule_ac_different_operands() {
+        let triple1: Triple = Triple::new("= x 1", "S", "∧ ∧ A B C");
+        let triple2: Triple = Triple::new("∧ A ∧ C C", "T", "= x 2");
+        assert!(composition_rule(&triple1, &triple2).is_err());
+        assert!(composition_rule_ac(&triple1, &triple2).is_err());
+        let triple2: Triple = Triple::new("∨ A ∨ B C", "T", "= x 2");
+        assert!(composition_rule_ac(&triple1, &triple2).is_err());
+    }
+
+    #[test]
+    fn test_composition_rule_with_modes() {
+        let left: Triple = Triple::new("P", "S", "∧ ∧ B = x y+0 C");
+        let modes: [MatchMode; 3] = [
+            MatchMode::Exact,
+            MatchMode::ModuloAC,
+            MatchMode::ModuloNormalization,
+        ];
+        // Each midcondition is first accepted by the mode of the same index.
+        let midconditions: [&str; 3] = ["∧ ∧ B = x y+0 C", "∧ B ∧ C = x y+0", "∧ C ∧ = x y B"];
+        for (first, midcondition) in midconditions.into_iter().enumerate() {
+            let right: Triple = Triple::new(midcondition, "T", "Q");
+            for (index, mode) in modes.iter().enumerate() {
+                let result: Result<Triple, String> = composition_rule_with(&left, &right, *mode);
+                assert_eq!(result.is_ok(), index >= first, "{midcondition} {mode:?}");
+            }
+        }
+        assert_eq!(MatchMode::default(), MatchMode::Exact);
+        let right: Triple = Triple::new("∧ ∧ B = x y+0 C", "T", "Q");
+        assert_eq!(
+            composition_rule(&left, &right),
+            composition_rule_with(&left, &right, MatchMode::default())
+        );
+    }
+
+    #[test]
+    fn test_composition_rule_with_errors() {
+        let left: Triple = Triple::new("P", "S", "∧ B C");
+        let right: Triple = Triple::new("∨ B C", "T", "Q");
+        let error = |mode: MatchMode| composition_rule_with(&left, &right, mode).unwrap_err();
+        assert!(error(MatchMode::Exact).starts_with(
+            "The input triples do not have matching midcondition\nleft postcondition: \"∧ B C\""
+        ));
+        assert!(error(MatchMode::ModuloAC).starts_with(
+            "The input triples do not have matching midcondition modulo the order and grouping of ∧ and ∨\n"
+        ));
+        assert!(error(MatchMode::ModuloNormalization).contains("and the normalization of terms"));
+    }
+
+    #[test]
+    fn test_composition_rule_normalized_fibonacci() {
+        // The assignments to preFib and index compose without the lemma rewriting index+1-1.
+        let second: Triple = Triple::new(
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            "preFib≔curFib+preFib",
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+        );
+        let third: Triple = Triple::new(
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index+1-1) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            "index≔index+1",
+            "∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input",
+        );
+        assert!(composition_rule(&second, &third).is_err());
+        let composed: Triple = composition_rule_normalized(&second, &third).unwrap();
+        assert_eq!(composed.precondition, second.precondition);
+        assert_eq!(composed.command, "preFib≔curFib+preFib;index≔index+1");
+        assert_eq!(composed.postcondition, third.postcondition);
+    }
+
+    #[test]
+    fn test_composition_rule_normalized_mismatch() {
+        let triple1: Triple = Triple::new("P", "S", "= x y+1-1");
+        let triple2: Triple = Triple::new("= x y+1", "T", "Q");
+        assert!(composition_rule_normalized(&triple1, &triple2).is_err());
+        let triple2: Triple = Triple::new("= x (y)*1", "T", "Q");
+        assert_eq!(
+            composition_rule_normalized(&triple1, &triple2),
+            Ok(Triple::new("P", "S;T", "Q"))
+        );
     }
 
     #[test]
@@ -476,11 +1950,33 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(
             result.err().unwrap(),
-            "The input triples do not match negated \"B\" and unnegated \"C\" conditions"
+            "The input triples have no condition among the conjuncts [\"B\", \"P\"] whose negation is among the conjuncts [\"¬ C\", \"P\"]"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_condition_rule_unnegated_condition() {
+        let triple1 = Triple::new("∧ B P", "S", "Q");
+        let triple2 = Triple::new("∧ B P", "T", "Q"); // The right condition is not negated
+        let result = condition_rule(&triple1, &triple2);
+        assert_eq!(
+            result.err().unwrap(),
+            "The input triples have no condition among the conjuncts [\"B\", \"P\"] whose negation is among the conjuncts [\"B\", \"P\"]"
                 .to_string()
         );
     }
 
+    #[test]
+    fn test_condition_rule_negated_condition_first() {
+        let triple1 = Triple::new("∧ ¬ < y r P", "S", "Q");
+        let triple2 = Triple::new("∧ < y r P", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Ok(Triple::new("P", "if ¬ < y r then S else T endif", "Q"))
+        );
+    }
+
     #[test]
     fn test_condition_rule_different_postconditions() {
         let triple1 = Triple::new("∧ B P", "S", "Q1");
@@ -494,6 +1990,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_condition_rule_condition_in_the_middle() {
+        let triple1 = Triple::new("∧ ∧ ∧ = x r+y*q < y r ≤ 0 r = N input", "S", "Q");
+        let triple2 = Triple::new("∧ = N input ∧ ≤ 0 r ∧ ¬ < y r = x r+y*q", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Ok(Triple::new(
+                "∧ ∧ = x r+y*q ≤ 0 r = N input",
+                "if < y r then S else T endif",
+                "Q"
+            ))
+        );
+        // The condition as the last conjunct keeps the grouping of the others.
+        let triple1 = Triple::new("∧ ∧ P R B", "S", "Q");
+        let triple2 = Triple::new("∧ ¬ B ∧ R P", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2).unwrap().precondition,
+            Formula::new("∧ P R")
+        );
+    }
+
+    #[test]
+    fn test_condition_rule_repeated_condition() {
+        let triple1 = Triple::new("∧ B ∧ P B", "S", "Q");
+        let triple2 = Triple::new("∧ ¬ B ∧ P ¬ B", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Ok(Triple::new("P", "if B then S else T endif", "Q"))
+        );
+        // A copy on one side only is removed all the same.
+        let triple2 = Triple::new("∧ ¬ B P", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Ok(Triple::new("P", "if B then S else T endif", "Q"))
+        );
+        let triple1 = Triple::new("∧ B B", "S", "Q");
+        let triple2 = Triple::new("∧ ¬ B ¬ B", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Ok(Triple::new("⊤", "if B then S else T endif", "Q"))
+        );
+    }
+
+    #[test]
+    fn test_condition_rule_multiple_candidates() {
+        let triple1 = Triple::new("∧ ∧ B P C", "S", "Q");
+        let triple2 = Triple::new("∧ ∧ ¬ C P ¬ B", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Err(
+                "The input triples have more than one candidate condition: [\"B\", \"C\"]"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_condition_rule_different_remaining_conjuncts() {
+        let triple1 = Triple::new("∧ B ∧ P R", "S", "Q");
+        let triple2 = Triple::new("∧ ¬ B ∧ P P", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Err(
+                "The input triples do not have the same conjuncts besides the condition \"B\"\nleft: [\"P\", \"R\"], right: [\"P\", \"P\"]"
+                    .to_string()
+            )
+        );
+        // Both sides are listed in the order they are written.
+        let triple1 = Triple::new("∧ B ∧ R P", "S", "Q");
+        let triple2 = Triple::new("∧ ∧ Q P ¬ B", "T", "Q");
+        assert_eq!(
+            condition_rule(&triple1, &triple2),
+            Err(
+                "The input triples do not have the same conjuncts besides the condition \"B\"\nleft: [\"R\", \"P\"], right: [\"Q\", \"P\"]"
+                    .to_string()
+            )
+        );
+    }
+
     #[test]
     fn test_condition_rule_empty_conditions() {
         let triple1 = Triple::new("∧ B P", "S", "Q");
@@ -561,6 +2136,206 @@ mod tests {
         assert_eq!(result.err().unwrap(), "The right `Formula` \"→ Q2 Q1\" does not match the postcondition of the middle `Triple` \"Q3\"".to_string());
     }
 
+    #[test]
+    fn test_consequence_rule_with_top() {
+        // Lines 0, 1 and 3 of the quotient and remainder proof.
+        let lemma = Formula::new("→ ⊤ = x x+y*0");
+        let triple = Triple::new("= x x+y*0", "r≔x", "= x r+y*0");
+        let result = consequence_rule(&lemma, &triple, &Formula::new("→ = x r+y*0 = x r+y*0"));
+        let expected = Triple::new("⊤", "r≔x", "= x r+y*0");
+        assert_eq!(result, Ok(expected));
+        assert_eq!(result.unwrap().precondition, Formula::Top);
+    }
+
+    #[test]
+    fn test_condition_rule_with_top() {
+        let triple1 = Triple::new("∧ B ⊤", "S", "⊥");
+        let triple2 = Triple::new("∧ ¬ B ⊤", "T", "⊥");
+        let result = condition_rule(&triple1, &triple2).unwrap();
+        assert_eq!(result.precondition, Formula::Top);
+        assert_eq!(result.postcondition, Formula::Bottom);
+    }
+
+    #[test]
+    fn test_rules_with_comparison_connectives() {
+        let triple1 = Triple::new("∧ > count 0 ≥ count 0", "S", "≥ count 0");
+        let triple2 = Triple::new("∧ ¬ > count 0 ≥ count 0", "T", "≥ count 0");
+        let result = condition_rule(&triple1, &triple2).unwrap();
+        let expected = Triple::new("≥ count 0", "if > count 0 then S else T endif", "≥ count 0");
+        assert_eq!(result, expected);
+
+        let triple = Triple::new("∧ ≤ index N < index N", "index≔index+1", "≤ index N");
+        let result = while_rule(&triple).unwrap();
+        let expected = Triple::new(
+            "≤ index N",
+            "while (index<N) do index≔index+1 done",
+            "∧ ¬ < index N ≤ index N",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_while_rule_with_not_equal_condition() {
+        // The loop of the euclidean algorithm, with `b ≠ 0` as the loop condition.
+        let invariant = Formula::new("= gcd(a,b) gcd(a,mod(a,b))");
+        let triple = Triple::new(
+            "∧ = gcd(a,b) gcd(a,mod(a,b)) ≠ b 0",
+            "temp≔b;b≔mod(a,b);a≔temp",
+            "= gcd(a,b) gcd(a,mod(a,b))",
+        );
+        let result = while_rule(&triple).unwrap();
+        assert_eq!(
+            result.postcondition,
+            Formula::new("∧ ¬ ≠ b 0 = gcd(a,b) gcd(a,mod(a,b))")
+        );
+        let exit_condition = Formula::new("≠ b 0").negate_comparison().unwrap();
+        let restated = Formula::Conjunction(Box::new(exit_condition), Box::new(invariant));
+        assert_eq!(restated, Formula::new("∧ = b 0 = gcd(a,b) gcd(a,mod(a,b))"));
+    }
+
+    #[test]
+    fn test_while_rule_postcondition_de_morgan() {
+        // The loop of the quotient and remainder proof, with `y ≤ r` written as `∨ < y r = y r`.
+        let triple = Triple::new("∧ = x r+y*q ∨ < y r = y r", "r≔r-y;q≔1+q", "= x r+y*q");
+        let result = while_rule(&triple).unwrap();
+        assert_eq!(
+            result.postcondition,
+            Formula::new("∧ ¬ ∨ < y r = y r = x r+y*q")
+        );
+        let expected = Formula::new("∧ ∧ ¬ < y r ¬ = y r = x r+y*q");
+        assert_eq!(
+            result.postcondition.push_negations_once(),
+            (expected.clone(), true)
+        );
+        assert_eq!(result.postcondition.de_morgan(), expected);
+    }
+
+    #[test]
+    fn test_consequence_rule_checked_accepts_tautologies() {
+        let triple: Triple = Triple::new("P", "S", "Q");
+        let left: Formula = Formula::new("→ ∧ P Q P");
+        let right: Formula = Formula::new("→ Q ¬ ¬ Q");
+        assert_eq!(
+            consequence_rule_checked(&left, &triple, &right),
+            Ok(Triple::new("∧ P Q", "S", "¬ ¬ Q"))
+        );
+        let triple: Triple = Triple::new("< y r", "S", "= y r");
+        assert_eq!(
+            consequence_rule_checked(
+                &Formula::new("→ ∧ < y r ⊤ < y r"),
+                &triple,
+                &Formula::new("→ = y r ∨ < y r = y r")
+            ),
+            Ok(Triple::new("∧ < y r ⊤", "S", "∨ < y r = y r"))
+        );
+    }
+
+    #[test]
+    fn test_consequence_rule_checked_rejects_invalid_lemmas() {
+        let triple: Triple = Triple::new("Q", "S", "Q");
+        assert_eq!(
+            consequence_rule_checked(&Formula::new("→ P Q"), &triple, &Formula::new("→ Q Q")),
+            Err(ConsequenceError::NotTautology {
+                lemma: Formula::new("→ P Q")
+            })
+        );
+        assert_eq!(
+            consequence_rule(&Formula::new("→ P Q"), &triple, &Formula::new("→ Q Q")),
+            Ok(Triple::new("P", "S", "Q"))
+        );
+        let error: ConsequenceError =
+            consequence_rule_checked(&Formula::new("→ Q Q"), &triple, &Formula::new("→ Q R"))
+                .unwrap_err();
+        assert_eq!(error.to_string(), "the lemma (Q→R) is not a tautology");
+    }
+
+    #[test]
+    fn test_consequence_rule_checked_unchecked_lemmas() {
+        let triple: Triple = Triple::new("= x q*y+r", "S", "P(y)");
+        let arithmetic: Formula = Formula::new("→ = x r+y*q = x q*y+r");
+        assert_eq!(
+            consequence_rule_checked(&arithmetic, &triple, &Formula::new("→ P(y) P(y)")),
+            Err(ConsequenceError::Unchecked {
+                lemma: Box::new(arithmetic),
+                reason: "the lemma has arithmetic terms".to_string()
+            })
+        );
+        let quantified: Formula = Formula::new("→ P(y) ∃ x P(x)");
+        let error: ConsequenceError = consequence_rule_checked(
+            &Formula::new("→ P(y) P(y)"),
+            &Triple::new("P(y)", "S", "P(y)"),
+            &quantified,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            ConsequenceError::Unchecked {
+                lemma: Box::new(quantified),
+                reason: "the formula quantifies over x, so it is first-order rather than quantifier-free"
+                    .to_string()
+            }
+        );
+    }
+
+    #[cfg(feature = "z3")]
+    #[test]
+    fn test_consequence_rule_checked_with_z3() {
+        let config: SolverConfig = SolverConfig::new();
+        let triple: Triple = Triple::new("= x q*y+r", "S", "= fib(n)+1 2");
+        let left: Formula = Formula::new("→ = x r+y*q = x q*y+r");
+        let right: Formula = Formula::new("→ = fib(n)+1 2 = fib(n) 1");
+        assert_eq!(
+            consequence_rule_checked_with(&left, &triple, &right, &config),
+            Ok(Triple::new("= x r+y*q", "S", "= fib(n) 1"))
+        );
+        assert!(matches!(
+            consequence_rule_checked(&left, &triple, &right),
+            Err(ConsequenceError::Unchecked { .. })
+        ));
+        let triple: Triple = Triple::new("< y x", "S", "Q");
+        let invalid: Formula = Formula::new("→ < x y < y x");
+        assert_eq!(
+            consequence_rule_checked_with(&invalid, &triple, &Formula::new("→ Q Q"), &config),
+            Err(ConsequenceError::NotTautology { lemma: invalid })
+        );
+        let power: Formula = Formula::new("→ = y x^2 < y x");
+        let error: ConsequenceError =
+            consequence_rule_checked_with(&power, &triple, &Formula::new("→ Q Q"), &config)
+                .unwrap_err();
+        assert!(
+            matches!(&error, ConsequenceError::Unchecked { lemma, .. } if **lemma == power),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_consequence_rule_checked_trivial_lemmas() {
+        let triple: Triple = Triple::new("= x r+y*q", "S", "∀ i P(i)");
+        let left: Formula = Formula::new("→ ∧ = x r+y*q ≤ 0 r = x r+y*q");
+        let right: Formula = Formula::new("→ ∀ i P(i) ∨ ∀ i P(i) ⊥");
+        assert_eq!(
+            consequence_rule_checked(&left, &triple, &right),
+            Ok(Triple::new("∧ = x r+y*q ≤ 0 r", "S", "∨ ∀ i P(i) ⊥"))
+        );
+        let paper_lemma: Formula = Formula::new("→ = x r+y*q = x r+y*q");
+        assert_eq!(
+            consequence_rule_checked(&paper_lemma, &triple, &Formula::new("→ ∀ i P(i) ⊤")),
+            Ok(Triple::new("= x r+y*q", "S", "⊤"))
+        );
+    }
+
+    #[test]
+    fn test_consequence_rule_checked_mismatch() {
+        let triple: Triple = Triple::new("P", "S", "Q");
+        assert_eq!(
+            consequence_rule_checked(&Formula::new("∧ P P"), &triple, &Formula::new("→ Q Q")),
+            Err(ConsequenceError::Mismatch {
+                message: consequence_rule(&Formula::new("∧ P P"), &triple, &Formula::new("→ Q Q"))
+                    .unwrap_err()
+            })
+        );
+    }
+
     #[test]
     fn test_while_rule_valid() {
         let triple1 = Triple::new("∧ P B", "S", "P");
@@ -577,6 +2352,18 @@ mod tests {
         assert_eq!(result.err().unwrap(), "The loop invariant is not preserved\nprecondition (P∧B): \"P\", postcondition (P): \"Q\"".to_string());
     }
 
+    #[test]
+    fn test_while_rule_invalid_conjunction() {
+        let triple1 = Triple::new("∨ P B", "S", "P"); // Not a conjunction
+        let result = while_rule(&triple1);
+        assert_eq!(
+            result.err().unwrap(),
+            "The precondition \"∨ P B\" of the input triple is not a `Conjunction` formula"
+                .to_string()
+        );
+        assert!(while_rule(&Triple::new("P", "S", "P")).is_err());
+    }
+
     #[test]
     fn test_while_rule_empty_command() {
         let triple1 = Triple::new("∧ P B", "", "P"); // Empty command
@@ -600,4 +2387,157 @@ mod tests {
         let expected = Triple::new("∧ A B", "while C do S done", "∧ ¬ C ∧ A B");
         assert_eq!(result, expected);
     }
+
+    /// Returns the triples of the fibonacci proof, in the order they are proved.
+    fn fibonacci_triples() -> Vec<Triple> {
+        let invariant: &str = "∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input";
+        let lemma = |lhs: &Formula, rhs: &str| Formula::new(format!("→ {lhs:#} {rhs}"));
+        let first: Triple = Triple::new(
+            "∧ ∧ ∧ ∧ = preFib+curFib fib(index+1) = preFib+curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            "curFib≔preFib+curFib",
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+        );
+        let strengthened: Triple = consequence_rule(
+            &Formula::new(format!(
+                "→ ∧ {invariant} < index N {:#}",
+                first.precondition
+            )),
+            &first,
+            &lemma(&first.postcondition, &format!("{:#}", first.postcondition)),
+        )
+        .unwrap();
+        let second: Triple = Triple::new(
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = curFib-preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            "preFib≔curFib+preFib",
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+        );
+        let weakened: Triple = consequence_rule(
+            &lemma(&second.precondition, &format!("{:#}", second.precondition)),
+            &second,
+            &lemma(
+                &second.postcondition,
+                "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index+1-1) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            ),
+        )
+        .unwrap();
+        let third: Triple = Triple::new(
+            "∧ ∧ ∧ ∧ = curFib fib(index+1) = preFib fib(index+1-1) < 0 index+1 ∨ < index+1 N = index+1 N = N input",
+            "index≔index+1",
+            invariant,
+        );
+        let body: Triple = composition_rule(&strengthened, &weakened).unwrap();
+        let body: Triple = composition_rule(&body, &third).unwrap();
+        let looped: Triple = while_rule(&body).unwrap();
+        let program: Triple = consequence_rule(
+            &Formula::new(format!(
+                "→ ∧ ∧ ∧ ∧ = preFib 0 = curFib 1 = index 1 ∨ < 1 N = 1 N = N input {:#}",
+                looped.precondition
+            )),
+            &looped,
+            &lemma(&looped.postcondition, "= curFib fib(input)"),
+        )
+        .unwrap();
+        vec![
+            first,
+            strengthened,
+            second,
+            weakened,
+            third,
+            body,
+            looped,
+            program,
+        ]
+    }
+
+    #[test]
+    fn test_intern_fibonacci_proof() {
+        let triples: Vec<Triple> = fibonacci_triples();
+        let mut store: FormulaStore = FormulaStore::new();
+        let interned: Vec<InternedTriple> = triples
+            .iter()
+            .map(|triple| triple.intern_into(&mut store))
+            .collect();
+        let stats = store.stats();
+        assert_eq!(stats.insertions, 16);
+        assert!(stats.unique < stats.insertions, "{stats:?}");
+        assert!(stats.unique_nodes * 3 < stats.nodes, "{stats:?}");
+        for (lhs, lhs_interned) in triples.iter().zip(&interned) {
+            assert_eq!(*store.get(lhs_interned.precondition), lhs.precondition);
+            for (rhs, rhs_interned) in triples.iter().zip(&interned) {
+                assert_eq!(lhs_interned == rhs_interned, lhs == rhs);
+                assert_eq!(
+                    store.equal(lhs_interned.postcondition, rhs_interned.postcondition),
+                    lhs.postcondition == rhs.postcondition
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_composing_shared_triples_shares_the_invariant() {
+        // The fibonacci invariant I, with 1,000 steps {I} S_k {I} composed one after the other
+        // and closed by the while rule conclusion of {∧ I B} S {I}.
+        let invariant: SharedFormula = SharedFormula::new(
+            "∧ ∧ ∧ ∧ = curFib fib(index) = preFib fib(index-1) < 0 index ∨ < index N = index N = N input",
+        );
+        let condition: SharedFormula = SharedFormula::new("< index N");
+        let body: SharedTriple = SharedTriple {
+            precondition: invariant.clone().and(condition.clone()),
+            command: "S".to_string(),
+            postcondition: invariant.clone(),
+        };
+        let looped: SharedTriple = while_rule_shared(&body).unwrap();
+        let mut triples: Vec<SharedTriple> = vec![looped.clone()];
+        for index in 0..1000 {
+            let step: SharedTriple = SharedTriple {
+                precondition: invariant.clone(),
+                command: format!("S{index}"),
+                postcondition: invariant.clone(),
+            };
+            let composed: SharedTriple = match triples.last() {
+                Some(last) if index > 0 => composition_rule_shared(last, &step).unwrap(),
+                _ => step,
+            };
+            triples.push(composed);
+        }
+        let program: SharedTriple = composition_rule_shared(&triples[1000], &looped).unwrap();
+        for triple in triples[1..].iter().chain([&program]) {
+            assert!(triple.precondition.ptr_eq(&invariant));
+        }
+        for triple in &triples[1..] {
+            assert!(triple.postcondition.ptr_eq(&invariant));
+        }
+        let negation: &SharedFormula = program.postcondition.children()[0];
+        assert!(negation.children()[0].ptr_eq(&condition));
+        assert!(program.postcondition.children()[1].ptr_eq(&invariant));
+        assert_eq!(
+            program.to_triple().postcondition,
+            Formula::new(format!("∧ ¬ < index N {:#}", invariant.to_formula()))
+        );
+        assert!(program.command.starts_with("S0;S1;S2;"));
+        assert!(program.command.ends_with(";S999;while (index<N) do S done"));
+    }
+
+    #[test]
+    fn test_shared_rules_match_the_rules() {
+        let triples: Vec<Triple> = vec![
+            Triple::new("∧ P B", "S", "P"),
+            Triple::new("∧ P B", "S", "Q"),
+            Triple::new("P", "T", "∧ P B"),
+            Triple::new("∨ P B", "U", "P"),
+        ];
+        for left in &triples {
+            assert_eq!(
+                while_rule_shared(&left.share()).map(|triple| triple.to_triple()),
+                while_rule(left)
+            );
+            for right in &triples {
+                assert_eq!(
+                    composition_rule_shared(&left.share(), &right.share())
+                        .map(|triple| triple.to_triple()),
+                    composition_rule(left, right)
+                );
+            }
+        }
+    }
 }
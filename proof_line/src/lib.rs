@@ -4,15 +4,17 @@
 //! and `hoare_triple` crates. It defines the `ProofLine` enum, which serves as a versatile container for
 //! representing elements of a proof, allowing users to manipulate proofs more easily.
 //!
-//! The `ProofLine` enum can hold either a logical formula from the `first_order` crate or a Hoare triple from
-//! the `hoare_triple` crate. This design enables users to create a vector of `ProofLine` instances, facilitating
-//! the construction and manipulation of proofs in a structured manner.
+//! The `ProofLine` enum can hold either a logical formula from the `first_order` crate, optionally justified by
+//! a natural deduction derivation, or a Hoare triple from the `hoare_triple` crate. This design enables users to
+//! create a vector of `ProofLine` instances, facilitating the construction and manipulation of proofs in a
+//! structured manner.
 //!
 //! # Usage
 //! Users can create a vector of `ProofLine` instances to represent a sequence of proof steps, making it easier
 //! to manage and manipulate logical arguments and Hoare triples in their programs.
 use backtrace::{Backtrace, BacktraceFrame, BacktraceSymbol};
 use first_order::Formula;
+use first_order::nd::{Derivation, DerivationError};
 use hoare_triple::Triple;
 use std::fmt;
 
@@ -26,6 +28,8 @@ pub enum ProofLine {
     Formula(Formula),
     /// A `Triple` type in `hoare_triple`
     Triple(Triple),
+    /// A `Formula` justified by a `Derivation` in `first_order::nd`, which proves it.
+    Derived(Derivation),
 }
 
 /// Returns a string representation of the current trace location.
@@ -45,7 +49,7 @@ fn trace() -> String {
                 .unwrap_or(true)
                 || s.lineno() != Some(current_line)
         })
-        .nth(1 + level as usize)
+        .nth(1 + level)
         .cloned();
     format!(
         "{:?}:{}",
@@ -85,7 +89,8 @@ impl ProofLine {
             }
         }
     }
-    /// A function to return a reference of a `Formula` from a `ProofLine::Formula` instance.
+    /// A function to return a reference of a `Formula` from a `ProofLine::Formula` instance, or
+    /// of the conclusion of a `ProofLine::Derived` instance.
     ///
     /// # Panics
     /// The function will panic if it is called on type `ProofLine::Triple`. The panic message will
@@ -104,13 +109,74 @@ impl ProofLine {
     /// ```
     pub fn get_formula(&self) -> &Formula {
         match self {
-            ProofLine::Formula(formula) => &formula,
+            ProofLine::Formula(formula) => formula,
+            ProofLine::Derived(derivation) => derivation.conclusion(),
             _ => panic!(
                 "Error at {}.\nAttempt to access Formula from a non-Formula ProofLine",
                 trace()
             ),
         }
     }
+    /// An interface for creating a `ProofLine` from a natural deduction derivation of a `Formula`.
+    ///
+    /// # Arguments
+    /// * `result` - A `result` type from building a `Derivation` in `first_order::nd`, which can either be:
+    ///   - `Ok(Derivation)`: A derivation of the formula, which must not rest on open assumptions.
+    ///   - `Err(DerivationError)`: An error indicating that a rule of the derivation does not apply.
+    ///
+    /// # Panics
+    /// The function will panic if the `Result` is an `Err` type or if the derivation has open assumptions. The
+    /// panic message will include the error and point to the location in the code where the error occurred.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    /// use first_order::nd::Derivation;
+    /// use proof_line::ProofLine;
+    ///
+    /// let left = Derivation::and_elim_left(Derivation::assume(Formula::new("∧ A B")));
+    /// let lemma = left.map(|left| Derivation::implies_intro(Formula::new("∧ A B"), left));
+    /// let test_proofline: ProofLine = ProofLine::new_formula_from_derivation(lemma);
+    /// assert_eq!(*test_proofline.get_formula(), Formula::new("→ ∧ A B A"));
+    /// assert!(test_proofline.get_derivation().is_some());
+    /// ```
+    pub fn new_formula_from_derivation(result: Result<Derivation, DerivationError>) -> Self {
+        match result {
+            Ok(derivation) if derivation.is_closed() => Self::Derived(derivation),
+            Ok(derivation) => {
+                let open: Vec<String> = derivation
+                    .open_assumptions()
+                    .iter()
+                    .map(|assumption| assumption.to_string())
+                    .collect();
+                panic!(
+                    "Error at {}.\nThe derivation of {} rests on the open assumptions {}",
+                    trace(),
+                    derivation.conclusion(),
+                    open.join(", ")
+                )
+            }
+            Err(err) => {
+                panic!("Error at {}.\n{err}", trace())
+            }
+        }
+    }
+    /// A function to return the `Derivation` justifying a `ProofLine::Derived` instance, if it is one.
+    ///
+    /// # Example
+    /// ```
+    /// use first_order::Formula;
+    /// use proof_line::ProofLine;
+    ///
+    /// let test_proofline: ProofLine = ProofLine::Formula(Formula::new("→ ∧ A B A"));
+    /// assert!(test_proofline.get_derivation().is_none());
+    /// ```
+    pub fn get_derivation(&self) -> Option<&Derivation> {
+        match self {
+            ProofLine::Derived(derivation) => Some(derivation),
+            _ => None,
+        }
+    }
     /// A function to return a reference of a `Triple` from a `ProofLine::Triple` instance.
     ///
     /// # Panics
@@ -128,7 +194,7 @@ impl ProofLine {
     /// ```
     pub fn get_triple(&self) -> &Triple {
         match self {
-            ProofLine::Triple(triple) => &triple,
+            ProofLine::Triple(triple) => triple,
             _ => panic!(
                 "Error at {}.\nAttempt to access Triple from a non-Triple ProofLine",
                 trace()
@@ -142,6 +208,7 @@ impl fmt::Display for ProofLine {
         match self {
             ProofLine::Formula(formula) => write!(f, "{}", formula),
             ProofLine::Triple(triple) => write!(f, "{}", triple),
+            ProofLine::Derived(derivation) => write!(f, "{}", derivation.conclusion()),
         }
     }
 }
@@ -1,5 +1,5 @@
 use first_order::Formula;
-use hoare_triple::{Triple, composition_rule, while_rule};
+use hoare_triple::{assignment_axiom, composition_rule, while_rule};
 use proof_line::ProofLine;
 /// Reference Code:
 ///```
@@ -15,25 +15,26 @@ use proof_line::ProofLine;
 ///     a
 /// }
 /// ```
-
 fn main() {
     let mut proof: Vec<ProofLine> = vec![];
 
-    proof.push(ProofLine::Triple(Triple::new(
-        "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
-        "temp≔b",
-        "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
-    )));
-    proof.push(ProofLine::Triple(Triple::new(
-        "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
-        "b≔mod(a,b)",
-        "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
-    )));
-    proof.push(ProofLine::Triple(Triple::new(
-        "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
-        "a≔temp",
-        "∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0",
-    )));
+    // The assignments are derived backwards from the postcondition of the loop body.
+    let invariant: Formula = Formula::new("∧ = gcd(a,b) gcd(a,mod(a,b)) ¬ = b 0");
+    let assign_a: ProofLine =
+        ProofLine::new_triple_from_rule(assignment_axiom(&invariant, "a", "temp"));
+    let assign_b: ProofLine = ProofLine::new_triple_from_rule(assignment_axiom(
+        &assign_a.get_triple().precondition,
+        "b",
+        "mod(a,b)",
+    ));
+    let assign_temp: ProofLine = ProofLine::new_triple_from_rule(assignment_axiom(
+        &assign_b.get_triple().precondition,
+        "temp",
+        "b",
+    ));
+    proof.push(assign_temp);
+    proof.push(assign_b);
+    proof.push(assign_a);
     proof.push(ProofLine::new_triple_from_rule(composition_rule(
         proof[0].get_triple(),
         proof[1].get_triple(),
@@ -18,19 +18,15 @@ fn main() {
     )));
 
     proof.push(ProofLine::new_triple_from_rule(composition_rule(
-        &proof[0].get_triple(),
-        &proof[1].get_triple(),
+        proof[0].get_triple(),
+        proof[1].get_triple(),
     )));
     proof.push(ProofLine::Formula(Formula::new("→ ∧ ∧ = result*fact(count) fact(x) ∨ < 0 count = 0 count ¬ = 0 count ∧ = (result*count)*fact(count-1) fact(x) ∨ < 0 (count-1) = 0 (count-1)")));
 
     proof.push(ProofLine::new_triple_from_rule(consequence_rule(
-        &proof[3].get_formula(),
-        &proof[2].get_triple(),
-        &Formula::new(format!(
-            "→ {} {}",
-            &proof[2].get_triple().postcondition.to_prefix_notation(),
-            &proof[2].get_triple().postcondition.to_prefix_notation()
-        )),
+        proof[3].get_formula(),
+        proof[2].get_triple(),
+        &Formula::identity_implication(&proof[2].get_triple().postcondition),
     )));
 
     proof.push(ProofLine::new_triple_from_rule(while_rule(
@@ -43,9 +39,9 @@ fn main() {
     )));
 
     proof.push(ProofLine::new_triple_from_rule(consequence_rule(
-        &proof[6].get_formula(),
-        &proof[5].get_triple(),
-        &proof[7].get_formula(),
+        proof[6].get_formula(),
+        proof[5].get_triple(),
+        proof[7].get_formula(),
     )));
 
     for (line_number, line) in proof.iter().enumerate() {
@@ -34,11 +34,9 @@ fn main() {
     ))));
 
     //proof[2] lemma 1
-    proof.push(ProofLine::Formula(Formula::new(format!(
-        "→ {} {}",
-        proof[0].get_triple().postcondition.to_prefix_notation(),
-        proof[0].get_triple().postcondition.to_prefix_notation()
-    ))));
+    proof.push(ProofLine::Formula(Formula::identity_implication(
+        &proof[0].get_triple().postcondition,
+    )));
 
     //proof[3] consequence rule with proof[1] proof[0] proof[3]
     proof.push(ProofLine::new_triple_from_rule(consequence_rule(
@@ -55,11 +53,9 @@ fn main() {
     )));
 
     //proof[5] lemma 2
-    proof.push(ProofLine::Formula(Formula::new(format!(
-        "→ {} {}",
-        proof[4].get_triple().precondition.to_prefix_notation(),
-        proof[4].get_triple().precondition.to_prefix_notation()
-    ))));
+    proof.push(ProofLine::Formula(Formula::identity_implication(
+        &proof[4].get_triple().precondition,
+    )));
 
     //proof[6] lemma 3
     proof.push(ProofLine::Formula(Formula::new(format!(
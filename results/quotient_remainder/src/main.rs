@@ -30,8 +30,8 @@ fn main() {
     )));
     //line 4
     proof.push(ProofLine::new_triple_from_rule(composition_rule(
-        &proof[3].get_triple(),
-        &proof[2].get_triple(),
+        proof[3].get_triple(),
+        proof[2].get_triple(),
     )));
     //line 5
     proof.push(ProofLine::Formula(Formula::new(
@@ -51,13 +51,13 @@ fn main() {
     )));
     //line 8
     proof.push(ProofLine::new_triple_from_rule(composition_rule(
-        &proof[6].get_triple(),
-        &proof[7].get_triple(),
+        proof[6].get_triple(),
+        proof[7].get_triple(),
     )));
     //line 9
     proof.push(ProofLine::new_triple_from_rule(consequence_rule(
-        &proof[5].get_formula(),
-        &proof[8].get_triple(),
+        proof[5].get_formula(),
+        proof[8].get_triple(),
         &Formula::new("→ = x r+y*q = x r+y*q"),
     )));
     //line 10
@@ -66,12 +66,12 @@ fn main() {
     )));
     //line 11
     proof.push(ProofLine::new_triple_from_rule(while_rule(
-        &proof[9].get_triple(),
+        proof[9].get_triple(),
     )));
     //line 12
     proof.push(ProofLine::new_triple_from_rule(composition_rule(
-        &proof[4].get_triple(),
-        &proof[11].get_triple(),
+        proof[4].get_triple(),
+        proof[11].get_triple(),
     )));
 
     //output